@@ -0,0 +1,517 @@
+//! Reading and writing proofs on a background thread.
+use std::io::{self, BufReader, Read, Write};
+use std::mem::replace;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+use std::vec;
+
+use varisat_internal_proof::{
+    binary_format::{read_header, HeaderError, Parser, ProofHeader, StepError},
+    OwnedProofStep, ProofStep,
+};
+
+use crate::callbacks::{CountingReader, ProofInput, RecordingReader};
+use crate::state::StepSource;
+
+/// Number of bytes collected before they are passed to the writer thread.
+const CHUNK_SIZE: usize = 1 << 16;
+
+/// Number of decoded steps passed to the checking thread at once.
+const STEP_BATCH: usize = 256;
+
+/// Messages sent to the writer thread.
+enum Message {
+    /// Data to write.
+    Data(Vec<u8>),
+    /// Flush the target and acknowledge when done.
+    Flush(SyncSender<()>),
+}
+
+/// Writer that performs the actual IO on a dedicated thread.
+///
+/// Written data is collected into chunks which are sent to the writer thread using a bounded
+/// channel. When the channel is full, writing blocks until the writer thread catches up. This
+/// keeps slow disks from stalling proof checking or solving without using unbounded memory.
+///
+/// IO errors of the writer thread are returned by the next call to `write` or `flush`. Calling
+/// `flush` waits until all data was written to the target and the target was flushed.
+pub struct BackgroundWriter {
+    buffer: Vec<u8>,
+    sender: Option<SyncSender<Message>>,
+    thread: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl BackgroundWriter {
+    /// Write to `target` on a new thread.
+    ///
+    /// At most `capacity` chunks are buffered before writing blocks.
+    pub fn new(target: impl Write + Send + 'static, capacity: usize) -> BackgroundWriter {
+        let (sender, receiver) = sync_channel(capacity);
+        let thread = thread::spawn(move || write_messages(target, receiver));
+        BackgroundWriter {
+            buffer: Vec::with_capacity(CHUNK_SIZE),
+            sender: Some(sender),
+            thread: Some(thread),
+        }
+    }
+
+    /// Write all remaining data and wait for the writer thread to finish.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.send_buffer()?;
+        self.sender = None;
+        match self.thread.take() {
+            Some(thread) => join_writer(thread),
+            None => Ok(()),
+        }
+    }
+
+    /// Send a message to the writer thread.
+    fn send(&mut self, message: Message) -> io::Result<()> {
+        let sent = match &self.sender {
+            Some(sender) => sender.send(message).is_ok(),
+            None => false,
+        };
+        if sent {
+            Ok(())
+        } else {
+            Err(self.writer_error())
+        }
+    }
+
+    /// Pass the collected data to the writer thread.
+    fn send_buffer(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            let chunk = replace(&mut self.buffer, Vec::with_capacity(CHUNK_SIZE));
+            self.send(Message::Data(chunk))?;
+        }
+        Ok(())
+    }
+
+    /// The error that stopped the writer thread.
+    fn writer_error(&mut self) -> io::Error {
+        self.sender = None;
+        match self.thread.take().map(join_writer) {
+            Some(Err(err)) => err,
+            _ => io::Error::new(io::ErrorKind::BrokenPipe, "proof writer thread stopped"),
+        }
+    }
+}
+
+impl Write for BackgroundWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= CHUNK_SIZE {
+            self.send_buffer()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send_buffer()?;
+        let (ack_sender, ack_receiver) = sync_channel(1);
+        self.send(Message::Flush(ack_sender))?;
+        match ack_receiver.recv() {
+            Ok(()) => Ok(()),
+            Err(_) => Err(self.writer_error()),
+        }
+    }
+}
+
+impl Drop for BackgroundWriter {
+    fn drop(&mut self) {
+        let _ignore_errors = self.send_buffer();
+        self.sender = None;
+        if let Some(thread) = self.thread.take() {
+            let _ignore_errors = join_writer(thread);
+        }
+    }
+}
+
+/// Wait for the writer thread to finish and return its result.
+fn join_writer(thread: JoinHandle<io::Result<()>>) -> io::Result<()> {
+    match thread.join() {
+        Ok(result) => result,
+        Err(_) => Err(io::Error::new(
+            io::ErrorKind::Other,
+            "proof writer thread panicked",
+        )),
+    }
+}
+
+/// Reader that performs the actual IO on a dedicated thread.
+///
+/// The reader thread reads chunks ahead and passes them to the consuming thread using a bounded
+/// channel. This overlaps reading and decompressing a proof with checking it.
+///
+/// IO errors of the reader thread are returned by the call to `read` that reaches the position of
+/// the error.
+pub struct BackgroundReader {
+    chunk: Vec<u8>,
+    pos: usize,
+    receiver: Receiver<io::Result<Vec<u8>>>,
+}
+
+impl BackgroundReader {
+    /// Read from `source` on a new thread.
+    ///
+    /// At most `capacity` chunks are read ahead.
+    pub fn new(source: impl Read + Send + 'static, capacity: usize) -> BackgroundReader {
+        BackgroundReader::spawn(move || Ok(source), capacity)
+    }
+
+    /// Read from a source that is opened on a new thread.
+    ///
+    /// This allows the reader thread to use sources that can't be sent between threads, e.g. a
+    /// decompressing reader. An error returned by `open` is returned by the first call to `read`.
+    pub fn spawn<R: Read>(
+        open: impl FnOnce() -> io::Result<R> + Send + 'static,
+        capacity: usize,
+    ) -> BackgroundReader {
+        let (sender, receiver) = sync_channel(capacity);
+        // The thread is detached, it stops when the source ends or the reader is dropped.
+        thread::spawn(move || match open() {
+            Ok(source) => read_chunks(source, sender),
+            Err(err) => {
+                let _ = sender.send(Err(err));
+            }
+        });
+        BackgroundReader {
+            chunk: vec![],
+            pos: 0,
+            receiver,
+        }
+    }
+}
+
+impl Read for BackgroundReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.chunk.len() {
+            match self.receiver.recv() {
+                Ok(Ok(chunk)) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                Ok(Err(err)) => return Err(err),
+                // The reader thread is done after sending the last chunk
+                Err(_) => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.chunk.len() - self.pos);
+        buf[..len].copy_from_slice(&self.chunk[self.pos..][..len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+/// Main loop of the reader thread.
+fn read_chunks(mut source: impl Read, sender: SyncSender<io::Result<Vec<u8>>>) {
+    loop {
+        let mut chunk = vec![0; CHUNK_SIZE];
+        let len = match source.read(&mut chunk) {
+            Ok(0) => return,
+            Ok(len) => len,
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => {
+                let _ = sender.send(Err(err));
+                return;
+            }
+        };
+        chunk.truncate(len);
+        if sender.send(Ok(chunk)).is_err() {
+            return;
+        }
+    }
+}
+
+/// A proof step decoded by the reader thread of a [`StepReader`].
+struct DecodedStep {
+    step: Result<OwnedProofStep, StepError>,
+    /// Raw bytes of the step.
+    bytes: Vec<u8>,
+    /// Number of bytes consumed up to the end of the step.
+    end_offset: u64,
+}
+
+/// Messages sent by the reader thread of a [`StepReader`].
+enum Decoded {
+    Header {
+        header: Result<ProofHeader, HeaderError>,
+        end_offset: u64,
+    },
+    Steps(Vec<DecodedStep>),
+}
+
+/// Source of proof steps that are read and decoded on a dedicated thread.
+///
+/// The reader thread reads, decompresses and decodes the proof ahead of the checker and passes
+/// batches of decoded steps to the checking thread using a bounded channel. This leaves only the
+/// checking of steps to the checking thread.
+///
+/// The reader thread stops after a step that ends the proof or can't be parsed.
+pub struct StepReader {
+    receiver: Receiver<Decoded>,
+    pending: vec::IntoIter<DecodedStep>,
+    step: OwnedProofStep,
+    bytes: Vec<u8>,
+    offset: u64,
+}
+
+impl StepReader {
+    /// Decode steps of a proof that is opened on a new thread.
+    ///
+    /// At most `capacity` batches of steps are decoded ahead. An error returned by `open` is
+    /// returned when reading the header.
+    pub fn spawn<R: Read>(
+        open: impl FnOnce() -> io::Result<R> + Send + 'static,
+        capacity: usize,
+    ) -> StepReader {
+        let (sender, receiver) = sync_channel(capacity);
+        // The thread is detached, it stops after the last step or when the reader is dropped.
+        thread::spawn(move || match open() {
+            Ok(source) => decode_steps(source, sender),
+            Err(err) => {
+                let _ = sender.send(Decoded::Header {
+                    header: Err(err.into()),
+                    end_offset: 0,
+                });
+            }
+        });
+        StepReader {
+            receiver,
+            pending: vec![].into_iter(),
+            step: OwnedProofStep::End,
+            bytes: vec![],
+            offset: 0,
+        }
+    }
+}
+
+/// Error returned when the reader thread stopped before sending the requested data.
+fn reader_stopped() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "proof reader thread stopped")
+}
+
+impl StepSource for StepReader {
+    fn read_header(&mut self) -> Result<ProofHeader, HeaderError> {
+        match self.receiver.recv() {
+            Ok(Decoded::Header { header, end_offset }) => {
+                self.offset = end_offset;
+                header
+            }
+            _ => Err(reader_stopped().into()),
+        }
+    }
+
+    fn next_step(&mut self) -> Result<ProofStep, StepError> {
+        let decoded = loop {
+            if let Some(decoded) = self.pending.next() {
+                break decoded;
+            }
+            match self.receiver.recv() {
+                Ok(Decoded::Steps(steps)) => self.pending = steps.into_iter(),
+                _ => return Err(reader_stopped().into()),
+            }
+        };
+        self.bytes = decoded.bytes;
+        self.offset = decoded.end_offset;
+        self.step = decoded.step?;
+        Ok(self.step.as_step())
+    }
+
+    fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    fn step_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// Main loop of the reader thread of a [`StepReader`].
+fn decode_steps(source: impl Read, sender: SyncSender<Decoded>) {
+    let mut input = RecordingReader::new(BufReader::new(CountingReader::new(source)));
+
+    let header = read_header(&mut input);
+    let header_ok = header.is_ok();
+    let end_offset = input.offset();
+    if sender.send(Decoded::Header { header, end_offset }).is_err() || !header_ok {
+        return;
+    }
+
+    let mut parser = Parser::default();
+    let mut batch = Vec::with_capacity(STEP_BATCH);
+    loop {
+        input.begin_step();
+        let step = parser
+            .parse_step(&mut input)
+            .map(|step| OwnedProofStep::from(&step));
+        // The checker doesn't read past these
+        let last = match step {
+            Ok(OwnedProofStep::End) | Err(_) => true,
+            _ => false,
+        };
+        batch.push(DecodedStep {
+            step,
+            bytes: input.step_bytes().to_vec(),
+            end_offset: input.offset(),
+        });
+        if last || batch.len() == STEP_BATCH {
+            let steps = replace(&mut batch, Vec::with_capacity(STEP_BATCH));
+            if sender.send(Decoded::Steps(steps)).is_err() || last {
+                return;
+            }
+        }
+    }
+}
+
+/// Main loop of the writer thread.
+fn write_messages(mut target: impl Write, receiver: Receiver<Message>) -> io::Result<()> {
+    for message in receiver {
+        match message {
+            Message::Data(chunk) => target.write_all(&chunk)?,
+            Message::Flush(ack) => {
+                target.flush()?;
+                let _ = ack.send(());
+            }
+        }
+    }
+    target.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use varisat_formula::Lit;
+    use varisat_internal_proof::binary_format::{write_header, write_step};
+
+    /// Shared in memory target.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Target accepting a limited number of bytes.
+    struct FailingTarget(usize);
+
+    impl Write for FailingTarget {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if buf.len() > self.0 {
+                return Err(io::Error::new(io::ErrorKind::Other, "disk full"));
+            }
+            self.0 -= buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn write_in_background() {
+        let target = SharedBuffer::default();
+        let mut writer = BackgroundWriter::new(target.clone(), 2);
+
+        let mut expected = vec![];
+        for i in 0..100_000u32 {
+            let bytes = i.to_le_bytes();
+            writer.write_all(&bytes).unwrap();
+            expected.extend_from_slice(&bytes);
+        }
+
+        writer.flush().unwrap();
+        assert_eq!(*target.0.lock().unwrap(), expected);
+
+        writer.write_all(b"end").unwrap();
+        writer.finish().unwrap();
+        expected.extend_from_slice(b"end");
+        assert_eq!(*target.0.lock().unwrap(), expected);
+    }
+
+    #[test]
+    fn propagate_errors() {
+        let mut writer = BackgroundWriter::new(FailingTarget(10), 2);
+
+        writer.write_all(&[0; 5]).unwrap();
+        writer.flush().unwrap();
+
+        writer.write_all(&[0; 10]).unwrap();
+        let err = writer.flush().unwrap_err();
+        assert_eq!(err.to_string(), "disk full");
+
+        assert!(writer.write_all(&[0; CHUNK_SIZE]).is_err());
+    }
+
+    #[test]
+    fn read_in_background() {
+        let data: Vec<u8> = (0..100_000u32)
+            .flat_map(|i| i.to_le_bytes().to_vec())
+            .collect();
+
+        let mut reader = BackgroundReader::new(io::Cursor::new(data.clone()), 2);
+        let mut read_data = vec![];
+        reader.read_to_end(&mut read_data).unwrap();
+        assert_eq!(read_data, data);
+
+        let mut reader = BackgroundReader::spawn(
+            || -> io::Result<io::Empty> { Err(io::Error::new(io::ErrorKind::Other, "no proof")) },
+            2,
+        );
+        let err = reader.read_to_end(&mut vec![]).unwrap_err();
+        assert_eq!(err.to_string(), "no proof");
+    }
+
+    #[test]
+    fn decode_in_background() {
+        let clauses: Vec<Vec<Lit>> = (1..1000)
+            .map(|i| vec![Lit::from_dimacs(i), Lit::from_dimacs(-i - 1)])
+            .collect();
+
+        let mut data = vec![];
+        write_header(&mut data, &[("solver", "test")]).unwrap();
+        let header_len = data.len() as u64;
+        let mut offsets = vec![];
+        for clause in clauses.iter() {
+            write_step(&mut data, &ProofStep::AddClause { clause }).unwrap();
+            offsets.push(data.len() as u64);
+        }
+        write_step(&mut data, &ProofStep::End).unwrap();
+
+        let mut reader = StepReader::spawn(move || Ok(io::Cursor::new(data)), 2);
+        let header = reader.read_header().unwrap();
+        assert_eq!(header.metadata, vec![("solver".into(), "test".into())]);
+        assert_eq!(reader.offset(), header_len);
+
+        for (clause, &offset) in clauses.iter().zip(offsets.iter()) {
+            assert_eq!(
+                OwnedProofStep::from(&reader.next_step().unwrap()),
+                OwnedProofStep::from(&ProofStep::AddClause { clause })
+            );
+            assert_eq!(reader.offset(), offset);
+        }
+        assert_eq!(
+            OwnedProofStep::from(&reader.next_step().unwrap()),
+            OwnedProofStep::End
+        );
+        assert!(reader.next_step().is_err());
+
+        let mut reader = StepReader::spawn(
+            || -> io::Result<io::Empty> { Err(io::Error::new(io::ErrorKind::Other, "no proof")) },
+            2,
+        );
+        let err = reader.read_header().unwrap_err();
+        assert_eq!(err.to_string(), "no proof");
+    }
+}
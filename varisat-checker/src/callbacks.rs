@@ -0,0 +1,298 @@
+//! User provided callbacks.
+use std::io::{self, BufRead, BufReader, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Number of proof steps between progress reports.
+const PROGRESS_INTERVAL: u64 = 100000;
+
+/// Progress of a proof check.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct CheckerProgress {
+    /// Number of proof steps processed.
+    pub steps: u64,
+    /// Number of non-unit clauses currently present.
+    pub clauses: usize,
+    /// Number of bytes of the proof consumed.
+    pub bytes: u64,
+}
+
+/// Statistics about a proof check.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct CheckerStats {
+    /// Number of checked AT clause steps.
+    pub at_steps: u64,
+    /// Number of literals propagated while checking clauses.
+    pub propagations: u64,
+    /// Number of non-unit clauses currently present.
+    pub clauses: usize,
+    /// Maximal number of non-unit clauses present at the same time.
+    pub peak_clauses: usize,
+    /// Number of garbage collections of the clause literal storage.
+    pub garbage_collections: u64,
+}
+
+/// Token used to cancel a running proof check.
+///
+/// The token can be cloned and sent to another thread. Cancelling any clone cancels the check the
+/// token was registered with.
+#[derive(Clone, Default, Debug)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// Create a new token that is not cancelled.
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    /// Request cancellation of the check.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether cancellation was requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// User provided callbacks invoked during checking.
+#[derive(Default)]
+pub struct Callbacks<'a> {
+    /// Invoked periodically with the current progress.
+    progress: Option<Box<dyn FnMut(&CheckerProgress) + 'a>>,
+    /// Polled at every step, checking is stopped once it is cancelled.
+    cancel: Option<CancelToken>,
+}
+
+impl<'a> Callbacks<'a> {
+    /// Set or remove the progress callback.
+    pub fn set_progress(&mut self, callback: Option<Box<dyn FnMut(&CheckerProgress) + 'a>>) {
+        self.progress = callback;
+    }
+
+    /// Set or remove the cancellation token.
+    pub fn set_cancel(&mut self, token: Option<CancelToken>) {
+        self.cancel = token;
+    }
+
+    /// Whether the user requested cancellation of the check.
+    pub fn is_cancelled(&self) -> bool {
+        match &self.cancel {
+            Some(token) => token.is_cancelled(),
+            None => false,
+        }
+    }
+
+    /// Whether a progress report is due at the given step.
+    pub fn wants_progress(&self, step: u64) -> bool {
+        self.progress.is_some() && step % PROGRESS_INTERVAL == 0
+    }
+
+    /// Invoke the progress callback, if present.
+    pub fn report_progress(&mut self, progress: &CheckerProgress) {
+        if let Some(callback) = &mut self.progress {
+            callback(progress);
+        }
+    }
+}
+
+/// Reader that counts the bytes read from it.
+pub struct CountingReader<R> {
+    inner: R,
+    bytes: u64,
+}
+
+impl<R> CountingReader<R> {
+    pub fn new(inner: R) -> CountingReader<R> {
+        CountingReader { inner, bytes: 0 }
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.bytes += len as u64;
+        Ok(len)
+    }
+}
+
+/// Number of bytes consumed from a buffered counting reader.
+pub fn bytes_consumed<R>(reader: &BufReader<CountingReader<R>>) -> u64 {
+    reader.get_ref().bytes - reader.buffer().len() as u64
+}
+
+/// Buffered reader that keeps a copy of the bytes consumed since the last call to `clear`.
+///
+/// Used to report the raw bytes of a failing proof step.
+pub struct RecordingReader<R> {
+    inner: R,
+    recorded: Vec<u8>,
+}
+
+impl<R> RecordingReader<R> {
+    pub fn new(inner: R) -> RecordingReader<R> {
+        RecordingReader {
+            inner,
+            recorded: vec![],
+        }
+    }
+
+    /// The wrapped reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Bytes consumed since the last call to `clear`.
+    pub fn recorded(&self) -> &[u8] {
+        &self.recorded
+    }
+
+    /// Forget the recorded bytes.
+    pub fn clear(&mut self) {
+        self.recorded.clear();
+    }
+}
+
+impl<R: BufRead> Read for RecordingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.recorded.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+}
+
+impl<R: BufRead> BufRead for RecordingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // The consumed bytes are still buffered, so this doesn't perform any IO.
+        if let Ok(buf) = self.inner.fill_buf() {
+            let len = amt.min(buf.len());
+            self.recorded.extend_from_slice(&buf[..len]);
+        }
+        self.inner.consume(amt);
+    }
+}
+
+/// Proof input that tracks the position and the raw bytes of the current step.
+pub trait ProofInput: BufRead {
+    /// Number of bytes consumed so far.
+    fn offset(&self) -> u64;
+
+    /// Start a new step, forgetting the bytes of the previous one.
+    fn begin_step(&mut self);
+
+    /// Bytes consumed since the last call to `begin_step`.
+    fn step_bytes(&self) -> &[u8];
+}
+
+impl<R: Read> ProofInput for RecordingReader<BufReader<CountingReader<R>>> {
+    fn offset(&self) -> u64 {
+        bytes_consumed(self.get_ref())
+    }
+
+    fn begin_step(&mut self) {
+        self.clear();
+    }
+
+    fn step_bytes(&self) -> &[u8] {
+        self.recorded()
+    }
+}
+
+/// Proof input reading directly from memory.
+///
+/// Unlike [`RecordingReader`] this doesn't copy the consumed bytes, as they can be referenced in
+/// the underlying slice.
+pub struct SliceInput<'a> {
+    data: &'a [u8],
+    pos: usize,
+    step_start: usize,
+}
+
+impl<'a> SliceInput<'a> {
+    pub fn new(data: &'a [u8]) -> SliceInput<'a> {
+        SliceInput {
+            data,
+            pos: 0,
+            step_start: 0,
+        }
+    }
+}
+
+impl<'a> Read for SliceInput<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.data.len() - self.pos);
+        buf[..len].copy_from_slice(&self.data[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+impl<'a> BufRead for SliceInput<'a> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(&self.data[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.data.len());
+    }
+}
+
+impl<'a> ProofInput for SliceInput<'a> {
+    fn offset(&self) -> u64 {
+        self.pos as u64
+    }
+
+    fn begin_step(&mut self) {
+        self.step_start = self.pos;
+    }
+
+    fn step_bytes(&self) -> &[u8] {
+        &self.data[self.step_start..self.pos]
+    }
+}
+
+/// Reader that reads multiple segments one after the other.
+///
+/// Segments are opened lazily, an error opening a segment is returned by the call to `read` that
+/// reaches that segment.
+pub struct SegmentedReader<'a, I> {
+    segments: I,
+    current: Option<Box<dyn Read + 'a>>,
+}
+
+impl<'a, I> SegmentedReader<'a, I> {
+    pub fn new(segments: I) -> SegmentedReader<'a, I> {
+        SegmentedReader {
+            segments,
+            current: None,
+        }
+    }
+}
+
+impl<'a, I> Read for SegmentedReader<'a, I>
+where
+    I: Iterator<Item = io::Result<Box<dyn Read + 'a>>>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.current.is_none() {
+                match self.segments.next() {
+                    Some(segment) => self.current = Some(segment?),
+                    None => return Ok(0),
+                }
+            }
+            let len = self.current.as_mut().unwrap().read(buf)?;
+            if len > 0 || buf.is_empty() {
+                return Ok(len);
+            }
+            self.current = None;
+        }
+    }
+}
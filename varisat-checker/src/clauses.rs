@@ -1,8 +1,8 @@
 //! Clause storage (unit and non-unit clauses).
 use std::convert::TryInto;
+use std::io;
 use std::mem::transmute;
 
-use hashbrown::HashMap;
 use partial_ref::{partial, PartialRef};
 use smallvec::SmallVec;
 
@@ -10,6 +10,8 @@ use varisat_formula::{lit::LitIdx, Lit};
 use varisat_internal_proof::ClauseHash;
 
 use crate::context::{parts::*, Context};
+use crate::hash::ClauseHashMap;
+use crate::literal_buffer::LiteralBuffer;
 use crate::processing::{process_step, CheckedProofStep};
 use crate::sorted_lits::copy_canonical;
 use crate::variables::{ensure_sampling_var, ensure_var};
@@ -25,7 +27,7 @@ pub struct ClauseLits {
 
 impl ClauseLits {
     /// Create a new ClauseLits, storing them in the given buffer if necessary
-    fn new(lits: &[Lit], buffer: &mut Vec<Lit>) -> ClauseLits {
+    fn new(lits: &[Lit], buffer: &mut LiteralBuffer) -> io::Result<ClauseLits> {
         let mut inline = [0; INLINE_LITS];
         let length = lits.len();
 
@@ -34,7 +36,7 @@ impl ClauseLits {
                 .len()
                 .try_into()
                 .expect("exceeded maximal literal buffer size");
-            buffer.extend(lits);
+            buffer.extend_from_slice(lits)?;
         } else {
             let lits = unsafe {
                 // Lit is a repr(transparent) wrapper of LitIdx
@@ -43,10 +45,10 @@ impl ClauseLits {
             inline[..length].copy_from_slice(lits);
         }
 
-        ClauseLits {
+        Ok(ClauseLits {
             length: length as LitIdx,
             inline,
-        }
+        })
     }
 
     /// Returns the literals as a slice given a storage buffer
@@ -88,6 +90,18 @@ pub struct Clause {
     pub lits: ClauseLits,
 }
 
+impl Clause {
+    /// How often the clause is present, counting irredundant and redundant copies.
+    pub fn ref_count(&self) -> u32 {
+        self.ref_count[0] + self.ref_count[1]
+    }
+
+    /// Whether the clause is present as an irredundant clause.
+    pub fn is_irredundant(&self) -> bool {
+        self.ref_count[0] > 0
+    }
+}
+
 /// Identifies the origin of a unit clause.
 #[derive(Copy, Clone, Debug)]
 pub enum UnitId {
@@ -118,17 +132,50 @@ pub enum DeleteClauseResult {
     NewlyRedundant,
     Removed,
 }
+/// When to compact the literal storage of long clauses.
+///
+/// Compaction walks all stored clauses, so it can take a significant part of the checking time for
+/// proofs with many deletions.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct GcPolicy {
+    /// Fraction of the literal storage that has to be used by deleted clauses before compacting.
+    ///
+    /// Defaults to 0.5.
+    pub garbage_ratio: f64,
+    /// Minimal number of literals of deleted clauses before compacting.
+    ///
+    /// Defaults to 0.
+    pub min_garbage: usize,
+    /// Defer compaction until the next long clause is stored.
+    ///
+    /// When this is enabled a burst of deletions causes at most one compaction. Otherwise the
+    /// storage is compacted as soon as a deletion exceeds the thresholds. Defaults to true.
+    pub deferred: bool,
+}
+
+impl Default for GcPolicy {
+    fn default() -> GcPolicy {
+        GcPolicy {
+            garbage_ratio: 0.5,
+            min_garbage: 0,
+            deferred: true,
+        }
+    }
+}
+
 /// Checker clause storage.
 #[derive(Default)]
 pub struct Clauses {
     /// Next clause id to use.
     pub next_clause_id: u64,
     /// Literal storage for clauses,
-    pub literal_buffer: Vec<Lit>,
+    pub literal_buffer: LiteralBuffer,
     /// Number of literals in the buffer which are from deleted clauses.
     garbage_size: usize,
+    /// When the literal buffer is compacted.
+    pub gc_policy: GcPolicy,
     /// Stores all known non-unit clauses indexed by their hash.
-    pub clauses: HashMap<ClauseHash, SmallVec<[Clause; 1]>>,
+    pub clauses: ClauseHashMap<SmallVec<[Clause; 1]>>,
     /// Stores known unit clauses and propagations during a clause check.
     pub unit_clauses: Vec<Option<UnitClause>>,
     /// This stores a conflict of input unit clauses.
@@ -136,14 +183,80 @@ pub struct Clauses {
     /// Our representation for unit clauses doesn't support conflicting units so this is used as a
     /// workaround.
     pub unit_conflict: Option<[u64; 2]>,
+    /// Number of non-unit clauses currently stored.
+    clause_count: usize,
+    /// Maximal value of `clause_count` so far.
+    peak_clause_count: usize,
+    /// Number of garbage collections of the literal buffer.
+    garbage_collections: u64,
+    /// Number of non-unit clauses above which the literal buffer is moved to disk.
+    pub spill_limit: Option<usize>,
 }
 
 impl Clauses {
+    /// Number of non-unit clauses currently stored.
+    pub fn clause_count(&self) -> usize {
+        self.clause_count
+    }
+
+    /// Maximal number of non-unit clauses stored at the same time.
+    pub fn peak_clause_count(&self) -> usize {
+        self.peak_clause_count
+    }
+
+    /// Describe the clauses stored with a given hash.
+    ///
+    /// Used to diagnose hash collisions. Clauses with the same hash are always distinct.
+    pub fn describe_bucket(&self, hash: ClauseHash) -> String {
+        let candidates = match self.clauses.get(&hash) {
+            Some(candidates) => candidates,
+            None => return format!("no clause has hash {:x}", hash),
+        };
+        let descriptions: Vec<String> = candidates
+            .iter()
+            .map(|clause| {
+                format!(
+                    "{:?} (id {})",
+                    clause.lits.slice(&self.literal_buffer),
+                    clause.id + 1
+                )
+            })
+            .collect();
+        match descriptions.len() {
+            0 => format!("no clause has hash {:x}", hash),
+            1 => format!("only clause {} has hash {:x}", descriptions[0], hash),
+            _ => format!(
+                "hash collision between clauses {} sharing hash {:x}",
+                descriptions.join(", "),
+                hash
+            ),
+        }
+    }
+
+    /// Number of garbage collections of the literal buffer.
+    pub fn garbage_collections(&self) -> u64 {
+        self.garbage_collections
+    }
+
     /// Value of a literal if known from unit clauses.
     pub fn lit_value(&self, lit: Lit) -> Option<(bool, UnitClause)> {
         self.unit_clauses[lit.index()]
             .map(|unit_clause| (unit_clause.value ^ lit.is_negative(), unit_clause))
     }
+
+    /// Move the literal buffer to disk if there are more clauses than the configured limit.
+    fn spill_if_required(&mut self) {
+        match self.spill_limit {
+            Some(limit) if self.clause_count > limit && !self.literal_buffer.is_spilled() => {
+                if let Err(err) = self.literal_buffer.spill() {
+                    log::warn!("could not move literal buffer to disk: {}", err);
+                    // Don't retry for every added clause
+                    self.spill_limit = None;
+                }
+            }
+            _ => (),
+        }
+    }
 }
 
 /// Adds a clause to the checker.
@@ -174,7 +287,7 @@ pub fn add_clause<'a>(
         ensure_sampling_var(ctx.borrow(), lit.var())?;
     }
 
-    let (id, added) = store_clause(ctx.borrow(), &tmp_data.tmp, false);
+    let (id, added) = store_clause(ctx.borrow(), &tmp_data.tmp, false)?;
 
     let (clauses, mut ctx) = ctx.split_part_mut(ClausesP);
 
@@ -232,7 +345,7 @@ pub fn store_clause(
     ),
     lits: &[Lit],
     redundant: bool,
-) -> (u64, StoreClauseResult) {
+) -> Result<(u64, StoreClauseResult), CheckerError> {
     for &lit in lits.iter() {
         ensure_var(ctx.borrow(), lit.var());
     }
@@ -243,10 +356,16 @@ pub fn store_clause(
             ctx.part_mut(ClausesP).next_clause_id += 1;
 
             ctx.part_mut(CheckerStateP).unsat = true;
-            (id, StoreClauseResult::New)
+            Ok((id, StoreClauseResult::New))
         }
-        [lit] => store_unit_clause(ctx.borrow(), lit),
+        [lit] => Ok(store_unit_clause(ctx.borrow(), lit)),
         _ => {
+            // Garbage is only collected when the buffer grows, so a burst of deletions causes at
+            // most one collection.
+            if ctx.part(ClausesP).gc_policy.deferred {
+                collect_garbage(ctx.borrow());
+            }
+
             let hash = ctx.part(ClauseHasherP).clause_hash(&lits);
 
             let (clauses, mut ctx) = ctx.split_part_mut(ClausesP);
@@ -264,10 +383,23 @@ pub fn store_clause(
 
                     let ref_count = &mut candidate.ref_count[redundant as usize];
                     *ref_count = ref_count.checked_add(1).expect("ref_count overflow");
-                    return (candidate.id, result);
+                    return Ok((candidate.id, result));
                 }
             }
 
+            let clause_lits = match ClauseLits::new(&lits, &mut clauses.literal_buffer) {
+                Ok(clause_lits) => clause_lits,
+                Err(cause) => {
+                    if candidates.is_empty() {
+                        clauses.clauses.remove(&hash);
+                    }
+                    return Err(CheckerError::StorageError {
+                        step: ctx.part(CheckerStateP).step,
+                        cause,
+                    });
+                }
+            };
+
             let id = clauses.next_clause_id;
 
             let mut ref_count = [0, 0];
@@ -276,16 +408,20 @@ pub fn store_clause(
             candidates.push(Clause {
                 id,
                 ref_count,
-                lits: ClauseLits::new(&lits, &mut clauses.literal_buffer),
+                lits: clause_lits,
             });
 
             clauses.next_clause_id += 1;
+            clauses.clause_count += 1;
+            clauses.peak_clause_count = clauses.peak_clause_count.max(clauses.clause_count);
 
             for &lit in lits.iter() {
                 ctx.part_mut(VariablesP).lit_data[lit.code()].clause_count += 1;
             }
 
-            (id, StoreClauseResult::New)
+            clauses.spill_if_required();
+
+            Ok((id, StoreClauseResult::New))
         }
     }
 }
@@ -363,7 +499,15 @@ pub fn delete_clause(
 
     let clauses = ctx.part_mut(ClausesP);
 
-    let candidates = clauses.clauses.entry(hash).or_default();
+    let candidates = match clauses.clauses.get_mut(&hash) {
+        Some(candidates) => candidates,
+        None => {
+            return Err(CheckerError::check_failed(
+                ctx.part(CheckerStateP).step,
+                format!("delete of unknown clause {:?}", lits),
+            ))
+        }
+    };
 
     let mut found = false;
 
@@ -405,13 +549,16 @@ pub fn delete_clause(
     }
 
     if let Some((_, DeleteClauseResult::Removed)) = result {
+        clauses.clause_count -= 1;
         for &lit in lits.iter() {
             ctx.part_mut(VariablesP).lit_data[lit.code()].clause_count -= 1;
         }
+        if !ctx.part(ClausesP).gc_policy.deferred {
+            collect_garbage(ctx.borrow());
+        }
     }
 
     if let Some(result) = result {
-        collect_garbage(ctx.borrow());
         return Ok(result);
     }
 
@@ -426,25 +573,50 @@ pub fn delete_clause(
     ));
 }
 
-/// Perform a garbage collection if required
+/// Perform a garbage collection if required by the [`GcPolicy`].
 fn collect_garbage(mut ctx: partial!(Context, mut ClausesP)) {
     let clauses = ctx.part_mut(ClausesP);
-    if clauses.garbage_size * 2 <= clauses.literal_buffer.len() {
+    let policy = clauses.gc_policy;
+    if clauses.garbage_size as f64 <= clauses.literal_buffer.len() as f64 * policy.garbage_ratio
+        || clauses.garbage_size < policy.min_garbage
+    {
         return;
     }
 
-    let mut new_buffer = vec![];
+    let mut new_buffer = match clauses
+        .literal_buffer
+        .empty_like(clauses.literal_buffer.len() - clauses.garbage_size)
+    {
+        Ok(new_buffer) => new_buffer,
+        Err(err) => {
+            log::warn!("could not allocate on-disk literal buffer: {}", err);
+            return;
+        }
+    };
 
-    new_buffer.reserve(clauses.literal_buffer.len());
+    // The clauses keep referring to the old buffer until all literals were copied, so a failure
+    // to grow the new buffer leaves the storage intact.
+    let mut new_clause_lits = Vec::with_capacity(clauses.clause_count);
+    for (_, candidates) in clauses.clauses.iter() {
+        for clause in candidates.iter() {
+            match ClauseLits::new(clause.lits.slice(&clauses.literal_buffer), &mut new_buffer) {
+                Ok(new_lits) => new_clause_lits.push(new_lits),
+                Err(err) => {
+                    log::warn!("could not compact on-disk literal buffer: {}", err);
+                    return;
+                }
+            }
+        }
+    }
 
+    let mut new_clause_lits = new_clause_lits.into_iter();
     for (_, candidates) in clauses.clauses.iter_mut() {
         for clause in candidates.iter_mut() {
-            let new_lits =
-                ClauseLits::new(clause.lits.slice(&clauses.literal_buffer), &mut new_buffer);
-            clause.lits = new_lits;
+            clause.lits = new_clause_lits.next().unwrap();
         }
     }
 
     clauses.literal_buffer = new_buffer;
     clauses.garbage_size = 0;
+    clauses.garbage_collections += 1;
 }
@@ -0,0 +1,117 @@
+//! Compressed proof streams.
+use std::io::{self, Read, Write};
+
+use flate2::{read::MultiGzDecoder, write::GzEncoder};
+
+/// Magic bytes at the start of a gzip stream.
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+/// Magic bytes at the start of a zstd frame.
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression formats supported for proof streams.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Compression format suggested by the extension of a file name.
+    ///
+    /// Returns `None` for uncompressed files.
+    pub fn from_path(path: &str) -> Option<Compression> {
+        if path.ends_with(".gz") {
+            Some(Compression::Gzip)
+        } else if path.ends_with(".zst") {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// Compression format detected from the first bytes of a stream.
+    pub(crate) fn detect(prefix: &[u8]) -> Option<Compression> {
+        if prefix.starts_with(GZIP_MAGIC) {
+            Some(Compression::Gzip)
+        } else if prefix.starts_with(ZSTD_MAGIC) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+/// Compress all data written to `target`.
+///
+/// The compressed stream is completed when the returned writer is dropped.
+pub fn compress<'a>(
+    target: impl Write + Send + 'a,
+    compression: Compression,
+) -> io::Result<Box<dyn Write + Send + 'a>> {
+    Ok(match compression {
+        Compression::Gzip => Box::new(GzEncoder::new(target, flate2::Compression::default())),
+        Compression::Zstd => Box::new(zstd::Encoder::new(target, 0)?.auto_finish()),
+    })
+}
+
+/// Transparently decompress a possibly compressed stream.
+///
+/// The compression format is detected from the first bytes of the stream. Uncompressed streams
+/// are passed through unchanged.
+pub fn decompress<'a>(mut input: impl Read + 'a) -> io::Result<Box<dyn Read + 'a>> {
+    let mut prefix = [0; 4];
+    let mut prefix_len = 0;
+    while prefix_len < prefix.len() {
+        match input.read(&mut prefix[prefix_len..]) {
+            Ok(0) => break,
+            Ok(len) => prefix_len += len,
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => (),
+            Err(err) => return Err(err),
+        }
+    }
+
+    let compression = Compression::detect(&prefix[..prefix_len]);
+    let input = io::Cursor::new(prefix).take(prefix_len as u64).chain(input);
+
+    Ok(match compression {
+        None => Box::new(input),
+        Some(Compression::Gzip) => Box::new(MultiGzDecoder::new(input)),
+        Some(Compression::Zstd) => Box::new(zstd::Decoder::new(input)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let data: Vec<u8> = (0..10_000u32)
+            .flat_map(|i| i.to_le_bytes().to_vec())
+            .collect();
+
+        for &compression in [Compression::Gzip, Compression::Zstd].iter() {
+            let mut compressed = vec![];
+            {
+                let mut writer = compress(&mut compressed, compression).unwrap();
+                writer.write_all(&data).unwrap();
+            }
+            assert_eq!(Compression::detect(&compressed), Some(compression));
+            assert!(compressed.len() < data.len());
+
+            let mut decompressed = vec![];
+            decompress(&compressed[..])
+                .unwrap()
+                .read_to_end(&mut decompressed)
+                .unwrap();
+            assert_eq!(decompressed, data);
+        }
+
+        let mut passed_through = vec![];
+        decompress(&data[..3])
+            .unwrap()
+            .read_to_end(&mut passed_through)
+            .unwrap();
+        assert_eq!(passed_through, &data[..3]);
+    }
+}
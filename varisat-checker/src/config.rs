@@ -0,0 +1,63 @@
+//! Checker configuration.
+use varisat_internal_proof::MAX_HASH_BITS;
+
+use crate::clauses::GcPolicy;
+use crate::state::UnitDeletionPolicy;
+
+/// Configuration of a [`Checker`](crate::Checker).
+///
+/// Passed to [`Checker::new_with_config`](crate::Checker::new_with_config). The default
+/// configuration is the one used by [`Checker::new`](crate::Checker::new).
+#[derive(Clone, Debug)]
+pub struct CheckerConfig {
+    /// How deletions of unit and empty clauses are handled.
+    ///
+    /// Some solvers log deletions of unit clauses, so checking their proofs requires ignoring or
+    /// performing these deletions. Defaults to [`UnitDeletionPolicy::Reject`].
+    pub unit_deletion_policy: UnitDeletionPolicy,
+    /// Width of clause hashes at the start of a proof.
+    ///
+    /// Varisat proofs start using 64 bit hashes and change the width with
+    /// [`ChangeHashBits`](crate::ProofStep::ChangeHashBits) steps. Checkers fed directly by a
+    /// solver can start with wider hashes. Must be between 1 and 128. Defaults to 64.
+    pub hash_bits: u32,
+    /// When the clause literal storage is compacted.
+    pub gc_policy: GcPolicy,
+    /// Move clause literals to disk when more than this many clauses are stored.
+    ///
+    /// Once the limit is exceeded, the literals of clauses with more than three literals are kept
+    /// in a memory mapped temporary file. Only the literals are moved: the index of all clauses
+    /// and the literals of shorter clauses stay in memory, so this reduces but does not bound the
+    /// memory used for large proofs. Requires the `disk-spill` feature; without it the clauses
+    /// stay in memory. Defaults to `None`, keeping all clauses in memory.
+    pub clause_spill_limit: Option<usize>,
+    /// Report hash collisions when a step fails.
+    ///
+    /// When a clause check fails, all clauses matching the step's hashes are inspected and
+    /// distinct clauses sharing a hash are included in the error message. This makes failures
+    /// caused by narrow hashes debuggable but slows down failing steps. Defaults to `false`.
+    pub collision_diagnostics: bool,
+}
+
+impl Default for CheckerConfig {
+    fn default() -> CheckerConfig {
+        CheckerConfig {
+            unit_deletion_policy: UnitDeletionPolicy::default(),
+            hash_bits: 64,
+            gc_policy: GcPolicy::default(),
+            clause_spill_limit: None,
+            collision_diagnostics: false,
+        }
+    }
+}
+
+impl CheckerConfig {
+    /// Panics if the configuration is invalid.
+    pub(crate) fn validate(&self) {
+        assert!(
+            self.hash_bits > 0 && self.hash_bits <= MAX_HASH_BITS,
+            "hash bits must be in range 1..={}",
+            MAX_HASH_BITS
+        );
+    }
+}
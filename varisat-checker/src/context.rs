@@ -1,6 +1,7 @@
 //! Central checker data structure.
 use partial_ref::{part, PartialRefTarget};
 
+use crate::callbacks::Callbacks;
 use crate::clauses::Clauses;
 use crate::hash::ClauseHasher;
 use crate::processing::Processing;
@@ -13,6 +14,7 @@ use crate::variables::Variables;
 pub mod parts {
     use super::*;
 
+    part!(pub CallbacksP<'a>: Callbacks<'a>);
     part!(pub CheckerStateP: CheckerState);
     part!(pub ClauseHasherP: ClauseHasher);
     part!(pub ClausesP: Clauses);
@@ -32,6 +34,8 @@ use parts::*;
 /// references.
 #[derive(PartialRefTarget, Default)]
 pub struct Context<'a> {
+    #[part(CallbacksP<'a>)]
+    pub callbacks: Callbacks<'a>,
     #[part(CheckerStateP)]
     pub checker_state: CheckerState,
     #[part(ClauseHasherP)]
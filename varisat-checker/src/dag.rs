@@ -0,0 +1,284 @@
+//! Export of the clause dependency graph of a proof.
+use std::io::{self, BufWriter, Write};
+
+use varisat_formula::Lit;
+
+use crate::processing::{
+    CheckedProofStep, CheckerData, ProcessorError, ProofProcessor, ProofVerdict,
+};
+
+/// Graph description formats supported by [`WriteProofDag`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum DagFormat {
+    /// The DOT language used by Graphviz.
+    Dot,
+    /// The XML based GraphML format.
+    GraphMl,
+}
+
+/// Kind of a node in the dependency graph.
+#[derive(Copy, Clone)]
+enum NodeKind {
+    Input,
+    At,
+    Rat,
+    Pr,
+}
+
+impl NodeKind {
+    fn name(self) -> &'static str {
+        match self {
+            NodeKind::Input => "input",
+            NodeKind::At => "at",
+            NodeKind::Rat => "rat",
+            NodeKind::Pr => "pr",
+        }
+    }
+}
+
+/// Proof processor that writes the dependency graph of the checked clauses.
+///
+/// Each input clause and each derived clause becomes a node, labeled by its literals. Node names
+/// are the clause ids plus one, as used in LRAT and TraceCheck proofs. For each clause used in the
+/// propagations justifying a derived clause, there is an edge from that clause to the derived
+/// clause. For RAT and PR clauses this includes the resolved clauses.
+///
+/// Duplicated and tautological input clauses are never used and are omitted. Deletions are
+/// ignored. The graph is completed when the proof ends.
+pub struct WriteProofDag<'a> {
+    format: DagFormat,
+    target: BufWriter<Box<dyn Write + 'a>>,
+    started: bool,
+    /// Sources of the edges of the current node.
+    sources: Vec<u64>,
+}
+
+impl<'a> ProofProcessor for WriteProofDag<'a> {
+    fn process_step(
+        &mut self,
+        step: &CheckedProofStep,
+        _data: CheckerData,
+    ) -> Result<(), ProcessorError> {
+        self.sources.clear();
+
+        let (id, clause, kind) = match step {
+            &CheckedProofStep::AddClause { id, clause } => (id, clause, NodeKind::Input),
+            &CheckedProofStep::AtClause {
+                id,
+                clause,
+                propagations,
+                ..
+            } => {
+                self.sources.extend_from_slice(propagations);
+                (id, clause, NodeKind::At)
+            }
+            &CheckedProofStep::RatClause {
+                id,
+                clause,
+                propagations,
+                ..
+            } => {
+                self.add_resolution_sources(propagations.iter());
+                (id, clause, NodeKind::Rat)
+            }
+            &CheckedProofStep::PrClause {
+                id,
+                clause,
+                propagations,
+                ..
+            } => {
+                self.add_resolution_sources(propagations.iter());
+                (id, clause, NodeKind::Pr)
+            }
+            _ => return Ok(()),
+        };
+
+        self.sources.sort_unstable();
+        self.sources.dedup();
+
+        self.write_node(id, clause, kind)?;
+        for i in 0..self.sources.len() {
+            self.write_edge(self.sources[i], id)?;
+        }
+
+        Ok(())
+    }
+
+    fn end_of_proof(&mut self, _verdict: ProofVerdict) -> Result<(), ProcessorError> {
+        self.start()?;
+        match self.format {
+            DagFormat::Dot => self.target.write_all(b"}\n")?,
+            DagFormat::GraphMl => self.target.write_all(b"  </graph>\n</graphml>\n")?,
+        }
+        Ok(self.flush()?)
+    }
+}
+
+impl<'a> WriteProofDag<'a> {
+    /// Create a processor writing the dependency graph in the given format.
+    ///
+    /// The graph is written to `target`.
+    pub fn new(target: impl Write + 'a, format: DagFormat) -> WriteProofDag<'a> {
+        WriteProofDag {
+            format,
+            target: BufWriter::new(Box::new(target)),
+            started: false,
+            sources: vec![],
+        }
+    }
+
+    /// Write out all steps processed so far.
+    ///
+    /// This is automatically called when the proof ends. Calling this explicitly is recommended to
+    /// handle possible IO errors.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.target.flush()
+    }
+
+    /// Add the resolved clauses and their propagations to the sources.
+    fn add_resolution_sources<'b>(&mut self, resolutions: impl Iterator<Item = (u64, &'b [u64])>) {
+        for (id, propagations) in resolutions {
+            self.sources.push(id);
+            self.sources.extend_from_slice(propagations);
+        }
+    }
+
+    /// Write the header if it wasn't written yet.
+    fn start(&mut self) -> io::Result<()> {
+        if !self.started {
+            self.started = true;
+            match self.format {
+                DagFormat::Dot => self.target.write_all(b"digraph proof {\n")?,
+                DagFormat::GraphMl => self.target.write_all(
+                    b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                      <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n  \
+                      <key id=\"clause\" for=\"node\" attr.name=\"clause\" attr.type=\"string\"/>\n  \
+                      <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>\n  \
+                      <graph id=\"proof\" edgedefault=\"directed\">\n",
+                )?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a node for a clause.
+    fn write_node(&mut self, id: u64, clause: &[Lit], kind: NodeKind) -> io::Result<()> {
+        self.start()?;
+
+        let label = clause
+            .iter()
+            .map(|lit| lit.to_dimacs().to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        match self.format {
+            DagFormat::Dot => {
+                let style = match kind {
+                    NodeKind::Input => " shape=box",
+                    NodeKind::At => "",
+                    NodeKind::Rat => " style=dashed",
+                    NodeKind::Pr => " style=dotted",
+                };
+                writeln!(self.target, "  {} [label=\"{}\"{}];", id + 1, label, style)?;
+            }
+            DagFormat::GraphMl => {
+                writeln!(
+                    self.target,
+                    "    <node id=\"{}\"><data key=\"clause\">{}</data>\
+                     <data key=\"kind\">{}</data></node>",
+                    id + 1,
+                    label,
+                    kind.name()
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write an edge from an antecedent to a derived clause.
+    fn write_edge(&mut self, source: u64, target: u64) -> io::Result<()> {
+        match self.format {
+            DagFormat::Dot => writeln!(self.target, "  {} -> {};", source + 1, target + 1)?,
+            DagFormat::GraphMl => writeln!(
+                self.target,
+                "    <edge source=\"{}\" target=\"{}\"/>",
+                source + 1,
+                target + 1
+            )?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::{cnf_formula, lits};
+
+    use crate::{Checker, ProofStep};
+
+    fn write_dag(format: DagFormat) -> String {
+        let mut dag = vec![];
+
+        {
+            let mut write_dag = WriteProofDag::new(&mut dag, format);
+            let mut checker = Checker::new();
+            checker.add_processor(&mut write_dag);
+
+            checker
+                .add_formula(&cnf_formula![
+                    1, 2;
+                    -1, 2;
+                    -2, 3;
+                ])
+                .unwrap();
+
+            let hashes = [
+                checker.clause_hash(&lits![1, 2]),
+                checker.clause_hash(&lits![-1, 2]),
+            ];
+
+            checker
+                .check_step(ProofStep::AtClause {
+                    redundant: false,
+                    clause: &lits![2],
+                    propagation_hashes: &hashes[..],
+                })
+                .unwrap();
+
+            let mut proof = vec![];
+            varisat_internal_proof::binary_format::write_header(&mut proof, &[]).unwrap();
+            varisat_internal_proof::binary_format::write_step(&mut proof, &ProofStep::End).unwrap();
+            checker.check_proof(&proof[..]).unwrap();
+        }
+
+        String::from_utf8(dag).unwrap()
+    }
+
+    #[test]
+    fn dot_graph() {
+        assert_eq!(
+            write_dag(DagFormat::Dot),
+            "digraph proof {\n  \
+             1 [label=\"1 2\" shape=box];\n  \
+             2 [label=\"-1 2\" shape=box];\n  \
+             3 [label=\"-2 3\" shape=box];\n  \
+             4 [label=\"2\"];\n  \
+             1 -> 4;\n  \
+             2 -> 4;\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn graphml_graph() {
+        let graph = write_dag(DagFormat::GraphMl);
+        assert!(graph.starts_with("<?xml"));
+        assert!(graph.contains(
+            "<node id=\"4\"><data key=\"clause\">2</data><data key=\"kind\">at</data></node>"
+        ));
+        assert!(graph.contains("<edge source=\"1\" target=\"4\"/>"));
+        assert!(graph.ends_with("  </graph>\n</graphml>\n"));
+    }
+}
@@ -0,0 +1,822 @@
+//! Checking of DRAT proofs without clause hashes.
+//!
+//! DRAT proofs generated by other solvers don't contain the propagation hashes of the native
+//! varisat format. Instead each lemma is checked by running unit propagation over all clauses,
+//! using a two-watched-literal scheme.
+//!
+//! Only the RUP part of DRAT is supported. Lemmas that require a resolution asymmetric tautology
+//! (RAT) check, as generated by solvers performing e.g. bounded variable addition or blocked
+//! clause addition, are rejected.
+use std::io::{self, BufRead};
+
+use partial_ref::{partial, PartialRef};
+use smallvec::SmallVec;
+use thiserror::Error;
+
+use varisat_formula::{Lit, Var};
+use varisat_internal_proof::{clause_hash, ClauseHash};
+
+use crate::callbacks::{bytes_consumed, CheckerProgress, CountingReader, RecordingReader};
+use crate::clauses::UnitId;
+use crate::context::{parts::*, Context};
+use crate::hash::ClauseHashMap;
+use crate::processing::{process_step, CheckedProofStep};
+use crate::sorted_lits::copy_canonical;
+use crate::state::process_unit_conflicts;
+use crate::variables::ensure_var;
+use crate::CheckerError;
+
+/// Encoding of a DRAT proof.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum DratFormat {
+    /// The textual DRAT format.
+    Text,
+    /// The binary DRAT format.
+    Binary,
+}
+
+/// A clause stored for DRAT checking.
+struct DratClause {
+    /// Clause id used when reporting steps to proof processors.
+    id: u64,
+    /// The clause's literals, the first two are watched.
+    lits: Vec<Lit>,
+    /// How often the clause is present, zero for deleted clauses.
+    count: u32,
+}
+
+/// Clause database and assignment used for checking DRAT lemmas.
+#[derive(Default)]
+struct DratCheck {
+    /// All clauses, including deleted clauses.
+    clauses: Vec<DratClause>,
+    /// Indices of present clauses by clause hash.
+    by_hash: ClauseHashMap<SmallVec<[usize; 1]>>,
+    /// Indices of clauses watching a literal, indexed by literal code.
+    ///
+    /// May contain deleted clauses, these are removed during propagation.
+    watches: Vec<Vec<usize>>,
+    /// Value of each variable.
+    values: Vec<Option<bool>>,
+    /// Clause that propagated each variable, `None` for the negated literals of a checked lemma.
+    reasons: Vec<Option<usize>>,
+    /// Assigned literals in assignment order.
+    trail: Vec<Lit>,
+    /// Number of literals on the trail that were propagated.
+    queue_head: usize,
+    /// Clause falsified by top-level propagation.
+    top_conflict: Option<usize>,
+    /// Clause ids used by the last successful RUP check.
+    trace_ids: Vec<u64>,
+    /// Number of literals propagated so far.
+    propagations: u64,
+    /// Variable flags used when collecting trace ids.
+    seen: Vec<bool>,
+}
+
+/// Value of a literal under a given assignment.
+fn lit_value(values: &[Option<bool>], lit: Lit) -> Option<bool> {
+    values[lit.index()].map(|value| value == lit.is_positive())
+}
+
+impl DratCheck {
+    /// Ensure that a variable is present.
+    fn ensure_var(&mut self, var: Var) {
+        if self.values.len() <= var.index() {
+            self.values.resize(var.index() + 1, None);
+            self.reasons.resize(var.index() + 1, None);
+            self.seen.resize(var.index() + 1, false);
+            self.watches.resize((var.index() + 1) * 2, vec![]);
+        }
+    }
+
+    /// Assign a literal.
+    fn assign(&mut self, lit: Lit, reason: Option<usize>) {
+        self.values[lit.index()] = Some(lit.is_positive());
+        self.reasons[lit.index()] = reason;
+        self.trail.push(lit);
+    }
+
+    /// Undo all assignments past the given trail length.
+    fn backtrack(&mut self, trail_len: usize) {
+        for lit in self.trail.drain(trail_len..) {
+            self.values[lit.index()] = None;
+            self.reasons[lit.index()] = None;
+        }
+        self.queue_head = trail_len;
+    }
+
+    /// Find a present clause.
+    ///
+    /// `lits` must be sorted and free of duplicates.
+    fn find_clause(&self, lits: &[Lit], hash: ClauseHash) -> Option<usize> {
+        let candidates = self.by_hash.get(&hash)?;
+        candidates.iter().cloned().find(|&index| {
+            let clause_lits = &self.clauses[index].lits;
+            clause_lits.len() == lits.len()
+                && clause_lits
+                    .iter()
+                    .all(|lit| lits.binary_search(lit).is_ok())
+        })
+    }
+
+    /// Add a new clause and propagate it at the top level.
+    ///
+    /// `lits` must be sorted and free of duplicates.
+    fn add_clause(&mut self, id: u64, lits: &[Lit], hash: ClauseHash, count: u32) {
+        let index = self.clauses.len();
+        self.clauses.push(DratClause {
+            id,
+            lits: lits.to_vec(),
+            count,
+        });
+        self.by_hash.entry(hash).or_default().push(index);
+        self.attach_clause(index);
+    }
+
+    /// Watch a clause and propagate it at the top level.
+    fn attach_clause(&mut self, index: usize) {
+        let values = &self.values;
+        let lits = &mut self.clauses[index].lits;
+
+        // Prefer watching true, then unassigned literals.
+        lits.sort_by_key(|&lit| match lit_value(values, lit) {
+            Some(true) => 0,
+            None => 1,
+            Some(false) => 2,
+        });
+
+        match lits.len() {
+            0 => {
+                self.top_conflict = self.top_conflict.or(Some(index));
+                return;
+            }
+            1 => match lit_value(values, lits[0]) {
+                Some(true) => (),
+                Some(false) => self.top_conflict = self.top_conflict.or(Some(index)),
+                None => {
+                    let lit = lits[0];
+                    self.assign(lit, Some(index));
+                }
+            },
+            _ => {
+                let (first, second) = (lits[0], lits[1]);
+                self.watches[first.code()].push(index);
+                self.watches[second.code()].push(index);
+                match (lit_value(values, first), lit_value(values, second)) {
+                    (Some(false), _) => self.top_conflict = self.top_conflict.or(Some(index)),
+                    (None, Some(false)) => self.assign(first, Some(index)),
+                    _ => (),
+                }
+            }
+        }
+
+        if self.top_conflict.is_none() {
+            self.top_conflict = self.propagate();
+        }
+    }
+
+    /// Delete a copy of a present clause.
+    ///
+    /// Returns true if no copy of the clause remains.
+    fn delete_clause(&mut self, index: usize, hash: ClauseHash) -> bool {
+        let clause = &mut self.clauses[index];
+        clause.count -= 1;
+        if clause.count > 0 {
+            return false;
+        }
+
+        if let Some(candidates) = self.by_hash.get_mut(&hash) {
+            candidates.retain(|&mut candidate| candidate != index);
+        }
+
+        let reasons = &self.reasons;
+        let is_reason = clause
+            .lits
+            .iter()
+            .any(|lit| reasons[lit.index()] == Some(index));
+
+        if is_reason || self.top_conflict == Some(index) {
+            // This only happens when deleting unit clauses or clauses that became unit, which is
+            // rare enough to recompute the top-level assignment from scratch.
+            self.rebuild();
+        }
+
+        true
+    }
+
+    /// Recompute all watches and the top-level assignment.
+    fn rebuild(&mut self) {
+        self.backtrack(0);
+        self.top_conflict = None;
+
+        for watches in self.watches.iter_mut() {
+            watches.clear();
+        }
+
+        for index in 0..self.clauses.len() {
+            if self.clauses[index].count > 0 {
+                self.attach_clause(index);
+            }
+        }
+    }
+
+    /// Propagate all assigned literals.
+    ///
+    /// Returns the index of a falsified clause on conflict.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.queue_head < self.trail.len() {
+            let false_lit = !self.trail[self.queue_head];
+            self.queue_head += 1;
+            self.propagations += 1;
+
+            let mut watches = std::mem::replace(&mut self.watches[false_lit.code()], vec![]);
+            let mut conflict = None;
+
+            let mut pos = 0;
+            while pos < watches.len() {
+                let index = watches[pos];
+                let clause = &mut self.clauses[index];
+
+                if clause.count == 0 {
+                    watches.swap_remove(pos);
+                    continue;
+                }
+
+                if clause.lits[0] == false_lit {
+                    clause.lits.swap(0, 1);
+                }
+
+                let first = clause.lits[0];
+
+                if lit_value(&self.values, first) == Some(true) {
+                    pos += 1;
+                    continue;
+                }
+
+                let values = &self.values;
+                if let Some(offset) = clause.lits[2..]
+                    .iter()
+                    .position(|&lit| lit_value(values, lit) != Some(false))
+                {
+                    clause.lits.swap(1, offset + 2);
+                    self.watches[clause.lits[1].code()].push(index);
+                    watches.swap_remove(pos);
+                    continue;
+                }
+
+                pos += 1;
+
+                if lit_value(&self.values, first) == Some(false) {
+                    conflict = Some(index);
+                    break;
+                }
+
+                self.assign(first, Some(index));
+            }
+
+            self.watches[false_lit.code()] = watches;
+
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// Check whether a clause is a reverse unit propagation (RUP) consequence.
+    ///
+    /// On success `trace_ids` contains the clauses involved in the conflict, in the order they
+    /// became unit followed by the conflicting clause.
+    fn check_rup(&mut self, lits: &[Lit]) -> bool {
+        if let Some(conflict) = self.top_conflict {
+            self.collect_trace(conflict, None);
+            return true;
+        }
+
+        let trail_len = self.trail.len();
+
+        for &lit in lits.iter() {
+            match lit_value(&self.values, lit) {
+                Some(true) => {
+                    let reason = self.reasons[lit.index()].expect("top-level lit without reason");
+                    self.collect_trace(reason, Some(lit.var()));
+                    self.backtrack(trail_len);
+                    return true;
+                }
+                Some(false) => (),
+                None => self.assign(!lit, None),
+            }
+        }
+
+        let conflict = self.propagate();
+
+        if let Some(conflict) = conflict {
+            self.collect_trace(conflict, None);
+        }
+
+        self.backtrack(trail_len);
+
+        conflict.is_some()
+    }
+
+    /// Collect the ids of all clauses involved in a conflict.
+    ///
+    /// The literal of `skip_var` is not followed for the conflict clause.
+    fn collect_trace(&mut self, conflict: usize, skip_var: Option<Var>) {
+        self.trace_ids.clear();
+
+        for &lit in self.clauses[conflict].lits.iter() {
+            if Some(lit.var()) != skip_var {
+                self.seen[lit.index()] = true;
+            }
+        }
+
+        for &lit in self.trail.iter().rev() {
+            if !self.seen[lit.index()] {
+                continue;
+            }
+            self.seen[lit.index()] = false;
+
+            if let Some(reason) = self.reasons[lit.index()] {
+                let clause = &self.clauses[reason];
+                self.trace_ids.push(clause.id);
+                for &other in clause.lits.iter() {
+                    if other.var() != lit.var() {
+                        self.seen[other.index()] = true;
+                    }
+                }
+            }
+        }
+
+        self.trace_ids.reverse();
+        self.trace_ids.push(self.clauses[conflict].id);
+    }
+}
+
+/// Peek at the next byte of the input.
+fn peek_byte(input: &mut impl BufRead) -> io::Result<Option<u8>> {
+    Ok(input.fill_buf()?.first().cloned())
+}
+
+/// Read a single byte from the input.
+fn read_byte(input: &mut impl BufRead) -> io::Result<Option<u8>> {
+    let byte = peek_byte(input)?;
+    if byte.is_some() {
+        input.consume(1);
+    }
+    Ok(byte)
+}
+
+/// Errors while parsing a DRAT proof.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum DratParseError {
+    #[error("unterminated clause")]
+    UnterminatedClause,
+    #[error("unexpected character {unexpected:?}")]
+    UnexpectedInput { unexpected: char },
+    #[error("unexpected step code {code:#x}")]
+    UnexpectedStepCode { code: u8 },
+    #[error("literal index too large")]
+    LiteralTooLarge,
+    #[error("invalid literal")]
+    InvalidLiteral,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Parser for DRAT proofs.
+struct DratParser<R> {
+    input: R,
+    format: DratFormat,
+}
+
+impl<R: BufRead> DratParser<R> {
+    /// Parse the literals of the next proof step into `lits`.
+    ///
+    /// Returns `None` at the end of the proof or whether the step is a deletion.
+    fn parse_step(&mut self, lits: &mut Vec<Lit>) -> Result<Option<bool>, DratParseError> {
+        lits.clear();
+        match self.format {
+            DratFormat::Text => self.parse_text_step(lits),
+            DratFormat::Binary => self.parse_binary_step(lits),
+        }
+    }
+
+    fn parse_text_step(&mut self, lits: &mut Vec<Lit>) -> Result<Option<bool>, DratParseError> {
+        let mut delete = false;
+        let mut in_step = false;
+
+        loop {
+            let byte = match peek_byte(&mut self.input)? {
+                Some(byte) => byte,
+                None if in_step => return Err(DratParseError::UnterminatedClause),
+                None => return Ok(None),
+            };
+
+            match byte {
+                b' ' | b'\t' | b'\r' | b'\n' => self.input.consume(1),
+                b'c' if !in_step => {
+                    let mut comment = vec![];
+                    self.input.read_until(b'\n', &mut comment)?;
+                }
+                b'd' if !in_step => {
+                    self.input.consume(1);
+                    delete = true;
+                    in_step = true;
+                }
+                b'-' | b'0'..=b'9' => {
+                    in_step = true;
+                    let number = self.parse_int()?;
+                    if number == 0 {
+                        return Ok(Some(delete));
+                    }
+                    lits.push(Lit::from_dimacs(number));
+                }
+                _ => {
+                    return Err(DratParseError::UnexpectedInput {
+                        unexpected: byte as char,
+                    })
+                }
+            }
+        }
+    }
+
+    /// Parse a literal in DIMACS encoding or the terminating zero.
+    fn parse_int(&mut self) -> Result<isize, DratParseError> {
+        let negative = peek_byte(&mut self.input)? == Some(b'-');
+        if negative {
+            self.input.consume(1);
+        }
+
+        let mut value = 0usize;
+        let mut digits = 0;
+
+        while let Some(byte @ b'0'..=b'9') = peek_byte(&mut self.input)? {
+            self.input.consume(1);
+            digits += 1;
+            value = value
+                .checked_mul(10)
+                .and_then(|value| value.checked_add((byte - b'0') as usize))
+                .filter(|&value| value <= Var::max_count())
+                .ok_or(DratParseError::LiteralTooLarge)?;
+        }
+
+        if digits == 0 || (negative && value == 0) {
+            return Err(DratParseError::InvalidLiteral);
+        }
+
+        if negative {
+            Ok(-(value as isize))
+        } else {
+            Ok(value as isize)
+        }
+    }
+
+    fn parse_binary_step(&mut self, lits: &mut Vec<Lit>) -> Result<Option<bool>, DratParseError> {
+        let delete = match read_byte(&mut self.input)? {
+            None => return Ok(None),
+            Some(b'a') => false,
+            Some(b'd') => true,
+            Some(code) => return Err(DratParseError::UnexpectedStepCode { code }),
+        };
+
+        loop {
+            let code = self.parse_varint()?;
+            if code == 0 {
+                return Ok(Some(delete));
+            }
+            let number = code >> 1;
+            if number > Var::max_count() as u64 {
+                return Err(DratParseError::LiteralTooLarge);
+            }
+            lits.push(Var::from_dimacs(number as isize).lit(code & 1 == 0));
+        }
+    }
+
+    /// Parse a variable length encoded integer.
+    fn parse_varint(&mut self) -> Result<u64, DratParseError> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = match read_byte(&mut self.input)? {
+                Some(byte) => byte,
+                None => return Err(DratParseError::UnterminatedClause),
+            };
+            if shift > 63 {
+                return Err(DratParseError::LiteralTooLarge);
+            }
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+}
+
+/// Checks a DRAT proof for the loaded formula.
+///
+/// Every added lemma has to be a reverse unit propagation (RUP) consequence of the current
+/// clauses, RAT lemmas are not supported. Deleting a clause that isn't present is an error. The
+/// proof is accepted as soon as the empty clause follows by unit propagation.
+pub fn check_drat_proof<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut CallbacksP<'a>,
+        mut CheckerStateP,
+        mut ClausesP,
+        mut ProcessingP<'a>,
+        mut VariablesP,
+    ),
+    input: impl io::Read,
+    format: DratFormat,
+) -> Result<(), CheckerError> {
+    if ctx.part(CheckerStateP).unsat {
+        return process_unit_conflicts(ctx.borrow());
+    }
+
+    let mut drat = DratCheck::default();
+
+    load_formula(ctx.borrow(), &mut drat);
+
+    let mut parser = DratParser {
+        input: RecordingReader::new(io::BufReader::new(CountingReader::new(input))),
+        format,
+    };
+
+    let mut lits = vec![];
+    let mut tmp = vec![];
+
+    while drat.top_conflict.is_none() {
+        ctx.part_mut(CheckerStateP).step += 1;
+
+        let step = ctx.part(CheckerStateP).step;
+        ctx.part_mut(ProcessingP).proof_step = step;
+
+        if ctx.part(CallbacksP).is_cancelled() {
+            return Err(CheckerError::Cancelled { step });
+        }
+
+        if ctx.part(CallbacksP).wants_progress(step) {
+            let progress = CheckerProgress {
+                steps: step,
+                clauses: drat
+                    .clauses
+                    .iter()
+                    .filter(|clause| clause.count > 0)
+                    .count(),
+                bytes: bytes_consumed(parser.input.get_ref()),
+            };
+            ctx.part_mut(CallbacksP).report_progress(&progress);
+        }
+
+        let offset = bytes_consumed(parser.input.get_ref());
+        parser.input.clear();
+
+        let delete = match parser.parse_step(&mut lits) {
+            Ok(Some(delete)) => delete,
+            Ok(None) => return Err(CheckerError::ProofIncomplete { step, offset }),
+            Err(DratParseError::Io(cause)) => {
+                return Err(CheckerError::IoError {
+                    step,
+                    offset,
+                    cause,
+                })
+            }
+            Err(err) => {
+                return Err(CheckerError::ParseError {
+                    step,
+                    offset,
+                    step_bytes: parser.input.recorded().to_vec(),
+                    cause: err.into(),
+                })
+            }
+        };
+
+        if copy_canonical(&mut tmp, &lits) {
+            // Tautologies are always redundant and never propagate
+            continue;
+        }
+
+        for &lit in tmp.iter() {
+            ensure_var(ctx.borrow(), lit.var());
+            drat.ensure_var(lit.var());
+        }
+
+        let hash = clause_hash(&tmp);
+
+        if delete {
+            match drat.find_clause(&tmp, hash) {
+                Some(index) => {
+                    let id = drat.clauses[index].id;
+                    if drat.delete_clause(index, hash) {
+                        process_step(
+                            ctx.borrow(),
+                            &CheckedProofStep::DeleteClause { id, clause: &tmp },
+                        )?;
+                    }
+                }
+                None => {
+                    return Err(CheckerError::check_failed(
+                        step,
+                        format!("delete of unknown clause {:?}", tmp),
+                    )
+                    .in_stream(offset, parser.input.recorded()))
+                }
+            }
+            continue;
+        }
+
+        if !drat.check_rup(&tmp) {
+            return Err(CheckerError::check_failed(
+                step,
+                format!(
+                    "lemma {:?} is not a RUP consequence (RAT lemmas are not supported)",
+                    tmp
+                ),
+            )
+            .in_stream(offset, parser.input.recorded()));
+        }
+
+        if let Some(index) = drat.find_clause(&tmp, hash) {
+            drat.clauses[index].count += 1;
+            continue;
+        }
+
+        let id = ctx.part(ClausesP).next_clause_id;
+        ctx.part_mut(ClausesP).next_clause_id += 1;
+        ctx.part_mut(ProcessingP).propagations = drat.propagations;
+
+        process_step(
+            ctx.borrow(),
+            &CheckedProofStep::AtClause {
+                id,
+                redundant: true,
+                clause: &tmp,
+                propagations: &drat.trace_ids,
+            },
+        )?;
+
+        if tmp.is_empty() {
+            ctx.part_mut(CheckerStateP).unsat = true;
+            return Ok(());
+        }
+
+        drat.add_clause(id, &tmp, hash, 1);
+    }
+
+    // The empty clause follows by top-level unit propagation
+    drat.check_rup(&[]);
+
+    let id = ctx.part(ClausesP).next_clause_id;
+    ctx.part_mut(ClausesP).next_clause_id += 1;
+    ctx.part_mut(ProcessingP).propagations = drat.propagations;
+
+    process_step(
+        ctx.borrow(),
+        &CheckedProofStep::AtClause {
+            id,
+            redundant: true,
+            clause: &[],
+            propagations: &drat.trace_ids,
+        },
+    )?;
+
+    ctx.part_mut(CheckerStateP).unsat = true;
+
+    Ok(())
+}
+
+/// Copy all present clauses of the checker into the DRAT clause database.
+fn load_formula(ctx: partial!(Context, ClausesP), drat: &mut DratCheck) {
+    let clauses = ctx.part(ClausesP);
+
+    if let Some(max_index) = clauses.unit_clauses.len().checked_sub(1) {
+        drat.ensure_var(Var::from_index(max_index));
+    }
+
+    for (index, unit) in clauses.unit_clauses.iter().enumerate() {
+        if let Some(unit) = unit {
+            if let UnitId::Global(id) = unit.id {
+                let lit = [Var::from_index(index).lit(unit.value)];
+                drat.add_clause(id, &lit, clause_hash(&lit), 1);
+            }
+        }
+    }
+
+    for candidates in clauses.clauses.values() {
+        for clause in candidates.iter() {
+            if clause.ref_count() == 0 {
+                continue;
+            }
+            let lits = clause.lits.slice(&clauses.literal_buffer);
+            drat.add_clause(clause.id, lits, clause_hash(lits), clause.ref_count());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::cnf_formula;
+
+    use crate::{Checker, ProofParseError};
+
+    fn check(proof: &[u8], format: DratFormat) -> Result<(), CheckerError> {
+        let mut checker = Checker::new();
+        checker
+            .add_formula(&cnf_formula![
+                1, 2;
+                -1, 2;
+                1, -2;
+                -1, -2, 3;
+                -1, -2, -3;
+            ])
+            .unwrap();
+        checker.check_drat_proof(proof, format)
+    }
+
+    #[test]
+    fn text_proof() {
+        check(b"c lemma\n2 0\nd 1 2 0\n", DratFormat::Text).unwrap();
+        check(b"-1 -2 0 d -1 -2 3 0\n2 0\n", DratFormat::Text).unwrap();
+    }
+
+    #[test]
+    fn binary_proof() {
+        check(&[b'd', 2, 0, b'a', 4, 0], DratFormat::Binary).unwrap();
+    }
+
+    #[test]
+    fn invalid_lemma() {
+        match check(b"3 0\n", DratFormat::Text) {
+            Err(CheckerError::CheckFailed { ref msg, .. }) if msg.contains("not a RUP") => (),
+            err => panic!("unexpected result {:?}", err),
+        }
+    }
+
+    #[test]
+    fn unknown_deletion() {
+        match check(b"-1 -2 0\nd 1 3 0\n", DratFormat::Text) {
+            Err(CheckerError::CheckFailed {
+                ref msg, offset, ..
+            }) if msg.contains("unknown clause") => assert_eq!(offset, Some(7)),
+            err => panic!("unexpected result {:?}", err),
+        }
+    }
+
+    #[test]
+    fn incomplete_proof() {
+        match check(b"d 1 2 0\n", DratFormat::Text) {
+            Err(CheckerError::ProofIncomplete { .. }) => (),
+            err => panic!("unexpected result {:?}", err),
+        }
+        match check(b"d 1 -2 0\n-1 0\n", DratFormat::Text) {
+            Err(CheckerError::ProofIncomplete { .. }) => (),
+            err => panic!("unexpected result {:?}", err),
+        }
+    }
+
+    #[test]
+    fn error_offsets() {
+        match check(b"d 1 2 0\n3 0\n", DratFormat::Text) {
+            Err(CheckerError::CheckFailed {
+                offset, step_bytes, ..
+            }) => {
+                assert_eq!(offset, Some(7));
+                assert_eq!(&step_bytes[..], &b"\n3 0"[..]);
+            }
+            err => panic!("unexpected result {:?}", err),
+        }
+        match check(b"2 0\n2 x 0\n", DratFormat::Text) {
+            Err(CheckerError::ParseError {
+                offset, step_bytes, ..
+            }) => {
+                assert_eq!(offset, 3);
+                assert_eq!(&step_bytes[..], &b"\n2 "[..]);
+            }
+            err => panic!("unexpected result {:?}", err),
+        }
+    }
+
+    #[test]
+    fn parse_error() {
+        match check(b"2 x 0\n", DratFormat::Text) {
+            Err(CheckerError::ParseError {
+                cause: ProofParseError::Drat(DratParseError::UnexpectedInput { unexpected: 'x' }),
+                ..
+            }) => (),
+            err => panic!("unexpected result {:?}", err),
+        }
+        match check(&[b'a', 4], DratFormat::Binary) {
+            Err(CheckerError::ParseError {
+                cause: ProofParseError::Drat(DratParseError::UnterminatedClause),
+                ..
+            }) => (),
+            err => panic!("unexpected result {:?}", err),
+        }
+    }
+}
@@ -1,16 +1,49 @@
 //! Computation of clause hashes.
+use std::hash::{BuildHasherDefault, Hasher};
 use std::mem::replace;
 
 use hashbrown::HashMap;
 use partial_ref::{partial, PartialRef};
 
 use varisat_formula::{Lit, Var};
-use varisat_internal_proof::{lit_code_hash, lit_hash, ClauseHash};
+use varisat_internal_proof::{lit_code_hash, lit_hash, ClauseHash, MAX_HASH_BITS};
 
 use crate::context::{parts::*, Context};
 
+/// Hash map keyed by clause hashes.
+///
+/// Clause hashes are already uniformly distributed, so they are used as hash values without
+/// hashing them again.
+pub type ClauseHashMap<V> = HashMap<ClauseHash, V, BuildHasherDefault<IdentityHasher>>;
+
+/// Hasher passing through clause hashes.
+///
+/// Clause hashes using fewer than 64 bits have zero high bits, which the hash map uses to filter
+/// candidates. A single multiplication moves the entropy into the high bits.
+#[derive(Default)]
+pub struct IdentityHasher(u64);
+
+impl Hasher for IdentityHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        // Only used for keys other than clause hashes
+        for &byte in bytes {
+            self.0 = self.0.rotate_left(8) ^ byte as u64;
+        }
+    }
+
+    fn write_u128(&mut self, value: u128) {
+        self.0 ^= value as u64 ^ (value >> 64) as u64;
+    }
+
+    fn finish(&self) -> u64 {
+        self.0.wrapping_mul(0x9e37_79b9_7f4a_7c15)
+    }
+}
+
 pub struct ClauseHasher {
     /// How many bits are used for storing clause hashes.
+    ///
+    /// Proofs start with 64 bit hashes, this is changed by `ChangeHashBits` steps.
     pub hash_bits: u32,
     /// Changed solver names that are not yet reflected in the checkers current clause hashes.
     pub buffered_solver_var_names: Vec<(Var, Option<Var>)>,
@@ -36,7 +69,7 @@ impl Default for ClauseHasher {
 impl ClauseHasher {
     /// Compute a clause hash of the current bit size
     pub fn clause_hash(&self, lits: &[Lit]) -> ClauseHash {
-        let shift_bits = ClauseHash::max_value().count_ones() - self.hash_bits;
+        let shift_bits = MAX_HASH_BITS - self.hash_bits;
         let mut hash = 0;
         for &lit in lits.iter() {
             match self.solver_var_names.get(&lit.var()) {
@@ -0,0 +1,146 @@
+//! Export of the origin of clause ids.
+use std::io::{self, BufWriter, Write};
+
+use crate::processing::{
+    CheckedProofStep, CheckerData, ProcessorError, ProofProcessor, ProofVerdict, StepFilter,
+};
+
+/// Proof processor that writes the origin of each clause id.
+///
+/// This allows correlating LRAT or TraceCheck proofs written by other processors with the input
+/// formula and the checked proof. For each clause a line is written, starting with the clause id
+/// plus one, as used in LRAT and TraceCheck proofs. For clauses of the input formula, this is
+/// followed by `input` and the position of the clause within the input formula, starting with 1.
+/// For clauses derived by the proof, this is followed by `step` and the number of the proof step,
+/// also starting with 1:
+///
+/// ```text
+/// 1 input 1
+/// 2 input 2
+/// 3 step 5
+/// ```
+///
+/// Duplicated and tautological input clauses also get an id, even though it is not used in other
+/// steps. Clause ids changed by a [`RenumberIds`](crate::RenumberIds) transformation are not
+/// reflected in the output.
+pub struct WriteClauseIdMap<'a> {
+    target: BufWriter<Box<dyn Write + 'a>>,
+    /// Number of input clauses seen so far.
+    input_clauses: u64,
+}
+
+impl<'a> ProofProcessor for WriteClauseIdMap<'a> {
+    fn process_step(
+        &mut self,
+        step: &CheckedProofStep,
+        data: CheckerData,
+    ) -> Result<(), ProcessorError> {
+        match step {
+            &CheckedProofStep::AddClause { id, .. }
+            | &CheckedProofStep::DuplicatedClause { id, .. }
+            | &CheckedProofStep::TautologicalClause { id, .. } => {
+                self.input_clauses += 1;
+                writeln!(self.target, "{} input {}", id + 1, self.input_clauses)?;
+            }
+            &CheckedProofStep::AtClause { id, .. }
+            | &CheckedProofStep::RatClause { id, .. }
+            | &CheckedProofStep::PrClause { id, .. } => {
+                writeln!(self.target, "{} step {}", id + 1, data.proof_step())?;
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn end_of_proof(&mut self, _verdict: ProofVerdict) -> Result<(), ProcessorError> {
+        Ok(self.flush()?)
+    }
+
+    fn step_filter(&self) -> StepFilter {
+        StepFilter {
+            inputs: true,
+            lemmas: true,
+            ..StepFilter::none()
+        }
+    }
+}
+
+impl<'a> WriteClauseIdMap<'a> {
+    /// Create a processor writing the origin of clause ids to `target`.
+    pub fn new(target: impl Write + 'a) -> WriteClauseIdMap<'a> {
+        WriteClauseIdMap {
+            target: BufWriter::new(Box::new(target)),
+            input_clauses: 0,
+        }
+    }
+
+    /// Write out all steps processed so far.
+    ///
+    /// This is automatically called when the proof ends. Calling this explicitly is recommended to
+    /// handle possible IO errors.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.target.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::{cnf_formula, lits};
+
+    use crate::{Checker, ProofStep};
+
+    #[test]
+    fn clause_origins() {
+        let mut map = vec![];
+
+        {
+            let mut write_map = WriteClauseIdMap::new(&mut map);
+            let mut checker = Checker::new();
+            checker.add_processor(&mut write_map);
+
+            checker
+                .add_formula(&cnf_formula![
+                    1, 2;
+                    1, 2;
+                    -1, 2;
+                    -2, 3;
+                ])
+                .unwrap();
+
+            let hashes = [
+                checker.clause_hash(&lits![1, 2]),
+                checker.clause_hash(&lits![-1, 2]),
+                checker.clause_hash(&lits![2]),
+                checker.clause_hash(&lits![-2, 3]),
+            ];
+
+            let mut proof = vec![];
+            varisat_internal_proof::binary_format::write_header(&mut proof, &[]).unwrap();
+            for step in [
+                ProofStep::AtClause {
+                    redundant: false,
+                    clause: &lits![2],
+                    propagation_hashes: &hashes[..2],
+                },
+                ProofStep::AtClause {
+                    redundant: false,
+                    clause: &lits![3],
+                    propagation_hashes: &hashes[2..],
+                },
+                ProofStep::End,
+            ]
+            .iter()
+            {
+                varisat_internal_proof::binary_format::write_step(&mut proof, step).unwrap();
+            }
+            checker.check_proof(&proof[..]).unwrap();
+        }
+
+        assert_eq!(
+            String::from_utf8(map).unwrap(),
+            "1 input 1\n2 input 2\n3 input 3\n4 input 4\n5 step 1\n6 step 2\n"
+        );
+    }
+}
@@ -4,7 +4,7 @@ use partial_ref::{IntoPartialRefMut, PartialRef};
 
 use varisat_internal_proof::ProofStep;
 
-use crate::state::{check_step, process_unit_conflicts};
+use crate::state::end_of_proof;
 use crate::{Checker, CheckerError};
 
 /// Varisat internal interface used for on-the-fly checking.
@@ -19,13 +19,22 @@ pub trait SelfChecker {
 
 impl<'a> SelfChecker for Checker<'a> {
     fn self_check_step(&mut self, step: ProofStep) -> Result<(), CheckerError> {
-        self.ctx.checker_state.step += 1;
-        let mut ctx = self.ctx.into_partial_ref_mut();
-        check_step(ctx.borrow(), step)
+        // The solver might end the proof repeatedly, processors are notified only once.
+        let ends_proof = match step {
+            ProofStep::End => !self.ctx.checker_state.ended,
+            _ => false,
+        };
+        let result = self.check_step(step);
+        if ends_proof {
+            let result = result.and_then(|_| self.check_delayed_steps());
+            let mut ctx = self.ctx.into_partial_ref_mut();
+            end_of_proof(ctx.borrow(), result)
+        } else {
+            result
+        }
     }
 
     fn self_check_delayed_steps(&mut self) -> Result<(), CheckerError> {
-        let mut ctx = self.ctx.into_partial_ref_mut();
-        process_unit_conflicts(ctx.borrow())
+        self.check_delayed_steps()
     }
 }
@@ -0,0 +1,511 @@
+//! Craig interpolation of checked proofs.
+use std::ops::Range;
+
+use hashbrown::{HashMap, HashSet};
+use thiserror::Error;
+
+use varisat_formula::{CnfFormula, ExtendFormula, Lit, Var};
+
+use crate::processing::{CheckedProofStep, CheckerData, ProcessorError, ProofProcessor};
+
+/// Errors while computing an interpolant.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum InterpolationError {
+    #[error("interpolation of proofs containing RAT clauses is not supported")]
+    RatClause,
+    #[error("interpolation of proofs containing PR clauses is not supported")]
+    PrClause,
+    #[error("unknown clause id {id}")]
+    UnknownClause { id: u64 },
+    #[error("AT clause {id} without propagations")]
+    MissingPropagations { id: u64 },
+    #[error("clause {id} does not propagate")]
+    NotPropagating { id: u64 },
+}
+
+/// A Craig interpolant encoded as a circuit in CNF.
+///
+/// The interpolant uses the variables shared by both parts of the input formula. Additional
+/// variables are introduced for the gates of the circuit. The `definitions` fix the value of each
+/// gate given the value of the shared variables, so that the interpolant is true whenever
+/// `output` is.
+#[derive(Debug)]
+pub struct Interpolant {
+    /// Clauses defining the gates of the circuit.
+    pub definitions: CnfFormula,
+    /// Literal corresponding to the value of the interpolant.
+    pub output: Lit,
+}
+
+/// A partial interpolant.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Node {
+    Const(bool),
+    Lit(Lit),
+}
+
+impl std::ops::Not for Node {
+    type Output = Node;
+
+    fn not(self) -> Node {
+        match self {
+            Node::Const(value) => Node::Const(!value),
+            Node::Lit(lit) => Node::Lit(!lit),
+        }
+    }
+}
+
+/// Builder for the gates of an interpolant.
+struct Circuit {
+    definitions: CnfFormula,
+    next_index: usize,
+}
+
+impl Circuit {
+    fn and(&mut self, a: Node, b: Node) -> Node {
+        match (a, b) {
+            (Node::Const(false), _) | (_, Node::Const(false)) => Node::Const(false),
+            (Node::Const(true), other) | (other, Node::Const(true)) => other,
+            (Node::Lit(a), Node::Lit(b)) if a == b => Node::Lit(a),
+            (Node::Lit(a), Node::Lit(b)) if a == !b => Node::Const(false),
+            (Node::Lit(a), Node::Lit(b)) => {
+                let gate = Var::from_index(self.next_index).positive();
+                self.next_index += 1;
+                self.definitions.add_clause(&[!gate, a]);
+                self.definitions.add_clause(&[!gate, b]);
+                self.definitions.add_clause(&[gate, !a, !b]);
+                Node::Lit(gate)
+            }
+        }
+    }
+
+    fn or(&mut self, a: Node, b: Node) -> Node {
+        !self.and(!a, !b)
+    }
+}
+
+/// A clause derived by a resolution chain.
+struct Lemma {
+    id: u64,
+    conflict: u64,
+    /// Antecedents and pivots in order of resolution.
+    resolutions: Range<usize>,
+}
+
+/// Proof processor that computes a Craig interpolant from a refutation.
+///
+/// The input clauses are partitioned into a part A and a part B. The interpolant I is a formula
+/// over the variables shared by A and B, such that A implies I and I is unsatisfiable together
+/// with B.
+///
+/// Each checked AT clause is turned into a resolution chain and the interpolant is built from the
+/// resolution proof of the empty clause using McMillan's interpolation system.
+pub struct Interpolator {
+    /// Ids of the input clauses in A.
+    a_clauses: HashSet<u64>,
+    /// Input clauses by id. Input clauses that also have a copy in B are marked as not in A.
+    inputs: HashMap<u64, (bool, Range<usize>)>,
+    /// Literals of all non-deleted clauses by id.
+    clauses: HashMap<u64, Range<usize>>,
+    lits: Vec<Lit>,
+    /// Variables occurring in B.
+    b_vars: HashSet<Var>,
+    lemmas: Vec<Lemma>,
+    resolutions: Vec<(u64, Var)>,
+    /// Partial assignment used to recover the pivots of a resolution chain.
+    values: Vec<Option<bool>>,
+    reasons: Vec<(u64, Lit)>,
+    interpolant: Option<Interpolant>,
+}
+
+impl ProofProcessor for Interpolator {
+    fn process_step(
+        &mut self,
+        step: &CheckedProofStep,
+        data: CheckerData,
+    ) -> Result<(), ProcessorError> {
+        if self.interpolant.is_some() {
+            return Ok(());
+        }
+
+        match step {
+            &CheckedProofStep::AddClause { id, clause } => {
+                let in_a = self.a_clauses.contains(&id);
+                if !in_a {
+                    self.b_vars.extend(clause.iter().map(|lit| lit.var()));
+                }
+                let lits = self.store_lits(clause);
+                self.inputs.insert(id, (in_a, lits.clone()));
+                self.clauses.insert(id, lits);
+                if clause.is_empty() {
+                    self.compute_interpolant(id, data);
+                }
+            }
+            &CheckedProofStep::DuplicatedClause {
+                id,
+                same_as_id,
+                clause,
+            } => {
+                if !self.a_clauses.contains(&id) {
+                    self.b_vars.extend(clause.iter().map(|lit| lit.var()));
+                    if let Some(input) = self.inputs.get_mut(&same_as_id) {
+                        input.0 = false;
+                    }
+                }
+            }
+            &CheckedProofStep::AtClause {
+                id,
+                clause,
+                propagations,
+                ..
+            } => {
+                self.add_lemma(id, clause, propagations)?;
+                let lits = self.store_lits(clause);
+                self.clauses.insert(id, lits);
+                if clause.is_empty() {
+                    self.compute_interpolant(id, data);
+                }
+            }
+            &CheckedProofStep::RatClause { .. } => {
+                return Err(InterpolationError::RatClause.into());
+            }
+            &CheckedProofStep::PrClause { .. } => {
+                return Err(InterpolationError::PrClause.into());
+            }
+            &CheckedProofStep::DeleteClause { id, .. }
+            | &CheckedProofStep::DeleteAtClause { id, .. }
+            | &CheckedProofStep::DeleteRatClause { id, .. } => {
+                self.clauses.remove(&id);
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+impl Interpolator {
+    /// Create a new interpolating processor.
+    ///
+    /// The ids of the input clauses in A are given by `a_clauses`, all other input clauses are in
+    /// B. The id of an input clause is its 0-based position in the input formula.
+    pub fn new(a_clauses: impl IntoIterator<Item = u64>) -> Interpolator {
+        Interpolator {
+            a_clauses: a_clauses.into_iter().collect(),
+            inputs: HashMap::new(),
+            clauses: HashMap::new(),
+            lits: vec![],
+            b_vars: HashSet::new(),
+            lemmas: vec![],
+            resolutions: vec![],
+            values: vec![],
+            reasons: vec![],
+            interpolant: None,
+        }
+    }
+
+    /// The computed interpolant.
+    ///
+    /// Returns `None` if the empty clause wasn't derived yet.
+    pub fn interpolant(&self) -> Option<&Interpolant> {
+        self.interpolant.as_ref()
+    }
+
+    /// Copy literals into the buffer.
+    fn store_lits(&mut self, lits: &[Lit]) -> Range<usize> {
+        let begin = self.lits.len();
+        self.lits.extend_from_slice(lits);
+        begin..self.lits.len()
+    }
+
+    /// Literals of a clause that wasn't deleted.
+    fn clause_lits(&self, id: u64) -> Result<&[Lit], InterpolationError> {
+        match self.clauses.get(&id) {
+            Some(range) => Ok(&self.lits[range.clone()]),
+            None => Err(InterpolationError::UnknownClause { id }),
+        }
+    }
+
+    fn lit_value(&self, lit: Lit) -> Option<bool> {
+        self.values
+            .get(lit.index())
+            .cloned()
+            .unwrap_or(None)
+            .map(|value| value ^ lit.is_negative())
+    }
+
+    fn assign(&mut self, lit: Lit) {
+        if self.values.len() <= lit.index() {
+            self.values.resize(lit.index() + 1, None);
+        }
+        self.values[lit.index()] = Some(lit.is_positive());
+    }
+
+    /// Turn the propagations of an AT check into a resolution chain.
+    ///
+    /// Propagations are replayed starting from the negated clause to find the pivot of each
+    /// propagating clause. Resolving the conflict clause with the propagating clauses in reverse
+    /// order yields the AT clause.
+    fn add_lemma(
+        &mut self,
+        id: u64,
+        clause: &[Lit],
+        propagations: &[u64],
+    ) -> Result<(), InterpolationError> {
+        let (&conflict, propagating) = match propagations.split_last() {
+            Some(split) => split,
+            None => return Err(InterpolationError::MissingPropagations { id }),
+        };
+
+        for &lit in clause.iter() {
+            self.assign(!lit);
+        }
+
+        self.reasons.clear();
+
+        for &reason in propagating.iter() {
+            let propagated = self
+                .clause_lits(reason)?
+                .iter()
+                .cloned()
+                .find(|&lit| self.lit_value(lit) != Some(false));
+            match propagated {
+                Some(lit) => {
+                    self.assign(lit);
+                    self.reasons.push((reason, lit));
+                }
+                None => return Err(InterpolationError::NotPropagating { id: reason }),
+            }
+        }
+
+        for &lit in clause.iter() {
+            self.values[lit.index()] = None;
+        }
+
+        let begin = self.resolutions.len();
+
+        for &(reason, lit) in self.reasons.iter().rev() {
+            self.values[lit.index()] = None;
+            self.resolutions.push((reason, lit.var()));
+        }
+
+        self.lemmas.push(Lemma {
+            id,
+            conflict,
+            resolutions: begin..self.resolutions.len(),
+        });
+
+        Ok(())
+    }
+
+    /// Build the interpolant for the empty clause with the given id.
+    fn compute_interpolant(&mut self, empty_id: u64, data: CheckerData) {
+        let map_lit = |lit: Lit| lit.map_var(|var| data.user_from_proof_var(var).unwrap_or(var));
+
+        let next_index = self
+            .inputs
+            .values()
+            .flat_map(|(_, range)| self.lits[range.clone()].iter())
+            .map(|&lit| map_lit(lit).index() + 1)
+            .max()
+            .unwrap_or(0);
+
+        let mut circuit = Circuit {
+            definitions: CnfFormula::new(),
+            next_index,
+        };
+
+        let mut used = HashSet::new();
+        used.insert(empty_id);
+
+        for lemma in self.lemmas.iter().rev() {
+            if used.contains(&lemma.id) {
+                used.insert(lemma.conflict);
+                used.extend(
+                    self.resolutions[lemma.resolutions.clone()]
+                        .iter()
+                        .map(|&(reason, _)| reason),
+                );
+            }
+        }
+
+        let mut nodes = HashMap::new();
+
+        for (&id, (in_a, range)) in self.inputs.iter() {
+            if !used.contains(&id) {
+                continue;
+            }
+            let mut node = Node::Const(!in_a);
+            if *in_a {
+                for &lit in self.lits[range.clone()].iter() {
+                    if self.b_vars.contains(&lit.var()) {
+                        node = circuit.or(node, Node::Lit(map_lit(lit)));
+                    }
+                }
+            }
+            nodes.insert(id, node);
+        }
+
+        for lemma in self.lemmas.iter() {
+            if !used.contains(&lemma.id) {
+                continue;
+            }
+            let mut node = nodes[&lemma.conflict];
+            for &(reason, pivot) in self.resolutions[lemma.resolutions.clone()].iter() {
+                let reason_node = nodes[&reason];
+                node = if self.b_vars.contains(&pivot) {
+                    circuit.and(node, reason_node)
+                } else {
+                    circuit.or(node, reason_node)
+                };
+            }
+            nodes.insert(lemma.id, node);
+        }
+
+        let output = match nodes[&empty_id] {
+            Node::Lit(lit) => lit,
+            Node::Const(value) => {
+                let output = Var::from_index(circuit.next_index);
+                circuit.definitions.add_clause(&[output.lit(value)]);
+                output.positive()
+            }
+        };
+
+        self.interpolant = Some(Interpolant {
+            definitions: circuit.definitions,
+            output,
+        });
+
+        self.lemmas.clear();
+        self.resolutions.clear();
+        self.clauses.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::{cnf_formula, lit, lits};
+
+    use crate::{Checker, ProofStep};
+
+    /// Evaluate an interpolant for all assignments of the variables `1..=shared`.
+    fn truth_table(interpolant: &Interpolant, shared: usize) -> Vec<bool> {
+        let var_count = interpolant
+            .definitions
+            .var_count()
+            .max(interpolant.output.index() + 1);
+        let mut table = vec![];
+        for shared_bits in 0..1usize << shared {
+            let mut output_values = vec![];
+            for aux_bits in 0..1usize << (var_count - shared) {
+                let bits = shared_bits | aux_bits << shared;
+                let value = |lit: Lit| ((bits >> lit.index()) & 1 == 1) == lit.is_positive();
+                if interpolant
+                    .definitions
+                    .iter()
+                    .all(|clause| clause.iter().any(|&lit| value(lit)))
+                {
+                    output_values.push(value(interpolant.output));
+                }
+            }
+            // The definitions must determine the output given the shared variables
+            assert!(!output_values.is_empty());
+            assert!(output_values.iter().all(|&value| value == output_values[0]));
+            table.push(output_values[0]);
+        }
+        table
+    }
+
+    #[test]
+    fn interpolant_of_chain() {
+        let mut interpolator = Interpolator::new(vec![0, 1]);
+
+        {
+            let mut checker = Checker::new();
+            checker.add_processor(&mut interpolator);
+
+            checker
+                .add_formula(&cnf_formula![
+                    3, 1;
+                    -3, 1;
+                    -1, 2;
+                    -1, -2;
+                ])
+                .unwrap();
+
+            let hashes = [
+                checker.clause_hash(&lits![3, 1]),
+                checker.clause_hash(&lits![-3, 1]),
+            ];
+
+            checker
+                .check_step(ProofStep::AtClause {
+                    redundant: true,
+                    clause: &lits![1],
+                    propagation_hashes: &hashes[..],
+                })
+                .unwrap();
+
+            let hashes = [
+                checker.clause_hash(&lits![-1, 2]),
+                checker.clause_hash(&lits![-1, -2]),
+            ];
+
+            checker
+                .check_step(ProofStep::AtClause {
+                    redundant: true,
+                    clause: &[],
+                    propagation_hashes: &hashes[..],
+                })
+                .unwrap();
+
+            checker.check_delayed_steps().unwrap();
+        }
+
+        let interpolant = interpolator.interpolant().unwrap();
+
+        assert_eq!(interpolant.output, lit!(1));
+        assert_eq!(interpolant.definitions.len(), 0);
+    }
+
+    #[test]
+    fn interpolant_with_gates() {
+        let mut interpolator = Interpolator::new(vec![0, 1]);
+
+        {
+            let mut checker = Checker::new();
+            checker.add_processor(&mut interpolator);
+
+            checker
+                .add_formula(&cnf_formula![
+                    3, 1;
+                    -3, 2;
+                    -1;
+                    -2;
+                ])
+                .unwrap();
+
+            let hashes = [
+                checker.clause_hash(&lits![3, 1]),
+                checker.clause_hash(&lits![-3, 2]),
+            ];
+
+            checker
+                .check_step(ProofStep::AtClause {
+                    redundant: true,
+                    clause: &[],
+                    propagation_hashes: &hashes[..],
+                })
+                .unwrap();
+
+            checker.check_delayed_steps().unwrap();
+        }
+
+        let interpolant = interpolator.interpolant().unwrap();
+
+        // The interpolant is equivalent to `1 or 2`
+        assert_eq!(truth_table(interpolant, 2), vec![false, true, true, true]);
+    }
+}
@@ -1,67 +1,189 @@
 //! Proof checker for Varisat proofs.
 
 use std::io;
+use std::mem::replace;
 
-use failure::{Error, Fail};
 use partial_ref::{IntoPartialRefMut, PartialRef};
+use thiserror::Error;
 
-use varisat_dimacs::DimacsParser;
-use varisat_formula::{CnfFormula, Lit};
+use varisat_dimacs::{DimacsParser, ParserError};
+use varisat_formula::{CnfFormula, ExtendFormula, Lit, Var};
+use varisat_internal_proof::ClauseHash;
 
 pub mod internal;
 
+mod background;
+mod callbacks;
 mod clauses;
+#[cfg(feature = "compression")]
+mod compression;
+mod config;
 mod context;
+mod dag;
+mod drat;
 mod hash;
+mod id_map;
+mod interpolant;
+mod literal_buffer;
+mod model;
 mod processing;
+mod profile;
 mod rup;
 mod sorted_lits;
 mod state;
 mod tmp;
+mod tracecheck;
 mod transcript;
+mod transform;
+mod trim;
+mod unsat_core;
 mod variables;
 
+pub use background::{BackgroundReader, BackgroundWriter};
+pub use callbacks::{CancelToken, CheckerProgress, CheckerStats};
+pub use clauses::GcPolicy;
+#[cfg(feature = "compression")]
+pub use compression::{compress, decompress, Compression};
+pub use config::CheckerConfig;
+pub use dag::{DagFormat, WriteProofDag};
+pub use drat::{DratFormat, DratParseError};
+pub use id_map::WriteClauseIdMap;
+pub use interpolant::{Interpolant, InterpolationError, Interpolator};
+pub use model::ModelError;
 pub use processing::{
-    CheckedProofStep, CheckedSamplingMode, CheckedUserVar, CheckerData, ProofProcessor,
-    ResolutionPropagations,
+    CheckedProofStep, CheckedSamplingMode, CheckedUserVar, CheckerData, ProcessorError,
+    ProofProcessor, ProofVerdict, ResolutionPropagations, StepFilter,
 };
+pub use profile::{Hotspot, StepKindProfile, StepProfiler};
+pub use state::UnitDeletionPolicy;
+pub use tracecheck::{TraceCheckError, WriteTraceCheck};
 pub use transcript::{ProofTranscriptProcessor, ProofTranscriptStep};
+pub use transform::{
+    DropDeletions, MergeDuplicates, ProofTransformer, RenumberIds, TransformError,
+    TransformProcessor,
+};
+pub use trim::ProofTrimmer;
+pub use unsat_core::{UnsatCore, UnsatCoreError};
+pub use varisat_internal_proof::{
+    binary_format::{HeaderError, StepError},
+    DeleteClauseProof, OwnedProofStep, ProofStep, SolveResult,
+};
 
+use callbacks::SegmentedReader;
 use clauses::add_clause;
 use context::Context;
-use state::check_proof;
+use drat::check_drat_proof;
+use hash::rehash;
+use model::{check_model, read_model};
+use state::{
+    check_proof, check_proof_slice, check_proof_steps, check_step, end_of_proof,
+    process_unit_conflicts,
+};
+use variables::ensure_var;
+
+/// Number of batches of decoded steps read ahead by [`Checker::check_proof_pipelined`].
+const READ_AHEAD_BATCHES: usize = 16;
 
 /// Possible errors while checking a varisat proof.
-#[derive(Debug, Fail)]
+#[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum CheckerError {
-    #[fail(display = "step {}: Unexpected end of proof file", step)]
-    ProofIncomplete { step: u64 },
-    #[fail(display = "step {}: Error reading proof file: {}", step, cause)]
+    #[error("step {step} (byte offset {offset}): Unexpected end of proof file")]
+    ProofIncomplete { step: u64, offset: u64 },
+    #[error("step {step} (byte offset {offset}): Error reading proof file: {cause}")]
     IoError {
         step: u64,
-        #[cause]
+        /// Byte offset of the step in the proof stream.
+        offset: u64,
+        #[source]
         cause: io::Error,
     },
-    #[fail(display = "step {}: Could not parse proof step: {}", step, cause)]
+    #[error("step {step}: Error storing clause literals: {cause}")]
+    StorageError {
+        step: u64,
+        #[source]
+        cause: io::Error,
+    },
+    #[error("step {step} (byte offset {offset}): Could not parse proof step: {cause}")]
     ParseError {
         step: u64,
-        #[cause]
-        cause: Error,
+        /// Byte offset of the step in the proof stream.
+        offset: u64,
+        /// Raw bytes of the step read before the error occurred.
+        step_bytes: Vec<u8>,
+        #[source]
+        cause: ProofParseError,
     },
-    #[fail(display = "step {}: Checking proof failed: {}", step, msg)]
+    #[error("step {step}: Checking proof failed: {msg}")]
     CheckFailed {
         step: u64,
         msg: String,
         debug_step: String,
+        /// Byte offset of the step in the proof stream.
+        ///
+        /// `None` for steps that were not read from a proof stream.
+        offset: Option<u64>,
+        /// Raw bytes of the step, empty for steps that were not read from a proof stream.
+        step_bytes: Vec<u8>,
+    },
+    #[error("Invalid proof header: {cause}")]
+    InvalidHeader {
+        #[source]
+        cause: HeaderError,
     },
-    #[fail(display = "Error in proof processor: {}", cause)]
+    #[error("Unsupported proof format version {version}, expected version {supported}")]
+    UnsupportedVersion { version: u64, supported: u64 },
+    #[error("step {step}: Checking was cancelled")]
+    Cancelled { step: u64 },
+    #[error("Error in proof processor: {cause}")]
     ProofProcessorError {
-        #[cause]
-        cause: Error,
+        #[source]
+        cause: ProcessorError,
     },
-    #[doc(hidden)]
-    #[fail(display = "__Nonexhaustive")]
-    __Nonexhaustive,
+    #[error("Invalid input formula: {cause}")]
+    InvalidFormula {
+        #[from]
+        cause: ParserError,
+    },
+    #[error("Invalid model: {cause}")]
+    InvalidModel {
+        #[from]
+        cause: ModelError,
+    },
+}
+
+/// Errors while parsing a single proof step.
+///
+/// Errors reading the proof are reported as [`CheckerError::IoError`] instead.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ProofParseError {
+    /// Invalid step of a proof in the native varisat format.
+    #[error(transparent)]
+    Varisat(#[from] StepError),
+    /// Invalid step of a DRAT proof.
+    #[error(transparent)]
+    Drat(#[from] DratParseError),
+}
+
+/// Result of a proof check that continues after failed steps.
+///
+/// See [`Checker::check_proof_lenient`].
+#[derive(Debug, Default)]
+pub struct CheckReport {
+    /// Errors of all failed steps in proof order.
+    pub errors: Vec<CheckerError>,
+    /// Whether checking stopped before reaching the end of the proof.
+    ///
+    /// This happens when the error limit is reached or when the proof cannot be read.
+    pub aborted: bool,
+}
+
+impl CheckReport {
+    /// Whether the proof was checked without errors.
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
 }
 
 impl CheckerError {
@@ -71,7 +193,21 @@ impl CheckerError {
             step,
             msg,
             debug_step: String::new(),
+            offset: None,
+            step_bytes: vec![],
+        }
+    }
+
+    /// Add the position and raw bytes of the failing step to a CheckFailed error.
+    fn in_stream(mut self, step_offset: u64, raw_step: &[u8]) -> CheckerError {
+        if let CheckerError::CheckFailed {
+            offset, step_bytes, ..
+        } = &mut self
+        {
+            *offset = Some(step_offset);
+            *step_bytes = raw_step.to_vec();
         }
+        self
     }
 }
 
@@ -87,8 +223,25 @@ impl<'a> Checker<'a> {
         Checker::default()
     }
 
+    /// Create a new checker using the given configuration.
+    ///
+    /// Panics if the configuration is invalid.
+    pub fn new_with_config(config: CheckerConfig) -> Checker<'a> {
+        config.validate();
+
+        let mut checker = Checker::default();
+        let ctx = &mut checker.ctx;
+        ctx.checker_state.unit_deletion_policy = config.unit_deletion_policy;
+        ctx.checker_state.collision_diagnostics = config.collision_diagnostics;
+        ctx.clause_hasher.hash_bits = config.hash_bits;
+        ctx.clauses.gc_policy = config.gc_policy;
+        ctx.clauses.spill_limit = config.clause_spill_limit;
+        checker
+    }
+
     /// Adds a clause to the checker.
     pub fn add_clause(&mut self, clause: &[Lit]) -> Result<(), CheckerError> {
+        self.ctx.processing.proof_step = 0;
         let mut ctx = self.ctx.into_partial_ref_mut();
         add_clause(ctx.borrow(), clause)
     }
@@ -105,9 +258,14 @@ impl<'a> Checker<'a> {
     /// Reads and adds a formula in DIMACS CNF format.
     ///
     /// Using this avoids creating a temporary [`CnfFormula`](varisat_formula::CnfFormula).
-    pub fn add_dimacs_cnf(&mut self, input: impl io::Read) -> Result<(), Error> {
+    ///
+    /// With the `compression` feature enabled, gzip, xz and zstd compressed input is decompressed
+    /// transparently.
+    ///
+    /// Errors of the parser are returned as [`CheckerError::InvalidFormula`].
+    pub fn add_dimacs_cnf(&mut self, input: impl io::Read) -> Result<(), CheckerError> {
         let parser = DimacsParser::parse_incremental(input, |parser| {
-            Ok(self.add_formula(&parser.take_formula())?)
+            self.add_formula(&parser.take_formula())
         })?;
 
         log::info!(
@@ -119,11 +277,82 @@ impl<'a> Checker<'a> {
         Ok(())
     }
 
+    /// Set how deletions of unit and empty clauses are handled.
+    ///
+    /// See [`CheckerConfig::unit_deletion_policy`].
+    pub fn set_unit_deletion_policy(&mut self, policy: UnitDeletionPolicy) {
+        self.ctx.checker_state.unit_deletion_policy = policy;
+    }
+
+    /// Move clause literals to disk when more than `limit` clauses are stored.
+    ///
+    /// See [`CheckerConfig::clause_spill_limit`].
+    pub fn set_clause_spill_limit(&mut self, limit: Option<usize>) {
+        self.ctx.clauses.spill_limit = limit;
+    }
+
+    /// Don't compact the clause literal storage before it contains this many literals of deleted
+    /// clauses.
+    ///
+    /// Compaction walks all stored clauses. Raising this limit reduces the time spent for it in
+    /// proofs with many deletions, at the cost of memory. The default is 0, so the storage is
+    /// compacted once more than half of it is garbage.
+    pub fn set_gc_min_garbage(&mut self, literals: usize) {
+        self.ctx.clauses.gc_policy.min_garbage = literals;
+    }
+
+    /// Configure when the clause literal storage is compacted.
+    ///
+    /// See [`CheckerConfig::gc_policy`] and [`GcPolicy`] for the available settings and their
+    /// defaults.
+    pub fn set_gc_policy(&mut self, policy: GcPolicy) {
+        self.ctx.clauses.gc_policy = policy;
+    }
+
+    /// Set a callback that is invoked periodically with the progress of a proof check.
+    pub fn set_progress_callback(&mut self, callback: impl FnMut(&CheckerProgress) + 'a) {
+        self.ctx.callbacks.set_progress(Some(Box::new(callback)));
+    }
+
+    /// Remove a callback set by [`set_progress_callback`](Checker::set_progress_callback).
+    pub fn clear_progress_callback(&mut self) {
+        self.ctx.callbacks.set_progress(None);
+    }
+
+    /// Set a token that can be used to cancel proof checking.
+    ///
+    /// When the token is cancelled, the running proof check is stopped and returns
+    /// [`CheckerError::Cancelled`].
+    pub fn set_cancel_token(&mut self, token: CancelToken) {
+        self.ctx.callbacks.set_cancel(Some(token));
+    }
+
+    /// Statistics about the proof checked so far.
+    pub fn stats(&self) -> CheckerStats {
+        let clauses = &self.ctx.clauses;
+        CheckerStats {
+            at_steps: self.ctx.checker_state.at_steps,
+            propagations: self.ctx.rup_check.propagations,
+            clauses: clauses.clause_count(),
+            peak_clauses: clauses.peak_clause_count(),
+            garbage_collections: clauses.garbage_collections(),
+        }
+    }
+
     /// Add a [`ProofProcessor`].
     ///
     /// This has to be called before loading any clauses or checking any proofs.
     pub fn add_processor(&mut self, processor: &'a mut dyn ProofProcessor) {
-        self.ctx.processing.processors.push(processor);
+        self.ctx.processing.add_processor(Box::new(processor));
+    }
+
+    /// Add a [`ProofProcessor`] owned by the checker.
+    ///
+    /// Unlike [`add_processor`](Checker::add_processor) this doesn't borrow the processor for the
+    /// lifetime of the checker. This has to be called before loading any clauses or checking any
+    /// proofs.
+    pub fn add_boxed_processor(&mut self, processor: Box<dyn ProofProcessor + 'a>) {
+        self.ctx.processing.add_processor(processor);
     }
 
     /// Add a [`ProofTranscriptProcessor`].
@@ -134,10 +363,284 @@ impl<'a> Checker<'a> {
     }
 
     /// Checks a proof in the native Varisat format.
+    ///
+    /// With the `compression` feature enabled, gzip and zstd compressed proofs are decompressed
+    /// transparently.
     pub fn check_proof(&mut self, input: impl io::Read) -> Result<(), CheckerError> {
+        self.take_pending_error()?;
+        let mut ctx = self.ctx.into_partial_ref_mut();
+        let result = maybe_decompress(input).and_then(|input| check_proof(ctx.borrow(), input));
+        end_of_proof(ctx.borrow(), result)
+    }
+
+    /// Checks a proof in the native Varisat format that is split into multiple segments.
+    ///
+    /// The segments are read one after the other and checked as a single proof, so only the first
+    /// segment contains the proof header and a proof step can span multiple segments. This allows
+    /// checking proofs that were written to multiple files. With the `compression` feature,
+    /// each segment is decompressed separately.
+    pub fn check_proof_segments<R: io::Read>(
+        &mut self,
+        segments: impl IntoIterator<Item = R>,
+    ) -> Result<(), CheckerError> {
+        self.take_pending_error()?;
+        let input = SegmentedReader::new(segments.into_iter().map(open_segment));
+
+        let mut ctx = self.ctx.into_partial_ref_mut();
+        let result = check_proof(ctx.borrow(), input);
+        end_of_proof(ctx.borrow(), result)
+    }
+
+    /// Checks a proof in the native Varisat format, reading it on a separate thread.
+    ///
+    /// Reading, decompressing (with the `compression` feature) and decoding the proof happens on a
+    /// background thread that decodes steps ahead of the checker. Only checking the decoded steps
+    /// remains on the calling thread. Otherwise this is the same as
+    /// [`check_proof`](Checker::check_proof).
+    pub fn check_proof_pipelined(
+        &mut self,
+        input: impl io::Read + Send + 'static,
+    ) -> Result<(), CheckerError> {
+        self.take_pending_error()?;
+        #[cfg(feature = "compression")]
+        let mut steps = StepReader::spawn(move || decompress(input), READ_AHEAD_BATCHES);
+        #[cfg(not(feature = "compression"))]
+        let mut steps = StepReader::spawn(move || Ok(input), READ_AHEAD_BATCHES);
+
+        let mut ctx = self.ctx.into_partial_ref_mut();
+        let result = check_proof_steps(ctx.borrow(), &mut steps);
+        end_of_proof(ctx.borrow(), result)
+    }
+
+    /// Checks a proof in the native Varisat format stored in memory.
+    ///
+    /// Steps are decoded directly from `proof`, avoiding the copies made when reading from an
+    /// [`io::Read`]. With the `compression` feature, compressed proofs are decompressed as in
+    /// [`check_proof`](Checker::check_proof), which doesn't avoid these copies.
+    pub fn check_proof_bytes(&mut self, proof: &[u8]) -> Result<(), CheckerError> {
+        self.take_pending_error()?;
+        #[cfg(feature = "compression")]
+        {
+            if Compression::detect(proof).is_some() {
+                return self.check_proof(proof);
+            }
+        }
+
+        let mut ctx = self.ctx.into_partial_ref_mut();
+        let result = check_proof_slice(ctx.borrow(), proof);
+        end_of_proof(ctx.borrow(), result)
+    }
+
+    /// Checks a proof file in the native Varisat format by memory mapping it.
+    ///
+    /// See [`check_proof_bytes`](Checker::check_proof_bytes). The file must not be modified while
+    /// it is checked. Requires the `disk-spill` feature.
+    #[cfg(feature = "disk-spill")]
+    pub fn check_proof_mmap(&mut self, file: &std::fs::File) -> Result<(), CheckerError> {
+        // An empty file cannot be mapped but is also an invalid proof.
+        let empty = file.metadata().map(|metadata| metadata.len() == 0);
+        if let Ok(true) = empty {
+            return self.check_proof_bytes(&[]);
+        }
+
+        let map = unsafe { memmap::Mmap::map(file) };
+        match map {
+            Ok(map) => self.check_proof_bytes(&map),
+            Err(cause) => {
+                let mut ctx = self.ctx.into_partial_ref_mut();
+                end_of_proof(
+                    ctx.borrow(),
+                    Err(CheckerError::IoError {
+                        step: 0,
+                        offset: 0,
+                        cause,
+                    }),
+                )
+            }
+        }
+    }
+
+    /// Metadata from the header of the checked proof.
+    ///
+    /// Empty until [`check_proof`](Checker::check_proof) read the header.
+    pub fn proof_metadata(&self) -> &[(String, String)] {
+        &self.ctx.checker_state.proof_metadata
+    }
+
+    /// Progress of the checker at the last checkpoint of the checked proof.
+    ///
+    /// Solvers write [`Checkpoint`](ProofStep::Checkpoint) steps when syncing the proof to disk.
+    /// When checking a proof that was cut short, e.g. by a crash of the solver, this identifies
+    /// the prefix of the proof that was written completely. The `bytes` of the returned progress
+    /// are the length of that prefix.
+    pub fn last_checkpoint(&self) -> Option<CheckerProgress> {
+        self.ctx.checker_state.last_checkpoint
+    }
+
+    /// Checks a proof in the native Varisat format, continuing after failed steps.
+    ///
+    /// Failed steps are skipped, so later steps depending on them may fail too. Checking is
+    /// aborted once `max_errors` steps failed or when an error other than a failed check occurs.
+    pub fn check_proof_lenient(&mut self, input: impl io::Read, max_errors: usize) -> CheckReport {
+        self.ctx.checker_state.error_limit = max_errors;
+        let result = self.check_proof(input);
+        self.ctx.checker_state.error_limit = 0;
+
+        let mut report = CheckReport {
+            errors: replace(&mut self.ctx.checker_state.recovered_errors, vec![]),
+            aborted: false,
+        };
+
+        if let Err(err) = result {
+            report.errors.push(err);
+            report.aborted = true;
+        }
+
+        report
+    }
+
+    /// Checks a proof in the DRAT format.
+    ///
+    /// DRAT proofs contain no clause hashes, so every lemma is checked using unit propagation over
+    /// all clauses. This is slower than checking a native proof, but allows checking proofs
+    /// generated by other solvers. Only RUP lemmas are supported, lemmas that are RAT but not RUP
+    /// consequences are rejected, as are deletions of clauses that aren't present.
+    ///
+    /// Succeeds only when the proof derives the empty clause. Compressed proofs are handled as
+    /// for [`check_proof`](Checker::check_proof).
+    pub fn check_drat_proof(
+        &mut self,
+        input: impl io::Read,
+        format: DratFormat,
+    ) -> Result<(), CheckerError> {
+        self.take_pending_error()?;
+        let mut ctx = self.ctx.into_partial_ref_mut();
+        let result =
+            maybe_decompress(input).and_then(|input| check_drat_proof(ctx.borrow(), input, format));
+        end_of_proof(ctx.borrow(), result)
+    }
+
+    /// Checks a single proof step.
+    ///
+    /// This allows solvers to stream their derivations into the checker without serializing them
+    /// first. Calls can be interleaved with [`add_clause`](Checker::add_clause) and
+    /// [`add_formula`](Checker::add_formula). After the last step
+    /// [`check_delayed_steps`](Checker::check_delayed_steps) has to be called.
+    ///
+    /// Steps that reference clauses by hash need to use the hashes computed by
+    /// [`clause_hash`](Checker::clause_hash).
+    pub fn check_step(&mut self, step: ProofStep) -> Result<(), CheckerError> {
+        self.take_pending_error()?;
+        self.ctx.checker_state.step += 1;
         let mut ctx = self.ctx.into_partial_ref_mut();
-        check_proof(ctx.borrow(), input)
+        check_step(ctx.borrow(), step)
     }
+
+    /// Process steps that are delayed until the end of a proof.
+    ///
+    /// This needs to be called once after the last call to [`check_step`](Checker::check_step). It
+    /// is not needed when using [`check_proof`](Checker::check_proof).
+    pub fn check_delayed_steps(&mut self) -> Result<(), CheckerError> {
+        self.take_pending_error()?;
+        let mut ctx = self.ctx.into_partial_ref_mut();
+        process_unit_conflicts(ctx.borrow())
+    }
+
+    /// Hash of a clause as expected in the `propagation_hashes` of a [`ProofStep`].
+    ///
+    /// This depends on the current solver variable names and hash bits.
+    pub fn clause_hash(&mut self, clause: &[Lit]) -> ClauseHash {
+        if self.ctx.clause_hasher.rename_in_buffered_solver_var_names {
+            let mut ctx = self.ctx.into_partial_ref_mut();
+            rehash(ctx.borrow());
+        }
+        self.ctx.clause_hasher.clause_hash(clause)
+    }
+
+    /// Checks that an assignment satisfies the formula.
+    ///
+    /// The assignment may be partial, but every irredundant clause added so far must contain a
+    /// literal of the assignment. If this is not the case, the violated clause with the smallest
+    /// clause id is reported.
+    pub fn check_model(&mut self, model: &[Lit]) -> Result<(), CheckerError> {
+        self.take_pending_error()?;
+        let mut ctx = self.ctx.into_partial_ref_mut();
+        check_model(ctx.borrow(), model)
+    }
+
+    /// Reads an assignment in the SAT competition solution format and checks it.
+    ///
+    /// See [`check_model`](Checker::check_model).
+    ///
+    /// Errors while reading the model are returned as [`CheckerError::InvalidModel`].
+    pub fn check_model_file(&mut self, input: impl io::Read) -> Result<(), CheckerError> {
+        let model = read_model(input)?;
+        self.check_model(&model)
+    }
+
+    /// Whether unsatisfiability of the current formula was proven.
+    pub fn is_unsat(&self) -> bool {
+        self.ctx.checker_state.unsat
+    }
+
+    /// Report an error of a clause added using [`ExtendFormula`].
+    fn take_pending_error(&mut self) -> Result<(), CheckerError> {
+        match self.ctx.checker_state.pending_error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Allows encoders to add their clauses directly to the checker.
+///
+/// As [`ExtendFormula::add_clause`] cannot fail, an error caused by adding a clause is reported by
+/// the next call that checks a proof, a proof step or a model. Clauses added after such an error are
+/// ignored.
+impl<'a> ExtendFormula for Checker<'a> {
+    fn add_clause(&mut self, clause: &[Lit]) {
+        if self.ctx.checker_state.pending_error.is_none() {
+            if let Err(err) = Checker::add_clause(self, clause) {
+                self.ctx.checker_state.pending_error = Some(err);
+            }
+        }
+    }
+
+    /// Returns the variable following the largest variable seen by the checker.
+    fn new_var(&mut self) -> Var {
+        let var = Var::from_index(self.ctx.variables.var_data.len());
+        let mut ctx = self.ctx.into_partial_ref_mut();
+        ensure_var(ctx.borrow(), var);
+        var
+    }
+}
+
+/// Decompress a proof stream if compression support is enabled.
+#[cfg(feature = "compression")]
+fn maybe_decompress<'a>(input: impl io::Read + 'a) -> Result<Box<dyn io::Read + 'a>, CheckerError> {
+    decompress(input).map_err(|cause| CheckerError::IoError {
+        step: 0,
+        offset: 0,
+        cause,
+    })
+}
+
+/// Decompress a proof stream if compression support is enabled.
+#[cfg(not(feature = "compression"))]
+fn maybe_decompress<R: io::Read>(input: R) -> Result<R, CheckerError> {
+    Ok(input)
+}
+
+/// Open a segment of a proof stream, decompressing it if compression support is enabled.
+#[cfg(feature = "compression")]
+fn open_segment<'a>(segment: impl io::Read + 'a) -> io::Result<Box<dyn io::Read + 'a>> {
+    decompress(segment)
+}
+
+/// Open a segment of a proof stream, decompressing it if compression support is enabled.
+#[cfg(not(feature = "compression"))]
+fn open_segment<'a>(segment: impl io::Read + 'a) -> io::Result<Box<dyn io::Read + 'a>> {
+    Ok(Box::new(segment))
 }
 
 #[cfg(test)]
@@ -145,9 +648,12 @@ mod tests {
     use super::internal::SelfChecker;
     use super::*;
 
-    use varisat_internal_proof::{DeleteClauseProof, ProofStep};
+    use varisat_internal_proof::{
+        binary_format::{write_header, write_step},
+        DeleteClauseProof, ProofStep,
+    };
 
-    use varisat_formula::{cnf_formula, lits, Var};
+    use varisat_formula::{cnf_formula, lit, lits, Var};
 
     fn expect_check_failed(result: Result<(), CheckerError>, contains: &str) {
         match result {
@@ -330,179 +836,1336 @@ mod tests {
     }
 
     #[test]
-    fn delete_clause_not_redundant() {
+    fn delete_hashed_clause() {
         let mut checker = Checker::new();
         checker
             .add_formula(&cnf_formula![
+                1;
                 1, 2, 3;
+                4, 5, 6;
+                -4, -5, -6;
+                7, 8, 9;
             ])
             .unwrap();
 
+        let hash = checker.clause_hash(&lits![1, 2, 3]);
+
+        checker
+            .self_check_step(ProofStep::DeleteHashedClause {
+                hash,
+                proof: DeleteClauseProof::Satisfied,
+            })
+            .unwrap();
+
         expect_check_failed(
-            checker.self_check_step(ProofStep::DeleteClause {
-                clause: &lits![1, 2, 3],
-                proof: DeleteClauseProof::Redundant,
+            checker.self_check_step(ProofStep::DeleteHashedClause {
+                hash,
+                proof: DeleteClauseProof::Satisfied,
             }),
-            "is irredundant",
-        )
+            "no clause found",
+        );
+
+        checker
+            .self_check_step(ProofStep::ChangeHashBits { bits: 1 })
+            .unwrap();
+
+        // With a single bit, at least two of the remaining three clauses share a hash.
+        let hashes = [
+            checker.clause_hash(&lits![4, 5, 6]),
+            checker.clause_hash(&lits![-4, -5, -6]),
+            checker.clause_hash(&lits![7, 8, 9]),
+        ];
+        let hash = hashes[0];
+        let count = hashes.iter().filter(|&&other| other == hash).count();
+        let hash = if count > 1 { hash } else { hashes[1] };
+        let count = hashes.iter().filter(|&&other| other == hash).count();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::DeleteHashedClause {
+                hash,
+                proof: DeleteClauseProof::Simplified,
+            }),
+            &format!("{} clauses found", count),
+        );
     }
 
     #[test]
-    fn delete_clause_not_satisfied() {
+    fn check_models() {
         let mut checker = Checker::new();
         checker
             .add_formula(&cnf_formula![
-                1, 2, 3;
+                1, 2;
                 -2;
-                4;
+                -1, 3, 4;
+                -3, -4;
+                1, 3;
             ])
             .unwrap();
 
+        checker.check_model(&lits![1, -2, 3, -4]).unwrap();
+        checker.check_model(&lits![1, -2, -3, 4]).unwrap();
+
         expect_check_failed(
-            checker.self_check_step(ProofStep::DeleteClause {
-                clause: &lits![1, 2, 3],
-                proof: DeleteClauseProof::Satisfied,
-            }),
-            "not satisfied",
-        )
+            checker.check_model(&lits![1, -2, 3, 4]),
+            "does not satisfy clause 3",
+        );
+        expect_check_failed(
+            checker.check_model(&lits![-1, -2]),
+            "does not satisfy clause 0",
+        );
+        expect_check_failed(
+            checker.check_model(&lits![1, 2, -2]),
+            "conflicting assignment",
+        );
+
+        checker
+            .check_model_file(&b"s SATISFIABLE\nv 1 -2 3\nv -4 0\n"[..])
+            .unwrap();
     }
 
     #[test]
-    fn delete_clause_not_simplified() {
+    fn boxed_processor() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        struct CountSteps(Rc<Cell<usize>>);
+
+        impl ProofProcessor for CountSteps {
+            fn process_step(
+                &mut self,
+                _step: &CheckedProofStep,
+                _data: CheckerData,
+            ) -> Result<(), ProcessorError> {
+                self.0.set(self.0.get() + 1);
+                Ok(())
+            }
+        }
+
+        let count = Rc::new(Cell::new(0));
+
         let mut checker = Checker::new();
+        checker.add_boxed_processor(Box::new(CountSteps(count.clone())));
+
         checker
             .add_formula(&cnf_formula![
-                1, 2, 3;
-                -3, 4;
+                1, 2;
+                -1, 2;
             ])
             .unwrap();
 
         let hashes = [
-            checker.ctx.clause_hasher.clause_hash(&lits![1, 2, 3]),
-            checker.ctx.clause_hasher.clause_hash(&lits![-3, 4]),
+            checker.clause_hash(&lits![1, 2]),
+            checker.clause_hash(&lits![-1, 2]),
         ];
 
         checker
             .self_check_step(ProofStep::AtClause {
                 redundant: false,
-                clause: &lits![1, 2, 4],
+                clause: &lits![2],
                 propagation_hashes: &hashes[..],
             })
             .unwrap();
 
-        expect_check_failed(
-            checker.self_check_step(ProofStep::DeleteClause {
-                clause: &lits![1, 2, 3],
-                proof: DeleteClauseProof::Simplified,
-            }),
-            "not subsumed",
-        )
+        let steps = count.get();
+        assert!(steps > 0);
+
+        drop(checker);
+        assert_eq!(Rc::strong_count(&count), 1);
+        assert_eq!(count.get(), steps);
     }
 
     #[test]
-    fn model_unit_conflict() {
+    fn extend_formula() {
+        struct RejectClauses;
+
+        impl ProofProcessor for RejectClauses {
+            fn process_step(
+                &mut self,
+                step: &CheckedProofStep,
+                _data: CheckerData,
+            ) -> Result<(), ProcessorError> {
+                match step {
+                    CheckedProofStep::AddClause { .. } => Err("rejected".into()),
+                    _ => Ok(()),
+                }
+            }
+        }
+
         let mut checker = Checker::new();
-        checker
-            .add_formula(&cnf_formula![
-                1;
-                2, 3;
-            ])
-            .unwrap();
+        checker.add_formula(&cnf_formula![1, 2;]).unwrap();
 
-        expect_check_failed(
-            checker.self_check_step(ProofStep::Model {
-                assignment: &lits![-1, 2, -3],
-            }),
-            "conflicts with unit clause",
-        )
-    }
+        let x = ExtendFormula::new_lit(&mut checker);
+        assert_eq!(x, lit!(3));
+        varisat_formula::encode::xor_constraint(&mut checker, &[lit!(1), lit!(2), x]);
 
-    #[test]
-    fn model_internal_conflict() {
+        checker.check_model(&lits![1, -2, -3]).unwrap();
+        expect_check_failed(checker.check_model(&lits![1, -2, 3]), "does not satisfy");
+
+        let mut reject = RejectClauses;
         let mut checker = Checker::new();
-        checker
-            .add_formula(&cnf_formula![
-                2, 3;
-            ])
-            .unwrap();
+        checker.add_processor(&mut reject);
 
-        expect_check_failed(
-            checker.self_check_step(ProofStep::Model {
-                assignment: &lits![-1, 1, 2, -3],
-            }),
-            "conflicting assignment",
-        )
+        ExtendFormula::add_clause(&mut checker, &lits![1, 2]);
+        match checker.check_model(&lits![1]) {
+            Err(CheckerError::ProofProcessorError { .. }) => (),
+            err => panic!("expected processor error but got {:?}", err),
+        }
+        checker.check_model(&lits![1]).unwrap();
     }
 
     #[test]
-    fn model_clause_unsat() {
-        let mut checker = Checker::new();
-        checker
-            .add_formula(&cnf_formula![
-                1, 2, 3;
-                -1, -2, 3;
-                1, -2, -3;
-            ])
-            .unwrap();
+    fn step_filter() {
+        struct RecordSteps {
+            filter: StepFilter,
+            steps: Vec<(bool, usize)>,
+        }
 
-        expect_check_failed(
-            checker.self_check_step(ProofStep::Model {
-                assignment: &lits![-1, 2, 3],
-            }),
-            "does not satisfy clause",
-        )
+        impl ProofProcessor for RecordSteps {
+            fn process_step(
+                &mut self,
+                step: &CheckedProofStep,
+                _data: CheckerData,
+            ) -> Result<(), ProcessorError> {
+                match step {
+                    CheckedProofStep::AtClause { propagations, .. } => {
+                        self.steps.push((true, propagations.len()))
+                    }
+                    _ => self.steps.push((false, 0)),
+                }
+                Ok(())
+            }
+
+            fn step_filter(&self) -> StepFilter {
+                self.filter
+            }
+        }
+
+        for &with_hints in [false, true].iter() {
+            let mut lemmas = RecordSteps {
+                filter: StepFilter {
+                    lemmas: true,
+                    ..StepFilter::none()
+                },
+                steps: vec![],
+            };
+            let mut inputs = RecordSteps {
+                filter: StepFilter {
+                    inputs: true,
+                    hints: with_hints,
+                    ..StepFilter::none()
+                },
+                steps: vec![],
+            };
+
+            {
+                let mut checker = Checker::new();
+                checker.add_processor(&mut lemmas);
+                checker.add_processor(&mut inputs);
+
+                checker
+                    .add_formula(&cnf_formula![
+                        1, 2;
+                        -1, 2;
+                    ])
+                    .unwrap();
+
+                let hashes = [
+                    checker.clause_hash(&lits![1, 2]),
+                    checker.clause_hash(&lits![-1, 2]),
+                ];
+
+                checker
+                    .self_check_step(ProofStep::AtClause {
+                        redundant: false,
+                        clause: &lits![2],
+                        propagation_hashes: &hashes[..],
+                    })
+                    .unwrap();
+            }
+
+            let expected_propagations = if with_hints { 2 } else { 0 };
+
+            assert_eq!(lemmas.steps, vec![(true, expected_propagations)]);
+            assert_eq!(inputs.steps, vec![(false, 0), (false, 0)]);
+        }
     }
+
     #[test]
-    fn model_conflicts_assumptions() {
+    fn checker_stats() {
         let mut checker = Checker::new();
+
         checker
             .add_formula(&cnf_formula![
-                1, 2;
-                -1, 2;
+                1, 2, 3;
+                -1, 2, 3;
+                -2, 3;
+                -3;
             ])
             .unwrap();
 
-        checker
+        let hashes = [
+            checker.clause_hash(&lits![1, 2, 3]),
+            checker.clause_hash(&lits![-1, 2, 3]),
+        ];
+
+        checker
+            .self_check_step(ProofStep::AtClause {
+                redundant: true,
+                clause: &lits![2],
+                propagation_hashes: &hashes[..],
+            })
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::DeleteClause {
+                clause: &lits![1, 2, 3],
+                proof: DeleteClauseProof::Satisfied,
+            })
+            .unwrap();
+
+        let stats = checker.stats();
+        assert_eq!(stats.at_steps, 1);
+        assert_eq!(stats.propagations, 1);
+        assert_eq!(stats.clauses, 2);
+        assert_eq!(stats.peak_clauses, 3);
+    }
+
+    #[test]
+    fn lazy_garbage_collection() {
+        let mut checker = Checker::new();
+
+        checker.add_clause(&lits![1]).unwrap();
+        for i in 2..20 {
+            checker.add_clause(&lits![1, i, i + 1]).unwrap();
+        }
+
+        for i in 2..20 {
+            checker
+                .self_check_step(ProofStep::DeleteClause {
+                    clause: &lits![1, i, i + 1],
+                    proof: DeleteClauseProof::Satisfied,
+                })
+                .unwrap();
+        }
+
+        assert_eq!(checker.stats().garbage_collections, 0);
+
+        checker.add_clause(&lits![2, 3]).unwrap();
+
+        assert_eq!(checker.stats().garbage_collections, 1);
+        assert_eq!(checker.stats().clauses, 1);
+    }
+
+    #[test]
+    fn gc_policy() {
+        for &(deferred, garbage_ratio, expected) in [
+            (false, 0.5, 2),
+            (false, 0.25, 4),
+            (false, 0.9, 0),
+            (true, 0.25, 0),
+        ]
+        .iter()
+        {
+            let mut checker = Checker::new();
+            checker.set_gc_policy(GcPolicy {
+                garbage_ratio,
+                deferred,
+                ..GcPolicy::default()
+            });
+
+            checker.add_clause(&lits![1]).unwrap();
+            for i in 2..10 {
+                checker.add_clause(&lits![1, i, i + 1, i + 2]).unwrap();
+            }
+
+            for i in 2..9 {
+                checker
+                    .self_check_step(ProofStep::DeleteClause {
+                        clause: &lits![1, i, i + 1, i + 2],
+                        proof: DeleteClauseProof::Satisfied,
+                    })
+                    .unwrap();
+            }
+
+            assert_eq!(
+                checker.stats().garbage_collections,
+                expected,
+                "deferred: {:?} ratio: {:?}",
+                deferred,
+                garbage_ratio
+            );
+        }
+    }
+
+    #[test]
+    fn lenient_check() {
+        let formula = cnf_formula![
+            1, 2;
+            -1, 2;
+            1, -2;
+            -1, -2;
+        ];
+
+        let mut checker = Checker::new();
+        checker.add_formula(&formula).unwrap();
+
+        let hashes = [
+            checker.clause_hash(&lits![1, 2]),
+            checker.clause_hash(&lits![-1, 2]),
+        ];
+
+        let mut proof = vec![];
+
+        write_header(&mut proof, &[]).unwrap();
+
+        for step in [
+            ProofStep::AtClause {
+                redundant: true,
+                clause: &lits![3],
+                propagation_hashes: &[],
+            },
+            ProofStep::AtClause {
+                redundant: true,
+                clause: &lits![2],
+                propagation_hashes: &hashes[..],
+            },
+            ProofStep::AtClause {
+                redundant: true,
+                clause: &lits![1],
+                propagation_hashes: &[],
+            },
+            ProofStep::End,
+        ]
+        .iter()
+        {
+            write_step(&mut proof, step).unwrap();
+        }
+
+        let report = checker.check_proof_lenient(&proof[..], 10);
+
+        assert!(!report.aborted);
+        assert_eq!(report.errors.len(), 2);
+        match report.errors[1] {
+            CheckerError::CheckFailed { step: 3, .. } => (),
+            ref err => panic!("unexpected error {:?}", err),
+        }
+
+        let mut checker = Checker::new();
+        checker.add_formula(&formula).unwrap();
+
+        let report = checker.check_proof_lenient(&proof[..], 2);
+
+        assert!(report.aborted);
+        assert_eq!(report.errors.len(), 2);
+    }
+
+    #[test]
+    fn proof_header() {
+        let mut checker = Checker::new();
+
+        match checker.check_proof(&b"p cnf 1 1\n"[..]) {
+            Err(CheckerError::InvalidHeader { .. }) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let mut proof = vec![];
+        write_header(&mut proof, &[("generator", "test")]).unwrap();
+        write_step(&mut proof, &ProofStep::End).unwrap();
+
+        checker.check_proof(&proof[..]).unwrap();
+
+        assert_eq!(
+            checker.proof_metadata(),
+            &[("generator".to_string(), "test".to_string())][..]
+        );
+    }
+
+    #[test]
+    fn pipelined_check() {
+        let mut proof = vec![];
+        write_header(&mut proof, &[("generator", "test")]).unwrap();
+        let header_len = proof.len();
+        write_step(&mut proof, &ProofStep::End).unwrap();
+
+        let mut checker = Checker::new();
+        checker.add_clause(&lits![1, 2]).unwrap();
+        checker
+            .check_proof_pipelined(io::Cursor::new(proof.clone()))
+            .unwrap();
+        assert_eq!(checker.proof_metadata().len(), 1);
+
+        let mut checker = Checker::new();
+        proof.truncate(header_len);
+        match checker.check_proof_pipelined(io::Cursor::new(proof)) {
+            Err(CheckerError::ProofIncomplete { .. }) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "disk-spill")]
+    #[test]
+    fn memory_mapped_check() {
+        let mut proof = vec![];
+        write_header(&mut proof, &[("generator", "test")]).unwrap();
+        let header_len = proof.len();
+        write_step(&mut proof, &ProofStep::End).unwrap();
+
+        let mut file = tempfile::tempfile().unwrap();
+        io::Write::write_all(&mut file, &proof).unwrap();
+
+        let mut checker = Checker::new();
+        checker.add_clause(&lits![1, 2]).unwrap();
+        checker.check_proof_mmap(&file).unwrap();
+        assert_eq!(checker.proof_metadata().len(), 1);
+
+        let mut checker = Checker::new();
+        match checker.check_proof_bytes(&proof[..header_len]) {
+            Err(CheckerError::ProofIncomplete { offset, .. }) => {
+                assert_eq!(offset, header_len as u64)
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn segmented_proof() {
+        let mut checker = Checker::new();
+        checker.add_formula(&cnf_formula![1, 2; -1, 2;]).unwrap();
+        let hashes = [
+            checker.clause_hash(&lits![1, 2]),
+            checker.clause_hash(&lits![-1, 2]),
+        ];
+
+        let mut proof = vec![];
+        write_header(&mut proof, &[]).unwrap();
+        write_step(
+            &mut proof,
+            &ProofStep::AtClause {
+                redundant: false,
+                clause: &lits![2],
+                propagation_hashes: &hashes[..],
+            },
+        )
+        .unwrap();
+        let end_offset = proof.len();
+        write_step(&mut proof, &ProofStep::End).unwrap();
+
+        for split in 0..=proof.len() {
+            let mut checker = Checker::new();
+            checker.add_formula(&cnf_formula![1, 2; -1, 2;]).unwrap();
+            checker
+                .check_proof_segments(vec![&proof[..split], &[][..], &proof[split..]])
+                .unwrap();
+        }
+
+        let mut checker = Checker::new();
+        checker.add_formula(&cnf_formula![1, 2; -1, 2;]).unwrap();
+        match checker.check_proof_segments(vec![&proof[..end_offset]]) {
+            Err(CheckerError::ProofIncomplete { offset, .. }) => {
+                assert_eq!(offset, end_offset as u64)
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cancelled_check() {
+        let mut checker = Checker::new();
+        let token = CancelToken::new();
+        checker.set_cancel_token(token.clone());
+
+        token.cancel();
+
+        let mut proof = vec![];
+        write_header(&mut proof, &[]).unwrap();
+
+        match checker.check_proof(&proof[..]) {
+            Err(CheckerError::Cancelled { step: 1 }) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "disk-spill")]
+    #[test]
+    fn spilled_clauses() {
+        let mut checker = Checker::new();
+        checker.set_clause_spill_limit(Some(2));
+
+        checker
+            .add_formula(&cnf_formula![
+                1, 2, 3, 4;
+                -1, 2, 3, 4;
+                -2, 3, 4;
+            ])
+            .unwrap();
+
+        assert!(checker.ctx.clauses.literal_buffer.is_spilled());
+
+        let hashes = [
+            checker.clause_hash(&lits![1, 2, 3, 4]),
+            checker.clause_hash(&lits![-1, 2, 3, 4]),
+        ];
+
+        checker
+            .self_check_step(ProofStep::AtClause {
+                redundant: true,
+                clause: &lits![2, 3, 4],
+                propagation_hashes: &hashes[..],
+            })
+            .unwrap();
+
+        let hashes = [
+            checker.clause_hash(&lits![2, 3, 4]),
+            checker.clause_hash(&lits![-2, 3, 4]),
+        ];
+
+        checker
+            .self_check_step(ProofStep::AtClause {
+                redundant: true,
+                clause: &lits![3, 4],
+                propagation_hashes: &hashes[..],
+            })
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::DeleteClause {
+                clause: &lits![2, 3, 4],
+                proof: DeleteClauseProof::Redundant,
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn ignore_unit_clause_deletion() {
+        let mut checker = Checker::new();
+        checker.set_unit_deletion_policy(UnitDeletionPolicy::Ignore);
+        checker
+            .add_formula(&cnf_formula![
+                1;
+            ])
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::DeleteClause {
+                clause: &lits![1],
+                proof: DeleteClauseProof::Redundant,
+            })
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::DeleteClause {
+                clause: &[],
+                proof: DeleteClauseProof::Redundant,
+            })
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::Model {
+                assignment: &lits![-1],
+            })
+            .unwrap_err();
+    }
+
+    #[test]
+    fn delete_unit_clause_with_policy() {
+        let mut checker = Checker::new();
+        checker.set_unit_deletion_policy(UnitDeletionPolicy::Delete);
+        checker
+            .add_formula(&cnf_formula![
+                1;
+            ])
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::DeleteClause {
+                clause: &lits![-1],
+                proof: DeleteClauseProof::Redundant,
+            }),
+            "unknown clause",
+        );
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::DeleteClause {
+                clause: &lits![1],
+                proof: DeleteClauseProof::Redundant,
+            }),
+            "which is irredundant",
+        );
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::DeleteClause {
+                clause: &lits![1],
+                proof: DeleteClauseProof::Satisfied,
+            }),
+            "not satisfied",
+        );
+
+        checker
+            .self_check_step(ProofStep::Model {
+                assignment: &lits![-1],
+            })
+            .unwrap_err();
+    }
+
+    #[test]
+    fn checker_config() {
+        let mut config = CheckerConfig::default();
+        config.unit_deletion_policy = UnitDeletionPolicy::Delete;
+        config.hash_bits = 128;
+
+        let mut checker = Checker::new_with_config(config);
+        checker
+            .add_formula(&cnf_formula![
+                1, 2;
+                -1, 2;
+                3;
+            ])
+            .unwrap();
+
+        let hashes = [
+            checker.clause_hash(&lits![1, 2]),
+            checker.clause_hash(&lits![-1, 2]),
+        ];
+        assert_eq!(hashes[0] >> 64, Checker::new().clause_hash(&lits![1, 2]));
+
+        checker
+            .self_check_step(ProofStep::AtClause {
+                redundant: false,
+                clause: &lits![2],
+                propagation_hashes: &hashes,
+            })
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::DeleteClause {
+                clause: &lits![3],
+                proof: DeleteClauseProof::Redundant,
+            }),
+            "which is irredundant",
+        );
+
+        checker
+            .self_check_step(ProofStep::Model {
+                assignment: &lits![2, 3],
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn collision_diagnostics() {
+        let mut config = CheckerConfig::default();
+        config.hash_bits = 1;
+        config.collision_diagnostics = true;
+
+        let mut checker = Checker::new_with_config(config);
+        checker
+            .add_formula(&cnf_formula![
+                1, 2;
+                3, 4;
+                5, 6;
+            ])
+            .unwrap();
+
+        // With only two different hashes, at least two clauses collide
+        let hashes = [
+            checker.clause_hash(&lits![1, 2]),
+            checker.clause_hash(&lits![3, 4]),
+            checker.clause_hash(&lits![5, 6]),
+        ];
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::AtClause {
+                redundant: false,
+                clause: &lits![7, 8],
+                propagation_hashes: &hashes,
+            }),
+            "hash collision between clauses",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "hash bits must be in range")]
+    fn invalid_checker_config() {
+        let mut config = CheckerConfig::default();
+        config.hash_bits = 0;
+        Checker::new_with_config(config);
+    }
+
+    #[test]
+    fn delete_clause_not_redundant() {
+        let mut checker = Checker::new();
+        checker
+            .add_formula(&cnf_formula![
+                1, 2, 3;
+            ])
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::DeleteClause {
+                clause: &lits![1, 2, 3],
+                proof: DeleteClauseProof::Redundant,
+            }),
+            "is irredundant",
+        )
+    }
+
+    #[test]
+    fn delete_clause_not_satisfied() {
+        let mut checker = Checker::new();
+        checker
+            .add_formula(&cnf_formula![
+                1, 2, 3;
+                -2;
+                4;
+            ])
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::DeleteClause {
+                clause: &lits![1, 2, 3],
+                proof: DeleteClauseProof::Satisfied,
+            }),
+            "not satisfied",
+        )
+    }
+
+    #[test]
+    fn delete_clause_not_simplified() {
+        let mut checker = Checker::new();
+        checker
+            .add_formula(&cnf_formula![
+                1, 2, 3;
+                -3, 4;
+            ])
+            .unwrap();
+
+        let hashes = [
+            checker.ctx.clause_hasher.clause_hash(&lits![1, 2, 3]),
+            checker.ctx.clause_hasher.clause_hash(&lits![-3, 4]),
+        ];
+
+        checker
+            .self_check_step(ProofStep::AtClause {
+                redundant: false,
+                clause: &lits![1, 2, 4],
+                propagation_hashes: &hashes[..],
+            })
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::DeleteClause {
+                clause: &lits![1, 2, 3],
+                proof: DeleteClauseProof::Simplified,
+            }),
+            "not subsumed",
+        )
+    }
+
+    #[test]
+    fn model_unit_conflict() {
+        let mut checker = Checker::new();
+        checker
+            .add_formula(&cnf_formula![
+                1;
+                2, 3;
+            ])
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::Model {
+                assignment: &lits![-1, 2, -3],
+            }),
+            "conflicts with unit clause",
+        )
+    }
+
+    #[test]
+    fn model_internal_conflict() {
+        let mut checker = Checker::new();
+        checker
+            .add_formula(&cnf_formula![
+                2, 3;
+            ])
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::Model {
+                assignment: &lits![-1, 1, 2, -3],
+            }),
+            "conflicting assignment",
+        )
+    }
+
+    #[test]
+    fn model_clause_unsat() {
+        let mut checker = Checker::new();
+        checker
+            .add_formula(&cnf_formula![
+                1, 2, 3;
+                -1, -2, 3;
+                1, -2, -3;
+            ])
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::Model {
+                assignment: &lits![-1, 2, 3],
+            }),
+            "does not satisfy clause",
+        )
+    }
+    #[test]
+    fn model_conflicts_assumptions() {
+        let mut checker = Checker::new();
+        checker
+            .add_formula(&cnf_formula![
+                1, 2;
+                -1, 2;
+            ])
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::Assumptions {
+                assumptions: &lits![-2],
+            })
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::Model {
+                assignment: &lits![1, 2],
+            }),
+            "does not contain assumption",
+        )
+    }
+
+    #[test]
+    fn model_misses_assumption() {
+        let mut checker = Checker::new();
+        checker
+            .add_formula(&cnf_formula![
+                1, 2;
+                -1, 2;
+            ])
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::Assumptions {
+                assumptions: &lits![-3],
+            })
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::Model {
+                assignment: &lits![1, 2],
+            }),
+            "does not contain assumption",
+        )
+    }
+
+    #[test]
+    fn failed_core_with_non_assumed_vars() {
+        let mut checker = Checker::new();
+        checker
+            .add_formula(&cnf_formula![
+                1, 2;
+                -1, 2;
+            ])
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::Assumptions {
+                assumptions: &lits![-2],
+            })
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::FailedAssumptions {
+                failed_core: &lits![-2, -3],
+                propagation_hashes: &[],
+            }),
+            "contains non-assumed variables",
+        )
+    }
+
+    #[test]
+    fn failed_assumptions_with_missing_propagations() {
+        let mut checker = Checker::new();
+        checker
+            .add_formula(&cnf_formula![
+                1, 2;
+                -1, 2;
+                -3, -2;
+            ])
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::Assumptions {
+                assumptions: &lits![3],
+            })
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::FailedAssumptions {
+                failed_core: &lits![3],
+                propagation_hashes: &[],
+            }),
+            "AT check failed",
+        )
+    }
+
+    #[test]
+    fn failed_assumptions_with_conflicting_assumptions() {
+        let mut checker = Checker::new();
+        checker
+            .add_formula(&cnf_formula![
+                1, 2;
+                -1, 2;
+                -3, -2;
+            ])
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::Assumptions {
+                assumptions: &lits![3, -3, 4],
+            })
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::FailedAssumptions {
+                failed_core: &lits![3, -3],
+                propagation_hashes: &[],
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn solve_results() {
+        let mut checker = Checker::new();
+        checker
+            .add_formula(&cnf_formula![
+                1, 2;
+            ])
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::SolveResult {
+                result: SolveResult::Sat,
+            }),
+            "not justified",
+        );
+
+        checker
+            .self_check_step(ProofStep::Model {
+                assignment: &lits![1, 2],
+            })
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::SolveResult {
+                result: SolveResult::Sat,
+            })
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::AddClause { clause: &lits![-1] })
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::SolveResult {
+                result: SolveResult::Sat,
+            }),
+            "not justified",
+        );
+
+        checker
             .self_check_step(ProofStep::Assumptions {
                 assumptions: &lits![-2],
             })
             .unwrap();
 
         expect_check_failed(
-            checker.self_check_step(ProofStep::Model {
-                assignment: &lits![1, 2],
+            checker.self_check_step(ProofStep::SolveResult {
+                result: SolveResult::Unsat,
             }),
-            "does not contain assumption",
+            "not justified",
+        );
+
+        let hashes = [checker.clause_hash(&lits![1, 2])];
+
+        checker
+            .self_check_step(ProofStep::FailedAssumptions {
+                failed_core: &lits![-2],
+                propagation_hashes: &hashes[..],
+            })
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::SolveResult {
+                result: SolveResult::Unsat,
+            })
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::SolveResult {
+                result: SolveResult::Unknown,
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn add_clause_to_non_sampling_var() {
+        let mut checker = Checker::new();
+
+        checker
+            .self_check_step(ProofStep::ChangeSamplingMode {
+                var: Var::from_dimacs(1),
+                sample: false,
+            })
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::AddClause {
+                clause: &lits![1, 2, 3],
+            }),
+            "not a sampling variable",
         )
     }
 
     #[test]
-    fn model_misses_assumption() {
+    fn add_clause_to_hidden_var() {
         let mut checker = Checker::new();
+
         checker
-            .add_formula(&cnf_formula![
-                1, 2;
-                -1, 2;
-            ])
+            .self_check_step(ProofStep::UserVarName {
+                global: Var::from_dimacs(1),
+                user: Some(Var::from_dimacs(1)),
+            })
             .unwrap();
 
         checker
-            .self_check_step(ProofStep::Assumptions {
-                assumptions: &lits![-3],
+            .self_check_step(ProofStep::UserVarName {
+                global: Var::from_dimacs(1),
+                user: None,
             })
             .unwrap();
 
         expect_check_failed(
-            checker.self_check_step(ProofStep::Model {
-                assignment: &lits![1, 2],
+            checker.self_check_step(ProofStep::AddClause {
+                clause: &lits![1, 2, 3],
+            }),
+            "not a sampling variable",
+        )
+    }
+
+    #[test]
+    fn colloding_user_vars() {
+        let mut checker = Checker::new();
+
+        checker
+            .self_check_step(ProofStep::UserVarName {
+                global: Var::from_dimacs(1),
+                user: Some(Var::from_dimacs(1)),
+            })
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::UserVarName {
+                global: Var::from_dimacs(2),
+                user: Some(Var::from_dimacs(1)),
+            }),
+            "used for two different variables",
+        )
+    }
+
+    #[test]
+    fn solver_var_steps() {
+        struct RecordSolverVars(Vec<(Var, Option<Var>, Option<Lit>)>);
+
+        impl ProofProcessor for RecordSolverVars {
+            fn process_step(
+                &mut self,
+                step: &CheckedProofStep,
+                data: CheckerData,
+            ) -> Result<(), ProcessorError> {
+                if let &CheckedProofStep::SolverVar { var, solver_var } = step {
+                    self.0
+                        .push((var, solver_var, data.user_from_proof_lit(!var.positive())));
+                }
+                Ok(())
+            }
+        }
+
+        let mut recorder = RecordSolverVars(vec![]);
+
+        {
+            let mut checker = Checker::new();
+            checker.add_processor(&mut recorder);
+
+            checker
+                .self_check_step(ProofStep::UserVarName {
+                    global: Var::from_dimacs(1),
+                    user: Some(Var::from_dimacs(3)),
+                })
+                .unwrap();
+
+            for &(solver, global) in [(Some(1), 1), (Some(2), 1), (None, 1)].iter() {
+                checker
+                    .self_check_step(ProofStep::SolverVarName {
+                        global: Var::from_dimacs(global),
+                        solver: solver.map(Var::from_dimacs),
+                    })
+                    .unwrap();
+            }
+        }
+
+        let user_lit = Some(Lit::from_dimacs(-3));
+
+        assert_eq!(
+            recorder.0,
+            vec![
+                (Var::from_dimacs(1), Some(Var::from_dimacs(1)), user_lit),
+                (Var::from_dimacs(1), Some(Var::from_dimacs(2)), user_lit),
+                (Var::from_dimacs(1), None, user_lit),
+            ]
+        );
+    }
+
+    #[test]
+    fn observe_without_setting_mode() {
+        let mut checker = Checker::new();
+
+        checker
+            .self_check_step(ProofStep::UserVarName {
+                global: Var::from_dimacs(1),
+                user: Some(Var::from_dimacs(1)),
+            })
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::UserVarName {
+                global: Var::from_dimacs(1),
+                user: None,
+            })
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::UserVarName {
+                global: Var::from_dimacs(1),
+                user: Some(Var::from_dimacs(1)),
+            }),
+            "still hidden",
+        )
+    }
+
+    #[test]
+    fn hide_hidden_var() {
+        let mut checker = Checker::new();
+
+        checker
+            .self_check_step(ProofStep::UserVarName {
+                global: Var::from_dimacs(1),
+                user: Some(Var::from_dimacs(1)),
+            })
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::UserVarName {
+                global: Var::from_dimacs(1),
+                user: None,
+            })
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::UserVarName {
+                global: Var::from_dimacs(1),
+                user: None,
+            }),
+            "no user name to remove",
+        )
+    }
+
+    #[test]
+    fn delete_user_var() {
+        let mut checker = Checker::new();
+
+        checker
+            .self_check_step(ProofStep::UserVarName {
+                global: Var::from_dimacs(1),
+                user: Some(Var::from_dimacs(1)),
+            })
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::DeleteVar {
+                var: Var::from_dimacs(1),
+            }),
+            "corresponds to user variable",
+        )
+    }
+
+    #[test]
+    fn delete_in_use_var() {
+        let mut checker = Checker::new();
+
+        checker
+            .self_check_step(ProofStep::UserVarName {
+                global: Var::from_dimacs(1),
+                user: Some(Var::from_dimacs(1)),
+            })
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::AddClause {
+                clause: &lits![1, 2, 3],
+            })
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::UserVarName {
+                global: Var::from_dimacs(1),
+                user: None,
+            })
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::DeleteVar {
+                var: Var::from_dimacs(1),
+            }),
+            "still has clauses",
+        )
+    }
+
+    #[test]
+    fn invalid_hidden_to_sample() {
+        let mut checker = Checker::new();
+
+        checker
+            .self_check_step(ProofStep::UserVarName {
+                global: Var::from_dimacs(1),
+                user: Some(Var::from_dimacs(1)),
+            })
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::UserVarName {
+                global: Var::from_dimacs(1),
+                user: None,
+            })
+            .unwrap();
+
+        expect_check_failed(
+            checker.self_check_step(ProofStep::ChangeSamplingMode {
+                var: Var::from_dimacs(1),
+                sample: true,
             }),
-            "does not contain assumption",
+            "cannot sample hidden variable",
         )
     }
 
     #[test]
-    fn failed_core_with_non_assumed_vars() {
+    fn online_checking() {
         let mut checker = Checker::new();
+
         checker
             .add_formula(&cnf_formula![
                 1, 2;
@@ -510,266 +2173,400 @@ mod tests {
             ])
             .unwrap();
 
+        let hashes = [
+            checker.clause_hash(&lits![1, 2]),
+            checker.clause_hash(&lits![-1, 2]),
+        ];
+
         checker
-            .self_check_step(ProofStep::Assumptions {
-                assumptions: &lits![-2],
+            .check_step(ProofStep::AtClause {
+                redundant: false,
+                clause: &lits![2],
+                propagation_hashes: &hashes[..],
             })
             .unwrap();
 
-        expect_check_failed(
-            checker.self_check_step(ProofStep::FailedAssumptions {
-                failed_core: &lits![-2, -3],
-                propagation_hashes: &[],
-            }),
-            "contains non-assumed variables",
-        )
+        assert!(!checker.is_unsat());
+
+        checker.add_clause(&lits![-2, 3]).unwrap();
+        checker.add_clause(&lits![-2, -3]).unwrap();
+
+        let hashes = [
+            checker.clause_hash(&lits![-2, 3]),
+            checker.clause_hash(&lits![-2, -3]),
+        ];
+
+        checker
+            .check_step(ProofStep::AtClause {
+                redundant: false,
+                clause: &[],
+                propagation_hashes: &hashes[..],
+            })
+            .unwrap();
+
+        checker.check_step(ProofStep::End).unwrap();
+        checker.check_delayed_steps().unwrap();
+
+        assert!(checker.is_unsat());
     }
 
     #[test]
-    fn failed_assumptions_with_missing_propagations() {
+    fn rat_clauses() {
         let mut checker = Checker::new();
+
         checker
             .add_formula(&cnf_formula![
                 1, 2;
                 -1, 2;
-                -3, -2;
             ])
             .unwrap();
 
         checker
-            .self_check_step(ProofStep::Assumptions {
-                assumptions: &lits![3],
+            .check_step(ProofStep::ChangeSamplingMode {
+                var: Var::from_dimacs(3),
+                sample: false,
+            })
+            .unwrap();
+
+        checker
+            .check_step(ProofStep::RatClause {
+                clause: &lits![3, 1],
+                pivot: lit!(3),
+                resolutions: &[],
+                propagation_hashes: &[],
+            })
+            .unwrap();
+
+        let resolutions = [(checker.clause_hash(&lits![3, 1]), 1)];
+        let hashes = [checker.clause_hash(&lits![1, 2])];
+
+        checker
+            .check_step(ProofStep::RatClause {
+                clause: &lits![-3, 2],
+                pivot: lit!(-3),
+                resolutions: &resolutions[..],
+                propagation_hashes: &hashes[..],
             })
             .unwrap();
 
         expect_check_failed(
-            checker.self_check_step(ProofStep::FailedAssumptions {
-                failed_core: &lits![3],
+            checker.check_step(ProofStep::RatClause {
+                clause: &lits![-3, 1],
+                pivot: lit!(-3),
+                resolutions: &[],
                 propagation_hashes: &[],
             }),
-            "AT check failed",
-        )
+            "not all clauses containing",
+        );
     }
 
     #[test]
-    fn failed_assumptions_with_conflicting_assumptions() {
+    fn extension() {
         let mut checker = Checker::new();
+
         checker
             .add_formula(&cnf_formula![
                 1, 2;
                 -1, 2;
-                -3, -2;
             ])
             .unwrap();
 
         checker
-            .self_check_step(ProofStep::Assumptions {
-                assumptions: &lits![3, -3, 4],
+            .check_step(ProofStep::ChangeSamplingMode {
+                var: Var::from_dimacs(3),
+                sample: false,
             })
             .unwrap();
 
+        expect_check_failed(
+            checker.check_step(ProofStep::Extension {
+                lit: lit!(3),
+                definition: &lits![1, -3],
+            }),
+            "used in its definition",
+        );
+
         checker
-            .self_check_step(ProofStep::FailedAssumptions {
-                failed_core: &lits![3, -3],
-                propagation_hashes: &[],
+            .check_step(ProofStep::Extension {
+                lit: lit!(-3),
+                definition: &lits![1, 2],
             })
             .unwrap();
-    }
 
-    #[test]
-    fn add_clause_to_non_sampling_var() {
-        let mut checker = Checker::new();
+        assert_eq!(checker.stats().clauses, 5);
+
+        let hashes = [checker.clause_hash(&lits![3, 1])];
 
         checker
-            .self_check_step(ProofStep::ChangeSamplingMode {
-                var: Var::from_dimacs(1),
-                sample: false,
+            .check_step(ProofStep::AtClause {
+                redundant: true,
+                clause: &lits![3, 1],
+                propagation_hashes: &hashes[..],
             })
             .unwrap();
 
         expect_check_failed(
-            checker.self_check_step(ProofStep::AddClause {
-                clause: &lits![1, 2, 3],
+            checker.check_step(ProofStep::Extension {
+                lit: lit!(3),
+                definition: &lits![-2],
             }),
-            "not a sampling variable",
-        )
+            "already in use",
+        );
     }
 
     #[test]
-    fn add_clause_to_hidden_var() {
+    fn variable_elimination() {
         let mut checker = Checker::new();
 
         checker
-            .self_check_step(ProofStep::UserVarName {
-                global: Var::from_dimacs(1),
-                user: Some(Var::from_dimacs(1)),
-            })
+            .add_formula(&cnf_formula![
+                1, 3;
+                2, -3;
+            ])
             .unwrap();
 
         checker
-            .self_check_step(ProofStep::UserVarName {
-                global: Var::from_dimacs(1),
-                user: None,
+            .check_step(ProofStep::ChangeSamplingMode {
+                var: Var::from_dimacs(3),
+                sample: false,
             })
             .unwrap();
 
-        expect_check_failed(
-            checker.self_check_step(ProofStep::AddClause {
-                clause: &lits![1, 2, 3],
-            }),
-            "not a sampling variable",
-        )
-    }
-
-    #[test]
-    fn colloding_user_vars() {
-        let mut checker = Checker::new();
+        let hashes = [
+            checker.clause_hash(&lits![1, 3]),
+            checker.clause_hash(&lits![2, -3]),
+        ];
 
         checker
-            .self_check_step(ProofStep::UserVarName {
-                global: Var::from_dimacs(1),
-                user: Some(Var::from_dimacs(1)),
+            .check_step(ProofStep::AtClause {
+                redundant: false,
+                clause: &lits![1, 2],
+                propagation_hashes: &hashes[..],
             })
             .unwrap();
 
+        // The deleted clause may not be used to justify its own deletion
         expect_check_failed(
-            checker.self_check_step(ProofStep::UserVarName {
-                global: Var::from_dimacs(2),
-                user: Some(Var::from_dimacs(1)),
+            checker.check_step(ProofStep::DeleteRatClause {
+                clause: &lits![1, 3],
+                pivot: lit!(3),
+                resolutions: &[(hashes[1], 2)],
+                propagation_hashes: &hashes[..],
             }),
-            "used for two different variables",
-        )
-    }
+            "AT check failed",
+        );
 
-    #[test]
-    fn observe_without_setting_mode() {
-        let mut checker = Checker::new();
+        let resolvent_hash = checker.clause_hash(&lits![1, 2]);
 
         checker
-            .self_check_step(ProofStep::UserVarName {
-                global: Var::from_dimacs(1),
-                user: Some(Var::from_dimacs(1)),
+            .check_step(ProofStep::DeleteRatClause {
+                clause: &lits![1, 3],
+                pivot: lit!(3),
+                resolutions: &[(hashes[1], 1)],
+                propagation_hashes: &[resolvent_hash],
             })
             .unwrap();
 
         checker
-            .self_check_step(ProofStep::UserVarName {
-                global: Var::from_dimacs(1),
-                user: None,
+            .check_step(ProofStep::DeleteRatClause {
+                clause: &lits![2, -3],
+                pivot: lit!(-3),
+                resolutions: &[],
+                propagation_hashes: &[],
             })
             .unwrap();
 
-        expect_check_failed(
-            checker.self_check_step(ProofStep::UserVarName {
-                global: Var::from_dimacs(1),
-                user: Some(Var::from_dimacs(1)),
-            }),
-            "still hidden",
-        )
+        assert_eq!(checker.stats().clauses, 1);
     }
 
     #[test]
-    fn hide_hidden_var() {
+    fn blocked_clauses() {
         let mut checker = Checker::new();
 
         checker
-            .self_check_step(ProofStep::UserVarName {
-                global: Var::from_dimacs(1),
-                user: Some(Var::from_dimacs(1)),
+            .add_formula(&cnf_formula![
+                1, 2;
+                -1, -2;
+                2, 3;
+            ])
+            .unwrap();
+
+        checker
+            .check_step(ProofStep::ChangeSamplingMode {
+                var: Var::from_dimacs(1),
+                sample: false,
             })
             .unwrap();
 
+        let blocking = [checker.clause_hash(&lits![-1, -2])];
+
         checker
-            .self_check_step(ProofStep::UserVarName {
-                global: Var::from_dimacs(1),
-                user: None,
+            .check_step(ProofStep::DeleteBlockedClause {
+                clause: &lits![1, 2],
+                pivot: lit!(1),
+                blocking: &blocking[..],
+            })
+            .unwrap();
+
+        checker
+            .check_step(ProofStep::BlockedClause {
+                clause: &lits![1, 2],
+                pivot: lit!(1),
+                blocking: &blocking[..],
             })
             .unwrap();
 
         expect_check_failed(
-            checker.self_check_step(ProofStep::UserVarName {
-                global: Var::from_dimacs(1),
-                user: None,
+            checker.check_step(ProofStep::BlockedClause {
+                clause: &lits![1, 3],
+                pivot: lit!(1),
+                blocking: &blocking[..],
             }),
-            "no user name to remove",
-        )
+            "AT check failed",
+        );
     }
 
     #[test]
-    fn delete_user_var() {
+    fn rat_clause_on_sampling_var() {
         let mut checker = Checker::new();
 
         checker
-            .self_check_step(ProofStep::UserVarName {
-                global: Var::from_dimacs(1),
-                user: Some(Var::from_dimacs(1)),
-            })
+            .add_formula(&cnf_formula![
+                1, 2;
+            ])
             .unwrap();
 
         expect_check_failed(
-            checker.self_check_step(ProofStep::DeleteVar {
-                var: Var::from_dimacs(1),
+            checker.check_step(ProofStep::RatClause {
+                clause: &lits![3, 1],
+                pivot: lit!(3),
+                resolutions: &[],
+                propagation_hashes: &[],
             }),
-            "corresponds to user variable",
-        )
+            "is a sampling variable",
+        );
     }
 
     #[test]
-    fn delete_in_use_var() {
+    fn rat_clause_with_invalid_resolvent() {
         let mut checker = Checker::new();
 
         checker
-            .self_check_step(ProofStep::UserVarName {
-                global: Var::from_dimacs(1),
-                user: Some(Var::from_dimacs(1)),
-            })
+            .add_formula(&cnf_formula![
+                1, 2;
+            ])
             .unwrap();
 
         checker
-            .self_check_step(ProofStep::AddClause {
-                clause: &lits![1, 2, 3],
+            .check_step(ProofStep::ChangeSamplingMode {
+                var: Var::from_dimacs(3),
+                sample: false,
             })
             .unwrap();
 
         checker
-            .self_check_step(ProofStep::UserVarName {
-                global: Var::from_dimacs(1),
-                user: None,
+            .check_step(ProofStep::RatClause {
+                clause: &lits![3, 1],
+                pivot: lit!(3),
+                resolutions: &[],
+                propagation_hashes: &[],
             })
             .unwrap();
 
+        let resolutions = [(checker.clause_hash(&lits![3, 1]), 0)];
+
         expect_check_failed(
-            checker.self_check_step(ProofStep::DeleteVar {
-                var: Var::from_dimacs(1),
+            checker.check_step(ProofStep::RatClause {
+                clause: &lits![-3, 2],
+                pivot: lit!(-3),
+                resolutions: &resolutions[..],
+                propagation_hashes: &[],
             }),
-            "still has clauses",
-        )
+            "AT check failed",
+        );
     }
 
     #[test]
-    fn invalid_hidden_to_sample() {
+    fn pr_clauses() {
         let mut checker = Checker::new();
 
         checker
-            .self_check_step(ProofStep::UserVarName {
-                global: Var::from_dimacs(1),
-                user: Some(Var::from_dimacs(1)),
-            })
+            .add_formula(&cnf_formula![
+                1, 2;
+                -1, 3;
+                -2, 3;
+            ])
             .unwrap();
 
+        for &var in [1, 3].iter() {
+            checker
+                .check_step(ProofStep::ChangeSamplingMode {
+                    var: Var::from_dimacs(var),
+                    sample: false,
+                })
+                .unwrap();
+        }
+
+        expect_check_failed(
+            checker.check_step(ProofStep::PrClause {
+                clause: &lits![1],
+                witness: &lits![1],
+                reduced_clauses: &[],
+                propagation_hashes: &[],
+            }),
+            "reduced by the witness was not checked",
+        );
+
+        let reduced_clauses = [(checker.clause_hash(&lits![-1, 3]), 0)];
+
+        expect_check_failed(
+            checker.check_step(ProofStep::PrClause {
+                clause: &lits![1],
+                witness: &lits![1],
+                reduced_clauses: &reduced_clauses[..],
+                propagation_hashes: &[],
+            }),
+            "AT check failed",
+        );
+
+        expect_check_failed(
+            checker.check_step(ProofStep::PrClause {
+                clause: &lits![1],
+                witness: &lits![-1, 3],
+                reduced_clauses: &[],
+                propagation_hashes: &[],
+            }),
+            "does not satisfy clause",
+        );
+
         checker
-            .self_check_step(ProofStep::UserVarName {
-                global: Var::from_dimacs(1),
-                user: None,
+            .check_step(ProofStep::PrClause {
+                clause: &lits![1],
+                witness: &lits![1, 3],
+                reduced_clauses: &[],
+                propagation_hashes: &[],
             })
             .unwrap();
+    }
+
+    #[test]
+    fn pr_clause_with_sampling_witness() {
+        let mut checker = Checker::new();
+
+        checker
+            .add_formula(&cnf_formula![
+                1, 2;
+            ])
+            .unwrap();
 
         expect_check_failed(
-            checker.self_check_step(ProofStep::ChangeSamplingMode {
-                var: Var::from_dimacs(1),
-                sample: true,
+            checker.check_step(ProofStep::PrClause {
+                clause: &lits![1],
+                witness: &lits![1],
+                reduced_clauses: &[],
+                propagation_hashes: &[],
             }),
-            "cannot sample hidden variable",
-        )
+            "is a sampling variable",
+        );
     }
 }
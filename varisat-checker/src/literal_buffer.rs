@@ -0,0 +1,193 @@
+//! Literal storage that can be moved to disk.
+#[cfg(feature = "disk-spill")]
+use std::fs::File;
+use std::io;
+#[cfg(feature = "disk-spill")]
+use std::mem::size_of;
+use std::ops::Deref;
+#[cfg(feature = "disk-spill")]
+use std::slice;
+
+#[cfg(feature = "disk-spill")]
+use memmap::{MmapMut, MmapOptions};
+
+use varisat_formula::Lit;
+
+/// Backing storage of a [`LiteralBuffer`].
+enum Storage {
+    /// Literals stored on the heap.
+    Memory(Vec<Lit>),
+    /// Literals stored in a memory mapped temporary file.
+    #[cfg(feature = "disk-spill")]
+    Mapped {
+        file: File,
+        map: MmapMut,
+        /// Number of literals in use.
+        len: usize,
+        /// Number of literals the file can hold.
+        capacity: usize,
+    },
+}
+
+/// Storage for the literals of long clauses.
+///
+/// The literals are kept in memory until [`spill`](LiteralBuffer::spill) is called. After that
+/// they are stored in a memory mapped temporary file, so that the operating system can page them
+/// out when memory is scarce. Moving the literals to disk requires the `disk-spill` feature.
+pub struct LiteralBuffer {
+    storage: Storage,
+}
+
+impl Default for LiteralBuffer {
+    fn default() -> LiteralBuffer {
+        LiteralBuffer {
+            storage: Storage::Memory(vec![]),
+        }
+    }
+}
+
+impl Deref for LiteralBuffer {
+    type Target = [Lit];
+
+    fn deref(&self) -> &[Lit] {
+        match &self.storage {
+            Storage::Memory(lits) => lits,
+            #[cfg(feature = "disk-spill")]
+            Storage::Mapped { map, len, .. } => unsafe {
+                // Lit is a repr(transparent) wrapper of LitIdx, the mapping is page aligned and
+                // holds at least len literals.
+                slice::from_raw_parts(map.as_ptr() as *const Lit, *len)
+            },
+        }
+    }
+}
+
+impl LiteralBuffer {
+    /// Whether the literals are stored on disk.
+    pub fn is_spilled(&self) -> bool {
+        match self.storage {
+            Storage::Memory(_) => false,
+            #[cfg(feature = "disk-spill")]
+            Storage::Mapped { .. } => true,
+        }
+    }
+
+    /// An empty buffer using the same kind of storage.
+    pub fn empty_like(&self, capacity: usize) -> io::Result<LiteralBuffer> {
+        match self.storage {
+            Storage::Memory(_) => Ok(LiteralBuffer {
+                storage: Storage::Memory(Vec::with_capacity(capacity)),
+            }),
+            #[cfg(feature = "disk-spill")]
+            Storage::Mapped { .. } => LiteralBuffer::mapped(&[], capacity),
+        }
+    }
+
+    /// Move the literals into a memory mapped temporary file.
+    ///
+    /// Does nothing if the literals are already stored on disk.
+    #[cfg(feature = "disk-spill")]
+    pub fn spill(&mut self) -> io::Result<()> {
+        if let Storage::Memory(lits) = &self.storage {
+            *self = LiteralBuffer::mapped(lits, lits.len())?;
+        }
+        Ok(())
+    }
+
+    /// Move the literals into a memory mapped temporary file.
+    ///
+    /// Always fails as this build doesn't include the `disk-spill` feature.
+    #[cfg(not(feature = "disk-spill"))]
+    pub fn spill(&mut self) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "the checker was built without the disk-spill feature",
+        ))
+    }
+
+    /// Append literals to the buffer.
+    ///
+    /// Fails if the on-disk storage cannot be grown.
+    pub fn extend_from_slice(&mut self, lits: &[Lit]) -> io::Result<()> {
+        match &mut self.storage {
+            Storage::Memory(buffer) => buffer.extend_from_slice(lits),
+            #[cfg(feature = "disk-spill")]
+            Storage::Mapped {
+                file,
+                map,
+                len,
+                capacity,
+            } => {
+                let new_len = *len + lits.len();
+                if new_len > *capacity {
+                    let new_capacity = new_len.max(*capacity * 2);
+                    *map = grow_mapping(file, new_capacity)?;
+                    *capacity = new_capacity;
+                }
+                let target = unsafe {
+                    // See deref
+                    slice::from_raw_parts_mut(map.as_mut_ptr() as *mut Lit, *capacity)
+                };
+                target[*len..new_len].copy_from_slice(lits);
+                *len = new_len;
+            }
+        }
+        Ok(())
+    }
+
+    /// Create a buffer backed by a temporary file containing the given literals.
+    #[cfg(feature = "disk-spill")]
+    fn mapped(lits: &[Lit], capacity: usize) -> io::Result<LiteralBuffer> {
+        let file = tempfile::tempfile()?;
+        let capacity = capacity.max(lits.len()).max(1);
+        let map = grow_mapping(&file, capacity)?;
+        let mut buffer = LiteralBuffer {
+            storage: Storage::Mapped {
+                file,
+                map,
+                len: 0,
+                capacity,
+            },
+        };
+        buffer.extend_from_slice(lits)?;
+        Ok(buffer)
+    }
+}
+
+/// Resize a file to hold `capacity` literals and map it into memory.
+#[cfg(feature = "disk-spill")]
+fn grow_mapping(file: &File, capacity: usize) -> io::Result<MmapMut> {
+    file.set_len((capacity * size_of::<Lit>()) as u64)?;
+    unsafe {
+        // The file is an unlinked temporary file, so nobody else can modify it.
+        MmapOptions::new().map_mut(file)
+    }
+}
+
+#[cfg(all(test, feature = "disk-spill"))]
+mod tests {
+    use super::*;
+
+    use varisat_formula::lits;
+
+    #[test]
+    fn spill_and_grow() {
+        let mut buffer = LiteralBuffer::default();
+        buffer.extend_from_slice(&lits![1, -2, 3]).unwrap();
+        assert!(!buffer.is_spilled());
+
+        buffer.spill().unwrap();
+        assert!(buffer.is_spilled());
+        assert_eq!(&buffer[..], &lits![1, -2, 3][..]);
+
+        for _ in 0..1000 {
+            buffer.extend_from_slice(&lits![4, -5]).unwrap();
+        }
+        assert_eq!(buffer.len(), 2003);
+        assert_eq!(&buffer[2001..], &lits![4, -5][..]);
+
+        let empty = buffer.empty_like(10).unwrap();
+        assert!(empty.is_spilled());
+        assert!(empty.is_empty());
+    }
+}
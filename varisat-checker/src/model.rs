@@ -0,0 +1,166 @@
+//! Checking of satisfying assignments.
+use std::io::{self, BufRead};
+
+use hashbrown::HashSet;
+use partial_ref::{partial, PartialRef};
+use thiserror::Error;
+
+use varisat_formula::{Lit, Var};
+
+use crate::clauses::UnitId;
+use crate::context::{parts::*, Context};
+use crate::CheckerError;
+
+/// Check that an assignment satisfies all irredundant clauses added so far.
+///
+/// If multiple clauses are violated, the one with the smallest clause id is reported.
+pub fn check_model(
+    ctx: partial!(Context, CheckerStateP, ClausesP),
+    model: &[Lit],
+) -> Result<(), CheckerError> {
+    let step = ctx.part(CheckerStateP).step;
+
+    if ctx.part(CheckerStateP).unsat {
+        return Err(CheckerError::check_failed(
+            step,
+            "formula contains conflicting unit clauses".to_string(),
+        ));
+    }
+
+    let mut assignments = HashSet::new();
+
+    for &lit in model.iter() {
+        if assignments.contains(&!lit) {
+            return Err(CheckerError::check_failed(
+                step,
+                format!("model contains conflicting assignment {:?}", !lit),
+            ));
+        }
+        assignments.insert(lit);
+    }
+
+    let clauses = ctx.part(ClausesP);
+
+    let mut first_violated: Option<(u64, Vec<Lit>)> = None;
+
+    let mut violated = |id: u64, lits: &[Lit]| {
+        if first_violated
+            .as_ref()
+            .map_or(true, |&(first_id, _)| id < first_id)
+        {
+            first_violated = Some((id, lits.to_vec()));
+        }
+    };
+
+    for (index, unit_clause) in clauses.unit_clauses.iter().enumerate() {
+        if let Some(unit_clause) = unit_clause {
+            let lit = Var::from_index(index).lit(unit_clause.value);
+            if !assignments.contains(&lit) {
+                let id = match unit_clause.id {
+                    UnitId::Global(id) => id,
+                    _ => u64::max_value(),
+                };
+                violated(id, &[lit]);
+            }
+        }
+    }
+
+    for (_, candidates) in clauses.clauses.iter() {
+        for clause in candidates.iter() {
+            if !clause.is_irredundant() {
+                continue;
+            }
+            let lits = clause.lits.slice(&clauses.literal_buffer);
+            if !lits.iter().any(|lit| assignments.contains(lit)) {
+                violated(clause.id, lits);
+            }
+        }
+    }
+
+    match first_violated {
+        Some((id, lits)) => Err(CheckerError::check_failed(
+            step,
+            format!("model does not satisfy clause {} {:?}", id, lits),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Errors while reading a model.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ModelError {
+    #[error("solution is not satisfiable: {line:?}")]
+    NotSatisfiable { line: String },
+    #[error("invalid literal {value:?} in model")]
+    InvalidLiteral { value: String },
+    #[error("literal {lit} in model is out of range")]
+    LiteralOutOfRange { lit: isize },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Read an assignment in the solution format of the SAT competition.
+///
+/// Lines starting with `v` contain the assignment as DIMACS literals, terminated by `0`. Comment
+/// and status lines are skipped. For convenience the `v` prefix may be omitted.
+pub fn read_model(input: impl io::Read) -> Result<Vec<Lit>, ModelError> {
+    let mut model = vec![];
+
+    for line in io::BufReader::new(input).lines() {
+        let line = line?;
+        let line = line.trim();
+
+        let values = if line.starts_with('v') {
+            &line[1..]
+        } else if line.starts_with('s') {
+            if line != "s SATISFIABLE" {
+                return Err(ModelError::NotSatisfiable {
+                    line: line.to_owned(),
+                });
+            }
+            continue;
+        } else if line.starts_with('c') {
+            continue;
+        } else {
+            line
+        };
+
+        for value in values.split_whitespace() {
+            let dimacs: isize = value.parse().map_err(|_| ModelError::InvalidLiteral {
+                value: value.to_owned(),
+            })?;
+            if dimacs == 0 {
+                continue;
+            }
+            if dimacs.abs() > Var::max_count() as isize {
+                return Err(ModelError::LiteralOutOfRange { lit: dimacs });
+            }
+            model.push(Lit::from_dimacs(dimacs));
+        }
+    }
+
+    Ok(model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::lits;
+
+    #[test]
+    fn parse_solution() {
+        let model = read_model(&b"c comment\ns SATISFIABLE\nv 1 -2\nv 3 0\n"[..]).unwrap();
+        assert_eq!(model, lits![1, -2, 3]);
+
+        assert!(matches!(
+            read_model(&b"s UNSATISFIABLE\n"[..]),
+            Err(ModelError::NotSatisfiable { .. })
+        ));
+        assert!(matches!(
+            read_model(&b"v 1 x 0\n"[..]),
+            Err(ModelError::InvalidLiteral { .. })
+        ));
+    }
+}
@@ -1,7 +1,8 @@
 //! Processing of checked proof steps.
+use std::error::Error;
+
 use partial_ref::{partial, PartialRef};
 
-use failure::Error;
 use varisat_formula::{Lit, Var};
 
 use crate::context::{parts::*, Context};
@@ -20,6 +21,12 @@ pub enum CheckedProofStep<'a> {
         var: Var,
         user_var: Option<CheckedUserVar>,
     },
+    /// Updates the solver variable corresponding to a proof variable.
+    ///
+    /// The solver may rename or compact its internal variables. Together with the `UserVar` steps
+    /// this allows processors to translate between solver, proof and user variables. A `solver_var`
+    /// of `None` means the proof variable is no longer present in the solver.
+    SolverVar { var: Var, solver_var: Option<Var> },
     /// A clause of the input formula.
     AddClause { id: u64, clause: &'a [Lit] },
     /// A duplicated clause of the input formula.
@@ -50,6 +57,37 @@ pub enum CheckedProofStep<'a> {
         clause: &'a [Lit],
         propagations: &'a [u64],
     },
+    /// Addition of a resolution asymmetric tautology (RAT) on the given pivot.
+    ///
+    /// A clause C containing the pivot p is a RAT wrt. a formula F, iff for all clauses D in F
+    /// that contain the negated pivot, the resolvent of C and D on p is a tautology or an AT. The
+    /// `propagations` field contains the ids of all such clauses D, each together with the
+    /// propagations that show that the resolvent is an AT. The propagations of a tautological
+    /// resolvent are empty.
+    ///
+    /// RAT clauses are always irredundant.
+    RatClause {
+        id: u64,
+        clause: &'a [Lit],
+        pivot: Lit,
+        propagations: &'a ResolutionPropagations,
+    },
+    /// Addition of a propagation redundant (PR) clause using the given witness.
+    ///
+    /// The witness is a consistent set of literals satisfying the clause. The `propagations` field
+    /// contains the ids of all clauses that contain a negated witness literal without being
+    /// satisfied by the witness. Each id is paired with the propagations showing that the clause,
+    /// reduced by removing the literals falsified by the witness, is implied by unit propagation
+    /// when all literals of the PR clause are set to false. The propagations are empty if the
+    /// reduced clause contains the negation of a literal of the PR clause.
+    ///
+    /// PR clauses are always irredundant.
+    PrClause {
+        id: u64,
+        clause: &'a [Lit],
+        witness: &'a [Lit],
+        propagations: &'a ResolutionPropagations,
+    },
     /// Deletion of a redundant clause.
     DeleteClause { id: u64, clause: &'a [Lit] },
     /// Deletion of a clause that is an asymmetric tautology w.r.t the remaining irredundant
@@ -102,14 +140,50 @@ pub struct CheckedUserVar {
 }
 
 /// A list of clauses to resolve and propagations to show that the resolvent is an AT.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ResolutionPropagations {
-    // TODO implement ResolutionPropagations
+    /// Ids of the resolved clauses and the end of their propagations in `propagations`.
+    resolutions: Vec<(u64, usize)>,
+    propagations: Vec<u64>,
+}
+
+impl ResolutionPropagations {
+    /// Remove all resolutions.
+    pub(crate) fn clear(&mut self) {
+        self.resolutions.clear();
+        self.propagations.clear();
+    }
+
+    /// Add a resolved clause and the propagations showing that the resolvent is an AT.
+    pub(crate) fn push(&mut self, id: u64, propagations: &[u64]) {
+        self.propagations.extend_from_slice(propagations);
+        self.resolutions.push((id, self.propagations.len()));
+    }
+
+    /// Number of resolved clauses.
+    pub fn len(&self) -> usize {
+        self.resolutions.len()
+    }
+
+    /// Whether there are no resolved clauses.
+    pub fn is_empty(&self) -> bool {
+        self.resolutions.is_empty()
+    }
+
+    /// Iterate over the ids of the resolved clauses together with their propagations.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &[u64])> {
+        let mut begin = 0;
+        self.resolutions.iter().map(move |&(id, end)| {
+            let propagations = &self.propagations[begin..end];
+            begin = end;
+            (id, propagations)
+        })
+    }
 }
 
 /// Checker data available to proof processors.
 #[derive(Copy, Clone)]
-pub struct CheckerData<'a, 'b>(pub partial!('a Context<'b>, VariablesP));
+pub struct CheckerData<'a, 'b>(pub partial!('a Context<'b>, VariablesP), u64, u64);
 
 impl<'a, 'b> CheckerData<'a, 'b> {
     /// User variable corresponding to proof variable.
@@ -133,32 +207,223 @@ impl<'a, 'b> CheckerData<'a, 'b> {
                 })
             })
     }
+
+    /// Number of the proof step that caused the processed step.
+    ///
+    /// Proof steps are numbered starting with 1. This is 0 for clauses added directly using
+    /// [`add_clause`](crate::Checker::add_clause) or similar methods.
+    pub fn proof_step(self) -> u64 {
+        self.1
+    }
+
+    /// Number of literals propagated while checking clauses so far.
+    ///
+    /// The difference between two processed steps is the work spent on the checks in between.
+    pub fn propagations(self) -> u64 {
+        self.2
+    }
+
+    /// User literal corresponding to proof literal.
+    ///
+    /// Returns `None` if the literal's variable is an internal or hidden variable.
+    pub fn user_from_proof_lit(self, proof_lit: Lit) -> Option<Lit> {
+        self.user_from_proof_var(proof_lit.var())
+            .map(|user_var| user_var.lit(proof_lit.is_positive()))
+    }
+}
+
+/// Outcome of a proof check, passed to [`ProofProcessor::end_of_proof`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ProofVerdict {
+    /// All steps were checked and the empty clause was derived.
+    Unsat,
+    /// All steps were checked, but the empty clause was not derived.
+    NotUnsat,
+    /// Checking failed or was aborted.
+    Failed,
+}
+
+/// Kinds of proof steps a [`ProofProcessor`] wants to receive.
+///
+/// Returned by [`ProofProcessor::step_filter`]. Steps of a kind that is not selected are not
+/// passed to the processor. When no registered processor selects `hints`, the checker skips
+/// computing the clauses used by AT checks, and the `propagations` of the passed steps are empty.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct StepFilter {
+    /// Input clauses, including duplicated and tautological ones.
+    pub inputs: bool,
+    /// Clauses added by the proof (AT, RAT and PR steps) and clauses made irredundant.
+    pub lemmas: bool,
+    /// Deleted clauses.
+    pub deletions: bool,
+    /// Propagations justifying added or deleted clauses and failed assumptions.
+    pub hints: bool,
+    /// All remaining steps (variables, models, assumptions and failed assumptions).
+    pub other: bool,
+}
+
+impl Default for StepFilter {
+    fn default() -> StepFilter {
+        StepFilter::all()
+    }
+}
+
+impl StepFilter {
+    /// Select all steps including hints.
+    pub fn all() -> StepFilter {
+        StepFilter {
+            inputs: true,
+            lemmas: true,
+            deletions: true,
+            hints: true,
+            other: true,
+        }
+    }
+
+    /// Select no steps.
+    pub fn none() -> StepFilter {
+        StepFilter {
+            inputs: false,
+            lemmas: false,
+            deletions: false,
+            hints: false,
+            other: false,
+        }
+    }
+
+    /// Combine two filters, selecting all steps selected by either.
+    pub fn union(self, other: StepFilter) -> StepFilter {
+        StepFilter {
+            inputs: self.inputs || other.inputs,
+            lemmas: self.lemmas || other.lemmas,
+            deletions: self.deletions || other.deletions,
+            hints: self.hints || other.hints,
+            other: self.other || other.other,
+        }
+    }
+
+    /// Whether a step is selected by this filter.
+    pub fn accepts(&self, step: &CheckedProofStep) -> bool {
+        match step {
+            CheckedProofStep::AddClause { .. }
+            | CheckedProofStep::DuplicatedClause { .. }
+            | CheckedProofStep::TautologicalClause { .. } => self.inputs,
+            CheckedProofStep::AtClause { .. }
+            | CheckedProofStep::RatClause { .. }
+            | CheckedProofStep::PrClause { .. }
+            | CheckedProofStep::MakeIrredundant { .. } => self.lemmas,
+            CheckedProofStep::DeleteClause { .. }
+            | CheckedProofStep::DeleteAtClause { .. }
+            | CheckedProofStep::DeleteRatClause { .. } => self.deletions,
+            CheckedProofStep::UserVar { .. }
+            | CheckedProofStep::SolverVar { .. }
+            | CheckedProofStep::Model { .. }
+            | CheckedProofStep::Assumptions { .. }
+            | CheckedProofStep::FailedAssumptions { .. } => self.other,
+        }
+    }
 }
 
+/// Error returned by a [`ProofProcessor`] or a
+/// [`ProofTranscriptProcessor`](crate::ProofTranscriptProcessor).
+///
+/// Processors can fail with any error type, which is reported as
+/// [`CheckerError::ProofProcessorError`]. The processors of this crate return typed errors like
+/// [`TraceCheckError`](crate::TraceCheckError) that can be recovered using `downcast_ref`.
+pub type ProcessorError = Box<dyn Error + Send + Sync>;
+
 /// Implement to process proof steps.
 pub trait ProofProcessor {
-    fn process_step(&mut self, step: &CheckedProofStep, data: CheckerData) -> Result<(), Error>;
+    fn process_step(
+        &mut self,
+        step: &CheckedProofStep,
+        data: CheckerData,
+    ) -> Result<(), ProcessorError>;
+
+    /// Kinds of steps passed to this processor.
+    ///
+    /// This is queried once when the processor is added to a checker. The default selects all
+    /// steps including hints.
+    fn step_filter(&self) -> StepFilter {
+        StepFilter::all()
+    }
+
+    /// Called once when checking the proof finished.
+    ///
+    /// This is not called when checking stopped because a proof processor returned an error.
+    fn end_of_proof(&mut self, _verdict: ProofVerdict) -> Result<(), ProcessorError> {
+        Ok(())
+    }
+}
+
+impl<'a, P: ProofProcessor + ?Sized> ProofProcessor for &'a mut P {
+    fn process_step(
+        &mut self,
+        step: &CheckedProofStep,
+        data: CheckerData,
+    ) -> Result<(), ProcessorError> {
+        (**self).process_step(step, data)
+    }
+
+    fn step_filter(&self) -> StepFilter {
+        (**self).step_filter()
+    }
+
+    fn end_of_proof(&mut self, verdict: ProofVerdict) -> Result<(), ProcessorError> {
+        (**self).end_of_proof(verdict)
+    }
 }
 
 /// Registry of proof and transcript processors.
 #[derive(Default)]
 pub struct Processing<'a> {
     /// Registered proof processors.
-    pub processors: Vec<&'a mut dyn ProofProcessor>,
+    ///
+    /// Borrowed processors are stored as boxed references.
+    ///
+    /// Use [`add_processor`](Processing::add_processor) to register a processor.
+    pub processors: Vec<Box<dyn ProofProcessor + 'a>>,
+    /// Step filter of each registered proof processor.
+    filters: Vec<StepFilter>,
+    /// Union of all filters of registered proof processors.
+    combined_filter: Option<StepFilter>,
     /// Registered transcript processors.
     pub transcript_processors: Vec<&'a mut dyn ProofTranscriptProcessor>,
     /// Proof step to transcript step conversion.
     transcript: transcript::Transcript,
+    /// Number of the proof step currently checked, see [`CheckerData::proof_step`].
+    pub proof_step: u64,
+    /// Number of propagated literals, see [`CheckerData::propagations`].
+    pub propagations: u64,
 }
 
 impl<'a> Processing<'a> {
+    /// Register a proof processor.
+    pub fn add_processor(&mut self, processor: Box<dyn ProofProcessor + 'a>) {
+        let filter = processor.step_filter();
+        self.combined_filter = Some(match self.combined_filter {
+            Some(combined) => combined.union(filter),
+            None => filter,
+        });
+        self.filters.push(filter);
+        self.processors.push(processor);
+    }
+
+    /// Whether any registered proof processor needs the propagations of AT checks.
+    pub fn needs_hints(&self) -> bool {
+        self.combined_filter.map_or(false, |filter| filter.hints)
+    }
+
     /// Process a single step
     pub fn step<'b>(
         &mut self,
         step: &CheckedProofStep<'b>,
         data: CheckerData,
     ) -> Result<(), CheckerError> {
-        for processor in self.processors.iter_mut() {
+        for (processor, filter) in self.processors.iter_mut().zip(self.filters.iter()) {
+            if !filter.accepts(step) {
+                continue;
+            }
             if let Err(err) = processor.process_step(step, data) {
                 return Err(CheckerError::ProofProcessorError { cause: err });
             }
@@ -175,6 +440,16 @@ impl<'a> Processing<'a> {
 
         Ok(())
     }
+
+    /// Notify all proof processors that checking finished.
+    pub fn end_of_proof(&mut self, verdict: ProofVerdict) -> Result<(), CheckerError> {
+        for processor in self.processors.iter_mut() {
+            if let Err(err) = processor.end_of_proof(verdict) {
+                return Err(CheckerError::ProofProcessorError { cause: err });
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Process a single step
@@ -183,5 +458,7 @@ pub fn process_step<'a, 'b>(
     step: &CheckedProofStep<'b>,
 ) -> Result<(), CheckerError> {
     let (processing, mut ctx) = ctx.split_part_mut(ProcessingP);
-    processing.step(step, CheckerData(ctx.borrow()))
+    let proof_step = processing.proof_step;
+    let propagations = processing.propagations;
+    processing.step(step, CheckerData(ctx.borrow(), proof_step, propagations))
 }
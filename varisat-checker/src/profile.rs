@@ -0,0 +1,296 @@
+//! Profiling of proof checking.
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crate::processing::{
+    CheckedProofStep, CheckerData, ProcessorError, ProofProcessor, StepFilter,
+};
+
+/// Number of most expensive steps recorded by default.
+const DEFAULT_HOTSPOTS: usize = 10;
+
+/// Time and work spent on a kind of checked proof step.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct StepKindProfile {
+    /// Number of steps of this kind.
+    pub count: u64,
+    /// Total time spent on steps of this kind.
+    pub time: Duration,
+    /// Total number of literals propagated for steps of this kind.
+    pub propagations: u64,
+    /// Maximal number of literals propagated for a single step of this kind.
+    pub max_propagations: u64,
+}
+
+/// A single expensive step.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Hotspot {
+    /// Time spent on the step.
+    pub time: Duration,
+    /// Number of the proof step that caused this step, see [`CheckerData::proof_step`].
+    pub proof_step: u64,
+    /// Name of the step kind.
+    pub kind: &'static str,
+    /// Id of the clause added or deleted by the step, if any.
+    pub id: Option<u64>,
+    /// Number of literals propagated for the step.
+    pub propagations: u64,
+}
+
+/// Proof processor that records where the time of a proof check is spent.
+///
+/// The time of a step is measured from the previous processed step, so it includes parsing and
+/// checking the step as well as the time spent in other proof processors. The first step is
+/// assigned no time. To avoid slowing down the check, hints are not requested, so adding only a
+/// profiler doesn't require computing the propagations used by AT checks.
+pub struct StepProfiler {
+    kinds: Vec<(&'static str, StepKindProfile)>,
+    hotspots: BinaryHeap<Reverse<Hotspot>>,
+    max_hotspots: usize,
+    last_time: Option<Instant>,
+    last_propagations: u64,
+}
+
+impl Default for StepProfiler {
+    fn default() -> StepProfiler {
+        StepProfiler::new()
+    }
+}
+
+impl StepProfiler {
+    /// Create a profiler recording the 10 most expensive steps.
+    pub fn new() -> StepProfiler {
+        StepProfiler::with_hotspots(DEFAULT_HOTSPOTS)
+    }
+
+    /// Create a profiler recording the given number of most expensive steps.
+    pub fn with_hotspots(max_hotspots: usize) -> StepProfiler {
+        StepProfiler {
+            kinds: vec![],
+            hotspots: BinaryHeap::new(),
+            max_hotspots,
+            last_time: None,
+            last_propagations: 0,
+        }
+    }
+
+    /// Profile of each step kind that occurred, in order of first occurrence.
+    pub fn kinds(&self) -> &[(&'static str, StepKindProfile)] {
+        &self.kinds
+    }
+
+    /// Most expensive steps, most expensive first.
+    pub fn hotspots(&self) -> Vec<Hotspot> {
+        let mut hotspots: Vec<_> = self.hotspots.iter().map(|hotspot| hotspot.0).collect();
+        hotspots.sort_by(|a, b| b.cmp(a));
+        hotspots
+    }
+
+    /// Total time of all profiled steps.
+    pub fn total_time(&self) -> Duration {
+        self.kinds.iter().map(|(_, kind)| kind.time).sum()
+    }
+
+    /// Write a human readable summary of the profile.
+    pub fn write_report(&self, mut target: impl Write) -> io::Result<()> {
+        writeln!(
+            target,
+            "{:<20} {:>12} {:>12} {:>14} {:>10}",
+            "step", "count", "time (ms)", "propagations", "max prop."
+        )?;
+        for (name, kind) in self.kinds.iter() {
+            writeln!(
+                target,
+                "{:<20} {:>12} {:>12.3} {:>14} {:>10}",
+                name,
+                kind.count,
+                millis(kind.time),
+                kind.propagations,
+                kind.max_propagations
+            )?;
+        }
+        writeln!(target, "total time: {:.3} ms", millis(self.total_time()))?;
+
+        let hotspots = self.hotspots();
+        if !hotspots.is_empty() {
+            writeln!(target, "most expensive steps:")?;
+            for hotspot in hotspots {
+                write!(
+                    target,
+                    "  {:.3} ms for {} in proof step {}",
+                    millis(hotspot.time),
+                    hotspot.kind,
+                    hotspot.proof_step
+                )?;
+                if let Some(id) = hotspot.id {
+                    write!(target, " (clause {})", id + 1)?;
+                }
+                writeln!(target, ", {} propagations", hotspot.propagations)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a step to the profile of its kind.
+    fn record_kind(&mut self, name: &'static str, time: Duration, propagations: u64) {
+        let index = match self.kinds.iter().position(|&(kind, _)| kind == name) {
+            Some(index) => index,
+            None => {
+                self.kinds.push((name, StepKindProfile::default()));
+                self.kinds.len() - 1
+            }
+        };
+        let kind = &mut self.kinds[index].1;
+        kind.count += 1;
+        kind.time += time;
+        kind.propagations += propagations;
+        kind.max_propagations = kind.max_propagations.max(propagations);
+    }
+
+    /// Keep a step if it is among the most expensive steps.
+    fn record_hotspot(&mut self, hotspot: Hotspot) {
+        if self.max_hotspots == 0 {
+            return;
+        }
+        if self.hotspots.len() < self.max_hotspots {
+            self.hotspots.push(Reverse(hotspot));
+        } else if let Some(mut cheapest) = self.hotspots.peek_mut() {
+            if cheapest.0 < hotspot {
+                *cheapest = Reverse(hotspot);
+            }
+        }
+    }
+}
+
+impl ProofProcessor for StepProfiler {
+    fn process_step(
+        &mut self,
+        step: &CheckedProofStep,
+        data: CheckerData,
+    ) -> Result<(), ProcessorError> {
+        let now = Instant::now();
+        let time = self
+            .last_time
+            .map_or(Duration::from_secs(0), |last| now.duration_since(last));
+        self.last_time = Some(now);
+
+        let propagations = data.propagations() - self.last_propagations;
+        self.last_propagations = data.propagations();
+
+        let (name, id) = step_kind(step);
+
+        self.record_kind(name, time, propagations);
+        self.record_hotspot(Hotspot {
+            time,
+            proof_step: data.proof_step(),
+            kind: name,
+            id,
+            propagations,
+        });
+
+        Ok(())
+    }
+
+    fn step_filter(&self) -> StepFilter {
+        StepFilter {
+            hints: false,
+            ..StepFilter::all()
+        }
+    }
+}
+
+/// Name of a step's kind and the id of the affected clause.
+fn step_kind(step: &CheckedProofStep) -> (&'static str, Option<u64>) {
+    match *step {
+        CheckedProofStep::UserVar { .. } => ("UserVar", None),
+        CheckedProofStep::SolverVar { .. } => ("SolverVar", None),
+        CheckedProofStep::AddClause { id, .. } => ("AddClause", Some(id)),
+        CheckedProofStep::DuplicatedClause { id, .. } => ("DuplicatedClause", Some(id)),
+        CheckedProofStep::TautologicalClause { id, .. } => ("TautologicalClause", Some(id)),
+        CheckedProofStep::AtClause { id, .. } => ("AtClause", Some(id)),
+        CheckedProofStep::RatClause { id, .. } => ("RatClause", Some(id)),
+        CheckedProofStep::PrClause { id, .. } => ("PrClause", Some(id)),
+        CheckedProofStep::DeleteClause { id, .. } => ("DeleteClause", Some(id)),
+        CheckedProofStep::DeleteAtClause { id, .. } => ("DeleteAtClause", Some(id)),
+        CheckedProofStep::DeleteRatClause { id, .. } => ("DeleteRatClause", Some(id)),
+        CheckedProofStep::MakeIrredundant { id, .. } => ("MakeIrredundant", Some(id)),
+        CheckedProofStep::Model { .. } => ("Model", None),
+        CheckedProofStep::Assumptions { .. } => ("Assumptions", None),
+        CheckedProofStep::FailedAssumptions { .. } => ("FailedAssumptions", None),
+    }
+}
+
+/// Duration in fractional milliseconds.
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs() as f64 * 1e3 + f64::from(duration.subsec_nanos()) * 1e-6
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::{cnf_formula, lits};
+
+    use crate::{Checker, ProofStep};
+
+    #[test]
+    fn profile_steps() {
+        let mut profiler = StepProfiler::with_hotspots(2);
+
+        {
+            let mut checker = Checker::new();
+            checker.add_processor(&mut profiler);
+
+            checker
+                .add_formula(&cnf_formula![
+                    1, 2, 3;
+                    -1, 2, 3;
+                    -2, 3;
+                    -3;
+                ])
+                .unwrap();
+
+            let hashes = [
+                checker.clause_hash(&lits![1, 2, 3]),
+                checker.clause_hash(&lits![-1, 2, 3]),
+            ];
+
+            let mut proof = vec![];
+            varisat_internal_proof::binary_format::write_header(&mut proof, &[]).unwrap();
+            for step in [
+                ProofStep::AtClause {
+                    redundant: true,
+                    clause: &lits![2, 3],
+                    propagation_hashes: &hashes[..],
+                },
+                ProofStep::End,
+            ]
+            .iter()
+            {
+                varisat_internal_proof::binary_format::write_step(&mut proof, step).unwrap();
+            }
+            checker.check_proof(&proof[..]).unwrap();
+        }
+
+        let kinds = profiler.kinds();
+        assert_eq!(kinds[0].0, "AddClause");
+        assert_eq!(kinds[0].1.count, 4);
+
+        let at_clause = kinds.iter().find(|(name, _)| *name == "AtClause").unwrap();
+        assert_eq!(at_clause.1.count, 1);
+        assert_eq!(at_clause.1.propagations, 1);
+        assert_eq!(at_clause.1.max_propagations, 1);
+
+        let hotspots = profiler.hotspots();
+        assert_eq!(hotspots.len(), 2);
+        assert!(hotspots[0].time >= hotspots[1].time);
+
+        let mut report = vec![];
+        profiler.write_report(&mut report).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(report.contains("AtClause"));
+        assert!(report.contains("most expensive steps:"));
+    }
+}
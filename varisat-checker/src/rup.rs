@@ -6,9 +6,10 @@ use partial_ref::{partial, PartialRef};
 use varisat_formula::{lit::LitIdx, Lit};
 use varisat_internal_proof::ClauseHash;
 
-use crate::clauses::{UnitClause, UnitId};
+use crate::clauses::{Clauses, UnitClause, UnitId};
 use crate::context::{parts::*, Context};
 use crate::hash::rehash;
+use crate::processing::ResolutionPropagations;
 use crate::variables::ensure_var;
 use crate::CheckerError;
 
@@ -29,6 +30,14 @@ pub struct RupCheck {
     trace_edges: Vec<LitIdx>,
     /// Just the ids of `trace`.
     pub trace_ids: Vec<u64>,
+    /// Resolved clauses and propagations of the last RAT check.
+    pub resolution_propagations: ResolutionPropagations,
+    /// Number of literals propagated during all checks.
+    pub propagations: u64,
+    /// Clause that is about to be deleted.
+    ///
+    /// When set, only irredundant clauses other than this one are used for propagation.
+    pub deleted_clause: Option<u64>,
 }
 
 /// Check whether a clause is implied by clauses of the given hashes.
@@ -58,6 +67,7 @@ pub fn check_clause_with_hashes<'a>(
     rup.trace_edges.clear();
 
     let mut rup_is_unsat = false;
+    let mut missing_hash = None;
 
     assert!(rup.trail.is_empty());
 
@@ -65,7 +75,7 @@ pub fn check_clause_with_hashes<'a>(
         ensure_var(ctx.borrow(), lit.var());
     }
 
-    let (clauses, ctx) = ctx.split_part_mut(ClausesP);
+    let (clauses, mut ctx) = ctx.split_part_mut(ClausesP);
 
     for &lit in lits.iter() {
         if let Some((true, unit)) = clauses.lit_value(lit) {
@@ -93,15 +103,19 @@ pub fn check_clause_with_hashes<'a>(
         let candidates = match clauses.clauses.get(&hash) {
             Some(candidates) if !candidates.is_empty() => candidates,
             _ => {
-                return Err(CheckerError::check_failed(
-                    ctx.part(CheckerStateP).step,
-                    format!("no clause found for hash {:x}", hash),
-                ))
+                missing_hash = Some(hash);
+                break 'hashes;
             }
         };
 
         // Check if any clause matching the hash propagates
         'candidates: for clause in candidates.iter() {
+            if let Some(deleted_clause) = rup.deleted_clause {
+                if clause.id == deleted_clause || !clause.is_irredundant() {
+                    continue 'candidates;
+                }
+            }
+
             let mut unassigned_count = 0;
             let mut unassigned_lit = None;
 
@@ -154,6 +168,7 @@ pub fn check_clause_with_hashes<'a>(
                     break 'hashes;
                 }
                 Some(lit) if unassigned_count == 1 => {
+                    rup.propagations += 1;
                     rup.trail.push((lit, clauses.unit_clauses[lit.index()]));
 
                     clauses.unit_clauses[lit.index()] = Some(UnitClause {
@@ -172,7 +187,7 @@ pub fn check_clause_with_hashes<'a>(
         }
     }
 
-    if rup_is_unsat && !ctx.part(ProcessingP).processors.is_empty() {
+    if rup_is_unsat && ctx.part(ProcessingP).needs_hints() {
         for i in (0..rup.trace.len()).rev() {
             if !rup.trace[i].unused {
                 let edges = rup.trace[i].edges.clone();
@@ -182,20 +197,68 @@ pub fn check_clause_with_hashes<'a>(
             }
         }
         rup.trace_ids.clear();
-        rup.trace_ids.extend(rup.trace.iter().map(|trace| trace.id));
+        rup.trace_ids.extend(
+            rup.trace
+                .iter()
+                .filter(|trace| !trace.unused)
+                .map(|trace| trace.id),
+        );
+    } else {
+        rup.trace_ids.clear();
     }
 
+    ctx.part_mut(ProcessingP).propagations = rup.propagations;
+
     // Undo temporary assignments
     for (lit, value) in rup.trail.drain(..).rev() {
         clauses.unit_clauses[lit.index()] = value;
     }
 
-    if rup_is_unsat {
+    let diagnostics = ctx.part(CheckerStateP).collision_diagnostics;
+
+    if let Some(hash) = missing_hash {
+        let mut msg = format!("no clause found for hash {:x}", hash);
+        if diagnostics {
+            msg.push_str(" (not caused by a hash collision)");
+        }
+        Err(CheckerError::check_failed(
+            ctx.part(CheckerStateP).step,
+            msg,
+        ))
+    } else if rup_is_unsat {
         Ok(())
     } else {
+        let mut msg = format!("AT check failed for {:?}", lits);
+        if diagnostics {
+            msg.push_str(&collision_report(clauses, propagation_hashes));
+        }
         Err(CheckerError::check_failed(
             ctx.part(CheckerStateP).step,
-            format!("AT check failed for {:?}", lits),
+            msg,
         ))
     }
 }
+
+/// Describe hash collisions between the clauses matched by the given hashes.
+fn collision_report(clauses: &Clauses, propagation_hashes: &[ClauseHash]) -> String {
+    let mut reported = vec![];
+    let mut report = String::new();
+
+    for &hash in propagation_hashes.iter() {
+        if reported.contains(&hash) {
+            continue;
+        }
+        reported.push(hash);
+
+        if clauses.clauses.get(&hash).map_or(0, |bucket| bucket.len()) > 1 {
+            report.push_str("; ");
+            report.push_str(&clauses.describe_bucket(hash));
+        }
+    }
+
+    if report.is_empty() {
+        report.push_str("; no hash collisions between the used clauses");
+    }
+
+    report
+}
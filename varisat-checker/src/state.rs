@@ -7,8 +7,12 @@ use hashbrown::HashSet;
 use partial_ref::{partial, PartialRef};
 
 use varisat_formula::{Lit, Var};
-use varisat_internal_proof::{binary_format::Parser, ClauseHash, DeleteClauseProof, ProofStep};
+use varisat_internal_proof::{
+    binary_format::{read_header, HeaderError, Parser, ProofHeader, StepError, FORMAT_VERSION},
+    ClauseHash, DeleteClauseProof, ProofStep, SolveResult, MAX_HASH_BITS,
+};
 
+use crate::callbacks::{CheckerProgress, CountingReader, ProofInput, RecordingReader, SliceInput};
 use crate::clauses::{
     add_clause, delete_clause, store_clause, store_unit_clause, DeleteClauseResult,
     StoreClauseResult, UnitClause, UnitId,
@@ -16,7 +20,8 @@ use crate::clauses::{
 use crate::context::{parts::*, Context};
 use crate::hash::rehash;
 use crate::processing::{
-    process_step, CheckedProofStep, CheckedSamplingMode, CheckedUserVar, ResolutionPropagations,
+    process_step, CheckedProofStep, CheckedSamplingMode, CheckedUserVar, ProofVerdict,
+    ResolutionPropagations,
 };
 use crate::rup::check_clause_with_hashes;
 use crate::sorted_lits::{copy_canonical, is_subset};
@@ -25,6 +30,26 @@ use crate::variables::{
 };
 use crate::CheckerError;
 
+/// How deletions of unit and empty clauses are handled.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum UnitDeletionPolicy {
+    /// Deleting a unit or empty clause is an error.
+    Reject,
+    /// Deletions of unit and empty clauses are skipped.
+    Ignore,
+    /// Unit clauses are deleted, deletions of empty clauses are skipped.
+    ///
+    /// Unit clauses are treated as irredundant, so their deletion must be justified by a
+    /// [`DeleteClauseProof::Simplified`] proof.
+    Delete,
+}
+
+impl Default for UnitDeletionPolicy {
+    fn default() -> UnitDeletionPolicy {
+        UnitDeletionPolicy::Reject
+    }
+}
+
 /// A checker for unsatisfiability proofs in the native varisat format.
 #[derive(Default)]
 pub struct CheckerState {
@@ -33,7 +58,7 @@ pub struct CheckerState {
     /// Whether unsatisfiability was proven.
     pub unsat: bool,
     /// Whether an end of proof step was checked.
-    ended: bool,
+    pub ended: bool,
     /// Last added irredundant clause id.
     ///
     /// Sorted and free of duplicates.
@@ -42,6 +67,30 @@ pub struct CheckerState {
     previous_irred_clause_lits: Vec<Lit>,
     /// Current assumptions, used to check FailedAssumptions and Model
     assumptions: Vec<Lit>,
+    /// Solve result justified by a Model (`true`) or FailedAssumptions (`false`) step.
+    ///
+    /// Reset when the justification becomes invalid, used to check SolveResult.
+    justified_result: Option<bool>,
+    /// How deletions of unit and empty clauses are handled.
+    pub unit_deletion_policy: UnitDeletionPolicy,
+    /// Whether failed steps report hash collisions, see [`CheckerConfig`](crate::CheckerConfig).
+    pub collision_diagnostics: bool,
+    /// Number of checked AT clause steps.
+    pub at_steps: u64,
+    /// Maximal number of failed steps before checking is aborted.
+    ///
+    /// Values below 2 abort at the first failed step.
+    pub error_limit: usize,
+    /// Errors of failed steps that were skipped.
+    pub recovered_errors: Vec<CheckerError>,
+    /// Metadata from the proof header.
+    pub proof_metadata: Vec<(String, String)>,
+    /// Progress at the last checkpoint step of a proof read from a file.
+    pub last_checkpoint: Option<CheckerProgress>,
+    /// Error of a clause added using [`ExtendFormula`](varisat_formula::ExtendFormula).
+    ///
+    /// Reported by the next call that checks proof steps or models.
+    pub pending_error: Option<CheckerError>,
 }
 
 impl CheckerState {
@@ -70,6 +119,8 @@ pub fn check_step<'a>(
     ),
     step: ProofStep,
 ) -> Result<(), CheckerError> {
+    ctx.part_mut(ProcessingP).proof_step = ctx.part(CheckerStateP).step;
+
     let mut result = match step {
         ProofStep::SolverVarName { global, solver } => {
             ctx.part_mut(ClauseHasherP)
@@ -79,7 +130,13 @@ pub fn check_step<'a>(
                 ctx.part_mut(ClauseHasherP)
                     .rename_in_buffered_solver_var_names = true;
             }
-            Ok(())
+            process_step(
+                ctx.borrow(),
+                &CheckedProofStep::SolverVar {
+                    var: global,
+                    solver_var: solver,
+                },
+            )
         }
         ProofStep::UserVarName { global, user } => {
             if let Some(user) = user {
@@ -93,23 +150,96 @@ pub fn check_step<'a>(
         ProofStep::ChangeSamplingMode { var, sample } => {
             check_change_sampling_mode(ctx.borrow(), var, sample)
         }
-        ProofStep::AddClause { clause } => add_clause(ctx.borrow(), clause),
+        ProofStep::AddClause { clause } => {
+            // A new clause can invalidate a model, but not a failed core
+            let state = ctx.part_mut(CheckerStateP);
+            if state.justified_result == Some(true) {
+                state.justified_result = None;
+            }
+            add_clause(ctx.borrow(), clause)
+        }
         ProofStep::AtClause {
             redundant,
             clause,
             propagation_hashes,
         } => check_at_clause_step(ctx.borrow(), redundant, clause, propagation_hashes),
+        ProofStep::RatClause {
+            clause,
+            pivot,
+            resolutions,
+            propagation_hashes,
+        } => check_rat_clause_step(ctx.borrow(), clause, pivot, resolutions, propagation_hashes),
+        ProofStep::PrClause {
+            clause,
+            witness,
+            reduced_clauses,
+            propagation_hashes,
+        } => check_pr_clause_step(
+            ctx.borrow(),
+            clause,
+            witness,
+            reduced_clauses,
+            propagation_hashes,
+        ),
+        ProofStep::Extension { lit, definition } => {
+            check_extension_step(ctx.borrow(), lit, definition)
+        }
         ProofStep::DeleteClause { clause, proof } => {
             check_delete_clause_step(ctx.borrow(), clause, proof)
         }
+        ProofStep::DeleteRatClause {
+            clause,
+            pivot,
+            resolutions,
+            propagation_hashes,
+        } => check_delete_rat_clause_step(
+            ctx.borrow(),
+            clause,
+            pivot,
+            resolutions,
+            propagation_hashes,
+        ),
+        ProofStep::BlockedClause {
+            clause,
+            pivot,
+            blocking,
+        } => {
+            let resolutions = blocking_resolutions(blocking);
+            check_rat_clause_step(ctx.borrow(), clause, pivot, &resolutions, &[])
+        }
+        ProofStep::DeleteBlockedClause {
+            clause,
+            pivot,
+            blocking,
+        } => {
+            let resolutions = blocking_resolutions(blocking);
+            check_delete_rat_clause_step(ctx.borrow(), clause, pivot, &resolutions, &[])
+        }
+        ProofStep::DeleteHashedClause { hash, proof } => {
+            check_delete_hashed_clause_step(ctx.borrow(), hash, proof)
+        }
         ProofStep::UnitClauses { units } => check_unit_clauses_step(ctx.borrow(), units),
         ProofStep::ChangeHashBits { bits } => {
-            ctx.part_mut(ClauseHasherP).hash_bits = bits;
-            rehash(ctx.borrow());
-            Ok(())
+            if bits == 0 || bits > MAX_HASH_BITS {
+                Err(CheckerError::check_failed(
+                    ctx.part(CheckerStateP).step,
+                    format!("unsupported clause hash width of {} bits", bits),
+                ))
+            } else {
+                ctx.part_mut(ClauseHasherP).hash_bits = bits;
+                rehash(ctx.borrow());
+                Ok(())
+            }
+        }
+        ProofStep::Model { assignment } => {
+            let result = check_model_step(ctx.borrow(), assignment);
+            if result.is_ok() {
+                ctx.part_mut(CheckerStateP).justified_result = Some(true);
+            }
+            result
         }
-        ProofStep::Model { assignment } => check_model_step(ctx.borrow(), assignment),
         ProofStep::Assumptions { assumptions } => {
+            ctx.part_mut(CheckerStateP).justified_result = None;
             for &lit in assumptions.iter() {
                 ensure_sampling_var(ctx.borrow(), lit.var())?;
             }
@@ -128,7 +258,16 @@ pub fn check_step<'a>(
         ProofStep::FailedAssumptions {
             failed_core,
             propagation_hashes,
-        } => check_failed_assumptions_step(ctx.borrow(), failed_core, propagation_hashes),
+        } => {
+            let result =
+                check_failed_assumptions_step(ctx.borrow(), failed_core, propagation_hashes);
+            if result.is_ok() {
+                ctx.part_mut(CheckerStateP).justified_result = Some(false);
+            }
+            result
+        }
+        ProofStep::SolveResult { result } => check_solve_result_step(ctx.borrow(), result),
+        ProofStep::Checkpoint => Ok(()),
         ProofStep::End => {
             ctx.part_mut(CheckerStateP).ended = true;
             Ok(())
@@ -190,7 +329,7 @@ fn check_delete_var_step<'a>(
                 keep_as_redundant: false,
                 clause: &clause[..],
                 pivot: clause[0],
-                propagations: &ResolutionPropagations {},
+                propagations: &ResolutionPropagations::default(),
             },
         )?;
         ctx.part_mut(ClausesP).unit_clauses[var.index()] = None;
@@ -281,7 +420,9 @@ fn check_at_clause_step<'a>(
 
     check_clause_with_hashes(ctx.borrow(), &tmp, &*propagation_hashes)?;
 
-    let (id, added) = store_clause(ctx.borrow(), &tmp, redundant);
+    ctx.part_mut(CheckerStateP).at_steps += 1;
+
+    let (id, added) = store_clause(ctx.borrow(), &tmp, redundant)?;
 
     if !redundant {
         let state = ctx.part_mut(CheckerStateP);
@@ -317,6 +458,623 @@ fn check_at_clause_step<'a>(
     Ok(())
 }
 
+/// Check an Extension step
+///
+/// After checking that the defined variable is fresh, the defining clauses are added as RAT
+/// clauses, so processors see them like any other RAT clause.
+fn check_extension_step<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut CheckerStateP,
+        mut ClauseHasherP,
+        mut ClausesP,
+        mut ProcessingP<'a>,
+        mut RupCheckP,
+        mut TmpDataP,
+        mut VariablesP,
+    ),
+    lit: Lit,
+    definition: &[Lit],
+) -> Result<(), CheckerError> {
+    ensure_var(ctx.borrow(), lit.var());
+
+    let mut definition = definition.to_vec();
+    definition.sort_unstable();
+    definition.dedup();
+
+    let step = ctx.part(CheckerStateP).step;
+
+    if definition.iter().any(|def_lit| def_lit.var() == lit.var()) {
+        return Err(CheckerError::check_failed(
+            step,
+            format!("extension variable {:?} used in its definition", lit.var()),
+        ));
+    }
+
+    let lit_data = &ctx.part(VariablesP).lit_data;
+    if lit_data[lit.code()].clause_count > 0
+        || lit_data[(!lit).code()].clause_count > 0
+        || ctx.part(ClausesP).lit_value(lit).is_some()
+    {
+        return Err(CheckerError::check_failed(
+            step,
+            format!("extension variable {:?} is already in use", lit.var()),
+        ));
+    }
+
+    // The long clause is RAT as no clause contains `!lit`.
+    let mut long_clause = vec![lit];
+    long_clause.extend(definition.iter().map(|&def_lit| !def_lit));
+    check_rat_clause_step(ctx.borrow(), &long_clause, lit, &[], &[])?;
+
+    if definition.is_empty() {
+        return Ok(());
+    }
+
+    // Each binary clause is RAT as its only resolvent, with the long clause, is a tautology.
+    let long_hash = ctx.part(ClauseHasherP).clause_hash(&long_clause);
+    for &def_lit in definition.iter() {
+        check_rat_clause_step(ctx.borrow(), &[!lit, def_lit], !lit, &[(long_hash, 0)], &[])?;
+    }
+
+    Ok(())
+}
+
+/// Check that a clause is a resolution asymmetric tautology (RAT) on the given pivot.
+///
+/// The propagations used for each resolvent are stored in `resolution_propagations`.
+fn check_rat<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut ClauseHasherP,
+        mut ClausesP,
+        mut ProcessingP<'a>,
+        mut RupCheckP,
+        mut VariablesP,
+        CheckerStateP,
+    ),
+    clause: &[Lit],
+    pivot: Lit,
+    resolutions: &[(ClauseHash, usize)],
+    propagation_hashes: &[ClauseHash],
+    resolvent: &mut Vec<Lit>,
+) -> Result<(), CheckerError> {
+    if !clause.contains(&pivot) {
+        return Err(CheckerError::check_failed(
+            ctx.part(CheckerStateP).step,
+            format!("pivot {:?} not in clause {:?}", pivot, clause),
+        ));
+    }
+
+    ensure_var(ctx.borrow(), pivot.var());
+
+    if ctx.part(VariablesP).var_data[pivot.index()].sampling_mode == SamplingMode::Sample {
+        return Err(CheckerError::check_failed(
+            ctx.part(CheckerStateP).step,
+            format!("pivot {:?} is a sampling variable", pivot),
+        ));
+    }
+
+    if ctx.part(ClausesP).lit_value(!pivot).is_some() {
+        return Err(CheckerError::check_failed(
+            ctx.part(CheckerStateP).step,
+            format!("pivot {:?} is assigned by a unit clause", pivot),
+        ));
+    }
+
+    if ctx.part(ClauseHasherP).rename_in_buffered_solver_var_names {
+        rehash(ctx.borrow());
+    }
+
+    ctx.part_mut(RupCheckP).resolution_propagations.clear();
+
+    let mut resolved = HashSet::new();
+    let mut hashes = propagation_hashes;
+
+    for &(hash, count) in resolutions.iter() {
+        if count > hashes.len() {
+            return Err(CheckerError::check_failed(
+                ctx.part(CheckerStateP).step,
+                format!("missing propagation hashes for resolution with {:x}", hash),
+            ));
+        }
+        let (resolvent_hashes, rest) = hashes.split_at(count);
+        hashes = rest;
+
+        let clauses = ctx.part(ClausesP);
+
+        let candidate = clauses.clauses.get(&hash).and_then(|candidates| {
+            candidates.iter().find(|candidate| {
+                !resolved.contains(&candidate.id)
+                    && candidate
+                        .lits
+                        .slice(&clauses.literal_buffer)
+                        .contains(&!pivot)
+            })
+        });
+
+        let id = match candidate {
+            Some(candidate) => {
+                resolvent.clear();
+                resolvent.extend(clause.iter().filter(|&&lit| lit != pivot));
+                resolvent.extend(
+                    candidate
+                        .lits
+                        .slice(&clauses.literal_buffer)
+                        .iter()
+                        .filter(|&&lit| lit != !pivot),
+                );
+                candidate.id
+            }
+            None => {
+                return Err(CheckerError::check_failed(
+                    ctx.part(CheckerStateP).step,
+                    format!(
+                        "no clause containing {:?} found for hash {:x}",
+                        !pivot, hash
+                    ),
+                ))
+            }
+        };
+
+        resolved.insert(id);
+
+        check_resolvent(ctx.borrow(), id, resolvent, resolvent_hashes)?;
+    }
+
+    if !hashes.is_empty() {
+        return Err(CheckerError::check_failed(
+            ctx.part(CheckerStateP).step,
+            format!("{} unused propagation hashes", hashes.len()),
+        ));
+    }
+
+    if resolved.len() != ctx.part(VariablesP).lit_data[(!pivot).code()].clause_count {
+        return Err(CheckerError::check_failed(
+            ctx.part(CheckerStateP).step,
+            format!("not all clauses containing {:?} were resolved", !pivot),
+        ));
+    }
+
+    Ok(())
+}
+
+/// RAT resolutions for a blocked clause, where no resolvent needs propagations.
+fn blocking_resolutions(blocking: &[ClauseHash]) -> Vec<(ClauseHash, usize)> {
+    blocking.iter().map(|&hash| (hash, 0)).collect()
+}
+
+/// Check a RatClause step
+fn check_rat_clause_step<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut CheckerStateP,
+        mut ClauseHasherP,
+        mut ClausesP,
+        mut ProcessingP<'a>,
+        mut RupCheckP,
+        mut TmpDataP,
+        mut VariablesP,
+    ),
+    clause: &[Lit],
+    pivot: Lit,
+    resolutions: &[(ClauseHash, usize)],
+    propagation_hashes: &[ClauseHash],
+) -> Result<(), CheckerError> {
+    let mut tmp = replace(&mut ctx.part_mut(TmpDataP).tmp, vec![]);
+    let mut resolvent = replace(&mut ctx.part_mut(TmpDataP).resolvent, vec![]);
+
+    if copy_canonical(&mut tmp, clause) {
+        return Err(CheckerError::check_failed(
+            ctx.part(CheckerStateP).step,
+            format!("clause {:?} is a tautology", tmp),
+        ));
+    }
+
+    check_rat(
+        ctx.borrow(),
+        &tmp,
+        pivot,
+        resolutions,
+        propagation_hashes,
+        &mut resolvent,
+    )?;
+
+    let (id, added) = store_clause(ctx.borrow(), &tmp, false)?;
+
+    let state = ctx.part_mut(CheckerStateP);
+    state.previous_irred_clause_id = Some(id);
+    state.previous_irred_clause_lits.clear();
+    state.previous_irred_clause_lits.extend_from_slice(&tmp);
+
+    match added {
+        StoreClauseResult::New => {
+            let (rup_check, mut ctx) = ctx.split_part(RupCheckP);
+            process_step(
+                ctx.borrow(),
+                &CheckedProofStep::RatClause {
+                    id,
+                    clause: &tmp,
+                    pivot,
+                    propagations: &rup_check.resolution_propagations,
+                },
+            )?;
+        }
+        StoreClauseResult::NewlyIrredundant => {
+            process_step(
+                ctx.borrow(),
+                &CheckedProofStep::MakeIrredundant { id, clause: &tmp },
+            )?;
+        }
+        StoreClauseResult::Duplicate => (),
+    }
+
+    ctx.part_mut(TmpDataP).tmp = tmp;
+    ctx.part_mut(TmpDataP).resolvent = resolvent;
+
+    Ok(())
+}
+
+/// Check a PrClause step
+fn check_pr_clause_step<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut CheckerStateP,
+        mut ClauseHasherP,
+        mut ClausesP,
+        mut ProcessingP<'a>,
+        mut RupCheckP,
+        mut TmpDataP,
+        mut VariablesP,
+    ),
+    clause: &[Lit],
+    witness: &[Lit],
+    reduced_clauses: &[(ClauseHash, usize)],
+    propagation_hashes: &[ClauseHash],
+) -> Result<(), CheckerError> {
+    let mut tmp = replace(&mut ctx.part_mut(TmpDataP).tmp, vec![]);
+    let mut resolvent = replace(&mut ctx.part_mut(TmpDataP).resolvent, vec![]);
+    let mut witness_tmp = replace(&mut ctx.part_mut(TmpDataP).witness, vec![]);
+
+    if copy_canonical(&mut tmp, clause) {
+        return Err(CheckerError::check_failed(
+            ctx.part(CheckerStateP).step,
+            format!("clause {:?} is a tautology", tmp),
+        ));
+    }
+
+    if copy_canonical(&mut witness_tmp, witness) {
+        return Err(CheckerError::check_failed(
+            ctx.part(CheckerStateP).step,
+            format!("witness {:?} is inconsistent", witness_tmp),
+        ));
+    }
+
+    let witness = &witness_tmp[..];
+    let in_witness = |lit: &Lit| witness.binary_search(lit).is_ok();
+
+    if !tmp.iter().any(in_witness) {
+        return Err(CheckerError::check_failed(
+            ctx.part(CheckerStateP).step,
+            format!("witness {:?} does not satisfy clause {:?}", witness, tmp),
+        ));
+    }
+
+    for &lit in witness.iter() {
+        ensure_var(ctx.borrow(), lit.var());
+
+        if ctx.part(VariablesP).var_data[lit.index()].sampling_mode == SamplingMode::Sample {
+            return Err(CheckerError::check_failed(
+                ctx.part(CheckerStateP).step,
+                format!("witness literal {:?} is a sampling variable", lit),
+            ));
+        }
+
+        if let Some((false, _)) = ctx.part(ClausesP).lit_value(lit) {
+            return Err(CheckerError::check_failed(
+                ctx.part(CheckerStateP).step,
+                format!("witness literal {:?} is falsified by a unit clause", lit),
+            ));
+        }
+    }
+
+    // A clause is reduced if the witness falsifies some but satisfies none of its literals
+    let is_reduced =
+        |lits: &[Lit]| lits.iter().any(|&lit| in_witness(&!lit)) && !lits.iter().any(in_witness);
+
+    if ctx.part(ClauseHasherP).rename_in_buffered_solver_var_names {
+        rehash(ctx.borrow());
+    }
+
+    ctx.part_mut(RupCheckP).resolution_propagations.clear();
+
+    let mut reduced = HashSet::new();
+    let mut hashes = propagation_hashes;
+
+    for &(hash, count) in reduced_clauses.iter() {
+        if count > hashes.len() {
+            return Err(CheckerError::check_failed(
+                ctx.part(CheckerStateP).step,
+                format!("missing propagation hashes for reduced clause {:x}", hash),
+            ));
+        }
+        let (reduced_hashes, rest) = hashes.split_at(count);
+        hashes = rest;
+
+        let clauses = ctx.part(ClausesP);
+
+        let candidate = clauses.clauses.get(&hash).and_then(|candidates| {
+            candidates.iter().find(|candidate| {
+                !reduced.contains(&candidate.id)
+                    && is_reduced(candidate.lits.slice(&clauses.literal_buffer))
+            })
+        });
+
+        let id = match candidate {
+            Some(candidate) => {
+                resolvent.clear();
+                resolvent.extend_from_slice(&tmp);
+                resolvent.extend(
+                    candidate
+                        .lits
+                        .slice(&clauses.literal_buffer)
+                        .iter()
+                        .filter(|&&lit| !in_witness(&!lit)),
+                );
+                candidate.id
+            }
+            None => {
+                return Err(CheckerError::check_failed(
+                    ctx.part(CheckerStateP).step,
+                    format!("no clause reduced by the witness found for hash {:x}", hash),
+                ))
+            }
+        };
+
+        reduced.insert(id);
+
+        check_resolvent(ctx.borrow(), id, &mut resolvent, reduced_hashes)?;
+    }
+
+    if !hashes.is_empty() {
+        return Err(CheckerError::check_failed(
+            ctx.part(CheckerStateP).step,
+            format!("{} unused propagation hashes", hashes.len()),
+        ));
+    }
+
+    let clauses = ctx.part(ClausesP);
+
+    for candidates in clauses.clauses.values() {
+        for candidate in candidates.iter() {
+            let lits = candidate.lits.slice(&clauses.literal_buffer);
+            if !reduced.contains(&candidate.id) && is_reduced(lits) {
+                return Err(CheckerError::check_failed(
+                    ctx.part(CheckerStateP).step,
+                    format!("clause {:?} reduced by the witness was not checked", lits),
+                ));
+            }
+        }
+    }
+
+    let (id, added) = store_clause(ctx.borrow(), &tmp, false)?;
+
+    let state = ctx.part_mut(CheckerStateP);
+    state.previous_irred_clause_id = Some(id);
+    state.previous_irred_clause_lits.clear();
+    state.previous_irred_clause_lits.extend_from_slice(&tmp);
+
+    match added {
+        StoreClauseResult::New => {
+            let (rup_check, mut ctx) = ctx.split_part(RupCheckP);
+            process_step(
+                ctx.borrow(),
+                &CheckedProofStep::PrClause {
+                    id,
+                    clause: &tmp,
+                    witness: &witness_tmp,
+                    propagations: &rup_check.resolution_propagations,
+                },
+            )?;
+        }
+        StoreClauseResult::NewlyIrredundant => {
+            process_step(
+                ctx.borrow(),
+                &CheckedProofStep::MakeIrredundant { id, clause: &tmp },
+            )?;
+        }
+        StoreClauseResult::Duplicate => (),
+    }
+
+    ctx.part_mut(TmpDataP).tmp = tmp;
+    ctx.part_mut(TmpDataP).resolvent = resolvent;
+    ctx.part_mut(TmpDataP).witness = witness_tmp;
+
+    Ok(())
+}
+
+/// Check that a resolvent is a tautology or an AT and record the used propagations.
+///
+/// The resolvent is sorted and duplicates are removed.
+fn check_resolvent<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut ClauseHasherP,
+        mut ClausesP,
+        mut ProcessingP<'a>,
+        mut RupCheckP,
+        mut VariablesP,
+        CheckerStateP,
+    ),
+    id: u64,
+    resolvent: &mut Vec<Lit>,
+    propagation_hashes: &[ClauseHash],
+) -> Result<(), CheckerError> {
+    resolvent.sort();
+    resolvent.dedup();
+
+    // Complementary literals are adjacent after sorting
+    if resolvent.windows(2).any(|pair| pair[0] == !pair[1]) {
+        ctx.part_mut(RupCheckP)
+            .resolution_propagations
+            .push(id, &[]);
+    } else {
+        check_clause_with_hashes(ctx.borrow(), resolvent, propagation_hashes)?;
+        let rup_check = ctx.part_mut(RupCheckP);
+        rup_check
+            .resolution_propagations
+            .push(id, &rup_check.trace_ids);
+    }
+
+    Ok(())
+}
+
+/// Check a DeleteRatClause step
+fn check_delete_rat_clause_step<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut CheckerStateP,
+        mut ClauseHasherP,
+        mut ClausesP,
+        mut ProcessingP<'a>,
+        mut RupCheckP,
+        mut TmpDataP,
+        mut VariablesP,
+    ),
+    clause: &[Lit],
+    pivot: Lit,
+    resolutions: &[(ClauseHash, usize)],
+    propagation_hashes: &[ClauseHash],
+) -> Result<(), CheckerError> {
+    let mut tmp = replace(&mut ctx.part_mut(TmpDataP).tmp, vec![]);
+    let mut resolvent = replace(&mut ctx.part_mut(TmpDataP).resolvent, vec![]);
+
+    if copy_canonical(&mut tmp, clause) {
+        return Err(CheckerError::check_failed(
+            ctx.part(CheckerStateP).step,
+            format!("clause {:?} is a tautology", tmp),
+        ));
+    }
+
+    if tmp.len() < 2 {
+        return Err(CheckerError::check_failed(
+            ctx.part(CheckerStateP).step,
+            format!("delete of unit or empty clause {:?}", tmp),
+        ));
+    }
+
+    if ctx.part(ClauseHasherP).rename_in_buffered_solver_var_names {
+        rehash(ctx.borrow());
+    }
+
+    let hash = ctx.part(ClauseHasherP).clause_hash(&tmp);
+    let clauses = ctx.part(ClausesP);
+    let deleted_id = clauses.clauses.get(&hash).and_then(|candidates| {
+        candidates
+            .iter()
+            .find(|candidate| {
+                candidate.is_irredundant()
+                    && candidate.lits.slice(&clauses.literal_buffer) == &tmp[..]
+            })
+            .map(|candidate| candidate.id)
+    });
+
+    if deleted_id.is_none() {
+        return Err(CheckerError::check_failed(
+            ctx.part(CheckerStateP).step,
+            format!("delete of unknown irredundant clause {:?}", tmp),
+        ));
+    }
+
+    // The deleted clause itself would make every resolvent an asymmetric tautology
+    ctx.part_mut(RupCheckP).deleted_clause = deleted_id;
+    let result = check_rat(
+        ctx.borrow(),
+        &tmp,
+        pivot,
+        resolutions,
+        propagation_hashes,
+        &mut resolvent,
+    );
+    ctx.part_mut(RupCheckP).deleted_clause = None;
+    result?;
+
+    ctx.part_mut(CheckerStateP).previous_irred_clause_id = None;
+    ctx.part_mut(CheckerStateP)
+        .previous_irred_clause_lits
+        .clear();
+
+    let (id, deleted) = delete_clause(ctx.borrow(), &tmp, false)?;
+
+    if deleted != DeleteClauseResult::Unchanged {
+        let (rup_check, mut ctx) = ctx.split_part(RupCheckP);
+        process_step(
+            ctx.borrow(),
+            &CheckedProofStep::DeleteRatClause {
+                id,
+                keep_as_redundant: deleted == DeleteClauseResult::NewlyRedundant,
+                clause: &tmp,
+                pivot,
+                propagations: &rup_check.resolution_propagations,
+            },
+        )?;
+    }
+
+    ctx.part_mut(TmpDataP).tmp = tmp;
+    ctx.part_mut(TmpDataP).resolvent = resolvent;
+    Ok(())
+}
+
+/// Check a DeleteHashedClause step
+fn check_delete_hashed_clause_step<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut CheckerStateP,
+        mut ClauseHasherP,
+        mut ClausesP,
+        mut ProcessingP<'a>,
+        mut TmpDataP,
+        mut VariablesP,
+    ),
+    hash: ClauseHash,
+    proof: DeleteClauseProof,
+) -> Result<(), CheckerError> {
+    if ctx.part(ClauseHasherP).rename_in_buffered_solver_var_names {
+        rehash(ctx.borrow());
+    }
+
+    // The resolvent buffer is only used while checking clause additions.
+    let mut lits = replace(&mut ctx.part_mut(TmpDataP).resolvent, vec![]);
+    lits.clear();
+
+    let clauses = ctx.part(ClausesP);
+    let found = match clauses.clauses.get(&hash) {
+        Some(candidates) if candidates.len() == 1 => {
+            lits.extend_from_slice(candidates[0].lits.slice(&clauses.literal_buffer));
+            Ok(())
+        }
+        Some(candidates) if !candidates.is_empty() => {
+            let mut msg = format!("{} clauses found for hash {:x}", candidates.len(), hash);
+            if ctx.part(CheckerStateP).collision_diagnostics {
+                msg.push_str(": ");
+                msg.push_str(&clauses.describe_bucket(hash));
+            }
+            Err(CheckerError::check_failed(
+                ctx.part(CheckerStateP).step,
+                msg,
+            ))
+        }
+        _ => Err(CheckerError::check_failed(
+            ctx.part(CheckerStateP).step,
+            format!("no clause found for hash {:x}", hash),
+        )),
+    };
+
+    let result = found.and_then(|_| check_delete_clause_step(ctx.borrow(), &lits, proof));
+    ctx.part_mut(TmpDataP).resolvent = lits;
+    result
+}
+
 /// Check a DeleteClause step
 fn check_delete_clause_step<'a>(
     mut ctx: partial!(
@@ -340,6 +1098,12 @@ fn check_delete_clause_step<'a>(
         ));
     }
 
+    if tmp.len() < 2 {
+        let result = check_delete_unit_or_empty_clause(ctx.borrow(), &tmp, proof);
+        ctx.part_mut(TmpDataP).tmp = tmp;
+        return result;
+    }
+
     let redundant = proof == DeleteClauseProof::Redundant;
 
     let mut subsumed_by = None;
@@ -428,6 +1192,107 @@ fn check_delete_clause_step<'a>(
     Ok(())
 }
 
+/// Check a DeleteClause step for a unit or empty clause
+///
+/// Unit clauses are stored without tracking redundancy, so they are treated as irredundant and a
+/// deletion needs to be justified like that of an irredundant long clause.
+fn check_delete_unit_or_empty_clause<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut CheckerStateP,
+        mut ClausesP,
+        mut ProcessingP<'a>,
+        mut VariablesP,
+    ),
+    lits: &[Lit],
+    proof: DeleteClauseProof,
+) -> Result<(), CheckerError> {
+    match ctx.part(CheckerStateP).unit_deletion_policy {
+        UnitDeletionPolicy::Reject => Err(CheckerError::check_failed(
+            ctx.part(CheckerStateP).step,
+            format!("delete of unit or empty clause {:?}", lits),
+        )),
+        UnitDeletionPolicy::Ignore => Ok(()),
+        UnitDeletionPolicy::Delete => {
+            let lit = match lits.first() {
+                Some(&lit) => lit,
+                None => return Ok(()),
+            };
+
+            ensure_var(ctx.borrow(), lit.var());
+
+            let id = match ctx.part(ClausesP).lit_value(lit) {
+                Some((
+                    true,
+                    UnitClause {
+                        id: UnitId::Global(id),
+                        ..
+                    },
+                )) => id,
+                _ => {
+                    return Err(CheckerError::check_failed(
+                        ctx.part(CheckerStateP).step,
+                        format!("delete of unknown clause {:?}", lits),
+                    ))
+                }
+            };
+
+            let subsumed_by = match proof {
+                DeleteClauseProof::Redundant => {
+                    return Err(CheckerError::check_failed(
+                        ctx.part(CheckerStateP).step,
+                        format!("delete of redundant clause {:?} which is irredundant", lits),
+                    ))
+                }
+                // The only unit clause satisfying the deleted clause is the clause itself.
+                DeleteClauseProof::Satisfied => {
+                    return Err(CheckerError::check_failed(
+                        ctx.part(CheckerStateP).step,
+                        format!("deleted clause {:?} is not satisfied", lits),
+                    ))
+                }
+                DeleteClauseProof::Simplified => {
+                    let state = ctx.part(CheckerStateP);
+                    match state.previous_irred_clause_id {
+                        Some(previous_id)
+                            if previous_id != id
+                                && state.subsumed_by_previous_irred_clause(lits) =>
+                        {
+                            previous_id
+                        }
+                        _ => {
+                            return Err(CheckerError::check_failed(
+                                state.step,
+                                format!(
+                                    "deleted clause {:?} is not subsumed by previous clause {:?}",
+                                    lits, state.previous_irred_clause_lits
+                                ),
+                            ))
+                        }
+                    }
+                }
+            };
+
+            ctx.part_mut(CheckerStateP).previous_irred_clause_id = None;
+            ctx.part_mut(CheckerStateP)
+                .previous_irred_clause_lits
+                .clear();
+
+            ctx.part_mut(ClausesP).unit_clauses[lit.index()] = None;
+            process_step(
+                ctx.borrow(),
+                &CheckedProofStep::DeleteAtClause {
+                    id,
+                    keep_as_redundant: false,
+                    clause: lits,
+                    propagations: &[subsumed_by],
+                },
+            )?;
+            Ok(())
+        }
+    }
+}
+
 /// Check a UnitClauses step
 fn check_unit_clauses_step<'a>(
     mut ctx: partial!(
@@ -519,6 +1384,28 @@ fn check_model_step<'a>(
     Ok(())
 }
 
+/// Check a SolveResult step
+fn check_solve_result_step(
+    ctx: partial!(Context, CheckerStateP),
+    result: SolveResult,
+) -> Result<(), CheckerError> {
+    let state = ctx.part(CheckerStateP);
+    let justified = match result {
+        SolveResult::Sat => state.justified_result == Some(true),
+        SolveResult::Unsat => state.unsat || state.justified_result == Some(false),
+        SolveResult::Unknown => true,
+    };
+
+    if justified {
+        Ok(())
+    } else {
+        Err(CheckerError::check_failed(
+            state.step,
+            format!("solve result {:?} is not justified", result),
+        ))
+    }
+}
+
 /// Check a FailedAssumptions step
 fn check_failed_assumptions_step<'a>(
     mut ctx: partial!(
@@ -579,6 +1466,7 @@ fn check_failed_assumptions_step<'a>(
 pub fn check_proof<'a>(
     mut ctx: partial!(
         Context<'a>,
+        mut CallbacksP<'a>,
         mut CheckerStateP,
         mut ClauseHasherP,
         mut ClausesP,
@@ -589,44 +1477,224 @@ pub fn check_proof<'a>(
     ),
     input: impl io::Read,
 ) -> Result<(), CheckerError> {
-    let mut buffer = io::BufReader::new(input);
-    let mut parser = Parser::default();
+    let mut input = RecordingReader::new(io::BufReader::new(CountingReader::new(input)));
+    check_proof_steps(ctx.borrow(), &mut ParsedInput::new(&mut input))
+}
+
+/// Checks a proof in the native Varisat format stored in memory.
+///
+/// Steps are parsed directly from `data` without an intermediate buffer.
+pub fn check_proof_slice<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut CallbacksP<'a>,
+        mut CheckerStateP,
+        mut ClauseHasherP,
+        mut ClausesP,
+        mut ProcessingP<'a>,
+        mut RupCheckP,
+        mut TmpDataP,
+        mut VariablesP,
+    ),
+    data: &[u8],
+) -> Result<(), CheckerError> {
+    check_proof_steps(
+        ctx.borrow(),
+        &mut ParsedInput::new(&mut SliceInput::new(data)),
+    )
+}
+
+/// Source of parsed proof steps in the native Varisat format.
+pub trait StepSource {
+    /// Read the proof header.
+    fn read_header(&mut self) -> Result<ProofHeader, HeaderError>;
+
+    /// Parse the next step.
+    fn next_step(&mut self) -> Result<ProofStep, StepError>;
+
+    /// Number of bytes consumed so far.
+    fn offset(&self) -> u64;
+
+    /// Raw bytes of the step returned by the last call to `next_step`.
+    fn step_bytes(&self) -> &[u8];
+}
+
+/// Steps parsed from a [`ProofInput`] on the checking thread.
+struct ParsedInput<'i, I> {
+    parser: Parser,
+    input: &'i mut I,
+}
+
+impl<'i, I> ParsedInput<'i, I> {
+    fn new(input: &'i mut I) -> ParsedInput<'i, I> {
+        ParsedInput {
+            parser: Parser::default(),
+            input,
+        }
+    }
+}
+
+impl<'i, I: ProofInput> StepSource for ParsedInput<'i, I> {
+    fn read_header(&mut self) -> Result<ProofHeader, HeaderError> {
+        read_header(&mut *self.input)
+    }
+
+    fn next_step(&mut self) -> Result<ProofStep, StepError> {
+        self.input.begin_step();
+        self.parser.parse_step(&mut *self.input)
+    }
+
+    fn offset(&self) -> u64 {
+        self.input.offset()
+    }
+
+    fn step_bytes(&self) -> &[u8] {
+        self.input.step_bytes()
+    }
+}
+
+/// Checks a proof in the native Varisat format read from a [`StepSource`].
+pub fn check_proof_steps<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut CallbacksP<'a>,
+        mut CheckerStateP,
+        mut ClauseHasherP,
+        mut ClausesP,
+        mut ProcessingP<'a>,
+        mut RupCheckP,
+        mut TmpDataP,
+        mut VariablesP,
+    ),
+    source: &mut impl StepSource,
+) -> Result<(), CheckerError> {
+    match source.read_header() {
+        Ok(header) => {
+            for (key, value) in header.metadata.iter() {
+                log::info!("proof {}: {}", key, value);
+            }
+            ctx.part_mut(CheckerStateP).proof_metadata = header.metadata;
+        }
+        Err(err) => return Err(header_error(err)),
+    }
 
     while !ctx.part(CheckerStateP).ended {
         ctx.part_mut(CheckerStateP).step += 1;
 
         let step = ctx.part(CheckerStateP).step;
 
-        if step % 100000 == 0 {
-            log::info!("checking step {}k", step / 1000);
-        }
-
-        match parser.parse_step(&mut buffer) {
-            Ok(step) => check_step(ctx.borrow(), step)?,
-            Err(err) => match err.downcast::<io::Error>() {
-                Ok(io_err) => {
-                    if io_err.kind() == io::ErrorKind::UnexpectedEof {
-                        return Err(CheckerError::ProofIncomplete { step });
-                    } else {
-                        return Err(CheckerError::IoError {
-                            step,
-                            cause: io_err,
-                        });
-                    }
+        if ctx.part(CallbacksP).is_cancelled() {
+            return Err(CheckerError::Cancelled { step });
+        }
+
+        if ctx.part(CallbacksP).wants_progress(step) {
+            let progress = CheckerProgress {
+                steps: step,
+                clauses: ctx.part(ClausesP).clause_count(),
+                bytes: source.offset(),
+            };
+            ctx.part_mut(CallbacksP).report_progress(&progress);
+        }
+
+        let offset = source.offset();
+
+        match source.next_step() {
+            Ok(proof_step) => {
+                let checkpoint = match proof_step {
+                    ProofStep::Checkpoint => true,
+                    _ => false,
+                };
+                if let Err(err) = check_step(ctx.borrow(), proof_step) {
+                    recover_from_error(ctx.borrow(), err.in_stream(offset, source.step_bytes()))?;
+                } else if checkpoint {
+                    ctx.part_mut(CheckerStateP).last_checkpoint = Some(CheckerProgress {
+                        steps: step,
+                        clauses: ctx.part(ClausesP).clause_count(),
+                        bytes: source.offset(),
+                    });
                 }
-                Err(err) => {
-                    return Err(CheckerError::ParseError {
+            }
+            Err(StepError::Io(io_err)) => {
+                if io_err.kind() == io::ErrorKind::UnexpectedEof {
+                    return Err(CheckerError::ProofIncomplete { step, offset });
+                } else {
+                    return Err(CheckerError::IoError {
                         step,
-                        cause: err.into(),
-                    })
+                        offset,
+                        cause: io_err,
+                    });
                 }
-            },
+            }
+            Err(err) => {
+                return Err(CheckerError::ParseError {
+                    step,
+                    offset,
+                    step_bytes: source.step_bytes().to_vec(),
+                    cause: err.into(),
+                })
+            }
         }
     }
 
     process_unit_conflicts(ctx.borrow())
 }
 
+/// Convert an error while reading the proof header into a CheckerError.
+fn header_error(err: HeaderError) -> CheckerError {
+    match err {
+        HeaderError::Io(io_err) if io_err.kind() != io::ErrorKind::UnexpectedEof => {
+            CheckerError::IoError {
+                step: 0,
+                offset: 0,
+                cause: io_err,
+            }
+        }
+        HeaderError::UnsupportedVersion { version } => CheckerError::UnsupportedVersion {
+            version,
+            supported: FORMAT_VERSION,
+        },
+        cause => CheckerError::InvalidHeader { cause },
+    }
+}
+
+/// Record the error of a failed step and skip the step if the error limit permits.
+///
+/// Returns the error if checking cannot continue.
+fn recover_from_error(
+    mut ctx: partial!(Context, mut CheckerStateP),
+    err: CheckerError,
+) -> Result<(), CheckerError> {
+    let state = ctx.part_mut(CheckerStateP);
+    match err {
+        CheckerError::CheckFailed { .. }
+            if state.recovered_errors.len() + 1 < state.error_limit =>
+        {
+            state.recovered_errors.push(err);
+            Ok(())
+        }
+        err => Err(err),
+    }
+}
+
+/// Notify the proof processors that checking finished.
+///
+/// Returns the given result of the check, or the error of a failing proof processor.
+pub fn end_of_proof<'a>(
+    mut ctx: partial!(Context<'a>, mut ProcessingP<'a>, CheckerStateP),
+    result: Result<(), CheckerError>,
+) -> Result<(), CheckerError> {
+    let checker_state = ctx.part(CheckerStateP);
+    let verdict = match result {
+        Err(CheckerError::ProofProcessorError { .. }) => return result,
+        Err(_) => ProofVerdict::Failed,
+        Ok(()) if !checker_state.recovered_errors.is_empty() => ProofVerdict::Failed,
+        Ok(()) if checker_state.unsat => ProofVerdict::Unsat,
+        Ok(()) => ProofVerdict::NotUnsat,
+    };
+    let end_result = ctx.part_mut(ProcessingP).end_of_proof(verdict);
+    result.and(end_result)
+}
+
 /// Process unit conflicts detected during clause loading.
 pub fn process_unit_conflicts<'a>(
     mut ctx: partial!(Context<'a>, mut ProcessingP<'a>, ClausesP, VariablesP),
@@ -5,4 +5,8 @@ use varisat_formula::Lit;
 pub struct TmpData {
     /// Temporary storage for literals.
     pub tmp: Vec<Lit>,
+    /// Temporary storage for resolvents.
+    pub resolvent: Vec<Lit>,
+    /// Temporary storage for witnesses.
+    pub witness: Vec<Lit>,
 }
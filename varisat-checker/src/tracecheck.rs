@@ -0,0 +1,184 @@
+//! TraceCheck proof generation.
+use std::io::{self, BufWriter, Write};
+
+use thiserror::Error;
+
+use varisat_formula::Lit;
+
+use crate::processing::{
+    CheckedProofStep, CheckerData, ProcessorError, ProofProcessor, ProofVerdict,
+};
+
+/// Errors for steps that can't be represented in the TraceCheck format.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum TraceCheckError {
+    #[error("RAT clauses are not supported by the TraceCheck format")]
+    RatClause,
+    #[error("PR clauses are not supported by the TraceCheck format")]
+    PrClause,
+}
+
+/// Proof processor that generates a resolution proof in the TraceCheck format.
+///
+/// Input clauses are written without antecedents. Each derived clause is followed by a resolution
+/// chain, consisting of the conflicting clause followed by the propagating clauses in reverse
+/// order of propagation. Resolving these clauses from left to right yields a subset of the derived
+/// clause.
+///
+/// Clause deletions have no counterpart in the TraceCheck format and are ignored. RAT and PR
+/// clauses can't be represented and result in a [`TraceCheckError`].
+pub struct WriteTraceCheck<'a> {
+    target: BufWriter<Box<dyn Write + 'a>>,
+}
+
+impl<'a> ProofProcessor for WriteTraceCheck<'a> {
+    fn process_step(
+        &mut self,
+        step: &CheckedProofStep,
+        _data: CheckerData,
+    ) -> Result<(), ProcessorError> {
+        match step {
+            &CheckedProofStep::AddClause { id, clause }
+            | &CheckedProofStep::DuplicatedClause { id, clause, .. } => {
+                self.write_clause(id, clause)?;
+                self.write_chain(&[])?;
+            }
+            &CheckedProofStep::AtClause {
+                id,
+                clause,
+                propagations,
+                ..
+            } => {
+                self.write_clause(id, clause)?;
+                self.write_chain(propagations)?;
+            }
+            &CheckedProofStep::RatClause { .. } => {
+                return Err(TraceCheckError::RatClause.into());
+            }
+            &CheckedProofStep::PrClause { .. } => {
+                return Err(TraceCheckError::PrClause.into());
+            }
+            &CheckedProofStep::TautologicalClause { .. }
+            | &CheckedProofStep::UserVar { .. }
+            | &CheckedProofStep::SolverVar { .. }
+            | &CheckedProofStep::DeleteClause { .. }
+            | &CheckedProofStep::DeleteAtClause { .. }
+            | &CheckedProofStep::DeleteRatClause { .. }
+            | &CheckedProofStep::MakeIrredundant { .. }
+            | &CheckedProofStep::Model { .. }
+            | &CheckedProofStep::Assumptions { .. }
+            | &CheckedProofStep::FailedAssumptions { .. } => (),
+        }
+        Ok(())
+    }
+
+    fn end_of_proof(&mut self, _verdict: ProofVerdict) -> Result<(), ProcessorError> {
+        Ok(self.flush()?)
+    }
+}
+
+impl<'a> WriteTraceCheck<'a> {
+    /// Create a TraceCheck writing processor.
+    ///
+    /// The proof is written to `target`.
+    pub fn new(target: impl Write + 'a) -> WriteTraceCheck<'a> {
+        WriteTraceCheck {
+            target: BufWriter::new(Box::new(target)),
+        }
+    }
+
+    /// Write out all steps processed so far.
+    ///
+    /// This is automatically called when this proof processor is dropped. Calling this explicitly
+    /// is recommended to handle possible IO errors.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.target.flush()
+    }
+
+    /// Write the id and literals of a clause.
+    fn write_clause(&mut self, id: u64, clause: &[Lit]) -> io::Result<()> {
+        write!(self.target, "{} ", id + 1)?;
+        for &lit in clause {
+            write!(self.target, "{} ", lit.to_dimacs())?;
+        }
+        self.target.write_all(b"0 ")?;
+        Ok(())
+    }
+
+    /// Write the antecedents of a clause given the propagations of an AT check.
+    fn write_chain(&mut self, propagations: &[u64]) -> io::Result<()> {
+        for &id in propagations.iter().rev() {
+            write!(self.target, "{} ", id + 1)?;
+        }
+        self.target.write_all(b"0\n")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::{cnf_formula, lits};
+
+    use crate::{Checker, ProofStep};
+
+    #[test]
+    fn resolution_chains() {
+        let mut trace = vec![];
+
+        {
+            let mut write_trace = WriteTraceCheck::new(&mut trace);
+            let mut checker = Checker::new();
+            checker.add_processor(&mut write_trace);
+
+            checker
+                .add_formula(&cnf_formula![
+                    1, 2;
+                    -1, 2;
+                    -2, 3;
+                    -2, -3;
+                ])
+                .unwrap();
+
+            let hashes = [
+                checker.clause_hash(&lits![1, 2]),
+                checker.clause_hash(&lits![-1, 2]),
+            ];
+
+            checker
+                .check_step(ProofStep::AtClause {
+                    redundant: false,
+                    clause: &lits![2],
+                    propagation_hashes: &hashes[..],
+                })
+                .unwrap();
+
+            let hashes = [
+                checker.clause_hash(&lits![-2, 3]),
+                checker.clause_hash(&lits![-2, -3]),
+            ];
+
+            checker
+                .check_step(ProofStep::AtClause {
+                    redundant: false,
+                    clause: &[],
+                    propagation_hashes: &hashes[..],
+                })
+                .unwrap();
+
+            checker.check_delayed_steps().unwrap();
+        }
+
+        assert_eq!(
+            std::str::from_utf8(&trace).unwrap(),
+            "1 1 2 0 0\n\
+             2 -1 2 0 0\n\
+             3 -2 3 0 0\n\
+             4 -2 -3 0 0\n\
+             5 2 0 2 1 0\n\
+             6 0 4 3 5 0\n"
+        );
+    }
+}
@@ -1,9 +1,9 @@
 //! Proof transcripts.
-use failure::Error;
-
 use varisat_formula::{Lit, Var};
 
-use crate::processing::{CheckedProofStep, CheckedSamplingMode, CheckedUserVar, CheckerData};
+use crate::processing::{
+    CheckedProofStep, CheckedSamplingMode, CheckedUserVar, CheckerData, ProcessorError,
+};
 
 /// Step of a proof transcript.
 ///
@@ -26,7 +26,7 @@ pub enum ProofTranscriptStep<'a> {
 /// Implement to process transcript steps.
 pub trait ProofTranscriptProcessor {
     /// Process a single proof transcript step.
-    fn process_step(&mut self, step: &ProofTranscriptStep) -> Result<(), Error>;
+    fn process_step(&mut self, step: &ProofTranscriptStep) -> Result<(), ProcessorError>;
 }
 
 /// Create a transcript from proof steps
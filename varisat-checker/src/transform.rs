@@ -0,0 +1,469 @@
+//! Composable rewriting of checked proofs.
+use hashbrown::HashMap;
+use thiserror::Error;
+
+use crate::processing::{
+    CheckedProofStep, CheckerData, ProcessorError, ProofProcessor, ProofVerdict,
+    ResolutionPropagations,
+};
+
+/// Errors of the built-in proof transformers.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum TransformError {
+    #[error("unknown clause id {id}")]
+    UnknownClause { id: u64 },
+}
+
+/// Implement to rewrite checked proof steps.
+///
+/// A transformer receives the steps of a proof and passes the rewritten steps on to a target
+/// processor. It can drop steps, change them or emit additional steps. Transformers are applied
+/// using a [`TransformProcessor`], which can also be the target of another transformer, so that
+/// multiple transformations can be chained within a single pass over the proof.
+pub trait ProofTransformer {
+    /// Rewrite a single step, passing the result to `target`.
+    fn transform_step(
+        &mut self,
+        step: &CheckedProofStep,
+        data: CheckerData,
+        target: &mut dyn ProofProcessor,
+    ) -> Result<(), ProcessorError>;
+
+    /// Called once when checking the proof finished.
+    ///
+    /// The default implementation forwards the verdict to `target`.
+    fn end_of_proof(
+        &mut self,
+        verdict: ProofVerdict,
+        target: &mut dyn ProofProcessor,
+    ) -> Result<(), ProcessorError> {
+        target.end_of_proof(verdict)
+    }
+}
+
+/// Proof processor that applies a [`ProofTransformer`] before passing steps to another processor.
+pub struct TransformProcessor<T, P> {
+    transformer: T,
+    target: P,
+}
+
+impl<T: ProofTransformer, P: ProofProcessor> TransformProcessor<T, P> {
+    /// Pass steps transformed by `transformer` to `target`.
+    pub fn new(transformer: T, target: P) -> TransformProcessor<T, P> {
+        TransformProcessor {
+            transformer,
+            target,
+        }
+    }
+
+    /// The processor receiving the transformed steps.
+    pub fn target(&self) -> &P {
+        &self.target
+    }
+
+    /// The processor receiving the transformed steps.
+    pub fn target_mut(&mut self) -> &mut P {
+        &mut self.target
+    }
+
+    /// Return the transformer and the target processor.
+    pub fn into_inner(self) -> (T, P) {
+        (self.transformer, self.target)
+    }
+}
+
+impl<T: ProofTransformer, P: ProofProcessor> ProofProcessor for TransformProcessor<T, P> {
+    fn process_step(
+        &mut self,
+        step: &CheckedProofStep,
+        data: CheckerData,
+    ) -> Result<(), ProcessorError> {
+        self.transformer
+            .transform_step(step, data, &mut self.target)
+    }
+
+    fn end_of_proof(&mut self, verdict: ProofVerdict) -> Result<(), ProcessorError> {
+        self.transformer.end_of_proof(verdict, &mut self.target)
+    }
+}
+
+/// Transformer that removes all clause deletions.
+///
+/// Clauses deleted as asymmetric tautologies or RAT clauses stay irredundant, so this can
+/// invalidate later RAT and PR steps. It is intended for processors that only need the derivation
+/// of clauses.
+#[derive(Default)]
+pub struct DropDeletions;
+
+impl ProofTransformer for DropDeletions {
+    fn transform_step(
+        &mut self,
+        step: &CheckedProofStep,
+        data: CheckerData,
+        target: &mut dyn ProofProcessor,
+    ) -> Result<(), ProcessorError> {
+        match step {
+            CheckedProofStep::DeleteClause { .. }
+            | CheckedProofStep::DeleteAtClause { .. }
+            | CheckedProofStep::DeleteRatClause { .. } => Ok(()),
+            _ => target.process_step(step, data),
+        }
+    }
+}
+
+/// Transformer that removes the steps for duplicated input clauses.
+///
+/// The checker already uses a single id for all copies of a clause, so the ids of
+/// [`DuplicatedClause`](CheckedProofStep::DuplicatedClause) steps are never referenced.
+#[derive(Default)]
+pub struct MergeDuplicates;
+
+impl ProofTransformer for MergeDuplicates {
+    fn transform_step(
+        &mut self,
+        step: &CheckedProofStep,
+        data: CheckerData,
+        target: &mut dyn ProofProcessor,
+    ) -> Result<(), ProcessorError> {
+        match step {
+            CheckedProofStep::DuplicatedClause { .. } => Ok(()),
+            _ => target.process_step(step, data),
+        }
+    }
+}
+
+/// Transformer that renumbers clauses with consecutive ids.
+///
+/// Clauses are numbered in the order they are added, starting at zero. This is useful after steps
+/// introducing clauses were removed by another transformer.
+#[derive(Default)]
+pub struct RenumberIds {
+    /// New ids of the present clauses.
+    ids: HashMap<u64, u64>,
+    next_id: u64,
+    propagations: Vec<u64>,
+    resolutions: ResolutionPropagations,
+}
+
+impl RenumberIds {
+    /// Assign the next id to a new clause.
+    fn add_id(&mut self, id: u64) -> u64 {
+        let new_id = self.skip_id();
+        self.ids.insert(id, new_id);
+        new_id
+    }
+
+    /// Allocate an id for a clause that is never referenced.
+    fn skip_id(&mut self) -> u64 {
+        let new_id = self.next_id;
+        self.next_id += 1;
+        new_id
+    }
+
+    /// Remove a deleted clause, returning its id.
+    fn remove_id(&mut self, id: u64) -> Result<u64, TransformError> {
+        self.ids
+            .remove(&id)
+            .ok_or(TransformError::UnknownClause { id })
+    }
+
+    /// Store the renumbered ids of a propagation list in `self.propagations`.
+    fn map_propagations(&mut self, propagations: &[u64]) -> Result<(), TransformError> {
+        self.propagations.clear();
+        map_ids(&self.ids, propagations, &mut self.propagations)
+    }
+
+    /// Store the renumbered ids of resolution propagations in `self.resolutions`.
+    fn map_resolutions(
+        &mut self,
+        resolutions: &ResolutionPropagations,
+    ) -> Result<(), TransformError> {
+        self.resolutions.clear();
+        for (id, propagations) in resolutions.iter() {
+            self.propagations.clear();
+            map_ids(&self.ids, propagations, &mut self.propagations)?;
+            let id = map_id(&self.ids, id)?;
+            self.resolutions.push(id, &self.propagations);
+        }
+        Ok(())
+    }
+}
+
+/// Renumber a single clause id.
+fn map_id(ids: &HashMap<u64, u64>, id: u64) -> Result<u64, TransformError> {
+    ids.get(&id)
+        .cloned()
+        .ok_or(TransformError::UnknownClause { id })
+}
+
+/// Renumber a list of clause ids.
+fn map_ids(
+    ids: &HashMap<u64, u64>,
+    source: &[u64],
+    target: &mut Vec<u64>,
+) -> Result<(), TransformError> {
+    for &id in source {
+        target.push(map_id(ids, id)?);
+    }
+    Ok(())
+}
+
+impl ProofTransformer for RenumberIds {
+    fn transform_step(
+        &mut self,
+        step: &CheckedProofStep,
+        data: CheckerData,
+        target: &mut dyn ProofProcessor,
+    ) -> Result<(), ProcessorError> {
+        let step = match *step {
+            CheckedProofStep::AddClause { id, clause } => CheckedProofStep::AddClause {
+                id: self.add_id(id),
+                clause,
+            },
+            CheckedProofStep::DuplicatedClause {
+                same_as_id, clause, ..
+            } => CheckedProofStep::DuplicatedClause {
+                id: self.skip_id(),
+                same_as_id: map_id(&self.ids, same_as_id)?,
+                clause,
+            },
+            CheckedProofStep::TautologicalClause { clause, .. } => {
+                CheckedProofStep::TautologicalClause {
+                    id: self.skip_id(),
+                    clause,
+                }
+            }
+            CheckedProofStep::AtClause {
+                id,
+                redundant,
+                clause,
+                propagations,
+            } => {
+                self.map_propagations(propagations)?;
+                CheckedProofStep::AtClause {
+                    id: self.add_id(id),
+                    redundant,
+                    clause,
+                    propagations: &self.propagations,
+                }
+            }
+            CheckedProofStep::RatClause {
+                id,
+                clause,
+                pivot,
+                propagations,
+            } => {
+                self.map_resolutions(propagations)?;
+                CheckedProofStep::RatClause {
+                    id: self.add_id(id),
+                    clause,
+                    pivot,
+                    propagations: &self.resolutions,
+                }
+            }
+            CheckedProofStep::PrClause {
+                id,
+                clause,
+                witness,
+                propagations,
+            } => {
+                self.map_resolutions(propagations)?;
+                CheckedProofStep::PrClause {
+                    id: self.add_id(id),
+                    clause,
+                    witness,
+                    propagations: &self.resolutions,
+                }
+            }
+            CheckedProofStep::DeleteClause { id, clause } => CheckedProofStep::DeleteClause {
+                id: self.remove_id(id)?,
+                clause,
+            },
+            CheckedProofStep::DeleteAtClause {
+                id,
+                keep_as_redundant,
+                clause,
+                propagations,
+            } => {
+                self.map_propagations(propagations)?;
+                let id = if keep_as_redundant {
+                    map_id(&self.ids, id)?
+                } else {
+                    self.remove_id(id)?
+                };
+                CheckedProofStep::DeleteAtClause {
+                    id,
+                    keep_as_redundant,
+                    clause,
+                    propagations: &self.propagations,
+                }
+            }
+            CheckedProofStep::DeleteRatClause {
+                id,
+                keep_as_redundant,
+                clause,
+                pivot,
+                propagations,
+            } => {
+                self.map_resolutions(propagations)?;
+                let id = if keep_as_redundant {
+                    map_id(&self.ids, id)?
+                } else {
+                    self.remove_id(id)?
+                };
+                CheckedProofStep::DeleteRatClause {
+                    id,
+                    keep_as_redundant,
+                    clause,
+                    pivot,
+                    propagations: &self.resolutions,
+                }
+            }
+            CheckedProofStep::MakeIrredundant { id, clause } => CheckedProofStep::MakeIrredundant {
+                id: map_id(&self.ids, id)?,
+                clause,
+            },
+            CheckedProofStep::FailedAssumptions {
+                failed_core,
+                propagations,
+            } => {
+                self.map_propagations(propagations)?;
+                CheckedProofStep::FailedAssumptions {
+                    failed_core,
+                    propagations: &self.propagations,
+                }
+            }
+            CheckedProofStep::UserVar { .. }
+            | CheckedProofStep::SolverVar { .. }
+            | CheckedProofStep::Model { .. }
+            | CheckedProofStep::Assumptions { .. } => return target.process_step(step, data),
+        };
+
+        target.process_step(&step, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::{cnf_formula, lits};
+
+    use crate::internal::SelfChecker;
+    use crate::{Checker, DeleteClauseProof, ProofStep};
+
+    /// Records the ids and propagations of clause steps.
+    #[derive(Default)]
+    struct RecordIds {
+        steps: Vec<(&'static str, u64, Vec<u64>)>,
+        verdict: Option<ProofVerdict>,
+    }
+
+    impl ProofProcessor for RecordIds {
+        fn process_step(
+            &mut self,
+            step: &CheckedProofStep,
+            _data: CheckerData,
+        ) -> Result<(), ProcessorError> {
+            let record = match *step {
+                CheckedProofStep::AddClause { id, .. } => ("add", id, vec![]),
+                CheckedProofStep::DuplicatedClause { id, same_as_id, .. } => {
+                    ("duplicate", id, vec![same_as_id])
+                }
+                CheckedProofStep::AtClause {
+                    id, propagations, ..
+                } => ("at", id, propagations.to_vec()),
+                CheckedProofStep::DeleteClause { id, .. } => ("delete", id, vec![]),
+                _ => return Ok(()),
+            };
+            self.steps.push(record);
+            Ok(())
+        }
+
+        fn end_of_proof(&mut self, verdict: ProofVerdict) -> Result<(), ProcessorError> {
+            self.verdict = Some(verdict);
+            Ok(())
+        }
+    }
+
+    fn check_transformed(target: &mut dyn ProofProcessor) {
+        let mut checker = Checker::new();
+        checker.add_processor(target);
+
+        checker
+            .add_formula(&cnf_formula![
+                3, 4;
+                1, 2;
+                3, 4;
+                -1, 2;
+            ])
+            .unwrap();
+
+        let hashes = [
+            checker.clause_hash(&lits![1, 2]),
+            checker.clause_hash(&lits![-1, 2]),
+        ];
+
+        checker
+            .self_check_step(ProofStep::AtClause {
+                redundant: true,
+                clause: &lits![2, 3],
+                propagation_hashes: &hashes[..],
+            })
+            .unwrap();
+
+        checker
+            .self_check_step(ProofStep::DeleteClause {
+                clause: &lits![2, 3],
+                proof: DeleteClauseProof::Redundant,
+            })
+            .unwrap();
+
+        checker.self_check_step(ProofStep::End).unwrap();
+    }
+
+    #[test]
+    fn identity() {
+        let mut record = RecordIds::default();
+        check_transformed(&mut record);
+
+        assert_eq!(
+            record.steps,
+            vec![
+                ("add", 0, vec![]),
+                ("add", 1, vec![]),
+                ("duplicate", 2, vec![0]),
+                ("add", 3, vec![]),
+                ("at", 4, vec![1, 3]),
+                ("delete", 4, vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_drop_renumber() {
+        let mut record = RecordIds::default();
+        {
+            let mut pipeline = TransformProcessor::new(
+                MergeDuplicates,
+                TransformProcessor::new(
+                    DropDeletions,
+                    TransformProcessor::new(RenumberIds::default(), &mut record),
+                ),
+            );
+            check_transformed(&mut pipeline);
+        }
+
+        assert_eq!(
+            record.steps,
+            vec![
+                ("add", 0, vec![]),
+                ("add", 1, vec![]),
+                ("add", 2, vec![]),
+                ("at", 3, vec![1, 2]),
+            ]
+        );
+        assert_eq!(record.verdict, Some(ProofVerdict::NotUnsat));
+    }
+}
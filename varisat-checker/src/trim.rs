@@ -0,0 +1,505 @@
+//! Backward trimming of checked proofs.
+use std::ops::Range;
+
+use hashbrown::HashSet;
+
+use varisat_formula::{Lit, Var};
+
+use crate::processing::{
+    CheckedProofStep, CheckedSamplingMode, CheckedUserVar, CheckerData, ProcessorError,
+    ProofProcessor, ProofVerdict, ResolutionPropagations,
+};
+
+/// A buffered proof step.
+enum TrimStep {
+    UserVar {
+        var: Var,
+        user_var: Option<(Var, bool, bool)>,
+    },
+    SolverVar {
+        var: Var,
+        solver_var: Option<Var>,
+    },
+    Input {
+        id: u64,
+        lits: Range<usize>,
+    },
+    Duplicate {
+        id: u64,
+        same_as_id: u64,
+        lits: Range<usize>,
+    },
+    Tautology {
+        id: u64,
+        lits: Range<usize>,
+    },
+    Lemma {
+        id: u64,
+        redundant: bool,
+        lits: Range<usize>,
+        propagations: Range<usize>,
+    },
+    RatLemma {
+        id: u64,
+        pivot: Lit,
+        lits: Range<usize>,
+        resolutions: Range<usize>,
+    },
+    PrLemma {
+        id: u64,
+        lits: Range<usize>,
+        witness: Range<usize>,
+        resolutions: Range<usize>,
+    },
+    Delete {
+        id: u64,
+        lits: Range<usize>,
+    },
+    MakeIrredundant {
+        id: u64,
+        lits: Range<usize>,
+    },
+}
+
+/// Proof processor that removes lemmas not needed to derive the empty clause.
+///
+/// All steps are buffered until the empty clause is derived. Then a backward pass starting from
+/// the empty clause determines the lemmas it depends on. The trimmed proof, consisting of the
+/// input clauses, the needed lemmas and the deletions of clauses that remain, is passed on to the
+/// proof processors added to the trimmer.
+///
+/// As the trimmed proof only certifies unsatisfiability, all deletions are passed on as
+/// [`DeleteClause`](CheckedProofStep::DeleteClause) steps. Steps following the empty clause are
+/// ignored.
+#[derive(Default)]
+pub struct ProofTrimmer<'a> {
+    processors: Vec<&'a mut dyn ProofProcessor>,
+    steps: Vec<TrimStep>,
+    lits: Vec<Lit>,
+    propagations: Vec<u64>,
+    /// Resolved clauses of RAT lemmas and their propagations.
+    resolutions: Vec<(u64, Range<usize>)>,
+    finished: bool,
+}
+
+impl<'a> ProofProcessor for ProofTrimmer<'a> {
+    fn process_step(
+        &mut self,
+        step: &CheckedProofStep,
+        data: CheckerData,
+    ) -> Result<(), ProcessorError> {
+        if self.finished {
+            return Ok(());
+        }
+
+        let trim_step = match step {
+            &CheckedProofStep::UserVar { var, ref user_var } => TrimStep::UserVar {
+                var,
+                user_var: user_var.as_ref().map(|user_var| {
+                    let witness = match user_var.sampling_mode {
+                        CheckedSamplingMode::Witness => true,
+                        CheckedSamplingMode::Sample => false,
+                    };
+                    (user_var.user_var, witness, user_var.new_var)
+                }),
+            },
+            &CheckedProofStep::SolverVar { var, solver_var } => {
+                TrimStep::SolverVar { var, solver_var }
+            }
+            &CheckedProofStep::AddClause { id, clause } => TrimStep::Input {
+                id,
+                lits: self.store_lits(clause),
+            },
+            &CheckedProofStep::DuplicatedClause {
+                id,
+                same_as_id,
+                clause,
+            } => TrimStep::Duplicate {
+                id,
+                same_as_id,
+                lits: self.store_lits(clause),
+            },
+            &CheckedProofStep::TautologicalClause { id, clause } => TrimStep::Tautology {
+                id,
+                lits: self.store_lits(clause),
+            },
+            &CheckedProofStep::AtClause {
+                id,
+                redundant,
+                clause,
+                propagations,
+            } => {
+                let begin = self.propagations.len();
+                self.propagations.extend_from_slice(propagations);
+                let trim_step = TrimStep::Lemma {
+                    id,
+                    redundant,
+                    lits: self.store_lits(clause),
+                    propagations: begin..self.propagations.len(),
+                };
+                if clause.is_empty() {
+                    self.steps.push(trim_step);
+                    self.finished = true;
+                    return self.trim(data);
+                }
+                trim_step
+            }
+            &CheckedProofStep::RatClause {
+                id,
+                clause,
+                pivot,
+                propagations,
+            } => {
+                let resolutions = self.store_resolutions(propagations);
+                TrimStep::RatLemma {
+                    id,
+                    pivot,
+                    lits: self.store_lits(clause),
+                    resolutions,
+                }
+            }
+            &CheckedProofStep::PrClause {
+                id,
+                clause,
+                witness,
+                propagations,
+            } => {
+                let resolutions = self.store_resolutions(propagations);
+                TrimStep::PrLemma {
+                    id,
+                    lits: self.store_lits(clause),
+                    witness: self.store_lits(witness),
+                    resolutions,
+                }
+            }
+            &CheckedProofStep::DeleteClause { id, clause }
+            | &CheckedProofStep::DeleteAtClause { id, clause, .. }
+            | &CheckedProofStep::DeleteRatClause { id, clause, .. } => TrimStep::Delete {
+                id,
+                lits: self.store_lits(clause),
+            },
+            &CheckedProofStep::MakeIrredundant { id, clause } => TrimStep::MakeIrredundant {
+                id,
+                lits: self.store_lits(clause),
+            },
+            &CheckedProofStep::Model { .. }
+            | &CheckedProofStep::Assumptions { .. }
+            | &CheckedProofStep::FailedAssumptions { .. } => return Ok(()),
+        };
+
+        self.steps.push(trim_step);
+
+        Ok(())
+    }
+
+    fn end_of_proof(&mut self, verdict: ProofVerdict) -> Result<(), ProcessorError> {
+        for processor in self.processors.iter_mut() {
+            processor.end_of_proof(verdict)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ProofTrimmer<'a> {
+    /// Create a new proof trimmer.
+    pub fn new() -> ProofTrimmer<'a> {
+        ProofTrimmer::default()
+    }
+
+    /// Add a [`ProofProcessor`] that receives the trimmed proof.
+    pub fn add_processor(&mut self, processor: &'a mut dyn ProofProcessor) {
+        self.processors.push(processor);
+    }
+
+    /// Copy literals into the buffer.
+    fn store_lits(&mut self, lits: &[Lit]) -> Range<usize> {
+        let begin = self.lits.len();
+        self.lits.extend_from_slice(lits);
+        begin..self.lits.len()
+    }
+
+    /// Copy resolved clauses and their propagations into the buffer.
+    fn store_resolutions(&mut self, propagations: &ResolutionPropagations) -> Range<usize> {
+        let begin = self.resolutions.len();
+        for (resolved_id, resolvent_propagations) in propagations.iter() {
+            let propagations_begin = self.propagations.len();
+            self.propagations.extend_from_slice(resolvent_propagations);
+            self.resolutions
+                .push((resolved_id, propagations_begin..self.propagations.len()));
+        }
+        begin..self.resolutions.len()
+    }
+
+    /// Determine the needed lemmas and pass on the trimmed proof.
+    fn trim(&mut self, data: CheckerData) -> Result<(), ProcessorError> {
+        let mut used = HashSet::new();
+
+        for step in self.steps.iter().rev() {
+            match step {
+                TrimStep::Lemma {
+                    id,
+                    lits,
+                    propagations,
+                    ..
+                } => {
+                    if lits.start == lits.end || used.contains(id) {
+                        used.extend(self.propagations[propagations.clone()].iter().cloned());
+                    }
+                }
+                TrimStep::RatLemma {
+                    id, resolutions, ..
+                }
+                | TrimStep::PrLemma {
+                    id, resolutions, ..
+                } => {
+                    if used.contains(id) {
+                        for (resolved_id, propagations) in
+                            self.resolutions[resolutions.clone()].iter()
+                        {
+                            used.insert(*resolved_id);
+                            used.extend(self.propagations[propagations.clone()].iter().cloned());
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let mut resolution_propagations = ResolutionPropagations::default();
+
+        let mut present = HashSet::new();
+        let mut dropped = 0;
+
+        for step in self.steps.iter() {
+            let lits = &self.lits;
+            let checked_step = match step {
+                &TrimStep::UserVar { var, user_var } => CheckedProofStep::UserVar {
+                    var,
+                    user_var: user_var.map(|(user_var, witness, new_var)| CheckedUserVar {
+                        user_var,
+                        sampling_mode: if witness {
+                            CheckedSamplingMode::Witness
+                        } else {
+                            CheckedSamplingMode::Sample
+                        },
+                        new_var,
+                    }),
+                },
+                &TrimStep::SolverVar { var, solver_var } => {
+                    CheckedProofStep::SolverVar { var, solver_var }
+                }
+                TrimStep::Input { id, lits: range } => {
+                    present.insert(*id);
+                    CheckedProofStep::AddClause {
+                        id: *id,
+                        clause: &lits[range.clone()],
+                    }
+                }
+                TrimStep::Duplicate {
+                    id,
+                    same_as_id,
+                    lits: range,
+                } => CheckedProofStep::DuplicatedClause {
+                    id: *id,
+                    same_as_id: *same_as_id,
+                    clause: &lits[range.clone()],
+                },
+                TrimStep::Tautology { id, lits: range } => CheckedProofStep::TautologicalClause {
+                    id: *id,
+                    clause: &lits[range.clone()],
+                },
+                TrimStep::Lemma {
+                    id,
+                    redundant,
+                    lits: range,
+                    propagations,
+                } => {
+                    if range.start != range.end && !used.contains(id) {
+                        dropped += 1;
+                        continue;
+                    }
+                    present.insert(*id);
+                    CheckedProofStep::AtClause {
+                        id: *id,
+                        redundant: *redundant,
+                        clause: &lits[range.clone()],
+                        propagations: &self.propagations[propagations.clone()],
+                    }
+                }
+                TrimStep::RatLemma {
+                    id,
+                    pivot,
+                    lits: range,
+                    resolutions,
+                } => {
+                    if !used.contains(id) {
+                        dropped += 1;
+                        continue;
+                    }
+                    present.insert(*id);
+                    resolution_propagations.clear();
+                    for (resolved_id, propagations) in self.resolutions[resolutions.clone()].iter()
+                    {
+                        resolution_propagations
+                            .push(*resolved_id, &self.propagations[propagations.clone()]);
+                    }
+                    CheckedProofStep::RatClause {
+                        id: *id,
+                        clause: &lits[range.clone()],
+                        pivot: *pivot,
+                        propagations: &resolution_propagations,
+                    }
+                }
+                TrimStep::PrLemma {
+                    id,
+                    lits: range,
+                    witness,
+                    resolutions,
+                } => {
+                    if !used.contains(id) {
+                        dropped += 1;
+                        continue;
+                    }
+                    present.insert(*id);
+                    resolution_propagations.clear();
+                    for (resolved_id, propagations) in self.resolutions[resolutions.clone()].iter()
+                    {
+                        resolution_propagations
+                            .push(*resolved_id, &self.propagations[propagations.clone()]);
+                    }
+                    CheckedProofStep::PrClause {
+                        id: *id,
+                        clause: &lits[range.clone()],
+                        witness: &lits[witness.clone()],
+                        propagations: &resolution_propagations,
+                    }
+                }
+                TrimStep::Delete { id, lits: range } => {
+                    if !present.remove(id) {
+                        continue;
+                    }
+                    CheckedProofStep::DeleteClause {
+                        id: *id,
+                        clause: &lits[range.clone()],
+                    }
+                }
+                TrimStep::MakeIrredundant { id, lits: range } => {
+                    if !present.contains(id) {
+                        continue;
+                    }
+                    CheckedProofStep::MakeIrredundant {
+                        id: *id,
+                        clause: &lits[range.clone()],
+                    }
+                }
+            };
+
+            for processor in self.processors.iter_mut() {
+                processor.process_step(&checked_step, data)?;
+            }
+        }
+
+        log::info!("trimming removed {} lemmas", dropped);
+
+        self.steps.clear();
+        self.lits.clear();
+        self.propagations.clear();
+        self.resolutions.clear();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::{cnf_formula, lits};
+
+    use crate::{Checker, DeleteClauseProof, ProofStep, WriteTraceCheck};
+
+    #[test]
+    fn unused_lemma_is_removed() {
+        let mut trace = vec![];
+
+        {
+            let mut write_trace = WriteTraceCheck::new(&mut trace);
+            let mut trimmer = ProofTrimmer::new();
+            trimmer.add_processor(&mut write_trace);
+
+            let mut checker = Checker::new();
+            checker.add_processor(&mut trimmer);
+
+            checker
+                .add_formula(&cnf_formula![
+                    1, 2;
+                    -1, 2;
+                    -2, 3;
+                    -2, -3;
+                    3, 4, 5;
+                    -3, 4, 5;
+                ])
+                .unwrap();
+
+            let hashes = [
+                checker.clause_hash(&lits![3, 4, 5]),
+                checker.clause_hash(&lits![-3, 4, 5]),
+            ];
+
+            checker
+                .check_step(ProofStep::AtClause {
+                    redundant: true,
+                    clause: &lits![4, 5],
+                    propagation_hashes: &hashes[..],
+                })
+                .unwrap();
+
+            let hashes = [
+                checker.clause_hash(&lits![1, 2]),
+                checker.clause_hash(&lits![-1, 2]),
+            ];
+
+            checker
+                .check_step(ProofStep::AtClause {
+                    redundant: true,
+                    clause: &lits![2],
+                    propagation_hashes: &hashes[..],
+                })
+                .unwrap();
+
+            checker
+                .check_step(ProofStep::DeleteClause {
+                    clause: &lits![4, 5],
+                    proof: DeleteClauseProof::Redundant,
+                })
+                .unwrap();
+
+            let hashes = [
+                checker.clause_hash(&lits![-2, 3]),
+                checker.clause_hash(&lits![-2, -3]),
+            ];
+
+            checker
+                .check_step(ProofStep::AtClause {
+                    redundant: false,
+                    clause: &[],
+                    propagation_hashes: &hashes[..],
+                })
+                .unwrap();
+
+            checker.check_delayed_steps().unwrap();
+        }
+
+        assert_eq!(
+            std::str::from_utf8(&trace).unwrap(),
+            "1 1 2 0 0\n\
+             2 -1 2 0 0\n\
+             3 -2 3 0 0\n\
+             4 -2 -3 0 0\n\
+             5 3 4 5 0 0\n\
+             6 -3 4 5 0 0\n\
+             8 2 0 2 1 0\n\
+             9 0 4 3 8 0\n"
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! Extraction of unsatisfiable cores.
+use std::io;
+use std::ops::Range;
+
+use hashbrown::HashSet;
+use thiserror::Error;
+
+use varisat_dimacs::write_dimacs;
+use varisat_formula::{CnfFormula, ExtendFormula, Lit};
+
+use crate::processing::{CheckedProofStep, CheckerData, ProcessorError, ProofProcessor};
+
+/// Errors while writing an unsatisfiable core.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum UnsatCoreError {
+    #[error("the empty clause was not derived")]
+    NotDerived,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Proof processor that computes an unsatisfiable core of the input formula.
+///
+/// The core consists of all input clauses used to derive the empty clause. Input clauses are
+/// identified by their clause id, which is their 0-based position in the input formula.
+#[derive(Default)]
+pub struct UnsatCore {
+    /// Ids and literal ranges of the input clauses.
+    inputs: Vec<(u64, Range<usize>)>,
+    /// Literals of input clauses using user variables.
+    lits: Vec<Lit>,
+    /// Ids and propagation ranges of derived clauses.
+    lemmas: Vec<(u64, Range<usize>)>,
+    propagations: Vec<u64>,
+    /// Ids of the core's clauses, once the empty clause was derived.
+    core: Option<Vec<u64>>,
+    /// Position of the core's clauses in `inputs`.
+    core_inputs: Vec<usize>,
+}
+
+impl ProofProcessor for UnsatCore {
+    fn process_step(
+        &mut self,
+        step: &CheckedProofStep,
+        data: CheckerData,
+    ) -> Result<(), ProcessorError> {
+        if self.core.is_some() {
+            return Ok(());
+        }
+
+        match step {
+            &CheckedProofStep::AddClause { id, clause } => {
+                let begin = self.lits.len();
+                self.lits.extend(clause.iter().map(|lit| {
+                    let user_var = data.user_from_proof_var(lit.var()).unwrap_or(lit.var());
+                    user_var.lit(lit.is_positive())
+                }));
+                self.inputs.push((id, begin..self.lits.len()));
+            }
+            &CheckedProofStep::DuplicatedClause { id, same_as_id, .. } => {
+                // Depending on the duplicate is the same as depending on the original clause
+                let begin = self.propagations.len();
+                self.propagations.push(same_as_id);
+                self.lemmas.push((id, begin..self.propagations.len()));
+            }
+            &CheckedProofStep::AtClause {
+                id,
+                clause,
+                propagations,
+                ..
+            } => {
+                let begin = self.propagations.len();
+                self.propagations.extend_from_slice(propagations);
+                self.lemmas.push((id, begin..self.propagations.len()));
+                if clause.is_empty() {
+                    self.compute_core();
+                }
+            }
+            &CheckedProofStep::RatClause {
+                id, propagations, ..
+            }
+            | &CheckedProofStep::PrClause {
+                id, propagations, ..
+            } => {
+                // A RAT or PR clause depends on all resolved or reduced clauses and the clauses
+                // used to show that the resolvents are ATs.
+                let begin = self.propagations.len();
+                for (resolved_id, resolvent_propagations) in propagations.iter() {
+                    self.propagations.push(resolved_id);
+                    self.propagations.extend_from_slice(resolvent_propagations);
+                }
+                self.lemmas.push((id, begin..self.propagations.len()));
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+impl UnsatCore {
+    /// Create a new unsat core processor.
+    pub fn new() -> UnsatCore {
+        UnsatCore::default()
+    }
+
+    /// Ids of the input clauses in the unsatisfiable core.
+    ///
+    /// Returns `None` if the empty clause wasn't derived yet. The ids are sorted.
+    pub fn core_ids(&self) -> Option<&[u64]> {
+        self.core.as_ref().map(|core| &core[..])
+    }
+
+    /// The unsatisfiable core as a formula.
+    ///
+    /// Returns `None` if the empty clause wasn't derived yet.
+    pub fn core_formula(&self) -> Option<CnfFormula> {
+        self.core.as_ref()?;
+        let mut formula = CnfFormula::new();
+        for &index in self.core_inputs.iter() {
+            formula.add_clause(&self.lits[self.inputs[index].1.clone()]);
+        }
+        Some(formula)
+    }
+
+    /// Write the unsatisfiable core in DIMACS CNF format.
+    pub fn write_dimacs(&self, target: &mut impl io::Write) -> Result<(), UnsatCoreError> {
+        match self.core_formula() {
+            Some(formula) => Ok(write_dimacs(target, &formula)?),
+            None => Err(UnsatCoreError::NotDerived),
+        }
+    }
+
+    /// Find all input clauses the last derived clause depends on.
+    fn compute_core(&mut self) {
+        let mut used = HashSet::new();
+
+        for (index, (id, propagations)) in self.lemmas.iter().enumerate().rev() {
+            if index + 1 == self.lemmas.len() || used.contains(id) {
+                used.extend(self.propagations[propagations.clone()].iter().cloned());
+            }
+        }
+
+        self.core_inputs = (0..self.inputs.len())
+            .filter(|&index| used.contains(&self.inputs[index].0))
+            .collect();
+
+        self.core = Some(
+            self.core_inputs
+                .iter()
+                .map(|&index| self.inputs[index].0)
+                .collect(),
+        );
+
+        self.lemmas.clear();
+        self.propagations.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::{cnf_formula, lits};
+
+    use crate::{Checker, ProofStep};
+
+    #[test]
+    fn core_of_refutation() {
+        let mut unsat_core = UnsatCore::new();
+
+        {
+            let mut checker = Checker::new();
+            checker.add_processor(&mut unsat_core);
+
+            checker
+                .add_formula(&cnf_formula![
+                    1, 2;
+                    3, 4;
+                    -1, 2;
+                    -2, 3;
+                    -3, 4;
+                    -2, -3;
+                ])
+                .unwrap();
+
+            let hashes = [
+                checker.clause_hash(&lits![1, 2]),
+                checker.clause_hash(&lits![-1, 2]),
+            ];
+
+            checker
+                .check_step(ProofStep::AtClause {
+                    redundant: true,
+                    clause: &lits![2],
+                    propagation_hashes: &hashes[..],
+                })
+                .unwrap();
+
+            let hashes = [
+                checker.clause_hash(&lits![-2, 3]),
+                checker.clause_hash(&lits![-2, -3]),
+            ];
+
+            checker
+                .check_step(ProofStep::AtClause {
+                    redundant: true,
+                    clause: &[],
+                    propagation_hashes: &hashes[..],
+                })
+                .unwrap();
+
+            checker.check_delayed_steps().unwrap();
+        }
+
+        assert_eq!(unsat_core.core_ids(), Some(&[0, 2, 3, 5][..]));
+
+        let mut dimacs = vec![];
+        unsat_core.write_dimacs(&mut dimacs).unwrap();
+
+        assert_eq!(
+            std::str::from_utf8(&dimacs).unwrap(),
+            "p cnf 3 4\n1 2 0\n-1 2 0\n-2 3 0\n-2 -3 0\n"
+        );
+    }
+}
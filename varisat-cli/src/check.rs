@@ -1,31 +1,149 @@
+use std::error::Error;
 use std::fs;
 use std::io;
 
-use clap::{App, ArgMatches, SubCommand};
-use failure::Error;
+use clap::{App, ArgGroup, ArgMatches, SubCommand};
 
-use varisat::checker::{Checker, CheckerError};
-use varisat_lrat::WriteLrat;
+use varisat_checker::{
+    Checker, CheckerConfig, CheckerError, DratFormat, ProofProcessor, ProofTrimmer, StepProfiler,
+    WriteClauseIdMap,
+};
+use varisat_lrat::{drat_to_lrat, LratFormat, WriteLrat};
 
-use super::{banner, init_logging};
+use super::{background_file, banner, init_logging};
 
 mod transcript;
 
 pub fn check_args() -> App<'static, 'static> {
     SubCommand::with_name("--check")
         .arg_from_usage("[INPUT] 'The input file to use (stdin if omitted)'")
-        .arg_from_usage("<proof-file> --proof=[FILE] 'The varisat proof file to check.'")
+        .arg_from_usage("[proof-file] --proof=[FILE] 'The varisat proof file to check.'")
+        .arg_from_usage(
+            "[model-file] --model=[FILE] 'Check a satisfying assignment in the SAT competition \
+             solution format instead of a proof.'",
+        )
+        .group(
+            ArgGroup::with_name("certificate")
+                .args(&["proof-file", "model-file"])
+                .required(true),
+        )
         .arg_from_usage("[lrat-file] --write-lrat=[FILE] 'Convert the proof to LRAT.'")
         .arg_from_usage(
             "[clrat-file] --write-clrat=[FILE] 'Convert the proof to compressed (binary) LRAT.'",
         )
+        .arg_from_usage(
+            "[id-map-file] --write-id-map=[FILE] 'Write the input clause or proof step of each \
+             LRAT clause id.'",
+        )
+        .arg_from_usage(
+            "[max-errors] --max-errors=[N] 'Continue after failed steps, reporting up to N \
+             errors.'",
+        )
+        .arg_from_usage(
+            "[spill-limit] --spill-limit=[N] 'Keep the literals of long clauses on disk when \
+             there are more than N clauses.'",
+        )
+        .arg_from_usage(
+            "[collision-diagnostics] --collision-diagnostics 'Report hash collisions between \
+             clauses when a step fails.'",
+        )
+        .arg_from_usage(
+            "[profile] --profile 'Report the time spent on each kind of step and the most \
+             expensive steps.'",
+        )
+        .arg_from_usage(
+            "[trim] --trim 'Remove lemmas not needed to derive the empty clause from the LRAT \
+             output.'",
+        )
+}
+
+pub fn drat_to_lrat_args() -> App<'static, 'static> {
+    SubCommand::with_name("--drat-to-lrat")
+        .arg_from_usage("[INPUT] 'The input file to use (stdin if omitted)'")
+        .arg_from_usage("<drat-file> --drat=<FILE> 'The DRAT proof file to convert.'")
+        .arg_from_usage("[binary-drat] --binary-drat 'Read the DRAT proof in the binary format.'")
+        .arg_from_usage("[lrat-file] --write-lrat=[FILE] 'Write the proof as LRAT.'")
+        .arg_from_usage(
+            "[clrat-file] --write-clrat=[FILE] 'Write the proof as compressed (binary) LRAT.'",
+        )
+        .group(
+            ArgGroup::with_name("output")
+                .args(&["lrat-file", "clrat-file"])
+                .required(true),
+        )
+}
+
+pub fn drat_to_lrat_main(matches: &ArgMatches) -> Result<i32, Box<dyn Error>> {
+    init_logging();
+    banner();
+
+    let dimacs: Box<dyn io::Read> = match matches.value_of("INPUT") {
+        Some(path) => {
+            log::info!("Reading file '{}'", path);
+            Box::new(fs::File::open(path)?)
+        }
+        None => {
+            log::info!("Reading from stdin");
+            Box::new(io::stdin())
+        }
+    };
+
+    let drat_format = if matches.is_present("binary-drat") {
+        DratFormat::Binary
+    } else {
+        DratFormat::Text
+    };
+
+    let (lrat_path, lrat_format) = match matches.value_of("lrat-file") {
+        Some(path) => (path, LratFormat::Text),
+        None => (matches.value_of("clrat-file").unwrap(), LratFormat::Binary),
+    };
+
+    let drat_path = matches.value_of("drat-file").unwrap();
+
+    log::info!("Converting DRAT proof file '{}'", drat_path);
+
+    match drat_to_lrat(
+        dimacs,
+        fs::File::open(drat_path)?,
+        drat_format,
+        background_file(lrat_path)?,
+        lrat_format,
+    ) {
+        Ok(()) => {
+            println!("s VERIFIED");
+            Ok(0)
+        }
+        Err(err) => {
+            log::error!("{}", err);
+            println!("s NOT VERIFIED");
+            Ok(1)
+        }
+    }
 }
 
-pub fn check_main(matches: &ArgMatches) -> Result<i32, Error> {
+pub fn check_main(matches: &ArgMatches) -> Result<i32, Box<dyn Error>> {
     init_logging();
     banner();
 
-    let mut checker = Checker::default();
+    let mut config = CheckerConfig::default();
+
+    if let Some(limit) = matches.value_of("spill-limit") {
+        config.clause_spill_limit = Some(limit.parse()?);
+    }
+
+    config.collision_diagnostics = matches.is_present("collision-diagnostics");
+
+    let mut checker = Checker::new_with_config(config);
+
+    checker.set_progress_callback(|progress| {
+        log::info!(
+            "checking step {}k ({} clauses, {} MiB read)",
+            progress.steps / 1000,
+            progress.clauses,
+            progress.bytes >> 20
+        );
+    });
 
     let stdin = io::stdin();
 
@@ -50,38 +168,113 @@ pub fn check_main(matches: &ArgMatches) -> Result<i32, Error> {
     checker.add_transcript(&mut transcript);
 
     let mut lrat_processor;
+    let mut clrat_processor;
+
+    let mut processors: Vec<&mut dyn ProofProcessor> = vec![];
 
     if let Some(lrat_path) = matches.value_of("lrat-file") {
-        lrat_processor = WriteLrat::new(fs::File::create(lrat_path)?, false);
-        checker.add_processor(&mut lrat_processor);
+        lrat_processor = WriteLrat::new(background_file(lrat_path)?, false);
+        processors.push(&mut lrat_processor);
     }
 
-    let mut clrat_processor;
-
     if let Some(clrat_path) = matches.value_of("clrat-file") {
-        clrat_processor = WriteLrat::new(fs::File::create(clrat_path)?, true);
-        checker.add_processor(&mut clrat_processor);
+        clrat_processor = WriteLrat::new(background_file(clrat_path)?, true);
+        processors.push(&mut clrat_processor);
+    }
+
+    let mut id_map_processor;
+
+    if let Some(id_map_path) = matches.value_of("id-map-file") {
+        id_map_processor = WriteClauseIdMap::new(background_file(id_map_path)?);
+        checker.add_processor(&mut id_map_processor);
+    }
+
+    let mut profiler = StepProfiler::new();
+
+    if matches.is_present("profile") {
+        checker.add_processor(&mut profiler);
+    }
+
+    let mut trimmer = ProofTrimmer::new();
+
+    if matches.is_present("trim") {
+        for processor in processors {
+            trimmer.add_processor(processor);
+        }
+        checker.add_processor(&mut trimmer);
+    } else {
+        for processor in processors {
+            checker.add_processor(processor);
+        }
     }
 
     checker.add_dimacs_cnf(file)?;
 
+    if let Some(path) = matches.value_of("model-file") {
+        log::info!("Checking model file '{}'", path);
+
+        return match checker.check_model_file(fs::File::open(path)?) {
+            Ok(()) => {
+                println!("s VERIFIED");
+                Ok(0)
+            }
+            Err(err) => {
+                log::error!("{}", err);
+                println!("s NOT VERIFIED");
+                Ok(1)
+            }
+        };
+    }
+
     let path = matches.value_of("proof-file").unwrap();
 
     log::info!("Checking proof file '{}'", path);
 
-    match checker.check_proof(fs::File::open(path)?) {
-        Ok(()) => println!("s VERIFIED"),
-        Err(err) => {
-            log::error!("{}", err);
-            if let CheckerError::CheckFailed { debug_step, .. } = err {
-                if !debug_step.is_empty() {
-                    log::error!("failed step was {}", debug_step)
-                }
+    let errors = match matches.value_of("max-errors") {
+        Some(max_errors) => {
+            let report = checker.check_proof_lenient(fs::File::open(path)?, max_errors.parse()?);
+            if report.aborted && report.errors.len() > 1 {
+                log::error!("aborted checking after {} errors", report.errors.len());
             }
-            println!("s NOT VERIFIED");
-            return Ok(1);
+            report.errors
         }
+        None => checker
+            .check_proof_pipelined(fs::File::open(path)?)
+            .err()
+            .into_iter()
+            .collect(),
+    };
+
+    drop(checker);
+
+    if matches.is_present("profile") {
+        profiler.write_report(io::stderr())?;
+    }
+
+    if errors.is_empty() {
+        println!("s VERIFIED");
+        return Ok(0);
+    }
+
+    for err in errors {
+        log_error(err);
     }
 
-    Ok(0)
+    println!("s NOT VERIFIED");
+    Ok(1)
+}
+
+fn log_error(err: CheckerError) {
+    log::error!("{}", err);
+    if let CheckerError::CheckFailed {
+        debug_step, offset, ..
+    } = err
+    {
+        if !debug_step.is_empty() {
+            log::error!("failed step was {}", debug_step)
+        }
+        if let Some(offset) = offset {
+            log::error!("failed step starts at byte offset {}", offset)
+        }
+    }
 }
@@ -1,6 +1,4 @@
-use varisat::checker::{ProofTranscriptProcessor, ProofTranscriptStep};
-
-use failure::Error;
+use varisat_checker::{ProcessorError, ProofTranscriptProcessor, ProofTranscriptStep};
 
 /// Steps that will be summarized
 #[derive(Copy, Clone, Eq, PartialEq)]
@@ -53,7 +51,7 @@ impl Transcript {
 }
 
 impl ProofTranscriptProcessor for Transcript {
-    fn process_step(&mut self, step: &ProofTranscriptStep) -> Result<(), Error> {
+    fn process_step(&mut self, step: &ProofTranscriptStep) -> Result<(), ProcessorError> {
         match &step {
             ProofTranscriptStep::WitnessVar { .. } => {
                 self.print_summary(Some(SummaryStep::WitnessVar));
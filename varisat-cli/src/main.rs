@@ -1,18 +1,18 @@
 use std::env;
+use std::error::Error;
 use std::fs;
-use std::io::{self, Read, Write};
+use std::io::{self, Write};
 
-use clap::{values_t, App, AppSettings, Arg};
+use clap::{App, AppSettings};
 use env_logger::{fmt, Builder, Target};
-use failure::Error;
 use log::{error, info};
 use log::{Level, LevelFilter, Record};
 
-use varisat::config::{SolverConfig, SolverConfigUpdate};
-use varisat::solver::{ProofFormat, Solver};
-use varisat_lrat::WriteLrat;
+use varisat_checker::{compress, BackgroundWriter, Compression};
 
 mod check;
+#[cfg(feature = "solver")]
+mod solve;
 
 fn main() {
     let exit_code = match main_with_err() {
@@ -56,141 +56,53 @@ fn banner() {
     );
 }
 
-fn main_with_err() -> Result<i32, Error> {
-    let matches = App::new("varisat")
+/// Create a proof output file that is written on a background thread.
+///
+/// Files ending in `.gz` or `.zst` are compressed, which also happens on the background thread.
+fn background_file(path: &str) -> io::Result<BackgroundWriter> {
+    background_file_with_compression(path, Compression::from_path(path))
+}
+
+/// Create an output file that is written on a background thread using the given compression.
+fn background_file_with_compression(
+    path: &str,
+    compression: Option<Compression>,
+) -> io::Result<BackgroundWriter> {
+    let file = fs::File::create(path)?;
+    Ok(match compression {
+        Some(compression) => BackgroundWriter::new(compress(file, compression)?, 64),
+        None => BackgroundWriter::new(file, 64),
+    })
+}
+
+fn main_with_err() -> Result<i32, Box<dyn Error>> {
+    let app = App::new("varisat")
         .version(env!("VARISAT_VERSION"))
         .setting(AppSettings::DisableHelpSubcommand)
         .setting(AppSettings::ArgsNegateSubcommands)
         .setting(AppSettings::VersionlessSubcommands)
-        .arg_from_usage("[INPUT] 'The input file to use (stdin if omitted)'")
-        .arg_from_usage("[config-file] --config=[FILE] 'Read parameters from configuration file'")
-        .arg(
-            Arg::from_usage("[config-option] -C --config-option")
-                .value_name("OPTION>=<VALUE")
-                .help(
-                    "Specify a single config option, see 'varisat -C help' for a list of options.",
-                )
-                .multiple(true)
-                .number_of_values(1),
-        )
-        .arg_from_usage("[proof-file] --proof=[FILE] 'Write a proof to the specified file'")
-        .arg(
-            Arg::from_usage(
-                "[proof-format] --proof-format=[FORMAT] 'Specify the proof format to use.'",
-            )
-            .possible_values(&["varisat", "drat", "binary-drat", "lrat", "clrat"])
-            .default_value("varisat")
-            .case_insensitive(true),
-        )
-        .arg_from_usage(
-            "--self-check 'Enable self checking by generating and verifying a proof on the fly'",
-        )
         .subcommand(check::check_args())
-        .get_matches();
+        .subcommand(check::drat_to_lrat_args());
 
-    if let Some(matches) = matches.subcommand_matches("--check") {
-        return check::check_main(matches);
-    }
+    #[cfg(feature = "solver")]
+    let app = solve::solve_args(app);
 
-    if values_t!(matches, "config-option", String)
-        .unwrap_or(vec![])
-        .iter()
-        .any(|option| option == "help")
-    {
-        print!("{}", SolverConfig::help());
-        return Ok(0);
-    }
+    #[cfg(not(feature = "solver"))]
+    let app = app.setting(AppSettings::SubcommandRequiredElseHelp);
 
-    init_logging();
-    banner();
+    let matches = app.get_matches();
 
-    let mut config_update = SolverConfigUpdate::new();
-
-    if let Some(config_path) = matches.value_of("config-file") {
-        let mut config_contents = String::new();
-        fs::File::open(config_path)?.read_to_string(&mut config_contents)?;
-
-        config_update.merge(toml::from_str(&config_contents)?);
-    }
-
-    for config_option in values_t!(matches, "config-option", String).unwrap_or(vec![]) {
-        config_update.merge(toml::from_str(&config_option)?);
+    if let Some(matches) = matches.subcommand_matches("--check") {
+        return check::check_main(matches);
     }
 
-    let mut lrat_processor;
-
-    let mut solver = Solver::new();
-
-    solver.config(&config_update)?;
-
-    let stdin = io::stdin();
-
-    let mut locked_stdin;
-    let mut opened_file;
-
-    let file = match matches.value_of("INPUT") {
-        Some(path) => {
-            info!("Reading file '{}'", path);
-            opened_file = fs::File::open(path)?;
-            &mut opened_file as &mut io::Read
-        }
-        None => {
-            info!("Reading from stdin");
-            locked_stdin = stdin.lock();
-            &mut locked_stdin as &mut io::Read
-        }
-    };
-
-    if let Some(path) = matches.value_of("proof-file") {
-        let proof_format_str = matches
-            .value_of("proof-format")
-            .unwrap()
-            .to_ascii_lowercase();
-
-        let proof_format = match &proof_format_str[..] {
-            "drat" => Some(ProofFormat::Drat),
-            "binary-drat" => Some(ProofFormat::BinaryDrat),
-            "varisat" => Some(ProofFormat::Varisat),
-            "lrat" | "clrat" => {
-                lrat_processor =
-                    WriteLrat::new(fs::File::create(path)?, proof_format_str == "clrat");
-                solver.add_proof_processor(&mut lrat_processor);
-                None
-            }
-            _ => unreachable!(),
-        };
-
-        info!("Writing {} proof to file '{}'", proof_format_str, path);
-
-        if let Some(proof_format) = proof_format {
-            solver.write_proof(fs::File::create(path)?, proof_format);
-        }
+    if let Some(matches) = matches.subcommand_matches("--drat-to-lrat") {
+        return check::drat_to_lrat_main(matches);
     }
 
-    if matches.is_present("self-check") {
-        solver.enable_self_checking();
-    }
+    #[cfg(feature = "solver")]
+    return solve::solve_main(&matches);
 
-    solver.add_dimacs_cnf(file)?;
-
-    match solver.solve() {
-        Ok(true) => {
-            println!("s SATISFIABLE");
-            print!("v");
-            for l in solver.model().unwrap() {
-                print!(" {}", l);
-            }
-            println!(" 0");
-            Ok(10)
-        }
-        Ok(false) => {
-            println!("s UNSATISFIABLE");
-            Ok(20)
-        }
-        Err(err) => {
-            log::error!("{}", err);
-            println!("s UNKNOWN");
-            Ok(0)
-        }
-    }
+    #[cfg(not(feature = "solver"))]
+    unreachable!();
 }
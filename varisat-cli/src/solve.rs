@@ -0,0 +1,234 @@
+//! Solving of DIMACS CNF formulas and incremental iCNF problems.
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read};
+
+use clap::{values_t, App, Arg, ArgMatches};
+use log::info;
+
+use varisat::config::{SolverConfig, SolverConfigUpdate};
+use varisat::dimacs::{write_solution, IcnfParser, Solution, XorCnfParser};
+use varisat::solver::{ProofFormat, Solver};
+use varisat_checker::Compression;
+use varisat_lrat::WriteLrat;
+
+use super::{background_file_with_compression, banner, init_logging};
+
+/// Add the arguments used for solving.
+pub fn solve_args(app: App<'static, 'static>) -> App<'static, 'static> {
+    app.arg_from_usage("[INPUT] 'The input file to use (stdin if omitted)'")
+        .arg_from_usage("[config-file] --config=[FILE] 'Read parameters from configuration file'")
+        .arg(
+            Arg::from_usage("[config-option] -C --config-option")
+                .value_name("OPTION>=<VALUE")
+                .help(
+                    "Specify a single config option, see 'varisat -C help' for a list of options.",
+                )
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg_from_usage("[proof-file] --proof=[FILE] 'Write a proof to the specified file'")
+        .arg(
+            Arg::from_usage(
+                "[proof-format] --proof-format=[FORMAT] 'Specify the proof format to use.'",
+            )
+            .possible_values(&["varisat", "drat", "binary-drat", "lrat", "clrat"])
+            .default_value("varisat")
+            .case_insensitive(true),
+        )
+        .arg(
+            Arg::from_usage(
+                "[proof-compression] --proof-compression=[FORMAT] 'Compress the proof file. By \
+                 default files ending in .gz or .zst are compressed.'",
+            )
+            .possible_values(&["none", "gzip", "zstd"])
+            .case_insensitive(true),
+        )
+        .arg_from_usage(
+            "[proof-hash-bits] --proof-hash-bits=[BITS] 'Use clause hashes of a fixed width (1 to \
+             128 bits) in varisat proofs.'",
+        )
+        .arg_from_usage(
+            "--self-check 'Enable self checking by generating and verifying a proof on the fly'",
+        )
+        .arg_from_usage(
+            "--icnf 'Read an incremental problem in iCNF format and solve each assumption cube'",
+        )
+        .arg_from_usage("--xor 'Accept XOR constraints given by lines starting with x'")
+}
+
+pub fn solve_main(matches: &ArgMatches) -> Result<i32, Box<dyn Error>> {
+    if values_t!(matches, "config-option", String)
+        .unwrap_or(vec![])
+        .iter()
+        .any(|option| option == "help")
+    {
+        print!("{}", SolverConfig::help());
+        return Ok(0);
+    }
+
+    init_logging();
+    banner();
+
+    let mut config_update = SolverConfigUpdate::new();
+
+    if let Some(config_path) = matches.value_of("config-file") {
+        let mut config_contents = String::new();
+        fs::File::open(config_path)?.read_to_string(&mut config_contents)?;
+
+        config_update.merge(toml::from_str(&config_contents)?);
+    }
+
+    for config_option in values_t!(matches, "config-option", String).unwrap_or(vec![]) {
+        config_update.merge(toml::from_str(&config_option)?);
+    }
+
+    let mut lrat_processor;
+
+    let mut solver = Solver::new();
+
+    solver.config(&config_update)?;
+
+    let stdin = io::stdin();
+
+    let mut locked_stdin;
+    let mut opened_file;
+
+    let file = match matches.value_of("INPUT") {
+        Some(path) => {
+            info!("Reading file '{}'", path);
+            opened_file = fs::File::open(path)?;
+            &mut opened_file as &mut io::Read
+        }
+        None => {
+            info!("Reading from stdin");
+            locked_stdin = stdin.lock();
+            &mut locked_stdin as &mut io::Read
+        }
+    };
+
+    if let Some(path) = matches.value_of("proof-file") {
+        let compression = match matches.value_of("proof-compression") {
+            None => Compression::from_path(path),
+            Some(compression) => match &compression.to_ascii_lowercase()[..] {
+                "none" => None,
+                "gzip" => Some(Compression::Gzip),
+                "zstd" => Some(Compression::Zstd),
+                _ => unreachable!(),
+            },
+        };
+        let background_file = |path| background_file_with_compression(path, compression);
+
+        let proof_format_str = matches
+            .value_of("proof-format")
+            .unwrap()
+            .to_ascii_lowercase();
+
+        let proof_format = match &proof_format_str[..] {
+            "drat" => Some(ProofFormat::Drat),
+            "binary-drat" => Some(ProofFormat::BinaryDrat),
+            "varisat" => Some(ProofFormat::Varisat),
+            "lrat" | "clrat" => {
+                lrat_processor =
+                    WriteLrat::new(background_file(path)?, proof_format_str == "clrat");
+                solver.add_proof_processor(&mut lrat_processor);
+                None
+            }
+            _ => unreachable!(),
+        };
+
+        info!("Writing {} proof to file '{}'", proof_format_str, path);
+
+        if let Some(bits) = matches.value_of("proof-hash-bits") {
+            let bits: u32 = bits.parse()?;
+            if bits == 0 || bits > 128 {
+                return Err("proof hash bits must be in range 1..=128".into());
+            }
+            solver.set_proof_hash_bits(Some(bits));
+        }
+
+        if let Some(proof_format) = proof_format {
+            solver.write_proof(background_file(path)?, proof_format);
+        }
+    }
+
+    if matches.is_present("self-check") {
+        solver.enable_self_checking();
+    }
+
+    if matches.is_present("icnf") {
+        return solve_icnf(&mut solver, file);
+    }
+
+    // Number of input variables, excluding auxiliary variables of encoded XOR constraints
+    let mut input_var_count = None;
+
+    if matches.is_present("xor") {
+        let formula = XorCnfParser::parse(file)?;
+        info!(
+            "Parsed formula with {} clauses and {} XOR constraints",
+            formula.clauses.len(),
+            formula.xors.len()
+        );
+        input_var_count = Some(formula.var_count());
+        solver.add_formula(&formula.to_cnf());
+    } else {
+        solver.add_dimacs_cnf(file)?;
+    }
+
+    let (solution, exit_code) = match solver.solve() {
+        Ok(true) => {
+            let mut model = solver.model().unwrap().lits();
+            if let Some(var_count) = input_var_count {
+                model.retain(|lit| lit.index() < var_count);
+            }
+            (Solution::Satisfiable(model), 10)
+        }
+        Ok(false) => (Solution::Unsatisfiable, 20),
+        Err(err) => {
+            log::error!("{}", err);
+            (Solution::Unknown, 0)
+        }
+    };
+
+    write_solution(&mut io::stdout(), &solution)?;
+
+    Ok(exit_code)
+}
+
+/// Solve all cubes of an iCNF file, printing a result for each cube.
+///
+/// For satisfiable cubes the model is printed, for unsatisfiable cubes the failed assumptions.
+fn solve_icnf(solver: &mut Solver, file: &mut io::Read) -> Result<i32, Box<dyn Error>> {
+    let formula = IcnfParser::parse(file)?;
+
+    info!("Parsed iCNF file with {} cubes", formula.cubes.len());
+
+    let result = solver.solve_icnf(&formula, |solver, index, result| {
+        println!("c cube {}", index + 1);
+        if result {
+            println!("s SATISFIABLE");
+            print!("v");
+            for l in solver.model().unwrap().iter() {
+                print!(" {}", l);
+            }
+            println!(" 0");
+        } else {
+            println!("s UNSATISFIABLE");
+            print!("f");
+            for l in solver.failed_core().unwrap() {
+                print!(" {}", l);
+            }
+            println!(" 0");
+        }
+    });
+
+    match result {
+        Ok(()) => Ok(0),
+        Err(err) => {
+            log::error!("{}", err);
+            println!("s UNKNOWN");
+            Ok(0)
+        }
+    }
+}
@@ -0,0 +1,511 @@
+//! AIGER reader converting and-inverter graphs to CNF.
+use std::io;
+
+use varisat_formula::{CnfFormula, ExtendFormula, Lit, Var};
+
+use crate::{decompress, ParserError};
+
+/// A latch of an and-inverter graph.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct AigerLatch {
+    /// Literal for the current state of the latch.
+    pub current: Lit,
+    /// Literal for the next state of the latch.
+    pub next: Lit,
+    /// Initial value of the latch, `None` when uninitialized.
+    pub reset: Option<bool>,
+}
+
+/// CNF encoding of an and-inverter graph.
+///
+/// Variable `n` of the graph corresponds to the variable with index `n - 1` of the formula. The
+/// formula contains one additional variable fixed to false, when the constant literals of the
+/// graph are used.
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct AigerCnf {
+    /// Tseitin encoding of all and gates.
+    pub formula: CnfFormula,
+    pub inputs: Vec<Lit>,
+    pub latches: Vec<AigerLatch>,
+    pub outputs: Vec<Lit>,
+}
+
+/// Header of an AIGER file.
+#[derive(Copy, Clone, Debug)]
+struct AigerHeader {
+    binary: bool,
+    max_var: u64,
+    inputs: u64,
+    latches: u64,
+    outputs: u64,
+    ands: u64,
+}
+
+/// Reader for and-inverter graphs in the ASCII (`aag`) and binary (`aig`) AIGER formats.
+///
+/// Each and gate is converted into three clauses using the Tseitin encoding. Latches are not
+/// unrolled, instead the literals for their current and next state are returned, so that callers
+/// can build a transition relation. The symbol table and comments are ignored.
+pub struct AigerParser;
+
+impl AigerParser {
+    /// Parse the given input.
+    ///
+    /// Compressed input is detected and decompressed, see [`decompress`].
+    pub fn parse(input: impl io::Read) -> Result<AigerCnf, ParserError> {
+        let mut data = vec![];
+        decompress(input)?.read_to_end(&mut data)?;
+
+        let mut reader = Reader {
+            data: &data,
+            pos: 0,
+            line: 1,
+        };
+
+        reader.aiger()
+    }
+}
+
+/// Byte level reader of an AIGER file.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Parse a complete AIGER file.
+    fn aiger(&mut self) -> Result<AigerCnf, ParserError> {
+        let header = self.header()?;
+
+        let mut builder = Builder {
+            max_var: header.max_var,
+            defined: vec![false; header.max_var as usize],
+            false_lit: None,
+            cnf: AigerCnf::default(),
+        };
+        builder.cnf.formula.set_var_count(header.max_var as usize);
+
+        for index in 0..header.inputs {
+            let line = self.line;
+            let lit = if header.binary {
+                2 * (index + 1)
+            } else {
+                let lit = self.number()?;
+                self.end_of_line()?;
+                lit
+            };
+            let lit = builder.define(line, lit)?;
+            builder.cnf.inputs.push(lit);
+        }
+
+        for index in 0..header.latches {
+            let current = if header.binary {
+                2 * (header.inputs + index + 1)
+            } else {
+                self.number()?
+            };
+            let next = self.number()?;
+            let reset = if self.at_end_of_line() {
+                0
+            } else {
+                self.number()?
+            };
+            let line = self.line;
+            self.end_of_line()?;
+
+            let current_lit = builder.define(line, current)?;
+            let next = builder.lit(line, next)?;
+            let reset = match reset {
+                0 => Some(false),
+                1 => Some(true),
+                _ if reset == current => None,
+                _ => {
+                    return Err(ParserError::InvalidAigerLiteral {
+                        line,
+                        lit: reset,
+                        reason: "invalid latch reset value",
+                    })
+                }
+            };
+            builder.cnf.latches.push(AigerLatch {
+                current: current_lit,
+                next,
+                reset,
+            });
+        }
+
+        for _ in 0..header.outputs {
+            let lit = self.number()?;
+            let line = self.line;
+            self.end_of_line()?;
+            let lit = builder.lit(line, lit)?;
+            builder.cnf.outputs.push(lit);
+        }
+
+        for index in 0..header.ands {
+            let line = self.line;
+            let (lhs, rhs0, rhs1) = if header.binary {
+                let lhs = 2 * (header.inputs + header.latches + index + 1);
+                let delta0 = self.varint()?;
+                let delta1 = self.varint()?;
+                let rhs0 = lhs.checked_sub(delta0);
+                let rhs1 = rhs0.and_then(|rhs0| rhs0.checked_sub(delta1));
+                match (rhs0, rhs1) {
+                    (Some(rhs0), Some(rhs1)) if delta0 > 0 => (lhs, rhs0, rhs1),
+                    _ => {
+                        return Err(ParserError::InvalidAigerLiteral {
+                            line,
+                            lit: lhs,
+                            reason: "invalid delta encoding of and gate",
+                        })
+                    }
+                }
+            } else {
+                let lhs = self.number()?;
+                let rhs0 = self.number()?;
+                let rhs1 = self.number()?;
+                self.end_of_line()?;
+                (lhs, rhs0, rhs1)
+            };
+
+            let lhs = builder.define(line, lhs)?;
+            let rhs0 = builder.lit(line, rhs0)?;
+            let rhs1 = builder.lit(line, rhs1)?;
+
+            let formula = &mut builder.cnf.formula;
+            formula.add_clause(&[!lhs, rhs0]);
+            formula.add_clause(&[!lhs, rhs1]);
+            formula.add_clause(&[lhs, !rhs0, !rhs1]);
+        }
+
+        Ok(builder.cnf)
+    }
+
+    /// Parse the header line.
+    fn header(&mut self) -> Result<AigerHeader, ParserError> {
+        let end = self.data[self.pos..]
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .map(|len| self.pos + len)
+            .unwrap_or(self.data.len());
+
+        let header_line = String::from_utf8_lossy(&self.data[self.pos..end]).into_owned();
+
+        let invalid = || ParserError::InvalidHeader {
+            line: 1,
+            header: header_line.clone(),
+        };
+
+        let mut fields = header_line.split_whitespace();
+
+        let binary = match fields.next() {
+            Some("aag") => false,
+            Some("aig") => true,
+            _ => return Err(invalid()),
+        };
+
+        let mut values = [0u64; 5];
+        for value in values.iter_mut() {
+            *value = match fields.next().and_then(|field| field.parse().ok()) {
+                Some(field) => field,
+                None => return Err(invalid()),
+            };
+        }
+
+        // Bad state, invariant constraint, justice and fairness sections of AIGER 1.9 are not
+        // supported
+        if fields.any(|field| field != "0") {
+            return Err(invalid());
+        }
+
+        let header = AigerHeader {
+            binary,
+            max_var: values[0],
+            inputs: values[1],
+            latches: values[2],
+            outputs: values[3],
+            ands: values[4],
+        };
+
+        let defined_count = header
+            .inputs
+            .checked_add(header.latches)
+            .and_then(|count| count.checked_add(header.ands));
+
+        let consistent = match defined_count {
+            Some(count) if binary => count == header.max_var,
+            Some(count) => count <= header.max_var,
+            None => false,
+        };
+
+        if !consistent || header.max_var > Var::max_count() as u64 {
+            return Err(invalid());
+        }
+
+        self.pos = (end + 1).min(self.data.len());
+        self.line += 1;
+
+        Ok(header)
+    }
+
+    /// Skip spaces and parse a decimal number.
+    fn number(&mut self) -> Result<u64, ParserError> {
+        while self.data.get(self.pos) == Some(&b' ') {
+            self.pos += 1;
+        }
+
+        let start = self.pos;
+        let mut value = 0u64;
+
+        while let Some(&byte) = self.data.get(self.pos) {
+            if !byte.is_ascii_digit() {
+                break;
+            }
+            value = match value
+                .checked_mul(10)
+                .and_then(|value| value.checked_add(u64::from(byte - b'0')))
+            {
+                Some(value) => value,
+                None => {
+                    return Err(ParserError::InvalidAigerLiteral {
+                        line: self.line,
+                        lit: value,
+                        reason: "literal too large",
+                    })
+                }
+            };
+            self.pos += 1;
+        }
+
+        if self.pos == start {
+            return Err(self.unexpected());
+        }
+
+        Ok(value)
+    }
+
+    /// Whether only spaces are left on the current line.
+    fn at_end_of_line(&mut self) -> bool {
+        while self.data.get(self.pos) == Some(&b' ') {
+            self.pos += 1;
+        }
+        match self.data.get(self.pos) {
+            None | Some(b'\n') | Some(b'\r') => true,
+            _ => false,
+        }
+    }
+
+    /// Skip spaces and the following line break.
+    fn end_of_line(&mut self) -> Result<(), ParserError> {
+        if !self.at_end_of_line() {
+            return Err(self.unexpected());
+        }
+        if self.data.get(self.pos) == Some(&b'\r') {
+            self.pos += 1;
+        }
+        match self.data.get(self.pos) {
+            Some(b'\n') => {
+                self.pos += 1;
+                self.line += 1;
+                Ok(())
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Parse a variable length integer of the binary format.
+    fn varint(&mut self) -> Result<u64, ParserError> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = match self.data.get(self.pos) {
+                Some(&byte) => byte,
+                None => return Err(self.unexpected()),
+            };
+            self.pos += 1;
+
+            if shift > 63 {
+                return Err(ParserError::InvalidAigerLiteral {
+                    line: self.line,
+                    lit: value,
+                    reason: "delta too large",
+                });
+            }
+
+            value |= u64::from(byte & 0x7f) << shift;
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Error for the byte at the current position.
+    fn unexpected(&self) -> ParserError {
+        match self.data.get(self.pos) {
+            Some(&byte) => ParserError::UnexpectedInput {
+                line: self.line,
+                unexpected: byte as char,
+            },
+            None => ParserError::UnexpectedEof { line: self.line },
+        }
+    }
+}
+
+/// Incrementally builds the CNF encoding.
+struct Builder {
+    max_var: u64,
+    /// Whether a variable is an input, latch or and gate, indexed by variable index.
+    defined: Vec<bool>,
+    /// Variable fixed to false, allocated when needed.
+    false_lit: Option<Lit>,
+    cnf: AigerCnf,
+}
+
+impl Builder {
+    /// Convert an AIGER literal.
+    fn lit(&mut self, line: usize, lit: u64) -> Result<Lit, ParserError> {
+        if lit / 2 > self.max_var {
+            return Err(ParserError::InvalidAigerLiteral {
+                line,
+                lit,
+                reason: "variable exceeds the maximal variable index",
+            });
+        }
+
+        let negated = lit & 1 == 1;
+
+        if lit / 2 == 0 {
+            let formula = &mut self.cnf.formula;
+            let false_lit = *self.false_lit.get_or_insert_with(|| {
+                let false_lit = formula.new_lit();
+                formula.add_clause(&[!false_lit]);
+                false_lit
+            });
+            Ok(false_lit ^ negated)
+        } else {
+            Ok(Var::from_index(lit as usize / 2 - 1).lit(!negated))
+        }
+    }
+
+    /// Convert the AIGER literal of an input, latch or and gate.
+    fn define(&mut self, line: usize, lit: u64) -> Result<Lit, ParserError> {
+        let invalid = |reason| ParserError::InvalidAigerLiteral { line, lit, reason };
+
+        if lit & 1 == 1 || lit < 2 {
+            return Err(invalid("defined literal must be a positive variable"));
+        }
+
+        let result = self.lit(line, lit)?;
+
+        let defined = &mut self.defined[result.index()];
+        if *defined {
+            return Err(invalid("variable defined more than once"));
+        }
+        *defined = true;
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::{cnf_formula, lit, lits};
+
+    fn expect_error(input: &[u8], check: impl FnOnce(&ParserError) -> bool) {
+        match AigerParser::parse(input) {
+            Ok(parsed) => panic!("expected error but got {:?}", parsed),
+            Err(err) => {
+                if !check(&err) {
+                    panic!("unexpected error {:?}", err)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ascii_and_gate() {
+        let parsed =
+            AigerParser::parse(b"aag 3 2 0 1 1\n2\n4\n6\n6 2 5\ni0 a\nc\ncomment\n" as &[_])
+                .unwrap();
+
+        assert_eq!(parsed.inputs, lits![1, 2]);
+        assert_eq!(parsed.outputs, lits![3]);
+        assert_eq!(
+            parsed.formula,
+            cnf_formula![
+                -3, 1;
+                -3, -2;
+                3, -1, 2;
+            ]
+        );
+    }
+
+    #[test]
+    fn binary_matches_ascii() {
+        let ascii = AigerParser::parse(
+            b"aag 7 2 1 2 4\n2\n4\n6 14\n12\n7\n8 6 4\n10 9 2\n12 11 3\n14 13 8\n" as &[_],
+        )
+        .unwrap();
+        let binary = AigerParser::parse(
+            b"aig 7 2 1 2 4\n14\n12\n7\n\x02\x02\x01\x07\x01\x08\x01\x05i0 x\n" as &[_],
+        )
+        .unwrap();
+
+        assert_eq!(ascii, binary);
+        assert_eq!(
+            binary.latches,
+            vec![AigerLatch {
+                current: lit!(3),
+                next: lit!(7),
+                reset: Some(false),
+            }]
+        );
+    }
+
+    #[test]
+    fn constants_and_latch_resets() {
+        let parsed = AigerParser::parse(b"aag 2 0 2 2 0\n2 1 1\n4 0 4\n0\n1\n" as &[_]).unwrap();
+
+        let false_lit = lit!(3);
+
+        assert_eq!(parsed.latches[0].next, !false_lit);
+        assert_eq!(parsed.latches[0].reset, Some(true));
+        assert_eq!(parsed.latches[1].next, false_lit);
+        assert_eq!(parsed.latches[1].reset, None);
+        assert_eq!(parsed.outputs, vec![false_lit, !false_lit]);
+        assert_eq!(parsed.formula, cnf_formula![-3;]);
+    }
+
+    #[test]
+    fn invalid_input() {
+        expect_error(b"aag 1 1 0 0\n2\n", |err| match err {
+            ParserError::InvalidHeader { .. } => true,
+            _ => false,
+        });
+        expect_error(b"aag 1 2 0 0 0\n2\n2\n", |err| match err {
+            ParserError::InvalidHeader { .. } => true,
+            _ => false,
+        });
+        expect_error(b"aag 2 2 0 0 0\n2\n2\n", |err| match err {
+            ParserError::InvalidAigerLiteral { line: 3, .. } => true,
+            _ => false,
+        });
+        expect_error(b"aag 1 0 0 1 0\n4\n", |err| match err {
+            ParserError::InvalidAigerLiteral { line: 2, .. } => true,
+            _ => false,
+        });
+        expect_error(b"aag 1 1 0 0 0\n3\n", |err| match err {
+            ParserError::InvalidAigerLiteral { line: 2, .. } => true,
+            _ => false,
+        });
+        expect_error(b"aig 1 0 0 0 1\n", |err| match err {
+            ParserError::UnexpectedEof { .. } => true,
+            _ => false,
+        });
+    }
+}
@@ -0,0 +1,296 @@
+//! Transparent decompression of DIMACS CNF input and compression of output.
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use thiserror::Error;
+
+/// Magic bytes at the start of a gzip stream.
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+/// Magic bytes at the start of an xz stream.
+const XZ_MAGIC: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+/// Magic bytes at the start of a zstd frame.
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Longest magic byte sequence.
+const MAX_MAGIC_LEN: usize = 6;
+
+/// Compression formats recognized in DIMACS CNF input and supported for output.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum InputCompression {
+    /// Requires the `flate2` feature.
+    Gzip,
+    /// Requires the `xz2` feature.
+    Xz,
+    /// Requires the `zstd` feature.
+    Zstd,
+}
+
+impl InputCompression {
+    /// Compression format detected from the first bytes of a stream.
+    pub fn detect(prefix: &[u8]) -> Option<InputCompression> {
+        if prefix.starts_with(GZIP_MAGIC) {
+            Some(InputCompression::Gzip)
+        } else if prefix.starts_with(XZ_MAGIC) {
+            Some(InputCompression::Xz)
+        } else if prefix.starts_with(ZSTD_MAGIC) {
+            Some(InputCompression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// Compression format suggested by the extension of a file name.
+    ///
+    /// Recognizes `.gz`, `.xz` and `.zst` and returns `None` for any other extension.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<InputCompression> {
+        match path
+            .as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("gz") => Some(InputCompression::Gzip),
+            Some("xz") => Some(InputCompression::Xz),
+            Some("zst") => Some(InputCompression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Whether support for this format was enabled at compile time.
+    pub fn is_supported(self) -> bool {
+        match self {
+            InputCompression::Gzip => cfg!(feature = "flate2"),
+            InputCompression::Xz => cfg!(feature = "xz2"),
+            InputCompression::Zstd => cfg!(feature = "zstd"),
+        }
+    }
+}
+
+/// Compressed input in a format whose support was not enabled at compile time.
+#[derive(Debug, Error)]
+#[error("Input is {compression:?} compressed, which is not supported by this build")]
+pub struct UnsupportedCompression {
+    pub compression: InputCompression,
+}
+
+/// Transparently decompress a possibly compressed stream.
+///
+/// The compression format is detected from the first bytes of the stream. Uncompressed streams
+/// are passed through unchanged. Compressed streams in a format that is not supported by this
+/// build result in an [`UnsupportedCompression`] error.
+pub fn decompress<'a>(mut input: impl Read + 'a) -> io::Result<Box<dyn Read + 'a>> {
+    let mut prefix = [0; MAX_MAGIC_LEN];
+    let mut prefix_len = 0;
+    while prefix_len < prefix.len() {
+        match input.read(&mut prefix[prefix_len..]) {
+            Ok(0) => break,
+            Ok(len) => prefix_len += len,
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => (),
+            Err(err) => return Err(err),
+        }
+    }
+
+    let compression = InputCompression::detect(&prefix[..prefix_len]);
+    let input = io::Cursor::new(prefix).take(prefix_len as u64).chain(input);
+
+    Ok(match compression {
+        None => Box::new(input),
+        #[cfg(feature = "flate2")]
+        Some(InputCompression::Gzip) => Box::new(flate2::read::MultiGzDecoder::new(input)),
+        #[cfg(feature = "xz2")]
+        Some(InputCompression::Xz) => Box::new(xz2::read::XzDecoder::new_multi_decoder(input)),
+        #[cfg(feature = "zstd")]
+        Some(InputCompression::Zstd) => Box::new(zstd::Decoder::new(input)?),
+        #[allow(unreachable_patterns)]
+        Some(compression) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                UnsupportedCompression { compression },
+            ))
+        }
+    })
+}
+
+/// Compress everything written to the returned writer.
+///
+/// The compressed stream is finished when the returned writer is dropped. Formats that are not
+/// supported by this build result in an [`UnsupportedCompression`] error.
+pub fn compress<'a>(
+    target: impl Write + Send + 'a,
+    compression: InputCompression,
+) -> io::Result<Box<dyn Write + Send + 'a>> {
+    match compression {
+        #[cfg(feature = "flate2")]
+        InputCompression::Gzip => Ok(Box::new(flate2::write::GzEncoder::new(
+            target,
+            flate2::Compression::default(),
+        ))),
+        #[cfg(feature = "xz2")]
+        InputCompression::Xz => Ok(Box::new(xz2::write::XzEncoder::new(target, 6))),
+        #[cfg(feature = "zstd")]
+        InputCompression::Zstd => Ok(Box::new(ZstdEncoder(zstd::Encoder::new(target, 0)?))),
+        #[allow(unreachable_patterns)]
+        compression => {
+            drop(target);
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                UnsupportedCompression { compression },
+            ))
+        }
+    }
+}
+
+/// Zstd encoder that finishes the stream when dropped.
+///
+/// Unlike [`zstd::stream::write::AutoFinishEncoder`] this is `Send` whenever the target is.
+#[cfg(feature = "zstd")]
+struct ZstdEncoder<W: Write>(zstd::Encoder<W>);
+
+#[cfg(feature = "zstd")]
+impl<W: Write> Write for ZstdEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl<W: Write> Drop for ZstdEncoder<W> {
+    fn drop(&mut self) {
+        // Errors can't be reported from drop, matching the other encoders.
+        let _ = self.0.do_finish();
+    }
+}
+
+/// Create a file, compressing the written data as suggested by the file's extension.
+///
+/// See [`InputCompression::from_path`] and [`compress`]. Files with other extensions are written
+/// uncompressed.
+pub fn create_compressed(path: impl AsRef<Path>) -> io::Result<Box<dyn Write + Send>> {
+    let file = io::BufWriter::new(fs::File::create(path.as_ref())?);
+    match InputCompression::from_path(path) {
+        Some(compression) => compress(file, compression),
+        None => Ok(Box::new(file)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect() {
+        assert_eq!(
+            InputCompression::detect(&[0x1f, 0x8b, 8, 0]),
+            Some(InputCompression::Gzip)
+        );
+        assert_eq!(
+            InputCompression::detect(b"\xfd7zXZ\x00\x00"),
+            Some(InputCompression::Xz)
+        );
+        assert_eq!(
+            InputCompression::detect(&[0x28, 0xb5, 0x2f, 0xfd]),
+            Some(InputCompression::Zstd)
+        );
+        assert_eq!(InputCompression::detect(b"p cnf 1 1\n"), None);
+        assert_eq!(InputCompression::detect(b""), None);
+    }
+
+    #[test]
+    fn from_path() {
+        assert_eq!(
+            InputCompression::from_path("formula.cnf.gz"),
+            Some(InputCompression::Gzip)
+        );
+        assert_eq!(
+            InputCompression::from_path("dir.xz/formula.cnf.xz"),
+            Some(InputCompression::Xz)
+        );
+        assert_eq!(
+            InputCompression::from_path("formula.zst"),
+            Some(InputCompression::Zstd)
+        );
+        assert_eq!(InputCompression::from_path("formula.gz/formula.cnf"), None);
+        assert_eq!(InputCompression::from_path("gz"), None);
+    }
+
+    #[test]
+    fn pass_through() {
+        let mut data = vec![];
+        decompress(&b"1 0\n"[..])
+            .unwrap()
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data, b"1 0\n");
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn gzip_input() {
+        use std::io::Write;
+
+        let mut compressed = vec![];
+        {
+            let mut encoder =
+                flate2::write::GzEncoder::new(&mut compressed, flate2::Compression::default());
+            encoder.write_all(b"p cnf 2 2\n1 2 0\n-1 0\n").unwrap();
+        }
+
+        let formula = crate::DimacsParser::parse(&compressed[..]).unwrap();
+        assert_eq!(formula, varisat_formula::cnf_formula![1, 2; -1;]);
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn gzip_output() {
+        let formula = varisat_formula::cnf_formula![1, -2; 3;];
+
+        let mut compressed = vec![];
+        {
+            let mut encoder = compress(&mut compressed, InputCompression::Gzip).unwrap();
+            crate::write_dimacs(&mut encoder, &formula).unwrap();
+        }
+
+        assert_eq!(
+            InputCompression::detect(&compressed),
+            Some(InputCompression::Gzip)
+        );
+        assert_eq!(
+            crate::DimacsParser::parse(&compressed[..]).unwrap(),
+            formula
+        );
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_output() {
+        let formula = varisat_formula::cnf_formula![1, -2; 3;];
+
+        let mut compressed = vec![];
+        {
+            let mut encoder = compress(&mut compressed, InputCompression::Zstd).unwrap();
+            crate::write_dimacs(&mut encoder, &formula).unwrap();
+        }
+
+        assert_eq!(
+            InputCompression::detect(&compressed),
+            Some(InputCompression::Zstd)
+        );
+        assert_eq!(
+            crate::DimacsParser::parse(&compressed[..]).unwrap(),
+            formula
+        );
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    #[test]
+    fn unsupported_compression() {
+        let err = crate::DimacsParser::parse(&[0x28, 0xb5, 0x2f, 0xfd, 0, 0][..]).unwrap_err();
+        assert!(err.to_string().contains("not supported"));
+
+        assert!(compress(Vec::new(), InputCompression::Zstd).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+//! Parser for group CNF (GCNF) files.
+use std::io;
+
+use varisat_formula::{CnfFormula, ExtendFormula};
+
+use crate::icnf::parse_lit;
+use crate::{decompress, ParserError};
+
+/// A formula with clauses partitioned into groups.
+///
+/// This is the input of group oriented minimal unsatisfiable subset (group-MUS) extraction.
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct GcnfFormula {
+    /// Clauses of group 0, which are always part of the formula.
+    pub hard: CnfFormula,
+    /// Clauses of the groups `1` to `groups.len()`, the clauses of group `n` are at index `n - 1`.
+    pub groups: Vec<CnfFormula>,
+}
+
+impl GcnfFormula {
+    /// Number of variables of the hard clauses and all groups.
+    pub fn var_count(&self) -> usize {
+        self.groups
+            .iter()
+            .map(|group| group.var_count())
+            .max()
+            .unwrap_or(0)
+            .max(self.hard.var_count())
+    }
+
+    /// The clauses of the group with the given number.
+    ///
+    /// Group 0 contains the hard clauses.
+    pub fn group(&self, group: usize) -> Option<&CnfFormula> {
+        if group == 0 {
+            Some(&self.hard)
+        } else {
+            self.groups.get(group - 1)
+        }
+    }
+}
+
+/// Parser for GCNF files.
+///
+/// A GCNF file starts with a `p gcnf <vars> <clauses> <groups>` header. Each clause is prefixed
+/// with its group number in braces, e.g. `{2} 1 -3 0`. Group 0 contains the hard clauses, the
+/// other groups are numbered from 1 to the group count of the header.
+pub struct GcnfParser;
+
+impl GcnfParser {
+    /// Parse the given input and check the header.
+    ///
+    /// Compressed input is detected and decompressed, see [`decompress`].
+    pub fn parse(input: impl io::Read) -> Result<GcnfFormula, ParserError> {
+        use io::BufRead;
+
+        let buffer = io::BufReader::new(decompress(input)?);
+
+        let mut parsed = GcnfFormula::default();
+        let mut header: Option<(usize, usize)> = None;
+        let mut clause_count = 0;
+        let mut clause = vec![];
+        let mut clause_line = 0;
+        let mut group = 0;
+
+        for (line_index, line) in buffer.split(b'\n').enumerate() {
+            let line = line?;
+            let line_number = line_index + 1;
+            let line = String::from_utf8_lossy(&line);
+            let mut tokens = line.split_whitespace();
+
+            match line.trim_start().as_bytes().first() {
+                None | Some(b'c') => continue,
+                Some(b'p') => {
+                    let invalid = || ParserError::InvalidHeader {
+                        line: line_number,
+                        header: line.trim_end().to_owned(),
+                    };
+                    if header.is_some() {
+                        return Err(invalid());
+                    }
+                    let (var_count, header_clause_count, group_count) =
+                        parse_header(&line).ok_or_else(invalid)?;
+                    parsed.hard.set_var_count(var_count);
+                    parsed.groups = (0..group_count).map(|_| CnfFormula::new()).collect();
+                    header = Some((var_count, header_clause_count));
+                    continue;
+                }
+                _ => (),
+            }
+
+            let (header_var_count, _) = match header {
+                Some(header) => header,
+                None => return Err(ParserError::MissingHeader { line: line_number }),
+            };
+
+            if clause.is_empty() {
+                let token = tokens.next().unwrap();
+                group = parse_group(line_number, token)?;
+                if group > parsed.groups.len() {
+                    return Err(ParserError::InvalidGroup {
+                        line: line_number,
+                        group,
+                        group_count: parsed.groups.len(),
+                    });
+                }
+                clause_line = line_number;
+            }
+
+            for token in tokens {
+                match parse_lit(line_number, token)? {
+                    Some(lit) => {
+                        if lit.index() >= header_var_count {
+                            return Err(ParserError::VarOutOfRange {
+                                line: line_number,
+                                var: lit.index() + 1,
+                                header_var_count,
+                            });
+                        }
+                        clause.push(lit)
+                    }
+                    None => {
+                        let target = if group == 0 {
+                            &mut parsed.hard
+                        } else {
+                            &mut parsed.groups[group - 1]
+                        };
+                        target.add_clause(&clause);
+                        clause.clear();
+                        clause_count += 1;
+                    }
+                }
+            }
+        }
+
+        if !clause.is_empty() {
+            return Err(ParserError::UnterminatedClause { line: clause_line });
+        }
+
+        match header {
+            None => return Err(ParserError::MissingHeader { line: 1 }),
+            Some((_, header_clause_count)) => {
+                if clause_count != header_clause_count {
+                    return Err(ParserError::ClauseCount {
+                        clause_count,
+                        header_clause_count,
+                    });
+                }
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+/// Parse a `p gcnf` header line.
+fn parse_header(line: &str) -> Option<(usize, usize, usize)> {
+    let mut tokens = line.split_whitespace();
+    if tokens.next() != Some("p") || tokens.next() != Some("gcnf") {
+        return None;
+    }
+    let var_count = tokens.next()?.parse().ok()?;
+    let clause_count = tokens.next()?.parse().ok()?;
+    let group_count = tokens.next()?.parse().ok()?;
+    if tokens.next().is_some() {
+        return None;
+    }
+    Some((var_count, clause_count, group_count))
+}
+
+/// Parse a group number in braces.
+fn parse_group(line: usize, token: &str) -> Result<usize, ParserError> {
+    if token.starts_with('{') && token.ends_with('}') && token.len() > 2 {
+        if let Ok(group) = token[1..token.len() - 1].parse() {
+            return Ok(group);
+        }
+    }
+    Err(ParserError::UnexpectedToken {
+        line,
+        token: token.to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::cnf_formula;
+
+    #[test]
+    fn parse_groups() {
+        let parsed = GcnfParser::parse(
+            b"c comment\np gcnf 4 5 3\n{0} 1 2 0\n{2} -1 0\n{1} 3\n -4 0\n\n{2} 4 0\n{0} -2 0\n"
+                as &[_],
+        )
+        .unwrap();
+
+        let mut hard = cnf_formula![
+            1, 2;
+            -2;
+        ];
+        hard.set_var_count(4);
+        assert_eq!(parsed.hard, hard);
+        assert_eq!(parsed.groups.len(), 3);
+        assert_eq!(parsed.group(1), Some(&cnf_formula![3, -4;]));
+        assert_eq!(parsed.group(2), Some(&cnf_formula![-1; 4;]));
+        assert_eq!(parsed.group(3), Some(&CnfFormula::new()));
+        assert_eq!(parsed.group(4), None);
+        assert_eq!(parsed.var_count(), 4);
+    }
+
+    #[test]
+    fn invalid_input() {
+        for &input in [
+            &b"{0} 1 0\n"[..],
+            b"p gcnf 2 1 1\n{2} 1 0\n",
+            b"p gcnf 2 1 1\n1 2 0\n",
+            b"p gcnf 2 1 1\n{1} 3 0\n",
+            b"p gcnf 2 2 1\n{1} 1 0\n",
+            b"p gcnf 2 1 1\n{1} 1\n",
+            b"p gcnf 2 1\n{1} 1 0\n",
+            b"",
+        ]
+        .iter()
+        {
+            assert!(GcnfParser::parse(input).is_err());
+        }
+    }
+}
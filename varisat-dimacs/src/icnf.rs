@@ -0,0 +1,220 @@
+//! Parser for the iCNF format describing incremental SAT problems.
+use std::io;
+
+use varisat_formula::{CnfFormula, ExtendFormula, Lit, Var};
+
+use crate::{decompress, ParserError};
+
+/// Clauses followed by a cube of assumptions to solve under.
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct IcnfCube {
+    /// Clauses to add before solving.
+    pub clauses: CnfFormula,
+    /// Literals to assume while solving.
+    pub assumptions: Vec<Lit>,
+}
+
+/// An incremental SAT problem.
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct IcnfFormula {
+    /// Cubes in the order they are solved.
+    ///
+    /// Clauses are never removed, so every cube is solved under all clauses of itself and of the
+    /// preceding cubes.
+    pub cubes: Vec<IcnfCube>,
+    /// Clauses after the last cube.
+    pub trailing_clauses: CnfFormula,
+}
+
+/// Parser for iCNF files.
+///
+/// An iCNF file starts with a `p inccnf` header and contains DIMACS CNF clauses interleaved with
+/// assumption cubes. A cube is a line starting with `a` followed by literals and a terminating
+/// zero. Clauses can span multiple lines, but a cube cannot start within a clause.
+pub struct IcnfParser;
+
+impl IcnfParser {
+    /// Parse the given input.
+    ///
+    /// Compressed input is detected and decompressed, see [`decompress`].
+    pub fn parse(input: impl io::Read) -> Result<IcnfFormula, ParserError> {
+        use io::BufRead;
+
+        let buffer = io::BufReader::new(decompress(input)?);
+
+        let mut parsed = IcnfFormula::default();
+        let mut clauses = CnfFormula::new();
+        let mut clause = vec![];
+        let mut clause_line = 0;
+        let mut seen_header = false;
+        let mut seen_content = false;
+
+        for (line_index, line) in buffer.split(b'\n').enumerate() {
+            let line = line?;
+            let line_number = line_index + 1;
+            let line = String::from_utf8_lossy(&line);
+            let mut tokens = line.split_whitespace();
+
+            match line.trim_start().as_bytes().first() {
+                Some(b'c') => continue,
+                Some(b'p') => {
+                    let valid = tokens.eq(["p", "inccnf"].iter().cloned());
+                    if seen_header || seen_content || !valid {
+                        return Err(ParserError::InvalidHeader {
+                            line: line_number,
+                            header: line.trim_end().to_owned(),
+                        });
+                    }
+                    seen_header = true;
+                    continue;
+                }
+                Some(b'a') => {
+                    if !clause.is_empty() {
+                        return Err(ParserError::UnterminatedClause { line: clause_line });
+                    }
+                    seen_content = true;
+                    tokens.next();
+
+                    let mut assumptions = vec![];
+                    let mut terminated = false;
+                    for token in tokens {
+                        if terminated {
+                            return Err(unexpected(line_number, token));
+                        }
+                        match parse_lit(line_number, token)? {
+                            Some(lit) => assumptions.push(lit),
+                            None => terminated = true,
+                        }
+                    }
+                    if !terminated {
+                        return Err(ParserError::UnterminatedClause { line: line_number });
+                    }
+
+                    for &lit in assumptions.iter() {
+                        clauses.set_var_count(lit.index() + 1);
+                    }
+
+                    parsed.cubes.push(IcnfCube {
+                        clauses: std::mem::replace(&mut clauses, CnfFormula::new()),
+                        assumptions,
+                    });
+                }
+                _ => {
+                    for token in tokens {
+                        seen_content = true;
+                        if clause.is_empty() {
+                            clause_line = line_number;
+                        }
+                        match parse_lit(line_number, token)? {
+                            Some(lit) => clause.push(lit),
+                            None => {
+                                clauses.add_clause(&clause);
+                                clause.clear();
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if !clause.is_empty() {
+            return Err(ParserError::UnterminatedClause { line: clause_line });
+        }
+
+        parsed.trailing_clauses = clauses;
+
+        Ok(parsed)
+    }
+}
+
+/// Error for a token that is not a literal.
+fn unexpected(line: usize, token: &str) -> ParserError {
+    ParserError::UnexpectedInput {
+        line,
+        unexpected: token.chars().next().unwrap_or(' '),
+    }
+}
+
+/// Parse a literal in DIMACS syntax.
+///
+/// Returns `None` for the terminating zero.
+pub(crate) fn parse_lit(line: usize, token: &str) -> Result<Option<Lit>, ParserError> {
+    let digits = if token.starts_with('-') {
+        &token[1..]
+    } else {
+        token
+    };
+
+    if let Some(byte) = digits.bytes().find(|byte| !byte.is_ascii_digit()) {
+        return Err(ParserError::UnexpectedInput {
+            line,
+            unexpected: byte as char,
+        });
+    }
+
+    if digits.is_empty() {
+        return Err(unexpected(line, token));
+    }
+
+    let index: usize = match digits.parse() {
+        Ok(index) if index <= Var::max_count() => index,
+        _ => {
+            let (index, final_digit) = digits.split_at(digits.len() - 1);
+            return Err(ParserError::LiteralTooLarge {
+                line,
+                index: index.parse().unwrap_or(usize::max_value()),
+                final_digit: final_digit.parse().unwrap(),
+            });
+        }
+    };
+
+    if index == 0 {
+        Ok(None)
+    } else {
+        Ok(Some(
+            Var::from_dimacs(index as isize).lit(!token.starts_with('-')),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::{cnf_formula, lits};
+
+    #[test]
+    fn parse_cubes() {
+        let parsed = IcnfParser::parse(
+            b"c comment\np inccnf\n1 2 0\n-1\n 3 0\na -2 0\na 1 4 0\n-3 0\n" as &[_],
+        )
+        .unwrap();
+
+        assert_eq!(parsed.cubes.len(), 2);
+        assert_eq!(
+            parsed.cubes[0].clauses,
+            cnf_formula![
+                1, 2;
+                -1, 3;
+            ]
+        );
+        assert_eq!(parsed.cubes[0].assumptions, lits![-2]);
+        assert_eq!(parsed.cubes[1].clauses.len(), 0);
+        assert_eq!(parsed.cubes[1].assumptions, lits![1, 4]);
+        assert_eq!(parsed.trailing_clauses, cnf_formula![-3;]);
+    }
+
+    #[test]
+    fn invalid_input() {
+        for &input in [
+            &b"p cnf 1 1\n1 0\n"[..],
+            b"1 2\na 1 0\n",
+            b"a 1 2\n",
+            b"1 x 0\n",
+        ]
+        .iter()
+        {
+            assert!(IcnfParser::parse(input).is_err());
+        }
+    }
+}
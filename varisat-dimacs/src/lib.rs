@@ -1,4 +1,12 @@
 //! DIMCAS CNF parser and writer for the Varisat SAT solver.
+//!
+//! Also includes a parser and writer for weighted CNF (WCNF) files describing MaxSAT instances,
+//! parsers for iCNF files describing incremental SAT problems, for group CNF (GCNF) files and
+//! for QDIMACS files describing quantified boolean formulas, as well as parsers for OPB and WBO
+//! files describing pseudo-Boolean problems and a reader converting AIGER and-inverter graphs to
+//! CNF. DIMACS CNF files with additional XOR constraints can be parsed as well. Solutions can be
+//! written and read in the output format of the SAT competition and variable names can be stored
+//! in DIMACS comments.
 
 use std::borrow::Borrow;
 use std::io;
@@ -6,47 +14,155 @@ use std::mem::replace;
 
 use varisat_formula::{CnfFormula, ExtendFormula, Lit, Var};
 
-use failure::{Error, Fail};
+use thiserror::Error;
+
+mod aiger;
+mod compression;
+mod gcnf;
+mod icnf;
+mod names;
+mod opb;
+mod parallel;
+mod qdimacs;
+mod solution;
+mod wbo;
+mod wcnf;
+mod writer;
+mod xor;
+
+pub use aiger::{AigerCnf, AigerLatch, AigerParser};
+pub use compression::{
+    compress, create_compressed, decompress, InputCompression, UnsupportedCompression,
+};
+pub use gcnf::{GcnfFormula, GcnfParser};
+pub use icnf::{IcnfCube, IcnfFormula, IcnfParser};
+pub use names::{read_var_names, write_var_names};
+pub use opb::OpbParser;
+pub use qdimacs::{QdimacsFormula, QdimacsParser, Quantifier, QuantifierBlock};
+pub use solution::{write_solution, Solution, SolutionParser};
+pub use wbo::WboParser;
+pub use wcnf::{write_wcnf, write_wcnf_classic, WcnfHeader, WcnfParser};
+pub use writer::{write_dimacs_with_config, DimacsHeaderMode, DimacsWriter, DimacsWriterConfig};
+pub use xor::{XorCnfFormula, XorCnfParser};
 
 /// Possible errors while parsing a DIMACS CNF formula.
-#[derive(Debug, Fail)]
+#[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum ParserError {
-    #[fail(
-        display = "line {}: Unexpected character in DIMACS CNF input: '{}'",
-        line, unexpected
-    )]
+    #[error("line {line}: Unexpected character in DIMACS CNF input: '{unexpected}'")]
     UnexpectedInput { line: usize, unexpected: char },
-    #[fail(
-        display = "line {}: Literal index is too large: {}{}...",
-        line, index, final_digit
-    )]
+    #[error("line {line}: Literal index is too large: {index}{final_digit}...")]
     LiteralTooLarge {
         line: usize,
         index: usize,
         final_digit: usize,
     },
-    #[fail(display = "line {}: Invalid header syntax: {}", line, header)]
+    #[error("line {line}: Invalid header syntax: {header}")]
     InvalidHeader { line: usize, header: String },
-    #[fail(display = "line {}: Unterminated clause", line)]
+    #[error("line {line}: Invalid clause weight: {weight}")]
+    InvalidWeight { line: usize, weight: String },
+    #[error("line {line}: Invalid quantifier block: {block}")]
+    InvalidQuantifierBlock { line: usize, block: String },
+    #[error("line {line}: Variable {var} is quantified more than once")]
+    DuplicateQuantifiedVar { line: usize, var: isize },
+    #[error("line {line}: Quantifier block after the first clause")]
+    QuantifierAfterClause { line: usize },
+    #[error("line {line}: Unexpected token: {token}")]
+    UnexpectedToken { line: usize, token: String },
+    #[error("line {line}: Non-linear terms are not supported")]
+    NonLinearTerm { line: usize },
+    #[error("line {line}: Coefficient {coefficient} is too large")]
+    CoefficientTooLarge { line: usize, coefficient: String },
+    #[error("line {line}: Constraint without relation or right hand side")]
+    IncompleteConstraint { line: usize },
+    #[error("line {line}: Unterminated constraint")]
+    UnterminatedConstraint { line: usize },
+    #[error("line {line}: Objective after the first constraint")]
+    UnexpectedObjective { line: usize },
+    #[error("line {line}: Invalid AIGER literal {lit}: {reason}")]
+    InvalidAigerLiteral {
+        line: usize,
+        lit: u64,
+        reason: &'static str,
+    },
+    #[error("line {line}: Unexpected end of input")]
+    UnexpectedEof { line: usize },
+    #[error("line {line}: Unterminated clause")]
     UnterminatedClause { line: usize },
-    #[fail(
-        display = "Formula has {} variables while the header specifies {} variables",
-        var_count, header_var_count
+    #[error(
+        "Formula has {var_count} variables while the header specifies {header_var_count} variables"
     )]
     VarCount {
         var_count: usize,
         header_var_count: usize,
     },
-    #[fail(
-        display = "Formula has {} clauses while the header specifies {} clauses",
-        clause_count, header_clause_count
-    )]
+    #[error("Formula has {clause_count} clauses while the header specifies {header_clause_count} clauses")]
     ClauseCount {
         clause_count: usize,
         header_clause_count: usize,
     },
-    #[fail(display = "Parser invoked after a previous error")]
+    #[error("Problem has {constraint_count} constraints while the header specifies {header_constraint_count} constraints")]
+    ConstraintCount {
+        constraint_count: usize,
+        header_constraint_count: usize,
+    },
+    #[error("line {line}: Clause before the DIMACS CNF header")]
+    MissingHeader { line: usize },
+    #[error("line {line}: Variable {var} exceeds the header's variable count {header_var_count}")]
+    VarOutOfRange {
+        line: usize,
+        var: usize,
+        header_var_count: usize,
+    },
+    #[error("line {line}: More clauses than the {header_clause_count} specified in the header")]
+    TooManyClauses {
+        line: usize,
+        header_clause_count: usize,
+    },
+    #[error("{error} (column {column})")]
+    InColumn {
+        column: usize,
+        error: Box<ParserError>,
+    },
+    #[error("line {line}: Invalid solution status: {status}")]
+    InvalidStatus { line: usize, status: String },
+    #[error("line {line}: Solution has more than one status line")]
+    DuplicateStatus { line: usize },
+    #[error("line {line}: Values without a preceding satisfiable status")]
+    UnexpectedValues { line: usize },
+    #[error("Solution has no status line")]
+    MissingStatus,
+    #[error("line {line}: Group {group} exceeds the header's group count {group_count}")]
+    InvalidGroup {
+        line: usize,
+        group: usize,
+        group_count: usize,
+    },
+    #[error("Parser invoked after a previous error")]
     PreviousError,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Recoverable issues reported while parsing a DIMACS CNF formula.
+///
+/// See [`DimacsParser::set_warning_callback`].
+#[derive(Clone, PartialEq, Eq, Debug, Error)]
+#[non_exhaustive]
+pub enum ParserWarning {
+    #[error("line {line}: Variable {var} exceeds the header's variable count {header_var_count}")]
+    VarOutOfRange {
+        line: usize,
+        var: usize,
+        header_var_count: usize,
+    },
+    #[error("line {line}: Clause contains literal {lit} more than once")]
+    DuplicateLiteral { line: usize, lit: Lit },
+    #[error("Formula has {clause_count} clauses while the header specifies {header_clause_count} clauses")]
+    ClauseCount {
+        clause_count: usize,
+        header_clause_count: usize,
+    },
 }
 
 /// Variable and clause count present in a DIMACS CNF header.
@@ -56,16 +172,48 @@ pub struct DimacsHeader {
     pub clause_count: usize,
 }
 
+/// Comment lines of a DIMACS CNF file.
+///
+/// Used to preserve comments when reading and writing a formula, see
+/// [`DimacsParser::parse_with_comments`] and [`write_dimacs_with_comments`]. Comments are stored
+/// without the leading `c` and a single following space.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct DimacsComments {
+    /// Comments before the header line.
+    pub header: Vec<String>,
+    /// Comments after the header, together with the index of the clause they precede.
+    ///
+    /// Comments after the last clause use the number of clauses as index. Without a header line
+    /// all comments are stored here.
+    pub clauses: Vec<(usize, String)>,
+}
+
 /// Parser for DIMACS CNF files.
 ///
 /// This parser can consume the input in chunks while also producing the parsed result in chunks.
+///
+/// By default the parser is permissive: the header is optional and its counts are only compared
+/// against the parsed formula by [`check_header`](DimacsParser::check_header). A parser created
+/// by [`new_strict`](DimacsParser::new_strict) requires a header before the first clause, rejects
+/// variables and clauses exceeding the header's counts as soon as they are parsed and reports the
+/// column of an error in addition to its line, using [`ParserError::InColumn`].
+///
+/// Instead of failing or ignoring them, a permissive parser can also report recoverable issues
+/// to a callback set using [`set_warning_callback`](DimacsParser::set_warning_callback).
 #[derive(Default)]
 pub struct DimacsParser {
     formula: CnfFormula,
     partial_clause: Vec<Lit>,
     header: Option<DimacsHeader>,
+    strict: bool,
+    warning_callback: Option<Box<dyn FnMut(ParserWarning) + Send>>,
+    comments: Option<DimacsComments>,
 
     line_number: usize,
+    column_number: usize,
+    /// Line and column of the current or last literal.
+    lit_line: usize,
+    lit_column: usize,
     clause_count: usize,
     partial_lit: usize,
     negate_next_lit: bool,
@@ -77,6 +225,7 @@ pub struct DimacsParser {
     error: bool,
 
     header_line: Vec<u8>,
+    comment_line: Vec<u8>,
 }
 
 impl DimacsParser {
@@ -86,8 +235,14 @@ impl DimacsParser {
             formula: CnfFormula::new(),
             partial_clause: vec![],
             header: None,
+            strict: false,
+            warning_callback: None,
+            comments: None,
 
             line_number: 1,
+            column_number: 0,
+            lit_line: 1,
+            lit_column: 1,
             clause_count: 0,
             partial_lit: 0,
             negate_next_lit: false,
@@ -99,16 +254,85 @@ impl DimacsParser {
             error: false,
 
             header_line: vec![],
+            comment_line: vec![],
+        }
+    }
+
+    /// Create a new DIMACS CNF parser in strict mode.
+    pub fn new_strict() -> DimacsParser {
+        DimacsParser {
+            strict: true,
+            ..DimacsParser::new()
+        }
+    }
+
+    /// Whether the parser is in strict mode.
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Whether to retain comment lines.
+    ///
+    /// Retained comments are available using [`take_comments`](DimacsParser::take_comments).
+    pub fn set_retain_comments(&mut self, retain: bool) {
+        if !retain {
+            self.comments = None;
+        } else if self.comments.is_none() {
+            self.comments = Some(DimacsComments::default());
         }
     }
 
+    /// Returns the comments retained since the last call to this method.
+    ///
+    /// Clause indices of the comments count all clauses parsed so far, including those already
+    /// returned by [`take_formula`](DimacsParser::take_formula). Returns no comments unless
+    /// enabled using [`set_retain_comments`](DimacsParser::set_retain_comments).
+    pub fn take_comments(&mut self) -> DimacsComments {
+        match &mut self.comments {
+            Some(comments) => replace(comments, DimacsComments::default()),
+            None => DimacsComments::default(),
+        }
+    }
+
+    /// Report recoverable issues to the given callback.
+    ///
+    /// The callback is invoked for variables exceeding the header's variable count and for
+    /// clauses containing a literal more than once as they are parsed. A mismatch of the header's
+    /// clause count is reported by [`eof`](DimacsParser::eof). When a callback is set, the counts
+    /// of the header are not checked by [`check_header`](DimacsParser::check_header).
+    ///
+    /// In strict mode variables and clauses exceeding the header's counts are still errors.
+    pub fn set_warning_callback(&mut self, callback: impl FnMut(ParserWarning) + Send + 'static) {
+        self.warning_callback = Some(Box::new(callback));
+    }
+
     /// Parse the given input and check the header if present.
     ///
     /// This parses the whole input into a single [`CnfFormula`](varisat_formula::CnfFormula).
+    /// Input compressed using gzip, xz or zstd is decompressed when the corresponding feature
+    /// (`flate2`, `xz2` or `zstd`) is enabled.
     /// Incremental parsing is possible using [`parse_incremental`](DimacsParser::parse_incremental)
     /// or the [`parse_chunk`](DimacsParser::parse_chunk) method.
-    pub fn parse(input: impl io::Read) -> Result<CnfFormula, Error> {
-        Ok(Self::parse_incremental(input, |_| Ok(()))?.take_formula())
+    pub fn parse(input: impl io::Read) -> Result<CnfFormula, ParserError> {
+        Ok(Self::parse_incremental(input, Self::no_callback)?.take_formula())
+    }
+
+    /// Parse an in-memory input and check the header if present.
+    ///
+    /// The input is parsed directly without buffering or copying, which makes this the fastest
+    /// way to load a large formula, e.g. from a memory-mapped file. Compressed input is detected
+    /// and decompressed like in [`parse`](DimacsParser::parse), which requires copying.
+    pub fn parse_slice(input: &[u8]) -> Result<CnfFormula, ParserError> {
+        if InputCompression::detect(input).is_some() {
+            return Self::parse(input);
+        }
+
+        let mut parser = Self::new();
+        parser.parse_chunk(input)?;
+        parser.eof()?;
+        parser.check_header()?;
+
+        Ok(parser.take_formula())
     }
 
     /// Parse the given input incrementally and check the header if present.
@@ -116,17 +340,61 @@ impl DimacsParser {
     /// The callback is invoked repeatedly with a reference to the parser. The callback can process
     /// the formula incrementally by calling [`take_formula`](DimacsParser::take_formula) on the
     /// passed argument.
-    pub fn parse_incremental(
+    ///
+    /// Compressed input is detected and decompressed, see [`decompress`]. Errors of the callback
+    /// are passed through, so it can fail with any error type that a [`ParserError`] converts into.
+    pub fn parse_incremental<E: From<ParserError>>(
         input: impl io::Read,
-        mut callback: impl FnMut(&mut DimacsParser) -> Result<(), Error>,
-    ) -> Result<DimacsParser, Error> {
-        use io::BufRead;
+        callback: impl FnMut(&mut DimacsParser) -> Result<(), E>,
+    ) -> Result<DimacsParser, E> {
+        Self::parse_with_parser(Self::new(), input, callback)
+    }
 
-        let mut buffer = io::BufReader::new(input);
+    /// Parse the given input in strict mode.
+    ///
+    /// Like [`parse`](DimacsParser::parse) but using a parser created by
+    /// [`new_strict`](DimacsParser::new_strict).
+    pub fn parse_strict(input: impl io::Read) -> Result<CnfFormula, ParserError> {
+        Ok(Self::parse_with_parser(Self::new_strict(), input, Self::no_callback)?.take_formula())
+    }
+
+    /// Parse the given input and check the header if present, retaining comment lines.
+    ///
+    /// The comments can be written back using [`write_dimacs_with_comments`].
+    pub fn parse_with_comments(
+        input: impl io::Read,
+    ) -> Result<(CnfFormula, DimacsComments), ParserError> {
         let mut parser = Self::new();
+        parser.set_retain_comments(true);
+        let mut parser = Self::parse_with_parser(parser, input, Self::no_callback)?;
+        Ok((parser.take_formula(), parser.take_comments()))
+    }
+
+    /// Parse the given input, reporting recoverable issues to a callback.
+    ///
+    /// Like [`parse`](DimacsParser::parse) but using a parser with the given
+    /// [warning callback](DimacsParser::set_warning_callback).
+    pub fn parse_with_warnings(
+        input: impl io::Read,
+        callback: impl FnMut(ParserWarning) + Send + 'static,
+    ) -> Result<CnfFormula, ParserError> {
+        let mut parser = Self::new();
+        parser.set_warning_callback(callback);
+        Ok(Self::parse_with_parser(parser, input, Self::no_callback)?.take_formula())
+    }
+
+    /// Shared implementation of the parse methods.
+    fn parse_with_parser<E: From<ParserError>>(
+        mut parser: DimacsParser,
+        input: impl io::Read,
+        mut callback: impl FnMut(&mut DimacsParser) -> Result<(), E>,
+    ) -> Result<DimacsParser, E> {
+        use io::BufRead;
+
+        let mut buffer = io::BufReader::new(decompress(input).map_err(ParserError::Io)?);
 
         loop {
-            let data = buffer.fill_buf()?;
+            let data = buffer.fill_buf().map_err(ParserError::Io)?;
             if data.is_empty() {
                 break;
             }
@@ -143,6 +411,11 @@ impl DimacsParser {
         Ok(parser)
     }
 
+    /// Callback of the parse methods that don't process the formula incrementally.
+    fn no_callback(_parser: &mut DimacsParser) -> Result<(), ParserError> {
+        Ok(())
+    }
+
     /// Parse a chunk of input.
     ///
     /// After parsing the last chunk call the [`eof`](DimacsParser::eof) method.
@@ -153,15 +426,24 @@ impl DimacsParser {
         if self.error {
             return Err(ParserError::PreviousError);
         }
+        let result = self.parse_chunk_bytes(chunk);
+        result.map_err(|err| self.locate(err))
+    }
+
+    fn parse_chunk_bytes(&mut self, chunk: &[u8]) -> Result<(), ParserError> {
         for &byte in chunk.iter() {
+            self.column_number += 1;
             if byte == b'\n' {
                 self.line_number += 1;
+                self.column_number = 0;
             }
             match byte {
                 b'\n' | b'\r' if self.in_comment_or_header => {
                     if self.in_header {
                         self.in_header = false;
                         self.parse_header_line()?;
+                    } else {
+                        self.finish_comment();
                     }
                     self.in_comment_or_header = false;
                     self.start_of_line = true
@@ -169,9 +451,14 @@ impl DimacsParser {
                 _ if self.in_comment_or_header => {
                     if self.in_header {
                         self.header_line.push(byte);
+                    } else if self.comments.is_some() {
+                        self.comment_line.push(byte);
                     }
                 }
                 b'0'...b'9' => {
+                    if !self.in_lit && !self.negate_next_lit {
+                        self.start_lit();
+                    }
                     self.in_lit = true;
                     let digit = (byte - b'0') as usize;
 
@@ -198,11 +485,12 @@ impl DimacsParser {
                     self.start_of_line = false
                 }
                 b'-' if !self.negate_next_lit && !self.in_lit => {
+                    self.start_lit();
                     self.negate_next_lit = true;
                     self.start_of_line = false
                 }
                 b' ' | b'\n' | b'\r' if !(self.negate_next_lit && !self.in_lit) => {
-                    self.finish_literal();
+                    self.finish_literal()?;
                     self.negate_next_lit = false;
                     self.in_lit = false;
                     self.partial_lit = 0;
@@ -212,6 +500,7 @@ impl DimacsParser {
                     self.in_comment_or_header = true;
                 }
                 b'p' if self.start_of_line && self.header.is_none() => {
+                    self.start_lit();
                     self.in_comment_or_header = true;
                     self.in_header = true;
                     self.header_line.push(b'p');
@@ -234,11 +523,18 @@ impl DimacsParser {
     /// This does not check whether the header information was correct, call
     /// [`check_header`](DimacsParser::check_header) for this.
     pub fn eof(&mut self) -> Result<(), ParserError> {
+        let result = self.eof_inner();
+        result.map_err(|err| self.locate(err))
+    }
+
+    fn eof_inner(&mut self) -> Result<(), ParserError> {
         if self.in_header {
             self.parse_header_line()?;
+        } else if self.in_comment_or_header {
+            self.finish_comment();
         }
 
-        self.finish_literal();
+        self.finish_literal()?;
 
         if !self.partial_clause.is_empty() {
             return Err(ParserError::UnterminatedClause {
@@ -246,13 +542,32 @@ impl DimacsParser {
             });
         }
 
+        if self.strict && self.header.is_none() {
+            return Err(ParserError::MissingHeader {
+                line: self.line_number,
+            });
+        }
+
+        if let Some(header) = self.header {
+            if self.clause_count != header.clause_count {
+                self.warn(ParserWarning::ClauseCount {
+                    clause_count: self.clause_count,
+                    header_clause_count: header.clause_count,
+                });
+            }
+        }
+
         Ok(())
     }
 
     /// Verifies the header information when present.
     ///
-    /// Does nothing when the input doesn't contain a header.
+    /// Does nothing when the input doesn't contain a header or when a warning callback is set.
     pub fn check_header(&self) -> Result<(), ParserError> {
+        if self.warning_callback.is_some() {
+            return Ok(());
+        }
+
         if let Some(header) = self.header {
             let var_count = self.formula.var_count();
             if var_count != header.var_count {
@@ -302,8 +617,20 @@ impl DimacsParser {
         self.formula.var_count()
     }
 
-    fn finish_literal(&mut self) {
+    /// Remember the position of a literal or header that starts at the current byte.
+    fn start_lit(&mut self) {
+        self.lit_line = self.line_number;
+        self.lit_column = self.column_number;
+    }
+
+    fn finish_literal(&mut self) -> Result<(), ParserError> {
         if self.in_lit {
+            if self.strict {
+                self.check_strict()?;
+            }
+            if self.warning_callback.is_some() {
+                self.check_warnings();
+            }
             if self.partial_lit == 0 {
                 self.formula.add_clause(&self.partial_clause);
                 self.partial_clause.clear();
@@ -313,6 +640,100 @@ impl DimacsParser {
                     .push(Var::from_dimacs(self.partial_lit as isize).lit(!self.negate_next_lit));
             }
         }
+        Ok(())
+    }
+
+    /// Check a complete literal against the header.
+    fn check_strict(&mut self) -> Result<(), ParserError> {
+        let line = self.lit_line;
+        let result = match self.header {
+            None => Err(ParserError::MissingHeader { line }),
+            Some(header) if self.partial_lit == 0 && self.clause_count == header.clause_count => {
+                Err(ParserError::TooManyClauses {
+                    line,
+                    header_clause_count: header.clause_count,
+                })
+            }
+            Some(header) if self.partial_lit > header.var_count => {
+                Err(ParserError::VarOutOfRange {
+                    line,
+                    var: self.partial_lit,
+                    header_var_count: header.var_count,
+                })
+            }
+            Some(_) => Ok(()),
+        };
+        if result.is_err() {
+            self.error = true;
+        }
+        result
+    }
+
+    /// Store a complete comment line when retaining comments.
+    fn finish_comment(&mut self) {
+        if let Some(comments) = &mut self.comments {
+            let line = String::from_utf8_lossy(&self.comment_line);
+            let text = if line.starts_with(' ') {
+                &line[1..]
+            } else {
+                &line[..]
+            };
+            comments.clauses.push((self.clause_count, text.to_owned()));
+            self.comment_line.clear();
+        }
+    }
+
+    /// Report issues of a complete literal to the warning callback.
+    fn check_warnings(&mut self) {
+        let line = self.lit_line;
+        if self.partial_lit == 0 {
+            let mut clause = self.partial_clause.clone();
+            clause.sort_unstable();
+            for (index, pair) in clause.windows(2).enumerate() {
+                let repeated = index > 0 && clause[index - 1] == pair[0];
+                if pair[0] == pair[1] && !repeated {
+                    self.warn(ParserWarning::DuplicateLiteral { line, lit: pair[0] });
+                }
+            }
+        } else if let Some(header) = self.header {
+            if self.partial_lit > self.formula.var_count() {
+                // Only report the first occurrence of each variable
+                self.formula.set_var_count(self.partial_lit);
+                self.warn(ParserWarning::VarOutOfRange {
+                    line,
+                    var: self.partial_lit,
+                    header_var_count: header.var_count,
+                });
+            }
+        }
+    }
+
+    /// Invoke the warning callback if set.
+    fn warn(&mut self, warning: ParserWarning) {
+        if let Some(callback) = &mut self.warning_callback {
+            callback(warning);
+        }
+    }
+
+    /// Add the column of an error's position in strict mode.
+    fn locate(&self, err: ParserError) -> ParserError {
+        if !self.strict {
+            return err;
+        }
+        let column = match err {
+            ParserError::UnexpectedInput { .. } => self.column_number,
+            ParserError::UnterminatedClause { .. } | ParserError::MissingHeader { .. }
+                if !self.in_lit =>
+            {
+                self.column_number + 1
+            }
+            ParserError::PreviousError => return err,
+            _ => self.lit_column,
+        };
+        ParserError::InColumn {
+            column,
+            error: Box::new(err),
+        }
     }
 
     fn parse_header_line(&mut self) -> Result<(), ParserError> {
@@ -364,6 +785,16 @@ impl DimacsParser {
 
         self.formula.set_var_count(var_count);
 
+        // Comments preceding the header form the header block
+        if self.clause_count == 0 && self.partial_clause.is_empty() {
+            if let Some(comments) = &mut self.comments {
+                let before_header = replace(&mut comments.clauses, vec![]);
+                comments
+                    .header
+                    .extend(before_header.into_iter().map(|(_, text)| text));
+            }
+        }
+
         Ok(())
     }
 
@@ -405,9 +836,53 @@ pub fn write_dimacs_clauses(
     Ok(())
 }
 
+/// Write a formula as DIMACS CNF including the given comments.
+///
+/// The header comments are written before the header line, the other comments before the clause
+/// with their index, see [`DimacsComments`]. Comments with an index beyond the last clause are
+/// written at the end.
+pub fn write_dimacs_with_comments(
+    target: &mut impl io::Write,
+    formula: &CnfFormula,
+    comments: &DimacsComments,
+) -> io::Result<()> {
+    let mut writer = DimacsWriter::new(
+        &mut *target,
+        &DimacsWriterConfig {
+            comments: comments.header.clone(),
+            header: DimacsHeaderMode::Custom(DimacsHeader {
+                var_count: formula.var_count(),
+                clause_count: formula.len(),
+            }),
+            ..DimacsWriterConfig::default()
+        },
+    )?;
+
+    let mut clause_comments = comments.clauses.iter().peekable();
+
+    for (index, clause) in formula.iter().enumerate() {
+        while let Some(&&(at, ref text)) = clause_comments.peek() {
+            if at > index {
+                break;
+            }
+            writer.write_comment(text)?;
+            clause_comments.next();
+        }
+        writer.write_clause(clause)?;
+    }
+
+    for (_, text) in clause_comments {
+        writer.write_comment(text)?;
+    }
+
+    Ok(())
+}
+
 /// Write a formula as DIMACS CNF.
 ///
-/// Use [`write_dimacs_header`] and [`write_dimacs_clauses`] to implement incremental writing.
+/// Use [`write_dimacs_header`] and [`write_dimacs_clauses`] or a [`DimacsWriter`] to implement
+/// incremental writing. Comments, custom headers and line wrapping are supported by
+/// [`write_dimacs_with_config`].
 pub fn write_dimacs(target: &mut impl io::Write, formula: &CnfFormula) -> io::Result<()> {
     write_dimacs_header(
         &mut *target,
@@ -423,13 +898,12 @@ pub fn write_dimacs(target: &mut impl io::Write, formula: &CnfFormula) -> io::Re
 mod tests {
     use super::*;
 
-    use failure::{Error, ResultExt};
     use proptest::*;
 
-    use varisat_formula::{cnf::strategy::*, cnf_formula};
+    use varisat_formula::{cnf::strategy::*, cnf_formula, lit};
 
     #[test]
-    fn odd_whitespace() -> Result<(), Error> {
+    fn odd_whitespace() -> Result<(), ParserError> {
         let parsed = DimacsParser::parse(
             b"p  cnf  4   3  \n  1  \n 2  3\n0 -4 0 2\nccomment  \n\n0\n\n" as &[_],
         )?;
@@ -449,12 +923,9 @@ mod tests {
         ( $input:expr, $( $cases:tt )* ) => {
             match DimacsParser::parse($input as &[_]) {
                 Ok(parsed) => panic!("Expexcted errror but got {:?}", parsed),
-                Err(err) => match err.downcast_ref() {
-                    Some(casted_err) => match casted_err {
-                        $( $cases )*,
-                        _ => panic!("Unexpected error {:?}", casted_err),
-                    },
-                    None => panic!("Unexpected error type {:?}", err),
+                Err(err) => match err {
+                    $( $cases )*,
+                    _ => panic!("Unexpected error {:?}", err),
                 }
             }
         };
@@ -501,6 +972,176 @@ mod tests {
         );
     }
 
+    fn expect_strict_error(input: &[u8], expected_column: usize) -> ParserError {
+        match DimacsParser::parse_strict(input) {
+            Ok(parsed) => panic!("Expected error but got {:?}", parsed),
+            Err(err) => match err {
+                ParserError::InColumn { column, error } => {
+                    assert_eq!(column, expected_column);
+                    *error
+                }
+                err => panic!("Unexpected error {:?}", err),
+            },
+        }
+    }
+
+    #[test]
+    fn parse_slice() {
+        let input = b"c comment\np cnf 3 2\n1 -3 0\n2\n0";
+        assert_eq!(
+            DimacsParser::parse_slice(input).unwrap(),
+            cnf_formula![
+                1, -3;
+                2;
+            ]
+        );
+
+        match DimacsParser::parse_slice(b"p cnf 3 2\n1 0\n").unwrap_err() {
+            ParserError::ClauseCount { .. } => (),
+            err => panic!("Unexpected error {:?}", err),
+        }
+    }
+
+    #[test]
+    fn strict_mode() {
+        let input = b"c comment\np cnf 3 2\n1 -3 0\n2 0\n";
+        assert_eq!(
+            DimacsParser::parse_strict(&input[..]).unwrap(),
+            DimacsParser::parse(&input[..]).unwrap()
+        );
+
+        match expect_strict_error(b"1 2 0\n", 1) {
+            ParserError::MissingHeader { line: 1 } => (),
+            err => panic!("Unexpected error {:?}", err),
+        }
+        match expect_strict_error(b"c empty\n", 1) {
+            ParserError::MissingHeader { line: 2 } => (),
+            err => panic!("Unexpected error {:?}", err),
+        }
+        match expect_strict_error(b"p cnf 3 2\n1 2 0\n3  -4 0\n", 4) {
+            ParserError::VarOutOfRange {
+                line: 3,
+                var: 4,
+                header_var_count: 3,
+            } => (),
+            err => panic!("Unexpected error {:?}", err),
+        }
+        match expect_strict_error(b"p cnf 3 1\n1 2 0 3 0\n", 9) {
+            ParserError::TooManyClauses {
+                line: 2,
+                header_clause_count: 1,
+            } => (),
+            err => panic!("Unexpected error {:?}", err),
+        }
+        match expect_strict_error(b"p cnf 3 1\n1 2 x 0\n", 5) {
+            ParserError::UnexpectedInput {
+                line: 2,
+                unexpected: 'x',
+            } => (),
+            err => panic!("Unexpected error {:?}", err),
+        }
+
+        // Permissive mode accepts the same input up to the header check
+        DimacsParser::parse(b"1 2 0\n" as &[_]).unwrap();
+    }
+
+    #[test]
+    fn comment_roundtrip() {
+        let input = b"c benchmark\nc\np cnf 3 3\nc first clause\n1 -2 0\n2\nc within clause\n3 0\n\
+                      -1 0\nc   indented\nc end";
+
+        let (formula, comments) = DimacsParser::parse_with_comments(&input[..]).unwrap();
+
+        assert_eq!(
+            comments,
+            DimacsComments {
+                header: vec!["benchmark".to_owned(), "".to_owned()],
+                clauses: vec![
+                    (0, "first clause".to_owned()),
+                    (1, "within clause".to_owned()),
+                    (3, "  indented".to_owned()),
+                    (3, "end".to_owned()),
+                ],
+            }
+        );
+
+        let mut output = vec![];
+        write_dimacs_with_comments(&mut output, &formula, &comments).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "c benchmark\nc\np cnf 3 3\nc first clause\n1 -2 0\nc within clause\n2 3 0\n-1 0\n\
+             c   indented\nc end\n"
+        );
+
+        assert_eq!(
+            DimacsParser::parse_with_comments(&b"c no header\n1 0\n"[..])
+                .unwrap()
+                .1
+                .clauses,
+            vec![(0, "no header".to_owned())]
+        );
+    }
+
+    #[test]
+    fn warnings() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        let formula = DimacsParser::parse_with_warnings(
+            &b"p cnf 2 3\n1 -2 1 0\n3 -4 3 1 3 0\n-4 0\n"[..],
+            move |warning| sender.send(warning).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            formula,
+            cnf_formula![
+                1, -2, 1;
+                3, -4, 3, 1, 3;
+                -4;
+            ]
+        );
+
+        assert_eq!(
+            receiver.iter().collect::<Vec<_>>(),
+            vec![
+                ParserWarning::DuplicateLiteral {
+                    line: 2,
+                    lit: lit!(1)
+                },
+                ParserWarning::VarOutOfRange {
+                    line: 3,
+                    var: 3,
+                    header_var_count: 2
+                },
+                ParserWarning::VarOutOfRange {
+                    line: 3,
+                    var: 4,
+                    header_var_count: 2
+                },
+                ParserWarning::DuplicateLiteral {
+                    line: 3,
+                    lit: lit!(3)
+                },
+            ]
+        );
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        DimacsParser::parse_with_warnings(&b"p cnf 2 3\n1 -2 0\n"[..], move |warning| {
+            sender.send(warning).unwrap()
+        })
+        .unwrap();
+
+        assert_eq!(
+            receiver.iter().collect::<Vec<_>>(),
+            vec![ParserWarning::ClauseCount {
+                clause_count: 1,
+                header_clause_count: 3
+            }]
+        );
+    }
+
     #[test]
     fn syntax_errors() {
         expect_error!(
@@ -561,10 +1202,9 @@ mod tests {
 
             write_dimacs(&mut buf, &input)?;
 
-            let parsed = DimacsParser::parse(&buf[..]).compat()?;
+            let parsed = DimacsParser::parse(&buf[..])?;
 
             prop_assert_eq!(parsed, input);
         }
     }
-
 }
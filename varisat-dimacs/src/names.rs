@@ -0,0 +1,97 @@
+//! Variable names stored in DIMACS CNF comments.
+use std::io;
+
+use varisat_formula::{names::VarNames, Var};
+
+use crate::icnf::parse_lit;
+use crate::ParserError;
+
+/// Write the names of variables as DIMACS comments.
+///
+/// Each name is written on a line `c var <variable> <name>`, ordered by variable. Names must not
+/// contain line breaks.
+pub fn write_var_names(target: &mut impl io::Write, names: &VarNames) -> io::Result<()> {
+    for (var, name) in names.iter() {
+        writeln!(target, "c var {} {}", var, name)?;
+    }
+    Ok(())
+}
+
+/// Read the variable names stored in DIMACS comments.
+///
+/// This reads the `c var <variable> <name>` lines written by [`write_var_names`] and ignores
+/// everything else, so it can be used on a complete DIMACS CNF file. Leading and trailing
+/// whitespace of names is removed.
+pub fn read_var_names(input: impl io::Read) -> Result<VarNames, ParserError> {
+    use io::BufRead;
+
+    let mut names = VarNames::new();
+
+    for (line_index, line) in io::BufReader::new(input).split(b'\n').enumerate() {
+        let line = line?;
+        if !line.starts_with(b"c var ") {
+            continue;
+        }
+        let line_number = line_index + 1;
+        let line = String::from_utf8_lossy(&line[6..]);
+        let line = line.trim();
+
+        let (var, name) = match line.find(char::is_whitespace) {
+            Some(split) => (&line[..split], line[split..].trim_start()),
+            None => (line, ""),
+        };
+
+        let var: Var = match parse_lit(line_number, var)? {
+            Some(lit) if lit.is_positive() && !name.is_empty() => lit.var(),
+            _ => {
+                return Err(ParserError::UnexpectedToken {
+                    line: line_number,
+                    token: line.to_owned(),
+                })
+            }
+        };
+
+        names.insert(name, var);
+    }
+
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::{var, CnfFormula, ExtendFormula};
+
+    use crate::{write_dimacs, DimacsParser};
+
+    #[test]
+    fn roundtrip() {
+        let mut formula = CnfFormula::new();
+        let mut names = VarNames::new();
+
+        let a = names.lit(&mut formula, "alpha");
+        let b = names.lit(&mut formula, "beta gamma");
+        formula.add_clause(&[a, !b]);
+
+        let mut output = vec![];
+        write_var_names(&mut output, &names).unwrap();
+        write_dimacs(&mut output, &formula).unwrap();
+
+        assert_eq!(
+            &output[..],
+            &b"c var 1 alpha\nc var 2 beta gamma\np cnf 2 1\n1 -2 0\n"[..]
+        );
+
+        assert_eq!(DimacsParser::parse(&output[..]).unwrap(), formula);
+
+        let parsed = read_var_names(&output[..]).unwrap();
+        assert_eq!(parsed.get("alpha"), Some(var!(1)));
+        assert_eq!(parsed.get("beta gamma"), Some(var!(2)));
+        assert_eq!(parsed.len(), 2);
+
+        assert!(read_var_names(&b"c var -1 x\n"[..]).is_err());
+        assert!(read_var_names(&b"c var 1\n"[..]).is_err());
+        assert!(read_var_names(&b"c variables\n"[..]).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,387 @@
+//! OPB parser for pseudo-Boolean problems.
+use std::io;
+
+use varisat_formula::{Lit, PbConstraint, PbProblem, PbRelation, Var};
+
+use crate::{decompress, ParserError};
+
+/// Parser for OPB files as used by the pseudo-Boolean competitions.
+///
+/// Supports linear constraints using `>=`, `=` and `<=`, negated literals written as `~x1` and
+/// an optional `min:` objective. A statement ends with a `;` and can span multiple lines. The
+/// `* #variable= n #constraint= m` comment line is used as header when present.
+///
+/// The resulting [`PbProblem`] can be encoded into CNF using [`PbProblem::to_cnf`].
+pub struct OpbParser;
+
+/// Variable and constraint count present in an OPB header comment.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct OpbHeader {
+    pub var_count: usize,
+    pub constraint_count: usize,
+}
+
+impl OpbParser {
+    /// Parse the given input and check the header if present.
+    ///
+    /// Compressed input is detected and decompressed, see [`decompress`].
+    pub fn parse(input: impl io::Read) -> Result<PbProblem, ParserError> {
+        use io::BufRead;
+
+        let buffer = io::BufReader::new(decompress(input)?);
+
+        let mut problem = PbProblem::default();
+        let mut header = None;
+        let mut statement = Statement::default();
+
+        for (line_index, line) in buffer.split(b'\n').enumerate() {
+            let line = line?;
+            let line_number = line_index + 1;
+            let line = String::from_utf8_lossy(&line);
+
+            if line.starts_with('*') {
+                if header.is_none() && problem.constraints.is_empty() {
+                    header = parse_header(&line);
+                }
+                continue;
+            }
+
+            for token in line.split_whitespace() {
+                // A `;` can be attached to the last token of a statement
+                let (token, terminated) = match token.len() {
+                    1 => (token, token == ";"),
+                    _ if token.ends_with(';') => (&token[..token.len() - 1], true),
+                    _ => (token, false),
+                };
+
+                if token != ";" {
+                    statement.token(line_number, token)?;
+                }
+                if terminated {
+                    statement.finish(line_number, &mut problem)?;
+                }
+            }
+        }
+
+        if statement.started {
+            return Err(ParserError::UnterminatedConstraint {
+                line: statement.line,
+            });
+        }
+
+        if let Some(header) = header {
+            if problem.var_count > header.var_count {
+                return Err(ParserError::VarCount {
+                    var_count: problem.var_count,
+                    header_var_count: header.var_count,
+                });
+            }
+            problem.var_count = header.var_count;
+
+            if problem.constraints.len() != header.constraint_count {
+                return Err(ParserError::ConstraintCount {
+                    constraint_count: problem.constraints.len(),
+                    header_constraint_count: header.constraint_count,
+                });
+            }
+        }
+
+        Ok(problem)
+    }
+}
+
+/// Parse the `* #variable= n #constraint= m` header comment.
+pub(crate) fn parse_header(line: &str) -> Option<OpbHeader> {
+    let mut var_count = None;
+    let mut constraint_count = None;
+
+    let mut tokens = line[1..].split_whitespace();
+    while let Some(token) = tokens.next() {
+        match token {
+            "#variable=" => var_count = tokens.next().and_then(|value| value.parse().ok()),
+            "#constraint=" => constraint_count = tokens.next().and_then(|value| value.parse().ok()),
+            _ => (),
+        }
+    }
+
+    Some(OpbHeader {
+        var_count: var_count?,
+        constraint_count: constraint_count?,
+    })
+}
+
+/// Statement that is currently parsed.
+#[derive(Default)]
+pub(crate) struct Statement {
+    /// Whether any token of the statement was parsed.
+    pub started: bool,
+    /// Line of the first token.
+    pub line: usize,
+    objective: bool,
+    terms: Vec<(i64, Lit)>,
+    /// Coefficient of the term that is currently parsed.
+    coefficient: Option<i64>,
+    /// Whether the current term already has a literal.
+    has_lit: bool,
+    relation: Option<PbRelation>,
+    rhs: Option<i64>,
+}
+
+impl Statement {
+    /// Process a token that is not the terminating `;`.
+    pub fn token(&mut self, line: usize, token: &str) -> Result<(), ParserError> {
+        if !self.started {
+            self.started = true;
+            self.line = line;
+            if token == "min:" {
+                self.objective = true;
+                return Ok(());
+            }
+        }
+
+        let unexpected = || ParserError::UnexpectedToken {
+            line,
+            token: token.to_owned(),
+        };
+
+        if self.rhs.is_some() {
+            return Err(unexpected());
+        }
+
+        if self.relation.is_some() {
+            self.rhs = Some(parse_coefficient(line, token)?.ok_or_else(unexpected)?);
+            return Ok(());
+        }
+
+        let relation = match token {
+            ">=" => Some(PbRelation::AtLeast),
+            "=" => Some(PbRelation::Equal),
+            "<=" => Some(PbRelation::AtMost),
+            _ => None,
+        };
+
+        if let Some(relation) = relation {
+            if self.objective || (self.coefficient.is_some() && !self.has_lit) {
+                return Err(unexpected());
+            }
+            self.relation = Some(relation);
+            return Ok(());
+        }
+
+        if let Some(coefficient) = parse_coefficient(line, token)? {
+            if self.coefficient.is_some() && !self.has_lit {
+                return Err(unexpected());
+            }
+            self.coefficient = Some(coefficient);
+            self.has_lit = false;
+            return Ok(());
+        }
+
+        let lit = parse_lit(line, token)?.ok_or_else(unexpected)?;
+
+        match self.coefficient {
+            None => return Err(unexpected()),
+            Some(_) if self.has_lit => return Err(ParserError::NonLinearTerm { line }),
+            Some(coefficient) => {
+                self.terms.push((coefficient, lit));
+                self.has_lit = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Process the terminating `;` of a statement.
+    pub fn finish(&mut self, line: usize, problem: &mut PbProblem) -> Result<(), ParserError> {
+        let statement = std::mem::replace(self, Statement::default());
+
+        if !statement.started {
+            return Ok(());
+        }
+
+        if statement.coefficient.is_some() && !statement.has_lit {
+            return Err(ParserError::UnexpectedToken {
+                line,
+                token: ";".to_owned(),
+            });
+        }
+
+        for &(_, lit) in statement.terms.iter() {
+            problem.var_count = problem.var_count.max(lit.index() + 1);
+        }
+
+        if statement.objective {
+            if problem.objective.is_some() || !problem.constraints.is_empty() {
+                return Err(ParserError::UnexpectedObjective {
+                    line: statement.line,
+                });
+            }
+            problem.objective = Some(statement.terms);
+        } else {
+            match (statement.relation, statement.rhs) {
+                (Some(relation), Some(rhs)) => problem.constraints.push(PbConstraint {
+                    terms: statement.terms,
+                    relation,
+                    rhs,
+                }),
+                _ => {
+                    return Err(ParserError::IncompleteConstraint {
+                        line: statement.line,
+                    })
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse an integer with optional sign.
+///
+/// Returns `None` if the token is not an integer.
+pub(crate) fn parse_coefficient(line: usize, token: &str) -> Result<Option<i64>, ParserError> {
+    let digits = match token.as_bytes().first() {
+        Some(b'+') | Some(b'-') => &token[1..],
+        _ => token,
+    };
+
+    if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Ok(None);
+    }
+
+    match token.parse() {
+        Ok(value) => Ok(Some(value)),
+        Err(_) => Err(ParserError::CoefficientTooLarge {
+            line,
+            coefficient: token.to_owned(),
+        }),
+    }
+}
+
+/// Parse a possibly negated variable like `x3` or `~x3`.
+///
+/// Returns `None` if the token is not a literal.
+fn parse_lit(line: usize, token: &str) -> Result<Option<Lit>, ParserError> {
+    let (negated, var) = if token.starts_with('~') {
+        (true, &token[1..])
+    } else {
+        (false, token)
+    };
+
+    if !var.starts_with('x') {
+        return Ok(None);
+    }
+
+    let digits = &var[1..];
+
+    if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Ok(None);
+    }
+
+    let index: usize = match digits.parse() {
+        Ok(index) if index <= Var::max_count() => index,
+        _ => {
+            let (index, final_digit) = digits.split_at(digits.len() - 1);
+            return Err(ParserError::LiteralTooLarge {
+                line,
+                index: index.parse().unwrap_or(usize::max_value()),
+                final_digit: final_digit.parse().unwrap(),
+            });
+        }
+    };
+
+    if index == 0 {
+        return Ok(None);
+    }
+
+    Ok(Some(Var::from_dimacs(index as isize).lit(!negated)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::lit;
+
+    fn expect_error(input: &[u8], check: impl FnOnce(&ParserError) -> bool) {
+        match OpbParser::parse(input) {
+            Ok(parsed) => panic!("expected error but got {:?}", parsed),
+            Err(err) => {
+                if !check(&err) {
+                    panic!("unexpected error {:?}", err)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse_problem() {
+        let parsed = OpbParser::parse(
+            b"* #variable= 4 #constraint= 3\n\
+              * comment\n\
+              min: +2 x1 -3 ~x2 ;\n\
+              +1 x1 +1 x2\n+1 x3 >= 2;\n\
+              -2 x1 +3 ~x3 = 1 ;\n\
+              1 x2 <= -0 ;\n" as &[_],
+        )
+        .unwrap();
+
+        assert_eq!(parsed.var_count, 4);
+        assert_eq!(parsed.objective, Some(vec![(2, lit!(1)), (-3, lit!(-2))]));
+        assert_eq!(
+            parsed.constraints,
+            vec![
+                PbConstraint {
+                    terms: vec![(1, lit!(1)), (1, lit!(2)), (1, lit!(3))],
+                    relation: PbRelation::AtLeast,
+                    rhs: 2,
+                },
+                PbConstraint {
+                    terms: vec![(-2, lit!(1)), (3, lit!(-3))],
+                    relation: PbRelation::Equal,
+                    rhs: 1,
+                },
+                PbConstraint {
+                    terms: vec![(1, lit!(2))],
+                    relation: PbRelation::AtMost,
+                    rhs: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_input() {
+        expect_error(b"+1 x1 x2 >= 1 ;\n", |err| match err {
+            ParserError::NonLinearTerm { line: 1 } => true,
+            _ => false,
+        });
+        expect_error(b"+1 x1 >= 1 ;\n+1 x1 >= y ;\n", |err| match err {
+            ParserError::UnexpectedToken { line: 2, .. } => true,
+            _ => false,
+        });
+        expect_error(b"+1 x1 >= 1\n", |err| match err {
+            ParserError::UnterminatedConstraint { line: 1 } => true,
+            _ => false,
+        });
+        expect_error(b"+1 x1 ;\n", |err| match err {
+            ParserError::IncompleteConstraint { line: 1 } => true,
+            _ => false,
+        });
+        expect_error(b"+99999999999999999999 x1 >= 1 ;\n", |err| match err {
+            ParserError::CoefficientTooLarge { line: 1, .. } => true,
+            _ => false,
+        });
+        expect_error(b"+1 x1 >= 1 ;\nmin: +1 x1 ;\n", |err| match err {
+            ParserError::UnexpectedObjective { line: 2 } => true,
+            _ => false,
+        });
+        expect_error(
+            b"* #variable= 1 #constraint= 1\n+1 x2 >= 1 ;\n",
+            |err| match err {
+                ParserError::VarCount { .. } => true,
+                _ => false,
+            },
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! Parallel parsing of large DIMACS CNF inputs.
+use varisat_formula::{CnfFormula, ExtendFormula};
+
+use crate::{DimacsParser, InputCompression, ParserError};
+
+impl DimacsParser {
+    /// Parse an in-memory input using multiple threads and check the header if present.
+    ///
+    /// The input is split into up to `threads` chunks at clause boundaries, which are parsed in
+    /// parallel and then merged into a single formula. The result is the same as that of
+    /// [`parse_slice`](DimacsParser::parse_slice). If a chunk cannot be parsed on its own, the
+    /// input is parsed again sequentially, so that errors are reported exactly as by the
+    /// sequential parser.
+    ///
+    /// Compressed input is always parsed sequentially.
+    pub fn parse_slice_parallel(input: &[u8], threads: usize) -> Result<CnfFormula, ParserError> {
+        if threads <= 1 || InputCompression::detect(input).is_some() {
+            return Self::parse_slice(input);
+        }
+
+        let chunks = split_at_clauses(input, threads);
+
+        if chunks.len() <= 1 {
+            return Self::parse_slice(input);
+        }
+
+        let results = crossbeam_utils::thread::scope(|scope| {
+            let handles: Vec<_> = chunks
+                .iter()
+                .map(|&chunk| {
+                    scope.spawn(move |_| -> Result<DimacsParser, ParserError> {
+                        let mut parser = DimacsParser::new();
+                        parser.parse_chunk(chunk)?;
+                        parser.eof()?;
+                        Ok(parser)
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .unwrap();
+
+        let mut parsers = vec![];
+        for result in results {
+            match result {
+                Ok(parser) => parsers.push(parser),
+                Err(_) => return Self::parse_slice(input),
+            }
+        }
+
+        // Whether a header after the first chunk is valid depends on the preceding chunks
+        if parsers[1..].iter().any(|parser| parser.header.is_some()) {
+            return Self::parse_slice(input);
+        }
+
+        let mut parsers = parsers.into_iter();
+        let mut merged = parsers.next().unwrap();
+
+        for mut parser in parsers {
+            let formula = parser.take_formula();
+            merged.formula.set_var_count(formula.var_count());
+            for clause in formula.iter() {
+                merged.formula.add_clause(clause);
+            }
+            merged.clause_count += parser.clause_count;
+        }
+
+        merged.check_header()?;
+
+        Ok(merged.take_formula())
+    }
+}
+
+/// Split the input into up to `parts` chunks of similar size that end at clause boundaries.
+fn split_at_clauses(input: &[u8], parts: usize) -> Vec<&[u8]> {
+    let mut chunks = vec![];
+    let mut start = 0;
+
+    for part in 1..parts {
+        let target = input.len() / parts * part;
+        if target < start {
+            continue;
+        }
+        match find_clause_boundary(input, target) {
+            Some(end) => {
+                chunks.push(&input[start..end]);
+                start = end;
+            }
+            None => break,
+        }
+    }
+
+    if start < input.len() || chunks.is_empty() {
+        chunks.push(&input[start..]);
+    }
+    chunks
+}
+
+/// Find the first position at or after `from` that directly follows a line ending a clause.
+fn find_clause_boundary(input: &[u8], from: usize) -> Option<usize> {
+    for pos in from..input.len() {
+        if input[pos] != b'\n' {
+            continue;
+        }
+        let line_start = input[..pos]
+            .iter()
+            .rposition(|&byte| byte == b'\n' || byte == b'\r')
+            .map(|line_break| line_break + 1)
+            .unwrap_or(0);
+
+        if ends_clause(&input[line_start..pos]) {
+            return Some(pos + 1);
+        }
+    }
+    None
+}
+
+/// Whether a line without line breaks ends with the terminating zero of a clause.
+///
+/// This is conservative, it only has to be exact for inputs without syntax errors.
+fn ends_clause(line: &[u8]) -> bool {
+    match line.first() {
+        None | Some(b'c') | Some(b'p') => false,
+        _ => {
+            let len = line.len() - line.iter().rev().take_while(|&&byte| byte == b' ').count();
+            let line = &line[..len];
+            line.ends_with(b"0") && (len == 1 || line[len - 2] == b' ')
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fmt::Write;
+
+    #[test]
+    fn split_points() {
+        let input = b"p cnf 3 3\nc 0\n1 2\n0\n-3 10\n3 0\n";
+        let chunks = split_at_clauses(input, 6);
+        assert_eq!(
+            chunks,
+            vec![&b"p cnf 3 3\nc 0\n1 2\n0\n"[..], &b"-3 10\n3 0\n"[..]]
+        );
+    }
+
+    #[test]
+    fn same_result_as_sequential() {
+        let mut input = String::from("c generated\np cnf 50 301\n");
+        for clause in 0..300 {
+            for lit in 0..(clause % 7) {
+                let var = (clause * 13 + lit * 7) % 50 + 1;
+                let sign = if (clause + lit) % 3 == 0 { "-" } else { "" };
+                write!(input, "{}{} ", sign, var).unwrap();
+                if lit == 3 {
+                    input.push('\n');
+                }
+            }
+            input.push_str("0\n");
+            if clause % 50 == 0 {
+                input.push_str("c comment 0\n");
+            }
+        }
+        input.push_str("50 0\n");
+
+        let sequential = DimacsParser::parse_slice(input.as_bytes()).unwrap();
+
+        for threads in 1..8 {
+            assert_eq!(
+                DimacsParser::parse_slice_parallel(input.as_bytes(), threads).unwrap(),
+                sequential
+            );
+        }
+    }
+
+    #[test]
+    fn errors_match_sequential() {
+        let mut input = String::from("p cnf 2 100\n");
+        for _ in 0..99 {
+            input.push_str("1 -2 0\n");
+        }
+        input.push_str("1 x 0\n");
+
+        match DimacsParser::parse_slice_parallel(input.as_bytes(), 4).unwrap_err() {
+            ParserError::UnexpectedInput {
+                line: 101,
+                unexpected: 'x',
+            } => (),
+            err => panic!("Unexpected error {:?}", err),
+        }
+    }
+}
@@ -0,0 +1,385 @@
+//! QDIMACS parser for quantified boolean formulas.
+use std::io;
+
+use varisat_formula::{CnfFormula, Var};
+
+use crate::{decompress, DimacsHeader, DimacsParser, ParserError};
+
+/// Quantifier of a block of variables.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Quantifier {
+    Exists,
+    Forall,
+}
+
+/// A block of variables sharing the same quantifier.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct QuantifierBlock {
+    pub quantifier: Quantifier,
+    pub vars: Vec<Var>,
+}
+
+/// A quantified boolean formula in prenex conjunctive normal form.
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct QdimacsFormula {
+    /// Quantifier blocks from outermost to innermost.
+    ///
+    /// Consecutive blocks always have different quantifiers. Variables of the matrix that are not
+    /// part of any block are free variables. They are usually treated as existentially quantified
+    /// in an outermost block.
+    pub prefix: Vec<QuantifierBlock>,
+    /// The propositional part of the formula.
+    pub matrix: CnfFormula,
+}
+
+/// Parser for QDIMACS files.
+///
+/// The quantifier prefix is parsed line by line, while the matrix is parsed by a
+/// [`DimacsParser`]. Like that parser, this parser can consume the input in chunks.
+pub struct QdimacsParser {
+    matrix: DimacsParser,
+    prefix: Vec<QuantifierBlock>,
+    /// Whether a variable is part of the prefix, indexed by variable index.
+    quantified: Vec<bool>,
+    /// Partial line of the prefix.
+    line: Vec<u8>,
+    line_number: usize,
+    in_matrix: bool,
+    error: bool,
+}
+
+impl Default for QdimacsParser {
+    fn default() -> QdimacsParser {
+        QdimacsParser::new()
+    }
+}
+
+impl QdimacsParser {
+    /// Create a new QDIMACS parser.
+    pub fn new() -> QdimacsParser {
+        QdimacsParser {
+            matrix: DimacsParser::new(),
+            prefix: vec![],
+            quantified: vec![],
+            line: vec![],
+            line_number: 1,
+            in_matrix: false,
+            error: false,
+        }
+    }
+
+    /// Parse the given input and check the header if present.
+    ///
+    /// Compressed input is detected and decompressed, see [`decompress`].
+    pub fn parse(input: impl io::Read) -> Result<QdimacsFormula, ParserError> {
+        use io::BufRead;
+
+        let mut buffer = io::BufReader::new(decompress(input)?);
+        let mut parser = Self::new();
+
+        loop {
+            let data = buffer.fill_buf()?;
+            if data.is_empty() {
+                break;
+            }
+            parser.parse_chunk(data)?;
+            let len = data.len();
+            buffer.consume(len);
+        }
+        parser.eof()?;
+        parser.check_header()?;
+
+        Ok(parser.into_formula())
+    }
+
+    /// Parse a chunk of input.
+    ///
+    /// After parsing the last chunk call the [`eof`](QdimacsParser::eof) method.
+    ///
+    /// If this method returns an error, the parser is in an invalid state and cannot parse further
+    /// chunks.
+    pub fn parse_chunk(&mut self, chunk: &[u8]) -> Result<(), ParserError> {
+        if self.error {
+            return Err(ParserError::PreviousError);
+        }
+
+        let mut rest = chunk;
+
+        while !self.in_matrix {
+            match rest.iter().position(|&byte| byte == b'\n') {
+                Some(pos) => {
+                    self.line.extend_from_slice(&rest[..=pos]);
+                    rest = &rest[pos + 1..];
+                    self.prefix_line()?;
+                }
+                None => {
+                    self.line.extend_from_slice(rest);
+                    return Ok(());
+                }
+            }
+        }
+
+        self.parse_matrix_chunk(rest)
+    }
+
+    /// Finish parsing the input.
+    ///
+    /// This does not check whether the header information was correct, call
+    /// [`check_header`](QdimacsParser::check_header) for this.
+    pub fn eof(&mut self) -> Result<(), ParserError> {
+        if self.error {
+            return Err(ParserError::PreviousError);
+        }
+        if !self.in_matrix && !self.line.is_empty() {
+            self.prefix_line()?;
+        }
+        self.matrix.eof()
+    }
+
+    /// Verifies the header information when present.
+    ///
+    /// Does nothing when the input doesn't contain a header.
+    pub fn check_header(&self) -> Result<(), ParserError> {
+        self.matrix.check_header()?;
+        if let Some(header) = self.matrix.header() {
+            if self.quantified.len() > header.var_count {
+                return Err(ParserError::VarCount {
+                    var_count: self.quantified.len(),
+                    header_var_count: header.var_count,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the DIMACS CNF header data if present.
+    pub fn header(&self) -> Option<DimacsHeader> {
+        self.matrix.header()
+    }
+
+    /// The quantifier blocks parsed so far.
+    pub fn prefix(&self) -> &[QuantifierBlock] {
+        &self.prefix
+    }
+
+    /// Returns the parsed formula.
+    ///
+    /// The variable count of the matrix includes all quantified variables.
+    pub fn into_formula(mut self) -> QdimacsFormula {
+        let mut matrix = self.matrix.take_formula();
+        matrix.set_var_count(self.quantified.len());
+        QdimacsFormula {
+            prefix: self.prefix,
+            matrix,
+        }
+    }
+
+    /// Pass a chunk of the matrix to the DIMACS parser.
+    fn parse_matrix_chunk(&mut self, chunk: &[u8]) -> Result<(), ParserError> {
+        let result = self.matrix.parse_chunk(chunk).map_err(|err| match err {
+            ParserError::UnexpectedInput {
+                line,
+                unexpected: 'a',
+            }
+            | ParserError::UnexpectedInput {
+                line,
+                unexpected: 'e',
+            } => ParserError::QuantifierAfterClause { line },
+            err => err,
+        });
+        if result.is_err() {
+            self.error = true;
+        }
+        result
+    }
+
+    /// Process a complete line before the matrix.
+    fn prefix_line(&mut self) -> Result<(), ParserError> {
+        let line = std::mem::replace(&mut self.line, vec![]);
+        let first = line
+            .iter()
+            .cloned()
+            .find(|&byte| byte != b' ' && byte != b'\t');
+
+        let result = match first {
+            Some(b'a') | Some(b'e') => {
+                let result = self.quantifier_line(&line);
+                // Keep the line numbers of the DIMACS parser in sync
+                if line.ends_with(b"\n") {
+                    self.parse_matrix_chunk(b"\n")?;
+                }
+                result
+            }
+            Some(b'c') | Some(b'p') | Some(b'\n') | Some(b'\r') | None => {
+                self.parse_matrix_chunk(&line)
+            }
+            Some(_) => {
+                self.in_matrix = true;
+                self.parse_matrix_chunk(&line)
+            }
+        };
+
+        self.line_number += 1;
+        self.line = line;
+        self.line.clear();
+
+        if result.is_err() {
+            self.error = true;
+        }
+        result
+    }
+
+    /// Parse a line containing a quantifier block.
+    fn quantifier_line(&mut self, line: &[u8]) -> Result<(), ParserError> {
+        let text = String::from_utf8_lossy(line);
+        let mut tokens = text.split_whitespace();
+
+        let line_number = self.line_number;
+
+        let invalid = || ParserError::InvalidQuantifierBlock {
+            line: line_number,
+            block: text.trim_end().to_owned(),
+        };
+
+        let quantifier = match tokens.next() {
+            Some("a") => Quantifier::Forall,
+            Some("e") => Quantifier::Exists,
+            _ => return Err(invalid()),
+        };
+
+        let mut vars = vec![];
+        let mut terminated = false;
+
+        for token in tokens {
+            if terminated {
+                return Err(invalid());
+            }
+            let index: usize = match token.parse() {
+                Ok(index) if token.bytes().all(|byte| byte.is_ascii_digit()) => index,
+                _ => return Err(invalid()),
+            };
+            if index == 0 {
+                terminated = true;
+            } else if index > Var::max_count() {
+                return Err(ParserError::LiteralTooLarge {
+                    line: line_number,
+                    index: index / 10,
+                    final_digit: index % 10,
+                });
+            } else {
+                vars.push(Var::from_dimacs(index as isize));
+            }
+        }
+
+        if !terminated {
+            return Err(invalid());
+        }
+
+        for &var in vars.iter() {
+            if self.quantified.len() <= var.index() {
+                self.quantified.resize(var.index() + 1, false);
+            }
+            if self.quantified[var.index()] {
+                return Err(ParserError::DuplicateQuantifiedVar {
+                    line: line_number,
+                    var: var.to_dimacs(),
+                });
+            }
+            self.quantified[var.index()] = true;
+        }
+
+        match self.prefix.last_mut() {
+            Some(block) if block.quantifier == quantifier => block.vars.extend(vars),
+            _ => self.prefix.push(QuantifierBlock { quantifier, vars }),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::{cnf_formula, vars};
+
+    fn expect_error(input: &[u8], check: impl FnOnce(&ParserError) -> bool) {
+        match QdimacsParser::parse(input) {
+            Ok(parsed) => panic!("expected error but got {:?}", parsed),
+            Err(err) => {
+                if !check(&err) {
+                    panic!("unexpected error {:?}", err)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse_prefix_and_matrix() {
+        let parsed = QdimacsParser::parse(
+            b"c comment\np cnf 4 2\na 1 2 0\ne 3 0\ne 4 0\n1 -3 4 0\n-2 3 0\n" as &[_],
+        )
+        .unwrap();
+
+        assert_eq!(
+            parsed.prefix,
+            vec![
+                QuantifierBlock {
+                    quantifier: Quantifier::Forall,
+                    vars: vars![1, 2].to_vec(),
+                },
+                QuantifierBlock {
+                    quantifier: Quantifier::Exists,
+                    vars: vars![3, 4].to_vec(),
+                },
+            ]
+        );
+
+        assert_eq!(
+            parsed.matrix,
+            cnf_formula![
+                1, -3, 4;
+                -2, 3;
+            ]
+        );
+    }
+
+    #[test]
+    fn chunked_input() {
+        let input = b"p cnf 3 1\ne 1 0\na 2 3 0\n1 2 3 0\n";
+        let mut parser = QdimacsParser::new();
+        for chunk in input.chunks(3) {
+            parser.parse_chunk(chunk).unwrap();
+        }
+        parser.eof().unwrap();
+        parser.check_header().unwrap();
+
+        let parsed = parser.into_formula();
+        assert_eq!(parsed.prefix.len(), 2);
+        assert_eq!(parsed.matrix.len(), 1);
+    }
+
+    #[test]
+    fn invalid_prefix() {
+        expect_error(b"p cnf 2 1\ne 1 -2 0\n1 2 0\n", |err| match err {
+            ParserError::InvalidQuantifierBlock { line: 2, .. } => true,
+            _ => false,
+        });
+        expect_error(b"p cnf 2 1\ne 1 2\n1 2 0\n", |err| match err {
+            ParserError::InvalidQuantifierBlock { line: 2, .. } => true,
+            _ => false,
+        });
+        expect_error(b"p cnf 2 1\ne 1 0\na 2 1 0\n1 2 0\n", |err| match err {
+            ParserError::DuplicateQuantifiedVar { line: 3, var: 1 } => true,
+            _ => false,
+        });
+        expect_error(b"p cnf 2 1\ne 1 0\n1 2 0\na 2 0\n", |err| match err {
+            ParserError::QuantifierAfterClause { line: 4 } => true,
+            _ => false,
+        });
+        expect_error(b"p cnf 2 1\ne 1 3 0\n1 2 0\n", |err| match err {
+            ParserError::VarCount { .. } => true,
+            _ => false,
+        });
+    }
+}
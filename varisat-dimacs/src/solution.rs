@@ -0,0 +1,194 @@
+//! Writer and parser for solutions in the format of the SAT competition.
+use std::io;
+
+use varisat_formula::Lit;
+
+use crate::icnf::parse_lit;
+use crate::ParserError;
+
+/// Maximal width of a `v` line written by [`write_solution`], not counting the line break.
+const MAX_VALUE_LINE_WIDTH: usize = 78;
+
+/// Result of solving a formula.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Solution {
+    /// The formula is satisfied by the given model.
+    Satisfiable(Vec<Lit>),
+    /// The formula has no model.
+    Unsatisfiable,
+    /// The formula wasn't solved.
+    Unknown,
+}
+
+/// Write a solution in the output format of the SAT competition.
+///
+/// This writes an `s SATISFIABLE`, `s UNSATISFIABLE` or `s UNKNOWN` status line. For satisfiable
+/// solutions the model follows on `v` lines, which are wrapped to at most 80 bytes and terminated
+/// by a `0`.
+pub fn write_solution(target: &mut impl io::Write, solution: &Solution) -> io::Result<()> {
+    let model = match solution {
+        Solution::Satisfiable(model) => model,
+        Solution::Unsatisfiable => return writeln!(target, "s UNSATISFIABLE"),
+        Solution::Unknown => return writeln!(target, "s UNKNOWN"),
+    };
+
+    writeln!(target, "s SATISFIABLE")?;
+
+    let mut buffer = vec![];
+    let mut line_width = 0;
+
+    let values = model.iter().map(|&lit| lit.to_dimacs()).chain(Some(0));
+
+    for value in values {
+        buffer.clear();
+        itoa::write(&mut buffer, value)?;
+
+        if line_width > 0 && line_width + 1 + buffer.len() > MAX_VALUE_LINE_WIDTH {
+            target.write_all(b"\n")?;
+            line_width = 0;
+        }
+
+        if line_width == 0 {
+            target.write_all(b"v")?;
+            line_width = 1;
+        }
+
+        target.write_all(b" ")?;
+        target.write_all(&buffer)?;
+        line_width += 1 + buffer.len();
+    }
+
+    target.write_all(b"\n")
+}
+
+/// Parser for solutions in the output format of the SAT competition.
+///
+/// The input has to contain exactly one status line. A satisfiable solution contains the model
+/// as DIMACS literals on `v` lines following the status line. Comment lines are skipped. The
+/// terminating `0` of the model may be omitted.
+pub struct SolutionParser;
+
+impl SolutionParser {
+    /// Parse the given input.
+    pub fn parse(input: impl io::Read) -> Result<Solution, ParserError> {
+        use io::BufRead;
+
+        let buffer = io::BufReader::new(input);
+
+        let mut solution = None;
+        let mut terminated = false;
+
+        for (line_index, line) in buffer.split(b'\n').enumerate() {
+            let line = line?;
+            let line_number = line_index + 1;
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim();
+
+            match line.as_bytes().first() {
+                None | Some(b'c') => (),
+                Some(b's') => {
+                    if solution.is_some() {
+                        return Err(ParserError::DuplicateStatus { line: line_number });
+                    }
+                    solution = Some(match line[1..].trim_start() {
+                        "SATISFIABLE" => Solution::Satisfiable(vec![]),
+                        "UNSATISFIABLE" => Solution::Unsatisfiable,
+                        "UNKNOWN" => Solution::Unknown,
+                        status => {
+                            return Err(ParserError::InvalidStatus {
+                                line: line_number,
+                                status: status.to_owned(),
+                            })
+                        }
+                    });
+                }
+                Some(b'v') => {
+                    let model = match solution {
+                        Some(Solution::Satisfiable(ref mut model)) => model,
+                        _ => return Err(ParserError::UnexpectedValues { line: line_number }),
+                    };
+                    for token in line[1..].split_whitespace() {
+                        if terminated {
+                            return Err(ParserError::UnexpectedToken {
+                                line: line_number,
+                                token: token.to_owned(),
+                            });
+                        }
+                        match parse_lit(line_number, token)? {
+                            Some(lit) => model.push(lit),
+                            None => terminated = true,
+                        }
+                    }
+                }
+                Some(&byte) => {
+                    return Err(ParserError::UnexpectedInput {
+                        line: line_number,
+                        unexpected: byte as char,
+                    })
+                }
+            }
+        }
+
+        solution.ok_or(ParserError::MissingStatus)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::lits;
+
+    fn write_to_string(solution: &Solution) -> String {
+        let mut output = vec![];
+        write_solution(&mut output, solution).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let model: Vec<Lit> = (1..=100)
+            .map(|index| Lit::from_dimacs(if index % 3 == 0 { -index } else { index }))
+            .collect();
+
+        for solution in vec![
+            Solution::Satisfiable(model),
+            Solution::Satisfiable(vec![]),
+            Solution::Unsatisfiable,
+            Solution::Unknown,
+        ] {
+            let output = write_to_string(&solution);
+            assert!(output.lines().all(|line| line.len() <= 80));
+            assert_eq!(SolutionParser::parse(output.as_bytes()).unwrap(), solution);
+        }
+
+        assert_eq!(
+            write_to_string(&Solution::Satisfiable(lits![1, -2].to_vec())),
+            "s SATISFIABLE\nv 1 -2 0\n"
+        );
+    }
+
+    #[test]
+    fn parse_solutions() {
+        assert_eq!(
+            SolutionParser::parse(&b"c comment\ns SATISFIABLE\nv 1 -2\nc between\nv 3\n"[..])
+                .unwrap(),
+            Solution::Satisfiable(lits![1, -2, 3].to_vec())
+        );
+
+        for &input in [
+            &b"v 1 0\ns SATISFIABLE\n"[..],
+            b"s UNSATISFIABLE\nv 1 0\n",
+            b"s SATISFIABLE\ns SATISFIABLE\n",
+            b"s SATISFIED\n",
+            b"s SATISFIABLE\nv 1 0 2\n",
+            b"s SATISFIABLE\nv 1 x 0\n",
+            b"1 2 0\n",
+            b"c no status\n",
+        ]
+        .iter()
+        {
+            assert!(SolutionParser::parse(input).is_err());
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! WBO parser for weighted Boolean optimization problems.
+use std::io;
+
+use varisat_formula::{PbProblem, WboProblem};
+
+use crate::opb::{parse_coefficient, parse_header, Statement};
+use crate::{decompress, ParserError};
+
+/// Parser for WBO files as used by the pseudo-Boolean competitions.
+///
+/// The syntax of constraints is the same as for [`OpbParser`](crate::OpbParser). A soft
+/// constraint is prefixed with its cost in brackets, e.g. `[3] +1 x1 +1 x2 >= 1 ;`. The first
+/// statement is a `soft: <top> ;` line, where the top cost may be omitted. Objectives are not
+/// allowed. The `* #variable= n #constraint= m` comment line, counting hard and soft constraints,
+/// is used as header when present.
+///
+/// The resulting [`WboProblem`] can be converted into a pseudo-Boolean optimization problem
+/// using [`WboProblem::to_pb_problem`].
+pub struct WboParser;
+
+impl WboParser {
+    /// Parse the given input and check the header if present.
+    ///
+    /// Compressed input is detected and decompressed, see [`decompress`].
+    pub fn parse(input: impl io::Read) -> Result<WboProblem, ParserError> {
+        use io::BufRead;
+
+        let buffer = io::BufReader::new(decompress(input)?);
+
+        let mut problem = WboProblem::default();
+        // Constraints are parsed into this problem and moved into the WBO problem one by one
+        let mut parsed = PbProblem::default();
+        let mut header = None;
+        let mut statement = Statement::default();
+        // Cost of the soft constraint that is currently parsed
+        let mut cost = None;
+        let mut in_soft_line = false;
+        let mut seen_soft_line = false;
+
+        for (line_index, line) in buffer.split(b'\n').enumerate() {
+            let line = line?;
+            let line_number = line_index + 1;
+            let line = String::from_utf8_lossy(&line);
+
+            if line.starts_with('*') {
+                if header.is_none() && !seen_soft_line && problem.hard.is_empty() {
+                    header = parse_header(&line);
+                }
+                continue;
+            }
+
+            for token in line.split_whitespace() {
+                // A `;` can be attached to the last token of a statement
+                let (token, terminated) = match token.len() {
+                    1 => (token, token == ";"),
+                    _ if token.ends_with(';') => (&token[..token.len() - 1], true),
+                    _ => (token, false),
+                };
+
+                let unexpected = || ParserError::UnexpectedToken {
+                    line: line_number,
+                    token: token.to_owned(),
+                };
+
+                if token == ";" {
+                    // Only terminates the statement
+                } else if in_soft_line {
+                    if problem.top.is_some() {
+                        return Err(unexpected());
+                    }
+                    problem.top =
+                        Some(parse_coefficient(line_number, token)?.ok_or_else(unexpected)?);
+                } else if !statement.started && cost.is_none() && token == "soft:" {
+                    let first = problem.hard.is_empty() && problem.soft.is_empty();
+                    if seen_soft_line || !first {
+                        return Err(unexpected());
+                    }
+                    in_soft_line = true;
+                    seen_soft_line = true;
+                } else if !statement.started && cost.is_none() && token.starts_with('[') {
+                    cost = Some(parse_cost(line_number, token)?);
+                } else {
+                    statement.token(line_number, token)?;
+                }
+
+                if terminated {
+                    if in_soft_line {
+                        in_soft_line = false;
+                        continue;
+                    }
+
+                    let start_line = statement.line;
+                    statement.finish(line_number, &mut parsed)?;
+
+                    if parsed.objective.is_some() {
+                        return Err(ParserError::UnexpectedObjective { line: start_line });
+                    }
+
+                    match (parsed.constraints.pop(), cost.take()) {
+                        (Some(constraint), None) => problem.hard.push(constraint),
+                        (Some(constraint), Some(cost)) => problem.soft.push((cost, constraint)),
+                        (None, Some(_)) => {
+                            return Err(ParserError::IncompleteConstraint { line: line_number })
+                        }
+                        (None, None) => (),
+                    }
+                }
+            }
+        }
+
+        if statement.started || cost.is_some() || in_soft_line {
+            return Err(ParserError::UnterminatedConstraint {
+                line: statement.line,
+            });
+        }
+
+        problem.var_count = parsed.var_count;
+
+        if let Some(header) = header {
+            if problem.var_count > header.var_count {
+                return Err(ParserError::VarCount {
+                    var_count: problem.var_count,
+                    header_var_count: header.var_count,
+                });
+            }
+            problem.var_count = header.var_count;
+
+            let constraint_count = problem.hard.len() + problem.soft.len();
+            if constraint_count != header.constraint_count {
+                return Err(ParserError::ConstraintCount {
+                    constraint_count,
+                    header_constraint_count: header.constraint_count,
+                });
+            }
+        }
+
+        Ok(problem)
+    }
+}
+
+/// Parse a positive cost in brackets.
+fn parse_cost(line: usize, token: &str) -> Result<i64, ParserError> {
+    if token.len() > 2 && token.ends_with(']') {
+        if let Some(cost) = parse_coefficient(line, &token[1..token.len() - 1])? {
+            if cost > 0 {
+                return Ok(cost);
+            }
+        }
+    }
+    Err(ParserError::InvalidWeight {
+        line,
+        weight: token.to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::{lit, PbConstraint, PbRelation};
+
+    #[test]
+    fn parse_problem() {
+        let parsed = WboParser::parse(
+            b"* #variable= 4 #constraint= 3 #soft= 2\n\
+              soft: 6 ;\n\
+              [2] +1 x1 +1 x2 >= 1 ;\n\
+              [3] +1 ~x3\n>= 1;\n\
+              -1 x1 -1 x2 >= -1 ;\n" as &[_],
+        )
+        .unwrap();
+
+        assert_eq!(parsed.var_count, 4);
+        assert_eq!(parsed.top, Some(6));
+        assert_eq!(
+            parsed.hard,
+            vec![PbConstraint {
+                terms: vec![(-1, lit!(1)), (-1, lit!(2))],
+                relation: PbRelation::AtLeast,
+                rhs: -1,
+            }]
+        );
+        assert_eq!(
+            parsed.soft,
+            vec![
+                (
+                    2,
+                    PbConstraint {
+                        terms: vec![(1, lit!(1)), (1, lit!(2))],
+                        relation: PbRelation::AtLeast,
+                        rhs: 1,
+                    }
+                ),
+                (
+                    3,
+                    PbConstraint {
+                        terms: vec![(1, lit!(-3))],
+                        relation: PbRelation::AtLeast,
+                        rhs: 1,
+                    }
+                ),
+            ]
+        );
+
+        let parsed = WboParser::parse(b"soft: ;\n[1] +1 x1 >= 1 ;\n" as &[_]).unwrap();
+        assert_eq!(parsed.top, None);
+        assert_eq!(parsed.soft.len(), 1);
+    }
+
+    #[test]
+    fn invalid_input() {
+        for &input in [
+            &b"soft: 1 ;\nsoft: 2 ;\n"[..],
+            b"+1 x1 >= 1 ;\nsoft: 2 ;\n",
+            b"soft: 1 2 ;\n",
+            b"[0] +1 x1 >= 1 ;\n",
+            b"[x] +1 x1 >= 1 ;\n",
+            b"[1] ;\n",
+            b"[1] +1 x1 >= 1\n",
+            b"min: +1 x1 ;\n",
+            b"* #variable= 1 #constraint= 2\n+1 x1 >= 1 ;\n",
+        ]
+        .iter()
+        {
+            assert!(WboParser::parse(input).is_err());
+        }
+    }
+}
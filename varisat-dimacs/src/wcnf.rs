@@ -0,0 +1,528 @@
+//! WCNF parser and writer for weighted MaxSAT instances.
+use std::io;
+use std::mem::replace;
+
+use varisat_formula::{ExtendFormula, Lit, Var, WcnfFormula};
+
+use crate::{decompress, write_dimacs_clauses, ParserError};
+
+/// Variable count, clause count and top weight present in a classic WCNF header.
+#[derive(Copy, Clone, Debug)]
+pub struct WcnfHeader {
+    pub var_count: usize,
+    pub clause_count: usize,
+    /// Clauses with a weight of at least `top` are hard clauses.
+    ///
+    /// `None` when the header doesn't specify a top weight, in which case all weighted clauses
+    /// are soft clauses.
+    pub top: Option<u64>,
+}
+
+/// Whether the clause currently parsed is hard or soft.
+#[derive(Copy, Clone)]
+enum ClauseKind {
+    Hard,
+    Soft(u64),
+}
+
+/// Parser for WCNF files.
+///
+/// Supports the classic format with a `p wcnf` header specifying a top weight that marks hard
+/// clauses, as well as the headerless format used since the 2022 MaxSAT evaluation, where hard
+/// clauses start with `h`. Clauses starting with `h` are also accepted in the classic format.
+///
+/// Like [`DimacsParser`](crate::DimacsParser), this parser can consume the input in chunks.
+#[derive(Default)]
+pub struct WcnfParser {
+    formula: WcnfFormula,
+    header: Option<WcnfHeader>,
+
+    line_number: usize,
+    clause_count: usize,
+
+    token: Vec<u8>,
+    clause_kind: Option<ClauseKind>,
+    clause: Vec<Lit>,
+
+    in_comment_or_header: bool,
+    in_header: bool,
+    start_of_line: bool,
+    error: bool,
+
+    header_line: Vec<u8>,
+}
+
+impl WcnfParser {
+    /// Create a new WCNF parser.
+    pub fn new() -> WcnfParser {
+        WcnfParser {
+            line_number: 1,
+            start_of_line: true,
+            ..WcnfParser::default()
+        }
+    }
+
+    /// Parse the given input and check the header if present.
+    ///
+    /// Compressed input is detected and decompressed, see [`decompress`].
+    pub fn parse(input: impl io::Read) -> Result<WcnfFormula, ParserError> {
+        use io::BufRead;
+
+        let mut buffer = io::BufReader::new(decompress(input)?);
+        let mut parser = Self::new();
+
+        loop {
+            let data = buffer.fill_buf()?;
+            if data.is_empty() {
+                break;
+            }
+            parser.parse_chunk(data)?;
+            let len = data.len();
+            buffer.consume(len);
+        }
+        parser.eof()?;
+        parser.check_header()?;
+
+        Ok(parser.take_formula())
+    }
+
+    /// Parse a chunk of input.
+    ///
+    /// After parsing the last chunk call the [`eof`](WcnfParser::eof) method.
+    ///
+    /// If this method returns an error, the parser is in an invalid state and cannot parse further
+    /// chunks.
+    pub fn parse_chunk(&mut self, chunk: &[u8]) -> Result<(), ParserError> {
+        if self.error {
+            return Err(ParserError::PreviousError);
+        }
+        for &byte in chunk.iter() {
+            match byte {
+                b'\n' | b'\r' if self.in_comment_or_header => {
+                    if self.in_header {
+                        self.in_header = false;
+                        self.parse_header_line()?;
+                    }
+                    self.in_comment_or_header = false;
+                    self.start_of_line = true;
+                }
+                _ if self.in_comment_or_header => {
+                    if self.in_header {
+                        self.header_line.push(byte);
+                    }
+                }
+                b' ' | b'\t' | b'\n' | b'\r' => {
+                    self.finish_token()?;
+                    if byte != b' ' && byte != b'\t' {
+                        self.start_of_line = true;
+                    }
+                }
+                b'c' if self.start_of_line => {
+                    self.in_comment_or_header = true;
+                }
+                b'p' if self.start_of_line && self.header.is_none() => {
+                    self.in_comment_or_header = true;
+                    self.in_header = true;
+                    self.header_line.push(b'p');
+                }
+                _ => {
+                    self.token.push(byte);
+                    self.start_of_line = false;
+                }
+            }
+            if byte == b'\n' {
+                self.line_number += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finish parsing the input.
+    ///
+    /// This does not check whether the header information was correct, call
+    /// [`check_header`](WcnfParser::check_header) for this.
+    pub fn eof(&mut self) -> Result<(), ParserError> {
+        if self.in_header {
+            self.in_header = false;
+            self.parse_header_line()?;
+        }
+
+        self.finish_token()?;
+
+        if self.clause_kind.is_some() {
+            return Err(ParserError::UnterminatedClause {
+                line: self.line_number,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Verifies the header information when present.
+    ///
+    /// Does nothing when the input doesn't contain a header.
+    pub fn check_header(&self) -> Result<(), ParserError> {
+        if let Some(header) = self.header {
+            let var_count = self.formula.var_count();
+            if var_count != header.var_count {
+                return Err(ParserError::VarCount {
+                    var_count,
+                    header_var_count: header.var_count,
+                });
+            }
+
+            if self.clause_count != header.clause_count {
+                return Err(ParserError::ClauseCount {
+                    clause_count: self.clause_count,
+                    header_clause_count: header.clause_count,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the formula parsed so far and resets the parser's formula.
+    pub fn take_formula(&mut self) -> WcnfFormula {
+        replace(&mut self.formula, WcnfFormula::new())
+    }
+
+    /// Return the WCNF header data if present.
+    pub fn header(&self) -> Option<WcnfHeader> {
+        self.header
+    }
+
+    /// Number of hard and soft clauses parsed.
+    pub fn clause_count(&self) -> usize {
+        self.clause_count
+    }
+
+    /// Process a complete whitespace separated token.
+    fn finish_token(&mut self) -> Result<(), ParserError> {
+        if self.token.is_empty() {
+            return Ok(());
+        }
+
+        let result = match self.clause_kind {
+            None => self.parse_weight(),
+            Some(_) => self.parse_lit(),
+        };
+
+        self.token.clear();
+
+        if result.is_err() {
+            self.error = true;
+        }
+
+        result
+    }
+
+    /// Parse the weight or hard clause marker at the start of a clause.
+    fn parse_weight(&mut self) -> Result<(), ParserError> {
+        if self.token == b"h" {
+            self.clause_kind = Some(ClauseKind::Hard);
+            return Ok(());
+        }
+
+        let weight: u64 = match std::str::from_utf8(&self.token)
+            .ok()
+            .filter(|token| token.bytes().all(|byte| byte.is_ascii_digit()))
+            .and_then(|token| token.parse().ok())
+        {
+            Some(weight) => weight,
+            None => {
+                return Err(ParserError::InvalidWeight {
+                    line: self.line_number,
+                    weight: String::from_utf8_lossy(&self.token).into_owned(),
+                })
+            }
+        };
+
+        let is_hard = match self.header {
+            Some(WcnfHeader { top: Some(top), .. }) => weight >= top,
+            _ => false,
+        };
+
+        self.clause_kind = Some(if is_hard {
+            ClauseKind::Hard
+        } else {
+            ClauseKind::Soft(weight)
+        });
+
+        Ok(())
+    }
+
+    /// Parse a literal or the terminating zero of a clause.
+    fn parse_lit(&mut self) -> Result<(), ParserError> {
+        let (negative, digits) = match self.token.split_first() {
+            Some((&b'-', digits)) if !digits.is_empty() => (true, digits),
+            _ => (false, &self.token[..]),
+        };
+
+        let mut index = 0usize;
+
+        for &byte in digits.iter() {
+            if !byte.is_ascii_digit() {
+                return Err(ParserError::UnexpectedInput {
+                    line: self.line_number,
+                    unexpected: byte as char,
+                });
+            }
+            let digit = (byte - b'0') as usize;
+            if index > (Var::max_count() - digit) / 10 {
+                return Err(ParserError::LiteralTooLarge {
+                    line: self.line_number,
+                    index,
+                    final_digit: digit,
+                });
+            }
+            index = index * 10 + digit;
+        }
+
+        if index == 0 {
+            match self.clause_kind.take() {
+                Some(ClauseKind::Hard) => self.formula.add_clause(&self.clause),
+                Some(ClauseKind::Soft(weight)) => {
+                    self.formula.add_soft_clause(weight, &self.clause)
+                }
+                None => unreachable!(),
+            }
+            self.clause.clear();
+            self.clause_count += 1;
+        } else {
+            self.clause
+                .push(Var::from_dimacs(index as isize).lit(!negative));
+        }
+
+        Ok(())
+    }
+
+    fn parse_header_line(&mut self) -> Result<(), ParserError> {
+        let header_line = String::from_utf8_lossy(&self.header_line).into_owned();
+
+        if !header_line.starts_with("p ") {
+            return self.invalid_header(header_line);
+        }
+
+        let mut header_values = header_line[2..].split_whitespace();
+
+        if header_values.next() != Some("wcnf") {
+            return self.invalid_header(header_line);
+        }
+
+        let var_count: usize = match header_values
+            .next()
+            .and_then(|value| str::parse(value).ok())
+        {
+            None => return self.invalid_header(header_line),
+            Some(value) => value,
+        };
+
+        if var_count > Var::max_count() {
+            self.error = true;
+            return Err(ParserError::LiteralTooLarge {
+                line: self.line_number,
+                index: var_count / 10,
+                final_digit: var_count % 10,
+            });
+        }
+
+        let clause_count: usize = match header_values
+            .next()
+            .and_then(|value| str::parse(value).ok())
+        {
+            None => return self.invalid_header(header_line),
+            Some(value) => value,
+        };
+
+        let top: Option<u64> = match header_values.next() {
+            None => None,
+            Some(value) => match str::parse(value) {
+                Ok(value) => Some(value),
+                Err(_) => return self.invalid_header(header_line),
+            },
+        };
+
+        if header_values.next().is_some() {
+            return self.invalid_header(header_line);
+        }
+
+        self.header = Some(WcnfHeader {
+            var_count,
+            clause_count,
+            top,
+        });
+
+        self.formula.set_var_count(var_count);
+
+        Ok(())
+    }
+
+    fn invalid_header(&mut self, header_line: String) -> Result<(), ParserError> {
+        self.error = true;
+        Err(ParserError::InvalidHeader {
+            line: self.line_number,
+            header: header_line,
+        })
+    }
+}
+
+/// Write a weighted CNF formula in the headerless format used since the 2022 MaxSAT evaluation.
+///
+/// Hard clauses start with `h`, soft clauses with their weight. The format has no header, so a
+/// variable count exceeding the largest variable of the clauses is not preserved.
+pub fn write_wcnf(target: &mut impl io::Write, formula: &WcnfFormula) -> io::Result<()> {
+    for clause in formula.hard().iter() {
+        target.write_all(b"h ")?;
+        write_dimacs_clauses(&mut *target, std::iter::once(clause))?;
+    }
+    write_soft_clauses(target, formula)
+}
+
+/// Write a weighted CNF formula in the classic format with a `p wcnf` header.
+///
+/// The top weight is one more than the total weight of all soft clauses, and hard clauses are
+/// written with the top weight. Fails if the top weight doesn't fit into an `u64`.
+pub fn write_wcnf_classic(target: &mut impl io::Write, formula: &WcnfFormula) -> io::Result<()> {
+    let top = formula
+        .total_soft_weight()
+        .and_then(|total| total.checked_add(1))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "total weight of the soft clauses is too large for a top weight",
+            )
+        })?;
+
+    writeln!(
+        target,
+        "p wcnf {} {} {}",
+        formula.var_count(),
+        formula.hard().len() + formula.soft().len(),
+        top
+    )?;
+
+    for clause in formula.hard().iter() {
+        itoa::write(&mut *target, top)?;
+        target.write_all(b" ")?;
+        write_dimacs_clauses(&mut *target, std::iter::once(clause))?;
+    }
+    write_soft_clauses(target, formula)
+}
+
+/// Write the soft clauses, each prefixed with its weight.
+fn write_soft_clauses(target: &mut impl io::Write, formula: &WcnfFormula) -> io::Result<()> {
+    for (weight, clause) in formula.soft_iter() {
+        itoa::write(&mut *target, weight)?;
+        target.write_all(b" ")?;
+        write_dimacs_clauses(&mut *target, std::iter::once(clause))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::lits;
+
+    #[test]
+    fn classic_format() {
+        let parsed = WcnfParser::parse(
+            b"c comment\np wcnf 3 4 10\n10 1 -2 0\n3 -1 0\n5 2\n 3 0\n10 -3 0\n" as &[_],
+        )
+        .unwrap();
+
+        let mut expected = WcnfFormula::new();
+        expected.add_clause(&lits![1, -2]);
+        expected.add_soft_clause(3, &lits![-1]);
+        expected.add_soft_clause(5, &lits![2, 3]);
+        expected.add_clause(&lits![-3]);
+
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn classic_format_without_top() {
+        let parsed = WcnfParser::parse(b"p wcnf 2 2\n1 1 0\n7 -2 0\n" as &[_]).unwrap();
+
+        assert_eq!(parsed.hard().len(), 0);
+        assert_eq!(parsed.weights(), &[1, 7]);
+    }
+
+    #[test]
+    fn headerless_format() {
+        let parsed = WcnfParser::parse(b"c comment\nh 1 2 0\n4 -1 0\nh -2 3 0\n" as &[_]).unwrap();
+
+        let mut expected = WcnfFormula::new();
+        expected.add_clause(&lits![1, 2]);
+        expected.add_soft_clause(4, &lits![-1]);
+        expected.add_clause(&lits![-2, 3]);
+
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn write_and_parse() {
+        let mut formula = WcnfFormula::new();
+        formula.add_clause(&lits![1, -2]);
+        formula.add_soft_clause(3, &lits![-1]);
+        formula.add_soft_clause(5, &lits![2, 4]);
+        formula.add_clause(&lits![-3]);
+        formula.add_clause(&[]);
+
+        let mut headerless = vec![];
+        write_wcnf(&mut headerless, &formula).unwrap();
+        assert_eq!(
+            &headerless[..],
+            &b"h 1 -2 0\nh -3 0\nh 0\n3 -1 0\n5 2 4 0\n"[..]
+        );
+
+        let mut classic = vec![];
+        write_wcnf_classic(&mut classic, &formula).unwrap();
+        assert_eq!(
+            &classic[..],
+            &b"p wcnf 4 5 9\n9 1 -2 0\n9 -3 0\n9 0\n3 -1 0\n5 2 4 0\n"[..]
+        );
+
+        // Parsing preserves the clauses but not their interleaving of hard and soft clauses
+        for output in [headerless, classic].iter() {
+            let parsed = WcnfParser::parse(&output[..]).unwrap();
+            assert!(parsed.hard().iter().eq(formula.hard().iter()));
+            assert!(parsed.soft().iter().eq(formula.soft().iter()));
+            assert_eq!(parsed.weights(), formula.weights());
+        }
+
+        let mut too_heavy = WcnfFormula::new();
+        too_heavy.add_soft_clause(u64::max_value(), &lits![1]);
+        assert!(write_wcnf_classic(&mut vec![], &too_heavy).is_err());
+    }
+
+    #[test]
+    fn invalid_input() {
+        match WcnfParser::parse(b"h 1 0\nx 2 0\n" as &[_]) {
+            Err(err) => match err {
+                ParserError::InvalidWeight { line: 2, .. } => (),
+                _ => panic!("unexpected error {:?}", err),
+            },
+            Ok(parsed) => panic!("expected error but got {:?}", parsed),
+        }
+
+        match WcnfParser::parse(b"h 1 2\n" as &[_]) {
+            Err(err) => match err {
+                ParserError::UnterminatedClause { .. } => (),
+                _ => panic!("unexpected error {:?}", err),
+            },
+            Ok(parsed) => panic!("expected error but got {:?}", parsed),
+        }
+
+        match WcnfParser::parse(b"p wcnf 2 3 10\n10 1 0\n1 2 0\n" as &[_]) {
+            Err(err) => match err {
+                ParserError::ClauseCount { .. } => (),
+                _ => panic!("unexpected error {:?}", err),
+            },
+            Ok(parsed) => panic!("expected error but got {:?}", parsed),
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! Configurable DIMACS CNF writer.
+use std::io;
+
+use varisat_formula::{CnfFormula, Lit};
+
+use crate::{write_dimacs_header, DimacsHeader};
+
+/// Header written by [`write_dimacs_with_config`].
+#[derive(Copy, Clone, Debug)]
+pub enum DimacsHeaderMode {
+    /// Use the variable count and length of the formula.
+    Formula,
+    /// Use the largest variable occurring in a clause as variable count.
+    ///
+    /// This ignores unused variables at the end of the formula's variable range.
+    Computed,
+    /// Write the given header.
+    Custom(DimacsHeader),
+    /// Don't write a header.
+    Omit,
+}
+
+impl Default for DimacsHeaderMode {
+    fn default() -> DimacsHeaderMode {
+        DimacsHeaderMode::Formula
+    }
+}
+
+/// Configurable parameters used when writing DIMACS CNF.
+#[derive(Clone, Default, Debug)]
+pub struct DimacsWriterConfig {
+    /// Comments written before the header, one `c` line per line of each comment.
+    pub comments: Vec<String>,
+    /// The header to write.
+    ///
+    /// The [`DimacsWriter`] only supports [`Custom`](DimacsHeaderMode::Custom) and
+    /// [`Omit`](DimacsHeaderMode::Omit), as it doesn't know the formula in advance.
+    pub header: DimacsHeaderMode,
+    /// Maximal number of bytes per line, not counting the line break.
+    ///
+    /// Long clauses are continued on the next line. A single literal longer than the limit is
+    /// still written on its own line. When `None` each clause is written on a single line.
+    pub max_line_width: Option<usize>,
+}
+
+/// Write a formula as DIMACS CNF using the given configuration.
+pub fn write_dimacs_with_config(
+    target: &mut impl io::Write,
+    formula: &CnfFormula,
+    config: &DimacsWriterConfig,
+) -> io::Result<()> {
+    let header = match config.header {
+        DimacsHeaderMode::Formula => DimacsHeaderMode::Custom(DimacsHeader {
+            var_count: formula.var_count(),
+            clause_count: formula.len(),
+        }),
+        DimacsHeaderMode::Computed => DimacsHeaderMode::Custom(DimacsHeader {
+            var_count: formula
+                .iter()
+                .flat_map(|clause| clause.iter())
+                .map(|lit| lit.index() + 1)
+                .max()
+                .unwrap_or(0),
+            clause_count: formula.len(),
+        }),
+        header => header,
+    };
+
+    let mut writer = DimacsWriter::new(
+        &mut *target,
+        &DimacsWriterConfig {
+            header,
+            ..config.clone()
+        },
+    )?;
+
+    for clause in formula.iter() {
+        writer.write_clause(clause)?;
+    }
+
+    Ok(())
+}
+
+/// Writes clauses as DIMACS CNF without building a [`CnfFormula`].
+///
+/// Comments and header are written on construction.
+pub struct DimacsWriter<W: io::Write> {
+    target: W,
+    max_line_width: Option<usize>,
+    var_count: usize,
+    clause_count: usize,
+    buffer: Vec<u8>,
+}
+
+impl<W: io::Write> DimacsWriter<W> {
+    /// Create a writer and write the comments and the header of the configuration.
+    ///
+    /// Panics if the configuration asks for a header computed from a formula.
+    pub fn new(mut target: W, config: &DimacsWriterConfig) -> io::Result<DimacsWriter<W>> {
+        for comment in config.comments.iter() {
+            write_comment_lines(&mut target, comment)?;
+        }
+
+        match config.header {
+            DimacsHeaderMode::Custom(header) => write_dimacs_header(&mut target, header)?,
+            DimacsHeaderMode::Omit => (),
+            DimacsHeaderMode::Formula | DimacsHeaderMode::Computed => {
+                panic!("DimacsWriter requires a custom header or no header")
+            }
+        }
+
+        Ok(DimacsWriter {
+            target,
+            max_line_width: config.max_line_width,
+            var_count: 0,
+            clause_count: 0,
+            buffer: vec![],
+        })
+    }
+
+    /// Write a single clause.
+    pub fn write_clause(&mut self, clause: &[Lit]) -> io::Result<()> {
+        let mut line_width = 0;
+
+        let tokens = clause.iter().map(|&lit| lit.to_dimacs()).chain(Some(0));
+
+        for token in tokens {
+            self.buffer.clear();
+            itoa::write(&mut self.buffer, token)?;
+
+            if line_width > 0 {
+                let fits = match self.max_line_width {
+                    Some(max_line_width) => line_width + 1 + self.buffer.len() <= max_line_width,
+                    None => true,
+                };
+                if fits {
+                    self.target.write_all(b" ")?;
+                    line_width += 1;
+                } else {
+                    self.target.write_all(b"\n")?;
+                    line_width = 0;
+                }
+            }
+
+            self.target.write_all(&self.buffer)?;
+            line_width += self.buffer.len();
+        }
+
+        self.target.write_all(b"\n")?;
+
+        for &lit in clause.iter() {
+            self.var_count = self.var_count.max(lit.index() + 1);
+        }
+        self.clause_count += 1;
+
+        Ok(())
+    }
+
+    /// Write a comment, using one `c` line per line of the comment.
+    pub fn write_comment(&mut self, comment: &str) -> io::Result<()> {
+        write_comment_lines(&mut self.target, comment)
+    }
+
+    /// Number of clauses written.
+    pub fn clause_count(&self) -> usize {
+        self.clause_count
+    }
+
+    /// Largest variable count of the written clauses.
+    pub fn var_count(&self) -> usize {
+        self.var_count
+    }
+
+    /// Flush and return the underlying writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.target.flush()?;
+        Ok(self.target)
+    }
+}
+
+/// Write a possibly multi-line comment.
+fn write_comment_lines(target: &mut impl io::Write, comment: &str) -> io::Result<()> {
+    if comment.is_empty() {
+        return target.write_all(b"c\n");
+    }
+    for line in comment.lines() {
+        if line.is_empty() {
+            target.write_all(b"c\n")?;
+        } else {
+            writeln!(target, "c {}", line)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::{cnf_formula, lits};
+
+    use crate::DimacsParser;
+
+    fn write_to_string(formula: &CnfFormula, config: &DimacsWriterConfig) -> String {
+        let mut output = vec![];
+        write_dimacs_with_config(&mut output, formula, config).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn header_modes_and_comments() {
+        let mut formula = cnf_formula![
+            1, -2;
+            2, 3;
+        ];
+        formula.set_var_count(5);
+
+        let mut config = DimacsWriterConfig {
+            comments: vec!["first\nsecond".to_owned()],
+            ..DimacsWriterConfig::default()
+        };
+
+        assert_eq!(
+            write_to_string(&formula, &config),
+            "c first\nc second\np cnf 5 2\n1 -2 0\n2 3 0\n"
+        );
+
+        config.comments.clear();
+        config.header = DimacsHeaderMode::Computed;
+        assert_eq!(
+            write_to_string(&formula, &config),
+            "p cnf 3 2\n1 -2 0\n2 3 0\n"
+        );
+
+        config.header = DimacsHeaderMode::Custom(DimacsHeader {
+            var_count: 7,
+            clause_count: 9,
+        });
+        assert_eq!(
+            write_to_string(&formula, &config),
+            "p cnf 7 9\n1 -2 0\n2 3 0\n"
+        );
+
+        config.header = DimacsHeaderMode::Omit;
+        assert_eq!(write_to_string(&formula, &config), "1 -2 0\n2 3 0\n");
+    }
+
+    #[test]
+    fn line_width() {
+        let formula = cnf_formula![
+            1, -2, 3, -40, 500;
+            1234567;
+        ];
+
+        let config = DimacsWriterConfig {
+            max_line_width: Some(6),
+            ..DimacsWriterConfig::default()
+        };
+
+        let output = write_to_string(&formula, &config);
+
+        assert_eq!(output, "p cnf 1234567 2\n1 -2 3\n-40\n500 0\n1234567\n0\n");
+        assert_eq!(DimacsParser::parse(output.as_bytes()).unwrap(), formula);
+    }
+
+    #[test]
+    fn streaming_writer() {
+        let config = DimacsWriterConfig {
+            header: DimacsHeaderMode::Custom(DimacsHeader {
+                var_count: 3,
+                clause_count: 2,
+            }),
+            ..DimacsWriterConfig::default()
+        };
+
+        let mut writer = DimacsWriter::new(vec![], &config).unwrap();
+        writer.write_clause(&lits![1, -3]).unwrap();
+        writer.write_comment("between").unwrap();
+        writer.write_clause(&lits![2]).unwrap();
+
+        assert_eq!(writer.var_count(), 3);
+        assert_eq!(writer.clause_count(), 2);
+
+        let output = writer.into_inner().unwrap();
+        assert_eq!(&output[..], &b"p cnf 3 2\n1 -3 0\nc between\n2 0\n"[..]);
+    }
+}
@@ -0,0 +1,247 @@
+//! Parser for DIMACS CNF files with XOR constraints.
+use std::io;
+
+use varisat_formula::{encode::xor_constraint, CnfFormula, ExtendFormula, Lit};
+
+use crate::icnf::parse_lit;
+use crate::{decompress, DimacsHeader, ParserError};
+
+/// A CNF formula with additional XOR constraints.
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct XorCnfFormula {
+    /// The clauses of the formula.
+    ///
+    /// The variable count includes the variables of the XOR constraints.
+    pub clauses: CnfFormula,
+    /// XOR constraints, each requiring an odd number of its literals to be true.
+    pub xors: Vec<Vec<Lit>>,
+}
+
+impl XorCnfFormula {
+    /// Number of variables of the clauses and XOR constraints.
+    pub fn var_count(&self) -> usize {
+        self.clauses.var_count()
+    }
+
+    /// Convert into an equisatisfiable CNF formula.
+    ///
+    /// The XOR constraints are encoded using
+    /// [`xor_constraint`](varisat_formula::encode::xor_constraint). Auxiliary variables are
+    /// allocated after all variables of the input, so the first [`var_count`] variables of a
+    /// model of the result form a model of the input.
+    ///
+    /// [`var_count`]: XorCnfFormula::var_count
+    pub fn to_cnf(&self) -> CnfFormula {
+        let mut formula = CnfFormula::new();
+        formula.set_var_count(self.var_count());
+        for clause in self.clauses.iter() {
+            formula.add_clause(clause);
+        }
+        for xor in self.xors.iter() {
+            xor_constraint(&mut formula, xor);
+        }
+        formula
+    }
+}
+
+/// Parser for DIMACS CNF files with XOR constraints.
+///
+/// This accepts the dialect used by CryptoMiniSat, where a line starting with `x` contains an
+/// XOR constraint over the following literals, terminated by a zero on the same line. The `x` may
+/// be directly followed by the first literal. The constraint requires an odd number of its
+/// literals to be true, so negating a literal requires an even number. XOR constraints are
+/// counted as clauses in the header.
+pub struct XorCnfParser;
+
+impl XorCnfParser {
+    /// Parse the given input and check the header if present.
+    ///
+    /// Compressed input is detected and decompressed, see [`decompress`].
+    pub fn parse(input: impl io::Read) -> Result<XorCnfFormula, ParserError> {
+        use io::BufRead;
+
+        let buffer = io::BufReader::new(decompress(input)?);
+
+        let mut parsed = XorCnfFormula::default();
+        let mut header: Option<DimacsHeader> = None;
+        let mut clause = vec![];
+        let mut clause_line = 0;
+
+        for (line_index, line) in buffer.split(b'\n').enumerate() {
+            let line = line?;
+            let line_number = line_index + 1;
+            let line = String::from_utf8_lossy(&line);
+            let trimmed = line.trim_start();
+
+            match trimmed.as_bytes().first() {
+                Some(b'c') => continue,
+                Some(b'p') => {
+                    let invalid = || ParserError::InvalidHeader {
+                        line: line_number,
+                        header: line.trim_end().to_owned(),
+                    };
+                    if header.is_some() || !clause.is_empty() || parsed.var_count() > 0 {
+                        return Err(invalid());
+                    }
+                    header = Some(parse_header(trimmed).ok_or_else(invalid)?);
+                }
+                Some(b'x') => {
+                    if !clause.is_empty() {
+                        return Err(ParserError::UnterminatedClause { line: clause_line });
+                    }
+
+                    let mut xor = vec![];
+                    let mut terminated = false;
+                    for token in trimmed[1..].split_whitespace() {
+                        if terminated {
+                            return Err(ParserError::UnexpectedToken {
+                                line: line_number,
+                                token: token.to_owned(),
+                            });
+                        }
+                        match parse_lit(line_number, token)? {
+                            Some(lit) => xor.push(lit),
+                            None => terminated = true,
+                        }
+                    }
+                    if !terminated {
+                        return Err(ParserError::UnterminatedClause { line: line_number });
+                    }
+
+                    for &lit in xor.iter() {
+                        parsed.clauses.set_var_count(lit.index() + 1);
+                    }
+                    parsed.xors.push(xor);
+                }
+                _ => {
+                    for token in trimmed.split_whitespace() {
+                        if clause.is_empty() {
+                            clause_line = line_number;
+                        }
+                        match parse_lit(line_number, token)? {
+                            Some(lit) => clause.push(lit),
+                            None => {
+                                parsed.clauses.add_clause(&clause);
+                                clause.clear();
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if !clause.is_empty() {
+            return Err(ParserError::UnterminatedClause { line: clause_line });
+        }
+
+        if let Some(header) = header {
+            if parsed.var_count() > header.var_count {
+                return Err(ParserError::VarCount {
+                    var_count: parsed.var_count(),
+                    header_var_count: header.var_count,
+                });
+            }
+            let clause_count = parsed.clauses.len() + parsed.xors.len();
+            if clause_count != header.clause_count {
+                return Err(ParserError::ClauseCount {
+                    clause_count,
+                    header_clause_count: header.clause_count,
+                });
+            }
+            parsed.clauses.set_var_count(header.var_count);
+        }
+
+        Ok(parsed)
+    }
+}
+
+/// Parse a `p cnf` header line.
+fn parse_header(line: &str) -> Option<DimacsHeader> {
+    let mut tokens = line.split_whitespace();
+    if tokens.next() != Some("p") || tokens.next() != Some("cnf") {
+        return None;
+    }
+    let var_count = tokens.next()?.parse().ok()?;
+    let clause_count = tokens.next()?.parse().ok()?;
+    if tokens.next().is_some() {
+        return None;
+    }
+    Some(DimacsHeader {
+        var_count,
+        clause_count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::{cnf_formula, lits, Var};
+
+    #[test]
+    fn parse_xors() {
+        let parsed = XorCnfParser::parse(
+            b"c comment\np cnf 5 4\n1 2 0\nx1 -2 3 0\n-1\n 4 0\n x 2 5 0\n" as &[_],
+        )
+        .unwrap();
+
+        let mut expected = cnf_formula![
+            1, 2;
+            -1, 4;
+        ];
+        expected.set_var_count(5);
+        assert_eq!(parsed.clauses, expected);
+        assert_eq!(
+            parsed.xors,
+            vec![lits![1, -2, 3].to_vec(), lits![2, 5].to_vec()]
+        );
+    }
+
+    #[test]
+    fn encoded_parity() {
+        let parsed = XorCnfParser::parse(b"x 1 2 3 4 5 6 0\nx -1 0\n" as &[_]).unwrap();
+        let formula = parsed.to_cnf();
+
+        let extend = |bits: u32| -> Vec<Lit> {
+            // Values of the auxiliary variables are chosen by brute force
+            let aux_count = formula.var_count() - 6;
+            for aux_bits in 0..1u32 << aux_count {
+                let model: Vec<Lit> = (0..formula.var_count())
+                    .map(|index| {
+                        let value = if index < 6 {
+                            bits & (1 << index) != 0
+                        } else {
+                            aux_bits & (1 << (index - 6)) != 0
+                        };
+                        Var::from_index(index).lit(value)
+                    })
+                    .collect();
+                if formula.evaluate(&model).is_true() {
+                    return model;
+                }
+            }
+            vec![]
+        };
+
+        for bits in 0..1u32 << 6 {
+            let satisfied = bits & 1 == 0 && bits.count_ones() % 2 == 1;
+            assert_eq!(!extend(bits).is_empty(), satisfied);
+        }
+    }
+
+    #[test]
+    fn invalid_input() {
+        for &input in [
+            &b"p cnf 3 2\nx 1 2 0\n"[..],
+            b"p cnf 1 1\nx 1 2 0\n",
+            b"1 2\nx 1 0\n",
+            b"x 1 2\n3 0\n",
+            b"x 1 0 2\n",
+            b"x 1 y 0\n",
+        ]
+        .iter()
+        {
+            assert!(XorCnfParser::parse(input).is_err());
+        }
+    }
+}
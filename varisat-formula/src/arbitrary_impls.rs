@@ -0,0 +1,72 @@
+//! Support for generating literals, variables and formulas with the `arbitrary` crate.
+//!
+//! Generated variables are restricted to the first [`ARBITRARY_VAR_COUNT`] variables. This keeps
+//! the memory used by consumers of generated values small and makes it likely that clauses share
+//! variables, which is what makes fuzzing solvers, checkers and parsers effective.
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::cnf::{CnfFormula, ExtendFormula};
+use crate::lit::{Lit, Var};
+
+/// Number of distinct variables produced by the `Arbitrary` implementations.
+pub const ARBITRARY_VAR_COUNT: usize = 256;
+
+impl<'a> Arbitrary<'a> for Var {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Var> {
+        Ok(Var::from_index(
+            u.int_in_range(0..=ARBITRARY_VAR_COUNT - 1)?,
+        ))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        usize::size_hint(depth)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Lit {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Lit> {
+        Ok(Lit::from_code(
+            u.int_in_range(0..=ARBITRARY_VAR_COUNT * 2 - 1)?,
+        ))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        usize::size_hint(depth)
+    }
+}
+
+/// The variable count is at least as large as required by the generated clauses.
+impl<'a> Arbitrary<'a> for CnfFormula {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<CnfFormula> {
+        let mut formula = CnfFormula::new();
+        formula.set_var_count(u.int_in_range(0..=ARBITRARY_VAR_COUNT)?);
+        for clause in u.arbitrary_iter::<Vec<Lit>>()? {
+            formula.add_clause(&clause?);
+        }
+        Ok(formula)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_values_in_range() {
+        let data: Vec<u8> = (0..4096u32).map(|i| (i * 7919 % 251) as u8).collect();
+        let mut u = Unstructured::new(&data);
+
+        let formula = CnfFormula::arbitrary(&mut u).unwrap();
+        assert!(formula.var_count() <= ARBITRARY_VAR_COUNT);
+        assert!(formula.len() > 0);
+        for clause in formula.iter() {
+            for lit in clause {
+                assert!(lit.index() < formula.var_count());
+            }
+        }
+
+        for _ in 0..100 {
+            assert!(Lit::arbitrary(&mut u).unwrap().index() < ARBITRARY_VAR_COUNT);
+        }
+    }
+}
@@ -3,6 +3,7 @@ use std::cmp::max;
 use std::fmt;
 use std::ops::Range;
 
+use crate::lbool::Lbool;
 use crate::lit::{Lit, Var};
 
 /// A formula in conjunctive normal form (CNF).
@@ -48,6 +49,280 @@ impl CnfFormula {
             .iter()
             .map(move |range| &literals[range.clone()])
     }
+
+    /// Evaluate the formula under a possibly partial assignment.
+    ///
+    /// Returns `True` if every clause is satisfied, `False` if a clause is falsified and `Undef`
+    /// otherwise. See [`check_model`](CnfFormula::check_model) for details.
+    pub fn evaluate(&self, assignment: &[Lit]) -> Lbool {
+        let values = self.assignment_values(assignment);
+        let mut value = Lbool::True;
+        for clause in self.iter() {
+            value &= clause_value(&values, clause);
+            if value.is_false() {
+                break;
+            }
+        }
+        value
+    }
+
+    /// Check a possibly partial assignment against the formula.
+    ///
+    /// The assignment is given as a list of true literals. A clause is satisfied when it contains
+    /// a true literal and falsified when all its literals are false. If the assignment contains
+    /// both polarities of a variable, the later literal is used.
+    pub fn check_model(&self, assignment: &[Lit]) -> ModelCheck {
+        let values = self.assignment_values(assignment);
+        let mut result = ModelCheck::default();
+        for (index, clause) in self.iter().enumerate() {
+            match clause_value(&values, clause) {
+                Lbool::True => (),
+                Lbool::False => result.violated.push(index),
+                Lbool::Undef => result.unresolved.push(index),
+            }
+        }
+        result
+    }
+
+    /// The clause with the given index.
+    pub fn clause(&self, index: usize) -> &[Lit] {
+        &self.literals[self.clause_ranges[index].clone()]
+    }
+
+    /// Remove the clause with the given index.
+    ///
+    /// The following clauses move down by one index. The literals of the removed clause stay in
+    /// the formula's storage until [`compact`](CnfFormula::compact) is called.
+    pub fn remove_clause(&mut self, index: usize) {
+        self.clause_ranges.remove(index);
+    }
+
+    /// Keep only the clauses for which the predicate returns true.
+    ///
+    /// This preserves the order of the retained clauses. Like
+    /// [`remove_clause`](CnfFormula::remove_clause) this doesn't free storage.
+    pub fn retain(&mut self, mut predicate: impl FnMut(&[Lit]) -> bool) {
+        let literals = &self.literals;
+        self.clause_ranges
+            .retain(|range| predicate(&literals[range.clone()]));
+    }
+
+    /// Replace the clause with the given index.
+    ///
+    /// The new clause reuses the storage of the old one if it isn't longer.
+    pub fn replace_clause(&mut self, index: usize, clause: &[Lit]) {
+        let range = self.clause_ranges[index].clone();
+        if clause.len() <= range.len() {
+            let end = range.start + clause.len();
+            self.literals[range.start..end].copy_from_slice(clause);
+            self.clause_ranges[index] = range.start..end;
+        } else {
+            let begin = self.literals.len();
+            self.literals.extend_from_slice(clause);
+            self.clause_ranges[index] = begin..self.literals.len();
+        }
+        for &lit in clause.iter() {
+            self.var_count = max(lit.index() + 1, self.var_count);
+        }
+    }
+
+    /// Mutable access to the literals of a clause.
+    ///
+    /// The variable count is not updated, so new literals must be of variables below
+    /// [`var_count`](CnfFormula::var_count). Use [`replace_clause`](CnfFormula::replace_clause)
+    /// to change the length of a clause or to add new variables.
+    pub fn clause_mut(&mut self, index: usize) -> &mut [Lit] {
+        &mut self.literals[self.clause_ranges[index].clone()]
+    }
+
+    /// Apply a function to every literal of the formula.
+    ///
+    /// This can be used to rename variables. The variable count grows as necessary.
+    pub fn map_literals(&mut self, mut f: impl FnMut(Lit) -> Lit) {
+        for range in self.clause_ranges.iter() {
+            for lit in self.literals[range.clone()].iter_mut() {
+                *lit = f(*lit);
+                self.var_count = max(lit.index() + 1, self.var_count);
+            }
+        }
+    }
+
+    /// Sort the literals within each clause.
+    pub fn sort_literals(&mut self) {
+        for range in self.clause_ranges.iter() {
+            self.literals[range.clone()].sort_unstable();
+        }
+    }
+
+    /// Sort the literals within each clause and remove repeated literals.
+    pub fn dedup_literals(&mut self) {
+        for range in self.clause_ranges.iter_mut() {
+            let clause = &mut self.literals[range.clone()];
+            clause.sort_unstable();
+
+            let mut len = 0;
+            for index in 0..clause.len() {
+                if len == 0 || clause[len - 1] != clause[index] {
+                    clause[len] = clause[index];
+                    len += 1;
+                }
+            }
+
+            range.end = range.start + len;
+        }
+    }
+
+    /// Sort the clauses lexicographically.
+    ///
+    /// Combined with [`sort_literals`](CnfFormula::sort_literals) this brings a formula into a
+    /// form where equal clauses are adjacent.
+    pub fn sort_clauses(&mut self) {
+        let literals = &self.literals;
+        self.clause_ranges
+            .sort_by(|a, b| literals[a.clone()].cmp(&literals[b.clone()]));
+    }
+
+    /// Remove clauses that are equal to a preceding clause.
+    ///
+    /// Clauses are compared literal by literal, so clauses that only differ in the order of their
+    /// literals are kept unless [`sort_literals`](CnfFormula::sort_literals) is called first.
+    pub fn dedup_clauses(&mut self) {
+        let literals = &self.literals;
+        let mut seen = std::collections::HashSet::new();
+        self.clause_ranges
+            .retain(|range| seen.insert(&literals[range.clone()]));
+    }
+
+    /// Number of literals in the formula's storage that are not part of any clause.
+    ///
+    /// These are left behind by removing or shrinking clauses and freed by
+    /// [`compact`](CnfFormula::compact).
+    pub fn unused_storage(&self) -> usize {
+        let used: usize = self.clause_ranges.iter().map(|range| range.len()).sum();
+        self.literals.len() - used
+    }
+
+    /// Free the storage of removed clauses and literals.
+    ///
+    /// This also stores the clauses contiguously in their current order and releases excess
+    /// capacity.
+    pub fn compact(&mut self) {
+        let mut literals = Vec::with_capacity(self.literals.len() - self.unused_storage());
+        for range in self.clause_ranges.iter_mut() {
+            let begin = literals.len();
+            literals.extend_from_slice(&self.literals[range.clone()]);
+            *range = begin..literals.len();
+        }
+        self.literals = literals;
+        self.clause_ranges.shrink_to_fit();
+    }
+
+    /// Bring the formula into canonical form.
+    ///
+    /// This sorts the literals of each clause, removes repeated literals, sorts the clauses,
+    /// removes repeated clauses and compacts the storage. Formulas with the same variable count
+    /// and the same set of clauses, up to the order and repetition of literals and clauses, are
+    /// equal after canonicalization.
+    pub fn canonicalize(&mut self) {
+        self.dedup_literals();
+        self.sort_clauses();
+        self.dedup_clauses();
+        self.compact();
+    }
+
+    /// Whether the formula is in the form produced by [`canonicalize`](CnfFormula::canonicalize).
+    ///
+    /// This ignores the storage layout.
+    pub fn is_canonical(&self) -> bool {
+        let clauses_sorted = self
+            .clause_ranges
+            .windows(2)
+            .all(|pair| self.literals[pair[0].clone()] < self.literals[pair[1].clone()]);
+        clauses_sorted
+            && self
+                .iter()
+                .all(|clause| clause.windows(2).all(|pair| pair[0] < pair[1]))
+    }
+
+    /// A stable hash of the formula's canonical form.
+    ///
+    /// Formulas that are equal after [`canonicalize`](CnfFormula::canonicalize) have the same
+    /// hash. The hash only depends on the variable count and the clauses in DIMACS encoding, not
+    /// on the platform, the Rust version or the formula's storage, so it can be persisted, e.g. as
+    /// a cache key. The formula itself is not modified.
+    pub fn canonical_hash(&self) -> u64 {
+        let mut clauses: Vec<Vec<Lit>> = self
+            .iter()
+            .map(|clause| {
+                let mut clause = clause.to_vec();
+                clause.sort_unstable();
+                clause.dedup();
+                clause
+            })
+            .collect();
+        clauses.sort_unstable();
+        clauses.dedup();
+
+        let mut hash = fnv1a(FNV_OFFSET_BASIS, self.var_count as u64);
+        for clause in clauses.iter() {
+            hash = fnv1a(hash, clause.len() as u64);
+            for &lit in clause.iter() {
+                hash = fnv1a(hash, lit.to_dimacs() as i64 as u64);
+            }
+        }
+        hash
+    }
+
+    /// Values of the formula's variables under an assignment.
+    fn assignment_values(&self, assignment: &[Lit]) -> Vec<Lbool> {
+        let mut values = vec![Lbool::Undef; self.var_count];
+        for &lit in assignment {
+            if let Some(value) = values.get_mut(lit.index()) {
+                *value = Lbool::from(lit.is_positive());
+            }
+        }
+        values
+    }
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x100_0000_01b3;
+
+/// Add the little endian bytes of a value to a 64-bit FNV-1a hash.
+fn fnv1a(mut hash: u64, value: u64) -> u64 {
+    for &byte in value.to_le_bytes().iter() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// Value of a clause given the values of its variables.
+fn clause_value(values: &[Lbool], clause: &[Lit]) -> Lbool {
+    let mut value = Lbool::False;
+    for &lit in clause {
+        value |= values[lit.index()] ^ lit.is_negative();
+        if value.is_true() {
+            break;
+        }
+    }
+    value
+}
+
+/// Result of [`CnfFormula::check_model`].
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct ModelCheck {
+    /// Indices of the clauses falsified by the assignment.
+    pub violated: Vec<usize>,
+    /// Indices of the clauses neither satisfied nor falsified by the assignment.
+    pub unresolved: Vec<usize>,
+}
+
+impl ModelCheck {
+    /// Whether the assignment satisfies every clause.
+    pub fn is_model(&self) -> bool {
+        self.violated.is_empty() && self.unresolved.is_empty()
+    }
 }
 
 /// Convert an iterable of [`Lit`] slices into a CnfFormula
@@ -87,6 +362,9 @@ impl PartialEq for CnfFormula {
 }
 
 /// Extend a formula with new variables and clauses.
+///
+/// Besides [`CnfFormula`] this is implemented by Varisat's solver and proof checker, so encodings
+/// can add their clauses to any of them without building an intermediate formula.
 pub trait ExtendFormula: Sized {
     /// Appends a clause to the formula.
     fn add_clause(&mut self, literals: &[Lit]);
@@ -295,6 +573,109 @@ mod tests {
         assert_eq!(formula.var_count(), 3);
     }
 
+    #[test]
+    fn check_models() {
+        let formula = CnfFormula::from(cnf![
+            1, 2;
+            -1, 3;
+            -2, -3;
+        ]);
+
+        assert_eq!(formula.evaluate(&lits![1, -2, 3]), Lbool::True);
+        assert_eq!(formula.evaluate(&lits![1, 2, 3]), Lbool::False);
+        assert_eq!(formula.evaluate(&lits![1, -2]), Lbool::Undef);
+
+        assert!(formula.check_model(&lits![-1, 2, -3]).is_model());
+        assert_eq!(
+            formula.check_model(&lits![-1, -2]),
+            ModelCheck {
+                violated: vec![0],
+                unresolved: vec![],
+            }
+        );
+        assert_eq!(
+            formula.check_model(&lits![1, 2]),
+            ModelCheck {
+                violated: vec![],
+                unresolved: vec![1, 2],
+            }
+        );
+    }
+
+    #[test]
+    fn editing() {
+        let mut formula = CnfFormula::from(cnf![
+            3, 1, 3;
+            -2, 4;
+            1, 3;
+            5;
+            1, 3;
+        ]);
+
+        formula.remove_clause(3);
+        assert_eq!(formula.len(), 4);
+        assert_eq!(formula.unused_storage(), 1);
+
+        formula.dedup_literals();
+        assert_eq!(formula.clause(0), &lits![1, 3]);
+
+        formula.dedup_clauses();
+        assert_eq!(formula.len(), 2);
+
+        formula.replace_clause(1, &lits![-2, 4, 6]);
+        assert_eq!(formula.var_count(), 6);
+        formula.clause_mut(0)[1] = lit!(-3);
+        formula.map_literals(|lit| !lit);
+        formula.retain(|clause| clause.len() > 1);
+
+        formula.sort_clauses();
+        assert_eq!(
+            formula,
+            CnfFormula::from(cnf![
+                -1, 3;
+                2, -4, -6;
+            ])
+        );
+
+        formula.compact();
+        assert_eq!(formula.unused_storage(), 0);
+        assert_eq!(formula.literals.len(), 5);
+        assert_eq!(formula.clause(1), &lits![2, -4, -6]);
+    }
+
+    #[test]
+    fn canonical_form() {
+        let mut formula = cnf_formula![
+            3;
+            -2, 1, -2;
+            3;
+            1, -2;
+        ];
+        let mut reordered = cnf_formula![
+            1, -2, 1;
+            3, 3;
+        ];
+
+        assert!(!formula.is_canonical());
+        assert_eq!(formula.canonical_hash(), reordered.canonical_hash());
+        assert_eq!(formula.canonical_hash(), 0x783c_7873_6bf9_5d5e);
+
+        formula.canonicalize();
+        reordered.canonicalize();
+
+        assert!(formula.is_canonical());
+        assert_eq!(formula, reordered);
+        assert_eq!(formula.unused_storage(), 0);
+        assert_eq!(formula.clause(0), &lits![1, -2]);
+
+        formula.set_var_count(4);
+        assert_ne!(formula.canonical_hash(), reordered.canonical_hash());
+        assert_ne!(
+            cnf_formula![1, 2;].canonical_hash(),
+            cnf_formula![1; 2;].canonical_hash()
+        );
+    }
+
     #[test]
     fn simple_roundtrip() {
         let input = cnf![
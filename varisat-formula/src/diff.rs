@@ -0,0 +1,97 @@
+//! Comparison of formulas.
+use std::collections::HashMap;
+
+use crate::cnf::{CnfFormula, ExtendFormula};
+use crate::lit::Lit;
+
+/// Clauses that differ between two formulas.
+///
+/// See [`diff_formulas`].
+#[derive(Debug, Default)]
+pub struct FormulaDiff {
+    /// Clauses of the new formula not present in the old formula.
+    pub added: CnfFormula,
+    /// Clauses of the old formula not present in the new formula.
+    pub removed: CnfFormula,
+}
+
+impl FormulaDiff {
+    /// Whether both formulas contain the same clauses.
+    pub fn is_empty(&self) -> bool {
+        self.added.len() == 0 && self.removed.len() == 0
+    }
+}
+
+/// Compare the clauses of two formulas, ignoring the order of clauses and of literals.
+///
+/// Formulas are compared as multisets of clauses, so a clause that occurs more often in one of
+/// the formulas is reported as often as it is missing from the other one. The reported clauses
+/// keep their literal order and appear in the order of their formula. Variable counts are not
+/// compared.
+pub fn diff_formulas(old: &CnfFormula, new: &CnfFormula) -> FormulaDiff {
+    let sorted = |clause: &[Lit]| {
+        let mut clause = clause.to_vec();
+        clause.sort_unstable();
+        clause
+    };
+
+    // Indices of the old clauses not yet matched, in reverse order, for each sorted clause
+    let mut unmatched: HashMap<Vec<Lit>, Vec<usize>> = HashMap::new();
+    for (index, clause) in old.iter().enumerate() {
+        unmatched.entry(sorted(clause)).or_default().push(index);
+    }
+    for indices in unmatched.values_mut() {
+        indices.reverse();
+    }
+
+    let mut diff = FormulaDiff::default();
+    let mut matched = vec![false; old.len()];
+
+    for clause in new.iter() {
+        match unmatched
+            .get_mut(&sorted(clause))
+            .and_then(|indices| indices.pop())
+        {
+            Some(index) => matched[index] = true,
+            None => diff.added.add_clause(clause),
+        }
+    }
+
+    for (clause, &matched) in old.iter().zip(matched.iter()) {
+        if !matched {
+            diff.removed.add_clause(clause);
+        }
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn added_and_removed() {
+        let old = cnf_formula![
+            1, 2;
+            -3;
+            2, 1;
+            4, 5, 6;
+        ];
+        let new = cnf_formula![
+            -3;
+            1, 2;
+            6, 4, 5;
+            -3;
+            -1, 7;
+        ];
+
+        let diff = diff_formulas(&old, &new);
+
+        assert!(!diff.is_empty());
+        assert_eq!(diff.added, cnf_formula![-3; -1, 7;]);
+        assert_eq!(diff.removed, cnf_formula![2, 1;]);
+
+        assert!(diff_formulas(&new, &new).is_empty());
+    }
+}
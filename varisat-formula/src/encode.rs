@@ -0,0 +1,59 @@
+//! Encodings of higher level constraints into clauses.
+//!
+//! All encodings add their clauses to an [`ExtendFormula`](crate::ExtendFormula) and allocate
+//! auxiliary variables using [`ExtendFormula::new_var`](crate::ExtendFormula::new_var), so they
+//! can be used with a [`CnfFormula`](crate::CnfFormula) as well as directly with a solver.
+pub mod at_most_one;
+pub mod cardinality;
+pub mod pb;
+pub mod xor;
+
+pub use at_most_one::{at_most_one, at_most_one_with, exactly_one, AtMostOneEncoding};
+pub use cardinality::{at_least, at_most, CardinalityEncoding, UnaryCount};
+pub use pb::{pb_constraint, PbEncoding};
+pub use xor::xor_constraint;
+
+#[cfg(test)]
+pub(crate) mod test {
+    use crate::cnf::CnfFormula;
+    use crate::lit::Lit;
+
+    /// Values of all variables after unit propagation of the given assumptions.
+    ///
+    /// Returns `None` if propagation results in a conflict.
+    pub fn propagate(formula: &CnfFormula, assumptions: &[Lit]) -> Option<Vec<Option<bool>>> {
+        let mut values = vec![None; formula.var_count()];
+        for &lit in assumptions.iter() {
+            values[lit.index()] = Some(lit.is_positive());
+        }
+
+        loop {
+            let mut changed = false;
+            for clause in formula.iter() {
+                let mut unassigned = vec![];
+                let mut satisfied = false;
+                for &lit in clause.iter() {
+                    match values[lit.index()] {
+                        Some(value) if value == lit.is_positive() => satisfied = true,
+                        Some(_) => (),
+                        None => unassigned.push(lit),
+                    }
+                }
+                if satisfied {
+                    continue;
+                }
+                match unassigned[..] {
+                    [] => return None,
+                    [lit] => {
+                        values[lit.index()] = Some(lit.is_positive());
+                        changed = true;
+                    }
+                    _ => (),
+                }
+            }
+            if !changed {
+                return Some(values);
+            }
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Encodings of at-most-one constraints.
+use crate::cnf::ExtendFormula;
+use crate::lit::Lit;
+
+/// Largest number of literals encoded pairwise by [`at_most_one`].
+const PAIRWISE_LIMIT: usize = 6;
+
+/// Number of literals per group of the commander encoding.
+const COMMANDER_GROUP_SIZE: usize = 3;
+
+/// Number of literals per group of the bimander encoding.
+const BIMANDER_GROUP_SIZE: usize = 2;
+
+/// Encodings for requiring at most one of a set of literals to be true.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AtMostOneEncoding {
+    /// A binary clause for every pair of literals.
+    ///
+    /// Uses no auxiliary variables but a quadratic number of clauses.
+    Pairwise,
+    /// Groups of three literals, each represented by a commander variable, with a recursive
+    /// at-most-one constraint over the commanders.
+    ///
+    /// Uses a linear number of clauses and auxiliary variables.
+    Commander,
+    /// Groups of two literals, each identified by a binary number over shared auxiliary
+    /// variables.
+    ///
+    /// Uses a logarithmic number of auxiliary variables and `O(n log n)` clauses.
+    Bimander,
+}
+
+/// Require at most one of the literals to be true.
+///
+/// Small constraints are encoded pairwise, larger ones using the commander encoding.
+pub fn at_most_one(formula: &mut impl ExtendFormula, lits: &[Lit]) {
+    let encoding = if lits.len() <= PAIRWISE_LIMIT {
+        AtMostOneEncoding::Pairwise
+    } else {
+        AtMostOneEncoding::Commander
+    };
+    at_most_one_with(formula, lits, encoding);
+}
+
+/// Require at most one of the literals to be true using the given encoding.
+pub fn at_most_one_with(
+    formula: &mut impl ExtendFormula,
+    lits: &[Lit],
+    encoding: AtMostOneEncoding,
+) {
+    match encoding {
+        AtMostOneEncoding::Pairwise => pairwise(formula, lits),
+        AtMostOneEncoding::Commander => commander(formula, lits),
+        AtMostOneEncoding::Bimander => bimander(formula, lits),
+    }
+}
+
+/// Require exactly one of the literals to be true.
+///
+/// Adds the clause of all literals and an at-most-one constraint using [`at_most_one`].
+pub fn exactly_one(formula: &mut impl ExtendFormula, lits: &[Lit]) {
+    formula.add_clause(lits);
+    at_most_one(formula, lits);
+}
+
+fn pairwise(formula: &mut impl ExtendFormula, lits: &[Lit]) {
+    for (i, &a) in lits.iter().enumerate() {
+        for &b in lits[..i].iter() {
+            formula.add_clause(&[!a, !b]);
+        }
+    }
+}
+
+fn commander(formula: &mut impl ExtendFormula, lits: &[Lit]) {
+    if lits.len() <= COMMANDER_GROUP_SIZE {
+        return pairwise(formula, lits);
+    }
+
+    let mut commanders = vec![];
+
+    for group in lits.chunks(COMMANDER_GROUP_SIZE) {
+        pairwise(formula, group);
+
+        // The commander is true if and only if a literal of the group is true
+        let commander = formula.new_lit();
+        for &lit in group.iter() {
+            formula.add_clause(&[!lit, commander]);
+        }
+        let mut clause = vec![!commander];
+        clause.extend_from_slice(group);
+        formula.add_clause(&clause);
+
+        commanders.push(commander);
+    }
+
+    commander(formula, &commanders);
+}
+
+fn bimander(formula: &mut impl ExtendFormula, lits: &[Lit]) {
+    let group_count = (lits.len() + BIMANDER_GROUP_SIZE - 1) / BIMANDER_GROUP_SIZE;
+    let mut bit_count = 0;
+    while (1 << bit_count) < group_count {
+        bit_count += 1;
+    }
+    let bits: Vec<Lit> = formula.new_lit_iter(bit_count).collect();
+
+    for (group_index, group) in lits.chunks(BIMANDER_GROUP_SIZE).enumerate() {
+        pairwise(formula, group);
+
+        // A true literal sets the bits to the binary representation of its group's index
+        for &lit in group.iter() {
+            for (bit_index, &bit) in bits.iter().enumerate() {
+                formula.add_clause(&[!lit, bit ^ (group_index & (1 << bit_index) == 0)]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cnf::CnfFormula;
+    use crate::encode::test::propagate;
+    use crate::lit::Var;
+
+    #[test]
+    fn encodings_match_constraint() {
+        for &encoding in [
+            AtMostOneEncoding::Pairwise,
+            AtMostOneEncoding::Commander,
+            AtMostOneEncoding::Bimander,
+        ]
+        .iter()
+        {
+            for lit_count in 0..12 {
+                let mut formula = CnfFormula::new();
+                let lits: Vec<Lit> = formula.new_lit_iter(lit_count).collect();
+                at_most_one_with(&mut formula, &lits, encoding);
+
+                for bits in 0..1u32 << lit_count {
+                    let assignment: Vec<Lit> = (0..lit_count)
+                        .map(|index| Var::from_index(index).lit(bits & (1 << index) != 0))
+                        .collect();
+
+                    assert_eq!(
+                        propagate(&formula, &assignment).is_some(),
+                        bits.count_ones() <= 1,
+                        "{:?} {:?}",
+                        encoding,
+                        assignment
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn exactly_one_constraint() {
+        let mut formula = CnfFormula::new();
+        let lits: Vec<Lit> = formula.new_lit_iter(9).collect();
+        exactly_one(&mut formula, &lits);
+
+        let none: Vec<Lit> = lits.iter().map(|&lit| !lit).collect();
+        assert!(propagate(&formula, &none).is_none());
+
+        let mut one = none.clone();
+        one[4] = lits[4];
+        assert!(propagate(&formula, &one).is_some());
+
+        one[7] = lits[7];
+        assert!(propagate(&formula, &one).is_none());
+    }
+}
@@ -0,0 +1,412 @@
+//! Encodings of cardinality constraints.
+use crate::cnf::ExtendFormula;
+use crate::lit::Lit;
+
+/// Encodings for counting the true literals among a set of inputs.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CardinalityEncoding {
+    /// Totalizer encoding, merging the unary counts of a balanced tree of input subsets.
+    ///
+    /// Uses `O(n log n)` variables and `O(n²)` clauses for `n` inputs, but only `O(n k)` clauses
+    /// when limited to `k` outputs.
+    Totalizer,
+    /// Sequential counter, counting the true inputs one input at a time.
+    ///
+    /// Uses `O(n k)` variables and clauses when limited to `k` outputs.
+    SequentialCounter,
+    /// Batcher's odd-even merge sorting network.
+    ///
+    /// Uses `O(n log² n)` variables and clauses independent of the number of outputs.
+    SortingNetwork,
+}
+
+impl Default for CardinalityEncoding {
+    fn default() -> CardinalityEncoding {
+        CardinalityEncoding::Totalizer
+    }
+}
+
+/// Unary representation of the number of true literals among a set of inputs.
+///
+/// The output literal with index `i` is true if and only if at least `i + 1` inputs are true.
+/// Both directions are encoded, so the outputs can be used for at-most and at-least bounds as
+/// well as in further constraints. Tightening a bound incrementally only requires adding a unit
+/// clause, see [`add_at_most`](UnaryCount::add_at_most).
+#[derive(Clone, Debug)]
+pub struct UnaryCount {
+    outputs: Vec<Lit>,
+    input_count: usize,
+}
+
+impl UnaryCount {
+    /// Encode the number of true inputs.
+    pub fn encode(
+        formula: &mut impl ExtendFormula,
+        inputs: &[Lit],
+        encoding: CardinalityEncoding,
+    ) -> UnaryCount {
+        Self::encode_limited(formula, inputs, inputs.len(), encoding)
+    }
+
+    /// Encode the number of true inputs, only representing counts up to `limit`.
+    ///
+    /// This creates at most `limit` outputs, which allows bounds `at_most(k)` for `k < limit`
+    /// and `at_least(k)` for `k <= limit`.
+    pub fn encode_limited(
+        formula: &mut impl ExtendFormula,
+        inputs: &[Lit],
+        limit: usize,
+        encoding: CardinalityEncoding,
+    ) -> UnaryCount {
+        let limit = limit.min(inputs.len());
+
+        let outputs = if limit == 0 {
+            vec![]
+        } else {
+            match encoding {
+                CardinalityEncoding::Totalizer => totalizer(formula, inputs, limit),
+                CardinalityEncoding::SequentialCounter => {
+                    sequential_counter(formula, inputs, limit)
+                }
+                CardinalityEncoding::SortingNetwork => sorting_network(formula, inputs, limit),
+            }
+        };
+
+        UnaryCount {
+            outputs,
+            input_count: inputs.len(),
+        }
+    }
+
+    /// The output literals, true if at least index + 1 inputs are true.
+    pub fn outputs(&self) -> &[Lit] {
+        &self.outputs
+    }
+
+    /// Number of counted inputs.
+    pub fn input_count(&self) -> usize {
+        self.input_count
+    }
+
+    /// Literal that is true if and only if at least `k` inputs are true.
+    ///
+    /// Returns `None` for `k == 0`, which is always true, and for `k` exceeding the number of
+    /// inputs, which is always false.
+    ///
+    /// Panics if `k` exceeds the limit used when encoding.
+    pub fn at_least_lit(&self, k: usize) -> Option<Lit> {
+        if k == 0 || k > self.input_count {
+            return None;
+        }
+        assert!(
+            k <= self.outputs.len(),
+            "count {} exceeds the encoded limit {}",
+            k,
+            self.outputs.len()
+        );
+        Some(self.outputs[k - 1])
+    }
+
+    /// Add a clause requiring at most `k` inputs to be true.
+    ///
+    /// Panics if `k` is not below the limit used when encoding, unless there are at most `k`
+    /// inputs.
+    pub fn add_at_most(&self, formula: &mut impl ExtendFormula, k: usize) {
+        if k < self.input_count {
+            let lit = self.at_least_lit(k + 1).unwrap();
+            formula.add_clause(&[!lit]);
+        }
+    }
+
+    /// Add a clause requiring at least `k` inputs to be true.
+    ///
+    /// Panics if `k` exceeds the limit used when encoding.
+    pub fn add_at_least(&self, formula: &mut impl ExtendFormula, k: usize) {
+        if k > self.input_count {
+            formula.add_clause(&[]);
+        } else if let Some(lit) = self.at_least_lit(k) {
+            formula.add_clause(&[lit]);
+        }
+    }
+}
+
+/// Require at most `k` of the inputs to be true.
+///
+/// The returned count can be used to tighten the bound further.
+pub fn at_most(
+    formula: &mut impl ExtendFormula,
+    inputs: &[Lit],
+    k: usize,
+    encoding: CardinalityEncoding,
+) -> UnaryCount {
+    let count = UnaryCount::encode_limited(formula, inputs, k.saturating_add(1), encoding);
+    count.add_at_most(formula, k);
+    count
+}
+
+/// Require at least `k` of the inputs to be true.
+///
+/// The returned count can be used to tighten the bound further, when it was encoded for more
+/// than `k` outputs.
+pub fn at_least(
+    formula: &mut impl ExtendFormula,
+    inputs: &[Lit],
+    k: usize,
+    encoding: CardinalityEncoding,
+) -> UnaryCount {
+    let count = UnaryCount::encode_limited(formula, inputs, k, encoding);
+    count.add_at_least(formula, k);
+    count
+}
+
+/// Totalizer encoding with at most `limit` outputs.
+fn totalizer(formula: &mut impl ExtendFormula, inputs: &[Lit], limit: usize) -> Vec<Lit> {
+    if inputs.len() <= 1 {
+        return inputs.to_vec();
+    }
+
+    let (left, right) = inputs.split_at(inputs.len() / 2);
+    let left = totalizer(formula, left, limit);
+    let right = totalizer(formula, right, limit);
+
+    let outputs: Vec<Lit> = formula
+        .new_lit_iter((left.len() + right.len()).min(limit))
+        .collect();
+
+    for i in 0..=left.len() {
+        for j in 0..=right.len() {
+            // At least i inputs on the left and j on the right imply at least i + j in total
+            if i + j > 0 && i + j <= outputs.len() {
+                let mut clause = vec![outputs[i + j - 1]];
+                if i > 0 {
+                    clause.push(!left[i - 1]);
+                }
+                if j > 0 {
+                    clause.push(!right[j - 1]);
+                }
+                formula.add_clause(&clause);
+            }
+            // At most i inputs on the left and j on the right imply at most i + j in total
+            if i + j < outputs.len() {
+                let mut clause = vec![!outputs[i + j]];
+                if i < left.len() {
+                    clause.push(left[i]);
+                }
+                if j < right.len() {
+                    clause.push(right[j]);
+                }
+                formula.add_clause(&clause);
+            }
+        }
+    }
+
+    outputs
+}
+
+/// Sequential counter encoding with at most `limit` outputs.
+fn sequential_counter(formula: &mut impl ExtendFormula, inputs: &[Lit], limit: usize) -> Vec<Lit> {
+    // Unary count of the inputs processed so far
+    let mut counts: Vec<Lit> = vec![];
+
+    for &input in inputs.iter() {
+        let next: Vec<Lit> = formula
+            .new_lit_iter((counts.len() + 1).min(limit))
+            .collect();
+
+        for (j, &output) in next.iter().enumerate() {
+            // next[j] <-> counts[j] | (input & counts[j - 1])
+            let same = counts.get(j).cloned();
+            let less = if j > 0 { Some(counts[j - 1]) } else { None };
+
+            if let Some(same) = same {
+                formula.add_clause(&[!same, output]);
+            }
+
+            let mut clause = vec![!input, output];
+            clause.extend(less.map(|less| !less));
+            formula.add_clause(&clause);
+
+            let mut clause = vec![!output, input];
+            clause.extend(same);
+            formula.add_clause(&clause);
+
+            if let Some(less) = less {
+                let mut clause = vec![!output, less];
+                clause.extend(same);
+                formula.add_clause(&clause);
+            }
+        }
+
+        counts = next;
+    }
+
+    counts
+}
+
+/// Sorting network encoding with at most `limit` outputs.
+fn sorting_network(formula: &mut impl ExtendFormula, inputs: &[Lit], limit: usize) -> Vec<Lit> {
+    // `None` represents a constant false value used for padding
+    let mut values: Vec<Option<Lit>> = inputs.iter().cloned().map(Some).collect();
+    values.resize(inputs.len().next_power_of_two(), None);
+
+    // Constants only move to the end, so the first outputs are always literals
+    sort(formula, values)
+        .into_iter()
+        .take(limit)
+        .map(|value| value.unwrap())
+        .collect()
+}
+
+/// Sort values in descending order.
+fn sort(formula: &mut impl ExtendFormula, mut values: Vec<Option<Lit>>) -> Vec<Option<Lit>> {
+    if values.len() <= 1 {
+        return values;
+    }
+    let right = values.split_off(values.len() / 2);
+    let left = sort(formula, values);
+    let right = sort(formula, right);
+    merge(formula, &left, &right)
+}
+
+/// Merge two sorted sequences of the same power of two length.
+fn merge(
+    formula: &mut impl ExtendFormula,
+    left: &[Option<Lit>],
+    right: &[Option<Lit>],
+) -> Vec<Option<Lit>> {
+    if left.len() == 1 {
+        let (max, min) = comparator(formula, left[0], right[0]);
+        return vec![max, min];
+    }
+
+    let every_other =
+        |values: &[Option<Lit>]| values.iter().cloned().step_by(2).collect::<Vec<_>>();
+
+    let evens = merge(formula, &every_other(left), &every_other(right));
+    let odds = merge(formula, &every_other(&left[1..]), &every_other(&right[1..]));
+
+    let mut merged = vec![evens[0]];
+    for i in 0..odds.len() - 1 {
+        let (max, min) = comparator(formula, odds[i], evens[i + 1]);
+        merged.push(max);
+        merged.push(min);
+    }
+    merged.push(odds[odds.len() - 1]);
+    merged
+}
+
+/// Return the maximum and minimum of two values.
+fn comparator(
+    formula: &mut impl ExtendFormula,
+    a: Option<Lit>,
+    b: Option<Lit>,
+) -> (Option<Lit>, Option<Lit>) {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            let (max, min) = formula.new_lits();
+            formula.add_clause(&[!a, max]);
+            formula.add_clause(&[!b, max]);
+            formula.add_clause(&[!max, a, b]);
+            formula.add_clause(&[!a, !b, min]);
+            formula.add_clause(&[!min, a]);
+            formula.add_clause(&[!min, b]);
+            (Some(max), Some(min))
+        }
+        (value, None) | (None, value) => (value, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cnf::CnfFormula;
+    use crate::encode::test::propagate;
+    use crate::lit::Var;
+
+    const ENCODINGS: [CardinalityEncoding; 3] = [
+        CardinalityEncoding::Totalizer,
+        CardinalityEncoding::SequentialCounter,
+        CardinalityEncoding::SortingNetwork,
+    ];
+
+    /// All assignments of the first `var_count` variables.
+    fn assignments(var_count: usize) -> impl Iterator<Item = Vec<Lit>> {
+        (0..1u32 << var_count).map(move |bits| {
+            (0..var_count)
+                .map(|index| Var::from_index(index).lit(bits & (1 << index) != 0))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn outputs_match_count() {
+        for &encoding in ENCODINGS.iter() {
+            for input_count in 0..7 {
+                for &limit in [input_count, 2].iter() {
+                    let mut formula = CnfFormula::new();
+                    let inputs: Vec<Lit> = formula.new_lit_iter(input_count).collect();
+                    let count = UnaryCount::encode_limited(&mut formula, &inputs, limit, encoding);
+
+                    assert_eq!(count.outputs().len(), limit.min(input_count));
+
+                    for assignment in assignments(input_count) {
+                        let true_count = assignment.iter().filter(|lit| lit.is_positive()).count();
+                        let values = propagate(&formula, &assignment).unwrap();
+                        for (index, output) in count.outputs().iter().enumerate() {
+                            assert_eq!(
+                                values[output.index()],
+                                Some(output.is_positive() == (true_count > index)),
+                                "{:?} {:?} output {}",
+                                encoding,
+                                assignment,
+                                index
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bounds() {
+        for &encoding in ENCODINGS.iter() {
+            for k in 0..6 {
+                for &at_most_bound in [false, true].iter() {
+                    let mut formula = CnfFormula::new();
+                    let inputs: Vec<Lit> = formula.new_lit_iter(4).collect();
+                    if at_most_bound {
+                        at_most(&mut formula, &inputs, k, encoding);
+                    } else {
+                        at_least(&mut formula, &inputs, k, encoding);
+                    }
+
+                    for assignment in assignments(4) {
+                        let true_count = assignment.iter().filter(|lit| lit.is_positive()).count();
+                        let satisfied = if at_most_bound {
+                            true_count <= k
+                        } else {
+                            true_count >= k
+                        };
+                        assert_eq!(propagate(&formula, &assignment).is_some(), satisfied);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tighten_bound() {
+        let mut formula = CnfFormula::new();
+        let inputs: Vec<Lit> = formula.new_lit_iter(5).collect();
+        let count = at_most(&mut formula, &inputs, 3, CardinalityEncoding::Totalizer);
+
+        let three = [inputs[0], inputs[1], inputs[2], !inputs[3], !inputs[4]];
+        assert!(propagate(&formula, &three).is_some());
+
+        count.add_at_most(&mut formula, 2);
+        assert!(propagate(&formula, &three).is_none());
+        assert!(propagate(&formula, &three[1..]).is_some());
+    }
+}
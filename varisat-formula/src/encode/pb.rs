@@ -0,0 +1,341 @@
+//! Encodings of pseudo-Boolean constraints.
+use std::collections::HashMap;
+
+use crate::cnf::ExtendFormula;
+use crate::lit::Lit;
+use crate::pb::{PbConstraint, PbRelation};
+
+/// Encodings for linear pseudo-Boolean constraints.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PbEncoding {
+    /// Binary decision diagram with one auxiliary variable per decision node.
+    ///
+    /// The number of nodes is bounded by the number of terms times the number of distinct
+    /// partial sums, so constraints with many large, varied coefficients can produce large
+    /// encodings.
+    Bdd,
+    /// Network of full and half adders computing the weighted sum in binary, compared against
+    /// the right hand side.
+    ///
+    /// The size is linear in the number of terms times the number of bits of the coefficients,
+    /// but unit propagation on the encoding is weaker than for [`Bdd`](PbEncoding::Bdd).
+    Adder,
+}
+
+impl Default for PbEncoding {
+    fn default() -> PbEncoding {
+        PbEncoding::Bdd
+    }
+}
+
+/// Add clauses enforcing a pseudo-Boolean constraint using the given encoding.
+///
+/// New variables are allocated for the encoding using [`ExtendFormula::new_var`].
+pub fn pb_constraint(
+    formula: &mut impl ExtendFormula,
+    constraint: &PbConstraint,
+    encoding: PbEncoding,
+) {
+    match constraint.relation {
+        PbRelation::AtLeast => encode_at_least(formula, constraint, 1, encoding),
+        PbRelation::AtMost => encode_at_least(formula, constraint, -1, encoding),
+        PbRelation::Equal => {
+            encode_at_least(formula, constraint, 1, encoding);
+            encode_at_least(formula, constraint, -1, encoding);
+        }
+    }
+}
+
+/// Encode `sign * sum >= sign * rhs`.
+///
+/// A sign of `-1` encodes `sum <= rhs`.
+fn encode_at_least(
+    formula: &mut impl ExtendFormula,
+    constraint: &PbConstraint,
+    sign: i128,
+    encoding: PbEncoding,
+) {
+    // Normalize to positive coefficients, using `a * l = a - a * !l`.
+    let mut rhs = sign * i128::from(constraint.rhs);
+    let mut normalized: Vec<(i128, Lit)> = vec![];
+    for &(coefficient, lit) in constraint.terms.iter() {
+        let coefficient = sign * i128::from(coefficient);
+        if coefficient > 0 {
+            normalized.push((coefficient, lit));
+        } else if coefficient < 0 {
+            rhs -= coefficient;
+            normalized.push((-coefficient, !lit));
+        }
+    }
+
+    if rhs <= 0 {
+        return;
+    }
+
+    // Coefficients above the rhs behave like the rhs itself
+    for term in normalized.iter_mut() {
+        term.0 = term.0.min(rhs);
+    }
+
+    match encoding {
+        PbEncoding::Bdd => encode_bdd(formula, normalized, rhs),
+        PbEncoding::Adder => encode_adder(formula, &normalized, rhs),
+    }
+}
+
+/// Decision diagram encoding of `sum >= rhs` for positive coefficients and rhs.
+fn encode_bdd(formula: &mut impl ExtendFormula, mut terms: Vec<(i128, Lit)>, rhs: i128) {
+    // Larger coefficients first keeps the diagram small
+    terms.sort_by(|a, b| b.0.cmp(&a.0));
+
+    // suffix_sums[i] is the maximal sum of the terms starting at i
+    let mut suffix_sums = vec![0; terms.len() + 1];
+    for i in (0..terms.len()).rev() {
+        suffix_sums[i] = suffix_sums[i + 1] + terms[i].0;
+    }
+
+    let mut encoder = BddEncoder {
+        terms: &terms,
+        suffix_sums: &suffix_sums,
+        nodes: HashMap::new(),
+    };
+
+    match encoder.node(formula, 0, rhs) {
+        Node::True => (),
+        Node::False => formula.add_clause(&[]),
+        Node::Lit(lit) => formula.add_clause(&[lit]),
+    }
+}
+
+/// Encoding of a decision diagram node.
+#[derive(Copy, Clone)]
+enum Node {
+    True,
+    False,
+    /// A literal that implies the node's condition.
+    Lit(Lit),
+}
+
+/// Decision diagram encoding of `sum >= rhs` for positive coefficients.
+struct BddEncoder<'a> {
+    terms: &'a [(i128, Lit)],
+    suffix_sums: &'a [i128],
+    nodes: HashMap<(usize, i128), Node>,
+}
+
+impl<'a> BddEncoder<'a> {
+    /// Node for the condition that the terms starting at `index` sum to at least `rhs`.
+    fn node(&mut self, formula: &mut impl ExtendFormula, index: usize, rhs: i128) -> Node {
+        if rhs <= 0 {
+            return Node::True;
+        }
+        if self.suffix_sums[index] < rhs {
+            return Node::False;
+        }
+        if let Some(&node) = self.nodes.get(&(index, rhs)) {
+            return node;
+        }
+
+        let (coefficient, lit) = self.terms[index];
+        let high = self.node(formula, index + 1, rhs - coefficient);
+        let low = self.node(formula, index + 1, rhs);
+
+        let node = match (high, low) {
+            (Node::True, Node::True) => Node::True,
+            (Node::True, Node::False) => Node::Lit(lit),
+            _ => {
+                let node_lit = formula.new_lit();
+                // When the literal is false, the remaining terms have to reach rhs
+                match low {
+                    Node::True => (),
+                    Node::False => formula.add_clause(&[!node_lit, lit]),
+                    Node::Lit(low_lit) => formula.add_clause(&[!node_lit, lit, low_lit]),
+                }
+                // In any case the remaining terms have to reach rhs minus the coefficient, which
+                // is implied by reaching rhs, so no case split is needed
+                match high {
+                    Node::True => (),
+                    Node::False => unreachable!(),
+                    Node::Lit(high_lit) => formula.add_clause(&[!node_lit, high_lit]),
+                }
+                Node::Lit(node_lit)
+            }
+        };
+
+        self.nodes.insert((index, rhs), node);
+        node
+    }
+}
+
+/// Adder network encoding of `sum >= rhs` for positive coefficients and rhs.
+fn encode_adder(formula: &mut impl ExtendFormula, terms: &[(i128, Lit)], rhs: i128) {
+    // buckets[i] contains the literals contributing 2^i to the sum
+    let mut buckets: Vec<Vec<Lit>> = vec![];
+    for &(coefficient, lit) in terms.iter() {
+        let mut bit = 0;
+        while coefficient >> bit != 0 {
+            if buckets.len() <= bit {
+                buckets.push(vec![]);
+            }
+            if (coefficient >> bit) & 1 != 0 {
+                buckets[bit].push(lit);
+            }
+            bit += 1;
+        }
+    }
+
+    // Binary digits of the sum, `None` is a constant zero
+    let mut sum_bits: Vec<Option<Lit>> = vec![];
+    let mut bit = 0;
+    while bit < buckets.len() {
+        while buckets[bit].len() >= 2 {
+            let a = buckets[bit].pop().unwrap();
+            let b = buckets[bit].pop().unwrap();
+            let (sum, carry) = if buckets[bit].is_empty() {
+                half_adder(formula, a, b)
+            } else {
+                let c = buckets[bit].pop().unwrap();
+                full_adder(formula, a, b, c)
+            };
+            buckets[bit].push(sum);
+            if buckets.len() <= bit + 1 {
+                buckets.push(vec![]);
+            }
+            buckets[bit + 1].push(carry);
+        }
+        sum_bits.push(buckets[bit].pop());
+        bit += 1;
+    }
+
+    // The sum is at least rhs iff for every one bit of rhs, that bit is set in the sum or a
+    // higher bit is set in the sum but not in rhs.
+    let rhs_bit = |bit: usize| (rhs >> bit) & 1 != 0;
+    let width = sum_bits.len().max(128 - rhs.leading_zeros() as usize);
+
+    for bit in 0..width {
+        if !rhs_bit(bit) {
+            continue;
+        }
+        let clause: Vec<Lit> = (bit..width)
+            .filter(|&higher| higher == bit || !rhs_bit(higher))
+            .filter_map(|higher| sum_bits.get(higher).cloned().unwrap_or(None))
+            .collect();
+        formula.add_clause(&clause);
+    }
+}
+
+/// Add a half adder and return its sum and carry outputs.
+fn half_adder(formula: &mut impl ExtendFormula, a: Lit, b: Lit) -> (Lit, Lit) {
+    let (sum, carry) = formula.new_lits();
+
+    formula.add_clause(&[!a, !b, !sum]);
+    formula.add_clause(&[a, b, !sum]);
+    formula.add_clause(&[!a, b, sum]);
+    formula.add_clause(&[a, !b, sum]);
+
+    formula.add_clause(&[!a, !b, carry]);
+    formula.add_clause(&[a, !carry]);
+    formula.add_clause(&[b, !carry]);
+
+    (sum, carry)
+}
+
+/// Add a full adder and return its sum and carry outputs.
+fn full_adder(formula: &mut impl ExtendFormula, a: Lit, b: Lit, c: Lit) -> (Lit, Lit) {
+    let (sum, carry) = formula.new_lits();
+
+    // The sum is true iff an odd number of inputs is true
+    for values in 0..8u32 {
+        let value = |index: usize| values & (1 << index) != 0;
+        let odd = values.count_ones() % 2 == 1;
+        formula.add_clause(&[a ^ value(0), b ^ value(1), c ^ value(2), sum ^ !odd]);
+    }
+
+    // The carry is true iff at least two inputs are true
+    for &(x, y) in [(a, b), (a, c), (b, c)].iter() {
+        formula.add_clause(&[!x, !y, carry]);
+        formula.add_clause(&[x, y, !carry]);
+    }
+
+    (sum, carry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cnf::CnfFormula;
+    use crate::encode::test::propagate;
+    use crate::lit::Var;
+
+    /// Check an encoding against the constraint for all assignments of its variables.
+    fn check_encoding(var_count: usize, constraint: &PbConstraint, encoding: PbEncoding) {
+        let mut formula = CnfFormula::new();
+        formula.set_var_count(var_count);
+        pb_constraint(&mut formula, constraint, encoding);
+
+        for bits in 0..1u32 << var_count {
+            let assignment: Vec<Lit> = (0..var_count)
+                .map(|index| Var::from_index(index).lit(bits & (1 << index) != 0))
+                .collect();
+
+            // Under a full assignment of the inputs, propagation either finds a conflict or
+            // assigns all auxiliary variables that are not unconstrained, which can be false.
+            let consistent = propagate(&formula, &assignment).map_or(false, |values| {
+                let model: Vec<Lit> = values
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, value)| Var::from_index(index).lit(value.unwrap_or(false)))
+                    .collect();
+                formula.evaluate(&model).is_true()
+            });
+
+            assert_eq!(
+                consistent,
+                constraint.is_satisfied(&assignment),
+                "{:?} {:?} under {:?}",
+                encoding,
+                constraint,
+                assignment
+            );
+        }
+    }
+
+    #[test]
+    fn encodings_match_constraints() {
+        let terms = vec![
+            (3, lit!(1)),
+            (-2, lit!(2)),
+            (2, lit!(-3)),
+            (1, lit!(4)),
+            (5, lit!(2)),
+            (7, lit!(5)),
+        ];
+
+        for &encoding in [PbEncoding::Bdd, PbEncoding::Adder].iter() {
+            for &relation in [PbRelation::AtLeast, PbRelation::Equal, PbRelation::AtMost].iter() {
+                for rhs in -3..20 {
+                    check_encoding(
+                        5,
+                        &PbConstraint {
+                            terms: terms.clone(),
+                            relation,
+                            rhs,
+                        },
+                        encoding,
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn large_coefficients() {
+        let constraint = PbConstraint {
+            terms: vec![(i64::max_value(), lit!(1)), (i64::max_value(), lit!(2))],
+            relation: PbRelation::AtLeast,
+            rhs: i64::max_value(),
+        };
+        check_encoding(2, &constraint, PbEncoding::Adder);
+    }
+}
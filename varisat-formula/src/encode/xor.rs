@@ -0,0 +1,84 @@
+//! Encoding of XOR constraints.
+use crate::cnf::ExtendFormula;
+use crate::lit::Lit;
+
+/// Number of literals that are encoded directly, without auxiliary variables.
+const DIRECT_LIMIT: usize = 4;
+
+/// Require an odd number of the literals to be true.
+///
+/// Negating a literal flips the required parity, so an even number of true literals can be
+/// required by negating one of them. Requiring an odd number of an empty set of literals adds
+/// the empty clause.
+///
+/// Long constraints are split into chunks of four literals connected by auxiliary variables,
+/// resulting in a linear number of clauses.
+pub fn xor_constraint(formula: &mut impl ExtendFormula, lits: &[Lit]) {
+    let mut lits = lits.to_vec();
+
+    while lits.len() > DIRECT_LIMIT {
+        // The auxiliary literal is true iff the chunk's literals have odd parity
+        let parity = formula.new_lit();
+        let mut chunk = lits.split_off(lits.len() - (DIRECT_LIMIT - 1));
+        chunk.push(!parity);
+        direct(formula, &chunk);
+        lits.push(parity);
+    }
+
+    direct(formula, &lits);
+}
+
+/// Encode an XOR constraint with one clause per assignment of even parity.
+fn direct(formula: &mut impl ExtendFormula, lits: &[Lit]) {
+    let mut clause = vec![];
+    for bits in 0..1u32 << lits.len() {
+        if bits.count_ones() % 2 == 1 {
+            continue;
+        }
+        clause.clear();
+        clause.extend(
+            lits.iter()
+                .enumerate()
+                .map(|(index, &lit)| lit ^ (bits & (1 << index) != 0)),
+        );
+        formula.add_clause(&clause);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cnf::CnfFormula;
+    use crate::encode::test::propagate;
+    use crate::lit::Var;
+
+    #[test]
+    fn parity() {
+        for lit_count in 0..10 {
+            for &negate_first in [false, true].iter() {
+                let mut formula = CnfFormula::new();
+                let mut lits: Vec<Lit> = formula.new_lit_iter(lit_count).collect();
+                if negate_first && lit_count > 0 {
+                    lits[0] = !lits[0];
+                }
+                xor_constraint(&mut formula, &lits);
+
+                for bits in 0..1u32 << lit_count {
+                    let assignment: Vec<Lit> = (0..lit_count)
+                        .map(|index| Var::from_index(index).lit(bits & (1 << index) != 0))
+                        .collect();
+
+                    let odd = (bits.count_ones() % 2 == 1) != (negate_first && lit_count > 0);
+
+                    assert_eq!(
+                        propagate(&formula, &assignment).is_some(),
+                        odd,
+                        "{:?}",
+                        assignment
+                    );
+                }
+            }
+        }
+    }
+}
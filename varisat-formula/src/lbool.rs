@@ -0,0 +1,209 @@
+//! Three-valued logic for partial assignments.
+use std::{fmt, ops};
+
+use crate::lit::{Lit, Var};
+
+/// A Boolean value that may be unknown.
+///
+/// Used for the values of variables, literals and formulas under partial assignments. The
+/// operators follow Kleene's strong three-valued logic, i.e. `Undef` stands for a value that could
+/// be either `True` or `False`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Lbool {
+    /// Known to be true.
+    True,
+    /// Known to be false.
+    False,
+    /// Unassigned or unknown.
+    Undef,
+}
+
+impl Default for Lbool {
+    fn default() -> Lbool {
+        Lbool::Undef
+    }
+}
+
+impl Lbool {
+    /// Whether the value is `True`.
+    #[inline]
+    pub fn is_true(self) -> bool {
+        self == Lbool::True
+    }
+
+    /// Whether the value is `False`.
+    #[inline]
+    pub fn is_false(self) -> bool {
+        self == Lbool::False
+    }
+
+    /// Whether the value is `Undef`.
+    #[inline]
+    pub fn is_undef(self) -> bool {
+        self == Lbool::Undef
+    }
+
+    /// Whether the value is `True` or `False`.
+    #[inline]
+    pub fn is_defined(self) -> bool {
+        !self.is_undef()
+    }
+
+    /// The value as an `Option<bool>`, mapping `Undef` to `None`.
+    #[inline]
+    pub fn to_option(self) -> Option<bool> {
+        match self {
+            Lbool::True => Some(true),
+            Lbool::False => Some(false),
+            Lbool::Undef => None,
+        }
+    }
+
+    /// The literal of a variable assigned this value.
+    ///
+    /// Returns `None` for `Undef`.
+    #[inline]
+    pub fn lit(self, var: Var) -> Option<Lit> {
+        self.to_option().map(|value| var.lit(value))
+    }
+}
+
+impl From<bool> for Lbool {
+    #[inline]
+    fn from(value: bool) -> Lbool {
+        if value {
+            Lbool::True
+        } else {
+            Lbool::False
+        }
+    }
+}
+
+impl From<Option<bool>> for Lbool {
+    #[inline]
+    fn from(value: Option<bool>) -> Lbool {
+        value.map_or(Lbool::Undef, Lbool::from)
+    }
+}
+
+impl From<Lbool> for Option<bool> {
+    #[inline]
+    fn from(value: Lbool) -> Option<bool> {
+        value.to_option()
+    }
+}
+
+impl ops::Not for Lbool {
+    type Output = Lbool;
+
+    #[inline]
+    fn not(self) -> Lbool {
+        match self {
+            Lbool::True => Lbool::False,
+            Lbool::False => Lbool::True,
+            Lbool::Undef => Lbool::Undef,
+        }
+    }
+}
+
+impl ops::BitAnd for Lbool {
+    type Output = Lbool;
+
+    #[inline]
+    fn bitand(self, rhs: Lbool) -> Lbool {
+        match (self, rhs) {
+            (Lbool::False, _) | (_, Lbool::False) => Lbool::False,
+            (Lbool::True, Lbool::True) => Lbool::True,
+            _ => Lbool::Undef,
+        }
+    }
+}
+
+impl ops::BitOr for Lbool {
+    type Output = Lbool;
+
+    #[inline]
+    fn bitor(self, rhs: Lbool) -> Lbool {
+        !(!self & !rhs)
+    }
+}
+
+/// Negates the value when `rhs` is `true`, as for the polarity of a literal.
+impl ops::BitXor<bool> for Lbool {
+    type Output = Lbool;
+
+    #[inline]
+    fn bitxor(self, rhs: bool) -> Lbool {
+        if rhs {
+            !self
+        } else {
+            self
+        }
+    }
+}
+
+impl ops::BitAndAssign for Lbool {
+    #[inline]
+    fn bitand_assign(&mut self, rhs: Lbool) {
+        *self = *self & rhs;
+    }
+}
+
+impl ops::BitOrAssign for Lbool {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: Lbool) {
+        *self = *self | rhs;
+    }
+}
+
+/// Uses `true`, `false` and `undef`.
+impl fmt::Display for Lbool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Lbool::True => write!(f, "true"),
+            Lbool::False => write!(f, "false"),
+            Lbool::Undef => write!(f, "undef"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALUES: [Lbool; 3] = [Lbool::True, Lbool::False, Lbool::Undef];
+
+    #[test]
+    fn kleene_logic() {
+        for &a in VALUES.iter() {
+            assert_eq!(!!a, a);
+            assert_eq!(a & Lbool::True, a);
+            assert_eq!(a | Lbool::False, a);
+            assert_eq!(a & Lbool::False, Lbool::False);
+            assert_eq!(a | Lbool::True, Lbool::True);
+            assert_eq!(a ^ true, !a);
+            assert_eq!(a ^ false, a);
+
+            for &b in VALUES.iter() {
+                assert_eq!(a & b, b & a);
+                assert_eq!(!(a | b), !a & !b);
+                if let (Some(x), Some(y)) = (a.to_option(), b.to_option()) {
+                    assert_eq!(a & b, Lbool::from(x && y));
+                    assert_eq!(a | b, Lbool::from(x || y));
+                }
+            }
+        }
+        assert_eq!(Lbool::Undef & Lbool::Undef, Lbool::Undef);
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(Lbool::from(Some(false)), Lbool::False);
+        assert_eq!(Lbool::from(None), Lbool::Undef);
+        assert_eq!(Option::<bool>::from(Lbool::True), Some(true));
+        assert_eq!(Lbool::default(), Lbool::Undef);
+        assert_eq!(Lbool::False.lit(var!(2)), Some(lit!(-2)));
+        assert_eq!(Lbool::Undef.lit(var!(2)), None);
+        assert_eq!(Lbool::Undef.to_string(), "undef");
+    }
+}
@@ -1,8 +1,15 @@
 //! Basic formula data types used by the Varisat SAT solver.
 
-/// Shortcut for tests
-#[cfg(any(test, feature = "internal-testing"))]
-#[doc(hidden)]
+/// Creates a [`Lit`](crate::lit::Lit) from its 1-based DIMACS CNF encoding.
+///
+/// ```
+/// use varisat_formula::{lit, Lit};
+///
+/// assert_eq!(lit!(-3), Lit::from_dimacs(-3));
+/// ```
+///
+/// The argument must be a non-zero integer within the range supported by
+/// [`Var`](crate::lit::Var).
 #[macro_export]
 macro_rules! lit {
     ($x:expr) => {
@@ -10,9 +17,16 @@ macro_rules! lit {
     };
 }
 
-/// Shortcut for tests
-#[cfg(any(test, feature = "internal-testing"))]
-#[doc(hidden)]
+/// Creates a [`Var`](crate::lit::Var) from its 1-based DIMACS CNF encoding.
+///
+/// ```
+/// use varisat_formula::{var, Var};
+///
+/// assert_eq!(var!(3), Var::from_dimacs(3));
+/// ```
+///
+/// The argument must be a positive integer within the range supported by
+/// [`Var`](crate::lit::Var).
 #[macro_export]
 macro_rules! var {
     ($x:expr) => {
@@ -20,45 +34,99 @@ macro_rules! var {
     };
 }
 
-/// Shortcut for tests
-#[cfg(any(test, feature = "internal-testing"))]
-#[doc(hidden)]
+/// Creates an array of [`Lit`](crate::lit::Lit)s from their DIMACS CNF encodings.
+///
+/// ```
+/// use varisat_formula::{lits, Var};
+///
+/// let x = Var::from_dimacs(2);
+/// assert_eq!(lits![1, -2], [Var::from_dimacs(1).positive(), x.negative()]);
+/// ```
 #[macro_export]
 macro_rules! lits {
     ( $( $x:expr ),* ) => { [ $( $crate::lit!( $x ) ),* ] };
     ( $( $x:expr ),* , ) => { $crate::lits! [ $( $ x),* ] };
 }
 
-/// Shortcut for tests
-#[cfg(any(test, feature = "internal-testing"))]
-#[doc(hidden)]
+/// Creates an array of [`Var`](crate::lit::Var)s from their DIMACS CNF encodings.
+///
+/// ```
+/// use varisat_formula::{vars, Var};
+///
+/// assert_eq!(vars![1, 3], [Var::from_index(0), Var::from_index(2)]);
+/// ```
 #[macro_export]
 macro_rules! vars {
     ( $( $x:expr ),* ) => { [ $( $crate::var!( $x ) ),* ] };
     ( $( $x:expr ),* , ) => { $crate::vars! [ $( $ x),* ] };
 }
 
-/// Shortcut for tests
-#[cfg(any(test, feature = "internal-testing"))]
-#[doc(hidden)]
+/// Creates an array of clauses, each a slice of [`Lit`](crate::lit::Lit)s.
+///
+/// Literals use the DIMACS CNF encoding, are separated by commas and every clause is terminated by
+/// a semicolon.
+///
+/// ```
+/// use varisat_formula::{cnf, lits};
+///
+/// let clauses = cnf![
+///     1, -2;
+///     2;
+/// ];
+/// assert_eq!(clauses, [&lits![1, -2][..], &lits![2][..]]);
+/// ```
 #[macro_export]
 macro_rules! cnf {
     ( $( $( $x:expr ),* );* ; ) => { [ $( &[ $( $crate::lit!( $x ) ),* ] as &[$crate::Lit] ),* ] };
 }
 
-/// Shortcut for tests
-#[cfg(any(test, feature = "internal-testing"))]
-#[doc(hidden)]
+/// Creates a [`CnfFormula`](crate::cnf::CnfFormula) using the syntax of [`cnf!`].
+///
+/// ```
+/// use varisat_formula::{cnf_formula, lits};
+///
+/// let formula = cnf_formula![
+///     1, -2;
+///     -1, 2, 3;
+/// ];
+/// assert_eq!(formula.var_count(), 3);
+/// assert_eq!(formula.iter().nth(1), Some(&lits![-1, 2, 3][..]));
+/// ```
+///
+/// The variable count of the resulting formula is the largest variable index used.
 #[macro_export]
 macro_rules! cnf_formula {
     ( $( $t:tt )* ) => { $crate::cnf::CnfFormula::from($crate::cnf![ $($t)* ].iter().cloned()) };
 }
 
 pub mod cnf;
+pub mod diff;
+pub mod encode;
+pub mod lbool;
 pub mod lit;
+pub mod map;
+pub mod miter;
+pub mod model;
+pub mod names;
+pub mod pb;
+pub mod simplify;
+pub mod subformula;
+pub mod wcnf;
+
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary_impls;
+
+#[cfg(feature = "serde")]
+mod serde_impls;
 
 #[cfg(any(test, feature = "internal-testing"))]
 pub mod test;
 
-pub use cnf::{CnfFormula, ExtendFormula};
+pub use cnf::{CnfFormula, ExtendFormula, ModelCheck};
+pub use lbool::Lbool;
 pub use lit::{Lit, Var};
+pub use map::{LitMap, VarMap};
+pub use model::Model;
+pub use names::VarNames;
+pub use pb::{PbConstraint, PbProblem, PbRelation, WboProblem};
+pub use wcnf::WcnfFormula;
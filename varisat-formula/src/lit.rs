@@ -1,9 +1,23 @@
 //! Literals and variables.
-use std::{fmt, ops};
+use std::{error, fmt, ops, str::FromStr};
 
 /// The backing type used to represent literals and variables.
+///
+/// This is `u32` unless the `large-index` feature is enabled, which widens it to `u64`. That
+/// raises the number of supported variables from 2^28 to 2^60, at the cost of doubling the memory
+/// used for literals.
+#[cfg(not(feature = "large-index"))]
 pub type LitIdx = u32;
 
+/// The backing type used to represent literals and variables.
+///
+/// This is `u64` as the `large-index` feature is enabled.
+#[cfg(feature = "large-index")]
+pub type LitIdx = u64;
+
+#[cfg(all(feature = "large-index", not(target_pointer_width = "64")))]
+compile_error!("the large-index feature requires a 64-bit target");
+
 /// A boolean variable.
 ///
 /// A boolean value is represented by an index. Internally these are 0-based, i.e. the first
@@ -28,6 +42,18 @@ impl Var {
         Var::from_index((number - 1) as usize)
     }
 
+    /// Creates a variable from a 1-based index, checking that it is in range.
+    ///
+    /// Unlike `from_dimacs` this accepts any integer and returns `None` when it does not denote a
+    /// supported variable.
+    pub fn checked_from_dimacs(number: i64) -> Option<Var> {
+        if number > 0 && number as u64 <= Var::max_count() as u64 {
+            Some(Var::from_dimacs(number as isize))
+        } else {
+            None
+        }
+    }
+
     /// Creates a variable from a 0-based index.
     ///
     /// The index may not represent a variable past `Var::max_var()`.
@@ -108,7 +134,19 @@ impl fmt::Display for Var {
     }
 }
 
-/// A boolean literal.
+/// Parses the 1-based DIMACS CNF encoding.
+impl FromStr for Var {
+    type Err = ParseLitError;
+
+    fn from_str(s: &str) -> Result<Var, ParseLitError> {
+        let number = parse_dimacs(s)?;
+        if number < 0 {
+            return Err(ParseLitError::Negative);
+        }
+        Var::checked_from_dimacs(number).ok_or(ParseLitError::OutOfRange)
+    }
+}
+
 ///
 /// A literal is a variable or the negation of a variable.
 ///
@@ -178,6 +216,15 @@ impl Lit {
         Lit::from_var(Var::from_dimacs(number.abs()), number > 0)
     }
 
+    /// Creates a literal from an integer, checking that it is in range.
+    ///
+    /// Unlike `from_dimacs` this accepts any integer and returns `None` for zero or when the
+    /// variable is not supported.
+    pub fn checked_from_dimacs(number: i64) -> Option<Lit> {
+        let var = Var::checked_from_dimacs(number.checked_abs()?)?;
+        Some(var.lit(number > 0))
+    }
+
     /// 1-based Integer representation of the literal, opposite of `from_dimacs`.
     #[inline]
     pub fn to_dimacs(self) -> isize {
@@ -272,6 +319,54 @@ impl fmt::Display for Lit {
     }
 }
 
+/// Parses the 1-based DIMACS CNF encoding.
+impl FromStr for Lit {
+    type Err = ParseLitError;
+
+    fn from_str(s: &str) -> Result<Lit, ParseLitError> {
+        Lit::checked_from_dimacs(parse_dimacs(s)?).ok_or(ParseLitError::OutOfRange)
+    }
+}
+
+fn parse_dimacs(s: &str) -> Result<i64, ParseLitError> {
+    let number: i64 = s.trim().parse().map_err(|_| ParseLitError::Invalid)?;
+    if number == 0 {
+        Err(ParseLitError::Zero)
+    } else {
+        Ok(number)
+    }
+}
+
+/// Error returned when parsing a variable or literal from a string fails.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ParseLitError {
+    /// The string is not an integer.
+    Invalid,
+    /// The integer is zero, which doesn't denote a variable or literal.
+    Zero,
+    /// A negative integer was given where a variable was expected.
+    Negative,
+    /// The integer's magnitude exceeds `Var::max_count()`.
+    OutOfRange,
+}
+
+impl fmt::Display for ParseLitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseLitError::Invalid => write!(f, "invalid integer"),
+            ParseLitError::Zero => write!(f, "zero is not a valid variable or literal"),
+            ParseLitError::Negative => write!(f, "variables cannot be negative"),
+            ParseLitError::OutOfRange => write!(
+                f,
+                "variable index exceeds the maximum of {}",
+                Var::max_count()
+            ),
+        }
+    }
+}
+
+impl error::Error for ParseLitError {}
+
 #[cfg(any(test, feature = "proptest-strategies"))]
 #[doc(hidden)]
 pub mod strategy {
@@ -286,3 +381,38 @@ pub mod strategy {
         (var(index), bool::ANY).prop_map(|(var, polarity)| var.lit(polarity))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dimacs_conversion() {
+        assert_eq!(Lit::checked_from_dimacs(-3), Some(lit!(-3)));
+        assert_eq!(Lit::checked_from_dimacs(0), None);
+        assert_eq!(Lit::checked_from_dimacs(i64::min_value()), None);
+        assert_eq!(
+            Var::checked_from_dimacs(Var::max_count() as i64),
+            Some(Var::max_var())
+        );
+        assert_eq!(Var::checked_from_dimacs(Var::max_count() as i64 + 1), None);
+        assert_eq!(Var::checked_from_dimacs(-1), None);
+    }
+
+    #[test]
+    fn parse_and_display() {
+        assert_eq!("-7".parse::<Lit>(), Ok(lit!(-7)));
+        assert_eq!(" 12 ".parse::<Lit>(), Ok(lit!(12)));
+        assert_eq!("12".parse::<Var>(), Ok(var!(12)));
+        assert_eq!("x".parse::<Lit>(), Err(ParseLitError::Invalid));
+        assert_eq!("0".parse::<Lit>(), Err(ParseLitError::Zero));
+        assert_eq!("-2".parse::<Var>(), Err(ParseLitError::Negative));
+        assert_eq!(
+            (Var::max_count() + 1).to_string().parse::<Lit>(),
+            Err(ParseLitError::OutOfRange)
+        );
+
+        assert_eq!(lit!(-7).to_string(), "-7");
+        assert_eq!(lit!(-7).to_string().parse::<Lit>(), Ok(lit!(-7)));
+    }
+}
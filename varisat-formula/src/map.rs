@@ -0,0 +1,223 @@
+//! Dense maps indexed by variables or literals.
+use std::ops::{Index, IndexMut};
+
+use crate::lit::{Lit, Var};
+
+/// A dense map from variables to values.
+///
+/// Every variable is mapped to a value. Variables that were never written map to a default value.
+/// Mutable access grows the underlying storage as needed, so the map doesn't have to be resized
+/// when new variables are allocated.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct VarMap<T> {
+    values: Vec<T>,
+    default: T,
+}
+
+impl<T: Default> Default for VarMap<T> {
+    fn default() -> VarMap<T> {
+        VarMap::with_default(T::default())
+    }
+}
+
+impl<T: Default> VarMap<T> {
+    /// Create an empty map using `T::default()` for unwritten variables.
+    pub fn new() -> VarMap<T> {
+        VarMap::default()
+    }
+}
+
+impl<T> VarMap<T> {
+    /// Create an empty map using the given value for unwritten variables.
+    pub fn with_default(default: T) -> VarMap<T> {
+        VarMap {
+            values: vec![],
+            default,
+        }
+    }
+
+    /// One above the largest index of a variable with allocated storage.
+    pub fn var_count(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Iterator over the variables with allocated storage and their values.
+    pub fn iter(&self) -> impl Iterator<Item = (Var, &T)> {
+        self.values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (Var::from_index(index), value))
+    }
+
+    /// Mutable iterator over the variables with allocated storage and their values.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Var, &mut T)> {
+        self.values
+            .iter_mut()
+            .enumerate()
+            .map(|(index, value)| (Var::from_index(index), value))
+    }
+}
+
+impl<T: Clone> VarMap<T> {
+    /// Allocate storage for all variables with an index below `count`.
+    pub fn set_var_count(&mut self, count: usize) {
+        if self.values.len() < count {
+            self.values.resize(count, self.default.clone());
+        }
+    }
+
+    /// Reset all variables to the default value.
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+}
+
+impl<T> Index<Var> for VarMap<T> {
+    type Output = T;
+
+    fn index(&self, var: Var) -> &T {
+        self.values.get(var.index()).unwrap_or(&self.default)
+    }
+}
+
+impl<T: Clone> IndexMut<Var> for VarMap<T> {
+    fn index_mut(&mut self, var: Var) -> &mut T {
+        self.set_var_count(var.index() + 1);
+        &mut self.values[var.index()]
+    }
+}
+
+/// A dense map from literals to values.
+///
+/// Like [`VarMap`], but with separate values for both polarities of a variable.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LitMap<T> {
+    values: Vec<T>,
+    default: T,
+}
+
+impl<T: Default> Default for LitMap<T> {
+    fn default() -> LitMap<T> {
+        LitMap::with_default(T::default())
+    }
+}
+
+impl<T: Default> LitMap<T> {
+    /// Create an empty map using `T::default()` for unwritten literals.
+    pub fn new() -> LitMap<T> {
+        LitMap::default()
+    }
+}
+
+impl<T> LitMap<T> {
+    /// Create an empty map using the given value for unwritten literals.
+    pub fn with_default(default: T) -> LitMap<T> {
+        LitMap {
+            values: vec![],
+            default,
+        }
+    }
+
+    /// One above the largest index of a variable with allocated storage.
+    pub fn var_count(&self) -> usize {
+        self.values.len() / 2
+    }
+
+    /// Iterator over the literals with allocated storage and their values.
+    ///
+    /// The positive literal of a variable comes before the negative one.
+    pub fn iter(&self) -> impl Iterator<Item = (Lit, &T)> {
+        self.values
+            .iter()
+            .enumerate()
+            .map(|(code, value)| (Lit::from_code(code), value))
+    }
+
+    /// Mutable iterator over the literals with allocated storage and their values.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Lit, &mut T)> {
+        self.values
+            .iter_mut()
+            .enumerate()
+            .map(|(code, value)| (Lit::from_code(code), value))
+    }
+}
+
+impl<T: Clone> LitMap<T> {
+    /// Allocate storage for both literals of all variables with an index below `count`.
+    pub fn set_var_count(&mut self, count: usize) {
+        if self.values.len() < count * 2 {
+            self.values.resize(count * 2, self.default.clone());
+        }
+    }
+
+    /// Reset all literals to the default value.
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+}
+
+impl<T> Index<Lit> for LitMap<T> {
+    type Output = T;
+
+    fn index(&self, lit: Lit) -> &T {
+        self.values.get(lit.code()).unwrap_or(&self.default)
+    }
+}
+
+impl<T: Clone> IndexMut<Lit> for LitMap<T> {
+    fn index_mut(&mut self, lit: Lit) -> &mut T {
+        self.set_var_count(lit.index() + 1);
+        &mut self.values[lit.code()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn var_map() {
+        let mut map: VarMap<u32> = VarMap::new();
+        assert_eq!(map[var!(5)], 0);
+        assert_eq!(map.var_count(), 0);
+
+        map[var!(3)] = 7;
+        map[var!(1)] += 2;
+        assert_eq!(map.var_count(), 3);
+        assert_eq!(map[var!(3)], 7);
+        assert_eq!(map[var!(100)], 0);
+
+        for (_, value) in map.iter_mut() {
+            *value += 1;
+        }
+        let entries: Vec<_> = map.iter().map(|(var, &value)| (var, value)).collect();
+        assert_eq!(entries, vec![(var!(1), 3), (var!(2), 1), (var!(3), 8)]);
+
+        let mut map = VarMap::with_default(None);
+        map[var!(2)] = Some("x");
+        map.clear();
+        assert_eq!(map[var!(2)], None);
+    }
+
+    #[test]
+    fn lit_map() {
+        let mut map: LitMap<Vec<usize>> = LitMap::new();
+        map[lit!(-2)].push(1);
+        map[lit!(2)].push(2);
+        map[lit!(-2)].push(3);
+
+        assert_eq!(map.var_count(), 2);
+        assert_eq!(map[lit!(-2)], vec![1, 3]);
+        assert_eq!(map[lit!(2)], vec![2]);
+        assert!(map[lit!(1)].is_empty());
+        assert!(map[lit!(-9)].is_empty());
+
+        let lits: Vec<Lit> = map.iter().map(|(lit, _)| lit).collect();
+        assert_eq!(lits, lits![1, -1, 2, -2]);
+
+        let mut counts = LitMap::with_default(1);
+        counts.set_var_count(1);
+        counts[lit!(-1)] = 4;
+        assert_eq!(counts.iter().map(|(_, &count)| count).sum::<i32>(), 5);
+    }
+}
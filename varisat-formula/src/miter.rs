@@ -0,0 +1,196 @@
+//! Miter construction for combinational equivalence checking.
+use crate::cnf::{CnfFormula, ExtendFormula};
+use crate::lit::Lit;
+
+/// A combinational circuit given by a formula that defines its outputs in terms of its inputs.
+///
+/// This is usually a Tseitin encoding of the circuit's gates, e.g. the formula of an
+/// and-inverter graph read from an AIGER file.
+#[derive(Copy, Clone, Debug)]
+pub struct CircuitCnf<'a> {
+    /// Clauses defining the outputs.
+    pub formula: &'a CnfFormula,
+    /// Literals of the formula corresponding to the inputs of the circuit.
+    pub inputs: &'a [Lit],
+    /// Literals of the formula corresponding to the outputs of the circuit.
+    pub outputs: &'a [Lit],
+}
+
+/// Add a miter of two circuits to a formula.
+///
+/// The `i`-th input of both circuits is connected to the literal `inputs[i]` of the target. All
+/// other variables of the circuits are replaced by new variables. The returned literal is true iff
+/// some pair of corresponding outputs differs. Asserting it, the target is unsatisfiable iff the
+/// circuits are equivalent, assuming each circuit's formula is satisfiable for all inputs.
+///
+/// Panics if the circuits don't have as many inputs as given or differ in their number of outputs.
+pub fn miter(
+    target: &mut impl ExtendFormula,
+    inputs: &[Lit],
+    left: CircuitCnf,
+    right: CircuitCnf,
+) -> Lit {
+    assert_eq!(left.inputs.len(), inputs.len(), "left input count mismatch");
+    assert_eq!(
+        right.inputs.len(),
+        inputs.len(),
+        "right input count mismatch"
+    );
+    assert_eq!(
+        left.outputs.len(),
+        right.outputs.len(),
+        "output count mismatch"
+    );
+
+    let left_outputs = copy_circuit(target, inputs, left);
+    let right_outputs = copy_circuit(target, inputs, right);
+
+    let mut differences = vec![];
+    for (&a, &b) in left_outputs.iter().zip(right_outputs.iter()) {
+        let difference = target.new_lit();
+        target.add_clause(&[!difference, a, b]);
+        target.add_clause(&[!difference, !a, !b]);
+        target.add_clause(&[difference, !a, b]);
+        target.add_clause(&[difference, a, !b]);
+        differences.push(difference);
+    }
+
+    let output = target.new_lit();
+    for &difference in differences.iter() {
+        target.add_clause(&[output, !difference]);
+    }
+    differences.push(!output);
+    target.add_clause(&differences);
+
+    output
+}
+
+/// Copy the clauses of a circuit, returning the literals of its outputs in the target.
+fn copy_circuit(target: &mut impl ExtendFormula, inputs: &[Lit], circuit: CircuitCnf) -> Vec<Lit> {
+    let var_count = circuit
+        .inputs
+        .iter()
+        .chain(circuit.outputs.iter())
+        .map(|lit| lit.index() + 1)
+        .max()
+        .unwrap_or(0)
+        .max(circuit.formula.var_count());
+
+    let mut var_map: Vec<Option<Lit>> = vec![None; var_count];
+    for (&circuit_input, &input) in circuit.inputs.iter().zip(inputs.iter()) {
+        var_map[circuit_input.index()] = Some(input ^ circuit_input.is_negative());
+    }
+
+    let mut clause = vec![];
+    for circuit_clause in circuit.formula.iter() {
+        clause.clear();
+        for &lit in circuit_clause.iter() {
+            clause.push(map_lit(target, &mut var_map, lit));
+        }
+        target.add_clause(&clause);
+    }
+
+    circuit
+        .outputs
+        .iter()
+        .map(|&lit| map_lit(target, &mut var_map, lit))
+        .collect()
+}
+
+/// Literal of the target corresponding to a literal of a circuit, allocating a new variable if
+/// necessary.
+fn map_lit(target: &mut impl ExtendFormula, var_map: &mut [Option<Lit>], lit: Lit) -> Lit {
+    let mapped = match var_map[lit.index()] {
+        Some(mapped) => mapped,
+        None => {
+            let mapped = target.new_lit();
+            var_map[lit.index()] = Some(mapped);
+            mapped
+        }
+    };
+    mapped ^ lit.is_negative()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::lit::Var;
+
+    /// Whether the formula together with the unit clause is satisfiable, by enumeration.
+    fn satisfiable(formula: &CnfFormula, assertion: Lit) -> bool {
+        let var_count = formula.var_count();
+        (0..1u32 << var_count).any(|bits| {
+            let model: Vec<Lit> = (0..var_count)
+                .map(|index| Var::from_index(index).lit(bits & (1 << index) != 0))
+                .collect();
+            formula.evaluate(&model).is_true() && model.contains(&assertion)
+        })
+    }
+
+    #[test]
+    fn equivalent_and_different_circuits() {
+        // 3 <-> 1 xor 2
+        let xor = cnf_formula![
+            -3, 1, 2;
+            -3, -1, -2;
+            3, -1, 2;
+            3, 1, -2;
+        ];
+        // 3 <-> 1 or 2, 4 <-> 1 and 2, 5 <-> 3 and not 4
+        let gates = cnf_formula![
+            -3, 1, 2;
+            3, -1;
+            3, -2;
+            -4, 1;
+            -4, 2;
+            4, -1, -2;
+            -5, 3;
+            -5, -4;
+            5, -3, 4;
+        ];
+        // 2 <-> 3 or 1 with swapped input variables
+        let or = cnf_formula![
+            -2, 3, 1;
+            2, -3;
+            2, -1;
+        ];
+
+        let xor_circuit = CircuitCnf {
+            formula: &xor,
+            inputs: &lits![1, 2],
+            outputs: &lits![3],
+        };
+        let gates_circuit = CircuitCnf {
+            formula: &gates,
+            inputs: &lits![1, 2],
+            outputs: &lits![5],
+        };
+        let or_circuit = CircuitCnf {
+            formula: &or,
+            inputs: &lits![1, 3],
+            outputs: &lits![2],
+        };
+        // Negating an input and the output of xor results in the same function
+        let xnor_circuit = CircuitCnf {
+            formula: &xor,
+            inputs: &lits![-1, 2],
+            outputs: &lits![-3],
+        };
+
+        for &(left, right, equivalent) in [
+            (xor_circuit, gates_circuit, true),
+            (xor_circuit, xnor_circuit, true),
+            (xor_circuit, or_circuit, false),
+        ]
+        .iter()
+        {
+            let mut formula = CnfFormula::new();
+            let inputs = formula.new_lits::<(Lit, Lit)>();
+            let output = miter(&mut formula, &[inputs.0, inputs.1], left, right);
+
+            assert_eq!(satisfiable(&formula, output), !equivalent);
+            assert!(satisfiable(&formula, !output));
+        }
+    }
+}
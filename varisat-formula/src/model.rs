@@ -0,0 +1,187 @@
+//! Assignments of variables, as returned for satisfiable formulas.
+use std::iter::FromIterator;
+use std::ops::Index;
+
+use crate::lbool::Lbool;
+use crate::lit::{Lit, Var};
+
+/// An assignment of Boolean values to variables.
+///
+/// A model can be partial, leaving some variables unassigned. This is the case for models
+/// projected onto a subset of the variables, see [`project`](Model::project).
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Model {
+    values: Vec<Lbool>,
+}
+
+impl Model {
+    /// Create an empty model.
+    pub fn new() -> Model {
+        Model::default()
+    }
+
+    /// Create a model assigning the given literals.
+    ///
+    /// If both polarities of a variable are present, the last one is used.
+    pub fn from_lits(lits: &[Lit]) -> Model {
+        lits.iter().cloned().collect()
+    }
+
+    /// Value assigned to a variable.
+    pub fn value(&self, var: Var) -> Lbool {
+        self.values.get(var.index()).cloned().unwrap_or_default()
+    }
+
+    /// Value of a literal under the model.
+    pub fn lit_value(&self, lit: Lit) -> Lbool {
+        self.value(lit.var()) ^ lit.is_negative()
+    }
+
+    /// Whether the literal is true under the model.
+    pub fn contains(&self, lit: Lit) -> bool {
+        self.lit_value(lit).is_true()
+    }
+
+    /// Make a literal true, replacing any previous value of its variable.
+    pub fn assign(&mut self, lit: Lit) {
+        if self.values.len() <= lit.index() {
+            self.values.resize(lit.index() + 1, Lbool::Undef);
+        }
+        self.values[lit.index()] = Lbool::from(lit.is_positive());
+    }
+
+    /// Remove the value of a variable.
+    pub fn unassign(&mut self, var: Var) {
+        if let Some(value) = self.values.get_mut(var.index()) {
+            *value = Lbool::Undef;
+        }
+    }
+
+    /// Number of assigned variables.
+    pub fn len(&self) -> usize {
+        self.values
+            .iter()
+            .filter(|value| value.is_defined())
+            .count()
+    }
+
+    /// Whether no variable is assigned.
+    pub fn is_empty(&self) -> bool {
+        self.values.iter().all(|value| value.is_undef())
+    }
+
+    /// Iterator over the true literals, ordered by variable.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = Lit> + 'a {
+        self.values
+            .iter()
+            .enumerate()
+            .filter_map(|(index, value)| value.lit(Var::from_index(index)))
+    }
+
+    /// The true literals, ordered by variable.
+    ///
+    /// This can be passed to functions expecting an assignment, like
+    /// [`CnfFormula::evaluate`](crate::CnfFormula::evaluate).
+    pub fn lits(&self) -> Vec<Lit> {
+        self.iter().collect()
+    }
+
+    /// The true literals in DIMACS encoding, as listed on the `v` lines of a solution.
+    pub fn to_dimacs(&self) -> Vec<isize> {
+        self.iter().map(|lit| lit.to_dimacs()).collect()
+    }
+
+    /// Partial model that only assigns the given variables.
+    ///
+    /// When enumerating models projected onto a set of variables, the projected models are
+    /// distinct, and [`blocking_clause`](Model::blocking_clause) of a projected model excludes
+    /// all models that agree on the given variables.
+    pub fn project(&self, vars: &[Var]) -> Model {
+        let mut projected = Model::new();
+        for &var in vars.iter() {
+            if let Some(lit) = self.value(var).lit(var) {
+                projected.assign(lit);
+            }
+        }
+        projected
+    }
+
+    /// Clause that is falsified by every assignment extending this model.
+    ///
+    /// Adding this clause excludes the model from further solutions.
+    pub fn blocking_clause(&self) -> Vec<Lit> {
+        self.iter().map(|lit| !lit).collect()
+    }
+}
+
+impl FromIterator<Lit> for Model {
+    fn from_iter<T: IntoIterator<Item = Lit>>(iter: T) -> Model {
+        let mut model = Model::new();
+        for lit in iter {
+            model.assign(lit);
+        }
+        model
+    }
+}
+
+impl Index<Var> for Model {
+    type Output = Lbool;
+
+    fn index(&self, var: Var) -> &Lbool {
+        self.values.get(var.index()).unwrap_or(&Lbool::Undef)
+    }
+}
+
+impl Index<Lit> for Model {
+    type Output = Lbool;
+
+    fn index(&self, lit: Lit) -> &Lbool {
+        match self.lit_value(lit) {
+            Lbool::True => &Lbool::True,
+            Lbool::False => &Lbool::False,
+            Lbool::Undef => &Lbool::Undef,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_and_projection() {
+        let mut model = Model::from_lits(&lits![1, -3, 4, 3]);
+
+        assert_eq!(model.len(), 3);
+        assert_eq!(model[var!(1)], Lbool::True);
+        assert_eq!(model[var!(2)], Lbool::Undef);
+        assert_eq!(model[var!(3)], Lbool::True);
+        assert_eq!(model[var!(7)], Lbool::Undef);
+        assert_eq!(model[lit!(-1)], Lbool::False);
+        assert_eq!(model[lit!(-2)], Lbool::Undef);
+        assert_eq!(model.lit_value(lit!(-3)), Lbool::False);
+        assert!(model.contains(lit!(4)));
+        assert!(!model.contains(lit!(-4)));
+        assert!(!model.contains(lit!(2)));
+
+        assert_eq!(model.lits(), lits![1, 3, 4]);
+        assert_eq!(model.to_dimacs(), vec![1, 3, 4]);
+
+        let formula = cnf_formula![
+            -1, 2, 3;
+            -4, -3;
+        ];
+        assert_eq!(formula.evaluate(&model.lits()), Lbool::False);
+
+        model.assign(lit!(-3));
+        model.unassign(var!(1));
+        assert_eq!(model.lits(), lits![-3, 4]);
+
+        let projected = model.project(&vars![2, 4]);
+        assert_eq!(projected.lits(), lits![4]);
+        assert_eq!(projected.blocking_clause(), lits![-4]);
+
+        assert!(Model::new().is_empty());
+        assert!(Model::new().project(&vars![1]).is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+//! Mapping between user provided names and variables.
+use std::collections::HashMap;
+
+use crate::cnf::ExtendFormula;
+use crate::lit::{Lit, Var};
+
+/// Bidirectional mapping between names and variables.
+///
+/// Variables for new names are allocated on demand using [`var`](VarNames::var). Each name
+/// refers to a single variable and each variable has at most one name.
+#[derive(Clone, Default, Debug)]
+pub struct VarNames {
+    vars: HashMap<String, Var>,
+    names: Vec<Option<String>>,
+}
+
+impl VarNames {
+    /// Create an empty mapping.
+    pub fn new() -> VarNames {
+        VarNames::default()
+    }
+
+    /// Return the variable with the given name, adding a new variable to the formula if the name
+    /// is not yet known.
+    pub fn var(&mut self, formula: &mut impl ExtendFormula, name: &str) -> Var {
+        if let Some(&var) = self.vars.get(name) {
+            return var;
+        }
+        let var = formula.new_var();
+        self.insert(name, var);
+        var
+    }
+
+    /// Return the positive literal of the variable with the given name, adding a new variable to
+    /// the formula if the name is not yet known.
+    pub fn lit(&mut self, formula: &mut impl ExtendFormula, name: &str) -> Lit {
+        self.var(formula, name).positive()
+    }
+
+    /// Assign a name to an existing variable.
+    ///
+    /// Any previous name of the variable and any previous variable of the name are unassigned.
+    pub fn insert(&mut self, name: &str, var: Var) {
+        if let Some(old_var) = self.vars.insert(name.to_owned(), var) {
+            self.names[old_var.index()] = None;
+        }
+        if self.names.len() <= var.index() {
+            self.names.resize(var.index() + 1, None);
+        }
+        if let Some(old_name) = self.names[var.index()].replace(name.to_owned()) {
+            if old_name != name {
+                self.vars.remove(&old_name);
+            }
+        }
+    }
+
+    /// The variable with the given name if present.
+    pub fn get(&self, name: &str) -> Option<Var> {
+        self.vars.get(name).cloned()
+    }
+
+    /// The name of a variable if present.
+    pub fn name(&self, var: Var) -> Option<&str> {
+        self.names
+            .get(var.index())
+            .and_then(|name| name.as_ref())
+            .map(|name| &name[..])
+    }
+
+    /// Number of named variables.
+    pub fn len(&self) -> usize {
+        self.vars.len()
+    }
+
+    /// Whether no variable has a name.
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    /// Iterator over all named variables and their names, ordered by variable index.
+    pub fn iter(&self) -> impl Iterator<Item = (Var, &str)> {
+        self.names.iter().enumerate().filter_map(|(index, name)| {
+            name.as_ref()
+                .map(|name| (Var::from_index(index), &name[..]))
+        })
+    }
+
+    /// Names and values of the named variables assigned by a model.
+    ///
+    /// Literals of unnamed variables are skipped.
+    pub fn assignment<'a>(
+        &'a self,
+        model: &'a [Lit],
+    ) -> impl Iterator<Item = (&'a str, bool)> + 'a {
+        model
+            .iter()
+            .filter_map(move |&lit| self.name(lit.var()).map(|name| (name, lit.is_positive())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cnf::CnfFormula;
+
+    #[test]
+    fn allocate_and_rename() {
+        let mut formula = CnfFormula::new();
+        let mut names = VarNames::new();
+
+        let x = names.var(&mut formula, "x");
+        let y = names.var(&mut formula, "y");
+        assert_eq!(names.var(&mut formula, "x"), x);
+        assert_eq!(formula.var_count(), 2);
+        assert_ne!(x, y);
+
+        assert_eq!(names.get("y"), Some(y));
+        assert_eq!(names.name(x), Some("x"));
+        assert_eq!(names.get("z"), None);
+
+        names.insert("z", x);
+        assert_eq!(names.get("x"), None);
+        assert_eq!(names.name(x), Some("z"));
+
+        names.insert("y", x);
+        assert_eq!(names.name(y), None);
+        assert_eq!(names.get("z"), None);
+        assert_eq!(names.len(), 1);
+
+        assert_eq!(names.iter().collect::<Vec<_>>(), vec![(x, "y")]);
+
+        assert_eq!(
+            names.assignment(&lits![-1, 2]).collect::<Vec<_>>(),
+            vec![("y", false)]
+        );
+    }
+}
@@ -0,0 +1,373 @@
+//! Pseudo-Boolean constraints and their encoding into CNF.
+use crate::cnf::{CnfFormula, ExtendFormula};
+use crate::encode::pb::{pb_constraint, PbEncoding};
+use crate::lit::{Lit, Var};
+
+/// Relation between the weighted sum of a constraint and its right hand side.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PbRelation {
+    /// The sum is at least the right hand side.
+    AtLeast,
+    /// The sum is equal to the right hand side.
+    Equal,
+    /// The sum is at most the right hand side.
+    AtMost,
+}
+
+/// A linear pseudo-Boolean constraint.
+///
+/// Each term contributes its coefficient to the sum when its literal is true.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PbConstraint {
+    pub terms: Vec<(i64, Lit)>,
+    pub relation: PbRelation,
+    pub rhs: i64,
+}
+
+impl PbConstraint {
+    /// Whether the constraint is satisfied by a full assignment.
+    ///
+    /// Literals missing from the assignment are treated as false.
+    pub fn is_satisfied(&self, assignment: &[Lit]) -> bool {
+        let sum = linear_sum(&self.terms, assignment);
+        let rhs = i128::from(self.rhs);
+        match self.relation {
+            PbRelation::AtLeast => sum >= rhs,
+            PbRelation::Equal => sum == rhs,
+            PbRelation::AtMost => sum <= rhs,
+        }
+    }
+}
+
+/// A pseudo-Boolean satisfaction or optimization problem.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct PbProblem {
+    /// Number of variables used by the constraints and the objective.
+    pub var_count: usize,
+    pub constraints: Vec<PbConstraint>,
+    /// Linear objective to minimize, if any.
+    pub objective: Option<Vec<(i64, Lit)>>,
+}
+
+impl PbProblem {
+    /// Encode all constraints into a CNF formula.
+    ///
+    /// The variables of the problem keep their index, auxiliary variables of the encoding come
+    /// after them. The objective is ignored, see [`bound_objective`](PbProblem::bound_objective).
+    pub fn to_cnf(&self) -> CnfFormula {
+        self.to_cnf_with(PbEncoding::Bdd)
+    }
+
+    /// Encode all constraints into a CNF formula using the given encoding.
+    ///
+    /// See [`to_cnf`](PbProblem::to_cnf).
+    pub fn to_cnf_with(&self, encoding: PbEncoding) -> CnfFormula {
+        let mut formula = CnfFormula::new();
+        formula.set_var_count(self.var_count);
+        for constraint in self.constraints.iter() {
+            pb_constraint(&mut formula, constraint, encoding);
+        }
+        formula
+    }
+
+    /// Value of the objective under a full assignment.
+    ///
+    /// Returns `None` if there is no objective or the value doesn't fit into an `i64`.
+    pub fn objective_value(&self, assignment: &[Lit]) -> Option<i64> {
+        let sum = linear_sum(self.objective.as_ref()?, assignment);
+        if sum >= i128::from(i64::min_value()) && sum <= i128::from(i64::max_value()) {
+            Some(sum as i64)
+        } else {
+            None
+        }
+    }
+
+    /// Add clauses requiring the objective to be at most `bound`.
+    ///
+    /// Solving with decreasing bounds finds an optimal solution. The formula must already
+    /// contain the variables of the problem, e.g. by being created using
+    /// [`to_cnf`](PbProblem::to_cnf). Does nothing if the problem has no objective.
+    pub fn bound_objective(&self, formula: &mut impl ExtendFormula, bound: i64) {
+        if let Some(objective) = &self.objective {
+            encode_pb_constraint(
+                formula,
+                &PbConstraint {
+                    terms: objective.clone(),
+                    relation: PbRelation::AtMost,
+                    rhs: bound,
+                },
+            );
+        }
+    }
+}
+
+/// A weighted Boolean optimization (WBO) problem.
+///
+/// Hard constraints must be satisfied, while the total cost of violated soft constraints is to be
+/// minimized.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct WboProblem {
+    /// Number of variables used by the constraints.
+    pub var_count: usize,
+    pub hard: Vec<PbConstraint>,
+    /// Soft constraints with the positive cost of violating them.
+    pub soft: Vec<(i64, PbConstraint)>,
+    /// Costs of at least this value are not acceptable, if present.
+    pub top: Option<i64>,
+}
+
+impl WboProblem {
+    /// Total cost of the soft constraints violated by a full assignment.
+    ///
+    /// Saturates at `i64::max_value()`.
+    pub fn cost(&self, assignment: &[Lit]) -> i64 {
+        self.soft
+            .iter()
+            .filter(|(_, constraint)| !constraint.is_satisfied(assignment))
+            .fold(0i64, |sum, &(cost, _)| sum.saturating_add(cost))
+    }
+
+    /// Convert into a pseudo-Boolean optimization problem.
+    ///
+    /// Each soft constraint gets a relaxation variable that satisfies the constraint when true.
+    /// The relaxation variable of the `i`-th soft constraint has the index `var_count + i`. The
+    /// objective is the weighted sum of the relaxation variables, and a top cost becomes a hard
+    /// upper bound on the objective. Minimizing the objective of the result minimizes the cost of
+    /// this problem.
+    pub fn to_pb_problem(&self) -> PbProblem {
+        let mut problem = PbProblem {
+            var_count: self.var_count + self.soft.len(),
+            constraints: self.hard.clone(),
+            objective: None,
+        };
+
+        let mut objective = vec![];
+
+        for (index, (cost, constraint)) in self.soft.iter().enumerate() {
+            let relax = Var::from_index(self.var_count + index).positive();
+            objective.push((*cost, relax));
+
+            let min_sum: i128 = constraint
+                .terms
+                .iter()
+                .map(|&(coefficient, _)| i128::from(coefficient.min(0)))
+                .sum();
+            let max_sum: i128 = constraint
+                .terms
+                .iter()
+                .map(|&(coefficient, _)| i128::from(coefficient.max(0)))
+                .sum();
+            let rhs = i128::from(constraint.rhs);
+
+            // A true relaxation variable shifts the sum just far enough to satisfy the relation
+            let relaxed = |relation, coefficient: i128| {
+                let coefficient = coefficient.max(i128::from(i64::min_value()));
+                let mut terms = constraint.terms.clone();
+                terms.push((coefficient.min(i128::from(i64::max_value())) as i64, relax));
+                PbConstraint {
+                    terms,
+                    relation,
+                    rhs: constraint.rhs,
+                }
+            };
+
+            if constraint.relation != PbRelation::AtMost {
+                let constraint = relaxed(PbRelation::AtLeast, (rhs - min_sum).max(0));
+                problem.constraints.push(constraint);
+            }
+            if constraint.relation != PbRelation::AtLeast {
+                let constraint = relaxed(PbRelation::AtMost, -(max_sum - rhs).max(0));
+                problem.constraints.push(constraint);
+            }
+        }
+
+        if let Some(top) = self.top {
+            problem.constraints.push(PbConstraint {
+                terms: objective.clone(),
+                relation: PbRelation::AtMost,
+                rhs: top - 1,
+            });
+        }
+
+        problem.objective = Some(objective);
+
+        problem
+    }
+}
+
+/// Weighted sum of the true literals.
+fn linear_sum(terms: &[(i64, Lit)], assignment: &[Lit]) -> i128 {
+    let var_count = terms.iter().map(|&(_, lit)| lit.index() + 1).max();
+    let mut values = vec![None; var_count.unwrap_or(0)];
+    for &lit in assignment.iter() {
+        if let Some(value) = values.get_mut(lit.index()) {
+            *value = Some(lit.is_positive());
+        }
+    }
+    terms
+        .iter()
+        .filter(|&&(_, lit)| values[lit.index()] == Some(lit.is_positive()))
+        .map(|&(coefficient, _)| i128::from(coefficient))
+        .sum()
+}
+
+/// Add clauses enforcing a pseudo-Boolean constraint.
+///
+/// New variables are allocated for the encoding using [`ExtendFormula::new_var`]. The
+/// constraint is encoded using a binary decision diagram, other encodings are available using
+/// [`encode::pb_constraint`](crate::encode::pb_constraint).
+pub fn encode_pb_constraint(formula: &mut impl ExtendFormula, constraint: &PbConstraint) {
+    pb_constraint(formula, constraint, PbEncoding::Bdd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::lit::Var;
+
+    /// Whether some extension of a total assignment of the first variables to the auxiliary
+    /// variables satisfies the formula.
+    fn is_extendable(formula: &CnfFormula, assignment: &[Lit]) -> bool {
+        let var_count = assignment.len();
+        let aux_count = formula.var_count() - var_count;
+        (0..1u32 << aux_count).any(|aux_bits| {
+            let mut full = assignment.to_vec();
+            full.extend(
+                (0..aux_count).map(|index| {
+                    Var::from_index(var_count + index).lit(aux_bits & (1 << index) != 0)
+                }),
+            );
+            formula.evaluate(&full).is_true()
+        })
+    }
+
+    /// Check an encoding against the constraint by enumerating all assignments of its variables.
+    fn check_encoding(var_count: usize, constraint: &PbConstraint) {
+        let mut formula = CnfFormula::new();
+        formula.set_var_count(var_count);
+        encode_pb_constraint(&mut formula, constraint);
+
+        for bits in 0..1u32 << var_count {
+            let assignment: Vec<Lit> = (0..var_count)
+                .map(|index| Var::from_index(index).lit(bits & (1 << index) != 0))
+                .collect();
+
+            assert_eq!(
+                is_extendable(&formula, &assignment),
+                constraint.is_satisfied(&assignment),
+                "{:?} under {:?}",
+                constraint,
+                assignment
+            );
+        }
+    }
+
+    #[test]
+    fn encodings_match_constraints() {
+        let terms = vec![
+            (3, lit!(1)),
+            (-2, lit!(2)),
+            (2, lit!(-3)),
+            (1, lit!(4)),
+            (5, lit!(2)),
+        ];
+
+        for &relation in [PbRelation::AtLeast, PbRelation::Equal, PbRelation::AtMost].iter() {
+            for rhs in -3..12 {
+                check_encoding(
+                    4,
+                    &PbConstraint {
+                        terms: terms.clone(),
+                        relation,
+                        rhs,
+                    },
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn objective() {
+        let problem = PbProblem {
+            var_count: 3,
+            constraints: vec![PbConstraint {
+                terms: vec![(1, lit!(1)), (1, lit!(2)), (1, lit!(3))],
+                relation: PbRelation::AtLeast,
+                rhs: 2,
+            }],
+            objective: Some(vec![(2, lit!(1)), (3, lit!(2)), (-1, lit!(-3))]),
+        };
+
+        assert_eq!(problem.objective_value(&lits![1, 2, -3]), Some(4));
+        assert_eq!(problem.objective_value(&lits![1, -2, 3]), Some(2));
+
+        let mut formula = problem.to_cnf();
+        problem.bound_objective(&mut formula, 2);
+        assert!(!is_extendable(&formula, &lits![1, 2, -3]));
+        assert!(is_extendable(&formula, &lits![1, -2, 3]));
+    }
+
+    #[test]
+    fn relaxed_soft_constraints() {
+        let constraint = |terms: Vec<(i64, Lit)>, relation, rhs| PbConstraint {
+            terms,
+            relation,
+            rhs,
+        };
+
+        let wbo = WboProblem {
+            var_count: 3,
+            hard: vec![constraint(
+                vec![(1, lit!(1)), (1, lit!(2)), (1, lit!(3))],
+                PbRelation::AtLeast,
+                1,
+            )],
+            soft: vec![
+                (2, constraint(vec![(1, lit!(-1))], PbRelation::AtLeast, 1)),
+                (
+                    3,
+                    constraint(vec![(2, lit!(2)), (-1, lit!(3))], PbRelation::Equal, 1),
+                ),
+                (
+                    4,
+                    constraint(vec![(3, lit!(1)), (1, lit!(3))], PbRelation::AtMost, 1),
+                ),
+            ],
+            top: Some(8),
+        };
+
+        let problem = wbo.to_pb_problem();
+        assert_eq!(problem.var_count, 6);
+
+        for bits in 0..1u32 << 3 {
+            let assignment: Vec<Lit> = (0..3)
+                .map(|index| Var::from_index(index).lit(bits & (1 << index) != 0))
+                .collect();
+
+            let feasible = wbo.hard.iter().all(|c| c.is_satisfied(&assignment));
+            let cost = wbo.cost(&assignment);
+
+            // The cheapest extension relaxes exactly the violated soft constraints
+            let best = (0..1u32 << 3)
+                .filter_map(|relax_bits| {
+                    let mut full = assignment.clone();
+                    full.extend((0..3).map(|index| {
+                        Var::from_index(3 + index).lit(relax_bits & (1 << index) != 0)
+                    }));
+                    if problem.constraints.iter().all(|c| c.is_satisfied(&full)) {
+                        problem.objective_value(&full)
+                    } else {
+                        None
+                    }
+                })
+                .min();
+
+            if feasible && cost < 8 {
+                assert_eq!(best, Some(cost), "{:?}", assignment);
+            } else {
+                assert_eq!(best, None, "{:?}", assignment);
+            }
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Serde support for literals, variables and formulas.
+//!
+//! Variables and literals are serialized as integers using the 1-based DIMACS CNF encoding. A
+//! [`CnfFormula`] is serialized as a struct containing its variable count and a sequence of
+//! clauses.
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::cnf::{CnfFormula, ExtendFormula};
+use crate::lit::{Lit, Var};
+
+impl Serialize for Var {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.to_dimacs() as i64)
+    }
+}
+
+impl Serialize for Lit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.to_dimacs() as i64)
+    }
+}
+
+/// Visitor accepting DIMACS encoded variables or literals.
+struct DimacsVisitor {
+    /// Whether negative numbers are accepted.
+    allow_negative: bool,
+}
+
+impl<'de> Visitor<'de> for DimacsVisitor {
+    type Value = isize;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.allow_negative {
+            write!(
+                formatter,
+                "a non-zero integer of magnitude at most {}",
+                Var::max_count()
+            )
+        } else {
+            write!(
+                formatter,
+                "a positive integer of at most {}",
+                Var::max_count()
+            )
+        }
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<isize, E> {
+        let in_range = match value.checked_abs() {
+            Some(abs) => abs as u64 <= Var::max_count() as u64,
+            None => false,
+        };
+        let valid = value != 0 && (self.allow_negative || value > 0) && in_range;
+        if valid {
+            Ok(value as isize)
+        } else {
+            Err(E::invalid_value(de::Unexpected::Signed(value), &self))
+        }
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<isize, E> {
+        if value != 0 && value <= Var::max_count() as u64 {
+            Ok(value as isize)
+        } else {
+            Err(E::invalid_value(de::Unexpected::Unsigned(value), &self))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Var {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Var, D::Error> {
+        let number = deserializer.deserialize_i64(DimacsVisitor {
+            allow_negative: false,
+        })?;
+        Ok(Var::from_dimacs(number))
+    }
+}
+
+impl<'de> Deserialize<'de> for Lit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Lit, D::Error> {
+        let number = deserializer.deserialize_i64(DimacsVisitor {
+            allow_negative: true,
+        })?;
+        Ok(Lit::from_dimacs(number))
+    }
+}
+
+/// Serializes the clauses of a formula as a sequence.
+struct Clauses<'a>(&'a CnfFormula);
+
+impl<'a> Serialize for Clauses<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter())
+    }
+}
+
+impl Serialize for CnfFormula {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("CnfFormula", 2)?;
+        state.serialize_field("var_count", &self.var_count())?;
+        state.serialize_field("clauses", &Clauses(self))?;
+        state.end()
+    }
+}
+
+/// Deserialized representation of a [`CnfFormula`].
+#[derive(serde::Deserialize)]
+#[serde(rename = "CnfFormula", deny_unknown_fields)]
+struct CnfFormulaData {
+    var_count: usize,
+    clauses: Vec<Vec<Lit>>,
+}
+
+impl<'de> Deserialize<'de> for CnfFormula {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<CnfFormula, D::Error> {
+        let data = CnfFormulaData::deserialize(deserializer)?;
+
+        if data.var_count > Var::max_count() {
+            return Err(de::Error::invalid_value(
+                de::Unexpected::Unsigned(data.var_count as u64),
+                &"a variable count supported by Var",
+            ));
+        }
+
+        let mut formula = CnfFormula::new();
+        formula.set_var_count(data.var_count);
+        for clause in data.clauses.iter() {
+            formula.add_clause(clause);
+        }
+        Ok(formula)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_roundtrip() {
+        let mut formula = cnf_formula![
+            1, -2;
+            ;
+            3;
+        ];
+        formula.set_var_count(5);
+
+        let json = serde_json::to_string(&formula).unwrap();
+        assert_eq!(json, r#"{"var_count":5,"clauses":[[1,-2],[],[3]]}"#);
+
+        let parsed: CnfFormula = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, formula);
+
+        assert_eq!(serde_json::to_string(&lit!(-7)).unwrap(), "-7");
+        assert_eq!(serde_json::from_str::<Var>("4").unwrap(), var!(4));
+    }
+
+    #[test]
+    fn invalid_values() {
+        assert!(serde_json::from_str::<Lit>("0").is_err());
+        assert!(serde_json::from_str::<Var>("-3").is_err());
+        assert!(serde_json::from_str::<Lit>("1.5").is_err());
+        assert!(serde_json::from_str::<Lit>(&(Var::max_count() + 1).to_string()).is_err());
+        assert!(serde_json::from_str::<CnfFormula>(r#"{"var_count":1,"clauses":[[0]]}"#).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+//! Simplification of CNF formulas without a solver.
+use crate::cnf::{CnfFormula, ExtendFormula};
+use crate::lit::{Lit, Var};
+
+/// Values of eliminated variables, used to extend a model of a simplified formula.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct ModelExtension {
+    fixed: Vec<Lit>,
+}
+
+impl ModelExtension {
+    /// Literals fixed by the simplification, in the order they were fixed.
+    pub fn fixed_lits(&self) -> &[Lit] {
+        &self.fixed
+    }
+
+    /// Extend a model of the simplified formula to a model of the original formula.
+    ///
+    /// The eliminated variables don't occur in the simplified formula, so any value the model
+    /// assigns to them is replaced. The returned model is ordered by variable and contains only
+    /// the variables assigned by the model or the extension.
+    pub fn extend(&self, model: &[Lit]) -> Vec<Lit> {
+        let var_count = model
+            .iter()
+            .chain(self.fixed.iter())
+            .map(|lit| lit.index() + 1)
+            .max()
+            .unwrap_or(0);
+
+        let mut values = vec![None; var_count];
+        for &lit in model.iter().chain(self.fixed.iter()) {
+            values[lit.index()] = Some(lit.is_positive());
+        }
+
+        values
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, value)| value.map(|value| Var::from_index(index).lit(value)))
+            .collect()
+    }
+}
+
+/// Simplify a formula using unit propagation and pure literal elimination.
+///
+/// Tautological clauses, repeated literals within a clause and repeated clauses are removed.
+/// Unit clauses and pure literals are repeatedly assigned, removing satisfied clauses and false
+/// literals, until neither remain.
+///
+/// Variables keep their index and the simplified formula has the same variable count. The
+/// returned extension maps models of the simplified formula to models of the original formula.
+/// If propagation finds a conflict, the simplified formula consists of a single empty clause.
+pub fn simplify_cnf(formula: &CnfFormula) -> (CnfFormula, ModelExtension) {
+    let mut clauses: Vec<Vec<Lit>> = vec![];
+
+    for clause in formula.iter() {
+        let mut clause = clause.to_vec();
+        clause.sort_unstable();
+        clause.dedup();
+        // Sorting places both polarities of a variable next to each other
+        if clause.windows(2).any(|pair| pair[0] == !pair[1]) {
+            continue;
+        }
+        clauses.push(clause);
+    }
+
+    let mut values: Vec<Option<bool>> = vec![None; formula.var_count()];
+    let mut extension = ModelExtension::default();
+
+    loop {
+        let mut changed = false;
+
+        // Unit propagation, removing satisfied clauses and false literals
+        let mut remaining = Vec::with_capacity(clauses.len());
+        for mut clause in clauses.drain(..) {
+            if clause
+                .iter()
+                .any(|lit| values[lit.index()] == Some(lit.is_positive()))
+            {
+                changed = true;
+                continue;
+            }
+            clause.retain(|lit| values[lit.index()].is_none());
+            match clause[..] {
+                [] => return unsat(formula.var_count(), extension),
+                [lit] => {
+                    values[lit.index()] = Some(lit.is_positive());
+                    extension.fixed.push(lit);
+                    changed = true;
+                }
+                _ => remaining.push(clause),
+            }
+        }
+        clauses = remaining;
+
+        if changed {
+            continue;
+        }
+
+        // Pure literal elimination
+        let mut occurs = vec![[false; 2]; formula.var_count()];
+        for &lit in clauses.iter().flat_map(|clause| clause.iter()) {
+            occurs[lit.index()][lit.is_positive() as usize] = true;
+        }
+        for (index, &[negative, positive]) in occurs.iter().enumerate() {
+            if negative != positive {
+                values[index] = Some(positive);
+                extension.fixed.push(Var::from_index(index).lit(positive));
+                changed = true;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    let mut simplified = CnfFormula::new();
+    simplified.set_var_count(formula.var_count());
+    for clause in clauses {
+        simplified.add_clause(&clause);
+    }
+    simplified.dedup_clauses();
+
+    (simplified, extension)
+}
+
+/// Simplified formula of an unsatisfiable input.
+fn unsat(var_count: usize, extension: ModelExtension) -> (CnfFormula, ModelExtension) {
+    let mut formula = CnfFormula::new();
+    formula.set_var_count(var_count);
+    formula.add_clause(&[]);
+    (formula, extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proptest::prelude::*;
+
+    use crate::cnf::strategy::*;
+
+    #[test]
+    fn propagation_and_pure_literals() {
+        let formula = cnf_formula![
+            1;
+            -1, 2, 3;
+            -2, 4, 5;
+            -2, -4, 5;
+            -5, 6, -3;
+            3, 3, 6;
+            4, -4, 7;
+            -6, -5, 3;
+            6, 5, -2;
+        ];
+
+        let (simplified, extension) = simplify_cnf(&formula);
+
+        assert_eq!(extension.fixed_lits()[0], lit!(1));
+
+        for clause in simplified.iter() {
+            assert!(clause.len() >= 2);
+        }
+
+        for &model in [
+            &lits![1, 2, 3, -4, 5, 6, 7][..],
+            &lits![-1, -2, 3, 4, -5, 6, -7],
+        ]
+        .iter()
+        {
+            assert!(simplified.evaluate(model).is_true());
+            assert!(formula.evaluate(&extension.extend(model)).is_true());
+        }
+    }
+
+    #[test]
+    fn conflict() {
+        let formula = cnf_formula![
+            1, 2;
+            -1;
+            -2, 3;
+            -3, -2;
+        ];
+
+        let (simplified, _) = simplify_cnf(&formula);
+
+        let mut expected = CnfFormula::new();
+        expected.set_var_count(3);
+        expected.add_clause(&[]);
+
+        assert_eq!(simplified, expected);
+    }
+
+    proptest! {
+        #[test]
+        fn models_extend(formula in cnf_formula(1..8usize, 0..20, 1..4)) {
+            let (simplified, extension) = simplify_cnf(&formula);
+
+            let var_count = formula.var_count();
+            for bits in 0..1u32 << var_count {
+                let model: Vec<Lit> = (0..var_count)
+                    .map(|index| Var::from_index(index).lit(bits & (1 << index) != 0))
+                    .collect();
+
+                if simplified.evaluate(&model).is_true() {
+                    prop_assert!(formula.evaluate(&extension.extend(&model)).is_true());
+                }
+                if formula.evaluate(&model).is_true() {
+                    // The formula is satisfiable, so the simplified formula must be too
+                    prop_assert!(simplified.iter().all(|clause| !clause.is_empty()));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Extraction of subformulas.
+use crate::cnf::{CnfFormula, ExtendFormula};
+use crate::lit::{Lit, Var};
+
+/// Clauses extracted from a formula with their variables renamed to a contiguous range.
+#[derive(Debug)]
+pub struct Subformula {
+    /// The extracted clauses using the renamed variables.
+    pub formula: CnfFormula,
+    /// Indices of the extracted clauses in the original formula, in increasing order.
+    pub clauses: Vec<usize>,
+    to_original: Vec<Var>,
+    from_original: Vec<Option<Var>>,
+}
+
+impl Subformula {
+    /// The variable of the original formula corresponding to a variable of the subformula.
+    pub fn original_var(&self, var: Var) -> Var {
+        self.to_original[var.index()]
+    }
+
+    /// The literal of the original formula corresponding to a literal of the subformula.
+    pub fn original_lit(&self, lit: Lit) -> Lit {
+        lit.map_var(|var| self.original_var(var))
+    }
+
+    /// The variable of the subformula corresponding to a variable of the original formula.
+    ///
+    /// Returns `None` if the variable is not part of the subformula.
+    pub fn renamed_var(&self, var: Var) -> Option<Var> {
+        self.from_original.get(var.index()).cloned().unwrap_or(None)
+    }
+
+    /// Variables of the original formula that are part of the subformula, in increasing order.
+    ///
+    /// The variable with index `i` in the subformula corresponds to the `i`-th variable of this
+    /// list.
+    pub fn original_vars(&self) -> &[Var] {
+        &self.to_original
+    }
+}
+
+/// Extract the syntactic cone of influence of the given variables.
+///
+/// The cone contains every clause that is connected to a seed variable by a chain of clauses
+/// where neighbouring clauses share a variable. The variables of the cone, including all seeds,
+/// are renumbered in increasing order of their original index.
+pub fn cone_of_influence(formula: &CnfFormula, seeds: &[Var]) -> Subformula {
+    let var_count = seeds
+        .iter()
+        .map(|var| var.index() + 1)
+        .max()
+        .unwrap_or(0)
+        .max(formula.var_count());
+
+    let mut occurrences: Vec<Vec<usize>> = vec![vec![]; var_count];
+    for (index, clause) in formula.iter().enumerate() {
+        for &lit in clause.iter() {
+            occurrences[lit.index()].push(index);
+        }
+    }
+
+    let mut var_reached = vec![false; var_count];
+    let mut clause_reached = vec![false; formula.len()];
+    let mut queue = vec![];
+
+    for &var in seeds.iter() {
+        if !var_reached[var.index()] {
+            var_reached[var.index()] = true;
+            queue.push(var);
+        }
+    }
+
+    while let Some(var) = queue.pop() {
+        for &index in occurrences[var.index()].iter() {
+            if clause_reached[index] {
+                continue;
+            }
+            clause_reached[index] = true;
+            for &lit in formula.clause(index).iter() {
+                if !var_reached[lit.index()] {
+                    var_reached[lit.index()] = true;
+                    queue.push(lit.var());
+                }
+            }
+        }
+    }
+
+    let mut to_original = vec![];
+    let mut from_original = vec![None; var_count];
+    for (index, &reached) in var_reached.iter().enumerate() {
+        if reached {
+            from_original[index] = Some(Var::from_index(to_original.len()));
+            to_original.push(Var::from_index(index));
+        }
+    }
+
+    let mut subformula = CnfFormula::new();
+    subformula.set_var_count(to_original.len());
+    let mut clauses = vec![];
+    let mut renamed = vec![];
+
+    for (index, clause) in formula.iter().enumerate() {
+        if clause_reached[index] {
+            renamed.clear();
+            renamed.extend(
+                clause
+                    .iter()
+                    .map(|lit| lit.map_var(|var| from_original[var.index()].unwrap())),
+            );
+            subformula.add_clause(&renamed);
+            clauses.push(index);
+        }
+    }
+
+    Subformula {
+        formula: subformula,
+        clauses,
+        to_original,
+        from_original,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_cone() {
+        let formula = cnf_formula![
+            1, -2;
+            3, 4;
+            2, 5;
+            -4, 6;
+            7;
+            -5, -1;
+        ];
+
+        let cone = cone_of_influence(&formula, &vars![2, 8]);
+
+        assert_eq!(cone.clauses, vec![0, 2, 5]);
+        assert_eq!(cone.original_vars(), &vars![1, 2, 5, 8]);
+        let mut expected = cnf_formula![
+            1, -2;
+            2, 3;
+            -3, -1;
+        ];
+        expected.set_var_count(4);
+        assert_eq!(cone.formula, expected);
+        assert_eq!(cone.renamed_var(var!(5)), Some(var!(3)));
+        assert_eq!(cone.renamed_var(var!(8)), Some(var!(4)));
+        assert_eq!(cone.renamed_var(var!(3)), None);
+        assert_eq!(cone.original_lit(lit!(-3)), lit!(-5));
+
+        for (clause, &index) in cone.formula.iter().zip(cone.clauses.iter()) {
+            let original: Vec<Lit> = clause.iter().map(|&lit| cone.original_lit(lit)).collect();
+            assert_eq!(&original[..], formula.clause(index));
+        }
+
+        let cone = cone_of_influence(&formula, &[]);
+        assert_eq!(cone.formula.len(), 0);
+        assert!(cone.original_vars().is_empty());
+    }
+}
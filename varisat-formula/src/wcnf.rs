@@ -0,0 +1,135 @@
+//! Weighted CNF formulas for MaxSAT.
+use std::cmp::max;
+
+use crate::cnf::{CnfFormula, ExtendFormula};
+use crate::lit::{Lit, Var};
+
+/// A weighted CNF formula consisting of hard and weighted soft clauses.
+///
+/// Hard clauses must be satisfied, while the total weight of falsified soft clauses is to be
+/// minimized. Clauses added using [`ExtendFormula::add_clause`] are hard clauses.
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct WcnfFormula {
+    hard: CnfFormula,
+    soft: CnfFormula,
+    weights: Vec<u64>,
+}
+
+impl WcnfFormula {
+    /// Create an empty weighted CNF formula.
+    pub fn new() -> WcnfFormula {
+        WcnfFormula::default()
+    }
+
+    /// Number of variables in the formula.
+    ///
+    /// This counts variables of hard and soft clauses.
+    pub fn var_count(&self) -> usize {
+        max(self.hard.var_count(), self.soft.var_count())
+    }
+
+    /// Increase the number of variables in the formula.
+    ///
+    /// If the parameter is less than the current variable count do nothing.
+    pub fn set_var_count(&mut self, count: usize) {
+        self.hard.set_var_count(count);
+        self.soft.set_var_count(count);
+    }
+
+    /// The hard clauses.
+    pub fn hard(&self) -> &CnfFormula {
+        &self.hard
+    }
+
+    /// The soft clauses without their weights.
+    ///
+    /// Weights are available from [`soft_iter`](WcnfFormula::soft_iter) and
+    /// [`weights`](WcnfFormula::weights).
+    pub fn soft(&self) -> &CnfFormula {
+        &self.soft
+    }
+
+    /// The weights of the soft clauses, in the order the clauses were added.
+    pub fn weights(&self) -> &[u64] {
+        &self.weights
+    }
+
+    /// Iterator over all soft clauses together with their weights.
+    pub fn soft_iter(&self) -> impl Iterator<Item = (u64, &[Lit])> {
+        self.weights.iter().cloned().zip(self.soft.iter())
+    }
+
+    /// Appends a soft clause with the given weight.
+    pub fn add_soft_clause(&mut self, weight: u64, literals: &[Lit]) {
+        self.soft.add_clause(literals);
+        self.weights.push(weight);
+    }
+
+    /// Total weight of all soft clauses.
+    ///
+    /// Returns `None` on overflow.
+    pub fn total_soft_weight(&self) -> Option<u64> {
+        self.weights
+            .iter()
+            .try_fold(0u64, |sum, &weight| sum.checked_add(weight))
+    }
+
+    /// Total weight of the soft clauses not satisfied by an assignment.
+    ///
+    /// Clauses that are neither satisfied nor falsified by a partial assignment count as not
+    /// satisfied. The weight saturates at `u64::max_value()`.
+    pub fn cost(&self, assignment: &[Lit]) -> u64 {
+        let mut values = vec![None; self.var_count()];
+        for &lit in assignment.iter() {
+            if let Some(value) = values.get_mut(lit.index()) {
+                *value = Some(lit.is_positive());
+            }
+        }
+
+        self.soft_iter()
+            .filter(|(_, clause)| {
+                !clause.iter().any(|&lit| {
+                    values.get(lit.index()).and_then(|&value| value) == Some(lit.is_positive())
+                })
+            })
+            .fold(0u64, |sum, (weight, _)| sum.saturating_add(weight))
+    }
+}
+
+impl ExtendFormula for WcnfFormula {
+    fn add_clause(&mut self, literals: &[Lit]) {
+        self.hard.add_clause(literals);
+    }
+
+    fn new_var(&mut self) -> Var {
+        let var = Var::from_index(self.var_count());
+        self.set_var_count(var.index() + 1);
+        var
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soft_clauses_and_cost() {
+        let mut formula = WcnfFormula::new();
+        formula.add_clause(&lits![1, 2]);
+        formula.add_soft_clause(3, &lits![-1]);
+        formula.add_soft_clause(5, &lits![-2, 4]);
+
+        assert_eq!(formula.var_count(), 4);
+        assert_eq!(formula.hard().len(), 1);
+        assert_eq!(formula.weights(), &[3, 5]);
+        assert_eq!(formula.total_soft_weight(), Some(8));
+
+        assert_eq!(formula.cost(&lits![1, -2]), 3);
+        assert_eq!(formula.cost(&lits![-1, 2, -4]), 5);
+        assert_eq!(formula.cost(&lits![-1, 2, 4]), 0);
+
+        let var = formula.new_var();
+        assert_eq!(var.index(), 4);
+        assert_eq!(formula.var_count(), 5);
+    }
+}
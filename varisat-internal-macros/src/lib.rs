@@ -92,6 +92,7 @@ fn derive_config_update(s: synstructure::Structure) -> TokenStream {
 
     let ident = &s.ast().ident;
     let update_struct_ident = Ident::new(&format!("{}Update", ident), ident.span());
+    let error_ident = Ident::new(&format!("{}Error", ident), ident.span());
 
     let vis = &s.ast().vis;
 
@@ -111,17 +112,20 @@ fn derive_config_update(s: synstructure::Structure) -> TokenStream {
             if let Some(range) = get_doc_field("range", &field.attrs) {
                 // TODO use toml instead of fmt::Debug for errors?
                 let ident = &field.ident;
-                let error_msg = format!(
-                    "{} must be in range {} but was set to {{:?}}",
-                    quote!(#ident),
-                    range.value()
-                );
+                let option = quote!(#ident).to_string();
+                let range_str = range.value();
                 let range = range
                     .parse::<TokenStream>()
                     .expect("error parsing range expression");
                 quote! {
                     if let Some(value) = &self.#ident {
-                        failure::ensure!((#range).contains(value), #error_msg, value);
+                        if !(#range).contains(value) {
+                            return Err(#error_ident::OutOfRange {
+                                option: #option,
+                                range: #range_str,
+                                value: format!("{:?}", value),
+                            });
+                        }
                     }
                 }
             } else {
@@ -170,6 +174,7 @@ fn derive_config_update(s: synstructure::Structure) -> TokenStream {
     }
 
     let doc = format!("Updates configuration values of [`{}`].", ident);
+    let error_doc = format!("Invalid update of a [`{}`].", ident);
 
     quote! {
         #[doc = #doc]
@@ -179,6 +184,18 @@ fn derive_config_update(s: synstructure::Structure) -> TokenStream {
             #update_struct_body
         }
 
+        #[doc = #error_doc]
+        #[derive(Debug, thiserror::Error)]
+        #[non_exhaustive]
+        #vis enum #error_ident {
+            #[error("{option} must be in range {range} but was set to {value}")]
+            OutOfRange {
+                option: &'static str,
+                range: &'static str,
+                value: String,
+            },
+        }
+
         impl #ident {
             /// Return a string describing all supported configuration options.
             pub fn help() -> &'static str {
@@ -195,7 +212,7 @@ fn derive_config_update(s: synstructure::Structure) -> TokenStream {
             /// Apply the configuration update.
             ///
             /// If an error occurs, the configuration is not changed.
-            pub fn apply(&self, config: &mut #ident) -> Result<(), failure::Error> {
+            pub fn apply(&self, config: &mut #ident) -> Result<(), #error_ident> {
                 #check_ranges
                 #apply_updates
                 Ok(())
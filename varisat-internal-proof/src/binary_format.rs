@@ -1,13 +1,13 @@
 //! Binary format for varisat proofs.
-use std::io::{self, BufRead, Write};
+use std::io::{self, BufRead, Read, Write};
 
-use failure::Error;
+use thiserror::Error;
 
 use varisat_formula::{Lit, Var};
 
-use crate::vli_enc::{read_u64, write_u64};
+use crate::vli_enc::{read_u64, read_u64s_with, write_u64, write_u64_iter};
 
-use super::{ClauseHash, DeleteClauseProof, ProofStep};
+use super::{ClauseHash, DeleteClauseProof, ProofStep, SolveResult};
 
 macro_rules! step_codes {
     ($counter:expr, $name:ident, ) => {
@@ -39,12 +39,127 @@ step_codes!(
     CODE_ADD_CLAUSE,
     CODE_ASSUMPTIONS,
     CODE_FAILED_ASSUMPTIONS,
+    CODE_RAT_CLAUSE,
+    CODE_PR_CLAUSE,
+    CODE_SOLVE_RESULT_SAT,
+    CODE_SOLVE_RESULT_UNSAT,
+    CODE_SOLVE_RESULT_UNKNOWN,
+    CODE_DELETE_HASHED_CLAUSE_REDUNDANT,
+    CODE_DELETE_HASHED_CLAUSE_SIMPLIFIED,
+    CODE_DELETE_HASHED_CLAUSE_SATISFIED,
+    CODE_EXTENSION,
+    CODE_DELETE_RAT_CLAUSE,
+    CODE_BLOCKED_CLAUSE,
+    CODE_DELETE_BLOCKED_CLAUSE,
+    CODE_CHECKPOINT,
 );
 
 // Using a random value here makes it unlikely that a corrupted proof will be silently truncated and
 // accepted
 const CODE_END: u64 = 0x9ac3391f4294c211;
 
+/// Magic number at the start of every varisat proof.
+pub const MAGIC: [u8; 8] = *b"varisat\xf0";
+
+/// Version of the proof format written by this crate.
+///
+/// Proofs using a different version are rejected by the parser.
+pub const FORMAT_VERSION: u64 = 2;
+
+/// Errors while reading a proof header.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum HeaderError {
+    #[error("missing magic number, this is not a varisat proof")]
+    MissingMagic,
+    #[error(
+        "unsupported proof format version {}, expected version {}",
+        version,
+        FORMAT_VERSION
+    )]
+    UnsupportedVersion { version: u64 },
+    #[error("metadata is not valid UTF-8")]
+    InvalidMetadata,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Errors while parsing a proof step.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum StepError {
+    #[error("unknown step code {}", code)]
+    UnknownStep { code: u64 },
+    #[error("expected a single clause hash")]
+    ExpectedSingleHash,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Header at the start of a varisat proof.
+#[derive(Clone, Default, Debug)]
+pub struct ProofHeader {
+    /// Version of the proof format.
+    pub version: u64,
+    /// Key value pairs describing the proof, e.g. the generating solver.
+    pub metadata: Vec<(String, String)>,
+}
+
+/// Writes the header of a varisat proof.
+pub fn write_header(target: &mut impl Write, metadata: &[(&str, &str)]) -> io::Result<()> {
+    target.write_all(&MAGIC)?;
+    write_u64(&mut *target, FORMAT_VERSION)?;
+    write_u64(&mut *target, metadata.len() as u64)?;
+    for &(key, value) in metadata.iter() {
+        write_string(&mut *target, key)?;
+        write_string(&mut *target, value)?;
+    }
+    Ok(())
+}
+
+/// Reads and validates the header of a varisat proof.
+///
+/// Reading errors are returned as [`HeaderError::Io`].
+pub fn read_header(source: &mut impl BufRead) -> Result<ProofHeader, HeaderError> {
+    let mut magic = [0; 8];
+    source.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err(HeaderError::MissingMagic);
+    }
+
+    let version = read_u64(&mut *source)?;
+    if version != FORMAT_VERSION {
+        return Err(HeaderError::UnsupportedVersion { version });
+    }
+
+    let len = read_u64(&mut *source)?;
+    let mut metadata = vec![];
+    for _ in 0..len {
+        let key = read_string(&mut *source)?;
+        let value = read_string(&mut *source)?;
+        metadata.push((key, value));
+    }
+
+    Ok(ProofHeader { version, metadata })
+}
+
+/// Writes the length and bytes of a string.
+fn write_string(target: &mut impl Write, string: &str) -> io::Result<()> {
+    write_u64(&mut *target, string.len() as u64)?;
+    target.write_all(string.as_bytes())
+}
+
+/// Reads a string written by `write_string`.
+fn read_string(source: &mut impl BufRead) -> Result<String, HeaderError> {
+    let len = read_u64(&mut *source)?;
+    let mut bytes = vec![];
+    (&mut *source).take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    String::from_utf8(bytes).map_err(|_| HeaderError::InvalidMetadata)
+}
+
 /// Writes a proof step in the varisat format
 pub fn write_step<'s>(target: &mut impl Write, step: &'s ProofStep<'s>) -> io::Result<()> {
     match *step {
@@ -103,6 +218,73 @@ pub fn write_step<'s>(target: &mut impl Write, step: &'s ProofStep<'s>) -> io::R
             write_hashes(&mut *target, propagation_hashes)?;
         }
 
+        ProofStep::RatClause {
+            clause,
+            pivot,
+            resolutions,
+            propagation_hashes,
+        } => {
+            write_u64(&mut *target, CODE_RAT_CLAUSE)?;
+            write_literals(&mut *target, clause)?;
+            write_u64(&mut *target, pivot.code() as u64)?;
+            write_resolutions(&mut *target, resolutions)?;
+            write_hashes(&mut *target, propagation_hashes)?;
+        }
+
+        ProofStep::PrClause {
+            clause,
+            witness,
+            reduced_clauses,
+            propagation_hashes,
+        } => {
+            write_u64(&mut *target, CODE_PR_CLAUSE)?;
+            write_literals(&mut *target, clause)?;
+            write_literals(&mut *target, witness)?;
+            write_resolutions(&mut *target, reduced_clauses)?;
+            write_hashes(&mut *target, propagation_hashes)?;
+        }
+
+        ProofStep::DeleteRatClause {
+            clause,
+            pivot,
+            resolutions,
+            propagation_hashes,
+        } => {
+            write_u64(&mut *target, CODE_DELETE_RAT_CLAUSE)?;
+            write_literals(&mut *target, clause)?;
+            write_u64(&mut *target, pivot.code() as u64)?;
+            write_resolutions(&mut *target, resolutions)?;
+            write_hashes(&mut *target, propagation_hashes)?;
+        }
+
+        ProofStep::BlockedClause {
+            clause,
+            pivot,
+            blocking,
+        } => {
+            write_u64(&mut *target, CODE_BLOCKED_CLAUSE)?;
+            write_literals(&mut *target, clause)?;
+            write_u64(&mut *target, pivot.code() as u64)?;
+            write_hashes(&mut *target, blocking)?;
+        }
+
+        ProofStep::DeleteBlockedClause {
+            clause,
+            pivot,
+            blocking,
+        } => {
+            write_u64(&mut *target, CODE_DELETE_BLOCKED_CLAUSE)?;
+            write_literals(&mut *target, clause)?;
+            write_u64(&mut *target, pivot.code() as u64)?;
+            write_hashes(&mut *target, blocking)?;
+        }
+
+        ProofStep::Extension { lit, definition } => {
+            write_u64(&mut *target, CODE_EXTENSION)?;
+            write_u64(&mut *target, lit.code() as u64)?;
+            write_literals(&mut *target, definition)?;
+        }
+
         ProofStep::UnitClauses { units } => {
             write_u64(&mut *target, CODE_UNIT_CLAUSES)?;
             write_unit_clauses(&mut *target, units)?;
@@ -124,6 +306,22 @@ pub fn write_step<'s>(target: &mut impl Write, step: &'s ProofStep<'s>) -> io::R
             write_literals(&mut *target, clause)?;
         }
 
+        ProofStep::DeleteHashedClause { hash, proof } => {
+            match proof {
+                DeleteClauseProof::Redundant => {
+                    write_u64(&mut *target, CODE_DELETE_HASHED_CLAUSE_REDUNDANT)?;
+                }
+                DeleteClauseProof::Simplified => {
+                    write_u64(&mut *target, CODE_DELETE_HASHED_CLAUSE_SIMPLIFIED)?;
+                }
+                DeleteClauseProof::Satisfied => {
+                    write_u64(&mut *target, CODE_DELETE_HASHED_CLAUSE_SATISFIED)?;
+                }
+            }
+
+            write_hashes(&mut *target, &[hash])?;
+        }
+
         ProofStep::ChangeHashBits { bits } => {
             write_u64(&mut *target, CODE_CHANGE_HASH_BITS)?;
             write_u64(&mut *target, bits as u64)?;
@@ -148,6 +346,21 @@ pub fn write_step<'s>(target: &mut impl Write, step: &'s ProofStep<'s>) -> io::R
             write_hashes(&mut *target, propagation_hashes)?;
         }
 
+        ProofStep::SolveResult { result } => {
+            write_u64(
+                &mut *target,
+                match result {
+                    SolveResult::Sat => CODE_SOLVE_RESULT_SAT,
+                    SolveResult::Unsat => CODE_SOLVE_RESULT_UNSAT,
+                    SolveResult::Unknown => CODE_SOLVE_RESULT_UNKNOWN,
+                },
+            )?;
+        }
+
+        ProofStep::Checkpoint => {
+            write_u64(&mut *target, CODE_CHECKPOINT)?;
+        }
+
         ProofStep::End => {
             write_u64(&mut *target, CODE_END)?;
         }
@@ -159,12 +372,17 @@ pub fn write_step<'s>(target: &mut impl Write, step: &'s ProofStep<'s>) -> io::R
 #[derive(Default)]
 pub struct Parser {
     lit_buf: Vec<Lit>,
+    witness_buf: Vec<Lit>,
     hash_buf: Vec<ClauseHash>,
     unit_buf: Vec<(Lit, ClauseHash)>,
+    resolution_buf: Vec<(ClauseHash, usize)>,
 }
 
 impl Parser {
-    pub fn parse_step<'a>(&'a mut self, source: &mut impl BufRead) -> Result<ProofStep<'a>, Error> {
+    pub fn parse_step<'a>(
+        &'a mut self,
+        source: &mut impl BufRead,
+    ) -> Result<ProofStep<'a>, StepError> {
         let code = read_u64(&mut *source)?;
         match code {
             CODE_SOLVER_VAR_NAME_UPDATE => {
@@ -214,6 +432,68 @@ impl Parser {
                     propagation_hashes: &self.hash_buf,
                 })
             }
+            CODE_RAT_CLAUSE => {
+                read_literals(&mut *source, &mut self.lit_buf)?;
+                let pivot = Lit::from_code(read_u64(&mut *source)? as usize);
+                read_resolutions(&mut *source, &mut self.resolution_buf)?;
+                read_hashes(&mut *source, &mut self.hash_buf)?;
+                Ok(ProofStep::RatClause {
+                    clause: &self.lit_buf,
+                    pivot,
+                    resolutions: &self.resolution_buf,
+                    propagation_hashes: &self.hash_buf,
+                })
+            }
+            CODE_PR_CLAUSE => {
+                read_literals(&mut *source, &mut self.lit_buf)?;
+                read_literals(&mut *source, &mut self.witness_buf)?;
+                read_resolutions(&mut *source, &mut self.resolution_buf)?;
+                read_hashes(&mut *source, &mut self.hash_buf)?;
+                Ok(ProofStep::PrClause {
+                    clause: &self.lit_buf,
+                    witness: &self.witness_buf,
+                    reduced_clauses: &self.resolution_buf,
+                    propagation_hashes: &self.hash_buf,
+                })
+            }
+            CODE_DELETE_RAT_CLAUSE => {
+                read_literals(&mut *source, &mut self.lit_buf)?;
+                let pivot = Lit::from_code(read_u64(&mut *source)? as usize);
+                read_resolutions(&mut *source, &mut self.resolution_buf)?;
+                read_hashes(&mut *source, &mut self.hash_buf)?;
+                Ok(ProofStep::DeleteRatClause {
+                    clause: &self.lit_buf,
+                    pivot,
+                    resolutions: &self.resolution_buf,
+                    propagation_hashes: &self.hash_buf,
+                })
+            }
+            CODE_BLOCKED_CLAUSE | CODE_DELETE_BLOCKED_CLAUSE => {
+                read_literals(&mut *source, &mut self.lit_buf)?;
+                let pivot = Lit::from_code(read_u64(&mut *source)? as usize);
+                read_hashes(&mut *source, &mut self.hash_buf)?;
+                if code == CODE_BLOCKED_CLAUSE {
+                    Ok(ProofStep::BlockedClause {
+                        clause: &self.lit_buf,
+                        pivot,
+                        blocking: &self.hash_buf,
+                    })
+                } else {
+                    Ok(ProofStep::DeleteBlockedClause {
+                        clause: &self.lit_buf,
+                        pivot,
+                        blocking: &self.hash_buf,
+                    })
+                }
+            }
+            CODE_EXTENSION => {
+                let lit = Lit::from_code(read_u64(&mut *source)? as usize);
+                read_literals(&mut *source, &mut self.lit_buf)?;
+                Ok(ProofStep::Extension {
+                    lit,
+                    definition: &self.lit_buf,
+                })
+            }
             CODE_UNIT_CLAUSES => {
                 read_unit_clauses(&mut *source, &mut self.unit_buf)?;
                 Ok(ProofStep::UnitClauses {
@@ -235,6 +515,24 @@ impl Parser {
                     proof,
                 })
             }
+            CODE_DELETE_HASHED_CLAUSE_REDUNDANT
+            | CODE_DELETE_HASHED_CLAUSE_SIMPLIFIED
+            | CODE_DELETE_HASHED_CLAUSE_SATISFIED => {
+                let proof = match code {
+                    CODE_DELETE_HASHED_CLAUSE_REDUNDANT => DeleteClauseProof::Redundant,
+                    CODE_DELETE_HASHED_CLAUSE_SIMPLIFIED => DeleteClauseProof::Simplified,
+                    CODE_DELETE_HASHED_CLAUSE_SATISFIED => DeleteClauseProof::Satisfied,
+                    _ => unreachable!(),
+                };
+                read_hashes(&mut *source, &mut self.hash_buf)?;
+                if self.hash_buf.len() != 1 {
+                    return Err(StepError::ExpectedSingleHash);
+                }
+                Ok(ProofStep::DeleteHashedClause {
+                    hash: self.hash_buf[0],
+                    proof,
+                })
+            }
             CODE_CHANGE_HASH_BITS => {
                 let bits = read_u64(&mut *source)? as u32;
                 Ok(ProofStep::ChangeHashBits { bits })
@@ -259,8 +557,18 @@ impl Parser {
                     propagation_hashes: &self.hash_buf,
                 })
             }
+            CODE_SOLVE_RESULT_SAT => Ok(ProofStep::SolveResult {
+                result: SolveResult::Sat,
+            }),
+            CODE_SOLVE_RESULT_UNSAT => Ok(ProofStep::SolveResult {
+                result: SolveResult::Unsat,
+            }),
+            CODE_SOLVE_RESULT_UNKNOWN => Ok(ProofStep::SolveResult {
+                result: SolveResult::Unknown,
+            }),
+            CODE_CHECKPOINT => Ok(ProofStep::Checkpoint),
             CODE_END => Ok(ProofStep::End),
-            _ => failure::bail!("parse error"),
+            code => Err(StepError::UnknownStep { code }),
         }
     }
 }
@@ -268,10 +576,7 @@ impl Parser {
 /// Writes a slice of literals for a varisat proof
 fn write_literals(target: &mut impl Write, literals: &[Lit]) -> io::Result<()> {
     write_u64(&mut *target, literals.len() as u64)?;
-    for &lit in literals {
-        write_u64(&mut *target, lit.code() as u64)?;
-    }
-    Ok(())
+    write_u64_iter(&mut *target, literals.iter().map(|lit| lit.code() as u64))
 }
 
 /// Read a slice of literals from a varisat proof
@@ -279,38 +584,83 @@ fn read_literals(source: &mut impl BufRead, literals: &mut Vec<Lit>) -> Result<(
     literals.clear();
     let len = read_u64(&mut *source)? as usize;
     literals.reserve(len);
-    for _ in 0..len {
-        literals.push(Lit::from_code(read_u64(&mut *source)? as usize));
+    read_u64s_with(&mut *source, len, |code| {
+        literals.push(Lit::from_code(code as usize))
+    })
+}
+
+/// Whether any of the given hashes needs more than 64 bits.
+fn has_wide_hashes(mut hashes: impl Iterator<Item = ClauseHash>) -> bool {
+    hashes.any(|hash| hash >> 64 != 0)
+}
+
+/// Writes the length of a list containing hashes.
+///
+/// The lowest bit marks lists that use 128 bit instead of 64 bit hashes.
+fn write_hash_list_len(target: &mut impl Write, len: usize, wide: bool) -> io::Result<()> {
+    write_u64(&mut *target, ((len as u64) << 1) | wide as u64)
+}
+
+/// Read the length of a list containing hashes and whether it uses 128 bit hashes.
+fn read_hash_list_len(source: &mut impl BufRead) -> Result<(usize, bool), io::Error> {
+    let code = read_u64(&mut *source)?;
+    Ok(((code >> 1) as usize, code & 1 != 0))
+}
+
+/// Writes a single clause hash for a varisat proof
+fn write_hash(target: &mut impl Write, hash: ClauseHash, wide: bool) -> io::Result<()> {
+    write_u64(&mut *target, hash as u64)?;
+    if wide {
+        write_u64(&mut *target, (hash >> 64) as u64)?;
     }
     Ok(())
 }
 
+/// Read a single clause hash from a varisat proof
+fn read_hash(source: &mut impl BufRead, wide: bool) -> Result<ClauseHash, io::Error> {
+    let mut hash = read_u64(&mut *source)? as ClauseHash;
+    if wide {
+        hash |= (read_u64(&mut *source)? as ClauseHash) << 64;
+    }
+    Ok(hash)
+}
+
 /// Writes a slice of clause hashes for a varisat proof
 fn write_hashes(target: &mut impl Write, hashes: &[ClauseHash]) -> io::Result<()> {
-    write_u64(&mut *target, hashes.len() as u64)?;
-    for &hash in hashes {
-        write_u64(&mut *target, hash as u64)?;
+    let wide = has_wide_hashes(hashes.iter().cloned());
+    write_hash_list_len(&mut *target, hashes.len(), wide)?;
+    if wide {
+        for &hash in hashes {
+            write_hash(&mut *target, hash, wide)?;
+        }
+        Ok(())
+    } else {
+        write_u64_iter(&mut *target, hashes.iter().map(|&hash| hash as u64))
     }
-    Ok(())
 }
 
 /// Read a slice of clause hashes from a varisat proof
 fn read_hashes(source: &mut impl BufRead, hashes: &mut Vec<ClauseHash>) -> Result<(), io::Error> {
     hashes.clear();
-    let len = read_u64(&mut *source)? as usize;
+    let (len, wide) = read_hash_list_len(&mut *source)?;
     hashes.reserve(len);
-    for _ in 0..len {
-        hashes.push(read_u64(&mut *source)? as ClauseHash);
+    if wide {
+        for _ in 0..len {
+            hashes.push(read_hash(&mut *source, wide)?);
+        }
+        Ok(())
+    } else {
+        read_u64s_with(&mut *source, len, |hash| hashes.push(hash as ClauseHash))
     }
-    Ok(())
 }
 
 /// Writes a slice of unit clauses for a varisat proof
 fn write_unit_clauses(target: &mut impl Write, units: &[(Lit, ClauseHash)]) -> io::Result<()> {
-    write_u64(&mut *target, units.len() as u64)?;
+    let wide = has_wide_hashes(units.iter().map(|&(_, hash)| hash));
+    write_hash_list_len(&mut *target, units.len(), wide)?;
     for &(lit, hash) in units {
         write_u64(&mut *target, lit.code() as u64)?;
-        write_u64(&mut *target, hash as u64)?;
+        write_hash(&mut *target, hash, wide)?;
     }
     Ok(())
 }
@@ -321,12 +671,130 @@ fn read_unit_clauses(
     units: &mut Vec<(Lit, ClauseHash)>,
 ) -> Result<(), io::Error> {
     units.clear();
-    let len = read_u64(&mut *source)? as usize;
+    let (len, wide) = read_hash_list_len(&mut *source)?;
     units.reserve(len);
     for _ in 0..len {
         let lit = Lit::from_code(read_u64(&mut *source)? as usize);
-        let hash = read_u64(&mut *source)? as ClauseHash;
+        let hash = read_hash(&mut *source, wide)?;
         units.push((lit, hash));
     }
     Ok(())
 }
+
+/// Writes a slice of resolved clause hashes and propagation counts for a varisat proof
+fn write_resolutions(
+    target: &mut impl Write,
+    resolutions: &[(ClauseHash, usize)],
+) -> io::Result<()> {
+    let wide = has_wide_hashes(resolutions.iter().map(|&(hash, _)| hash));
+    write_hash_list_len(&mut *target, resolutions.len(), wide)?;
+    for &(hash, count) in resolutions {
+        write_hash(&mut *target, hash, wide)?;
+        write_u64(&mut *target, count as u64)?;
+    }
+    Ok(())
+}
+
+/// Read a slice of resolved clause hashes and propagation counts from a varisat proof
+fn read_resolutions(
+    source: &mut impl BufRead,
+    resolutions: &mut Vec<(ClauseHash, usize)>,
+) -> Result<(), io::Error> {
+    resolutions.clear();
+    let (len, wide) = read_hash_list_len(&mut *source)?;
+    resolutions.reserve(len);
+    for _ in 0..len {
+        let hash = read_hash(&mut *source, wide)?;
+        let count = read_u64(&mut *source)? as usize;
+        resolutions.push((hash, count));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_roundtrip() {
+        let mut buf = vec![];
+        write_header(&mut buf, &[("generator", "test")]).unwrap();
+
+        let header = read_header(&mut &buf[..]).unwrap();
+        assert_eq!(header.version, FORMAT_VERSION);
+        assert_eq!(
+            header.metadata,
+            vec![("generator".to_string(), "test".to_string())]
+        );
+    }
+
+    #[test]
+    fn hash_width_roundtrip() {
+        use varisat_formula::lits;
+
+        let narrow = 0x1234_5678_9abc_def0;
+        let wide = 0xfedc_ba98_7654_3210_0123_4567_89ab_cdef;
+
+        let mut buf = vec![];
+        for &hashes in [[narrow, 3], [narrow, wide]].iter() {
+            write_step(
+                &mut buf,
+                &ProofStep::AtClause {
+                    redundant: true,
+                    clause: &lits![1, 2],
+                    propagation_hashes: &hashes,
+                },
+            )
+            .unwrap();
+        }
+        write_step(
+            &mut buf,
+            &ProofStep::UnitClauses {
+                units: &[(Lit::from_dimacs(3), wide)],
+            },
+        )
+        .unwrap();
+
+        let mut parser = Parser::default();
+        let mut source = &buf[..];
+        for &expected in [[narrow, 3], [narrow, wide]].iter() {
+            match parser.parse_step(&mut source).unwrap() {
+                ProofStep::AtClause {
+                    propagation_hashes, ..
+                } => assert_eq!(propagation_hashes, &expected[..]),
+                other => panic!("unexpected step {:?}", other),
+            }
+        }
+        match parser.parse_step(&mut source).unwrap() {
+            ProofStep::UnitClauses { units } => {
+                assert_eq!(units, &[(Lit::from_dimacs(3), wide)][..])
+            }
+            other => panic!("unexpected step {:?}", other),
+        }
+    }
+
+    #[test]
+    fn header_errors() {
+        let err = read_header(&mut &b"DRAT proof file"[..]).unwrap_err();
+        match err {
+            HeaderError::MissingMagic => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let mut buf = MAGIC.to_vec();
+        write_u64(&mut buf, FORMAT_VERSION + 1).unwrap();
+        let err = read_header(&mut &buf[..]).unwrap_err();
+        match err {
+            HeaderError::UnsupportedVersion { version } => {
+                assert_eq!(version, FORMAT_VERSION + 1)
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let err = read_header(&mut &MAGIC[..4]).unwrap_err();
+        match err {
+            HeaderError::Io(err) => assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
@@ -2,11 +2,17 @@
 use varisat_formula::{Lit, Var};
 
 pub mod binary_format;
+pub mod owned;
 
-mod vli_enc;
+pub mod vli_enc;
+
+pub use owned::OwnedProofStep;
 
 // Integer type used to store a hash of a clause.
-pub type ClauseHash = u64;
+pub type ClauseHash = u128;
+
+/// Number of bits of a full width [`ClauseHash`].
+pub const MAX_HASH_BITS: u32 = 128;
 
 /// Hash a single literal.
 ///
@@ -20,7 +26,7 @@ pub fn lit_hash(lit: Lit) -> ClauseHash {
 /// This doesn't require the code to correspond a valid literal.
 pub fn lit_code_hash(lit_code: usize) -> ClauseHash {
     // Constant based on the golden ratio provides good mixing for the resulting upper bits
-    (!(lit_code as u64)).wrapping_mul(0x61c8864680b583ebu64)
+    (!(lit_code as u128)).wrapping_mul(0x61c8864680b583ea0c633f9fa31237cdu128)
 }
 
 /// A fast hash function for clauses (or other *sets* of literals).
@@ -37,6 +43,7 @@ pub fn clause_hash(lits: &[Lit]) -> ClauseHash {
 
 /// Justifications for a simple clause deletion.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub enum DeleteClauseProof {
     /// The clause is known to be redundant.
     Redundant,
@@ -48,6 +55,18 @@ pub enum DeleteClauseProof {
     Satisfied,
 }
 
+/// Result of a solve call.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub enum SolveResult {
+    /// The formula is satisfiable under the current assumptions.
+    Sat,
+    /// The formula is unsatisfiable, possibly only under the current assumptions.
+    Unsat,
+    /// The solve call was interrupted.
+    Unknown,
+}
+
 /// A single proof step.
 ///
 /// Represents a mutation of the current formula and a justification for the mutation's validity.
@@ -95,6 +114,70 @@ pub enum ProofStep<'a> {
         clause: &'a [Lit],
         propagation_hashes: &'a [ClauseHash],
     },
+    /// Add a clause that is a resolution asymmetric tautology (RAT) on the given pivot.
+    ///
+    /// The pivot must be one of the clause's literals. Resolving the clause on the pivot with any
+    /// clause containing the negated pivot must result in a tautology or an asymmetric tautology.
+    /// RAT clauses are always added as irredundant clauses. As adding them can remove models of
+    /// the formula, the pivot must not be a sampling variable.
+    ///
+    /// The `resolutions` slice contains the hash of each clause containing the negated pivot
+    /// together with the number of propagation hashes used to show that the resolvent is an AT.
+    /// These propagation hashes are stored consecutively in `propagation_hashes` in the order of
+    /// `resolutions`. Tautological resolvents use no propagation hashes.
+    ///
+    /// When generating DRAT proofs the pivot is written as the first literal and the hashes are
+    /// ignored.
+    RatClause {
+        clause: &'a [Lit],
+        pivot: Lit,
+        resolutions: &'a [(ClauseHash, usize)],
+        propagation_hashes: &'a [ClauseHash],
+    },
+    /// Add a clause that is blocked on the given pivot.
+    ///
+    /// This is a `RatClause` where every resolvent is a tautology, so no propagation hashes are
+    /// needed. The `blocking` slice contains the hash of each clause containing the negated pivot.
+    ///
+    /// When generating DRAT proofs the pivot is written as the first literal and the hashes are
+    /// ignored.
+    BlockedClause {
+        clause: &'a [Lit],
+        pivot: Lit,
+        blocking: &'a [ClauseHash],
+    },
+    /// Add a clause that is propagation redundant (PR) with the given witness.
+    ///
+    /// The witness is a consistent set of literals that satisfies the clause. All clauses that
+    /// contain a negated witness literal without being satisfied by the witness must be reduced,
+    /// i.e. their literals that are falsified by the witness are removed. Adding the clause's
+    /// literals to such a reduced clause must result in a tautology or an asymmetric tautology. PR
+    /// clauses are always added as irredundant clauses. As adding them can remove models of the
+    /// formula, the witness must not contain sampling variables.
+    ///
+    /// The `reduced_clauses` slice contains the hash of each clause reduced by the witness
+    /// together with the number of propagation hashes used to show the AT property. These
+    /// propagation hashes are stored consecutively in `propagation_hashes` in the order of
+    /// `reduced_clauses`.
+    ///
+    /// When generating DRAT proofs, the PR step is written in the DPR format and the hashes are
+    /// ignored.
+    PrClause {
+        clause: &'a [Lit],
+        witness: &'a [Lit],
+        reduced_clauses: &'a [(ClauseHash, usize)],
+        propagation_hashes: &'a [ClauseHash],
+    },
+    /// Introduce a definition for a fresh variable (extended resolution).
+    ///
+    /// This defines `lit` as the conjunction of the `definition` literals by adding the clauses
+    /// `!lit | d` for each literal `d` of the definition and the clause `lit | !d_1 | ... | !d_n`.
+    /// The variable of `lit` must not be used by any clause, must not be a sampling variable and
+    /// must not occur in the definition. The clauses are added as irredundant clauses.
+    ///
+    /// When generating DRAT proofs, the long clause followed by the binary clauses are written as
+    /// RAT clauses on `lit` and `!lit` respectively.
+    Extension { lit: Lit, definition: &'a [Lit] },
     /// Unit clauses found by top-level unit-propagation.
     ///
     /// Pairs of unit clauses and the original clause that became unit. Clauses are in chronological
@@ -109,7 +192,43 @@ pub enum ProofStep<'a> {
         clause: &'a [Lit],
         proof: DeleteClauseProof,
     },
+    /// Delete an irredundant clause that is a resolution asymmetric tautology (RAT) on the given
+    /// pivot.
+    ///
+    /// This allows logging bounded variable elimination: after adding all non-tautological
+    /// resolvents on the eliminated variable, each clause containing it can be deleted this way.
+    /// Resolutions and propagation hashes are given as for `RatClause`, but only the remaining
+    /// irredundant clauses can be used for propagation. The pivot must not be a sampling variable.
+    ///
+    /// When generating DRAT proofs this is written as a plain deletion.
+    DeleteRatClause {
+        clause: &'a [Lit],
+        pivot: Lit,
+        resolutions: &'a [(ClauseHash, usize)],
+        propagation_hashes: &'a [ClauseHash],
+    },
+    /// Delete an irredundant clause that is blocked on the given pivot.
+    ///
+    /// This is a `DeleteRatClause` where every resolvent is a tautology, used for blocked clause
+    /// elimination. The `blocking` slice is as for `BlockedClause`.
+    ///
+    /// When generating DRAT proofs this is written as a plain deletion.
+    DeleteBlockedClause {
+        clause: &'a [Lit],
+        pivot: Lit,
+        blocking: &'a [ClauseHash],
+    },
+    /// Delete the clause with the given hash.
+    ///
+    /// This avoids repeating the literals of long clauses. The hash must belong to exactly one
+    /// present non-unit clause.
+    DeleteHashedClause {
+        hash: ClauseHash,
+        proof: DeleteClauseProof,
+    },
     /// Change the number of clause hash bits used
+    ///
+    /// Proofs start out using 64 bits. At most [`MAX_HASH_BITS`] bits can be used.
     ChangeHashBits { bits: u32 },
     /// A (partial) assignment that satisfies all clauses and assumptions.
     Model { assignment: &'a [Lit] },
@@ -122,6 +241,18 @@ pub enum ProofStep<'a> {
         failed_core: &'a [Lit],
         propagation_hashes: &'a [ClauseHash],
     },
+    /// Marks the end of a solve call and its result.
+    ///
+    /// A `Sat` result must be justified by a `Model` step and an `Unsat` result by a
+    /// `FailedAssumptions` step or by the derivation of the empty clause. Changing the assumptions
+    /// invalidates both justifications, adding a clause invalidates the justification of a `Sat`
+    /// result.
+    SolveResult { result: SolveResult },
+    /// Marks a point at which the proof was synced to disk.
+    ///
+    /// All previous steps were written completely, so a proof truncated after this step can be
+    /// checked up to here.
+    Checkpoint,
     /// Signals the end of a proof.
     ///
     /// A varisat proof must end with this command or else the checker will complain about an
@@ -134,7 +265,13 @@ impl<'a> ProofStep<'a> {
     pub fn contains_hashes(&self) -> bool {
         match self {
             ProofStep::AtClause { .. }
+            | ProofStep::RatClause { .. }
+            | ProofStep::PrClause { .. }
             | ProofStep::UnitClauses { .. }
+            | ProofStep::BlockedClause { .. }
+            | ProofStep::DeleteRatClause { .. }
+            | ProofStep::DeleteBlockedClause { .. }
+            | ProofStep::DeleteHashedClause { .. }
             | ProofStep::FailedAssumptions { .. } => true,
 
             ProofStep::SolverVarName { .. }
@@ -142,10 +279,13 @@ impl<'a> ProofStep<'a> {
             | ProofStep::DeleteVar { .. }
             | ProofStep::ChangeSamplingMode { .. }
             | ProofStep::AddClause { .. }
+            | ProofStep::Extension { .. }
             | ProofStep::DeleteClause { .. }
             | ProofStep::ChangeHashBits { .. }
             | ProofStep::Model { .. }
             | ProofStep::Assumptions { .. }
+            | ProofStep::SolveResult { .. }
+            | ProofStep::Checkpoint
             | ProofStep::End => false,
         }
     }
@@ -0,0 +1,346 @@
+//! Proof steps that own their data.
+use varisat_formula::{Lit, Var};
+
+use crate::{ClauseHash, DeleteClauseProof, ProofStep, SolveResult};
+
+/// A [`ProofStep`] that owns its literals and hashes.
+///
+/// [`ProofStep`] borrows its slices to avoid allocations while generating or parsing proofs. This
+/// variant can be stored or generated independently, e.g. by fuzz targets using the `fuzzing`
+/// feature, and borrowed as a `ProofStep` using [`as_step`](OwnedProofStep::as_step).
+///
+/// See [`ProofStep`] for the meaning of the individual steps.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub enum OwnedProofStep {
+    SolverVarName {
+        global: Var,
+        solver: Option<Var>,
+    },
+    UserVarName {
+        global: Var,
+        user: Option<Var>,
+    },
+    DeleteVar {
+        var: Var,
+    },
+    ChangeSamplingMode {
+        var: Var,
+        sample: bool,
+    },
+    AddClause {
+        clause: Vec<Lit>,
+    },
+    AtClause {
+        redundant: bool,
+        clause: Vec<Lit>,
+        propagation_hashes: Vec<ClauseHash>,
+    },
+    RatClause {
+        clause: Vec<Lit>,
+        pivot: Lit,
+        resolutions: Vec<(ClauseHash, usize)>,
+        propagation_hashes: Vec<ClauseHash>,
+    },
+    BlockedClause {
+        clause: Vec<Lit>,
+        pivot: Lit,
+        blocking: Vec<ClauseHash>,
+    },
+    PrClause {
+        clause: Vec<Lit>,
+        witness: Vec<Lit>,
+        reduced_clauses: Vec<(ClauseHash, usize)>,
+        propagation_hashes: Vec<ClauseHash>,
+    },
+    Extension {
+        lit: Lit,
+        definition: Vec<Lit>,
+    },
+    UnitClauses {
+        units: Vec<(Lit, ClauseHash)>,
+    },
+    DeleteClause {
+        clause: Vec<Lit>,
+        proof: DeleteClauseProof,
+    },
+    DeleteRatClause {
+        clause: Vec<Lit>,
+        pivot: Lit,
+        resolutions: Vec<(ClauseHash, usize)>,
+        propagation_hashes: Vec<ClauseHash>,
+    },
+    DeleteBlockedClause {
+        clause: Vec<Lit>,
+        pivot: Lit,
+        blocking: Vec<ClauseHash>,
+    },
+    DeleteHashedClause {
+        hash: ClauseHash,
+        proof: DeleteClauseProof,
+    },
+    ChangeHashBits {
+        bits: u32,
+    },
+    Model {
+        assignment: Vec<Lit>,
+    },
+    Assumptions {
+        assumptions: Vec<Lit>,
+    },
+    FailedAssumptions {
+        failed_core: Vec<Lit>,
+        propagation_hashes: Vec<ClauseHash>,
+    },
+    SolveResult {
+        result: SolveResult,
+    },
+    Checkpoint,
+    End,
+}
+
+impl OwnedProofStep {
+    /// Borrow this as a [`ProofStep`].
+    pub fn as_step(&self) -> ProofStep {
+        match *self {
+            OwnedProofStep::SolverVarName { global, solver } => {
+                ProofStep::SolverVarName { global, solver }
+            }
+            OwnedProofStep::UserVarName { global, user } => ProofStep::UserVarName { global, user },
+            OwnedProofStep::DeleteVar { var } => ProofStep::DeleteVar { var },
+            OwnedProofStep::ChangeSamplingMode { var, sample } => {
+                ProofStep::ChangeSamplingMode { var, sample }
+            }
+            OwnedProofStep::AddClause { ref clause } => ProofStep::AddClause { clause },
+            OwnedProofStep::AtClause {
+                redundant,
+                ref clause,
+                ref propagation_hashes,
+            } => ProofStep::AtClause {
+                redundant,
+                clause,
+                propagation_hashes,
+            },
+            OwnedProofStep::RatClause {
+                ref clause,
+                pivot,
+                ref resolutions,
+                ref propagation_hashes,
+            } => ProofStep::RatClause {
+                clause,
+                pivot,
+                resolutions,
+                propagation_hashes,
+            },
+            OwnedProofStep::BlockedClause {
+                ref clause,
+                pivot,
+                ref blocking,
+            } => ProofStep::BlockedClause {
+                clause,
+                pivot,
+                blocking,
+            },
+            OwnedProofStep::PrClause {
+                ref clause,
+                ref witness,
+                ref reduced_clauses,
+                ref propagation_hashes,
+            } => ProofStep::PrClause {
+                clause,
+                witness,
+                reduced_clauses,
+                propagation_hashes,
+            },
+            OwnedProofStep::Extension {
+                lit,
+                ref definition,
+            } => ProofStep::Extension { lit, definition },
+            OwnedProofStep::UnitClauses { ref units } => ProofStep::UnitClauses { units },
+            OwnedProofStep::DeleteClause { ref clause, proof } => {
+                ProofStep::DeleteClause { clause, proof }
+            }
+            OwnedProofStep::DeleteRatClause {
+                ref clause,
+                pivot,
+                ref resolutions,
+                ref propagation_hashes,
+            } => ProofStep::DeleteRatClause {
+                clause,
+                pivot,
+                resolutions,
+                propagation_hashes,
+            },
+            OwnedProofStep::DeleteBlockedClause {
+                ref clause,
+                pivot,
+                ref blocking,
+            } => ProofStep::DeleteBlockedClause {
+                clause,
+                pivot,
+                blocking,
+            },
+            OwnedProofStep::DeleteHashedClause { hash, proof } => {
+                ProofStep::DeleteHashedClause { hash, proof }
+            }
+            OwnedProofStep::ChangeHashBits { bits } => ProofStep::ChangeHashBits { bits },
+            OwnedProofStep::Model { ref assignment } => ProofStep::Model { assignment },
+            OwnedProofStep::Assumptions { ref assumptions } => {
+                ProofStep::Assumptions { assumptions }
+            }
+            OwnedProofStep::FailedAssumptions {
+                ref failed_core,
+                ref propagation_hashes,
+            } => ProofStep::FailedAssumptions {
+                failed_core,
+                propagation_hashes,
+            },
+            OwnedProofStep::SolveResult { result } => ProofStep::SolveResult { result },
+            OwnedProofStep::Checkpoint => ProofStep::Checkpoint,
+            OwnedProofStep::End => ProofStep::End,
+        }
+    }
+}
+
+impl<'a> From<&ProofStep<'a>> for OwnedProofStep {
+    fn from(step: &ProofStep<'a>) -> OwnedProofStep {
+        match *step {
+            ProofStep::SolverVarName { global, solver } => {
+                OwnedProofStep::SolverVarName { global, solver }
+            }
+            ProofStep::UserVarName { global, user } => OwnedProofStep::UserVarName { global, user },
+            ProofStep::DeleteVar { var } => OwnedProofStep::DeleteVar { var },
+            ProofStep::ChangeSamplingMode { var, sample } => {
+                OwnedProofStep::ChangeSamplingMode { var, sample }
+            }
+            ProofStep::AddClause { clause } => OwnedProofStep::AddClause {
+                clause: clause.to_vec(),
+            },
+            ProofStep::AtClause {
+                redundant,
+                clause,
+                propagation_hashes,
+            } => OwnedProofStep::AtClause {
+                redundant,
+                clause: clause.to_vec(),
+                propagation_hashes: propagation_hashes.to_vec(),
+            },
+            ProofStep::RatClause {
+                clause,
+                pivot,
+                resolutions,
+                propagation_hashes,
+            } => OwnedProofStep::RatClause {
+                clause: clause.to_vec(),
+                pivot,
+                resolutions: resolutions.to_vec(),
+                propagation_hashes: propagation_hashes.to_vec(),
+            },
+            ProofStep::BlockedClause {
+                clause,
+                pivot,
+                blocking,
+            } => OwnedProofStep::BlockedClause {
+                clause: clause.to_vec(),
+                pivot,
+                blocking: blocking.to_vec(),
+            },
+            ProofStep::PrClause {
+                clause,
+                witness,
+                reduced_clauses,
+                propagation_hashes,
+            } => OwnedProofStep::PrClause {
+                clause: clause.to_vec(),
+                witness: witness.to_vec(),
+                reduced_clauses: reduced_clauses.to_vec(),
+                propagation_hashes: propagation_hashes.to_vec(),
+            },
+            ProofStep::Extension { lit, definition } => OwnedProofStep::Extension {
+                lit,
+                definition: definition.to_vec(),
+            },
+            ProofStep::UnitClauses { units } => OwnedProofStep::UnitClauses {
+                units: units.to_vec(),
+            },
+            ProofStep::DeleteClause { clause, proof } => OwnedProofStep::DeleteClause {
+                clause: clause.to_vec(),
+                proof,
+            },
+            ProofStep::DeleteRatClause {
+                clause,
+                pivot,
+                resolutions,
+                propagation_hashes,
+            } => OwnedProofStep::DeleteRatClause {
+                clause: clause.to_vec(),
+                pivot,
+                resolutions: resolutions.to_vec(),
+                propagation_hashes: propagation_hashes.to_vec(),
+            },
+            ProofStep::DeleteBlockedClause {
+                clause,
+                pivot,
+                blocking,
+            } => OwnedProofStep::DeleteBlockedClause {
+                clause: clause.to_vec(),
+                pivot,
+                blocking: blocking.to_vec(),
+            },
+            ProofStep::DeleteHashedClause { hash, proof } => {
+                OwnedProofStep::DeleteHashedClause { hash, proof }
+            }
+            ProofStep::ChangeHashBits { bits } => OwnedProofStep::ChangeHashBits { bits },
+            ProofStep::Model { assignment } => OwnedProofStep::Model {
+                assignment: assignment.to_vec(),
+            },
+            ProofStep::Assumptions { assumptions } => OwnedProofStep::Assumptions {
+                assumptions: assumptions.to_vec(),
+            },
+            ProofStep::FailedAssumptions {
+                failed_core,
+                propagation_hashes,
+            } => OwnedProofStep::FailedAssumptions {
+                failed_core: failed_core.to_vec(),
+                propagation_hashes: propagation_hashes.to_vec(),
+            },
+            ProofStep::SolveResult { result } => OwnedProofStep::SolveResult { result },
+            ProofStep::Checkpoint => OwnedProofStep::Checkpoint,
+            ProofStep::End => OwnedProofStep::End,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use varisat_formula::{lit, lits, var};
+
+    #[test]
+    fn borrow_roundtrip() {
+        let steps = vec![
+            OwnedProofStep::UserVarName {
+                global: var!(3),
+                user: Some(var!(1)),
+            },
+            OwnedProofStep::RatClause {
+                clause: lits![1, -2, 3].to_vec(),
+                pivot: lit!(1),
+                resolutions: vec![(7, 1), (9, 0)],
+                propagation_hashes: vec![4],
+            },
+            OwnedProofStep::UnitClauses {
+                units: vec![(lit!(-2), 5)],
+            },
+            OwnedProofStep::SolveResult {
+                result: SolveResult::Unsat,
+            },
+            OwnedProofStep::End,
+        ];
+
+        for step in steps {
+            assert_eq!(OwnedProofStep::from(&step.as_step()), step);
+        }
+    }
+}
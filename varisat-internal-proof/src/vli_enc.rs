@@ -15,28 +15,76 @@
 //! the bits are kept consecutive and the length can be determined from the first or first two
 //! bytes.
 //!
-//! The current implementations are not optimal but fast enough to not be the bottleneck when
-//! checking proofs.
+//! Sequences of numbers are best written using [`write_u64s`] or a [`VliWriter`] and read using
+//! [`read_u64s`], which avoid the overhead of an IO call per number.
 use std::convert::{TryFrom, TryInto};
 use std::io::{self, BufRead, Write};
 
-/// Write an encoded 64 bit number.
-pub fn write_u64(target: &mut impl Write, mut value: u64) -> Result<(), io::Error> {
+/// Maximal number of bytes used to encode a 64 bit number.
+pub const MAX_ENCODED_LEN: usize = 10;
+
+/// Number of bytes encoded in a scratch buffer before writing them out.
+const SCRATCH_LEN: usize = 256;
+
+/// Default buffer capacity of a [`VliWriter`].
+const WRITER_CAPACITY: usize = 8 * 1024;
+
+/// Encode a 64 bit number into the first bytes of `bytes`.
+///
+/// The remaining bytes are overwritten with unspecified values. Returns the encoded length.
+fn encode_u64(mut value: u64, bytes: &mut [u8; 16]) -> usize {
     let bits = (64 - value.leading_zeros()) as u32;
     let blocks = (bits * (64 / 7)) / 64;
     if value < (1 << (8 * 7)) {
         value = ((value << 1) | 1) << blocks;
-        let bytes = unsafe { std::mem::transmute::<u64, [u8; 8]>(value.to_le()) };
-        target.write_all(&bytes[..(blocks + 1) as usize])
+        bytes[..8].copy_from_slice(&value.to_le_bytes());
     } else {
         let lo_data = ((value << 1) | 1) << blocks;
-        let lo_bytes = unsafe { std::mem::transmute::<u64, [u8; 8]>(lo_data.to_le()) };
         let hi_data = value >> (64 - (blocks + 1));
-        let hi_bytes = unsafe { std::mem::transmute::<u64, [u8; 8]>(hi_data.to_le()) };
+        bytes[..8].copy_from_slice(&lo_data.to_le_bytes());
+        bytes[8..].copy_from_slice(&hi_data.to_le_bytes());
+    }
+    (blocks + 1) as usize
+}
+
+/// Write an encoded 64 bit number.
+///
+/// When writing many numbers, [`write_u64s`] or a [`VliWriter`] are faster.
+pub fn write_u64(target: &mut impl Write, value: u64) -> Result<(), io::Error> {
+    let mut bytes = [0; 16];
+    let len = encode_u64(value, &mut bytes);
+    target.write_all(&bytes[..len])
+}
 
-        target.write_all(&lo_bytes)?;
-        target.write_all(&hi_bytes[..(blocks as usize) + 1 - 8])
+/// Write a sequence of encoded 64 bit numbers.
+///
+/// The numbers are encoded into a scratch buffer, so only one write is performed for many
+/// numbers.
+pub fn write_u64_iter(
+    target: &mut impl Write,
+    values: impl IntoIterator<Item = u64>,
+) -> Result<(), io::Error> {
+    let mut scratch = [0; SCRATCH_LEN + 16];
+    let mut len = 0;
+    for value in values {
+        let bytes: &mut [u8; 16] = (&mut scratch[len..len + 16]).try_into().unwrap();
+        len += encode_u64(value, bytes);
+        if len >= SCRATCH_LEN {
+            target.write_all(&scratch[..len])?;
+            len = 0;
+        }
+    }
+    if len > 0 {
+        target.write_all(&scratch[..len])?;
     }
+    Ok(())
+}
+
+/// Write a slice of encoded 64 bit numbers.
+///
+/// See [`write_u64_iter`].
+pub fn write_u64s(target: &mut impl Write, values: &[u64]) -> Result<(), io::Error> {
+    write_u64_iter(target, values.iter().cloned())
 }
 
 /// Read an encoded 64 bit number, if at least 16 bytes lookahead are available.
@@ -103,6 +151,137 @@ pub fn read_u64(source: &mut impl BufRead) -> Result<u64, io::Error> {
     }
 }
 
+/// Read `count` encoded 64 bit numbers, passing each to `f`.
+///
+/// As long as enough lookahead is available, this decodes numbers directly from the reader's
+/// buffer and consumes them all at once.
+pub fn read_u64s_with(
+    source: &mut impl BufRead,
+    mut count: usize,
+    mut f: impl FnMut(u64),
+) -> Result<(), io::Error> {
+    while count > 0 {
+        let buf = source.fill_buf()?;
+        let mut pos = 0;
+        while count > 0 && buf.len() - pos >= 16 {
+            let next_bytes: &[u8; 16] = (&buf[pos..pos + 16]).try_into().unwrap();
+            let (value, advance) = read_u64_fast(next_bytes);
+            f(value);
+            pos += advance;
+            count -= 1;
+        }
+        if pos > 0 {
+            source.consume(pos);
+        } else {
+            f(read_u64(source)?);
+            count -= 1;
+        }
+    }
+    Ok(())
+}
+
+/// Read `count` encoded 64 bit numbers, appending them to `values`.
+pub fn read_u64s(
+    source: &mut impl BufRead,
+    count: usize,
+    values: &mut Vec<u64>,
+) -> Result<(), io::Error> {
+    values.reserve(count);
+    read_u64s_with(source, count, |value| values.push(value))
+}
+
+/// Buffered writer for encoded numbers.
+///
+/// This works like a [`BufWriter`](std::io::BufWriter) but encodes numbers directly into its
+/// buffer, avoiding a separate write call for each number. Any buffered data is written when the
+/// writer is dropped, ignoring errors, so [`flush`](Write::flush) should be called explicitly.
+pub struct VliWriter<W: Write> {
+    inner: W,
+    buf: Vec<u8>,
+    capacity: usize,
+}
+
+impl<W: Write> VliWriter<W> {
+    /// Create a buffered writer with the default capacity.
+    pub fn new(inner: W) -> VliWriter<W> {
+        VliWriter::with_capacity(WRITER_CAPACITY, inner)
+    }
+
+    /// Create a buffered writer buffering up to `capacity` bytes.
+    pub fn with_capacity(capacity: usize, inner: W) -> VliWriter<W> {
+        VliWriter {
+            inner,
+            buf: Vec::with_capacity(capacity + 16),
+            capacity,
+        }
+    }
+
+    /// The wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Mutable reference to the wrapped writer.
+    ///
+    /// Writing to it directly bypasses buffered data.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Write an encoded 64 bit number.
+    pub fn write_u64(&mut self, value: u64) -> Result<(), io::Error> {
+        let len = self.buf.len();
+        self.buf.resize(len + 16, 0);
+        let bytes: &mut [u8; 16] = (&mut self.buf[len..]).try_into().unwrap();
+        let encoded_len = encode_u64(value, bytes);
+        self.buf.truncate(len + encoded_len);
+        if self.buf.len() >= self.capacity {
+            self.flush_buf()?;
+        }
+        Ok(())
+    }
+
+    /// Write a slice of encoded 64 bit numbers.
+    pub fn write_u64s(&mut self, values: &[u64]) -> Result<(), io::Error> {
+        for &value in values {
+            self.write_u64(value)?;
+        }
+        Ok(())
+    }
+
+    /// Write the buffered data to the wrapped writer.
+    fn flush_buf(&mut self) -> Result<(), io::Error> {
+        let result = self.inner.write_all(&self.buf);
+        self.buf.clear();
+        result
+    }
+}
+
+impl<W: Write> Write for VliWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.buf.len() + data.len() > self.capacity {
+            self.flush_buf()?;
+        }
+        if data.len() >= self.capacity {
+            self.inner.write(data)
+        } else {
+            self.buf.extend_from_slice(data);
+            Ok(data.len())
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_buf()?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for VliWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.flush_buf();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -130,5 +309,36 @@ mod tests {
 
             prop_assert_eq!(numbers, out);
         }
+
+        #[test]
+        fn bulk_roundtrip (
+            numbers in prop::collection::vec(prop::num::u64::ANY, 0..10_000),
+            split in 0..10_000usize,
+        ) {
+            let split = split.min(numbers.len());
+
+            let mut buf = vec![];
+            write_u64s(&mut buf, &numbers[..split])?;
+            for &num in numbers[split..].iter() {
+                write_u64(&mut buf, num)?;
+            }
+
+            let mut buffered = vec![];
+            {
+                let mut writer = VliWriter::with_capacity(100, &mut buffered);
+                writer.write_u64s(&numbers[split..])?;
+                writer.flush()?;
+            }
+
+            let mut read = std::io::BufReader::with_capacity(128, &buf[..]);
+            let mut out = vec![];
+            read_u64s(&mut read, numbers.len(), &mut out)?;
+            prop_assert!(read_u64(&mut read).is_err());
+            prop_assert_eq!(&numbers, &out);
+
+            out.clear();
+            read_u64s(&mut &buffered[..], numbers.len() - split, &mut out)?;
+            prop_assert_eq!(&numbers[split..], &out[..]);
+        }
     }
 }
@@ -1,10 +1,10 @@
-use failure::{ensure, Error};
 use std::env;
+use std::error::Error;
 use std::io::Write;
 use std::process::{Command, Stdio};
 use std::str::from_utf8;
 
-fn have_check_lrat() -> Result<(), Error> {
+fn have_check_lrat() -> Result<(), Box<dyn Error>> {
     println!("rerun-if-env-changed=VARISAT_HAVE_CHECK_LRAT");
     if env::var("VARISAT_HAVE_CHECK_LRAT").is_ok() {
         return Ok(());
@@ -20,15 +20,14 @@ fn have_check_lrat() -> Result<(), Error> {
     let output = child.wait_with_output()?;
     let stdout = from_utf8(&output.stdout)?;
 
-    ensure!(
-        stdout.contains("INCLUDE-BOOK \"projects/sat/lrat/stobj-based/run\""),
-        "stobj-based lrat-check not found"
-    );
+    if !stdout.contains("INCLUDE-BOOK \"projects/sat/lrat/stobj-based/run\"") {
+        return Err("stobj-based lrat-check not found".into());
+    }
 
     Ok(())
 }
 
-fn have_check_clrat() -> Result<(), Error> {
+fn have_check_clrat() -> Result<(), Box<dyn Error>> {
     println!("rerun-if-env-changed=VARISAT_HAVE_CHECK_LRAT");
     if env::var("VARISAT_HAVE_CHECK_LRAT").is_ok() {
         return Ok(());
@@ -44,10 +43,9 @@ fn have_check_clrat() -> Result<(), Error> {
     let output = child.wait_with_output()?;
     let stdout = from_utf8(&output.stdout)?;
 
-    ensure!(
-        stdout.contains("INCLUDE-BOOK \"projects/sat/lrat/incremental/run\""),
-        "incremental lrat-check not found"
-    );
+    if !stdout.contains("INCLUDE-BOOK \"projects/sat/lrat/incremental/run\"") {
+        return Err("incremental lrat-check not found".into());
+    }
 
     Ok(())
 }
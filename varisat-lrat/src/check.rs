@@ -0,0 +1,247 @@
+//! Checking of LRAT proofs.
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Read};
+
+use thiserror::Error;
+
+use varisat_formula::{CnfFormula, Lit};
+
+use crate::read::{LratParseError, LratReader, LratStep};
+use crate::LratFormat;
+
+/// Errors of invalid LRAT proofs.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum LratCheckError {
+    #[error("deleted clause {id} not present")]
+    MissingDeletedClause { id: u64 },
+    #[error("final clauses do not match the present clauses")]
+    FinalClausesMismatch,
+    #[error("clause id {id} already in use")]
+    DuplicateClauseId { id: u64 },
+    #[error("empty clause {id} is not an asymmetric tautology")]
+    EmptyClauseNotAt { id: u64 },
+    #[error("missing resolution hint for clause {candidate_id} when adding clause {id}")]
+    MissingResolutionHint { id: u64, candidate_id: u64 },
+    #[error("resolved clause {resolved_id} not present")]
+    MissingResolvedClause { resolved_id: u64 },
+    #[error("resolved clause {resolved_id} does not contain the negated pivot {pivot:?}")]
+    MissingNegatedPivot { resolved_id: u64, pivot: Lit },
+    #[error("resolvent of clause {id} with clause {resolved_id} is not an asymmetric tautology")]
+    ResolventNotAt { id: u64, resolved_id: u64 },
+    #[error("hinted clause {hint} not present")]
+    MissingHintedClause { hint: u64 },
+    #[error("hinted clause {hint} {clause:?} is not unit")]
+    HintNotUnit { hint: u64, clause: Vec<Lit> },
+    #[error(transparent)]
+    Parse(#[from] LratParseError),
+}
+
+/// Checker for LRAT proofs.
+///
+/// Unlike the varisat checker, this does not search for propagations but only verifies the hints
+/// given in the proof. It is intended for testing the generated LRAT proofs.
+pub struct LratChecker {
+    /// Present clauses by id.
+    clauses: HashMap<u64, Vec<Lit>>,
+    /// Literals assigned true while checking a clause addition.
+    assigned: HashSet<Lit>,
+    /// Whether the empty clause was added.
+    unsat: bool,
+}
+
+impl LratChecker {
+    /// Create a checker for proofs of the given formula.
+    ///
+    /// The clauses of the formula get the ids `0..formula.len()`.
+    pub fn new(formula: &CnfFormula) -> LratChecker {
+        LratChecker {
+            clauses: formula
+                .iter()
+                .enumerate()
+                .map(|(id, clause)| (id as u64, clause.to_vec()))
+                .collect(),
+            assigned: HashSet::new(),
+            unsat: formula.iter().any(|clause| clause.is_empty()),
+        }
+    }
+
+    /// Whether the empty clause was derived.
+    pub fn unsat(&self) -> bool {
+        self.unsat
+    }
+
+    /// Check a single proof step.
+    pub fn check_step(&mut self, step: &LratStep) -> Result<(), LratCheckError> {
+        match step {
+            LratStep::Add {
+                id,
+                clause,
+                propagations,
+                resolutions,
+            } => self.check_add(*id, clause, propagations, resolutions),
+            LratStep::Delete { ids } => {
+                for id in ids {
+                    if self.clauses.remove(id).is_none() {
+                        return Err(LratCheckError::MissingDeletedClause { id: *id });
+                    }
+                }
+                Ok(())
+            }
+            LratStep::Final { ids } => {
+                let ids: HashSet<u64> = ids.iter().cloned().collect();
+                if ids.len() != self.clauses.len()
+                    || !ids.iter().all(|id| self.clauses.contains_key(id))
+                {
+                    return Err(LratCheckError::FinalClausesMismatch);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Check and add a clause.
+    fn check_add(
+        &mut self,
+        id: u64,
+        clause: &[Lit],
+        propagations: &[u64],
+        resolutions: &[(u64, Vec<u64>)],
+    ) -> Result<(), LratCheckError> {
+        if self.clauses.contains_key(&id) {
+            return Err(LratCheckError::DuplicateClauseId { id });
+        }
+
+        self.assigned.clear();
+        let mut tautology = false;
+        for &lit in clause.iter() {
+            tautology |= self.assigned.contains(&lit);
+            self.assigned.insert(!lit);
+        }
+
+        if !tautology && !self.propagate(propagations)? {
+            // LRAT uses the first literal as pivot
+            let pivot = match clause.first() {
+                Some(&pivot) => pivot,
+                None => return Err(LratCheckError::EmptyClauseNotAt { id }),
+            };
+            self.check_resolutions(id, pivot, resolutions)?;
+        }
+
+        if clause.is_empty() {
+            self.unsat = true;
+        }
+        self.clauses.insert(id, clause.to_vec());
+        Ok(())
+    }
+
+    /// Check that all resolvents on the pivot are asymmetric tautologies.
+    fn check_resolutions(
+        &mut self,
+        id: u64,
+        pivot: Lit,
+        resolutions: &[(u64, Vec<u64>)],
+    ) -> Result<(), LratCheckError> {
+        let base = self.assigned.clone();
+
+        let hinted: HashSet<u64> = resolutions.iter().map(|&(id, _)| id).collect();
+
+        for (&candidate_id, candidate) in self.clauses.iter() {
+            if !candidate.contains(&!pivot) || hinted.contains(&candidate_id) {
+                continue;
+            }
+            if !candidate
+                .iter()
+                .any(|&lit| lit != !pivot && base.contains(&!lit))
+            {
+                return Err(LratCheckError::MissingResolutionHint { id, candidate_id });
+            }
+        }
+
+        for (resolved_id, resolution_propagations) in resolutions.iter() {
+            let resolved =
+                self.clauses
+                    .get(resolved_id)
+                    .ok_or(LratCheckError::MissingResolvedClause {
+                        resolved_id: *resolved_id,
+                    })?;
+            if !resolved.contains(&!pivot) {
+                return Err(LratCheckError::MissingNegatedPivot {
+                    resolved_id: *resolved_id,
+                    pivot,
+                });
+            }
+
+            self.assigned.clone_from(&base);
+            let mut tautology = false;
+            for &lit in resolved.iter() {
+                if lit == !pivot {
+                    continue;
+                }
+                if self.assigned.contains(&lit) {
+                    tautology = true;
+                    break;
+                }
+                self.assigned.insert(!lit);
+            }
+
+            if !tautology && !self.propagate(resolution_propagations)? {
+                return Err(LratCheckError::ResolventNotAt {
+                    id,
+                    resolved_id: *resolved_id,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Propagate the hinted clauses under the current assignment.
+    ///
+    /// Returns whether a conflict was found.
+    fn propagate(&mut self, hints: &[u64]) -> Result<bool, LratCheckError> {
+        for hint in hints {
+            let clause = self
+                .clauses
+                .get(hint)
+                .ok_or(LratCheckError::MissingHintedClause { hint: *hint })?;
+
+            let mut unit = None;
+            for &lit in clause.iter() {
+                if self.assigned.contains(&!lit) {
+                    continue;
+                }
+                if unit.is_some() || self.assigned.contains(&lit) {
+                    return Err(LratCheckError::HintNotUnit {
+                        hint: *hint,
+                        clause: clause.clone(),
+                    });
+                }
+                unit = Some(lit);
+            }
+
+            match unit {
+                None => return Ok(true),
+                Some(lit) => {
+                    self.assigned.insert(lit);
+                }
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Check an LRAT proof of the given formula.
+///
+/// Returns whether the proof derives the empty clause. Fails if any step of the proof is invalid.
+pub fn check_lrat(
+    formula: &CnfFormula,
+    proof: impl Read,
+    format: LratFormat,
+) -> Result<bool, LratCheckError> {
+    let mut checker = LratChecker::new(formula);
+    for step in LratReader::new(io::BufReader::new(proof), format) {
+        checker.check_step(&step?)?;
+    }
+    Ok(checker.unsat())
+}
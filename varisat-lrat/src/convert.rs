@@ -0,0 +1,47 @@
+//! Conversion of DRAT proofs to LRAT.
+use std::io::{self, Read, Write};
+
+use thiserror::Error;
+
+use varisat_checker::{Checker, CheckerError, DratFormat, ProofTrimmer};
+
+use crate::{LratFormat, WriteLrat};
+
+/// Errors while converting a DRAT proof to LRAT.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ConvertError {
+    /// The input formula or the DRAT proof is invalid.
+    #[error(transparent)]
+    Check(#[from] CheckerError),
+    /// Writing the LRAT proof failed.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Convert a DRAT proof of a DIMACS formula into an LRAT proof.
+///
+/// The DRAT proof is checked while converting, so this fails if the proof is invalid or does not
+/// derive the empty clause. Lemmas not needed to derive the empty clause are removed, which
+/// requires buffering the whole proof in memory.
+pub fn drat_to_lrat(
+    dimacs: impl Read,
+    drat: impl Read,
+    drat_format: DratFormat,
+    target: impl Write,
+    lrat_format: LratFormat,
+) -> Result<(), ConvertError> {
+    let mut write_lrat = WriteLrat::with_format(target, lrat_format);
+
+    {
+        let mut trimmer = ProofTrimmer::new();
+        trimmer.add_processor(&mut write_lrat);
+
+        let mut checker = Checker::new();
+        checker.add_processor(&mut trimmer);
+        checker.add_dimacs_cnf(dimacs)?;
+        checker.check_drat_proof(drat, drat_format)?;
+    }
+
+    Ok(write_lrat.flush()?)
+}
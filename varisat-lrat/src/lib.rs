@@ -1,46 +1,129 @@
 //! LRAT proof generation for the Varisat SAT solver.
-use std::io::{BufWriter, Write};
+use std::collections::BTreeSet;
+use std::io::{self, BufWriter, Write};
 use std::mem::replace;
 
-use failure::Error;
+use thiserror::Error;
 
-use varisat_checker::{CheckedProofStep, CheckerData, ProofProcessor};
+use varisat_checker::{
+    CheckedProofStep, CheckerData, ProcessorError, ProofProcessor, ProofVerdict,
+    ResolutionPropagations,
+};
 use varisat_formula::Lit;
+use varisat_internal_proof::vli_enc::write_u64;
+
+pub mod check;
+pub mod convert;
+pub mod read;
+
+pub use check::{check_lrat, LratCheckError, LratChecker};
+pub use convert::{drat_to_lrat, ConvertError};
+pub use read::{read_lrat, LratParseError, LratReader, LratStep};
+
+/// Encoding used for generated LRAT proofs.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum LratFormat {
+    /// The textual LRAT format.
+    Text,
+    /// The binary LRAT encoding.
+    ///
+    /// This is also known as compressed LRAT (CLRAT). It is accepted by the formally verified
+    /// checkers that ship with ACL2 and is much smaller and faster to write than the textual
+    /// format.
+    Binary,
+    /// Delta encoded binary LRAT.
+    ///
+    /// This uses the step structure of the binary encoding, but stores numbers using the variable
+    /// length encoding of varisat's native proof format. Each clause id is stored as the zigzag
+    /// encoded difference to the previously stored id plus one. The sorted literals of a clause are
+    /// stored as the code of the first literal plus one, followed by the differences between
+    /// consecutive literal codes. Lists are terminated by a zero.
+    ///
+    /// This produces significantly smaller proofs, but requires a checker supporting this
+    /// encoding. RAT clauses are not supported by this encoding.
+    Delta,
+}
+
+/// Output options for generated LRAT proofs.
+#[derive(Copy, Clone, Debug)]
+pub struct LratOptions {
+    /// Write clause deletion steps.
+    ///
+    /// Defaults to true.
+    pub deletions: bool,
+    /// Combine consecutive clause deletions into a single deletion step.
+    ///
+    /// When this is false, each deleted clause gets its own deletion step. Defaults to true.
+    pub coalesce_deletions: bool,
+    /// After the last step, write a final step listing the ids of all clauses still present.
+    ///
+    /// The final step starts with `f` followed by the clause ids and is terminated like a deletion
+    /// step. This is not part of the LRAT format and only understood by some consumers. Defaults
+    /// to false.
+    pub final_clauses: bool,
+}
+
+impl Default for LratOptions {
+    fn default() -> LratOptions {
+        LratOptions {
+            deletions: true,
+            coalesce_deletions: true,
+            final_clauses: false,
+        }
+    }
+}
+
+/// Errors for steps that can't be represented in the selected LRAT encoding.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum WriteLratError {
+    #[error("RAT clauses are not supported by the delta encoded LRAT format")]
+    RatClause,
+    #[error("PR clauses are not supported by the LRAT format")]
+    PrClause,
+}
 
 /// Proof processor that generates an LRAT proof.
+///
+/// Proofs containing steps that can't be represented result in a [`WriteLratError`].
 pub struct WriteLrat<'a> {
-    binary: bool,
+    format: LratFormat,
+    options: LratOptions,
     target: BufWriter<Box<dyn Write + 'a>>,
     delete_open: bool,
     last_added_id: u64,
     buffered_deletes: Vec<u64>,
+    /// Previously written clause id, used by the delta encoding.
+    last_written_id: u64,
+    /// Outcome of the check, once it finished.
+    verdict: Option<ProofVerdict>,
+    /// Ids of the present clauses, only tracked when writing final clauses.
+    live_clauses: BTreeSet<u64>,
 }
 
 impl<'a> ProofProcessor for WriteLrat<'a> {
-    fn process_step(&mut self, step: &CheckedProofStep, _data: CheckerData) -> Result<(), Error> {
+    fn process_step(
+        &mut self,
+        step: &CheckedProofStep,
+        _data: CheckerData,
+    ) -> Result<(), ProcessorError> {
         match step {
             &CheckedProofStep::AddClause { .. } => (),
             &CheckedProofStep::DuplicatedClause { .. } => (),
-            _ => {
-                if !self.buffered_deletes.is_empty() {
-                    let buffered_deletes = replace(&mut self.buffered_deletes, vec![]);
-                    self.open_delete()?;
-                    self.write_ids(&buffered_deletes)?;
-                }
-            }
+            _ => self.write_buffered_deletes()?,
         }
 
         match step {
             &CheckedProofStep::AddClause { id, .. } => {
                 self.last_added_id = id;
+                self.add_live_clause(id);
             }
             &CheckedProofStep::DuplicatedClause { id, .. }
             | &CheckedProofStep::TautologicalClause { id, .. } => {
                 self.last_added_id = id;
-                if self.binary {
-                    self.open_delete()?;
-                    self.write_ids(&[id])?;
-                } else {
+                if self.format != LratFormat::Text {
+                    self.delete_clauses(&[id])?;
+                } else if self.options.deletions {
                     // In the textual format the delete command is prefixed by an id which we do not
                     // know yet.
                     self.buffered_deletes.push(id);
@@ -54,6 +137,7 @@ impl<'a> ProofProcessor for WriteLrat<'a> {
             } => {
                 self.close_delete()?;
                 self.last_added_id = id;
+                self.add_live_clause(id);
                 self.write_add_step()?;
                 self.write_ids(&[id])?;
                 self.write_lits(clause)?;
@@ -61,6 +145,32 @@ impl<'a> ProofProcessor for WriteLrat<'a> {
                 self.write_ids(propagations)?;
                 self.write_end()?;
             }
+            &CheckedProofStep::RatClause {
+                id,
+                clause,
+                pivot,
+                propagations,
+            } => {
+                if self.format == LratFormat::Delta {
+                    return Err(WriteLratError::RatClause.into());
+                }
+                self.close_delete()?;
+                self.last_added_id = id;
+                self.add_live_clause(id);
+                self.write_add_step()?;
+                self.write_ids(&[id])?;
+                // LRAT uses the first literal as pivot
+                let mut lits = Vec::with_capacity(clause.len());
+                lits.push(pivot);
+                lits.extend(clause.iter().cloned().filter(|&lit| lit != pivot));
+                self.write_lits(&lits)?;
+                self.write_sep()?;
+                self.write_rat_hints(propagations)?;
+                self.write_end()?;
+            }
+            &CheckedProofStep::PrClause { .. } => {
+                return Err(WriteLratError::PrClause.into());
+            }
             &CheckedProofStep::DeleteAtClause {
                 id,
                 keep_as_redundant,
@@ -72,15 +182,14 @@ impl<'a> ProofProcessor for WriteLrat<'a> {
                 ..
             } => {
                 if !keep_as_redundant {
-                    self.open_delete()?;
-                    self.write_ids(&[id])?;
+                    self.delete_clauses(&[id])?;
                 }
             }
             &CheckedProofStep::DeleteClause { id, .. } => {
-                self.open_delete()?;
-                self.write_ids(&[id])?;
+                self.delete_clauses(&[id])?;
             }
             &CheckedProofStep::UserVar { .. }
+            | &CheckedProofStep::SolverVar { .. }
             | &CheckedProofStep::MakeIrredundant { .. }
             | &CheckedProofStep::Model { .. }
             | &CheckedProofStep::Assumptions { .. }
@@ -88,6 +197,15 @@ impl<'a> ProofProcessor for WriteLrat<'a> {
         }
         Ok(())
     }
+
+    fn end_of_proof(&mut self, verdict: ProofVerdict) -> Result<(), ProcessorError> {
+        self.write_buffered_deletes()?;
+        if self.options.final_clauses {
+            self.write_final_clauses()?;
+        }
+        self.verdict = Some(verdict);
+        Ok(self.flush()?)
+    }
 }
 
 impl<'a> WriteLrat<'a> {
@@ -97,13 +215,43 @@ impl<'a> WriteLrat<'a> {
     /// is true, the compressed LRAT format is used which is a compact binary encoding. Despite the
     /// name, even a compressed LRAT proof can usually still be compressed a lot using a general
     /// data compression algorithm.
+    ///
+    /// To keep slow disks from stalling the checker, `target` can be a
+    /// `varisat_checker::BackgroundWriter`.
     pub fn new(target: impl Write + 'a, binary: bool) -> WriteLrat<'a> {
+        let format = if binary {
+            LratFormat::Binary
+        } else {
+            LratFormat::Text
+        };
+        WriteLrat::with_format(target, format)
+    }
+
+    /// Create a lrat writing processor using the given encoding.
+    ///
+    /// The proof is written to `target`.
+    pub fn with_format(target: impl Write + 'a, format: LratFormat) -> WriteLrat<'a> {
+        WriteLrat::with_options(target, format, LratOptions::default())
+    }
+
+    /// Create a lrat writing processor using the given encoding and output options.
+    ///
+    /// The proof is written to `target`.
+    pub fn with_options(
+        target: impl Write + 'a,
+        format: LratFormat,
+        options: LratOptions,
+    ) -> WriteLrat<'a> {
         WriteLrat {
-            binary,
+            format,
+            options,
             target: BufWriter::new(Box::new(target)),
             delete_open: false,
             last_added_id: 0,
             buffered_deletes: vec![],
+            last_written_id: 0,
+            verdict: None,
+            live_clauses: BTreeSet::new(),
         }
     }
 
@@ -111,16 +259,75 @@ impl<'a> WriteLrat<'a> {
     ///
     /// This is automatically called when this proof processor is dropped. Calling this explicitly
     /// is recommended to handle possible IO errors.
-    pub fn flush(&mut self) -> Result<(), Error> {
+    pub fn flush(&mut self) -> io::Result<()> {
         self.close_delete()?;
-        self.target.flush()?;
+        self.target.flush()
+    }
+
+    /// Outcome of the check that produced this proof.
+    ///
+    /// This is `None` until checking finished. The written proof is only a refutation of the input
+    /// formula if this is `Some(ProofVerdict::Unsat)`.
+    pub fn verdict(&self) -> Option<ProofVerdict> {
+        self.verdict
+    }
+
+    /// Remember a newly added clause if final clauses are written.
+    fn add_live_clause(&mut self, id: u64) {
+        if self.options.final_clauses {
+            self.live_clauses.insert(id);
+        }
+    }
+
+    /// Delete clauses, writing deletion steps if enabled.
+    fn delete_clauses(&mut self, ids: &[u64]) -> io::Result<()> {
+        if self.options.final_clauses {
+            for id in ids {
+                self.live_clauses.remove(id);
+            }
+        }
+        if !self.options.deletions {
+            return Ok(());
+        }
+        if self.options.coalesce_deletions {
+            self.open_delete()?;
+            self.write_ids(ids)?;
+        } else {
+            for &id in ids {
+                self.open_delete()?;
+                self.write_ids(&[id])?;
+                self.close_delete()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write deletions delayed until the id prefixing the deletion step is known.
+    fn write_buffered_deletes(&mut self) -> io::Result<()> {
+        if !self.buffered_deletes.is_empty() {
+            let buffered_deletes = replace(&mut self.buffered_deletes, vec![]);
+            self.delete_clauses(&buffered_deletes)?;
+        }
         Ok(())
     }
 
+    /// Write a step listing the ids of all present clauses.
+    fn write_final_clauses(&mut self) -> io::Result<()> {
+        self.close_delete()?;
+        if self.format != LratFormat::Text {
+            self.target.write_all(b"f")?;
+        } else {
+            self.target.write_all(b"f ")?;
+        }
+        let live_clauses: Vec<u64> = self.live_clauses.iter().cloned().collect();
+        self.write_ids(&live_clauses)?;
+        self.write_end()
+    }
+
     /// If necessary begin a batched delete step.
-    fn open_delete(&mut self) -> Result<(), Error> {
+    fn open_delete(&mut self) -> io::Result<()> {
         if !self.delete_open {
-            if !self.binary {
+            if self.format == LratFormat::Text {
                 self.write_ids(&[self.last_added_id])?;
             }
             self.write_delete_step()?;
@@ -130,7 +337,7 @@ impl<'a> WriteLrat<'a> {
     }
 
     /// If necessary end a batched delete step.
-    fn close_delete(&mut self) -> Result<(), Error> {
+    fn close_delete(&mut self) -> io::Result<()> {
         if self.delete_open {
             self.write_end()?;
             self.delete_open = false;
@@ -139,8 +346,8 @@ impl<'a> WriteLrat<'a> {
     }
 
     /// Begin a batched delete step.
-    fn write_delete_step(&mut self) -> Result<(), Error> {
-        if self.binary {
+    fn write_delete_step(&mut self) -> io::Result<()> {
+        if self.format != LratFormat::Text {
             self.target.write_all(b"d")?;
         } else {
             self.target.write_all(b"d ")?;
@@ -149,59 +356,107 @@ impl<'a> WriteLrat<'a> {
     }
 
     /// Begin a clause addition step.
-    fn write_add_step(&mut self) -> Result<(), Error> {
-        if self.binary {
+    fn write_add_step(&mut self) -> io::Result<()> {
+        if self.format != LratFormat::Text {
             self.target.write_all(b"a")?;
         }
         Ok(())
     }
 
     /// Write a list of clause ids.
-    fn write_ids(&mut self, ids: &[u64]) -> Result<(), Error> {
-        if self.binary {
-            for &id in ids {
-                leb128::write::unsigned(&mut self.target, (id + 1) * 2)?;
+    fn write_ids(&mut self, ids: &[u64]) -> io::Result<()> {
+        match self.format {
+            LratFormat::Text => {
+                for &id in ids {
+                    itoa::write(&mut self.target, id + 1)?;
+                    self.target.write_all(b" ")?;
+                }
             }
-        } else {
-            for &id in ids {
-                itoa::write(&mut self.target, id + 1)?;
-                self.target.write_all(b" ")?;
+            LratFormat::Binary => {
+                for &id in ids {
+                    leb128::write::unsigned(&mut self.target, (id + 1) * 2)?;
+                }
+            }
+            LratFormat::Delta => {
+                for &id in ids {
+                    let delta = (id + 1).wrapping_sub(self.last_written_id) as i64;
+                    let zigzag = ((delta << 1) ^ (delta >> 63)) as u64;
+                    write_u64(&mut self.target, zigzag + 1)?;
+                    self.last_written_id = id + 1;
+                }
             }
         }
         Ok(())
     }
 
+    /// Write the hints of a RAT clause.
+    ///
+    /// Each resolved clause is written as a negated id, followed by the propagations for the
+    /// resolvent.
+    fn write_rat_hints(&mut self, propagations: &ResolutionPropagations) -> io::Result<()> {
+        for (id, resolvent_propagations) in propagations.iter() {
+            match self.format {
+                LratFormat::Text => {
+                    self.target.write_all(b"-")?;
+                    itoa::write(&mut self.target, id + 1)?;
+                    self.target.write_all(b" ")?;
+                }
+                LratFormat::Binary => {
+                    leb128::write::unsigned(&mut self.target, (id + 1) * 2 + 1)?;
+                }
+                LratFormat::Delta => unreachable!(),
+            }
+            self.write_ids(resolvent_propagations)?;
+        }
+        Ok(())
+    }
+
     /// Write a list of literals.
-    fn write_lits(&mut self, lits: &[Lit]) -> Result<(), Error> {
-        if self.binary {
-            for &lit in lits {
-                leb128::write::unsigned(&mut self.target, lit.code() as u64 + 2)?;
+    fn write_lits(&mut self, lits: &[Lit]) -> io::Result<()> {
+        match self.format {
+            LratFormat::Text => {
+                for &lit in lits {
+                    itoa::write(&mut self.target, lit.to_dimacs())?;
+                    self.target.write_all(b" ")?;
+                }
             }
-        } else {
-            for &lit in lits {
-                itoa::write(&mut self.target, lit.to_dimacs())?;
-                self.target.write_all(b" ")?;
+            LratFormat::Binary => {
+                for &lit in lits {
+                    leb128::write::unsigned(&mut self.target, lit.code() as u64 + 2)?;
+                }
+            }
+            LratFormat::Delta => {
+                let mut previous_code = None;
+                for &lit in lits {
+                    let code = lit.code() as u64;
+                    let delta = match previous_code {
+                        Some(previous_code) => code - previous_code,
+                        None => code + 1,
+                    };
+                    write_u64(&mut self.target, delta)?;
+                    previous_code = Some(code);
+                }
             }
         }
         Ok(())
     }
 
     /// End the current step.
-    fn write_end(&mut self) -> Result<(), Error> {
-        if self.binary {
-            self.target.write_all(&[0])?
-        } else {
-            self.target.write_all(b"0\n")?
+    fn write_end(&mut self) -> io::Result<()> {
+        match self.format {
+            LratFormat::Text => self.target.write_all(b"0\n")?,
+            LratFormat::Binary => self.target.write_all(&[0])?,
+            LratFormat::Delta => write_u64(&mut self.target, 0)?,
         }
         Ok(())
     }
 
     /// Write a separator.
-    fn write_sep(&mut self) -> Result<(), Error> {
-        if self.binary {
-            self.target.write_all(&[0])?
-        } else {
-            self.target.write_all(b"0 ")?
+    fn write_sep(&mut self) -> io::Result<()> {
+        match self.format {
+            LratFormat::Text => self.target.write_all(b"0 ")?,
+            LratFormat::Binary => self.target.write_all(&[0])?,
+            LratFormat::Delta => write_u64(&mut self.target, 0)?,
         }
         Ok(())
     }
@@ -219,6 +474,7 @@ mod tests {
 
     use proptest::prelude::*;
 
+    use std::error::Error;
     use std::fs::File;
     use std::path::PathBuf;
     use std::process::{Command, Stdio};
@@ -227,11 +483,16 @@ mod tests {
 
     use varisat::dimacs::write_dimacs;
     use varisat::{ProofFormat, Solver};
-    use varisat_checker::Checker;
+    use varisat_checker::{Checker, DeleteClauseProof, DratFormat, ProofStep};
     use varisat_formula::test::sgen_unsat_formula;
-    use varisat_formula::{cnf_formula, CnfFormula};
+    use varisat_formula::{cnf_formula, lit, lits, CnfFormula, Var};
+    use varisat_internal_proof::binary_format::{write_header, write_step};
 
-    fn check_lrat(tool: &str, cnf_file: &PathBuf, proof_file: &PathBuf) -> Result<bool, Error> {
+    fn check_lrat(
+        tool: &str,
+        cnf_file: &PathBuf,
+        proof_file: &PathBuf,
+    ) -> Result<bool, Box<dyn Error>> {
         let mut child = Command::new(tool)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
@@ -250,7 +511,7 @@ mod tests {
         formula: CnfFormula,
         binary: bool,
         direct: bool,
-    ) -> Result<bool, Error> {
+    ) -> Result<bool, Box<dyn Error>> {
         let tmp = TempDir::new()?;
 
         let lrat_proof = tmp.path().join("proof.lrat");
@@ -259,7 +520,13 @@ mod tests {
         let mut dimacs = vec![];
         let mut proof = vec![];
 
-        let mut write_lrat = WriteLrat::new(File::create(&lrat_proof)?, binary);
+        let format = if binary {
+            LratFormat::Binary
+        } else {
+            LratFormat::Text
+        };
+
+        let mut write_lrat = WriteLrat::with_format(File::create(&lrat_proof)?, format);
         write_dimacs(&mut File::create(&cnf_file)?, &formula)?;
 
         let mut solver = Solver::new();
@@ -351,6 +618,361 @@ mod tests {
         }
     }
 
+    fn lrat_with_options(options: LratOptions) -> String {
+        let mut lrat = vec![];
+
+        {
+            let mut write_lrat = WriteLrat::with_options(&mut lrat, LratFormat::Text, options);
+            let mut checker = Checker::new();
+            checker.add_processor(&mut write_lrat);
+
+            checker
+                .add_formula(&cnf_formula![
+                    1, 2;
+                    -1, 2;
+                ])
+                .unwrap();
+
+            let hashes = [
+                checker.clause_hash(&lits![1, 2]),
+                checker.clause_hash(&lits![-1, 2]),
+            ];
+
+            for clause in [lits![2, 3], lits![2, 4]].iter() {
+                checker
+                    .check_step(ProofStep::AtClause {
+                        redundant: true,
+                        clause,
+                        propagation_hashes: &hashes[..],
+                    })
+                    .unwrap();
+            }
+
+            for clause in [lits![2, 3], lits![2, 4]].iter() {
+                checker
+                    .check_step(ProofStep::DeleteClause {
+                        clause,
+                        proof: DeleteClauseProof::Redundant,
+                    })
+                    .unwrap();
+            }
+
+            let mut proof = vec![];
+            write_header(&mut proof, &[]).unwrap();
+            write_step(&mut proof, &ProofStep::End).unwrap();
+            checker.check_proof(&proof[..]).unwrap();
+        }
+
+        String::from_utf8(lrat).unwrap()
+    }
+
+    #[test]
+    fn output_options() {
+        let additions = "3 2 3 0 1 2 0\n4 2 4 0 1 2 0\n";
+
+        assert_eq!(
+            lrat_with_options(LratOptions::default()),
+            format!("{}4 d 3 4 0\n", additions)
+        );
+
+        assert_eq!(
+            lrat_with_options(LratOptions {
+                coalesce_deletions: false,
+                ..LratOptions::default()
+            }),
+            format!("{}4 d 3 0\n4 d 4 0\n", additions)
+        );
+
+        assert_eq!(
+            lrat_with_options(LratOptions {
+                deletions: false,
+                final_clauses: true,
+                ..LratOptions::default()
+            }),
+            format!("{}f 1 2 0\n", additions)
+        );
+    }
+
+    #[test]
+    fn end_of_proof_verdict() {
+        let mut lrat = vec![];
+        let mut write_lrat = WriteLrat::new(&mut lrat, false);
+
+        {
+            let mut checker = Checker::new();
+            checker.add_processor(&mut write_lrat);
+
+            checker
+                .add_formula(&cnf_formula![
+                    1, 2;
+                    1;
+                    -1;
+                ])
+                .unwrap();
+
+            let mut proof = vec![];
+            write_header(&mut proof, &[]).unwrap();
+            write_step(&mut proof, &ProofStep::End).unwrap();
+
+            checker.check_proof(&proof[..]).unwrap();
+        }
+
+        assert_eq!(write_lrat.verdict(), Some(ProofVerdict::Unsat));
+    }
+
+    #[test]
+    fn rat_clause_hints() {
+        let mut lrat = vec![];
+
+        {
+            let mut write_lrat = WriteLrat::with_format(&mut lrat, LratFormat::Text);
+            let mut checker = Checker::new();
+            checker.add_processor(&mut write_lrat);
+
+            checker
+                .add_formula(&cnf_formula![
+                    1, 2;
+                    -1, 2;
+                ])
+                .unwrap();
+
+            checker
+                .check_step(ProofStep::ChangeSamplingMode {
+                    var: Var::from_dimacs(3),
+                    sample: false,
+                })
+                .unwrap();
+
+            checker
+                .check_step(ProofStep::RatClause {
+                    clause: &lits![1, 3],
+                    pivot: lit!(3),
+                    resolutions: &[],
+                    propagation_hashes: &[],
+                })
+                .unwrap();
+
+            let resolutions = [(checker.clause_hash(&lits![1, 3]), 1)];
+            let hashes = [checker.clause_hash(&lits![1, 2])];
+
+            checker
+                .check_step(ProofStep::RatClause {
+                    clause: &lits![2, -3],
+                    pivot: lit!(-3),
+                    resolutions: &resolutions[..],
+                    propagation_hashes: &hashes[..],
+                })
+                .unwrap();
+        }
+
+        assert_eq!(
+            std::str::from_utf8(&lrat).unwrap(),
+            "3 3 1 0 0\n4 -3 2 0 -3 1 0\n"
+        );
+
+        let formula = cnf_formula![
+            1, 2;
+            -1, 2;
+        ];
+
+        assert_eq!(
+            read_lrat(&lrat[..], LratFormat::Text).unwrap(),
+            vec![
+                LratStep::Add {
+                    id: 2,
+                    clause: lits![3, 1],
+                    propagations: vec![],
+                    resolutions: vec![],
+                },
+                LratStep::Add {
+                    id: 3,
+                    clause: lits![-3, 2],
+                    propagations: vec![],
+                    resolutions: vec![(2, vec![0])],
+                },
+            ]
+        );
+
+        assert!(!crate::check_lrat(&formula, &lrat[..], LratFormat::Text).unwrap());
+
+        let missing_hint = b"3 3 1 0 0\n4 -3 2 0 0\n";
+        assert!(matches!(
+            crate::check_lrat(&formula, &missing_hint[..], LratFormat::Text),
+            Err(LratCheckError::MissingResolutionHint { .. })
+        ));
+    }
+
+    #[test]
+    fn convert_drat() {
+        let formula = cnf_formula![
+            1, 2;
+            -1, 2;
+            1, -2;
+            -1, -2;
+        ];
+
+        let mut dimacs = vec![];
+        write_dimacs(&mut dimacs, &formula).unwrap();
+
+        let drat = b"1 2 3 0\n1 0\nd 1 2 3 0\n0\n";
+
+        for &format in [LratFormat::Text, LratFormat::Binary, LratFormat::Delta].iter() {
+            let mut lrat = vec![];
+            drat_to_lrat(&dimacs[..], &drat[..], DratFormat::Text, &mut lrat, format).unwrap();
+
+            let steps = read_lrat(&lrat[..], format).unwrap();
+            assert!(steps.iter().all(|step| match step {
+                LratStep::Add { clause, .. } => !clause.contains(&lit!(3)),
+                _ => true,
+            }));
+
+            assert!(crate::check_lrat(&formula, &lrat[..], format).unwrap());
+        }
+
+        let mut lrat = vec![];
+        assert!(drat_to_lrat(
+            &dimacs[..],
+            &b"1 0\n"[..],
+            DratFormat::Text,
+            &mut lrat,
+            LratFormat::Text
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn check_invalid_lrat() {
+        let formula = cnf_formula![
+            1, 2;
+            -1, 2;
+            -2;
+        ];
+
+        assert!(
+            crate::check_lrat(&formula, &b"4 2 0 1 2 0\n5 0 4 3 0\n"[..], LratFormat::Text)
+                .unwrap()
+        );
+
+        for proof in [
+            &b"4 2 0 1 0\n"[..],
+            &b"4 2 0 5 2 0\n"[..],
+            &b"3 2 0 1 2 0\n"[..],
+            &b"4 0 3 0\n"[..],
+            &b"3 d 4 0\n"[..],
+            &b"4 2 0 1 2\n"[..],
+        ]
+        .iter()
+        {
+            assert!(
+                crate::check_lrat(&formula, *proof, LratFormat::Text).is_err(),
+                "{:?}",
+                std::str::from_utf8(proof)
+            );
+        }
+    }
+
+    /// A decoded binary LRAT step.
+    #[derive(PartialEq, Eq, Debug)]
+    enum DecodedStep {
+        Add {
+            id: u64,
+            lits: Vec<usize>,
+            hints: Vec<u64>,
+        },
+        Delete {
+            ids: Vec<u64>,
+        },
+    }
+
+    fn decode_binary(mut proof: &[u8]) -> Vec<DecodedStep> {
+        let mut steps = vec![];
+        let read = |proof: &mut &[u8]| leb128::read::unsigned(proof).unwrap();
+
+        while let Some((&code, rest)) = proof.split_first() {
+            proof = rest;
+            if code == b'a' {
+                let id = read(&mut proof) / 2;
+                let mut lits = vec![];
+                loop {
+                    match read(&mut proof) {
+                        0 => break,
+                        value => lits.push(value as usize - 2),
+                    }
+                }
+                let mut hints = vec![];
+                loop {
+                    match read(&mut proof) {
+                        0 => break,
+                        value => hints.push(value / 2),
+                    }
+                }
+                steps.push(DecodedStep::Add { id, lits, hints });
+            } else {
+                let mut ids = vec![];
+                loop {
+                    match read(&mut proof) {
+                        0 => break,
+                        value => ids.push(value / 2),
+                    }
+                }
+                steps.push(DecodedStep::Delete { ids });
+            }
+        }
+        steps
+    }
+
+    fn decode_delta(mut proof: &[u8]) -> Vec<DecodedStep> {
+        let mut steps = vec![];
+        let mut last_id = 0u64;
+
+        let read = |proof: &mut &[u8]| varisat_internal_proof::vli_enc::read_u64(proof).unwrap();
+
+        let mut read_id = |value: u64| {
+            let zigzag = value - 1;
+            let delta = ((zigzag >> 1) as i64) ^ -((zigzag & 1) as i64);
+            last_id = last_id.wrapping_add(delta as u64);
+            last_id
+        };
+
+        while let Some((&code, rest)) = proof.split_first() {
+            proof = rest;
+            if code == b'a' {
+                let id = read_id(read(&mut proof));
+                let mut lits: Vec<usize> = vec![];
+                loop {
+                    match read(&mut proof) {
+                        0 => break,
+                        value => {
+                            let lit = match lits.last() {
+                                Some(&previous) => previous + value as usize,
+                                None => value as usize - 1,
+                            };
+                            lits.push(lit);
+                        }
+                    }
+                }
+                let mut hints = vec![];
+                loop {
+                    match read(&mut proof) {
+                        0 => break,
+                        value => hints.push(read_id(value)),
+                    }
+                }
+                steps.push(DecodedStep::Add { id, lits, hints });
+            } else {
+                let mut ids = vec![];
+                loop {
+                    match read(&mut proof) {
+                        0 => break,
+                        value => ids.push(read_id(value)),
+                    }
+                }
+                steps.push(DecodedStep::Delete { ids });
+            }
+        }
+        steps
+    }
+
     proptest! {
 
         #[cfg_attr(not(test_check_lrat), ignore)]
@@ -362,5 +984,68 @@ mod tests {
         ) {
             prop_assert!(solve_and_check_lrat(formula, binary, direct).unwrap());
         }
+
+        #[test]
+        fn sgen_unsat_delta_matches_binary(
+            formula in sgen_unsat_formula(1..7usize),
+        ) {
+            let mut proof = vec![];
+            let mut binary_proof = vec![];
+            let mut delta_proof = vec![];
+
+            let mut solver = Solver::new();
+            solver.write_proof(&mut proof, ProofFormat::Varisat);
+            solver.add_formula(&formula);
+            prop_assert_eq!(solver.solve().ok(), Some(false));
+            solver.close_proof().unwrap();
+            drop(solver);
+
+            {
+                let mut write_binary = WriteLrat::with_format(&mut binary_proof, LratFormat::Binary);
+                let mut write_delta = WriteLrat::with_format(&mut delta_proof, LratFormat::Delta);
+
+                let mut checker = Checker::new();
+                checker.add_processor(&mut write_binary);
+                checker.add_processor(&mut write_delta);
+                checker.add_formula(&formula).unwrap();
+                checker.check_proof(&mut &proof[..]).unwrap();
+            }
+
+            prop_assert_eq!(decode_binary(&binary_proof), decode_delta(&delta_proof));
+        }
+
+        #[test]
+        fn sgen_unsat_lrat_recheck(
+            formula in sgen_unsat_formula(1..7usize),
+            final_clauses in proptest::bool::ANY,
+        ) {
+            let mut proof = vec![];
+
+            let mut solver = Solver::new();
+            solver.write_proof(&mut proof, ProofFormat::Varisat);
+            solver.add_formula(&formula);
+            prop_assert_eq!(solver.solve().ok(), Some(false));
+            solver.close_proof().unwrap();
+            drop(solver);
+
+            let options = LratOptions {
+                final_clauses,
+                ..LratOptions::default()
+            };
+
+            for &format in [LratFormat::Text, LratFormat::Binary, LratFormat::Delta].iter() {
+                let mut lrat = vec![];
+                {
+                    let mut write_lrat = WriteLrat::with_options(&mut lrat, format, options);
+
+                    let mut checker = Checker::new();
+                    checker.add_processor(&mut write_lrat);
+                    checker.add_formula(&formula).unwrap();
+                    checker.check_proof(&mut &proof[..]).unwrap();
+                }
+
+                prop_assert!(crate::check_lrat(&formula, &lrat[..], format).unwrap());
+            }
+        }
     }
 }
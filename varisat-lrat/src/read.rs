@@ -0,0 +1,342 @@
+//! Reading of LRAT proofs.
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Read};
+
+use thiserror::Error;
+
+use varisat_formula::{Lit, Var};
+use varisat_internal_proof::vli_enc::read_u64;
+
+use crate::LratFormat;
+
+/// Errors while parsing an LRAT proof.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum LratParseError {
+    #[error("invalid LRAT step code {code:?}")]
+    InvalidStepCode { code: u8 },
+    #[error("invalid clause id 0")]
+    ZeroClauseId,
+    #[error("invalid clause id {token:?} in proof")]
+    InvalidClauseId { token: String },
+    #[error("unexpected negative clause id {id}")]
+    NegativeClauseId { id: i64 },
+    #[error("invalid number {token:?} in proof")]
+    InvalidNumber { token: String },
+    #[error("number too large")]
+    NumberTooLarge,
+    #[error("literal {lit} is out of range")]
+    LiteralOutOfRange { lit: i64 },
+    #[error("invalid literal code {code}")]
+    InvalidLiteralCode { code: i64 },
+    #[error("literal code {code} is out of range")]
+    LiteralCodeOutOfRange { code: u64 },
+    #[error("unexpected end of proof")]
+    UnexpectedEnd,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// A step of an LRAT proof.
+///
+/// Clause ids use the numbering of [`CheckedProofStep`](varisat_checker::CheckedProofStep), i.e.
+/// they are one less than the ids stored in the proof file. The clauses of the input formula have
+/// the ids `0..n`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum LratStep {
+    /// Addition of a clause.
+    ///
+    /// The `propagations` are the unit propagation hints applied before the first resolution
+    /// hint. For RAT clauses, the first literal of the clause is the pivot and `resolutions`
+    /// contains the ids of the resolved clauses, each together with the propagations showing that
+    /// the resolvent is an asymmetric tautology.
+    Add {
+        id: u64,
+        clause: Vec<Lit>,
+        propagations: Vec<u64>,
+        resolutions: Vec<(u64, Vec<u64>)>,
+    },
+    /// Deletion of clauses.
+    Delete { ids: Vec<u64> },
+    /// Ids of all clauses present after the last step.
+    ///
+    /// This is only written by [`WriteLrat`](crate::WriteLrat) when
+    /// [`LratOptions::final_clauses`](crate::LratOptions::final_clauses) is enabled.
+    Final { ids: Vec<u64> },
+}
+
+/// Parser for LRAT proofs.
+pub struct LratReader<R> {
+    format: LratFormat,
+    input: R,
+    /// Remaining tokens of the current line, for the textual format.
+    tokens: VecDeque<String>,
+    /// Previously read clause id as stored in the proof.
+    last_read_id: u64,
+}
+
+impl<R: BufRead> LratReader<R> {
+    /// Create a parser reading a proof in the given encoding.
+    pub fn new(input: R, format: LratFormat) -> LratReader<R> {
+        LratReader {
+            format,
+            input,
+            tokens: VecDeque::new(),
+            last_read_id: 0,
+        }
+    }
+
+    /// Parse the next step of the proof.
+    ///
+    /// Returns `None` at the end of the proof.
+    pub fn next_step(&mut self) -> Result<Option<LratStep>, LratParseError> {
+        let code = match self.format {
+            LratFormat::Text => {
+                let token = match self.next_token()? {
+                    None => return Ok(None),
+                    Some(token) => token,
+                };
+                if token == "f" {
+                    b'f'
+                } else {
+                    let id = parse_id(&token)?;
+                    self.last_read_id = id;
+                    match self.peek_token()? {
+                        Some("d") => {
+                            self.tokens.pop_front();
+                            b'd'
+                        }
+                        _ => b'a',
+                    }
+                }
+            }
+            LratFormat::Binary | LratFormat::Delta => {
+                let mut code = [0];
+                if self.input.fill_buf()?.is_empty() {
+                    return Ok(None);
+                }
+                self.input.read_exact(&mut code)?;
+                code[0]
+            }
+        };
+
+        let step = match code {
+            b'a' => {
+                let id = match self.format {
+                    LratFormat::Text => self.last_read_id,
+                    _ => self.read_id()?,
+                };
+                if id == 0 {
+                    return Err(LratParseError::ZeroClauseId);
+                }
+                let clause = self.read_lits()?;
+                let (propagations, resolutions) = self.read_hints()?;
+                LratStep::Add {
+                    id: id - 1,
+                    clause,
+                    propagations,
+                    resolutions,
+                }
+            }
+            b'd' => LratStep::Delete {
+                ids: self.read_ids()?,
+            },
+            b'f' => LratStep::Final {
+                ids: self.read_ids()?,
+            },
+            _ => return Err(LratParseError::InvalidStepCode { code }),
+        };
+
+        Ok(Some(step))
+    }
+
+    /// Read the next whitespace separated token of the textual format.
+    fn next_token(&mut self) -> io::Result<Option<String>> {
+        self.fill_tokens()?;
+        Ok(self.tokens.pop_front())
+    }
+
+    /// Return the next token of the textual format without consuming it.
+    fn peek_token(&mut self) -> io::Result<Option<&str>> {
+        self.fill_tokens()?;
+        Ok(self.tokens.front().map(|token| &token[..]))
+    }
+
+    /// Read lines until a token is available or the input ends.
+    fn fill_tokens(&mut self) -> io::Result<()> {
+        let mut line = String::new();
+        while self.tokens.is_empty() {
+            line.clear();
+            if self.input.read_line(&mut line)? == 0 {
+                break;
+            }
+            if line.starts_with('c') {
+                continue;
+            }
+            self.tokens
+                .extend(line.split_whitespace().map(str::to_owned));
+        }
+        Ok(())
+    }
+
+    /// Read a single number, returning 0 for a terminator.
+    ///
+    /// In the textual format the number is signed, in the binary formats it is the raw encoded
+    /// value.
+    fn read_value(&mut self) -> Result<i64, LratParseError> {
+        match self.format {
+            LratFormat::Text => {
+                let token = self.next_token()?.ok_or(LratParseError::UnexpectedEnd)?;
+                token
+                    .parse()
+                    .map_err(|_| LratParseError::InvalidNumber { token })
+            }
+            LratFormat::Binary => match leb128::read::unsigned(&mut self.input) {
+                Ok(value) => Ok(value as i64),
+                Err(leb128::read::Error::IoError(err)) => Err(err.into()),
+                Err(leb128::read::Error::Overflow) => Err(LratParseError::NumberTooLarge),
+            },
+            LratFormat::Delta => Ok(read_u64(&mut self.input)? as i64),
+        }
+    }
+
+    /// Read a clause id as stored in the proof.
+    fn read_id(&mut self) -> Result<u64, LratParseError> {
+        let value = self.read_value()?;
+        self.decode_id(value)
+    }
+
+    /// Decode a nonzero value as a clause id as stored in the proof.
+    fn decode_id(&mut self, value: i64) -> Result<u64, LratParseError> {
+        match self.format {
+            LratFormat::Text => {
+                if value < 0 {
+                    return Err(LratParseError::NegativeClauseId { id: value });
+                }
+                Ok(value as u64)
+            }
+            LratFormat::Binary => {
+                if value & 1 != 0 {
+                    return Err(LratParseError::NegativeClauseId { id: value / 2 });
+                }
+                Ok(value as u64 / 2)
+            }
+            LratFormat::Delta => {
+                let zigzag = value as u64 - 1;
+                let delta = ((zigzag >> 1) as i64) ^ -((zigzag & 1) as i64);
+                self.last_read_id = self.last_read_id.wrapping_add(delta as u64);
+                Ok(self.last_read_id)
+            }
+        }
+    }
+
+    /// Read a zero terminated list of clause ids, converted to checker ids.
+    fn read_ids(&mut self) -> Result<Vec<u64>, LratParseError> {
+        let mut ids = vec![];
+        loop {
+            match self.read_value()? {
+                0 => break,
+                value => ids.push(checker_id(self.decode_id(value)?)?),
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Read a zero terminated list of literals.
+    fn read_lits(&mut self) -> Result<Vec<Lit>, LratParseError> {
+        let mut lits: Vec<Lit> = vec![];
+        loop {
+            let value = self.read_value()?;
+            if value == 0 {
+                break;
+            }
+            let lit = match self.format {
+                LratFormat::Text => {
+                    if value.abs() > Var::max_count() as i64 {
+                        return Err(LratParseError::LiteralOutOfRange { lit: value });
+                    }
+                    Lit::from_dimacs(value as isize)
+                }
+                LratFormat::Binary => {
+                    if value < 2 {
+                        return Err(LratParseError::InvalidLiteralCode { code: value });
+                    }
+                    lit_from_code(value as u64 - 2)?
+                }
+                LratFormat::Delta => lit_from_code(match lits.last() {
+                    Some(previous) => previous.code() as u64 + value as u64,
+                    None => value as u64 - 1,
+                })?,
+            };
+            lits.push(lit);
+        }
+        Ok(lits)
+    }
+
+    /// Read the zero terminated hints of a clause addition.
+    fn read_hints(&mut self) -> Result<(Vec<u64>, Vec<(u64, Vec<u64>)>), LratParseError> {
+        let mut propagations = vec![];
+        let mut resolutions: Vec<(u64, Vec<u64>)> = vec![];
+        loop {
+            let value = self.read_value()?;
+            if value == 0 {
+                break;
+            }
+            let negative = match self.format {
+                LratFormat::Text => value < 0,
+                LratFormat::Binary => value & 1 != 0,
+                LratFormat::Delta => false,
+            };
+            if negative {
+                let id = match self.format {
+                    LratFormat::Text => -value as u64,
+                    _ => value as u64 / 2,
+                };
+                resolutions.push((checker_id(id)?, vec![]));
+            } else {
+                let id = checker_id(self.decode_id(value)?)?;
+                match resolutions.last_mut() {
+                    Some((_, resolution_propagations)) => resolution_propagations.push(id),
+                    None => propagations.push(id),
+                }
+            }
+        }
+        Ok((propagations, resolutions))
+    }
+}
+
+impl<R: BufRead> Iterator for LratReader<R> {
+    type Item = Result<LratStep, LratParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_step().transpose()
+    }
+}
+
+/// Parse all steps of an LRAT proof.
+pub fn read_lrat(input: impl Read, format: LratFormat) -> Result<Vec<LratStep>, LratParseError> {
+    LratReader::new(io::BufReader::new(input), format).collect()
+}
+
+/// Convert a clause id as stored in the proof to a checker id.
+fn checker_id(id: u64) -> Result<u64, LratParseError> {
+    if id == 0 {
+        return Err(LratParseError::ZeroClauseId);
+    }
+    Ok(id - 1)
+}
+
+/// Parse a positive clause id of the textual format.
+fn parse_id(token: &str) -> Result<u64, LratParseError> {
+    token.parse().map_err(|_| LratParseError::InvalidClauseId {
+        token: token.to_owned(),
+    })
+}
+
+/// Convert a literal code of the binary formats.
+fn lit_from_code(code: u64) -> Result<Lit, LratParseError> {
+    if code / 2 >= Var::max_count() as u64 {
+        return Err(LratParseError::LiteralCodeOutOfRange { code });
+    }
+    Ok(Lit::from_code(code as usize))
+}
@@ -1,9 +1,9 @@
-use failure::{ensure, Error};
 use std::env;
+use std::error::Error;
 use std::process::Command;
 use std::str::from_utf8;
 
-fn have_drat_trim() -> Result<(), Error> {
+fn have_drat_trim() -> Result<(), Box<dyn Error>> {
     println!("rerun-if-env-changed=VARISAT_HAVE_DRAT_TRIM");
     if env::var("VARISAT_HAVE_DRAT_TRIM").is_ok() {
         return Ok(());
@@ -12,15 +12,14 @@ fn have_drat_trim() -> Result<(), Error> {
     let output = Command::new("drat-trim").output()?;
     let stdout = from_utf8(&output.stdout)?;
 
-    ensure!(
-        stdout.contains("force binary proof parse mode"),
-        "no force binary proof option found"
-    );
+    if !stdout.contains("force binary proof parse mode") {
+        return Err("no force binary proof option found".into());
+    }
 
     Ok(())
 }
 
-fn have_rate() -> Result<(), Error> {
+fn have_rate() -> Result<(), Box<dyn Error>> {
     println!("rerun-if-env-changed=VARISAT_HAVE_RATE");
     if env::var("VARISAT_HAVE_RATE").is_ok() {
         return Ok(());
@@ -1,13 +1,16 @@
 //! Incremental solving.
+use std::collections::BTreeSet;
+
+use hashbrown::HashMap;
 
 use partial_ref::{partial, PartialRef};
 
-use varisat_formula::Lit;
+use varisat_formula::{Lbool, Lit, Var};
 use varisat_internal_proof::{clause_hash, lit_hash, ClauseHash, ProofStep};
 
 use crate::context::{parts::*, Context};
 use crate::proof;
-use crate::prop::{enqueue_assignment, full_restart, Reason};
+use crate::prop::{enqueue_assignment, full_restart, propagate, Reason};
 use crate::state::SatState;
 use crate::variables;
 
@@ -19,6 +22,16 @@ pub struct Assumptions {
     user_failed_core: Vec<Lit>,
     assumption_levels: usize,
     failed_propagation_hashes: Vec<ClauseHash>,
+    /// Assumptions as given by the user, without activation literals.
+    user_assumptions: Vec<Lit>,
+    /// User variables that activate removable clauses when assumed.
+    activations: BTreeSet<Var>,
+    /// Activation variables of the failed core.
+    failed_activations: Vec<Var>,
+    /// Activation variables of clause groups.
+    group_activations: HashMap<usize, Var>,
+    /// Clause groups by activation variable.
+    groups: HashMap<Var, usize>,
 }
 
 impl Assumptions {
@@ -38,10 +51,35 @@ impl Assumptions {
     }
 
     /// Subset of assumptions that made the formula unsatisfiable.
+    ///
+    /// This uses user variable names and excludes activation literals.
     pub fn user_failed_core(&self) -> &[Lit] {
         &self.user_failed_core
     }
 
+    /// Activation variables of removable clauses involved in the failed core.
+    pub fn failed_activations(&self) -> &[Var] {
+        &self.failed_activations
+    }
+
+    /// Whether a user variable is used to activate removable clauses.
+    pub fn is_activation(&self, user_var: Var) -> bool {
+        self.activations.contains(&user_var)
+    }
+
+    /// Activation variable for a clause group, if the group exists.
+    pub fn group_activation(&self, group: usize) -> Option<Var> {
+        self.group_activations.get(&group).cloned()
+    }
+
+    /// Clause groups involved in the failed core.
+    pub fn failed_groups(&self) -> Vec<usize> {
+        self.failed_activations
+            .iter()
+            .filter_map(|var| self.groups.get(var).cloned())
+            .collect()
+    }
+
     /// Current assumptions.
     pub fn assumptions(&self) -> &[Lit] {
         &self.assumptions
@@ -75,6 +113,112 @@ pub fn set_assumptions<'a>(
         mut WatchlistsP,
     ),
     user_assumptions: &[Lit],
+) {
+    let assumptions = ctx.part_mut(AssumptionsP);
+    assumptions.user_assumptions.clear();
+    assumptions
+        .user_assumptions
+        .extend_from_slice(user_assumptions);
+
+    update_assumptions(ctx.borrow());
+}
+
+/// Make a user variable activate removable clauses.
+///
+/// The variable is assumed to be true for all future calls to solve, until it is deactivated.
+pub fn add_activation<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut AnalyzeConflictP,
+        mut AssignmentP,
+        mut AssumptionsP,
+        mut BinaryClausesP,
+        mut ImplGraphP,
+        mut ProofP<'a>,
+        mut SolverStateP,
+        mut TmpFlagsP,
+        mut TrailP,
+        mut VariablesP,
+        mut VsidsP,
+        mut WatchlistsP,
+    ),
+    user_var: Var,
+) {
+    ctx.part_mut(AssumptionsP).activations.insert(user_var);
+    update_assumptions(ctx.borrow());
+}
+
+/// Use a user variable to activate the clauses of a clause group.
+pub fn add_group_activation<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut AnalyzeConflictP,
+        mut AssignmentP,
+        mut AssumptionsP,
+        mut BinaryClausesP,
+        mut ImplGraphP,
+        mut ProofP<'a>,
+        mut SolverStateP,
+        mut TmpFlagsP,
+        mut TrailP,
+        mut VariablesP,
+        mut VsidsP,
+        mut WatchlistsP,
+    ),
+    group: usize,
+    user_var: Var,
+) {
+    let assumptions = ctx.part_mut(AssumptionsP);
+    assumptions.group_activations.insert(group, user_var);
+    assumptions.groups.insert(user_var, group);
+    add_activation(ctx.borrow(), user_var);
+}
+
+/// Stop assuming an activation variable.
+///
+/// Panics if the variable was not activating any clauses.
+pub fn remove_activation<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut AnalyzeConflictP,
+        mut AssignmentP,
+        mut AssumptionsP,
+        mut BinaryClausesP,
+        mut ImplGraphP,
+        mut ProofP<'a>,
+        mut SolverStateP,
+        mut TmpFlagsP,
+        mut TrailP,
+        mut VariablesP,
+        mut VsidsP,
+        mut WatchlistsP,
+    ),
+    user_var: Var,
+) {
+    assert!(
+        ctx.part_mut(AssumptionsP).activations.remove(&user_var),
+        "clause handle was already removed"
+    );
+    update_assumptions(ctx.borrow());
+}
+
+/// Recompute the assumptions from the user assumptions and the activation variables.
+fn update_assumptions<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut AnalyzeConflictP,
+        mut AssignmentP,
+        mut AssumptionsP,
+        mut BinaryClausesP,
+        mut ImplGraphP,
+        mut ProofP<'a>,
+        mut SolverStateP,
+        mut TmpFlagsP,
+        mut TrailP,
+        mut VariablesP,
+        mut VsidsP,
+        mut WatchlistsP,
+    ),
 ) {
     full_restart(ctx.borrow());
 
@@ -94,10 +238,17 @@ pub fn set_assumptions<'a>(
             .assumed = false;
     }
 
+    let user_assumptions: Vec<Lit> = assumptions
+        .user_assumptions
+        .iter()
+        .cloned()
+        .chain(assumptions.activations.iter().map(|var| var.positive()))
+        .collect();
+
     variables::solver_from_user_lits(
         ctx_2.borrow(),
         &mut assumptions.assumptions,
-        user_assumptions,
+        &user_assumptions,
         true,
     );
 
@@ -234,13 +385,17 @@ fn analyze_assumption_conflict<'a>(
     assumptions.failed_propagation_hashes.reverse();
 
     assumptions.user_failed_core.clear();
-    assumptions
-        .user_failed_core
-        .extend(assumptions.failed_core.iter().map(|solver_lit| {
-            let user_lit = solver_lit
-                .map_var(|solver_var| ctx.part(VariablesP).existing_user_from_solver(solver_var));
-            user_lit
-        }));
+    assumptions.failed_activations.clear();
+
+    for solver_lit in assumptions.failed_core.iter() {
+        let user_lit = solver_lit
+            .map_var(|solver_var| ctx.part(VariablesP).existing_user_from_solver(solver_var));
+        if assumptions.activations.contains(&user_lit.var()) {
+            assumptions.failed_activations.push(user_lit.var());
+        } else {
+            assumptions.user_failed_core.push(user_lit);
+        }
+    }
 
     proof::add_step(
         ctx.borrow(),
@@ -252,6 +407,225 @@ fn analyze_assumption_conflict<'a>(
     );
 }
 
+/// Result of [`propagate_assumptions`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum PropagationResult {
+    /// Literals implied by the assumptions.
+    ///
+    /// This includes the assumptions themselves, but not literals that are implied by the formula
+    /// alone.
+    Implied(Vec<Lit>),
+    /// The assumptions result in a conflict.
+    ///
+    /// Contains a subset of the assumptions that is already conflicting. This is empty when the
+    /// formula itself is unsatisfiable.
+    Conflict(Vec<Lit>),
+}
+
+impl PropagationResult {
+    /// Value of a literal after propagating the assumptions.
+    ///
+    /// Implied literals are `True` and their negations `False`. All other literals, including
+    /// literals implied by the formula alone, are `Undef`, as are all literals after a conflict.
+    pub fn lit_value(&self, lit: Lit) -> Lbool {
+        match self {
+            PropagationResult::Implied(implied) => {
+                if implied.contains(&lit) {
+                    Lbool::True
+                } else if implied.contains(&!lit) {
+                    Lbool::False
+                } else {
+                    Lbool::Undef
+                }
+            }
+            PropagationResult::Conflict(_) => Lbool::Undef,
+        }
+    }
+}
+
+/// Enqueue the given assumptions and perform unit propagation without any search.
+///
+/// The input and output use user variable names. This does not change the currently active
+/// assumptions and undoes all assignments except those on the top level before returning.
+pub fn propagate_assumptions<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut AnalyzeConflictP,
+        mut AssignmentP,
+        mut AssumptionsP,
+        mut BinaryClausesP,
+        mut ClauseAllocP,
+        mut ImplGraphP,
+        mut ProofP<'a>,
+        mut SolverStateP,
+        mut TmpDataP,
+        mut TmpFlagsP,
+        mut TrailP,
+        mut VariablesP,
+        mut VsidsP,
+        mut WatchlistsP,
+        ClauseDbP,
+    ),
+    user_assumptions: &[Lit],
+) -> PropagationResult {
+    propagate_user_lits(ctx.borrow(), user_assumptions, true)
+}
+
+/// Enqueue the given literals and perform unit propagation without any search.
+///
+/// Like [`propagate_assumptions`], but the activation literals are only assumed when
+/// `with_activations` is set. Without them, a conflict only depends on the clauses that can't be
+/// removed.
+pub fn propagate_user_lits<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut AnalyzeConflictP,
+        mut AssignmentP,
+        mut AssumptionsP,
+        mut BinaryClausesP,
+        mut ClauseAllocP,
+        mut ImplGraphP,
+        mut ProofP<'a>,
+        mut SolverStateP,
+        mut TmpDataP,
+        mut TmpFlagsP,
+        mut TrailP,
+        mut VariablesP,
+        mut VsidsP,
+        mut WatchlistsP,
+        ClauseDbP,
+    ),
+    user_assumptions: &[Lit],
+    with_activations: bool,
+) -> PropagationResult {
+    full_restart(ctx.borrow());
+
+    if ctx.part(SolverStateP).sat_state == SatState::Unsat {
+        return PropagationResult::Conflict(vec![]);
+    }
+
+    if propagate(ctx.borrow()).is_err() {
+        // We don't derive the empty clause here, so the next search has to find this conflict again.
+        ctx.part_mut(TrailP).reset_queue();
+        return PropagationResult::Conflict(vec![]);
+    }
+
+    let user_assumptions: Vec<Lit> = ctx
+        .part(AssumptionsP)
+        .activations
+        .iter()
+        .filter(|_| with_activations)
+        .map(|var| var.positive())
+        .chain(user_assumptions.iter().cloned())
+        .collect();
+
+    let (tmp, mut ctx) = ctx.split_part_mut(TmpDataP);
+
+    variables::solver_from_user_lits(ctx.borrow(), &mut tmp.lits, &user_assumptions, true);
+
+    let top_level_len = ctx.part(TrailP).trail().len();
+
+    let mut conflict = None;
+
+    for &assumption in tmp.lits.iter() {
+        match ctx.part(AssignmentP).lit_value(assumption) {
+            Some(true) => (),
+            Some(false) => {
+                tmp.lits_2.clear();
+                tmp.lits_2.push(assumption);
+                conflict = Some(vec![assumption]);
+                break;
+            }
+            None => {
+                ctx.part_mut(TrailP).new_decision_level();
+                enqueue_assignment(ctx.borrow(), assumption, Reason::Unit);
+                if let Err(prop_conflict) = propagate(ctx.borrow()) {
+                    tmp.lits_2.clear();
+                    tmp.lits_2
+                        .extend_from_slice(prop_conflict.lits(&ctx.borrow()));
+                    conflict = Some(vec![]);
+                    break;
+                }
+            }
+        }
+    }
+
+    let result = if let Some(mut core) = conflict {
+        propagation_core(ctx.borrow(), &tmp.lits_2, &mut core);
+        let variables = ctx.part(VariablesP);
+        let assumptions = ctx.part(AssumptionsP);
+        for lit in core.iter_mut() {
+            *lit = lit.map_var(|solver_var| variables.existing_user_from_solver(solver_var));
+        }
+        core.retain(|lit| !assumptions.is_activation(lit.var()));
+        PropagationResult::Conflict(core)
+    } else {
+        let variables = ctx.part(VariablesP);
+        let assumptions = ctx.part(AssumptionsP);
+        let implied = ctx.part(TrailP).trail()[top_level_len..]
+            .iter()
+            .filter_map(|&solver_lit| {
+                let global = variables.global_from_solver().get(solver_lit.var())?;
+                let user = variables.user_from_global().get(global)?;
+                if assumptions.is_activation(user) {
+                    return None;
+                }
+                Some(user.lit(solver_lit.is_positive()))
+            })
+            .collect();
+        PropagationResult::Implied(implied)
+    };
+
+    full_restart(ctx.borrow());
+
+    result
+}
+
+/// Find the assumptions that caused a set of literals to be false.
+///
+/// The found assumptions are appended to `core`. Literals assigned on the top level are ignored.
+fn propagation_core(
+    mut ctx: partial!(Context, mut TmpFlagsP, ClauseAllocP, ImplGraphP, TrailP),
+    false_lits: &[Lit],
+    core: &mut Vec<Lit>,
+) {
+    let (tmp, mut ctx) = ctx.split_part_mut(TmpFlagsP);
+    let (trail, mut ctx) = ctx.split_part(TrailP);
+    let (impl_graph, mut ctx) = ctx.split_part(ImplGraphP);
+
+    let flags = &mut tmp.flags;
+    let mut flag_count = 0;
+
+    for &lit in false_lits {
+        if impl_graph.level(lit.var()) > 0 && !flags[lit.index()] {
+            flags[lit.index()] = true;
+            flag_count += 1;
+        }
+    }
+
+    for &lit in trail.trail().iter().rev() {
+        if flag_count == 0 {
+            break;
+        }
+        if flags[lit.index()] {
+            flags[lit.index()] = false;
+            flag_count -= 1;
+
+            match impl_graph.reason(lit.var()) {
+                Reason::Unit => core.push(lit),
+                reason => {
+                    for &reason_lit in reason.lits(&ctx.borrow()) {
+                        if impl_graph.level(reason_lit.var()) > 0 && !flags[reason_lit.index()] {
+                            flags[reason_lit.index()] = true;
+                            flag_count += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,5 +1,7 @@
 //! Binary clauses.
 
+use std::mem::size_of;
+
 use partial_ref::{partial, PartialRef};
 
 use varisat_formula::Lit;
@@ -38,6 +40,16 @@ impl BinaryClauses {
     pub fn count(&self) -> usize {
         self.count
     }
+
+    /// Heap memory used for storing binary clauses in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.by_lit.capacity() * size_of::<Vec<Lit>>()
+            + self
+                .by_lit
+                .iter()
+                .map(|implied| implied.capacity() * size_of::<Lit>())
+                .sum::<usize>()
+    }
 }
 
 /// Remove binary clauses that have an assigned literal.
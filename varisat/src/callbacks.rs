@@ -0,0 +1,90 @@
+//! User provided callbacks.
+
+/// Statistics about the current state of the search.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct SolverStats {
+    /// Number of conflicts since the solver was created.
+    pub conflicts: u64,
+    /// Number of restarts since the solver was created.
+    pub restarts: u64,
+    /// Number of variables not assigned at the top level.
+    pub vars: usize,
+    /// Number of binary clauses.
+    pub binary_clauses: usize,
+    /// Number of irredundant long clauses.
+    pub irred_clauses: usize,
+    /// Number of learned long clauses in the core tier.
+    pub core_clauses: usize,
+    /// Number of learned long clauses in the mid tier.
+    pub mid_clauses: usize,
+    /// Number of learned long clauses in the local tier.
+    pub local_clauses: usize,
+}
+
+/// Events reported to a progress callback.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[non_exhaustive]
+pub enum SolverEvent {
+    /// Periodic progress report.
+    Progress,
+    /// The search was restarted.
+    Restart,
+    /// The local tier of learned clauses was reduced.
+    ReduceLocals,
+    /// The mid tier of learned clauses was reduced.
+    ReduceMids,
+    /// The formula was simplified using newly found unit clauses.
+    UnitSimplify,
+}
+
+/// User provided callbacks invoked during solving.
+#[derive(Default)]
+pub struct Callbacks<'a> {
+    /// Polled at every conflict, solving is interrupted when this returns true.
+    terminate: Option<Box<dyn FnMut() -> bool + 'a>>,
+    /// Invoked for every [`SolverEvent`].
+    event: Option<Box<dyn FnMut(SolverEvent, &SolverStats) + 'a>>,
+    /// Statistics passed to the event callback.
+    ///
+    /// Only kept up to date while an event callback is set.
+    stats: SolverStats,
+}
+
+impl<'a> Callbacks<'a> {
+    /// Set or remove the termination callback.
+    pub fn set_terminate(&mut self, callback: Option<Box<dyn FnMut() -> bool + 'a>>) {
+        self.terminate = callback;
+    }
+
+    /// Whether the user requested termination of the current solve call.
+    pub fn should_terminate(&mut self) -> bool {
+        match &mut self.terminate {
+            Some(terminate) => terminate(),
+            None => false,
+        }
+    }
+
+    /// Set or remove the event callback.
+    pub fn set_event(&mut self, callback: Option<Box<dyn FnMut(SolverEvent, &SolverStats) + 'a>>) {
+        self.event = callback;
+    }
+
+    /// Whether an event callback is set.
+    ///
+    /// Used to avoid collecting statistics when nobody is listening.
+    pub fn wants_events(&self) -> bool {
+        self.event.is_some()
+    }
+
+    /// Mutable access to the statistics passed to the event callback.
+    pub fn stats_mut(&mut self) -> &mut SolverStats {
+        &mut self.stats
+    }
+
+    /// Invoke the event callback, if present.
+    pub fn emit(&mut self, event: SolverEvent) {
+        if let Some(callback) = &mut self.event {
+            callback(event, &self.stats);
+        }
+    }
+}
@@ -6,6 +6,7 @@ use varisat_internal_proof::ProofStep;
 
 use crate::analyze_conflict::analyze_conflict;
 use crate::assumptions::{enqueue_assumption, EnqueueAssumption};
+use crate::callbacks::SolverEvent;
 use crate::clause::{assess_learned_clause, bump_clause, db, decay_clause_activities};
 use crate::context::{parts::*, Context};
 use crate::decision::make_decision;
@@ -23,6 +24,7 @@ pub fn conflict_step<'a>(
         mut AssignmentP,
         mut AssumptionsP,
         mut BinaryClausesP,
+        mut CallbacksP<'a>,
         mut ClauseActivityP,
         mut ClauseAllocP,
         mut ClauseDbP,
@@ -120,6 +122,7 @@ fn find_conflict<'a>(
         mut AssignmentP,
         mut AssumptionsP,
         mut BinaryClausesP,
+        mut CallbacksP<'a>,
         mut ClauseAllocP,
         mut ClauseDbP,
         mut ImplGraphP,
@@ -141,6 +144,7 @@ fn find_conflict<'a>(
 
         if new_unit {
             unit_simplify(ctx.borrow());
+            ctx.part_mut(CallbacksP).emit(SolverEvent::UnitSimplify);
         }
 
         match enqueue_assumption(ctx.borrow()) {
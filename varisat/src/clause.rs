@@ -15,7 +15,7 @@ pub use activity::{bump_clause_activity, decay_clause_activities, ClauseActivity
 pub use alloc::{ClauseAlloc, ClauseRef};
 pub use assess::{assess_learned_clause, bump_clause};
 pub use db::{ClauseDb, Tier};
-pub use gc::collect_garbage;
+pub use gc::{collect_garbage, collect_garbage_now};
 pub use header::ClauseHeader;
 
 use header::HEADER_LEN;
@@ -1,5 +1,5 @@
 //! Clause allocator.
-use std::mem::transmute;
+use std::mem::{size_of, transmute};
 use std::slice;
 
 use varisat_formula::{lit::LitIdx, Lit};
@@ -7,8 +7,16 @@ use varisat_formula::{lit::LitIdx, Lit};
 use super::{Clause, ClauseHeader, HEADER_LEN};
 
 /// Integer type used to store offsets into [`ClauseAlloc`]'s memory.
+#[cfg(not(feature = "large-index"))]
 type ClauseOffset = u32;
 
+/// Integer type used to store offsets into [`ClauseAlloc`]'s memory.
+///
+/// Widened along with [`LitIdx`] so that the clause storage of instances with huge variable counts
+/// is not limited to 2^32 words.
+#[cfg(feature = "large-index")]
+type ClauseOffset = u64;
+
 /// Bump allocator for clause storage.
 ///
 /// Clauses are allocated from a single continuous buffer. Clauses cannot be freed individually. To
@@ -173,6 +181,16 @@ impl ClauseAlloc {
     pub fn buffer_size(&self) -> usize {
         self.buffer.len()
     }
+
+    /// Heap memory allocated for clause storage in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.buffer.capacity() * size_of::<LitIdx>()
+    }
+
+    /// Release unused capacity of the buffer.
+    pub fn shrink_to_fit(&mut self) {
+        self.buffer.shrink_to_fit();
+    }
 }
 
 /// Compact reference to a clause.
@@ -1,5 +1,5 @@
 //! Database for long clauses.
-use std::mem::transmute;
+use std::mem::{size_of, transmute};
 
 use partial_ref::{partial, PartialRef};
 
@@ -59,6 +59,20 @@ impl ClauseDb {
     pub fn count_by_tier(&self, tier: Tier) -> usize {
         self.count_by_tier[tier as usize]
     }
+
+    /// Heap memory used for clause references in bytes.
+    ///
+    /// This does not include the memory used to store the clauses themselves, which is owned by
+    /// the [`ClauseAlloc`].
+    pub fn memory_usage(&self) -> usize {
+        let refs = self.clauses.capacity()
+            + self
+                .by_tier
+                .iter()
+                .map(|crefs| crefs.capacity())
+                .sum::<usize>();
+        refs * size_of::<ClauseRef>()
+    }
 }
 
 /// Add a long clause to the database.
@@ -30,7 +30,7 @@ pub fn collect_garbage(
 ///
 /// This needs to invalidate or update any other data structure containing references to
 /// clauses.
-fn collect_garbage_now(
+pub fn collect_garbage_now(
     mut ctx: partial!(
         Context,
         mut ClauseAllocP,
@@ -1,4 +1,6 @@
 //! Solver configuration.
+use std::sync::{Arc, Mutex};
+
 use varisat_internal_macros::{ConfigUpdate, DocDefault};
 
 /// Configurable parameters used during solving.
@@ -28,4 +30,45 @@ pub struct SolverConfig {
     ///
     /// [default: 128]  [range: 1..]
     pub luby_restart_interval_scale: u64,
+
+    /// Memory budget in bytes for clause storage, watchlists, VSIDS and proof buffers.
+    ///
+    /// The self checker and proof steps queued for a background writer are not included. When
+    /// exceeded, learned clauses are removed aggressively. If that is not sufficient, solving
+    /// is stopped with an error.
+    ///
+    /// [default: None]
+    pub memory_limit: Option<usize>,
+}
+
+/// Handle for updating the configuration of a solver while it is running.
+///
+/// Updates made through a handle are picked up by the solver before its next scheduling step, i.e.
+/// when the next [`solve`](crate::Solver::solve) call starts or before the next conflict of a
+/// running search. A handle can be cloned and sent to other threads.
+#[derive(Clone, Default)]
+pub struct SolverConfigHandle {
+    pending: Arc<Mutex<Option<SolverConfigUpdate>>>,
+}
+
+impl SolverConfigHandle {
+    /// Queue a configuration update.
+    ///
+    /// The update is validated immediately, so that errors can be reported to the caller.
+    /// Multiple pending updates are merged, with later updates overwriting earlier ones.
+    pub fn update(&self, config_update: SolverConfigUpdate) -> Result<(), SolverConfigError> {
+        config_update.apply(&mut SolverConfig::default())?;
+
+        let mut pending = self.pending.lock().unwrap();
+        match &mut *pending {
+            Some(pending) => pending.merge(config_update),
+            None => *pending = Some(config_update),
+        }
+        Ok(())
+    }
+
+    /// Remove and return all pending updates.
+    pub(crate) fn take_pending(&self) -> Option<SolverConfigUpdate> {
+        self.pending.lock().unwrap().take()
+    }
 }
@@ -8,6 +8,7 @@ use partial_ref::{part, partial, PartialRef, PartialRefTarget};
 use crate::analyze_conflict::AnalyzeConflict;
 use crate::assumptions::Assumptions;
 use crate::binary::BinaryClauses;
+use crate::callbacks::Callbacks;
 use crate::clause::{ClauseActivity, ClauseAlloc, ClauseDb};
 use crate::config::{SolverConfig, SolverConfigUpdate};
 use crate::decision::vsids::Vsids;
@@ -26,6 +27,7 @@ pub mod parts {
     part!(pub AnalyzeConflictP: AnalyzeConflict);
     part!(pub AssignmentP: Assignment);
     part!(pub BinaryClausesP: BinaryClauses);
+    part!(pub CallbacksP<'a>: Callbacks<'a>);
     part!(pub ClauseActivityP: ClauseActivity);
     part!(pub ClauseAllocP: ClauseAlloc);
     part!(pub ClauseDbP: ClauseDb);
@@ -60,6 +62,8 @@ pub struct Context<'a> {
     pub assignment: Assignment,
     #[part(BinaryClausesP)]
     pub binary_clauses: BinaryClauses,
+    #[part(CallbacksP<'a>)]
+    pub callbacks: Callbacks<'a>,
     #[part(ClauseActivityP)]
     pub clause_activity: ClauseActivity,
     #[part(ClauseAllocP)]
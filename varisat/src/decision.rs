@@ -2,7 +2,7 @@
 
 use partial_ref::{partial, PartialRef};
 
-use varisat_formula::Var;
+use varisat_formula::{Lit, Var};
 
 use crate::context::{parts::*, Context};
 use crate::prop::{enqueue_assignment, Reason};
@@ -57,3 +57,39 @@ pub fn initialize_var(mut ctx: partial!(Context, mut VsidsP), var: Var, availabl
 pub fn remove_var(mut ctx: partial!(Context, mut VsidsP), var: Var) {
     ctx.part_mut(VsidsP).make_unavailable(var);
 }
+
+/// Seed the saved phases from an assignment given using user variables.
+///
+/// If `prioritize` is set, the variables are also moved to the front of the decision order.
+pub fn set_initial_phases(
+    mut ctx: partial!(
+        Context,
+        mut AssignmentP,
+        mut TmpDataP,
+        mut VsidsP,
+        VariablesP
+    ),
+    user_assignment: &[Lit],
+    prioritize: bool,
+) {
+    let (tmp, mut ctx) = ctx.split_part_mut(TmpDataP);
+    let variables = ctx.part(VariablesP);
+
+    tmp.lits.clear();
+    tmp.lits
+        .extend(user_assignment.iter().filter_map(|&user_lit| {
+            let global = variables.global_from_user().get(user_lit.var())?;
+            let solver = variables.solver_from_global().get(global)?;
+            Some(solver.lit(user_lit.is_positive()))
+        }));
+
+    for &lit in tmp.lits.iter() {
+        ctx.part_mut(AssignmentP)
+            .set_last_var_value(lit.var(), lit.is_positive());
+    }
+
+    if prioritize {
+        let vars: Vec<Var> = tmp.lits.iter().map(|lit| lit.var()).collect();
+        ctx.part_mut(VsidsP).prioritize(&vars);
+    }
+}
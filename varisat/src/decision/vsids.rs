@@ -12,6 +12,8 @@
 //! others) by bumping all variables in the conflict clause and all variables resolved on during
 //! conflict analysis.
 
+use std::mem::size_of;
+
 use ordered_float::OrderedFloat;
 
 use varisat_formula::Var;
@@ -51,6 +53,13 @@ impl Default for Vsids {
 }
 
 impl Vsids {
+    /// Heap memory used in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.activity.capacity() * size_of::<OrderedFloat<f32>>()
+            + self.heap.capacity() * size_of::<Var>()
+            + self.position.capacity() * size_of::<Option<usize>>()
+    }
+
     /// Update structures for a new variable count.
     pub fn set_var_count(&mut self, count: usize) {
         self.activity.resize(count, OrderedFloat(0.0));
@@ -84,6 +93,29 @@ impl Vsids {
         }
     }
 
+    /// Increase the activity of the given variables above that of all other variables.
+    ///
+    /// Earlier variables in the slice get a higher activity than later ones.
+    pub fn prioritize(&mut self, vars: &[Var]) {
+        let mut top = self
+            .activity
+            .iter()
+            .cloned()
+            .max()
+            .unwrap_or(OrderedFloat(0.0))
+            .0;
+        for &var in vars.iter().rev() {
+            top += self.bump;
+            self.activity[var.index()] = OrderedFloat(top);
+            if let Some(pos) = self.position[var.index()] {
+                self.sift_up(pos);
+            }
+        }
+        if top >= Self::rescale_limit() {
+            self.rescale();
+        }
+    }
+
     /// Decay all variable activities.
     pub fn decay(&mut self) {
         self.bump *= self.inv_decay;
@@ -298,6 +330,32 @@ mod tests {
         assert_eq!(vsids.next(), None);
     }
 
+    #[test]
+    fn heap_prioritize() {
+        let mut vsids = Vsids::default();
+        vsids.set_var_count(8);
+
+        for _ in 0..8 {
+            vsids.next();
+        }
+
+        for i in 0..8 {
+            for _ in 0..i {
+                vsids.bump(Var::from_index(i));
+            }
+        }
+
+        for i in 0..8 {
+            vsids.make_available(Var::from_index(i));
+        }
+
+        vsids.prioritize(&[var!(2), var!(1)]);
+
+        assert_eq!(vsids.next(), Some(var!(2)));
+        assert_eq!(vsids.next(), Some(var!(1)));
+        assert_eq!(vsids.next(), Some(Var::from_index(7)));
+    }
+
     #[test]
     fn heap_bump() {
         let mut vsids = Vsids::default();
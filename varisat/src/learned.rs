@@ -0,0 +1,170 @@
+//! Export and import of learned clauses.
+use std::io;
+
+use partial_ref::{partial, PartialRef};
+use thiserror::Error;
+
+use varisat_dimacs::{write_dimacs, DimacsParser, ParserError};
+use varisat_formula::{CnfFormula, ExtendFormula, Lit};
+
+use crate::assumptions::{propagate_user_lits, PropagationResult};
+use crate::clause::{db, Tier};
+use crate::context::{parts::*, Context};
+use crate::load::load_clause_with_tier;
+use crate::variables::{data::SamplingMode, Variables};
+
+/// Errors while importing learned clauses.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ImportLearnedError {
+    #[error("learned clauses cannot be imported while generating a proof")]
+    ProofActive,
+    #[error("Invalid learned clauses: {cause}")]
+    InvalidInput {
+        #[from]
+        cause: ParserError,
+    },
+}
+
+/// Write all learned long clauses in DIMACS CNF format.
+///
+/// The output uses user variable names. Clauses containing variables that have no corresponding
+/// user variable are skipped.
+pub fn export_learned(
+    mut ctx: partial!(Context, ClauseAllocP, ClauseDbP, VariablesP),
+    target: &mut impl io::Write,
+) -> io::Result<()> {
+    let (alloc, mut ctx) = ctx.split_part(ClauseAllocP);
+    let (variables, mut ctx) = ctx.split_part(VariablesP);
+
+    let mut formula = CnfFormula::new();
+    let mut user_lits = vec![];
+
+    'clauses: for cref in db::clauses_iter(&ctx.borrow()) {
+        let clause = alloc.clause(cref);
+        if clause.header().tier() == Tier::Irred {
+            continue;
+        }
+
+        user_lits.clear();
+        for &lit in clause.lits() {
+            let user_var = variables
+                .global_from_solver()
+                .get(lit.var())
+                .and_then(|global| variables.user_from_global().get(global));
+            match user_var {
+                Some(user_var) => user_lits.push(user_var.lit(lit.is_positive())),
+                None => continue 'clauses,
+            }
+        }
+
+        formula.add_clause(&user_lits);
+    }
+
+    write_dimacs(target, &formula)
+}
+
+/// Read clauses in DIMACS CNF format and add those that are implied by the current formula.
+///
+/// Each clause is validated by checking that it has the reverse unit propagation (RUP) property
+/// with respect to the clauses that can't be removed. Clauses that fail this check or that contain
+/// variables unknown to the solver are skipped. Accepted clauses are stored as learned clauses.
+///
+/// Returns the number of accepted clauses.
+pub fn import_learned<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut AnalyzeConflictP,
+        mut AssignmentP,
+        mut AssumptionsP,
+        mut BinaryClausesP,
+        mut ClauseAllocP,
+        mut ClauseDbP,
+        mut ImplGraphP,
+        mut ProofP<'a>,
+        mut SolverStateP,
+        mut TmpDataP,
+        mut TmpFlagsP,
+        mut TrailP,
+        mut VariablesP,
+        mut VsidsP,
+        mut WatchlistsP,
+    ),
+    input: impl io::Read,
+) -> Result<usize, ImportLearnedError> {
+    if ctx.part(ProofP).is_active() {
+        return Err(ImportLearnedError::ProofActive);
+    }
+
+    let formula = DimacsParser::parse(input)?;
+
+    let mut imported = 0;
+
+    for clause in formula.iter() {
+        if import_learned_clause(ctx.borrow(), clause)? {
+            imported += 1;
+        }
+    }
+
+    Ok(imported)
+}
+
+/// Add a single clause if it is implied by the current formula.
+///
+/// Like [`import_learned`] but for a single clause using user variables. Returns whether the
+/// clause was accepted.
+pub fn import_learned_clause<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut AnalyzeConflictP,
+        mut AssignmentP,
+        mut AssumptionsP,
+        mut BinaryClausesP,
+        mut ClauseAllocP,
+        mut ClauseDbP,
+        mut ImplGraphP,
+        mut ProofP<'a>,
+        mut SolverStateP,
+        mut TmpDataP,
+        mut TmpFlagsP,
+        mut TrailP,
+        mut VariablesP,
+        mut VsidsP,
+        mut WatchlistsP,
+    ),
+    clause: &[Lit],
+) -> Result<bool, ImportLearnedError> {
+    if ctx.part(ProofP).is_active() {
+        return Err(ImportLearnedError::ProofActive);
+    }
+
+    let variables = ctx.part(VariablesP);
+    if !clause
+        .iter()
+        .all(|&lit| is_known_sampling_var(variables, lit))
+    {
+        return Ok(false);
+    }
+
+    let negated: Vec<Lit> = clause.iter().map(|&lit| !lit).collect();
+
+    // Removable and grouped clauses may be removed later, so the clause must be implied without
+    // them.
+    match propagate_user_lits(ctx.borrow(), &negated, false) {
+        PropagationResult::Conflict(_) => {
+            load_clause_with_tier(ctx.borrow(), clause, Tier::Local);
+            Ok(true)
+        }
+        PropagationResult::Implied(_) => Ok(false),
+    }
+}
+
+/// Whether the variable of a user literal is present in the solver and can be constrained.
+fn is_known_sampling_var(variables: &Variables, user_lit: Lit) -> bool {
+    variables
+        .global_from_user()
+        .get(user_lit.var())
+        .filter(|&global| variables.var_data_global(global).sampling_mode == SamplingMode::Sample)
+        .and_then(|global| variables.solver_from_global().get(global))
+        .is_some()
+}
@@ -14,23 +14,32 @@ pub mod solver;
 mod analyze_conflict;
 mod assumptions;
 mod binary;
+mod callbacks;
 mod cdcl;
 mod clause;
 mod context;
 mod decision;
 mod glue;
+mod learned;
 mod load;
+mod memory;
 mod model;
 mod proof;
 mod prop;
+mod replay;
 mod schedule;
 mod state;
 mod tmp;
 mod unit_simplify;
 mod variables;
 
-pub use solver::{ProofFormat, Solver};
-pub use varisat_formula::{cnf, lit, CnfFormula, ExtendFormula, Lit, Var};
+pub use replay::ProofReplay;
+pub use solver::{ProofFormat, ProofSink, Solver};
+pub use varisat_formula::{
+    cnf, cnf_formula, diff, encode, lbool, lit, lits, map, miter, model, names, pb, simplify,
+    subformula, var, vars, wcnf, CnfFormula, ExtendFormula, Lbool, Lit, LitMap, Model, PbProblem,
+    Var, VarMap, VarNames, WcnfFormula,
+};
 
 pub mod dimacs {
     //! DIMCAS CNF parser and writer.
@@ -40,7 +49,15 @@ pub mod dimacs {
 pub mod checker {
     //! Proof checker for Varisat proofs.
     pub use varisat_checker::{
-        CheckedProofStep, Checker, CheckerData, CheckerError, ProofProcessor,
-        ProofTranscriptProcessor, ProofTranscriptStep,
+        BackgroundReader, BackgroundWriter, CancelToken, CheckReport, CheckedProofStep, Checker,
+        CheckerConfig, CheckerData, CheckerError, CheckerProgress, CheckerStats, DratFormat,
+        DropDeletions, GcPolicy, Interpolant, InterpolationError, Interpolator, MergeDuplicates,
+        OwnedProofStep, ProcessorError, ProofParseError, ProofProcessor, ProofStep,
+        ProofTranscriptProcessor, ProofTranscriptStep, ProofTransformer, ProofTrimmer,
+        ProofVerdict, RenumberIds, StepFilter, StepProfiler, TransformError, TransformProcessor,
+        UnitDeletionPolicy, UnsatCore, UnsatCoreError, WriteClauseIdMap,
     };
+
+    #[cfg(feature = "compression")]
+    pub use varisat_checker::{compress, decompress, Compression};
 }
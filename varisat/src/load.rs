@@ -40,6 +40,35 @@ pub fn load_clause<'a>(
         mut WatchlistsP,
     ),
     user_lits: &[Lit],
+) {
+    load_clause_with_tier(ctx.borrow(), user_lits, Tier::Irred)
+}
+
+/// Adds a clause to the current formula, storing long clauses in the given tier.
+///
+/// See [`load_clause`]. Using a tier other than [`Tier::Irred`] is only valid for clauses implied
+/// by the current formula.
+pub fn load_clause_with_tier<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut AnalyzeConflictP,
+        mut AssignmentP,
+        mut AssumptionsP,
+        mut BinaryClausesP,
+        mut ClauseAllocP,
+        mut ClauseDbP,
+        mut ImplGraphP,
+        mut ProofP<'a>,
+        mut SolverStateP,
+        mut TmpDataP,
+        mut TmpFlagsP,
+        mut TrailP,
+        mut VariablesP,
+        mut VsidsP,
+        mut WatchlistsP,
+    ),
+    user_lits: &[Lit],
+    tier: Tier,
 ) {
     match ctx.part(SolverStateP).sat_state {
         SatState::Unsat => return,
@@ -158,7 +187,7 @@ pub fn load_clause<'a>(
         }
         _ => {
             let mut header = ClauseHeader::new();
-            header.set_tier(Tier::Irred);
+            header.set_tier(tier);
 
             db::add_clause(ctx.borrow(), header, lits);
         }
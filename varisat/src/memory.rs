@@ -0,0 +1,71 @@
+//! Memory accounting and enforcement of the memory limit.
+use partial_ref::{partial, PartialRef};
+
+use crate::clause::collect_garbage_now;
+use crate::clause::reduce::{reduce_locals, reduce_mids};
+use crate::context::{parts::*, Context};
+
+/// Heap memory used by the solver in bytes.
+///
+/// This covers the clause database, binary clauses, watchlists, the VSIDS heuristic and buffers
+/// used for writing the proof. It does not include the self checker enabled by proof processors,
+/// batches of proof steps queued for a background writer thread, or smaller per-variable data.
+pub fn memory_usage<'a>(
+    ctx: partial!(
+        Context<'a>,
+        BinaryClausesP,
+        ClauseAllocP,
+        ClauseDbP,
+        ProofP<'a>,
+        VsidsP,
+        WatchlistsP
+    ),
+) -> usize {
+    ctx.part(BinaryClausesP).memory_usage()
+        + ctx.part(ClauseAllocP).memory_usage()
+        + ctx.part(ClauseDbP).memory_usage()
+        + ctx.part(ProofP).memory_usage()
+        + ctx.part(VsidsP).memory_usage()
+        + ctx.part(WatchlistsP).memory_usage()
+}
+
+/// Reduce the memory usage if it exceeds the given limit.
+///
+/// The usage is estimated by [`memory_usage`]. This demotes inactive mid tier clauses, removes half
+/// of the local tier clauses and compacts the clause storage. Returns false if the memory usage is
+/// still above the limit after that.
+pub fn enforce_memory_limit<'a>(
+    mut ctx: partial!(
+        Context<'a>,
+        mut ClauseAllocP,
+        mut ClauseDbP,
+        mut ImplGraphP,
+        mut ProofP<'a>,
+        mut SolverStateP,
+        mut WatchlistsP,
+        AssignmentP,
+        BinaryClausesP,
+        TrailP,
+        VariablesP,
+        VsidsP,
+    ),
+    limit: usize,
+) -> bool {
+    let usage = memory_usage(ctx.borrow());
+    if usage <= limit {
+        return true;
+    }
+
+    log::info!(
+        "memory usage of {} bytes exceeds limit of {} bytes, reducing clause database",
+        usage,
+        limit
+    );
+
+    reduce_mids(ctx.borrow());
+    reduce_locals(ctx.borrow());
+    collect_garbage_now(ctx.borrow());
+    ctx.part_mut(ClauseAllocP).shrink_to_fit();
+
+    memory_usage(ctx.borrow()) <= limit
+}
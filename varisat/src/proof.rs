@@ -1,19 +1,27 @@
 //! Proof generation.
 
-use std::io::{self, sink, BufWriter, Write};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
 
 use partial_ref::{partial, PartialRef};
 
 use varisat_checker::{internal::SelfChecker, Checker, CheckerError, ProofProcessor};
 use varisat_formula::{Lit, Var};
-use varisat_internal_proof::{ClauseHash, ProofStep};
+use varisat_internal_proof::{binary_format, clause_hash, ProofStep, SolveResult, MAX_HASH_BITS};
 
 use crate::context::{parts::*, Context};
 use crate::solver::SolverError;
+use crate::state::SatState;
 
+mod background;
 mod drat;
 mod map_step;
 
+/// Minimal length of clauses whose deletion is written using the clause hash.
+///
+/// For shorter clauses, writing the literals takes less space than writing the hash.
+const MIN_HASHED_DELETE_LEN: usize = 5;
+
 /// Proof formats that can be generated during solving.
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum ProofFormat {
@@ -22,23 +30,148 @@ pub enum ProofFormat {
     BinaryDrat,
 }
 
+/// Receiver of generated proof steps.
+///
+/// The solver passes all steps of the proof to a sink, which can serialize them, send them over a
+/// network or process them directly. Steps use the global variable names of the solver. For the
+/// [`Varisat`](ProofFormat::Varisat) format, steps contain reduced clause hashes and
+/// [`ChangeHashBits`](ProofStep::ChangeHashBits) steps announce changes of the hash width. For
+/// the DRAT formats, clause hashes are not reduced and should be ignored.
+///
+/// IO errors returned by a sink stop proof generation and are reported by the solver.
+pub trait ProofSink {
+    /// Format of the generated proof.
+    ///
+    /// This is queried once when the sink is added to the solver.
+    fn format(&self) -> ProofFormat;
+
+    /// Process a single proof step.
+    fn write_step(&mut self, step: &ProofStep) -> io::Result<()>;
+
+    /// Write out any buffered steps.
+    ///
+    /// This is called before solving returns.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Write out any buffered steps and make sure they are persisted.
+    ///
+    /// This is called periodically when a sync interval is set using
+    /// [`set_proof_sync_interval`](crate::Solver::set_proof_sync_interval). The default
+    /// implementation flushes the sink.
+    fn sync(&mut self) -> io::Result<()> {
+        self.flush()
+    }
+
+    /// Finish the proof.
+    ///
+    /// This is called after the final [`End`](ProofStep::End) step when the proof is closed. The
+    /// sink is dropped afterwards. The default implementation flushes the sink.
+    fn close(&mut self) -> io::Result<()> {
+        self.flush()
+    }
+
+    /// Heap memory used for buffering steps in bytes.
+    ///
+    /// This is counted towards the solver's memory limit. The default implementation returns 0.
+    fn memory_usage(&self) -> usize {
+        0
+    }
+}
+
+/// Proof sink serializing steps to a writer.
+struct WriteSink<'a> {
+    target: BufWriter<Box<dyn Write + 'a>>,
+    format: ProofFormat,
+    /// Handle of the target file, used to sync it to disk.
+    file: Option<File>,
+}
+
+impl<'a> WriteSink<'a> {
+    fn new(target: impl Write + 'a, format: ProofFormat) -> WriteSink<'a> {
+        WriteSink {
+            target: BufWriter::new(Box::new(target)),
+            format,
+            file: None,
+        }
+    }
+
+    /// Write the proof header, if the format uses one.
+    fn write_header(&mut self) -> io::Result<()> {
+        if self.format == ProofFormat::Varisat {
+            binary_format::write_header(
+                &mut self.target,
+                &[("generator", concat!("varisat ", env!("CARGO_PKG_VERSION")))],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ProofSink for WriteSink<'a> {
+    fn format(&self) -> ProofFormat {
+        self.format
+    }
+
+    fn write_step(&mut self, step: &ProofStep) -> io::Result<()> {
+        match self.format {
+            ProofFormat::Varisat => binary_format::write_step(&mut self.target, step),
+            ProofFormat::Drat => drat::write_step(&mut self.target, step),
+            ProofFormat::BinaryDrat => drat::write_binary_step(&mut self.target, step),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.target.flush()
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.target.flush()?;
+        if let Some(file) = &self.file {
+            file.sync_data()?;
+        }
+        Ok(())
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        self.sync()
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.target.capacity()
+    }
+}
+
 /// Number of added or removed clauses.
 pub fn clause_count_delta(step: &ProofStep) -> isize {
     match step {
-        ProofStep::AddClause { clause } | ProofStep::AtClause { clause, .. } => {
+        ProofStep::AddClause { clause }
+        | ProofStep::AtClause { clause, .. }
+        | ProofStep::RatClause { clause, .. }
+        | ProofStep::BlockedClause { clause, .. }
+        | ProofStep::PrClause { clause, .. } => {
             if clause.len() > 1 {
                 1
             } else {
                 0
             }
         }
-        ProofStep::DeleteClause { clause, .. } => {
+        ProofStep::DeleteClause { clause, .. }
+        | ProofStep::DeleteRatClause { clause, .. }
+        | ProofStep::DeleteBlockedClause { clause, .. } => {
             if clause.len() > 1 {
                 -1
             } else {
                 0
             }
         }
+        ProofStep::DeleteHashedClause { .. } => -1,
+        ProofStep::Extension { definition, .. } => {
+            // One binary clause per literal and the long clause, which is a unit clause for an
+            // empty definition.
+            definition.len() as isize + if definition.is_empty() { 0 } else { 1 }
+        }
         ProofStep::SolverVarName { .. }
         | ProofStep::UserVarName { .. }
         | ProofStep::DeleteVar { .. }
@@ -48,6 +181,8 @@ pub fn clause_count_delta(step: &ProofStep) -> isize {
         | ProofStep::Model { .. }
         | ProofStep::Assumptions { .. }
         | ProofStep::FailedAssumptions { .. }
+        | ProofStep::SolveResult { .. }
+        | ProofStep::Checkpoint
         | ProofStep::End => 0,
     }
 }
@@ -55,11 +190,21 @@ pub fn clause_count_delta(step: &ProofStep) -> isize {
 /// Proof generation.
 pub struct Proof<'a> {
     format: Option<ProofFormat>,
-    target: BufWriter<Box<dyn Write + 'a>>,
+    sink: Option<Box<dyn ProofSink + 'a>>,
     checker: Option<Checker<'a>>,
     map_step: map_step::MapStep,
     /// How many bits are used for storing clause hashes.
     hash_bits: u32,
+    /// Fixed number of hash bits requested by the user.
+    ///
+    /// When this is `None`, the number of bits is adjusted to the number of clauses.
+    fixed_hash_bits: Option<u32>,
+    /// Whether deletions of long clauses are written using the clause hash.
+    delete_by_hash: bool,
+    /// Number of steps between syncs of the proof.
+    sync_interval: Option<u64>,
+    /// Number of steps written since the last sync.
+    steps_since_sync: u64,
     /// How many clauses are currently in the db.
     ///
     /// This is used to pick a good number of hash_bits
@@ -70,26 +215,65 @@ impl<'a> Default for Proof<'a> {
     fn default() -> Proof<'a> {
         Proof {
             format: None,
-            target: BufWriter::new(Box::new(sink())),
+            sink: None,
             checker: None,
             map_step: Default::default(),
             hash_bits: 64,
+            fixed_hash_bits: None,
+            delete_by_hash: false,
             clause_count: 0,
+            sync_interval: None,
+            steps_since_sync: 0,
         }
     }
 }
 
 impl<'a> Proof<'a> {
-    /// Start writing proof steps to the given target with the given format.
-    pub fn write_proof(&mut self, target: impl Write + 'a, format: ProofFormat) {
-        self.format = Some(format);
-        self.target = BufWriter::new(Box::new(target))
+    /// Start passing proof steps to the given sink.
+    pub fn write_proof_to_sink(&mut self, sink: Box<dyn ProofSink + 'a>) {
+        self.format = Some(sink.format());
+        self.sink = Some(sink);
+    }
+
+    /// Use a fixed number of bits for clause hashes of the native format.
+    ///
+    /// When `bits` is `None` the number of bits is chosen based on the number of clauses.
+    pub fn set_hash_bits(&mut self, bits: Option<u32>) {
+        if let Some(bits) = bits {
+            assert!(
+                bits > 0 && bits <= MAX_HASH_BITS,
+                "hash bits must be in range 1..={}",
+                MAX_HASH_BITS
+            );
+        }
+        self.fixed_hash_bits = bits;
+    }
+
+    /// Sync the proof every `steps` proof steps.
+    ///
+    /// When `steps` is `None` the proof is only flushed when solving returns.
+    pub fn set_sync_interval(&mut self, steps: Option<u64>) {
+        assert!(steps != Some(0), "sync interval must be positive");
+        self.sync_interval = steps;
+        self.steps_since_sync = 0;
+    }
+
+    /// Write deletions of long clauses using the clause hash instead of the literals.
+    pub fn set_delete_by_hash(&mut self, enabled: bool) {
+        self.delete_by_hash = enabled;
     }
 
     /// Begin checking proof steps.
     pub fn begin_checking(&mut self) {
         if self.checker.is_none() {
-            self.checker = Some(Checker::new())
+            let mut checker = Checker::new();
+            // Steps are checked before reducing the hashes, so the checker needs full width hashes.
+            checker
+                .self_check_step(ProofStep::ChangeHashBits {
+                    bits: MAX_HASH_BITS,
+                })
+                .expect("failed to set hash width of self checker");
+            self.checker = Some(checker)
         }
     }
 
@@ -124,6 +308,66 @@ impl<'a> Proof<'a> {
     pub fn models_in_proof(&self) -> bool {
         self.native_format()
     }
+
+    /// Heap memory used for buffering proof steps in bytes.
+    ///
+    /// This doesn't include the memory used by the self checker.
+    pub fn memory_usage(&self) -> usize {
+        self.map_step.memory_usage() + self.sink.as_ref().map_or(0, |sink| sink.memory_usage())
+    }
+}
+
+/// Begin writing proof steps to the given target.
+///
+/// For the native format this also writes the proof header.
+pub fn write_proof<'a>(
+    mut ctx: partial!(Context<'a>, mut ProofP<'a>, mut SolverStateP),
+    target: impl Write + 'a,
+    format: ProofFormat,
+) {
+    begin_writing(ctx.borrow(), WriteSink::new(target, format));
+}
+
+/// Begin writing proof steps to the given file.
+///
+/// Like [`write_proof`], but syncing the proof also syncs the file to disk.
+pub fn write_proof_file<'a>(
+    mut ctx: partial!(Context<'a>, mut ProofP<'a>, mut SolverStateP),
+    file: File,
+    format: ProofFormat,
+) {
+    match file.try_clone() {
+        Ok(handle) => {
+            let mut sink = WriteSink::new(file, format);
+            sink.file = Some(handle);
+            begin_writing(ctx.borrow(), sink);
+        }
+        Err(err) => handle_io_errors(ctx.borrow(), Err(err)),
+    }
+}
+
+/// Begin serializing and writing proof steps to the given target on a separate thread.
+///
+/// At most `capacity` batches of steps are queued. For the native format the writer thread also
+/// writes the proof header.
+pub fn write_proof_in_background<'a>(
+    mut ctx: partial!(Context<'a>, mut ProofP<'a>),
+    target: impl Write + Send + 'static,
+    format: ProofFormat,
+    capacity: usize,
+) {
+    let sink = background::BackgroundSink::new(target, format, capacity);
+    ctx.part_mut(ProofP).write_proof_to_sink(Box::new(sink));
+}
+
+/// Write the proof header and start passing proof steps to a [`WriteSink`].
+fn begin_writing<'a>(
+    mut ctx: partial!(Context<'a>, mut ProofP<'a>, mut SolverStateP),
+    mut sink: WriteSink<'a>,
+) {
+    let result = sink.write_header();
+    ctx.part_mut(ProofP).write_proof_to_sink(Box::new(sink));
+    handle_io_errors(ctx.borrow(), result);
 }
 
 /// Call when adding an external clause.
@@ -191,14 +435,13 @@ pub fn add_step<'a, 's>(
     };
 
     let io_result = match proof.format {
-        Some(ProofFormat::Varisat) => write_varisat_step(ctx.borrow(), map_vars, step),
-        Some(ProofFormat::Drat) => {
-            let step = proof.map_step.map(step, map_vars, |hash| hash);
-            drat::write_step(&mut proof.target, &step)
-        }
-        Some(ProofFormat::BinaryDrat) => {
+        Some(ProofFormat::Varisat) => write_varisat_step(ctx.borrow(), solver_vars, map_vars, step),
+        Some(ProofFormat::Drat) | Some(ProofFormat::BinaryDrat) => {
             let step = proof.map_step.map(step, map_vars, |hash| hash);
-            drat::write_binary_step(&mut proof.target, &step)
+            match &mut proof.sink {
+                Some(sink) => sink.write_step(&step),
+                None => Ok(()),
+            }
         }
         None => Ok(()),
     };
@@ -212,12 +455,40 @@ pub fn add_step<'a, 's>(
         }
     }
 
+    // Nothing follows the end of the proof, which is synced when the proof is closed.
+    let io_result = match step {
+        ProofStep::End => io_result,
+        _ => io_result.and_then(|()| sync_if_due(ctx.part_mut(ProofP))),
+    };
+
     handle_io_errors(ctx.borrow(), io_result);
 }
 
+/// Sync the proof when the sync interval elapsed.
+///
+/// For our native format this first writes a checkpoint step.
+fn sync_if_due(proof: &mut Proof) -> io::Result<()> {
+    let interval = match proof.sync_interval {
+        Some(interval) => interval,
+        None => return Ok(()),
+    };
+    if let Some(sink) = &mut proof.sink {
+        proof.steps_since_sync += 1;
+        if proof.steps_since_sync >= interval {
+            proof.steps_since_sync = 0;
+            if proof.format == Some(ProofFormat::Varisat) {
+                sink.write_step(&ProofStep::Checkpoint)?;
+            }
+            sink.sync()?;
+        }
+    }
+    Ok(())
+}
+
 /// Write a step using our native format
 fn write_varisat_step<'a, 's>(
     mut ctx: partial!(Context<'a>, mut ProofP<'a>, SolverStateP),
+    solver_vars: bool,
     map_vars: impl Fn(Var) -> Var,
     step: &'s ProofStep<'s>,
 ) -> io::Result<()> {
@@ -227,33 +498,63 @@ fn write_varisat_step<'a, 's>(
 
     let mut rehash = false;
     // Should we change the hash size?
-    while proof.clause_count > (1 << (proof.hash_bits / 2)) {
-        proof.hash_bits += 2;
-        rehash = true;
-    }
-    if ctx.part(SolverStateP).solver_invoked {
-        while proof.hash_bits > 6 && proof.clause_count * 4 < (1 << (proof.hash_bits / 2)) {
-            proof.hash_bits -= 2;
+    if let Some(bits) = proof.fixed_hash_bits {
+        if proof.hash_bits != bits {
+            proof.hash_bits = bits;
             rehash = true;
         }
+    } else {
+        let clause_count = proof.clause_count.max(0) as u128;
+        while proof.hash_bits < MAX_HASH_BITS && clause_count > (1 << (proof.hash_bits / 2)) {
+            proof.hash_bits += 2;
+            rehash = true;
+        }
+        if ctx.part(SolverStateP).solver_invoked {
+            while proof.hash_bits > 6 && clause_count * 4 < (1 << (proof.hash_bits / 2)) {
+                proof.hash_bits -= 2;
+                rehash = true;
+            }
+        }
     }
 
     if rehash {
-        varisat_internal_proof::binary_format::write_step(
-            &mut proof.target,
-            &ProofStep::ChangeHashBits {
+        if let Some(sink) = &mut proof.sink {
+            sink.write_step(&ProofStep::ChangeHashBits {
                 bits: proof.hash_bits,
-            },
-        )?;
+            })?;
+        }
     }
 
-    let shift_bits = ClauseHash::max_value().count_ones() - proof.hash_bits;
+    // Clause hashes use solver var names, so this is only possible for steps using them. Hashes
+    // with fewer than 64 bits are likely to collide.
+    let hashed_step;
+    let step = match *step {
+        ProofStep::DeleteClause {
+            clause,
+            proof: reason,
+        } if proof.delete_by_hash
+            && solver_vars
+            && proof.hash_bits >= 64
+            && clause.len() >= MIN_HASHED_DELETE_LEN =>
+        {
+            hashed_step = ProofStep::DeleteHashedClause {
+                hash: clause_hash(clause),
+                proof: reason,
+            };
+            &hashed_step
+        }
+        _ => step,
+    };
+
+    let shift_bits = MAX_HASH_BITS - proof.hash_bits;
 
     let map_hash = |hash| hash >> shift_bits;
     let step = proof.map_step.map(step, map_vars, map_hash);
 
     if proof.format == Some(ProofFormat::Varisat) {
-        varisat_internal_proof::binary_format::write_step(&mut proof.target, &step)?;
+        if let Some(sink) = &mut proof.sink {
+            sink.write_step(&step)?;
+        }
     }
 
     Ok(())
@@ -262,7 +563,10 @@ fn write_varisat_step<'a, 's>(
 /// Flush buffers used for writing proof steps.
 pub fn flush_proof<'a>(mut ctx: partial!(Context<'a>, mut ProofP<'a>, mut SolverStateP)) {
     // We need to explicitly flush to handle IO errors.
-    let result = ctx.part_mut(ProofP).target.flush();
+    let result = match &mut ctx.part_mut(ProofP).sink {
+        Some(sink) => sink.flush(),
+        None => Ok(()),
+    };
     handle_io_errors(ctx.borrow(), result);
 }
 
@@ -271,9 +575,24 @@ pub fn close_proof<'a>(
     mut ctx: partial!(Context<'a>, mut ProofP<'a>, mut SolverStateP, VariablesP),
 ) {
     add_step(ctx.borrow(), true, &ProofStep::End);
-    flush_proof(ctx.borrow());
+    let result = match ctx.part_mut(ProofP).sink.take() {
+        Some(mut sink) => sink.close(),
+        None => Ok(()),
+    };
     ctx.part_mut(ProofP).format = None;
-    ctx.part_mut(ProofP).target = BufWriter::new(Box::new(sink()));
+    handle_io_errors(ctx.borrow(), result);
+}
+
+/// Add the result of a finished solve call to the proof.
+pub fn add_solve_result<'a>(
+    mut ctx: partial!(Context<'a>, mut ProofP<'a>, mut SolverStateP, VariablesP),
+) {
+    let result = match ctx.part(SolverStateP).sat_state {
+        SatState::Sat => SolveResult::Sat,
+        SatState::Unsat | SatState::UnsatUnderAssumptions => SolveResult::Unsat,
+        SatState::Unknown => SolveResult::Unknown,
+    };
+    add_step(ctx.borrow(), false, &ProofStep::SolveResult { result });
 }
 
 /// Called before solve returns to flush buffers and to trigger delayed unit conflict processing.
@@ -289,7 +608,7 @@ pub fn solve_finished<'a>(mut ctx: partial!(Context<'a>, mut ProofP<'a>, mut Sol
 
 /// Handle results of on the fly checking.
 ///
-/// Panics when the proof is incorrect and aborts solving when a proof processor produced an error.
+/// Aborts solving when the proof is incorrect or when a proof processor produced an error.
 fn handle_self_check_result<'a>(
     mut ctx: partial!(Context<'a>, mut ProofP<'a>, mut SolverStateP),
     result: Result<(), CheckerError>,
@@ -302,12 +621,14 @@ fn handle_self_check_result<'a>(
         }
         Err(err) => {
             log::error!("{}", err);
-            if let CheckerError::CheckFailed { debug_step, .. } = err {
+            if let CheckerError::CheckFailed { debug_step, .. } = &err {
                 if !debug_step.is_empty() {
                     log::error!("failed step was {}", debug_step)
                 }
             }
-            panic!("self check failure");
+            ctx.part_mut(SolverStateP).solver_error =
+                Some(SolverError::SelfCheckFailed { cause: err });
+            *ctx.part_mut(ProofP) = Proof::default();
         }
         Ok(()) => (),
     }
@@ -333,7 +654,7 @@ mod tests {
     use std::fs::File;
     use std::process::Command;
 
-    use failure::Fail;
+    use partial_ref::IntoPartialRefMut;
 
     use tempfile::TempDir;
 
@@ -370,7 +691,7 @@ mod tests {
 
         prop_assert_eq!(solver.solve().ok(), Some(false));
 
-        solver.close_proof().map_err(|e| e.compat())?;
+        solver.close_proof()?;
 
         let output = match checker {
             Checker::DratTrim => {
@@ -398,6 +719,31 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn self_check_failure_is_an_error() {
+        let mut ctx = Context::default();
+        let mut ctx = ctx.into_partial_ref_mut();
+
+        ctx.part_mut(ProofP).begin_checking();
+
+        add_step(
+            ctx.borrow(),
+            false,
+            &ProofStep::AtClause {
+                redundant: true,
+                clause: &[],
+                propagation_hashes: &[],
+            },
+        );
+
+        match ctx.part_mut(SolverStateP).solver_error.take() {
+            Some(SolverError::SelfCheckFailed { .. }) => (),
+            err => panic!("expected self check failure but got {:?}", err),
+        }
+
+        assert!(!ctx.part(ProofP).is_active());
+    }
+
     proptest! {
         #[cfg_attr(not(test_drat_trim), ignore)]
         #[test]
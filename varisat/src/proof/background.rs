@@ -0,0 +1,161 @@
+//! Serializing and writing proof steps on a background thread.
+use std::io::{self, Write};
+use std::mem::{replace, size_of};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+
+use varisat_internal_proof::{OwnedProofStep, ProofStep};
+
+use super::{ProofFormat, ProofSink, WriteSink};
+
+/// Number of steps collected before they are passed to the writer thread.
+const STEP_BATCH: usize = 256;
+
+/// Messages sent to the writer thread.
+enum Message {
+    /// Steps to serialize and write.
+    Steps(Vec<OwnedProofStep>),
+    /// Flush the target and acknowledge when done.
+    Flush(SyncSender<()>),
+}
+
+/// Proof sink that serializes and writes steps on a dedicated thread.
+///
+/// Steps are copied into [`OwnedProofStep`]s and sent to the writer thread in batches, using a
+/// bounded channel. When the channel is full, solving blocks until the writer thread catches up.
+///
+/// IO errors of the writer thread are returned when passing the next batch of steps or when
+/// flushing the sink.
+pub struct BackgroundSink {
+    format: ProofFormat,
+    batch: Vec<OwnedProofStep>,
+    sender: Option<SyncSender<Message>>,
+    thread: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl BackgroundSink {
+    /// Write a proof in the given format to `target` on a new thread.
+    ///
+    /// The writer thread first writes the proof header, if the format uses one. At most
+    /// `capacity` batches of steps are queued before writing blocks.
+    pub fn new(
+        target: impl Write + Send + 'static,
+        format: ProofFormat,
+        capacity: usize,
+    ) -> BackgroundSink {
+        let (sender, receiver) = sync_channel(capacity);
+        let thread =
+            thread::spawn(move || write_messages(WriteSink::new(target, format), receiver));
+        BackgroundSink {
+            format,
+            batch: Vec::with_capacity(STEP_BATCH),
+            sender: Some(sender),
+            thread: Some(thread),
+        }
+    }
+
+    /// Send a message to the writer thread.
+    fn send(&mut self, message: Message) -> io::Result<()> {
+        let sent = match &self.sender {
+            Some(sender) => sender.send(message).is_ok(),
+            None => false,
+        };
+        if sent {
+            Ok(())
+        } else {
+            Err(self.writer_error())
+        }
+    }
+
+    /// Pass the collected steps to the writer thread.
+    fn send_batch(&mut self) -> io::Result<()> {
+        if !self.batch.is_empty() {
+            let steps = replace(&mut self.batch, Vec::with_capacity(STEP_BATCH));
+            self.send(Message::Steps(steps))?;
+        }
+        Ok(())
+    }
+
+    /// The error that stopped the writer thread.
+    fn writer_error(&mut self) -> io::Error {
+        self.sender = None;
+        match self.thread.take().map(join_writer) {
+            Some(Err(err)) => err,
+            _ => io::Error::new(io::ErrorKind::BrokenPipe, "proof writer thread stopped"),
+        }
+    }
+}
+
+impl ProofSink for BackgroundSink {
+    fn format(&self) -> ProofFormat {
+        self.format
+    }
+
+    fn write_step(&mut self, step: &ProofStep) -> io::Result<()> {
+        self.batch.push(OwnedProofStep::from(step));
+        if self.batch.len() >= STEP_BATCH {
+            self.send_batch()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send_batch()?;
+        let (ack_sender, ack_receiver) = sync_channel(1);
+        self.send(Message::Flush(ack_sender))?;
+        match ack_receiver.recv() {
+            Ok(()) => Ok(()),
+            Err(_) => Err(self.writer_error()),
+        }
+    }
+
+    fn memory_usage(&self) -> usize {
+        // Only counts the batch being collected, queued batches are owned by the channel.
+        self.batch.capacity() * size_of::<OwnedProofStep>()
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        self.send_batch()?;
+        self.sender = None;
+        match self.thread.take() {
+            Some(thread) => join_writer(thread),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for BackgroundSink {
+    fn drop(&mut self) {
+        let _ignore_errors = self.close();
+    }
+}
+
+/// Wait for the writer thread to finish and return its result.
+fn join_writer(thread: JoinHandle<io::Result<()>>) -> io::Result<()> {
+    match thread.join() {
+        Ok(result) => result,
+        Err(_) => Err(io::Error::new(
+            io::ErrorKind::Other,
+            "proof writer thread panicked",
+        )),
+    }
+}
+
+/// Main loop of the writer thread.
+fn write_messages(mut sink: WriteSink, receiver: Receiver<Message>) -> io::Result<()> {
+    sink.write_header()?;
+    for message in receiver {
+        match message {
+            Message::Steps(steps) => {
+                for step in steps.iter() {
+                    sink.write_step(&step.as_step())?;
+                }
+            }
+            Message::Flush(ack) => {
+                sink.flush()?;
+                let _ = ack.send(());
+            }
+        }
+    }
+    sink.close()
+}
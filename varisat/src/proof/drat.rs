@@ -12,12 +12,49 @@ fn drat_step(
         ProofStep::AtClause { clause, .. } => {
             emit_drat_step(true, &clause)?;
         }
+        ProofStep::RatClause { clause, pivot, .. }
+        | ProofStep::BlockedClause { clause, pivot, .. } => {
+            // DRAT uses the first literal as pivot
+            let mut lits = Vec::with_capacity(clause.len());
+            lits.push(*pivot);
+            lits.extend(clause.iter().cloned().filter(|lit| lit != pivot));
+            emit_drat_step(true, &lits)?;
+        }
+        ProofStep::PrClause {
+            clause, witness, ..
+        } => {
+            // DPR repeats the first literal of the clause to mark the beginning of the witness, so
+            // the first literal needs to be part of the witness
+            let first = clause
+                .iter()
+                .cloned()
+                .find(|lit| witness.contains(lit))
+                .expect("witness does not satisfy the PR clause");
+            let mut lits = Vec::with_capacity(clause.len() + witness.len());
+            lits.push(first);
+            lits.extend(clause.iter().cloned().filter(|&lit| lit != first));
+            lits.push(first);
+            lits.extend(witness.iter().cloned().filter(|&lit| lit != first));
+            emit_drat_step(true, &lits)?;
+        }
+        ProofStep::Extension { lit, definition } => {
+            // DRAT uses the first literal as pivot
+            let mut lits = Vec::with_capacity(definition.len() + 1);
+            lits.push(*lit);
+            lits.extend(definition.iter().map(|&def_lit| !def_lit));
+            emit_drat_step(true, &lits)?;
+            for &def_lit in definition.iter() {
+                emit_drat_step(true, &[!*lit, def_lit])?;
+            }
+        }
         ProofStep::UnitClauses { units } => {
             for &(unit, _hash) in units.iter() {
                 emit_drat_step(true, &[unit])?;
             }
         }
-        ProofStep::DeleteClause { clause, .. } => {
+        ProofStep::DeleteClause { clause, .. }
+        | ProofStep::DeleteRatClause { clause, .. }
+        | ProofStep::DeleteBlockedClause { clause, .. } => {
             emit_drat_step(false, &clause[..])?;
         }
         ProofStep::SolverVarName { .. }
@@ -26,11 +63,16 @@ fn drat_step(
         | ProofStep::ChangeSamplingMode { .. }
         | ProofStep::ChangeHashBits { .. }
         | ProofStep::Model { .. }
+        | ProofStep::SolveResult { .. }
+        | ProofStep::Checkpoint
         | ProofStep::End => (),
         ProofStep::AddClause { .. } => {
             // TODO allow error handling here?
             panic!("incremental clause additions not supported by DRAT proofs");
         }
+        ProofStep::DeleteHashedClause { .. } => {
+            panic!("hashed clause deletions not supported by DRAT proofs");
+        }
         ProofStep::Assumptions { .. } | ProofStep::FailedAssumptions { .. } => {
             // TODO allow error handling here?
             panic!("assumptions not supported by DRAT proofs");
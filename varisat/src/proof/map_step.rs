@@ -1,5 +1,7 @@
 //! Maps literals and hashes of clause steps between the solver and the checker.
 
+use std::mem::size_of;
+
 use varisat_formula::{Lit, Var};
 
 use super::{ClauseHash, ProofStep};
@@ -8,11 +10,21 @@ use super::{ClauseHash, ProofStep};
 #[derive(Default)]
 pub struct MapStep {
     lit_buf: Vec<Lit>,
+    witness_buf: Vec<Lit>,
     hash_buf: Vec<ClauseHash>,
     unit_buf: Vec<(Lit, ClauseHash)>,
+    resolution_buf: Vec<(ClauseHash, usize)>,
 }
 
 impl MapStep {
+    /// Heap memory used by the buffers in bytes.
+    pub fn memory_usage(&self) -> usize {
+        (self.lit_buf.capacity() + self.witness_buf.capacity()) * size_of::<Lit>()
+            + self.hash_buf.capacity() * size_of::<ClauseHash>()
+            + self.unit_buf.capacity() * size_of::<(Lit, ClauseHash)>()
+            + self.resolution_buf.capacity() * size_of::<(ClauseHash, usize)>()
+    }
+
     pub fn map_lits(&mut self, lits: &[Lit], map_var: impl Fn(Var) -> Var) -> &[Lit] {
         let map_var_ref = &map_var;
         self.lit_buf.clear();
@@ -59,6 +71,125 @@ impl MapStep {
                 }
             }
 
+            ProofStep::RatClause {
+                clause,
+                pivot,
+                resolutions,
+                propagation_hashes,
+            } => {
+                self.lit_buf.clear();
+                self.lit_buf.extend(clause.iter().cloned().map(map_lit));
+                self.resolution_buf.clear();
+                self.resolution_buf.extend(
+                    resolutions
+                        .iter()
+                        .map(|&(hash, count)| (map_hash(hash), count)),
+                );
+                self.hash_buf.clear();
+                self.hash_buf
+                    .extend(propagation_hashes.iter().cloned().map(map_hash));
+                ProofStep::RatClause {
+                    clause: &self.lit_buf,
+                    pivot: map_lit(pivot),
+                    resolutions: &self.resolution_buf,
+                    propagation_hashes: &self.hash_buf,
+                }
+            }
+
+            ProofStep::BlockedClause {
+                clause,
+                pivot,
+                blocking,
+            } => {
+                self.lit_buf.clear();
+                self.lit_buf.extend(clause.iter().cloned().map(map_lit));
+                self.hash_buf.clear();
+                self.hash_buf.extend(blocking.iter().cloned().map(map_hash));
+                ProofStep::BlockedClause {
+                    clause: &self.lit_buf,
+                    pivot: map_lit(pivot),
+                    blocking: &self.hash_buf,
+                }
+            }
+
+            ProofStep::DeleteBlockedClause {
+                clause,
+                pivot,
+                blocking,
+            } => {
+                self.lit_buf.clear();
+                self.lit_buf.extend(clause.iter().cloned().map(map_lit));
+                self.hash_buf.clear();
+                self.hash_buf.extend(blocking.iter().cloned().map(map_hash));
+                ProofStep::DeleteBlockedClause {
+                    clause: &self.lit_buf,
+                    pivot: map_lit(pivot),
+                    blocking: &self.hash_buf,
+                }
+            }
+
+            ProofStep::DeleteRatClause {
+                clause,
+                pivot,
+                resolutions,
+                propagation_hashes,
+            } => {
+                self.lit_buf.clear();
+                self.lit_buf.extend(clause.iter().cloned().map(map_lit));
+                self.resolution_buf.clear();
+                self.resolution_buf.extend(
+                    resolutions
+                        .iter()
+                        .map(|&(hash, count)| (map_hash(hash), count)),
+                );
+                self.hash_buf.clear();
+                self.hash_buf
+                    .extend(propagation_hashes.iter().cloned().map(map_hash));
+                ProofStep::DeleteRatClause {
+                    clause: &self.lit_buf,
+                    pivot: map_lit(pivot),
+                    resolutions: &self.resolution_buf,
+                    propagation_hashes: &self.hash_buf,
+                }
+            }
+
+            ProofStep::PrClause {
+                clause,
+                witness,
+                reduced_clauses,
+                propagation_hashes,
+            } => {
+                self.lit_buf.clear();
+                self.lit_buf.extend(clause.iter().cloned().map(map_lit));
+                self.witness_buf.clear();
+                self.witness_buf
+                    .extend(witness.iter().cloned().map(map_lit));
+                self.resolution_buf.clear();
+                self.resolution_buf.extend(
+                    reduced_clauses
+                        .iter()
+                        .map(|&(hash, count)| (map_hash(hash), count)),
+                );
+                self.hash_buf.clear();
+                self.hash_buf
+                    .extend(propagation_hashes.iter().cloned().map(map_hash));
+                ProofStep::PrClause {
+                    clause: &self.lit_buf,
+                    witness: &self.witness_buf,
+                    reduced_clauses: &self.resolution_buf,
+                    propagation_hashes: &self.hash_buf,
+                }
+            }
+
+            ProofStep::Extension { lit, definition } => {
+                self.lit_buf.clear();
+                self.lit_buf.extend(definition.iter().cloned().map(map_lit));
+                ProofStep::Extension {
+                    lit: map_lit(lit),
+                    definition: &self.lit_buf,
+                }
+            }
+
             ProofStep::UnitClauses { units } => {
                 self.unit_buf.clear();
                 self.unit_buf.extend(
@@ -80,6 +211,11 @@ impl MapStep {
                 }
             }
 
+            ProofStep::DeleteHashedClause { hash, proof } => ProofStep::DeleteHashedClause {
+                hash: map_hash(hash),
+                proof,
+            },
+
             ProofStep::Model { assignment } => {
                 self.lit_buf.clear();
                 self.lit_buf.extend(assignment.iter().cloned().map(map_lit));
@@ -113,7 +249,10 @@ impl MapStep {
                 }
             }
 
-            ProofStep::ChangeHashBits { .. } | ProofStep::End => step.clone(),
+            ProofStep::ChangeHashBits { .. }
+            | ProofStep::SolveResult { .. }
+            | ProofStep::Checkpoint
+            | ProofStep::End => step.clone(),
 
             ProofStep::SolverVarName { .. }
             | ProofStep::UserVarName { .. }
@@ -45,6 +45,13 @@ impl Assignment {
         self.last_value[var.index()]
     }
 
+    /// Overwrite the value last assigned to a variable.
+    ///
+    /// This is used as the phase for the next decision on that variable.
+    pub fn set_last_var_value(&mut self, var: Var, value: bool) {
+        self.last_value[var.index()] = value;
+    }
+
     /// Value assigned to a literal.
     pub fn lit_value(&self, lit: Lit) -> Option<bool> {
         self.assignment[lit.index()].map(|b| b ^ lit.is_negative())
@@ -32,6 +32,8 @@
 //! [handbook-ch4]: https://www.satassociation.org/articles/FAIA185-0131.pdf
 //! [minisat-2.1]: https://www.cril.univ-artois.fr/SAT09/solvers/booklet.pdf
 
+use std::mem::size_of;
+
 use partial_ref::{partial, PartialRef};
 
 use varisat_formula::Lit;
@@ -109,6 +111,16 @@ impl Watchlists {
     pub fn disable(&mut self) {
         self.enabled = false;
     }
+
+    /// Heap memory used by the watchlists in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.watches.capacity() * size_of::<Vec<Watch>>()
+            + self
+                .watches
+                .iter()
+                .map(|watches| watches.capacity() * size_of::<Watch>())
+                .sum::<usize>()
+    }
 }
 
 /// Enable and rebuild watchlists.
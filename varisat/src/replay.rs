@@ -0,0 +1,113 @@
+//! Replaying proofs into a solver.
+use varisat_checker::{CheckedProofStep, CheckerData, ProcessorError, ProofProcessor, StepFilter};
+use varisat_formula::Lit;
+
+use crate::solver::Solver;
+
+/// Proof processor that adds the lemmas of a proof to a solver as learned clauses.
+///
+/// This can be used to warm-start a solver for the same formula using the proof of a previous
+/// solve. All added clauses of the proof are passed to
+/// [`import_learned_clause`](Solver::import_learned_clause), so the target solver only keeps
+/// lemmas that it can derive by unit propagation. Lemmas containing variables that are hidden or
+/// unknown to the target solver are skipped.
+pub struct ProofReplay<'s, 'a> {
+    solver: &'s mut Solver<'a>,
+    clause: Vec<Lit>,
+    imported: usize,
+    rejected: usize,
+}
+
+impl<'s, 'a> ProofReplay<'s, 'a> {
+    /// Create a processor replaying a proof into the given solver.
+    pub fn new(solver: &'s mut Solver<'a>) -> ProofReplay<'s, 'a> {
+        ProofReplay {
+            solver,
+            clause: vec![],
+            imported: 0,
+            rejected: 0,
+        }
+    }
+
+    /// Number of lemmas added to the solver.
+    pub fn imported(&self) -> usize {
+        self.imported
+    }
+
+    /// Number of lemmas that were not added to the solver.
+    pub fn rejected(&self) -> usize {
+        self.rejected
+    }
+}
+
+impl<'s, 'a> ProofProcessor for ProofReplay<'s, 'a> {
+    fn process_step(
+        &mut self,
+        step: &CheckedProofStep,
+        data: CheckerData,
+    ) -> Result<(), ProcessorError> {
+        let clause = match step {
+            CheckedProofStep::AtClause { clause, .. }
+            | CheckedProofStep::RatClause { clause, .. }
+            | CheckedProofStep::PrClause { clause, .. } => clause,
+            _ => return Ok(()),
+        };
+
+        self.clause.clear();
+        for &lit in clause.iter() {
+            match data.user_from_proof_lit(lit) {
+                Some(user_lit) => self.clause.push(user_lit),
+                None => {
+                    self.rejected += 1;
+                    return Ok(());
+                }
+            }
+        }
+
+        if self.solver.import_learned_clause(&self.clause)? {
+            self.imported += 1;
+        } else {
+            self.rejected += 1;
+        }
+
+        Ok(())
+    }
+
+    fn step_filter(&self) -> StepFilter {
+        StepFilter {
+            lemmas: true,
+            ..StepFilter::none()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proptest::prelude::*;
+
+    use varisat_formula::test::sgen_unsat_formula;
+
+    proptest! {
+        #[test]
+        fn replay_sgen_unsat(formula in sgen_unsat_formula(1..7usize)) {
+            let mut target = Solver::new();
+            target.add_formula(&formula);
+
+            {
+                let mut replay = ProofReplay::new(&mut target);
+
+                let mut solver = Solver::new();
+                solver.add_proof_processor(&mut replay);
+                solver.add_formula(&formula);
+                prop_assert_eq!(solver.solve().ok(), Some(false));
+                drop(solver);
+
+                prop_assert!(replay.imported() > 0);
+            }
+
+            prop_assert_eq!(target.solve().ok(), Some(false));
+        }
+    }
+}
@@ -5,17 +5,24 @@ use log::info;
 
 use partial_ref::{partial, PartialRef};
 
+use crate::callbacks::{SolverEvent, SolverStats};
 use crate::cdcl::conflict_step;
 use crate::clause::reduce::{reduce_locals, reduce_mids};
 use crate::clause::{collect_garbage, Tier};
-use crate::context::{parts::*, Context};
+use crate::config::SolverConfigHandle;
+use crate::context::{config_changed, parts::*, Context};
+use crate::memory::enforce_memory_limit;
 use crate::prop::restart;
+use crate::solver::SolverError;
 use crate::state::SatState;
 
 mod luby;
 
 use luby::LubySequence;
 
+/// Number of conflicts between checks of the memory limit.
+const MEMORY_CHECK_INTERVAL: u64 = 100;
+
 /// Scheduling of processing and solving steps.
 #[derive(Default)]
 pub struct Schedule {
@@ -23,9 +30,37 @@ pub struct Schedule {
     next_restart: u64,
     restarts: u64,
     luby: LubySequence,
+    config_handle: SolverConfigHandle,
+}
+
+impl Schedule {
+    /// Handle for updating the configuration during solving.
+    pub fn config_handle(&self) -> &SolverConfigHandle {
+        &self.config_handle
+    }
+}
+
+/// Apply configuration updates made through the schedule's [`SolverConfigHandle`].
+fn apply_config_updates(
+    mut ctx: partial!(
+        Context,
+        mut ClauseActivityP,
+        mut SolverConfigP,
+        mut VsidsP,
+        ScheduleP,
+    ),
+) {
+    if let Some(config_update) = ctx.part(ScheduleP).config_handle.take_pending() {
+        config_update
+            .apply(ctx.part_mut(SolverConfigP))
+            .expect("config update was validated by the handle");
+        config_changed(ctx.borrow(), &config_update);
+    }
 }
 
 /// Perform one step of the schedule.
+///
+/// Pending updates of the configuration are applied before each step.
 pub fn schedule_step<'a>(
     mut ctx: partial!(
         Context<'a>,
@@ -33,6 +68,7 @@ pub fn schedule_step<'a>(
         mut AssignmentP,
         mut AssumptionsP,
         mut BinaryClausesP,
+        mut CallbacksP<'a>,
         mut ClauseActivityP,
         mut ClauseAllocP,
         mut ClauseDbP,
@@ -47,44 +83,67 @@ pub fn schedule_step<'a>(
         mut VariablesP,
         mut VsidsP,
         mut WatchlistsP,
-        SolverConfigP,
+        mut SolverConfigP,
     ),
 ) -> bool {
+    apply_config_updates(ctx.borrow());
+
     let (schedule, mut ctx) = ctx.split_part_mut(ScheduleP);
+
     let (config, mut ctx) = ctx.split_part(SolverConfigP);
 
     if ctx.part(SolverStateP).sat_state != SatState::Unknown {
         false
     } else if ctx.part(SolverStateP).solver_error.is_some() {
         false
+    } else if ctx.part_mut(CallbacksP).should_terminate() {
+        false
     } else {
-        if schedule.conflicts > 0 && schedule.conflicts % 5000 == 0 {
-            let db = ctx.part(ClauseDbP);
-            let units = ctx.part(TrailP).top_level_assignment_count();
-            info!(
-                "confl: {}k rest: {} vars: {} bin: {} irred: {} core: {} mid: {} local: {}",
-                schedule.conflicts / 1000,
-                schedule.restarts,
-                ctx.part(AssignmentP).assignment().len() - units,
-                ctx.part(BinaryClausesP).count(),
-                db.count_by_tier(Tier::Irred),
-                db.count_by_tier(Tier::Core),
-                db.count_by_tier(Tier::Mid),
-                db.count_by_tier(Tier::Local)
-            );
+        let report_progress = schedule.conflicts > 0 && schedule.conflicts % 5000 == 0;
+
+        if report_progress || ctx.part(CallbacksP).wants_events() {
+            let stats = collect_stats(ctx.borrow(), schedule);
+            *ctx.part_mut(CallbacksP).stats_mut() = stats;
+
+            if report_progress {
+                info!(
+                    "confl: {}k rest: {} vars: {} bin: {} irred: {} core: {} mid: {} local: {}",
+                    stats.conflicts / 1000,
+                    stats.restarts,
+                    stats.vars,
+                    stats.binary_clauses,
+                    stats.irred_clauses,
+                    stats.core_clauses,
+                    stats.mid_clauses,
+                    stats.local_clauses
+                );
+                ctx.part_mut(CallbacksP).emit(SolverEvent::Progress);
+            }
         }
 
         if schedule.next_restart == schedule.conflicts {
             restart(ctx.borrow());
             schedule.restarts += 1;
             schedule.next_restart += config.luby_restart_interval_scale * schedule.luby.advance();
+            ctx.part_mut(CallbacksP).emit(SolverEvent::Restart);
         }
 
         if schedule.conflicts % config.reduce_locals_interval == 0 {
             reduce_locals(ctx.borrow());
+            ctx.part_mut(CallbacksP).emit(SolverEvent::ReduceLocals);
         }
         if schedule.conflicts % config.reduce_mids_interval == 0 {
             reduce_mids(ctx.borrow());
+            ctx.part_mut(CallbacksP).emit(SolverEvent::ReduceMids);
+        }
+
+        if let Some(limit) = config.memory_limit {
+            if schedule.conflicts % MEMORY_CHECK_INTERVAL == 0
+                && !enforce_memory_limit(ctx.borrow(), limit)
+            {
+                ctx.part_mut(SolverStateP).solver_error = Some(SolverError::MemoryLimitExceeded);
+                return false;
+            }
         }
 
         collect_garbage(ctx.borrow());
@@ -94,3 +153,22 @@ pub fn schedule_step<'a>(
         true
     }
 }
+
+/// Collect statistics about the current state of the search.
+fn collect_stats(
+    ctx: partial!(Context, AssignmentP, BinaryClausesP, ClauseDbP, TrailP),
+    schedule: &Schedule,
+) -> SolverStats {
+    let db = ctx.part(ClauseDbP);
+    let units = ctx.part(TrailP).top_level_assignment_count();
+    SolverStats {
+        conflicts: schedule.conflicts,
+        restarts: schedule.restarts,
+        vars: ctx.part(AssignmentP).assignment().len() - units,
+        binary_clauses: ctx.part(BinaryClausesP).count(),
+        irred_clauses: db.count_by_tier(Tier::Irred),
+        core_clauses: db.count_by_tier(Tier::Core),
+        mid_clauses: db.count_by_tier(Tier::Mid),
+        local_clauses: db.count_by_tier(Tier::Local),
+    }
+}
@@ -2,54 +2,76 @@
 use std::io;
 
 use partial_ref::{IntoPartialRef, IntoPartialRefMut, PartialRef};
+use thiserror::Error;
 
-use failure::{Error, Fail};
+use varisat_checker::{CheckerError, ProcessorError, ProofProcessor};
+use varisat_dimacs::{DimacsParser, GcnfFormula, IcnfFormula, ParserError};
+use varisat_formula::{CnfFormula, ExtendFormula, Lit, Model, Var};
 
-use varisat_checker::ProofProcessor;
-use varisat_dimacs::DimacsParser;
-use varisat_formula::{CnfFormula, ExtendFormula, Lit, Var};
-
-use crate::assumptions::set_assumptions;
-use crate::config::SolverConfigUpdate;
+use crate::assumptions::{
+    add_activation, add_group_activation, propagate_assumptions, remove_activation, set_assumptions,
+};
+use crate::config::{SolverConfigError, SolverConfigHandle, SolverConfigUpdate};
 use crate::context::{config_changed, parts::*, Context};
+use crate::decision::set_initial_phases;
+use crate::learned::{export_learned, import_learned, import_learned_clause};
 use crate::load::load_clause;
 use crate::proof;
 use crate::schedule::schedule_step;
 use crate::state::SatState;
 use crate::variables;
 
-pub use crate::proof::ProofFormat;
+pub use crate::assumptions::PropagationResult;
+pub use crate::callbacks::{SolverEvent, SolverStats};
+pub use crate::learned::ImportLearnedError;
+pub use crate::proof::{ProofFormat, ProofSink};
+
+/// Number of batches of steps queued by [`Solver::write_proof_in_background`] before solving
+/// blocks.
+const PROOF_QUEUE_BATCHES: usize = 64;
 
 /// Possible errors while solving a formula.
-#[derive(Debug, Fail)]
+#[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum SolverError {
-    #[fail(display = "The solver was interrupted")]
+    #[error("The solver was interrupted")]
     Interrupted,
-    #[fail(display = "Error in proof processor: {}", cause)]
+    #[error("The memory limit was exceeded")]
+    MemoryLimitExceeded,
+    #[error("Error in proof processor: {cause}")]
     ProofProcessorError {
-        #[cause]
-        cause: Error,
+        #[source]
+        cause: ProcessorError,
     },
-    #[fail(display = "Error writing proof file: {}", cause)]
+    #[error("Error writing proof file: {cause}")]
     ProofIoError {
-        #[cause]
+        #[source]
         cause: io::Error,
     },
-    #[doc(hidden)]
-    #[fail(display = "__Nonexhaustive")]
-    __Nonexhaustive,
+    #[error("Self check failed: {cause}")]
+    SelfCheckFailed {
+        #[source]
+        cause: CheckerError,
+    },
 }
 
 impl SolverError {
     /// Whether a Solver instance can be used after producing such an error.
     pub fn is_recoverable(&self) -> bool {
         match self {
-            SolverError::Interrupted => true,
+            SolverError::Interrupted | SolverError::MemoryLimitExceeded => true,
             _ => false,
         }
     }
 }
 
+/// Handle to a clause added using [`Solver::add_removable_clause`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ClauseHandle {
+    /// User variable that activates the clause when assumed.
+    activation: Var,
+}
+
 /// A boolean satisfiability solver.
 #[derive(Default)]
 pub struct Solver<'a> {
@@ -63,13 +85,23 @@ impl<'a> Solver<'a> {
     }
 
     /// Change the solver configuration.
-    pub fn config(&mut self, config_update: &SolverConfigUpdate) -> Result<(), Error> {
+    pub fn config(&mut self, config_update: &SolverConfigUpdate) -> Result<(), SolverConfigError> {
         config_update.apply(&mut self.ctx.solver_config)?;
         let mut ctx = self.ctx.into_partial_ref_mut();
         config_changed(ctx.borrow(), config_update);
         Ok(())
     }
 
+    /// Handle for changing the solver configuration while solving.
+    ///
+    /// Updates made through the returned handle take effect before the solver's next scheduling
+    /// step, when the next [`solve`](Solver::solve) call starts or before the next conflict of a
+    /// running search. This can be used from a different thread or from within a callback while
+    /// [`solve`](Solver::solve) is running.
+    pub fn config_handle(&self) -> SolverConfigHandle {
+        self.ctx.schedule.config_handle().clone()
+    }
+
     /// Add a formula to the solver.
     pub fn add_formula(&mut self, formula: &CnfFormula) {
         let mut ctx = self.ctx.into_partial_ref_mut();
@@ -78,12 +110,100 @@ impl<'a> Solver<'a> {
         }
     }
 
+    /// Add a clause that can be removed later.
+    ///
+    /// This is implemented using an activation literal that is automatically assumed during
+    /// solving. The activation literal uses a new user variable, which is not part of the model.
+    pub fn add_removable_clause(&mut self, clause: &[Lit]) -> ClauseHandle {
+        self.map_user_vars(clause);
+        let activation = self.new_var();
+
+        let mut lits = clause.to_owned();
+        lits.push(activation.negative());
+        self.add_clause(&lits);
+
+        let mut ctx = self.ctx.into_partial_ref_mut();
+        add_activation(ctx.borrow(), activation);
+
+        ClauseHandle { activation }
+    }
+
+    /// Remove a clause added using [`add_removable_clause`](Solver::add_removable_clause).
+    ///
+    /// Panics if the clause was already removed.
+    pub fn remove_clause(&mut self, handle: ClauseHandle) {
+        {
+            let mut ctx = self.ctx.into_partial_ref_mut();
+            remove_activation(ctx.borrow(), handle.activation);
+        }
+        self.add_clause(&[handle.activation.negative()]);
+        self.hide_var(handle.activation);
+    }
+
+    /// Add a clause to a clause group.
+    ///
+    /// Clause groups are implemented using an activation literal per group. When the formula is
+    /// unsatisfiable, [`failed_groups`](Solver::failed_groups) returns the groups involved.
+    pub fn add_group_clause(&mut self, group: usize, clause: &[Lit]) {
+        self.map_user_vars(clause);
+        let activation = match self.ctx.assumptions.group_activation(group) {
+            Some(activation) => activation,
+            None => {
+                let activation = self.new_var();
+                let mut ctx = self.ctx.into_partial_ref_mut();
+                add_group_activation(ctx.borrow(), group, activation);
+                activation
+            }
+        };
+
+        let mut lits = clause.to_owned();
+        lits.push(activation.negative());
+        self.add_clause(&lits);
+    }
+
+    /// Add a formula parsed from a GCNF file.
+    ///
+    /// The hard clauses are added as normal clauses, while the clauses of each group are added to
+    /// the clause group of the same number using [`add_group_clause`](Solver::add_group_clause).
+    /// This makes [`failed_groups`](Solver::failed_groups) report GCNF group numbers.
+    pub fn add_gcnf(&mut self, formula: &GcnfFormula) {
+        {
+            // Allocate all variables up front, so activation variables don't collide with
+            // variables of later groups.
+            let mut ctx = self.ctx.into_partial_ref_mut();
+            for index in 0..formula.var_count() {
+                variables::global_from_user(ctx.borrow(), Var::from_index(index), false);
+            }
+        }
+
+        self.add_formula(&formula.hard);
+        for (index, group) in formula.groups.iter().enumerate() {
+            for clause in group.iter() {
+                self.add_group_clause(index + 1, clause);
+            }
+        }
+    }
+
+    /// Make sure the variables of a clause are allocated.
+    ///
+    /// This prevents new activation variables from colliding with variables of the clause.
+    fn map_user_vars(&mut self, clause: &[Lit]) {
+        let mut ctx = self.ctx.into_partial_ref_mut();
+        for &lit in clause {
+            variables::global_from_user(ctx.borrow(), lit.var(), false);
+        }
+    }
+
     /// Reads and adds a formula in DIMACS CNF format.
     ///
     /// Using this avoids creating a temporary [`CnfFormula`].
-    pub fn add_dimacs_cnf(&mut self, input: impl io::Read) -> Result<(), Error> {
-        let parser = DimacsParser::parse_incremental(input, |parser| {
-            Ok(self.add_formula(&parser.take_formula()))
+    ///
+    /// With the `compression` feature enabled, gzip, xz and zstd compressed input is decompressed
+    /// transparently.
+    pub fn add_dimacs_cnf(&mut self, input: impl io::Read) -> Result<(), ParserError> {
+        let parser = DimacsParser::parse_incremental(input, |parser| -> Result<(), ParserError> {
+            self.add_formula(&parser.take_formula());
+            Ok(())
         })?;
 
         log::info!(
@@ -95,6 +215,61 @@ impl<'a> Solver<'a> {
         Ok(())
     }
 
+    /// Solve an incremental problem parsed from an iCNF file.
+    ///
+    /// The cubes are solved in order, each one after adding its clauses and under its assumptions.
+    /// After solving a cube, `report` is invoked with the solver, the index of the cube and the
+    /// result, so that the model or failed core can be queried. The trailing clauses are added
+    /// after the last cube and the assumptions are cleared.
+    ///
+    /// Stops at the first error, which is returned.
+    pub fn solve_icnf(
+        &mut self,
+        formula: &IcnfFormula,
+        mut report: impl FnMut(&Solver<'a>, usize, bool),
+    ) -> Result<(), SolverError> {
+        for (index, cube) in formula.cubes.iter().enumerate() {
+            self.add_formula(&cube.clauses);
+            self.assume(&cube.assumptions);
+            let result = self.solve()?;
+            report(self, index, result);
+        }
+
+        self.add_formula(&formula.trailing_clauses);
+        self.assume(&[]);
+
+        Ok(())
+    }
+
+    /// Write the learned long clauses in DIMACS CNF format.
+    ///
+    /// The written clauses can be passed to [`import_learned`](Solver::import_learned) of a solver
+    /// for the same formula, to avoid learning them again.
+    pub fn export_learned(&self, mut target: impl io::Write) -> io::Result<()> {
+        let mut ctx = self.ctx.into_partial_ref();
+        export_learned(ctx.borrow(), &mut target)
+    }
+
+    /// Add previously learned clauses in DIMACS CNF format.
+    ///
+    /// Each clause is validated using unit propagation and skipped if it isn't implied in that way
+    /// by the current formula. This cannot be used while a proof is generated.
+    ///
+    /// Returns the number of clauses that were added.
+    pub fn import_learned(&mut self, input: impl io::Read) -> Result<usize, ImportLearnedError> {
+        let mut ctx = self.ctx.into_partial_ref_mut();
+        import_learned(ctx.borrow(), input)
+    }
+
+    /// Add a single previously learned clause.
+    ///
+    /// Like [`import_learned`](Solver::import_learned), the clause is only added if it is implied
+    /// by the current formula using unit propagation. Returns whether the clause was added.
+    pub fn import_learned_clause(&mut self, clause: &[Lit]) -> Result<bool, ImportLearnedError> {
+        let mut ctx = self.ctx.into_partial_ref_mut();
+        import_learned_clause(ctx.borrow(), clause)
+    }
+
     /// Sets the "witness" sampling mode for a variable.
     pub fn witness_var(&mut self, var: Var) {
         // TODO add link to sampling mode section of the manual when written
@@ -147,6 +322,7 @@ impl<'a> Solver<'a> {
 
         while schedule_step(ctx.borrow()) {}
 
+        proof::add_solve_result(ctx.borrow());
         proof::solve_finished(ctx.borrow());
 
         self.check_for_solver_error()?;
@@ -184,13 +360,72 @@ impl<'a> Solver<'a> {
         set_assumptions(ctx.borrow(), assumptions);
     }
 
-    /// Set of literals that satisfy the formula.
-    pub fn model(&self) -> Option<Vec<Lit>> {
+    /// Perform unit propagation under the given assumptions without searching.
+    ///
+    /// Returns the literals implied by the assumptions or a subset of conflicting assumptions.
+    /// This doesn't change the assumptions set using [`assume`](Solver::assume).
+    pub fn propagate(&mut self, assumptions: &[Lit]) -> PropagationResult {
+        let mut ctx = self.ctx.into_partial_ref_mut();
+        propagate_assumptions(ctx.borrow(), assumptions)
+    }
+
+    /// Seed the saved phases from a (partial) assignment.
+    ///
+    /// When the solver makes a decision on a variable of the given assignment, it uses the value
+    /// given here. This is useful for re-solving after small changes of a formula, starting from a
+    /// previously found near-solution. Literals of variables that are not present in the solver are
+    /// ignored.
+    pub fn set_initial_assignment(&mut self, assignment: &[Lit]) {
+        let mut ctx = self.ctx.into_partial_ref_mut();
+        set_initial_phases(ctx.borrow(), assignment, false);
+    }
+
+    /// Seed the saved phases from a (partial) assignment and decide on these variables first.
+    ///
+    /// Like [`set_initial_assignment`](Solver::set_initial_assignment), but also makes sure that
+    /// the next decisions are made on the given variables, in the given order.
+    pub fn set_initial_decisions(&mut self, assignment: &[Lit]) {
+        let mut ctx = self.ctx.into_partial_ref_mut();
+        set_initial_phases(ctx.borrow(), assignment, true);
+    }
+
+    /// Set a callback that is polled at every conflict.
+    ///
+    /// When the callback returns `true`, the current call to [`solve`](Solver::solve) is stopped
+    /// and returns [`SolverError::Interrupted`]. The solver can be used again after that.
+    pub fn set_terminate_callback(&mut self, callback: impl FnMut() -> bool + 'a) {
+        self.ctx.callbacks.set_terminate(Some(Box::new(callback)));
+    }
+
+    /// Remove a callback set by [`set_terminate_callback`](Solver::set_terminate_callback).
+    pub fn clear_terminate_callback(&mut self) {
+        self.ctx.callbacks.set_terminate(None);
+    }
+
+    /// Set a callback that is invoked for restarts, clause database reductions, simplifications
+    /// and periodic progress reports.
+    ///
+    /// Each event is reported together with current [`SolverStats`].
+    pub fn set_event_callback(&mut self, callback: impl FnMut(SolverEvent, &SolverStats) + 'a) {
+        self.ctx.callbacks.set_event(Some(Box::new(callback)));
+    }
+
+    /// Remove a callback set by [`set_event_callback`](Solver::set_event_callback).
+    pub fn clear_event_callback(&mut self) {
+        self.ctx.callbacks.set_event(None);
+    }
+
+    /// Assignment that satisfies the formula.
+    ///
+    /// Assigns all user variables except activation variables of removable clauses and clause
+    /// groups.
+    pub fn model(&self) -> Option<Model> {
         let ctx = self.ctx.into_partial_ref();
         if ctx.part(SolverStateP).sat_state == SatState::Sat {
             Some(
                 ctx.part(VariablesP)
                     .user_var_iter()
+                    .filter(|&user_var| !ctx.part(AssumptionsP).is_activation(user_var))
                     .flat_map(|user_var| {
                         let global_var = ctx
                             .part(VariablesP)
@@ -218,6 +453,18 @@ impl<'a> Solver<'a> {
         }
     }
 
+    /// Clause groups involved in making the formula unsatisfiable.
+    ///
+    /// This is not guaranteed to be minimal. Returns an empty list when the formula is
+    /// unsatisfiable without the clauses of any group.
+    pub fn failed_groups(&self) -> Option<Vec<usize>> {
+        match self.ctx.solver_state.sat_state {
+            SatState::UnsatUnderAssumptions => Some(self.ctx.assumptions.failed_groups()),
+            SatState::Unsat => Some(vec![]),
+            SatState::Unknown | SatState::Sat => None,
+        }
+    }
+
     /// Generate a proof of unsatisfiability during solving.
     ///
     /// This needs to be called before any clauses are added.
@@ -226,7 +473,121 @@ impl<'a> Solver<'a> {
             self.ctx.solver_state.formula_is_empty,
             "called after clauses were added"
         );
-        self.ctx.proof.write_proof(target, format);
+        let mut ctx = self.ctx.into_partial_ref_mut();
+        proof::write_proof(ctx.borrow(), target, format);
+    }
+
+    /// Generate a proof of unsatisfiability during solving, writing it to a file.
+    ///
+    /// Like [`write_proof`](Solver::write_proof), but syncing the proof also syncs the file to
+    /// disk. See [`set_proof_sync_interval`](Solver::set_proof_sync_interval).
+    ///
+    /// This needs to be called before any clauses are added.
+    pub fn write_proof_file(&mut self, file: std::fs::File, format: ProofFormat) {
+        assert!(
+            self.ctx.solver_state.formula_is_empty,
+            "called after clauses were added"
+        );
+        let mut ctx = self.ctx.into_partial_ref_mut();
+        proof::write_proof_file(ctx.borrow(), file, format);
+    }
+
+    /// Sync the generated proof every `steps` proof steps.
+    ///
+    /// Syncing flushes all buffers of the proof output. For proofs written using
+    /// [`write_proof_file`](Solver::write_proof_file) the file is also synced to disk. Proofs in
+    /// the [`Varisat`](ProofFormat::Varisat) format get a checkpoint step before each sync. When
+    /// solving is aborted, e.g. by a crash, the prefix of the proof up to the last checkpoint is
+    /// complete and can be found using
+    /// [`Checker::last_checkpoint`](varisat_checker::Checker::last_checkpoint).
+    ///
+    /// By default (`None`) the proof is only flushed before solving returns.
+    pub fn set_proof_sync_interval(&mut self, steps: Option<u64>) {
+        self.ctx.proof.set_sync_interval(steps);
+    }
+
+    /// Pass the steps of a proof of unsatisfiability to a custom sink during solving.
+    ///
+    /// Unlike [`write_proof`](Solver::write_proof) this doesn't write a proof header for the
+    /// [`Varisat`](ProofFormat::Varisat) format. See [`ProofSink`] for details on the passed steps.
+    ///
+    /// This needs to be called before any clauses are added.
+    pub fn write_proof_to_sink(&mut self, sink: impl ProofSink + 'a) {
+        assert!(
+            self.ctx.solver_state.formula_is_empty,
+            "called after clauses were added"
+        );
+        self.ctx.proof.write_proof_to_sink(Box::new(sink));
+    }
+
+    /// Generate a proof of unsatisfiability, writing it on a separate thread.
+    ///
+    /// Like [`write_proof`](Solver::write_proof), but proof steps are copied and queued for a
+    /// writer thread, which serializes them and writes them to `target`. This keeps both
+    /// serialization and slow IO off the solving thread. Only when the queue is full, solving waits
+    /// for the writer thread to catch up. IO errors are reported when queuing the next steps or
+    /// when calling [`close_proof`](Solver::close_proof), which waits until the whole proof was
+    /// written.
+    ///
+    /// This needs to be called before any clauses are added.
+    pub fn write_proof_in_background(
+        &mut self,
+        target: impl io::Write + Send + 'static,
+        format: ProofFormat,
+    ) {
+        assert!(
+            self.ctx.solver_state.formula_is_empty,
+            "called after clauses were added"
+        );
+        let mut ctx = self.ctx.into_partial_ref_mut();
+        proof::write_proof_in_background(ctx.borrow(), target, format, PROOF_QUEUE_BATCHES);
+    }
+
+    /// Generate a compressed proof of unsatisfiability during solving.
+    ///
+    /// Like [`write_proof`](Solver::write_proof), but compresses the written proof.
+    ///
+    /// This needs to be called before any clauses are added.
+    #[cfg(feature = "compression")]
+    pub fn write_compressed_proof(
+        &mut self,
+        target: impl io::Write + Send + 'a,
+        format: ProofFormat,
+        compression: varisat_checker::Compression,
+    ) -> io::Result<()> {
+        let target = varisat_checker::compress(target, compression)?;
+        self.write_proof(target, format);
+        Ok(())
+    }
+
+    /// Use a fixed width for clause hashes in generated varisat proofs.
+    ///
+    /// Wider hashes reduce the number of hash collisions the checker has to resolve, at the cost of
+    /// a larger proof. By default (`None`) the width is chosen based on the number of clauses. The
+    /// width must be between 1 and 128 bits.
+    ///
+    /// This needs to be called before any clauses are added.
+    pub fn set_proof_hash_bits(&mut self, bits: Option<u32>) {
+        assert!(
+            self.ctx.solver_state.formula_is_empty,
+            "called after clauses were added"
+        );
+        self.ctx.proof.set_hash_bits(bits);
+    }
+
+    /// Write deletions of long clauses using clause hashes in generated varisat proofs.
+    ///
+    /// This makes proofs dominated by deletions of long clauses smaller. Hashes are only used
+    /// while clause hashes have at least 64 bits, as the hash has to identify a single clause. See
+    /// also [`set_proof_hash_bits`](Solver::set_proof_hash_bits).
+    ///
+    /// This needs to be called before any clauses are added.
+    pub fn set_proof_delete_by_hash(&mut self, enabled: bool) {
+        assert!(
+            self.ctx.solver_state.formula_is_empty,
+            "called after clauses were added"
+        );
+        self.ctx.proof.set_delete_by_hash(enabled);
     }
 
     /// Stop generating a proof of unsatisfiability.
@@ -240,6 +601,9 @@ impl<'a> Solver<'a> {
 
     /// Generate and check a proof on the fly.
     ///
+    /// Every step of the generated proof is verified as soon as it is produced. A step that fails
+    /// to check aborts solving with a [`SolverError::SelfCheckFailed`] error.
+    ///
     /// This needs to be called before any clauses are added.
     pub fn enable_self_checking(&mut self) {
         assert!(
@@ -292,7 +656,7 @@ mod tests {
 
     use varisat_checker::{CheckedProofStep, CheckerData};
     use varisat_formula::test::{sat_formula, sgen_unsat_formula};
-    use varisat_formula::{cnf_formula, lits};
+    use varisat_formula::{cnf_formula, lit, lits, Lbool};
 
     use varisat_dimacs::write_dimacs;
 
@@ -329,6 +693,56 @@ mod tests {
         let _ = solver.solve();
     }
 
+    struct VecSink<'v> {
+        proof: &'v mut Vec<u8>,
+        closed: &'v mut bool,
+    }
+
+    impl<'v> ProofSink for VecSink<'v> {
+        fn format(&self) -> ProofFormat {
+            ProofFormat::Varisat
+        }
+
+        fn write_step(&mut self, step: &varisat_checker::ProofStep) -> io::Result<()> {
+            varisat_internal_proof::binary_format::write_step(self.proof, step)
+        }
+
+        fn close(&mut self) -> io::Result<()> {
+            *self.closed = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn custom_proof_sink() {
+        let formula = cnf_formula![
+            -1, -2, -3; -1, -2, -4; -1, -2, -5; -1, -3, -4; -1, -3, -5; -1, -4, -5; -2, -3, -4;
+            -2, -3, -5; -2, -4, -5; -3, -4, -5; 1, 2, 5; 1, 2, 3; 1, 2, 4; 1, 5, 3; 1, 5, 4;
+            1, 3, 4; 2, 5, 3; 2, 5, 4; 2, 3, 4; 5, 3, 4;
+        ];
+
+        let mut proof = vec![];
+        varisat_internal_proof::binary_format::write_header(&mut proof, &[]).unwrap();
+        let mut closed = false;
+
+        {
+            let mut solver = Solver::new();
+            solver.write_proof_to_sink(VecSink {
+                proof: &mut proof,
+                closed: &mut closed,
+            });
+            solver.add_formula(&formula);
+            assert_eq!(solver.solve().ok(), Some(false));
+            solver.close_proof().unwrap();
+        }
+
+        assert!(closed);
+
+        let mut checker = varisat_checker::Checker::new();
+        checker.add_formula(&formula).unwrap();
+        checker.check_proof(&proof[..]).unwrap();
+    }
+
     struct FailingProcessor;
 
     impl ProofProcessor for FailingProcessor {
@@ -336,8 +750,8 @@ mod tests {
             &mut self,
             _step: &CheckedProofStep,
             _data: CheckerData,
-        ) -> Result<(), Error> {
-            failure::bail!("failing processor");
+        ) -> Result<(), ProcessorError> {
+            Err("failing processor".into())
         }
     }
     #[test]
@@ -409,6 +823,310 @@ mod tests {
         assert_eq!(solver.solve().ok(), Some(true));
     }
 
+    #[test]
+    fn terminate_callback() {
+        let mut solver = Solver::new();
+
+        solver.add_formula(&cnf_formula![
+            -1, -2, -3; -1, -2, -4; -1, -2, -5; -1, -3, -4; -1, -3, -5; -1, -4, -5; -2, -3, -4;
+            -2, -3, -5; -2, -4, -5; -3, -4, -5; 1, 2, 5; 1, 2, 3; 1, 2, 4; 1, 5, 3; 1, 5, 4;
+            1, 3, 4; 2, 5, 3; 2, 5, 4; 2, 3, 4; 5, 3, 4;
+        ]);
+
+        solver.set_terminate_callback(|| true);
+
+        assert!(match solver.solve() {
+            Err(SolverError::Interrupted) => true,
+            _ => false,
+        });
+
+        solver.clear_terminate_callback();
+
+        assert_eq!(solver.solve().ok(), Some(false));
+    }
+
+    #[test]
+    fn config_handle() {
+        let mut solver = Solver::new();
+        let handle = solver.config_handle();
+
+        let mut config = SolverConfigUpdate::new();
+        config.luby_restart_interval_scale = Some(0);
+        assert!(handle.update(config).is_err());
+
+        let mut config = SolverConfigUpdate::new();
+        config.luby_restart_interval_scale = Some(64);
+        config.reduce_mids_interval = Some(100);
+        handle.update(config).unwrap();
+
+        let mut config = SolverConfigUpdate::new();
+        config.reduce_mids_interval = Some(200);
+        handle.update(config).unwrap();
+
+        solver.add_clause(&lits![1, 2]);
+        assert_eq!(solver.solve().ok(), Some(true));
+
+        assert_eq!(solver.ctx.solver_config.luby_restart_interval_scale, 64);
+        assert_eq!(solver.ctx.solver_config.reduce_mids_interval, 200);
+
+        let mut config = SolverConfigUpdate::new();
+        config.reduce_mids_interval = Some(300);
+        handle.update(config).unwrap();
+
+        assert_eq!(solver.solve().ok(), Some(true));
+
+        assert_eq!(solver.ctx.solver_config.luby_restart_interval_scale, 64);
+        assert_eq!(solver.ctx.solver_config.reduce_mids_interval, 300);
+    }
+
+    #[test]
+    fn memory_limit() {
+        let mut solver = Solver::new();
+
+        solver.add_formula(&cnf_formula![
+            -1, -2, -3; -1, -2, -4; -1, -2, -5; -1, -3, -4; -1, -3, -5; -1, -4, -5; -2, -3, -4;
+            -2, -3, -5; -2, -4, -5; -3, -4, -5; 1, 2, 5; 1, 2, 3; 1, 2, 4; 1, 5, 3; 1, 5, 4;
+            1, 3, 4; 2, 5, 3; 2, 5, 4; 2, 3, 4; 5, 3, 4;
+        ]);
+
+        let mut config = SolverConfigUpdate::new();
+        config.memory_limit = Some(Some(16));
+        solver.config(&config).unwrap();
+
+        assert!(match solver.solve() {
+            Err(SolverError::MemoryLimitExceeded) => true,
+            _ => false,
+        });
+
+        let mut config = SolverConfigUpdate::new();
+        config.memory_limit = Some(Some(1 << 20));
+        solver.config(&config).unwrap();
+
+        assert_eq!(solver.solve().ok(), Some(false));
+    }
+
+    #[test]
+    fn propagate_under_assumptions() {
+        let mut solver = Solver::new();
+
+        solver.add_formula(&cnf_formula![
+            -1, 2;
+            -2, 3;
+            -3, -4;
+            5, 6, 7;
+        ]);
+
+        let result = solver.propagate(&lits![1]);
+        assert_eq!(result.lit_value(lit!(3)), Lbool::True);
+        assert_eq!(result.lit_value(lit!(4)), Lbool::False);
+        assert_eq!(result.lit_value(lit!(5)), Lbool::Undef);
+
+        let mut implied = match result {
+            PropagationResult::Implied(implied) => implied,
+            result => panic!("unexpected result {:?}", result),
+        };
+        implied.sort();
+        assert_eq!(implied, lits![1, 2, 3, -4]);
+
+        match solver.propagate(&lits![5, 1, 4]) {
+            PropagationResult::Conflict(mut core) => {
+                core.sort();
+                assert_eq!(core, lits![1, 4]);
+            }
+            result => panic!("unexpected result {:?}", result),
+        };
+
+        match solver.propagate(&lits![4, 2]) {
+            PropagationResult::Conflict(mut core) => {
+                core.sort();
+                assert_eq!(core, lits![2, 4]);
+            }
+            result => panic!("unexpected result {:?}", result),
+        };
+
+        assert_eq!(solver.solve().ok(), Some(true));
+    }
+
+    #[test]
+    fn removable_clauses() {
+        let mut solver = Solver::new();
+
+        solver.add_clause(&lits![1, 2]);
+        let handle_a = solver.add_removable_clause(&lits![-1]);
+        let handle_b = solver.add_removable_clause(&lits![-2]);
+
+        assert_eq!(solver.solve().ok(), Some(false));
+        assert_eq!(solver.failed_core(), Some(&[][..]));
+
+        solver.remove_clause(handle_a);
+
+        assert_eq!(solver.solve().ok(), Some(true));
+        assert_eq!(solver.model().unwrap().lits(), lits![1, -2]);
+
+        solver.remove_clause(handle_b);
+        solver.add_clause(&lits![-1]);
+
+        assert_eq!(solver.solve().ok(), Some(true));
+        assert_eq!(solver.model().unwrap().lits(), lits![-1, 2]);
+    }
+
+    #[test]
+    fn clause_groups() {
+        let mut solver = Solver::new();
+
+        solver.add_group_clause(1, &lits![1, 2]);
+        solver.add_group_clause(2, &lits![-1]);
+        solver.add_group_clause(3, &lits![4, 5]);
+        solver.add_group_clause(4, &lits![-2, 3]);
+        solver.add_group_clause(4, &lits![-3, -2]);
+
+        assert_eq!(solver.failed_groups(), None);
+        assert_eq!(solver.solve().ok(), Some(false));
+
+        let mut groups = solver.failed_groups().unwrap();
+        groups.sort();
+        assert_eq!(groups, vec![1, 2, 4]);
+
+        let mut model_solver = Solver::new();
+        model_solver.add_group_clause(1, &lits![1, 2]);
+        model_solver.add_group_clause(2, &lits![-1]);
+        assert_eq!(model_solver.solve().ok(), Some(true));
+        assert_eq!(model_solver.model().unwrap().lits(), lits![-1, 2]);
+    }
+
+    #[test]
+    fn gcnf_groups() {
+        let formula = varisat_dimacs::GcnfParser::parse(
+            b"p gcnf 4 6 4\n{0} 1 2 0\n{1} -1 0\n{2} 4 0\n{3} -2 3 0\n{3} -3 -2 0\n{4} 4 -1 0\n"
+                as &[_],
+        )
+        .unwrap();
+
+        let mut solver = Solver::new();
+        solver.add_gcnf(&formula);
+
+        assert_eq!(solver.solve().ok(), Some(false));
+
+        let mut groups = solver.failed_groups().unwrap();
+        groups.sort();
+        assert_eq!(groups, vec![1, 3]);
+    }
+
+    #[test]
+    fn icnf_cubes() {
+        let formula = varisat_dimacs::IcnfParser::parse(
+            b"p inccnf\n1 2 0\n-1 2 0\na -2 0\na 1 0\n-2 3 0\na 1 0\na -3 0\n" as &[_],
+        )
+        .unwrap();
+
+        let mut solver = Solver::new();
+        let mut results = vec![];
+
+        solver
+            .solve_icnf(&formula, |solver, index, result| {
+                if result {
+                    let model = solver.model().unwrap();
+                    for &lit in formula.cubes[index].assumptions.iter() {
+                        assert!(model.contains(lit));
+                    }
+                } else {
+                    assert!(solver.failed_core().is_some());
+                }
+                results.push(result);
+            })
+            .unwrap();
+
+        assert_eq!(results, vec![false, true, true, false]);
+    }
+
+    #[test]
+    fn import_learned_validation() {
+        let mut solver = Solver::new();
+
+        solver.add_formula(&cnf_formula![
+            1, 2;
+            -2, 3;
+            4, 5, -1;
+        ]);
+
+        let learned = b"1 3 4 0\n1 4 5 0\n1 3 6 0\n";
+
+        assert_eq!(solver.import_learned(&learned[..]).unwrap(), 1);
+        assert_eq!(solver.solve().ok(), Some(true));
+    }
+
+    #[test]
+    fn import_learned_with_removable_clause() {
+        let mut solver = Solver::new();
+
+        solver.add_clause(&lits![1, 2]);
+        let handle = solver.add_removable_clause(&lits![-1]);
+
+        assert_eq!(solver.import_learned(&b"-1 0\n2 0\n"[..]).unwrap(), 0);
+
+        solver.remove_clause(handle);
+        solver.add_clause(&lits![1, -2]);
+
+        assert_eq!(solver.solve().ok(), Some(true));
+    }
+
+    #[test]
+    fn import_learned_with_proof() {
+        let mut solver = Solver::new();
+        solver.enable_self_checking();
+        solver.add_clause(&lits![1, 2, 3]);
+
+        assert!(solver.import_learned(&b"1 2 3 0\n"[..]).is_err());
+    }
+
+    #[test]
+    fn initial_assignment() {
+        let formula = cnf_formula![
+            1, 2, 3;
+            -1, 4, 5;
+            -2, -4;
+        ];
+
+        let mut solver = Solver::new();
+        solver.add_formula(&formula);
+        solver.set_initial_assignment(&lits![1, 2, 3, -4, 5]);
+
+        assert_eq!(solver.solve().ok(), Some(true));
+        assert_eq!(solver.model().unwrap().lits(), lits![1, 2, 3, -4, 5]);
+
+        let mut solver = Solver::new();
+        solver.add_formula(&formula);
+        solver.set_initial_decisions(&lits![-2, 4]);
+
+        assert_eq!(solver.solve().ok(), Some(true));
+        let model = solver.model().unwrap();
+        assert!(model.contains(Lit::from_dimacs(-2)));
+        assert!(model.contains(Lit::from_dimacs(4)));
+    }
+
+    #[test]
+    fn event_callback() {
+        let mut events = vec![];
+
+        {
+            let mut solver = Solver::new();
+
+            solver.set_event_callback(|event, stats| events.push((event, stats.conflicts)));
+
+            solver.add_formula(&cnf_formula![
+                -1, -2, -3; -1, -2, -4; -1, -2, -5; -1, -3, -4; -1, -3, -5; -1, -4, -5; -2, -3, -4;
+                -2, -3, -5; -2, -4, -5; -3, -4, -5; 1, 2, 5; 1, 2, 3; 1, 2, 4; 1, 5, 3; 1, 5, 4;
+                1, 3, 4; 2, 5, 3; 2, 5, 4; 2, 3, 4; 5, 3, 4;
+            ]);
+
+            assert_eq!(solver.solve().ok(), Some(false));
+        }
+
+        assert_eq!(events.first(), Some(&(SolverEvent::Restart, 0)));
+        assert!(events.contains(&(SolverEvent::ReduceLocals, 0)));
+        assert!(events.contains(&(SolverEvent::ReduceMids, 0)));
+    }
+
     proptest! {
         #[test]
         fn sgen_unsat(
@@ -462,7 +1180,7 @@ mod tests {
             let model = solver.model().unwrap();
 
             for clause in formula.iter() {
-                prop_assert!(clause.iter().any(|lit| model.contains(lit)));
+                prop_assert!(clause.iter().any(|&lit| model.contains(lit)));
             }
         }
 
@@ -481,10 +1199,31 @@ mod tests {
             let model = solver.model().unwrap();
 
             for clause in formula.iter() {
-                prop_assert!(clause.iter().any(|lit| model.contains(lit)));
+                prop_assert!(clause.iter().any(|&lit| model.contains(lit)));
             }
         }
 
+        #[test]
+        fn sgen_unsat_learned_roundtrip(formula in sgen_unsat_formula(1..7usize)) {
+            let mut solver = Solver::new();
+            enable_test_schedule(&mut solver);
+            solver.add_formula(&formula);
+
+            prop_assert_eq!(solver.solve().ok(), Some(false));
+
+            let mut learned = vec![];
+            solver.export_learned(&mut learned).unwrap();
+            let exported = DimacsParser::parse(&learned[..]).unwrap().len();
+
+            let mut solver = Solver::new();
+            solver.add_formula(&formula);
+
+            let imported = solver.import_learned(&learned[..]).unwrap();
+            prop_assert!(imported <= exported);
+
+            prop_assert_eq!(solver.solve().ok(), Some(false));
+        }
+
         #[test]
         fn sgen_unsat_incremental_clauses(formula in sgen_unsat_formula(1..7usize)) {
             let mut solver = Solver::new();
@@ -1,11 +1,9 @@
 //! Checker tests, that require a Solver instance, so they cannot be unit tests of the
 //! varisat-checker crate.
 
-use failure::{Error, Fail};
-
 use proptest::prelude::*;
 
-use varisat::checker::{Checker, ProofTranscriptProcessor, ProofTranscriptStep};
+use varisat::checker::{Checker, ProcessorError, ProofTranscriptProcessor, ProofTranscriptStep};
 use varisat::{dimacs::write_dimacs, CnfFormula, ExtendFormula, Lit, ProofFormat, Solver, Var};
 use varisat_formula::test::{conditional_pigeon_hole, sgen_unsat_formula};
 
@@ -25,7 +23,7 @@ proptest! {
 
         prop_assert_eq!(solver.solve().ok(), Some(false));
 
-        solver.close_proof().map_err(|e| e.compat())?;
+        solver.close_proof()?;
 
         drop(solver);
 
@@ -36,6 +34,57 @@ proptest! {
         checker.check_proof(&mut &proof[..]).unwrap();
     }
 
+    #[test]
+    fn checked_unsat_with_fixed_hash_bits(
+        formula in sgen_unsat_formula(1..7usize),
+        bits in prop::sample::select(vec![16u32, 64, 100, 128]),
+    ) {
+        let mut proof = vec![];
+
+        let mut solver = Solver::new();
+
+        solver.set_proof_hash_bits(Some(bits));
+        solver.write_proof(&mut proof, ProofFormat::Varisat);
+
+        solver.add_formula(&formula);
+
+        prop_assert_eq!(solver.solve().ok(), Some(false));
+
+        solver.close_proof()?;
+
+        drop(solver);
+
+        let mut checker = Checker::new();
+
+        checker.add_formula(&formula).unwrap();
+
+        checker.check_proof(&mut &proof[..]).unwrap();
+    }
+
+    #[test]
+    fn checked_unsat_with_hashed_deletes(formula in sgen_unsat_formula(1..7usize)) {
+        let mut proof = vec![];
+
+        let mut solver = Solver::new();
+
+        solver.set_proof_delete_by_hash(true);
+        solver.write_proof(&mut proof, ProofFormat::Varisat);
+
+        solver.add_formula(&formula);
+
+        prop_assert_eq!(solver.solve().ok(), Some(false));
+
+        solver.close_proof()?;
+
+        drop(solver);
+
+        let mut checker = Checker::new();
+
+        checker.add_formula(&formula).unwrap();
+
+        checker.check_proof(&mut &proof[..]).unwrap();
+    }
+
     #[test]
     fn sgen_checked_unsat_incremental_clauses(formula in sgen_unsat_formula(1..7usize)) {
         let mut proof = vec![];
@@ -79,7 +128,7 @@ proptest! {
             fn process_step(
                 &mut self,
                 step: &ProofTranscriptStep,
-            ) -> Result<(), Error> {
+            ) -> Result<(), ProcessorError> {
                 if let ProofTranscriptStep::Model { .. } = step {
                     self.counter += 1;
                 } else if let ProofTranscriptStep::Unsat = step {
@@ -164,7 +213,7 @@ proptest! {
             fn process_step(
                 &mut self,
                 step: &ProofTranscriptStep,
-            ) -> Result<(), Error> {
+            ) -> Result<(), ProcessorError> {
                 match step {
                     ProofTranscriptStep::Model { .. } => {
                         self.sat += 1;
@@ -188,3 +237,113 @@ proptest! {
         prop_assert_eq!(count_results.unsat, expected_unsat);
     }
 }
+
+#[test]
+fn checked_unsat_background() {
+    use std::io::{Seek, SeekFrom};
+    use varisat_formula::cnf_formula;
+
+    let formula = cnf_formula![
+        -1, -2, -3; -1, -2, -4; -1, -2, -5; -1, -3, -4; -1, -3, -5; -1, -4, -5; -2, -3, -4;
+        -2, -3, -5; -2, -4, -5; -3, -4, -5; 1, 2, 5; 1, 2, 3; 1, 2, 4; 1, 5, 3; 1, 5, 4;
+        1, 3, 4; 2, 5, 3; 2, 5, 4; 2, 3, 4; 5, 3, 4;
+    ];
+
+    let mut proof = tempfile::tempfile().unwrap();
+
+    let mut solver = Solver::new();
+    solver.write_proof_in_background(proof.try_clone().unwrap(), ProofFormat::Varisat);
+    solver.add_formula(&formula);
+
+    assert_eq!(solver.solve().ok(), Some(false));
+
+    solver.close_proof().unwrap();
+    drop(solver);
+
+    proof.seek(SeekFrom::Start(0)).unwrap();
+
+    let mut checker = Checker::new();
+    checker.add_formula(&formula).unwrap();
+    checker.check_proof(proof).unwrap();
+}
+
+#[test]
+fn checked_unsat_checkpoints() {
+    use std::io::{Read, Seek, SeekFrom};
+    use varisat::checker::CheckerError;
+    use varisat_formula::cnf_formula;
+
+    let formula = cnf_formula![
+        -1, -2, -3; -1, -2, -4; -1, -2, -5; -1, -3, -4; -1, -3, -5; -1, -4, -5; -2, -3, -4;
+        -2, -3, -5; -2, -4, -5; -3, -4, -5; 1, 2, 5; 1, 2, 3; 1, 2, 4; 1, 5, 3; 1, 5, 4;
+        1, 3, 4; 2, 5, 3; 2, 5, 4; 2, 3, 4; 5, 3, 4;
+    ];
+
+    let mut file = tempfile::tempfile().unwrap();
+
+    let mut solver = Solver::new();
+    solver.write_proof_file(file.try_clone().unwrap(), ProofFormat::Varisat);
+    solver.set_proof_sync_interval(Some(5));
+    solver.add_formula(&formula);
+
+    assert_eq!(solver.solve().ok(), Some(false));
+
+    solver.close_proof().unwrap();
+    drop(solver);
+
+    let mut proof = vec![];
+    file.seek(SeekFrom::Start(0)).unwrap();
+    file.read_to_end(&mut proof).unwrap();
+
+    let mut checker = Checker::new();
+    checker.add_formula(&formula).unwrap();
+    checker.check_proof(&proof[..]).unwrap();
+
+    let checkpoint = checker.last_checkpoint().unwrap();
+    assert!(checkpoint.bytes < proof.len() as u64);
+
+    let truncated = &proof[..proof.len() - 1];
+
+    let mut checker = Checker::new();
+    checker.add_formula(&formula).unwrap();
+    match checker.check_proof(truncated) {
+        Err(CheckerError::ProofIncomplete { .. }) => (),
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert_eq!(
+        checker.last_checkpoint().map(|checkpoint| checkpoint.bytes),
+        Some(checkpoint.bytes)
+    );
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn checked_unsat_compressed() {
+    use varisat::checker::Compression;
+    use varisat_formula::cnf_formula;
+
+    let formula = cnf_formula![
+        -1, -2, -3; -1, -2, -4; -1, -2, -5; -1, -3, -4; -1, -3, -5; -1, -4, -5; -2, -3, -4;
+        -2, -3, -5; -2, -4, -5; -3, -4, -5; 1, 2, 5; 1, 2, 3; 1, 2, 4; 1, 5, 3; 1, 5, 4;
+        1, 3, 4; 2, 5, 3; 2, 5, 4; 2, 3, 4; 5, 3, 4;
+    ];
+
+    for &compression in [Compression::Gzip, Compression::Zstd].iter() {
+        let mut proof = vec![];
+
+        let mut solver = Solver::new();
+        solver
+            .write_compressed_proof(&mut proof, ProofFormat::Varisat, compression)
+            .unwrap();
+        solver.add_formula(&formula);
+
+        assert_eq!(solver.solve().ok(), Some(false));
+
+        solver.close_proof().unwrap();
+        drop(solver);
+
+        let mut checker = Checker::new();
+        checker.add_formula(&formula).unwrap();
+        checker.check_proof(&mut &proof[..]).unwrap();
+    }
+}
@@ -1,7 +1,4 @@
-use std::collections::HashSet;
-
 use varisat::solver::Solver;
-use varisat::Lit;
 
 macro_rules! test_cnf {
     ($name:ident, $result:expr) => {
@@ -15,9 +12,9 @@ macro_rules! test_cnf {
             let result = $result;
             assert_eq!(solver.solve().expect("solve failed"), result);
             if result {
-                let model: HashSet<Lit> = solver.model().unwrap().into_iter().collect();
+                let model = solver.model().unwrap();
                 for clause in formula.iter() {
-                    assert!(clause.iter().any(|&lit| model.contains(&lit)));
+                    assert!(clause.iter().any(|&lit| model.contains(lit)));
                 }
             }
         }